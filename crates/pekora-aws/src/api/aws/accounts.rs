@@ -0,0 +1,108 @@
+use crate::api::aws::auth::AwsAuthConfig;
+use crate::api::aws::util::{AwsClientError, AwsClientResult};
+use crate::util::ClientSet;
+use aws_config::SdkConfig;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// Clients of one SDK across several accounts, keyed by account alias and region. Each account
+/// authenticates with its own settings, loaded when its first client is asked for.
+pub struct MultiAccountClientSet<T> {
+    accounts: BTreeMap<String, AwsAuthConfig>,
+    client_factory: fn(&SdkConfig) -> T,
+    client_sets: tokio::sync::Mutex<HashMap<String, AccountClients<T>>>,
+}
+
+/// SDK config of an account with the clients created for it.
+pub struct AccountClients<T> {
+    pub config: SdkConfig,
+    pub client_set: Arc<ClientSet<SdkConfig, T>>,
+}
+
+impl<T> Clone for AccountClients<T> {
+    fn clone(&self) -> Self {
+        Self {
+            config: self.config.clone(),
+            client_set: self.client_set.clone(),
+        }
+    }
+}
+
+impl<T: 'static> MultiAccountClientSet<T> {
+    /// `accounts` maps aliases to their credentials, `client_factory` creates a client from a
+    /// config whose region is already set.
+    pub fn new(
+        accounts: BTreeMap<String, AwsAuthConfig>,
+        client_factory: fn(&SdkConfig) -> T,
+    ) -> Self {
+        Self {
+            accounts,
+            client_factory,
+            client_sets: tokio::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Aliases of the configured accounts, sorted.
+    pub fn accounts(&self) -> Vec<&str> {
+        self.accounts.keys().map(String::as_str).collect()
+    }
+
+    /// Config and clients of `account`.
+    pub async fn account(&self, account: &str) -> AwsClientResult<AccountClients<T>> {
+        let auth = self
+            .accounts
+            .get(account)
+            .ok_or_else(|| AwsClientError::UnknownAccount(account.to_string()))?;
+        let mut client_sets = self.client_sets.lock().await;
+        if let Some(clients) = client_sets.get(account) {
+            return Ok(clients.clone());
+        }
+        let config = auth.sdk_config().await;
+        let client_factory = self.client_factory;
+        let clients = AccountClients {
+            client_set: Arc::new(ClientSet::new(
+                config.clone(),
+                Box::new(move |config: SdkConfig, region| {
+                    let mut builder = config.into_builder();
+                    builder.set_region(aws_config::Region::new(region));
+                    client_factory(&builder.build())
+                }),
+            )),
+            config,
+        };
+        client_sets.insert(account.to_string(), clients.clone());
+        Ok(clients)
+    }
+
+    /// Client of `account` in `region`.
+    pub async fn get(&self, account: &str, region: &str) -> AwsClientResult<Arc<T>> {
+        Ok(self.account(account).await?.client_set.get(region).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MultiAccountClientSet;
+    use crate::api::aws::auth::AwsAuthConfig;
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    async fn test_multi_account_client_set() {
+        let accounts = BTreeMap::from([(
+            "prod".to_string(),
+            AwsAuthConfig {
+                region: Some("eu-west-1".to_string()),
+                ..AwsAuthConfig::default()
+            },
+        )]);
+        let clients = MultiAccountClientSet::new(accounts, |config| {
+            config.region().map(|region| region.to_string())
+        });
+        assert_eq!(clients.accounts(), ["prod"]);
+        let client = clients.get("prod", "ap-northeast-2").await.unwrap();
+        assert_eq!(client.as_deref(), Some("ap-northeast-2"));
+        let account = clients.account("prod").await.unwrap();
+        assert_eq!(account.client_set.regions().await, ["ap-northeast-2"]);
+        assert!(clients.get("staging", "us-east-1").await.is_err());
+    }
+}
@@ -0,0 +1,62 @@
+use aws_config::sts::AssumeRoleProvider;
+use aws_config::{BehaviorVersion, ConfigLoader, Region, SdkConfig};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+/// Session name of assumed roles, shown in CloudTrail of the target account.
+const SESSION_NAME: &str = "pekora";
+
+/// How clients calling AWS APIs authenticate, on top of the default credential chain.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AwsAuthConfig {
+    /// Shared config profile, e.g. from `~/.aws/config`
+    pub profile: Option<String>,
+    /// Role assumed with the credentials of the profile, e.g. of another account
+    pub role_arn: Option<String>,
+    /// External ID the role's trust policy asks for
+    pub external_id: Option<String>,
+    /// Region of the SDK config, used for STS and region discovery
+    pub region: Option<String>,
+}
+
+impl AwsAuthConfig {
+    fn loader(&self) -> ConfigLoader {
+        let mut loader = aws_config::defaults(BehaviorVersion::latest());
+        if let Some(profile) = &self.profile {
+            loader = loader.profile_name(profile);
+        }
+        if let Some(region) = &self.region {
+            loader = loader.region(Region::new(region.clone()));
+        }
+        loader
+    }
+
+    /// Loads the SDK config, with credentials of the assumed role if one is set.
+    pub async fn sdk_config(&self) -> SdkConfig {
+        let base = self.loader().load().await;
+        let role_arn = match &self.role_arn {
+            Some(role_arn) => role_arn,
+            None => return base,
+        };
+        info!("Assuming role {}", role_arn);
+        let mut builder = AssumeRoleProvider::builder(role_arn)
+            .session_name(SESSION_NAME)
+            .configure(&base);
+        if let Some(external_id) = &self.external_id {
+            builder = builder.external_id(external_id);
+        }
+        self.loader()
+            .credentials_provider(builder.build().await)
+            .load()
+            .await
+    }
+
+    /// `aws_sdk_config` if given, or else the SDK config of these settings.
+    pub async fn resolve(&self, aws_sdk_config: Option<SdkConfig>) -> SdkConfig {
+        match aws_sdk_config {
+            Some(config) => config,
+            None => self.sdk_config().await,
+        }
+    }
+}
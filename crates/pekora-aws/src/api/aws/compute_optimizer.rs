@@ -0,0 +1,186 @@
+use crate::api::aws::accounts::MultiAccountClientSet;
+use crate::api::aws::auth::AwsAuthConfig;
+use crate::api::aws::fan_out::{fan_out, FanOutConfig, RegionResults};
+use crate::api::aws::util::{AwsClientError, AwsClientResult};
+use crate::metrics;
+use crate::util::ClientSet;
+use aws_config::SdkConfig;
+use log::info;
+use std::sync::Arc;
+
+pub use pekora_core::model::aws::compute_optimizer::{
+    Ec2InstanceRecommendationsResponse, InstanceRecommendation, InstanceRecommendationOption,
+};
+
+fn build_client_set(config: SdkConfig) -> ClientSet<SdkConfig, aws_sdk_computeoptimizer::Client> {
+    ClientSet::new(
+        config,
+        Box::new(|config, region| {
+            let mut builder = config.into_builder();
+            builder.set_region(aws_config::Region::new(region));
+            let new_config = builder.build();
+            aws_sdk_computeoptimizer::Client::new(&new_config)
+        }),
+    )
+}
+
+/// Client of Compute Optimizer, which only has recommendations for accounts opted in to it.
+pub struct ComputeOptimizerClient {
+    client_set: Arc<ClientSet<SdkConfig, aws_sdk_computeoptimizer::Client>>,
+    regions: Vec<String>,
+    fan_out: FanOutConfig,
+}
+
+impl ComputeOptimizerClient {
+    /// Queries `regions`, each of which has recommendations of its own instances.
+    pub async fn new(aws_sdk_config: Option<SdkConfig>, regions: Vec<String>) -> Self {
+        Self::with_auth(aws_sdk_config, &AwsAuthConfig::default(), regions).await
+    }
+
+    /// Like `new`, authenticating with `auth` unless an SDK config is given.
+    pub async fn with_auth(
+        aws_sdk_config: Option<SdkConfig>,
+        auth: &AwsAuthConfig,
+        regions: Vec<String>,
+    ) -> Self {
+        let config = auth.resolve(aws_sdk_config).await;
+        Self {
+            client_set: Arc::new(build_client_set(config)),
+            regions,
+            fan_out: FanOutConfig::default(),
+        }
+    }
+
+    /// Queries `regions` of `account`.
+    pub async fn for_account(
+        accounts: &MultiAccountClientSet<aws_sdk_computeoptimizer::Client>,
+        account: &str,
+        regions: Vec<String>,
+    ) -> AwsClientResult<Self> {
+        Ok(Self {
+            client_set: accounts.account(account).await?.client_set,
+            regions,
+            fan_out: FanOutConfig::default(),
+        })
+    }
+
+    /// Concurrency and retries of calls made in every region.
+    pub fn with_fan_out(mut self, fan_out: FanOutConfig) -> Self {
+        self.fan_out = fan_out;
+        self
+    }
+
+    async fn region_clients(&self) -> Vec<(String, Arc<aws_sdk_computeoptimizer::Client>)> {
+        let mut clients = Vec::with_capacity(self.regions.len());
+        for region in &self.regions {
+            clients.push((region.clone(), self.client_set.get(region).await));
+        }
+        clients
+    }
+
+    /// Rightsizing recommendations of the EC2 instances in each region, the input
+    /// `transform::aws::rightsizing` prices.
+    pub async fn get_ec2_instance_recommendations(
+        &self,
+    ) -> RegionResults<Vec<InstanceRecommendation>> {
+        fan_out(
+            &self.fan_out,
+            self.region_clients().await,
+            get_ec2_instance_recommendations,
+        )
+        .await
+    }
+}
+
+async fn get_ec2_instance_recommendations(
+    client: Arc<aws_sdk_computeoptimizer::Client>,
+) -> AwsClientResult<Vec<InstanceRecommendation>> {
+    let mut recommendations = Vec::new();
+    let mut next_token: Option<String> = None;
+    loop {
+        info!(
+            "ComputeOptimizerClient: Requesting GetEC2InstanceRecommendations (region={:?})",
+            client.config().region()
+        );
+        metrics::global().record_request();
+        let output = client
+            .get_ec2_instance_recommendations()
+            .set_next_token(next_token)
+            .send()
+            .await
+            .map_err(AwsClientError::GetEc2InstanceRecommendationsFailure)?;
+        recommendations.extend(
+            output
+                .instance_recommendations
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(instance_recommendation),
+        );
+        next_token = output.next_token.filter(|token| !token.is_empty());
+        if next_token.is_none() {
+            break;
+        }
+    }
+    Ok(recommendations)
+}
+
+/// Typed `recommendation`, `None` if it lacks an instance ARN, instance type or finding.
+pub fn instance_recommendation(
+    recommendation: aws_sdk_computeoptimizer::types::InstanceRecommendation,
+) -> Option<InstanceRecommendation> {
+    Some(InstanceRecommendation {
+        instance_arn: recommendation.instance_arn?,
+        account_id: recommendation.account_id,
+        instance_name: recommendation.instance_name,
+        current_instance_type: recommendation.current_instance_type?,
+        finding: recommendation.finding?.as_str().to_string(),
+        recommendation_options: recommendation
+            .recommendation_options
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|option| {
+                Some(InstanceRecommendationOption {
+                    instance_type: option.instance_type?,
+                    rank: option.rank,
+                    performance_risk: Some(option.performance_risk),
+                    migration_effort: option
+                        .migration_effort
+                        .map(|effort| effort.as_str().to_string()),
+                })
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::instance_recommendation;
+    use aws_sdk_computeoptimizer::types::{
+        Finding, InstanceRecommendation, InstanceRecommendationOption,
+    };
+
+    #[test]
+    fn test_instance_recommendation() {
+        let recommendation = InstanceRecommendation::builder()
+            .instance_arn("arn:aws:ec2:us-west-2:123456789012:instance/i-0a1b2c3d")
+            .current_instance_type("m5.2xlarge")
+            .finding(Finding::OverProvisioned)
+            .recommendation_options(
+                InstanceRecommendationOption::builder()
+                    .instance_type("m5.xlarge")
+                    .rank(1)
+                    .build(),
+            )
+            .recommendation_options(InstanceRecommendationOption::builder().rank(2).build())
+            .build();
+        let recommendation = instance_recommendation(recommendation).unwrap();
+        assert_eq!(recommendation.finding, "Overprovisioned");
+        assert_eq!(recommendation.region(), Some("us-west-2"));
+        assert_eq!(recommendation.recommendation_options.len(), 1);
+        assert_eq!(
+            recommendation.best_option().unwrap().instance_type,
+            "m5.xlarge"
+        );
+        assert!(instance_recommendation(InstanceRecommendation::builder().build()).is_none());
+    }
+}
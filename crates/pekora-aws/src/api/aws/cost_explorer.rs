@@ -0,0 +1,339 @@
+use crate::api::aws::auth::AwsAuthConfig;
+use crate::api::aws::util::{AwsClientError, AwsClientResult};
+use crate::metrics;
+use aws_config::SdkConfig;
+use aws_sdk_costexplorer::error::BuildError;
+use aws_sdk_costexplorer::types::{
+    DateInterval, Dimension, DimensionValues, Expression, Granularity, GroupDefinitionType,
+};
+use log::info;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+pub use pekora_core::model::aws::cost_explorer::{
+    CostAndUsageResponse, CoverageHours, Group, GroupDefinition, MetricValue, ReservationCoverage,
+    ReservationCoverageResponse, ResultByTime, SavingsPlansSavings, SavingsPlansUtilization,
+    SavingsPlansUtilizationResponse, SavingsPlansUtilizationTotal, TimePeriod,
+};
+
+/// Cost Explorer is served from a single region, whatever the region of the usage.
+const COST_EXPLORER_REGION: &str = "us-east-1";
+
+/// Usage type group of the running hours of EC2 instances.
+const EC2_RUNNING_HOURS: &str = "EC2: Running Hours";
+
+/// Client of Cost Explorer, for the actual usage and commitments of an account. Requires billing
+/// permissions, `ce:Get*`, usually granted in the management account only.
+pub struct CostExplorerClient {
+    client: aws_sdk_costexplorer::Client,
+}
+
+impl CostExplorerClient {
+    pub async fn new(aws_sdk_config: Option<SdkConfig>) -> Self {
+        Self::with_auth(aws_sdk_config, &AwsAuthConfig::default()).await
+    }
+
+    /// Like `new`, authenticating with `auth` unless an SDK config is given.
+    pub async fn with_auth(aws_sdk_config: Option<SdkConfig>, auth: &AwsAuthConfig) -> Self {
+        let mut builder = auth.resolve(aws_sdk_config).await.into_builder();
+        builder.set_region(Some(aws_config::Region::new(COST_EXPLORER_REGION)));
+        Self {
+            client: aws_sdk_costexplorer::Client::new(&builder.build()),
+        }
+    }
+
+    /// Daily running hours of EC2 instances in `region` grouped by `INSTANCE_TYPE`, the input
+    /// `transform::aws::cost_explorer::ec2_usage` expects.
+    pub async fn get_ec2_usage(
+        &self,
+        time_period: &TimePeriod,
+        region: &str,
+    ) -> AwsClientResult<CostAndUsageResponse> {
+        let request = self
+            .client
+            .get_cost_and_usage()
+            .time_period(date_interval(time_period)?)
+            .granularity(Granularity::Daily)
+            .metrics("UsageQuantity")
+            .filter(
+                Expression::builder()
+                    .and(dimension(Dimension::UsageTypeGroup, EC2_RUNNING_HOURS))
+                    .and(dimension(Dimension::Region, region))
+                    .build(),
+            )
+            .group_by(
+                aws_sdk_costexplorer::types::GroupDefinition::builder()
+                    .r#type(GroupDefinitionType::Dimension)
+                    .key("INSTANCE_TYPE")
+                    .build(),
+            );
+
+        let mut response = CostAndUsageResponse {
+            results_by_time: Vec::new(),
+            group_definitions: Vec::new(),
+            next_page_token: None,
+        };
+        let mut next_page_token: Option<String> = None;
+        loop {
+            info!(
+                "CostExplorerClient: Requesting GetCostAndUsage ({} to {}, region={})",
+                time_period.start, time_period.end, region
+            );
+            metrics::global().record_request();
+            let output = request
+                .clone()
+                .set_next_page_token(next_page_token)
+                .send()
+                .await
+                .map_err(AwsClientError::GetCostAndUsageFailure)?;
+            if response.group_definitions.is_empty() {
+                response.group_definitions = output
+                    .group_definitions
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(group_definition)
+                    .collect();
+            }
+            response.results_by_time.extend(
+                output
+                    .results_by_time
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(result_by_time),
+            );
+            next_page_token = output.next_page_token.filter(|token| !token.is_empty());
+            if next_page_token.is_none() {
+                break;
+            }
+        }
+        Ok(response)
+    }
+
+    /// Utilization of the Savings Plans of the account over `time_period`. Cost Explorer fails
+    /// with `DataUnavailableException` if there were none.
+    pub async fn get_savings_plans_utilization(
+        &self,
+        time_period: &TimePeriod,
+    ) -> AwsClientResult<SavingsPlansUtilizationResponse> {
+        info!(
+            "CostExplorerClient: Requesting GetSavingsPlansUtilization ({} to {})",
+            time_period.start, time_period.end
+        );
+        metrics::global().record_request();
+        let output = self
+            .client
+            .get_savings_plans_utilization()
+            .time_period(date_interval(time_period)?)
+            .send()
+            .await
+            .map_err(AwsClientError::GetSavingsPlansUtilizationFailure)?;
+        let total = output
+            .total
+            .and_then(savings_plans_utilization_total)
+            .ok_or(AwsClientError::MissingField("Total"))?;
+        Ok(SavingsPlansUtilizationResponse { total })
+    }
+
+    /// Coverage of running hours by reservations over `time_period`. The total is the same on
+    /// every page, so only the first is requested.
+    pub async fn get_reservation_coverage(
+        &self,
+        time_period: &TimePeriod,
+    ) -> AwsClientResult<ReservationCoverageResponse> {
+        info!(
+            "CostExplorerClient: Requesting GetReservationCoverage ({} to {})",
+            time_period.start, time_period.end
+        );
+        metrics::global().record_request();
+        let output = self
+            .client
+            .get_reservation_coverage()
+            .time_period(date_interval(time_period)?)
+            .send()
+            .await
+            .map_err(AwsClientError::GetReservationCoverageFailure)?;
+        let total = output
+            .total
+            .and_then(|total| total.coverage_hours)
+            .and_then(coverage_hours)
+            .ok_or(AwsClientError::MissingField("Total.CoverageHours"))?;
+        Ok(ReservationCoverageResponse {
+            total: ReservationCoverage {
+                coverage_hours: total,
+            },
+        })
+    }
+}
+
+fn date_interval(time_period: &TimePeriod) -> Result<DateInterval, BuildError> {
+    DateInterval::builder()
+        .start(&time_period.start)
+        .end(&time_period.end)
+        .build()
+}
+
+fn dimension(key: Dimension, value: &str) -> Expression {
+    Expression::builder()
+        .dimensions(DimensionValues::builder().key(key).values(value).build())
+        .build()
+}
+
+/// Amounts come as decimal strings, `None` if missing or malformed.
+fn decimal(value: Option<String>) -> Option<Decimal> {
+    value?.parse().ok()
+}
+
+fn metrics_by_name(
+    metrics: Option<HashMap<String, aws_sdk_costexplorer::types::MetricValue>>,
+) -> HashMap<String, MetricValue> {
+    metrics
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(name, metric)| {
+            Some((
+                name,
+                MetricValue {
+                    amount: decimal(metric.amount)?,
+                    unit: metric.unit?,
+                },
+            ))
+        })
+        .collect()
+}
+
+fn group_definition(
+    definition: aws_sdk_costexplorer::types::GroupDefinition,
+) -> Option<GroupDefinition> {
+    Some(GroupDefinition {
+        kind: definition.r#type?.as_str().to_string(),
+        key: definition.key?,
+    })
+}
+
+/// Typed `result`, `None` if it lacks a time period.
+pub fn result_by_time(result: aws_sdk_costexplorer::types::ResultByTime) -> Option<ResultByTime> {
+    let time_period = result.time_period?;
+    Some(ResultByTime {
+        time_period: TimePeriod {
+            start: time_period.start,
+            end: time_period.end,
+        },
+        total: metrics_by_name(result.total),
+        groups: result
+            .groups
+            .unwrap_or_default()
+            .into_iter()
+            .map(|group| Group {
+                keys: group.keys.unwrap_or_default(),
+                metrics: metrics_by_name(group.metrics),
+            })
+            .collect(),
+        estimated: result.estimated,
+    })
+}
+
+/// Typed `total`, `None` if it lacks a utilization amount.
+pub fn savings_plans_utilization_total(
+    total: aws_sdk_costexplorer::types::SavingsPlansUtilizationAggregates,
+) -> Option<SavingsPlansUtilizationTotal> {
+    let utilization = total.utilization?;
+    Some(SavingsPlansUtilizationTotal {
+        utilization: SavingsPlansUtilization {
+            total_commitment: decimal(utilization.total_commitment)?,
+            used_commitment: decimal(utilization.used_commitment)?,
+            unused_commitment: decimal(utilization.unused_commitment)?,
+            utilization_percentage: decimal(utilization.utilization_percentage)?,
+        },
+        savings: total.savings.and_then(|savings| {
+            Some(SavingsPlansSavings {
+                net_savings: decimal(savings.net_savings)?,
+                on_demand_cost_equivalent: decimal(savings.on_demand_cost_equivalent)?,
+            })
+        }),
+    })
+}
+
+/// Typed `hours`, `None` if it lacks any of them.
+pub fn coverage_hours(hours: aws_sdk_costexplorer::types::CoverageHours) -> Option<CoverageHours> {
+    Some(CoverageHours {
+        on_demand_hours: decimal(hours.on_demand_hours)?,
+        reserved_hours: decimal(hours.reserved_hours)?,
+        total_running_hours: decimal(hours.total_running_hours)?,
+        coverage_hours_percentage: decimal(hours.coverage_hours_percentage)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{coverage_hours, result_by_time, savings_plans_utilization_total};
+    use aws_sdk_costexplorer::types::{
+        CoverageHours, DateInterval, Group, MetricValue, ResultByTime, SavingsPlansUtilization,
+        SavingsPlansUtilizationAggregates,
+    };
+    use pekora_core::model::aws::cost_explorer::{CostAndUsageResponse, GroupDefinition};
+    use pekora_core::transform::aws::cost_explorer::ec2_usage;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_result_by_time() {
+        let result = ResultByTime::builder()
+            .time_period(
+                DateInterval::builder()
+                    .start("2024-03-01")
+                    .end("2024-03-02")
+                    .build()
+                    .unwrap(),
+            )
+            .groups(
+                Group::builder()
+                    .keys("m5.large")
+                    .metrics(
+                        "UsageQuantity",
+                        MetricValue::builder().amount("48").unit("Hrs").build(),
+                    )
+                    .build(),
+            )
+            .build();
+        let response = CostAndUsageResponse {
+            results_by_time: vec![result_by_time(result).unwrap()],
+            group_definitions: vec![GroupDefinition {
+                kind: "DIMENSION".to_string(),
+                key: "INSTANCE_TYPE".to_string(),
+            }],
+            next_page_token: None,
+        };
+        let usage = ec2_usage(&response).unwrap();
+        assert_eq!(usage[0].instance_type, "m5.large");
+        assert_eq!(usage[0].instances, Decimal::from(2));
+        assert!(result_by_time(ResultByTime::builder().build()).is_none());
+    }
+
+    #[test]
+    fn test_commitment_totals() {
+        let total = SavingsPlansUtilizationAggregates::builder()
+            .utilization(
+                SavingsPlansUtilization::builder()
+                    .total_commitment("100")
+                    .used_commitment("90")
+                    .unused_commitment("10")
+                    .utilization_percentage("90")
+                    .build(),
+            )
+            .build();
+        let total = savings_plans_utilization_total(total).unwrap();
+        assert_eq!(total.utilization.unused_commitment, Decimal::from(10));
+        assert!(total.savings.is_none());
+
+        let hours = CoverageHours::builder()
+            .on_demand_hours("25")
+            .reserved_hours("75")
+            .total_running_hours("100")
+            .coverage_hours_percentage("75")
+            .build();
+        assert_eq!(
+            coverage_hours(hours).unwrap().coverage_hours_percentage,
+            Decimal::from(75)
+        );
+        assert!(coverage_hours(CoverageHours::builder().on_demand_hours("n/a").build()).is_none());
+    }
+}
@@ -0,0 +1,214 @@
+use crate::api::aws::accounts::MultiAccountClientSet;
+use crate::api::aws::auth::AwsAuthConfig;
+use crate::api::aws::util::{AwsClientError, AwsClientResult};
+use crate::cache::{CacheKey, Cacheable, CacheableArc};
+use crate::metrics;
+use crate::util::ClientSet;
+use async_trait::async_trait;
+use aws_config::SdkConfig;
+use log::info;
+use pekora_core::model::aws::rds::{AvailabilityZone, OrderableDbInstanceOption};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+pub const DEFAULT_PARAMETER_GROUP_FAMILY: &str = "docdb5.0";
+
+/// Engine default parameters are the same everywhere, so they are asked of a single region.
+const PARAMETER_REGION: &str = "us-east-1";
+
+/// Engine name of DocumentDB in the RDS-style APIs it shares.
+const ENGINE: &str = "docdb";
+
+async fn build_client_set(
+    aws_sdk_config: Option<SdkConfig>,
+    auth: &AwsAuthConfig,
+) -> ClientSet<SdkConfig, aws_sdk_docdb::Client> {
+    let config = auth.resolve(aws_sdk_config).await;
+    ClientSet::new(
+        config,
+        Box::new(|config, region| {
+            let mut builder = config.into_builder();
+            builder.set_region(aws_config::Region::new(region));
+            let new_config = builder.build();
+            aws_sdk_docdb::Client::new(&new_config)
+        }),
+    )
+}
+
+/// Map of (parameter name) -> (parameter value)
+pub type DocDbClusterParameters = BTreeMap<String, String>;
+
+pub struct DocDbClient {
+    client_set: Arc<ClientSet<SdkConfig, aws_sdk_docdb::Client>>,
+}
+
+impl DocDbClient {
+    pub async fn new(aws_sdk_config: Option<SdkConfig>) -> Self {
+        Self::with_auth(aws_sdk_config, &AwsAuthConfig::default()).await
+    }
+
+    /// Like `new`, authenticating with `auth` unless an SDK config is given.
+    pub async fn with_auth(aws_sdk_config: Option<SdkConfig>, auth: &AwsAuthConfig) -> Self {
+        Self {
+            client_set: Arc::new(build_client_set(aws_sdk_config, auth).await),
+        }
+    }
+
+    pub async fn for_account(
+        accounts: &MultiAccountClientSet<aws_sdk_docdb::Client>,
+        account: &str,
+    ) -> AwsClientResult<Self> {
+        Ok(Self {
+            client_set: accounts.account(account).await?.client_set,
+        })
+    }
+
+    /// Caches engine default cluster parameters by parameter group family, e.g. `docdb5.0`.
+    pub async fn new_cacheable_arc(
+        aws_sdk_config: Option<SdkConfig>,
+    ) -> CacheableArc<String, DocDbClusterParameters, AwsClientError> {
+        Arc::new(Box::new(Self::new(aws_sdk_config).await))
+    }
+
+    /// Engine default cluster parameters of `parameter_group_family`. Parameters without a value
+    /// are skipped.
+    pub async fn describe_engine_default_cluster_parameters(
+        &self,
+        parameter_group_family: &str,
+    ) -> AwsClientResult<DocDbClusterParameters> {
+        let client = self.client_set.get(PARAMETER_REGION).await;
+        let mut result = DocDbClusterParameters::new();
+        let mut marker: Option<String> = None;
+        loop {
+            info!(
+                "DocDbClient: DescribeEngineDefaultClusterParameters for {}",
+                parameter_group_family
+            );
+            metrics::global().record_request();
+            let output = client
+                .describe_engine_default_cluster_parameters()
+                .db_parameter_group_family(parameter_group_family)
+                .set_marker(marker)
+                .send()
+                .await
+                .map_err(AwsClientError::DescribeEngineDefaultClusterParametersFailure)?;
+            let engine_defaults = match output.engine_defaults {
+                Some(engine_defaults) => engine_defaults,
+                None => break,
+            };
+            result.extend(
+                engine_defaults
+                    .parameters
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|parameter| {
+                        Some((parameter.parameter_name?, parameter.parameter_value?))
+                    }),
+            );
+            marker = engine_defaults.marker.filter(|marker| !marker.is_empty());
+            if marker.is_none() {
+                break;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Instance classes orderable in `region`, in the shape of their RDS equivalent.
+    pub async fn describe_orderable_db_instance_options(
+        &self,
+        region: &str,
+    ) -> AwsClientResult<Vec<OrderableDbInstanceOption>> {
+        let client = self.client_set.get(region).await;
+        info!(
+            "DocDbClient: DescribeOrderableDBInstanceOptions (region={})",
+            region
+        );
+        let mut stream = client
+            .describe_orderable_db_instance_options()
+            .engine(ENGINE)
+            .into_paginator()
+            .send();
+
+        let mut result = Vec::new();
+        while let Some(page_result) = stream.next().await {
+            metrics::global().record_request();
+            let page = page_result
+                .map_err(AwsClientError::DescribeOrderableDocDbInstanceOptionsFailure)?;
+            result.extend(
+                page.orderable_db_instance_options
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(orderable_db_instance_option),
+            );
+        }
+        Ok(result)
+    }
+}
+
+/// Typed `option`, `None` if it lacks an engine or instance class. DocumentDB instances are
+/// never Multi-AZ; replicas are separate instances.
+pub fn orderable_db_instance_option(
+    option: aws_sdk_docdb::types::OrderableDbInstanceOption,
+) -> Option<OrderableDbInstanceOption> {
+    Some(OrderableDbInstanceOption {
+        engine: option.engine?,
+        engine_version: option.engine_version,
+        db_instance_class: option.db_instance_class?,
+        license_model: option.license_model,
+        availability_zones: option
+            .availability_zones
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|zone| Some(AvailabilityZone { name: zone.name? }))
+            .collect(),
+        multi_az_capable: false,
+        storage_type: option.storage_type,
+    })
+}
+
+/// Engine default cluster parameters of the parameter group family given as input. Engine
+/// defaults carry no validators, so cached parameters are reused until they expire.
+#[async_trait]
+impl Cacheable<String, DocDbClusterParameters, AwsClientError> for DocDbClient {
+    async fn get_cache_key(&self, input: &String) -> Result<CacheKey, AwsClientError> {
+        Ok(CacheKey {
+            content_key: self.content_key(input),
+            content_hash: None,
+        })
+    }
+
+    async fn load(&self, input: &String) -> Result<DocDbClusterParameters, AwsClientError> {
+        self.describe_engine_default_cluster_parameters(input).await
+    }
+
+    fn category_key(&self) -> String {
+        "aws/docdb/engine-default-cluster-parameters".to_string()
+    }
+
+    fn content_key(&self, input: &String) -> Option<String> {
+        Some(format!("{}_{}", PARAMETER_REGION, input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::orderable_db_instance_option;
+    use aws_sdk_docdb::types::{AvailabilityZone, OrderableDbInstanceOption};
+
+    #[test]
+    fn test_orderable_db_instance_option() {
+        let option = OrderableDbInstanceOption::builder()
+            .engine("docdb")
+            .engine_version("5.0.0")
+            .db_instance_class("db.r6g.large")
+            .availability_zones(AvailabilityZone::builder().name("us-east-1a").build())
+            .build();
+        let option = orderable_db_instance_option(option).unwrap();
+        assert_eq!(option.db_instance_class, "db.r6g.large");
+        assert_eq!(option.availability_zones[0].name, "us-east-1a");
+        assert!(!option.multi_az_capable);
+        assert!(
+            orderable_db_instance_option(OrderableDbInstanceOption::builder().build()).is_none()
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use crate::api::aws::price_bulk::{
+    response_content_hash, validator_header, PriceBulkError, PriceBulkResult,
+};
+use crate::metrics;
+use log::{debug, info, warn};
+use reqwest::header::{ACCEPT_ENCODING, CONTENT_ENCODING, IF_RANGE, RANGE};
+use reqwest::StatusCode;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+/// Content coding requested for downloads. Pricing JSON compresses about tenfold.
+const DOWNLOAD_ENCODING: &str = "gzip";
+const IDENTITY: &str = "identity";
+
+pub struct CompletedDownload {
+    pub path: PathBuf,
+    pub content_hash: Option<String>,
+}
+
+/// Downloads `url` to `partial_path`, returning `None` if the content still matches the
+/// `content_hash` of a previous download.
+///
+/// If an earlier attempt left a partial file behind, the download resumes from its end with a
+/// `Range` request. The content hash (ETag or `Last-Modified`) of the partial content is kept in
+/// a sidecar file and sent as `If-Range`, so a file that changed upstream in the meantime is
+/// downloaded from scratch.
+///
+/// Bodies are requested gzipped and stored as received, so ranges of a resumed download refer to
+/// the encoded bytes already on disk. `client` must therefore not decode response bodies, and the
+/// file is decoded when parsed. The content coding of the partial content is kept in another
+/// sidecar file and requested again when resuming.
+pub async fn download_resumable(
+    client: &reqwest::Client,
+    url: &str,
+    content_hash: Option<&str>,
+    partial_path: &Path,
+) -> PriceBulkResult<Option<CompletedDownload>> {
+    let etag_path = sidecar_path(partial_path, "etag");
+    let length_path = sidecar_path(partial_path, "length");
+    let encoding_path = sidecar_path(partial_path, "encoding");
+    if let Some(folder) = partial_path.parent() {
+        fs::create_dir_all(folder)
+            .await
+            .map_err(PriceBulkError::IO)?;
+    }
+
+    loop {
+        let partial_len = match fs::metadata(partial_path).await {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(PriceBulkError::IO(e)),
+        };
+        let partial_etag = fs::read_to_string(&etag_path).await.ok();
+        // Partial files without the sidecar predate compressed downloads
+        let partial_encoding = fs::read_to_string(&encoding_path)
+            .await
+            .unwrap_or(IDENTITY.to_string());
+
+        let mut request = client.get(url);
+        let resuming = match (&partial_etag, partial_len) {
+            (Some(etag), len) if len > 0 => {
+                info!("Resuming download of {} from byte {}", url, len);
+                request = request
+                    .header(ACCEPT_ENCODING, partial_encoding.as_str())
+                    .header(RANGE, format!("bytes={}-", len))
+                    .header(IF_RANGE, validator_header(etag).1);
+                true
+            }
+            _ => {
+                request = request.header(ACCEPT_ENCODING, DOWNLOAD_ENCODING);
+                if let Some(content_hash) = content_hash {
+                    let (name, value) = validator_header(content_hash);
+                    request = request.header(name, value);
+                }
+                false
+            }
+        };
+
+        debug!("Downloading URL: {}", url);
+        metrics::global().record_request();
+        let response = request.send().await?;
+        match response.status() {
+            StatusCode::NOT_MODIFIED if !resuming => return Ok(None),
+            StatusCode::RANGE_NOT_SATISFIABLE if resuming => {
+                warn!("Partial download of {} is unusable, restarting", url);
+                remove_partial(partial_path).await;
+                continue;
+            }
+            _ => {}
+        }
+        let mut response = response
+            .error_for_status()
+            .map_err(PriceBulkError::HttpResponseFailure)?;
+
+        let encoding = response
+            .headers()
+            .get(CONTENT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or(IDENTITY)
+            .to_string();
+        let append = response.status() == StatusCode::PARTIAL_CONTENT;
+        if append && encoding != partial_encoding {
+            warn!(
+                "Partial download of {} is {} but the rest is {}, restarting",
+                url, partial_encoding, encoding
+            );
+            remove_partial(partial_path).await;
+            continue;
+        }
+        let etag = if append {
+            partial_etag
+        } else {
+            let etag = response_content_hash(&response);
+            match &etag {
+                Some(etag) => fs::write(&etag_path, etag).await,
+                None => remove_if_exists(&etag_path).await,
+            }
+            .map_err(PriceBulkError::IO)?;
+            // Lets observers such as `pekora top` report progress
+            match response.content_length() {
+                Some(length) => fs::write(&length_path, length.to_string()).await,
+                None => remove_if_exists(&length_path).await,
+            }
+            .map_err(PriceBulkError::IO)?;
+            fs::write(&encoding_path, &encoding)
+                .await
+                .map_err(PriceBulkError::IO)?;
+            etag
+        };
+
+        let mut options = fs::OpenOptions::new();
+        if append {
+            options.append(true);
+        } else {
+            options.write(true).create(true).truncate(true);
+        }
+        let mut file = options
+            .open(partial_path)
+            .await
+            .map_err(PriceBulkError::IO)?;
+        while let Some(chunk) = response.chunk().await? {
+            metrics::global().record_bytes_downloaded(chunk.len() as u64);
+            file.write_all(&chunk).await.map_err(PriceBulkError::IO)?;
+        }
+        file.flush().await.map_err(PriceBulkError::IO)?;
+
+        return Ok(Some(CompletedDownload {
+            path: partial_path.to_path_buf(),
+            content_hash: etag,
+        }));
+    }
+}
+
+/// Removes a partial download and its sidecar files.
+pub async fn remove_partial(partial_path: &Path) {
+    for path in [
+        partial_path.to_path_buf(),
+        sidecar_path(partial_path, "etag"),
+        sidecar_path(partial_path, "length"),
+        sidecar_path(partial_path, "encoding"),
+    ] {
+        if let Err(e) = remove_if_exists(&path).await {
+            warn!("Failed to remove {:?}: {}", path, e);
+        }
+    }
+}
+
+async fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn sidecar_path(partial_path: &Path, extension: &str) -> PathBuf {
+    let mut path = partial_path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{download_resumable, remove_partial};
+    use crate::util::testing::serve;
+    use axum::http::{header, HeaderMap, StatusCode};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::get;
+    use axum::Router;
+    use flate2::read::GzDecoder;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::{Read, Write};
+
+    const BODY: &str = "0123456789";
+
+    fn gzip(body: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(body.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    async fn ranged(headers: HeaderMap) -> Response {
+        let gzipped = headers
+            .get(header::ACCEPT_ENCODING)
+            .is_some_and(|encoding| encoding == "gzip");
+        let (body, encoding) = match gzipped {
+            true => (gzip(BODY), "gzip"),
+            false => (BODY.as_bytes().to_vec(), "identity"),
+        };
+        match headers.get(header::RANGE) {
+            Some(range) if headers.get(header::IF_RANGE).is_some_and(|e| e == "\"v1\"") => {
+                let start = range.to_str().unwrap()["bytes=".len()..]
+                    .trim_end_matches('-')
+                    .parse::<usize>()
+                    .unwrap();
+                (
+                    StatusCode::PARTIAL_CONTENT,
+                    [
+                        (header::ETAG, "\"v1\""),
+                        (header::CONTENT_ENCODING, encoding),
+                    ],
+                    body[start..].to_vec(),
+                )
+                    .into_response()
+            }
+            _ => (
+                [
+                    (header::ETAG, "\"v1\""),
+                    (header::CONTENT_ENCODING, encoding),
+                ],
+                body,
+            )
+                .into_response(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_partial_download() {
+        let base_url = serve(Router::new().route("/file", get(ranged))).await;
+        let directory =
+            std::env::temp_dir().join(format!("pekora-download-{}", std::process::id()));
+        let partial_path = directory.join("file.partial");
+        std::fs::create_dir_all(&directory).unwrap();
+
+        // Partial content of the current version is resumed
+        std::fs::write(&partial_path, &BODY[..4]).unwrap();
+        std::fs::write(directory.join("file.partial.etag"), "v1").unwrap();
+        let client = reqwest::Client::builder()
+            .no_gzip()
+            .no_deflate()
+            .build()
+            .unwrap();
+        let url = format!("{}/file", base_url);
+        let download = download_resumable(&client, &url, None, &partial_path)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&download.path).unwrap(), BODY);
+        assert_eq!(download.content_hash, Some("v1".to_string()));
+
+        // Partial content of an older version is discarded
+        std::fs::write(&partial_path, "abc").unwrap();
+        std::fs::write(directory.join("file.partial.etag"), "v0").unwrap();
+        let download = download_resumable(&client, &url, None, &partial_path)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&download.path).unwrap(), BODY);
+
+        // Downloads from scratch are stored gzipped
+        remove_partial(&partial_path).await;
+        let download = download_resumable(&client, &url, None, &partial_path)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(std::fs::read(&download.path).unwrap(), gzip(BODY));
+        assert_eq!(
+            std::fs::read_to_string(directory.join("file.partial.encoding")).unwrap(),
+            "gzip"
+        );
+
+        // Gzipped partial content is resumed from the encoded bytes
+        std::fs::write(&partial_path, &gzip(BODY)[..12]).unwrap();
+        std::fs::write(directory.join("file.partial.etag"), "v1").unwrap();
+        let download = download_resumable(&client, &url, None, &partial_path)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut decoded = String::new();
+        GzDecoder::new(std::fs::File::open(&download.path).unwrap())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, BODY);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
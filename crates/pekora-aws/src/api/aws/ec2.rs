@@ -0,0 +1,512 @@
+use crate::api::aws::accounts::MultiAccountClientSet;
+use crate::api::aws::auth::AwsAuthConfig;
+use crate::api::aws::fan_out::{fan_out, merge_regions, FanOutConfig, RegionResults};
+use crate::api::aws::types::{ContractLength, PurchaseOption};
+use crate::api::aws::util::{AwsClientError, AwsClientResult};
+use crate::cache::{CacheKey, Cacheable, CacheableArc};
+use crate::metrics;
+use crate::transform::aws::dedicated_host::HostReservationOffering;
+use crate::transform::aws::instance_specs::InstanceSpec;
+use crate::util::ClientSet;
+use async_trait::async_trait;
+use aws_config::SdkConfig;
+use aws_sdk_ec2::types::{HostOffering, InstanceTypeInfo, PaymentOption};
+use log::info;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+pub use aws_sdk_ec2::types::LocationType;
+
+/// Region asked for the enabled regions when the SDK config has none.
+const DISCOVERY_REGION: &str = "us-east-1";
+
+fn build_client_set(config: SdkConfig) -> ClientSet<SdkConfig, aws_sdk_ec2::Client> {
+    ClientSet::new(
+        config,
+        Box::new(|config, region| {
+            let mut builder = config.into_builder();
+            builder.set_region(aws_config::Region::new(region));
+            let new_config = builder.build();
+            aws_sdk_ec2::Client::new(&new_config)
+        }),
+    )
+}
+
+/// Map of (instance type) -> (locations it is offered in), e.g. availability zones
+pub type InstanceTypeOfferings = BTreeMap<String, BTreeSet<String>>;
+
+pub struct Ec2Client {
+    client_set: Arc<ClientSet<SdkConfig, aws_sdk_ec2::Client>>,
+    discovery_region: String,
+    regions: Option<Vec<String>>,
+    enabled_regions: OnceCell<Vec<String>>,
+    fan_out: FanOutConfig,
+}
+
+impl Ec2Client {
+    /// Queries `regions`, or every region enabled for the account if `None`.
+    pub async fn new(aws_sdk_config: Option<SdkConfig>, regions: Option<Vec<String>>) -> Self {
+        Self::with_auth(aws_sdk_config, &AwsAuthConfig::default(), regions).await
+    }
+
+    /// Like `new`, authenticating with `auth` unless an SDK config is given.
+    pub async fn with_auth(
+        aws_sdk_config: Option<SdkConfig>,
+        auth: &AwsAuthConfig,
+        regions: Option<Vec<String>>,
+    ) -> Self {
+        let config = auth.resolve(aws_sdk_config).await;
+        Self::with_client_set(&config, Arc::new(build_client_set(config.clone())), regions)
+    }
+
+    /// Queries `regions` of `account`, or every region enabled for it if `None`.
+    pub async fn for_account(
+        accounts: &MultiAccountClientSet<aws_sdk_ec2::Client>,
+        account: &str,
+        regions: Option<Vec<String>>,
+    ) -> AwsClientResult<Self> {
+        let clients = accounts.account(account).await?;
+        Ok(Self::with_client_set(
+            &clients.config,
+            clients.client_set,
+            regions,
+        ))
+    }
+
+    fn with_client_set(
+        config: &SdkConfig,
+        client_set: Arc<ClientSet<SdkConfig, aws_sdk_ec2::Client>>,
+        regions: Option<Vec<String>>,
+    ) -> Self {
+        let discovery_region = config
+            .region()
+            .map_or(DISCOVERY_REGION.to_string(), |region| region.to_string());
+        Self {
+            client_set,
+            discovery_region,
+            regions,
+            enabled_regions: OnceCell::new(),
+            fan_out: FanOutConfig::default(),
+        }
+    }
+
+    /// Concurrency and retries of calls made in every region.
+    pub fn with_fan_out(mut self, fan_out: FanOutConfig) -> Self {
+        self.fan_out = fan_out;
+        self
+    }
+
+    async fn region_clients(&self, regions: &[String]) -> Vec<(String, Arc<aws_sdk_ec2::Client>)> {
+        let mut clients = Vec::with_capacity(regions.len());
+        for region in regions {
+            clients.push((region.clone(), self.client_set.get(region).await));
+        }
+        clients
+    }
+
+    /// Regions enabled for the account, sorted. Asked once per client.
+    pub async fn describe_regions(&self) -> AwsClientResult<&[String]> {
+        self.enabled_regions
+            .get_or_try_init(|| async {
+                let client = self.client_set.get(&self.discovery_region).await;
+                describe_regions(client).await
+            })
+            .await
+            .map(Vec::as_slice)
+    }
+
+    /// Regions given to the client, or else the enabled regions.
+    pub async fn regions(&self) -> AwsClientResult<&[String]> {
+        match &self.regions {
+            Some(regions) => Ok(regions),
+            None => self.describe_regions().await,
+        }
+    }
+
+    /// Caches the instance specs of a set of regions, see `Cacheable for Ec2Client`.
+    pub async fn new_cacheable_arc(
+        aws_sdk_config: Option<SdkConfig>,
+    ) -> CacheableArc<Vec<String>, HashMap<String, InstanceSpec>, AwsClientError> {
+        Arc::new(Box::new(Self::new(aws_sdk_config, None).await))
+    }
+
+    pub async fn describe_all_instance_types(
+        &self,
+    ) -> AwsClientResult<RegionResults<HashMap<String, InstanceTypeInfo>>> {
+        let regions = self.regions().await?;
+        Ok(self.describe_instance_types_in(regions).await)
+    }
+
+    /// Instance types offered in each of `regions`, by region.
+    pub async fn describe_instance_types_in(
+        &self,
+        regions: &[String],
+    ) -> RegionResults<HashMap<String, InstanceTypeInfo>> {
+        fan_out(
+            &self.fan_out,
+            self.region_clients(regions).await,
+            |client| describe_instance_types(client, None),
+        )
+        .await
+    }
+
+    /// Instance type offerings by region, located by region or by availability zone (ID) as
+    /// `location_type` asks.
+    pub async fn describe_instance_type_offerings(
+        &self,
+        location_type: LocationType,
+    ) -> AwsClientResult<RegionResults<InstanceTypeOfferings>> {
+        let regions = self.regions().await?;
+        Ok(fan_out(
+            &self.fan_out,
+            self.region_clients(regions).await,
+            move |client| describe_instance_type_offerings(client, location_type.clone()),
+        )
+        .await)
+    }
+
+    /// Dedicated Host reservations offered in each region, by region.
+    pub async fn describe_host_reservation_offerings(
+        &self,
+    ) -> AwsClientResult<RegionResults<Vec<HostReservationOffering>>> {
+        let regions = self.regions().await?;
+        Ok(fan_out(
+            &self.fan_out,
+            self.region_clients(regions).await,
+            describe_host_reservation_offerings,
+        )
+        .await)
+    }
+}
+
+async fn describe_regions(client: Arc<aws_sdk_ec2::Client>) -> AwsClientResult<Vec<String>> {
+    info!(
+        "Ec2Client: Requesting DescribeRegions (region={:?})",
+        client.config().region()
+    );
+    metrics::global().record_request();
+    let output = client
+        .describe_regions()
+        .send()
+        .await
+        .map_err(AwsClientError::DescribeRegionsFailure)?;
+    let mut regions = output
+        .regions
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|region| region.opt_in_status.as_deref() != Some("not-opted-in"))
+        .filter_map(|region| region.region_name)
+        .collect::<Vec<_>>();
+    regions.sort();
+    Ok(regions)
+}
+
+async fn describe_instance_type_offerings(
+    client: Arc<aws_sdk_ec2::Client>,
+    location_type: LocationType,
+) -> AwsClientResult<InstanceTypeOfferings> {
+    info!(
+        "Ec2Client: Requesting DescribeInstanceTypeOfferings (region={:?}, location_type={})",
+        client.config().region(),
+        location_type.as_str()
+    );
+    let mut stream = client
+        .describe_instance_type_offerings()
+        .location_type(location_type)
+        .into_paginator()
+        .send();
+
+    let mut offerings = InstanceTypeOfferings::new();
+    while let Some(page_result) = stream.next().await {
+        metrics::global().record_request();
+        let page = page_result.map_err(AwsClientError::DescribeInstanceTypeOfferingsFailure)?;
+        for offering in page.instance_type_offerings.unwrap_or_default() {
+            if let (Some(instance_type), Some(location)) =
+                (offering.instance_type, offering.location)
+            {
+                offerings
+                    .entry(instance_type.to_string())
+                    .or_default()
+                    .insert(location);
+            }
+        }
+    }
+    Ok(offerings)
+}
+
+async fn describe_host_reservation_offerings(
+    client: Arc<aws_sdk_ec2::Client>,
+) -> AwsClientResult<Vec<HostReservationOffering>> {
+    info!(
+        "Ec2Client: Requesting DescribeHostReservationOfferings (region={:?})",
+        client.config().region()
+    );
+    let mut stream = client
+        .describe_host_reservation_offerings()
+        .into_paginator()
+        .send();
+
+    let mut offerings = Vec::new();
+    while let Some(page_result) = stream.next().await {
+        metrics::global().record_request();
+        let page = page_result.map_err(AwsClientError::DescribeHostReservationOfferingsFailure)?;
+        offerings.extend(
+            page.offering_set
+                .unwrap_or_default()
+                .iter()
+                .filter_map(host_reservation_offering),
+        );
+    }
+    Ok(offerings)
+}
+
+/// Reservation of `offering`, `None` if it lacks an ID, family, payment option or USD prices.
+pub fn host_reservation_offering(offering: &HostOffering) -> Option<HostReservationOffering> {
+    if offering
+        .currency_code()
+        .is_some_and(|currency| currency.as_str() != "USD")
+    {
+        return None;
+    }
+    let purchase_option = match offering.payment_option()? {
+        PaymentOption::AllUpfront => PurchaseOption::AllUpfront,
+        PaymentOption::PartialUpfront => PurchaseOption::PartialUpfront,
+        PaymentOption::NoUpfront => PurchaseOption::NoUpfront,
+        other => PurchaseOption::Unknown(other.as_str().to_string()),
+    };
+    Some(HostReservationOffering {
+        offering_id: offering.offering_id()?.to_string(),
+        instance_family: offering.instance_family()?.to_string(),
+        term: offering
+            .duration()
+            .and_then(|seconds| ContractLength::from_seconds(seconds.into())),
+        purchase_option,
+        upfront_usd: offering.upfront_price()?.parse().ok()?,
+        usd_per_hour: offering.hourly_price()?.parse().ok()?,
+    })
+}
+
+async fn describe_instance_types(
+    client: Arc<aws_sdk_ec2::Client>,
+    instance_types: Option<Vec<String>>,
+) -> AwsClientResult<HashMap<String, InstanceTypeInfo>> {
+    let mut request = client.describe_instance_types();
+
+    if let Some(instance_types) = instance_types {
+        let instance_type_enums = instance_types
+            .iter()
+            .map(|f| aws_sdk_ec2::types::InstanceType::from(f.as_str()))
+            .collect::<Vec<_>>();
+        request = request.set_instance_types(Some(instance_type_enums));
+    }
+
+    let mut result_map = HashMap::new();
+    let mut next_token: Option<String> = None;
+    loop {
+        info!(
+            "Ec2Client: Requesting DescribeInstanceTypes (region={:?})",
+            client.config().region(),
+        );
+        metrics::global().record_request();
+        let result = request
+            .clone()
+            .set_next_token(next_token.clone())
+            .send()
+            .await
+            .map_err(AwsClientError::DescribeInstanceTypesFailure)?;
+        next_token = result.next_token;
+        if result.instance_types.is_none() {
+            break;
+        }
+        let instance_types = result.instance_types.unwrap();
+        info!(
+            "Ec2Client: Found DescribeInstanceTypes (region={:?}, count={})",
+            client.config().region(),
+            instance_types.len()
+        );
+        for ref item in instance_types {
+            if let Some(instance_type) = item.instance_type.as_ref() {
+                result_map.insert(instance_type.to_string(), item.clone());
+            }
+        }
+        if next_token.is_none() {
+            break;
+        }
+    }
+    Ok(result_map)
+}
+
+/// Hardware of `info`, `None` if it has no instance type.
+pub fn instance_spec(info: &InstanceTypeInfo) -> Option<InstanceSpec> {
+    let gpus = info.gpu_info().map(|gpu_info| {
+        gpu_info
+            .gpus()
+            .iter()
+            .map(|gpu| gpu.count().unwrap_or_default())
+            .sum()
+    });
+    Some(InstanceSpec {
+        instance_type: info.instance_type()?.as_str().to_string(),
+        architecture: info
+            .processor_info()
+            .and_then(|processor| processor.supported_architectures().first())
+            .map(|architecture| architecture.as_str().to_string()),
+        vcpus: info.v_cpu_info().and_then(|vcpu| vcpu.default_v_cpus()),
+        memory_gib: info
+            .memory_info()
+            .and_then(|memory| memory.size_in_mib())
+            .map(mib_to_gib),
+        network_performance: info
+            .network_info()
+            .and_then(|network| network.network_performance())
+            .map(str::to_string),
+        gpus,
+        gpu_memory_gib: info
+            .gpu_info()
+            .and_then(|gpu_info| gpu_info.total_gpu_memory_in_mib())
+            .map(|mib| mib_to_gib(mib.into())),
+        gpu_model: info
+            .gpu_info()
+            .and_then(|gpu_info| gpu_info.gpus().first())
+            .and_then(|gpu| match (gpu.manufacturer(), gpu.name()) {
+                (Some(manufacturer), Some(name)) => Some(format!("{} {}", manufacturer, name)),
+                (manufacturer, name) => manufacturer.or(name).map(str::to_string),
+            }),
+        launch_date: None,
+    })
+}
+
+fn mib_to_gib(mib: i64) -> Decimal {
+    Decimal::from(mib) / Decimal::from(1024)
+}
+
+/// Specs of `instance_types` by instance type, e.g. from `Ec2Client::describe_all_instance_types`.
+pub fn instance_specs(
+    instance_types: &HashMap<String, InstanceTypeInfo>,
+) -> HashMap<String, InstanceSpec> {
+    instance_types
+        .values()
+        .filter_map(instance_spec)
+        .map(|spec| (spec.instance_type.clone(), spec))
+        .collect()
+}
+
+/// Instance specs of the regions given as input, e.g. `["us-east-1"]`. DescribeInstanceTypes
+/// carries no validators, so cached specs are reused until they expire.
+/// Specs are only cached once every region answered.
+#[async_trait]
+impl Cacheable<Vec<String>, HashMap<String, InstanceSpec>, AwsClientError> for Ec2Client {
+    async fn get_cache_key(&self, input: &Vec<String>) -> Result<CacheKey, AwsClientError> {
+        Ok(CacheKey {
+            content_key: self.content_key(input),
+            content_hash: None,
+        })
+    }
+
+    async fn load(
+        &self,
+        input: &Vec<String>,
+    ) -> Result<HashMap<String, InstanceSpec>, AwsClientError> {
+        let instance_types = self
+            .describe_instance_types_in(input)
+            .await
+            .into_complete()?;
+        Ok(instance_specs(&merge_regions(instance_types)))
+    }
+
+    fn category_key(&self) -> String {
+        "aws/ec2/instance-types".to_string()
+    }
+
+    fn content_key(&self, input: &Vec<String>) -> Option<String> {
+        Some(region_set_key(input))
+    }
+}
+
+/// Key of a set of regions, independent of their order and duplicates.
+fn region_set_key(regions: &[String]) -> String {
+    regions
+        .iter()
+        .map(String::as_str)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{host_reservation_offering, instance_spec, region_set_key};
+    use crate::api::aws::types::{ContractLength, PurchaseOption};
+    use aws_sdk_ec2::types::{
+        ArchitectureType, GpuDeviceInfo, GpuInfo, HostOffering, InstanceType, InstanceTypeInfo,
+        MemoryInfo, PaymentOption, ProcessorInfo, VCpuInfo,
+    };
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_instance_spec() {
+        let info = InstanceTypeInfo::builder()
+            .instance_type(InstanceType::M5Large)
+            .processor_info(
+                ProcessorInfo::builder()
+                    .supported_architectures(ArchitectureType::X8664)
+                    .build(),
+            )
+            .v_cpu_info(VCpuInfo::builder().default_v_cpus(2).build())
+            .memory_info(MemoryInfo::builder().size_in_mib(8192).build())
+            .build();
+        let spec = instance_spec(&info).unwrap();
+        assert_eq!(spec.instance_type, "m5.large");
+        assert_eq!(spec.architecture.as_deref(), Some("x86_64"));
+        assert_eq!(spec.vcpus, Some(2));
+        assert_eq!(spec.memory_gib, Some(Decimal::from(8)));
+        assert_eq!(spec.gpus, None);
+        assert!(instance_spec(&InstanceTypeInfo::builder().build()).is_none());
+
+        let info = InstanceTypeInfo::builder()
+            .instance_type(InstanceType::G512xlarge)
+            .gpu_info(
+                GpuInfo::builder()
+                    .gpus(
+                        GpuDeviceInfo::builder()
+                            .name("A10G")
+                            .manufacturer("NVIDIA")
+                            .count(4)
+                            .build(),
+                    )
+                    .total_gpu_memory_in_mib(98304)
+                    .build(),
+            )
+            .build();
+        let spec = instance_spec(&info).unwrap();
+        assert_eq!(spec.gpus, Some(4));
+        assert_eq!(spec.gpu_memory_gib, Some(Decimal::from(96)));
+        assert_eq!(spec.gpu_model.as_deref(), Some("NVIDIA A10G"));
+    }
+
+    #[test]
+    fn test_host_reservation_offering() {
+        let offering = HostOffering::builder()
+            .offering_id("hro-0a1b2c3d")
+            .instance_family("m5")
+            .duration(94608000)
+            .payment_option(PaymentOption::PartialUpfront)
+            .upfront_price("30000.000")
+            .hourly_price("1.141")
+            .build();
+        let reservation = host_reservation_offering(&offering).unwrap();
+        assert_eq!(reservation.term, Some(ContractLength::ThreeYear));
+        assert_eq!(reservation.purchase_option, PurchaseOption::PartialUpfront);
+        assert_eq!(reservation.usd_per_hour, Decimal::new(1141, 3));
+        assert!(host_reservation_offering(&HostOffering::builder().build()).is_none());
+    }
+
+    #[test]
+    fn test_region_set_key() {
+        let regions = ["us-east-1", "ap-northeast-2", "us-east-1"].map(str::to_string);
+        assert_eq!(region_set_key(&regions), "ap-northeast-2_us-east-1");
+    }
+}
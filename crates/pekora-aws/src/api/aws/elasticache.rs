@@ -0,0 +1,240 @@
+use crate::api::aws::accounts::MultiAccountClientSet;
+use crate::api::aws::auth::AwsAuthConfig;
+use crate::api::aws::util::{AwsClientError, AwsClientResult};
+use crate::cache::{CacheKey, Cacheable, CacheableArc};
+use crate::metrics;
+use crate::util::ClientSet;
+use async_trait::async_trait;
+use aws_config::SdkConfig;
+use aws_sdk_elasticache::types::CacheNodeTypeSpecificParameter;
+use log::info;
+use pekora_core::model::aws::rds::RecurringCharge;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub use pekora_core::model::aws::elasticache::ReservedCacheNodesOffering;
+
+pub const REDIS_PARAMETER_GROUP_FAMILY: &str = "redis7";
+pub const MEMCACHED_PARAMETER_GROUP_FAMILY: &str = "memcached1.6";
+
+/// Engine default parameters are the same everywhere, so they are asked of a single region.
+const PARAMETER_REGION: &str = "us-east-1";
+
+async fn build_client_set(
+    aws_sdk_config: Option<SdkConfig>,
+    auth: &AwsAuthConfig,
+) -> ClientSet<SdkConfig, aws_sdk_elasticache::Client> {
+    let config = auth.resolve(aws_sdk_config).await;
+    ClientSet::new(
+        config,
+        Box::new(|config, region| {
+            let mut builder = config.into_builder();
+            builder.set_region(aws_config::Region::new(region));
+            let new_config = builder.build();
+            aws_sdk_elasticache::Client::new(&new_config)
+        }),
+    )
+}
+
+/// Map of (instance type) -> (parameter name) -> (parameter value)
+pub type TypeSpecificParameters = HashMap<String, HashMap<String, String>>;
+
+pub struct ElasticacheClient {
+    client_set: Arc<ClientSet<SdkConfig, aws_sdk_elasticache::Client>>,
+}
+
+impl ElasticacheClient {
+    pub async fn new(aws_sdk_config: Option<SdkConfig>) -> Self {
+        Self::with_auth(aws_sdk_config, &AwsAuthConfig::default()).await
+    }
+
+    /// Like `new`, authenticating with `auth` unless an SDK config is given.
+    pub async fn with_auth(aws_sdk_config: Option<SdkConfig>, auth: &AwsAuthConfig) -> Self {
+        Self {
+            client_set: Arc::new(build_client_set(aws_sdk_config, auth).await),
+        }
+    }
+
+    pub async fn for_account(
+        accounts: &MultiAccountClientSet<aws_sdk_elasticache::Client>,
+        account: &str,
+    ) -> AwsClientResult<Self> {
+        Ok(Self {
+            client_set: accounts.account(account).await?.client_set,
+        })
+    }
+
+    /// Caches type specific parameters by parameter group family, e.g. `redis7`.
+    pub async fn new_cacheable_arc(
+        aws_sdk_config: Option<SdkConfig>,
+    ) -> CacheableArc<String, TypeSpecificParameters, AwsClientError> {
+        Arc::new(Box::new(Self::new(aws_sdk_config).await))
+    }
+
+    pub async fn list_redis_type_specific_parameters(
+        &self,
+    ) -> AwsClientResult<TypeSpecificParameters> {
+        self.list_cache_node_type_specific_parameters(REDIS_PARAMETER_GROUP_FAMILY)
+            .await
+    }
+
+    pub async fn list_memcached_type_specific_parameters(
+        &self,
+    ) -> AwsClientResult<TypeSpecificParameters> {
+        self.list_cache_node_type_specific_parameters(MEMCACHED_PARAMETER_GROUP_FAMILY)
+            .await
+    }
+
+    pub async fn list_cache_node_type_specific_parameters(
+        &self,
+        parameter_group_family: &str,
+    ) -> AwsClientResult<TypeSpecificParameters> {
+        let client = self.client_set.get(PARAMETER_REGION).await;
+
+        let result =
+            list_cache_node_type_specific_parameters(client, parameter_group_family).await?;
+
+        let mut result_map = HashMap::new();
+        for parameter in result {
+            let parameter_name = match &parameter.parameter_name {
+                Some(name) => name,
+                None => continue,
+            };
+            for item in parameter
+                .cache_node_type_specific_values
+                .unwrap_or(Vec::new())
+            {
+                let instance_type = match &item.cache_node_type {
+                    Some(instance_type) => instance_type,
+                    None => continue,
+                };
+                let parameter_value = match &item.value {
+                    Some(parameter_value) => parameter_value,
+                    None => continue,
+                };
+                result_map
+                    .entry(instance_type.clone())
+                    .or_insert(HashMap::new())
+                    .insert(parameter_name.clone(), parameter_value.clone());
+            }
+        }
+        Ok(result_map)
+    }
+
+    /// Reserved node offerings sold in `region`, of `cache_node_type` only if given.
+    pub async fn describe_reserved_cache_nodes_offerings(
+        &self,
+        region: &str,
+        cache_node_type: Option<&str>,
+    ) -> AwsClientResult<Vec<ReservedCacheNodesOffering>> {
+        let client = self.client_set.get(region).await;
+        info!(
+            "ElasticacheClient: DescribeReservedCacheNodesOfferings (region={}, node_type={:?})",
+            region, cache_node_type
+        );
+        let mut stream = client
+            .describe_reserved_cache_nodes_offerings()
+            .set_cache_node_type(cache_node_type.map(str::to_string))
+            .into_paginator()
+            .send();
+
+        let mut result = Vec::new();
+        while let Some(page_result) = stream.next().await {
+            metrics::global().record_request();
+            let page =
+                page_result.map_err(AwsClientError::DescribeReservedCacheNodesOfferingsFailure)?;
+            result.extend(
+                page.reserved_cache_nodes_offerings
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(reserved_cache_nodes_offering),
+            );
+        }
+        Ok(result)
+    }
+}
+
+fn reserved_cache_nodes_offering(
+    offering: aws_sdk_elasticache::types::ReservedCacheNodesOffering,
+) -> ReservedCacheNodesOffering {
+    ReservedCacheNodesOffering {
+        reserved_cache_nodes_offering_id: offering
+            .reserved_cache_nodes_offering_id
+            .unwrap_or_default(),
+        cache_node_type: offering.cache_node_type.unwrap_or_default(),
+        duration: offering.duration.unwrap_or_default().into(),
+        fixed_price: offering.fixed_price.unwrap_or_default(),
+        usage_price: offering.usage_price.unwrap_or_default(),
+        product_description: offering.product_description.unwrap_or_default(),
+        offering_type: offering.offering_type.unwrap_or_default(),
+        recurring_charges: offering
+            .recurring_charges
+            .unwrap_or_default()
+            .into_iter()
+            .map(|charge| RecurringCharge {
+                recurring_charge_amount: charge.recurring_charge_amount.unwrap_or_default(),
+                recurring_charge_frequency: charge.recurring_charge_frequency.unwrap_or_default(),
+            })
+            .collect(),
+    }
+}
+
+/// Type specific parameters of the parameter group family given as input. Engine defaults carry
+/// no validators, so cached parameters are reused until they expire.
+#[async_trait]
+impl Cacheable<String, TypeSpecificParameters, AwsClientError> for ElasticacheClient {
+    async fn get_cache_key(&self, input: &String) -> Result<CacheKey, AwsClientError> {
+        Ok(CacheKey {
+            content_key: self.content_key(input),
+            content_hash: None,
+        })
+    }
+
+    async fn load(&self, input: &String) -> Result<TypeSpecificParameters, AwsClientError> {
+        self.list_cache_node_type_specific_parameters(input).await
+    }
+
+    fn category_key(&self) -> String {
+        "aws/elasticache/type-specific-parameters".to_string()
+    }
+
+    fn content_key(&self, input: &String) -> Option<String> {
+        Some(format!("{}_{}", PARAMETER_REGION, input))
+    }
+}
+
+async fn list_cache_node_type_specific_parameters(
+    client: Arc<aws_sdk_elasticache::Client>,
+    parameter_group_family: &str,
+) -> AwsClientResult<Vec<CacheNodeTypeSpecificParameter>> {
+    info!(
+        "ElasticacheClient: DescribeEngineDefaultParameters for {}",
+        parameter_group_family
+    );
+    let request = client
+        .describe_engine_default_parameters()
+        .set_cache_parameter_group_family(Some(parameter_group_family.to_string()))
+        .into_paginator();
+
+    let mut stream = request.send();
+    let mut result = Vec::new();
+
+    while let Some(page_result) = stream.next().await {
+        metrics::global().record_request();
+        match page_result {
+            Ok(page) => {
+                let engine_defaults = match page.engine_defaults {
+                    Some(parameters) => parameters,
+                    None => continue,
+                };
+                result.extend(
+                    engine_defaults
+                        .cache_node_type_specific_parameters
+                        .unwrap_or(Vec::new()),
+                );
+            }
+            Err(e) => return Err(AwsClientError::DescribeEngineDefaultParametersFailure(e)),
+        }
+    }
+    Ok(result)
+}
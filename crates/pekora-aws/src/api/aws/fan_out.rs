@@ -0,0 +1,196 @@
+use crate::api::aws::util::{AwsClientError, AwsClientResult};
+use crate::util::{AdaptiveLimiter, RetryClass, RetryPolicy};
+use log::warn;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// How calls fanned out to several regions are run.
+#[derive(Debug, Clone)]
+pub struct FanOutConfig {
+    /// Regions called at the same time at first, and after throttling
+    pub min_concurrency: usize,
+    /// Regions called at the same time at most, ramped up to while nothing is throttled
+    pub max_concurrency: usize,
+    /// Retries of each region, on throttling and other transient failures
+    pub retry_policy: RetryPolicy,
+}
+
+impl Default for FanOutConfig {
+    fn default() -> Self {
+        Self {
+            min_concurrency: 2,
+            max_concurrency: 8,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+}
+
+/// Results of a call made in several regions. A region failing doesn't fail the others, its
+/// error is kept instead.
+#[derive(Debug)]
+pub struct RegionResults<T> {
+    pub results: BTreeMap<String, T>,
+    pub errors: BTreeMap<String, AwsClientError>,
+}
+
+impl<T> RegionResults<T> {
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Results of every region, or the error of the first region that failed.
+    #[allow(clippy::result_large_err)]
+    pub fn into_complete(self) -> AwsClientResult<BTreeMap<String, T>> {
+        match self.errors.into_iter().next() {
+            Some((region, source)) => Err(AwsClientError::Region {
+                region,
+                source: Box::new(source),
+            }),
+            None => Ok(self.results),
+        }
+    }
+}
+
+/// Entries of all regions in one map. Keys present in several regions keep the value of the
+/// first region by name.
+pub fn merge_regions<K: Eq + Hash, V>(results: BTreeMap<String, HashMap<K, V>>) -> HashMap<K, V> {
+    let mut merged = HashMap::new();
+    for entries in results.into_values() {
+        for (key, value) in entries {
+            merged.entry(key).or_insert(value);
+        }
+    }
+    merged
+}
+
+/// Calls `operation` with the input of each region, retrying each region as
+/// `config.retry_policy` allows. Regions are called between `config.min_concurrency` and
+/// `config.max_concurrency` at a time, backing off when a region is throttled.
+pub(crate) async fn fan_out<I, T, F, Fut>(
+    config: &FanOutConfig,
+    inputs: Vec<(String, I)>,
+    operation: F,
+) -> RegionResults<T>
+where
+    I: Clone + Send + 'static,
+    T: Send + 'static,
+    F: Fn(I) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = AwsClientResult<T>> + Send,
+{
+    let limiter = Arc::new(AdaptiveLimiter::new(
+        config.min_concurrency,
+        config.max_concurrency,
+    ));
+    let operation = Arc::new(operation);
+    let mut tasks = Vec::with_capacity(inputs.len());
+    for (region, input) in inputs {
+        let limiter = limiter.clone();
+        let operation = operation.clone();
+        let policy = config.retry_policy.clone();
+        let task_region = region.clone();
+        tasks.push((
+            region,
+            tokio::spawn(async move {
+                let mut attempt = 1;
+                loop {
+                    // The permit is released while backing off
+                    let mut permit = limiter.acquire().await;
+                    let result = operation(input.clone()).await;
+                    if let Err(e) = &result {
+                        if e.retry_class() == Some(RetryClass::TooManyRequests) {
+                            permit.throttled();
+                        }
+                    }
+                    drop(permit);
+                    match result {
+                        Ok(result) => return Ok(result),
+                        Err(e) if policy.should_retry(attempt, e.retry_class()) => {
+                            let backoff = policy.backoff(attempt);
+                            warn!(
+                                "{} failed (attempt {}/{}), retrying in {:?}: {}",
+                                task_region, attempt, policy.max_attempts, backoff, e
+                            );
+                            tokio::time::sleep(backoff).await;
+                            attempt += 1;
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+            }),
+        ));
+    }
+
+    let mut results = RegionResults {
+        results: BTreeMap::new(),
+        errors: BTreeMap::new(),
+    };
+    for (region, task) in tasks {
+        let result = match task.await {
+            Ok(result) => result,
+            Err(e) => Err(AwsClientError::Tokio(e)),
+        };
+        match result {
+            Ok(result) => {
+                results.results.insert(region, result);
+            }
+            Err(e) => {
+                warn!("{} failed: {}", region, e);
+                results.errors.insert(region, e);
+            }
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fan_out, FanOutConfig};
+    use crate::api::aws::util::AwsClientError;
+    use crate::util::RetryPolicy;
+    use aws_sdk_ec2::error::SdkError;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_fan_out() {
+        let config = FanOutConfig {
+            min_concurrency: 1,
+            max_concurrency: 1,
+            retry_policy: RetryPolicy {
+                initial_backoff: Duration::from_millis(1),
+                ..RetryPolicy::default()
+            },
+        };
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let inputs = ["ap-northeast-2", "us-east-1"]
+            .map(|region| (region.to_string(), region))
+            .to_vec();
+        let results = fan_out(&config, inputs, move |region| {
+            let attempt = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                match region {
+                    // fails once with a retryable timeout, then succeeds
+                    "ap-northeast-2" if attempt == 0 => Err(
+                        AwsClientError::DescribeRegionsFailure(SdkError::timeout_error("slow")),
+                    ),
+                    "ap-northeast-2" => Ok(region.len()),
+                    _ => Err(AwsClientError::DescribeRegionsFailure(
+                        SdkError::construction_failure("invalid"),
+                    )),
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(results.results.len(), 1);
+        assert_eq!(results.results["ap-northeast-2"], 14);
+        assert!(results.errors.contains_key("us-east-1"));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(!results.is_complete());
+        assert!(results.into_complete().is_err());
+    }
+}
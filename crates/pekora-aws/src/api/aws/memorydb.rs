@@ -0,0 +1,123 @@
+use crate::api::aws::accounts::MultiAccountClientSet;
+use crate::api::aws::auth::AwsAuthConfig;
+use crate::api::aws::util::{AwsClientError, AwsClientResult};
+use crate::cache::{CacheKey, Cacheable, CacheableArc};
+use crate::metrics;
+use crate::util::ClientSet;
+use async_trait::async_trait;
+use aws_config::SdkConfig;
+use log::info;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+pub const DEFAULT_PARAMETER_GROUP: &str = "default.memorydb-redis7";
+
+/// Default parameter groups are the same everywhere, so they are asked of a single region.
+const PARAMETER_REGION: &str = "us-east-1";
+
+async fn build_client_set(
+    aws_sdk_config: Option<SdkConfig>,
+    auth: &AwsAuthConfig,
+) -> ClientSet<SdkConfig, aws_sdk_memorydb::Client> {
+    let config = auth.resolve(aws_sdk_config).await;
+    ClientSet::new(
+        config,
+        Box::new(|config, region| {
+            let mut builder = config.into_builder();
+            builder.set_region(aws_config::Region::new(region));
+            let new_config = builder.build();
+            aws_sdk_memorydb::Client::new(&new_config)
+        }),
+    )
+}
+
+/// Map of (parameter name) -> (parameter value)
+pub type MemoryDbParameters = BTreeMap<String, String>;
+
+pub struct MemoryDbClient {
+    client_set: Arc<ClientSet<SdkConfig, aws_sdk_memorydb::Client>>,
+}
+
+impl MemoryDbClient {
+    pub async fn new(aws_sdk_config: Option<SdkConfig>) -> Self {
+        Self::with_auth(aws_sdk_config, &AwsAuthConfig::default()).await
+    }
+
+    /// Like `new`, authenticating with `auth` unless an SDK config is given.
+    pub async fn with_auth(aws_sdk_config: Option<SdkConfig>, auth: &AwsAuthConfig) -> Self {
+        Self {
+            client_set: Arc::new(build_client_set(aws_sdk_config, auth).await),
+        }
+    }
+
+    pub async fn for_account(
+        accounts: &MultiAccountClientSet<aws_sdk_memorydb::Client>,
+        account: &str,
+    ) -> AwsClientResult<Self> {
+        Ok(Self {
+            client_set: accounts.account(account).await?.client_set,
+        })
+    }
+
+    /// Caches parameters by parameter group name, e.g. `default.memorydb-redis7`.
+    pub async fn new_cacheable_arc(
+        aws_sdk_config: Option<SdkConfig>,
+    ) -> CacheableArc<String, MemoryDbParameters, AwsClientError> {
+        Arc::new(Box::new(Self::new(aws_sdk_config).await))
+    }
+
+    /// Parameters of the parameter group named `parameter_group_name`. Parameters without a
+    /// value are skipped.
+    pub async fn describe_parameters(
+        &self,
+        parameter_group_name: &str,
+    ) -> AwsClientResult<MemoryDbParameters> {
+        let client = self.client_set.get(PARAMETER_REGION).await;
+        info!(
+            "MemoryDbClient: DescribeParameters for {}",
+            parameter_group_name
+        );
+        let mut stream = client
+            .describe_parameters()
+            .parameter_group_name(parameter_group_name)
+            .into_paginator()
+            .send();
+
+        let mut result = MemoryDbParameters::new();
+        while let Some(page_result) = stream.next().await {
+            metrics::global().record_request();
+            let page = page_result.map_err(AwsClientError::DescribeParametersFailure)?;
+            result.extend(
+                page.parameters
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|parameter| Some((parameter.name?, parameter.value?))),
+            );
+        }
+        Ok(result)
+    }
+}
+
+/// Parameters of the parameter group named as input. Default groups carry no validators, so
+/// cached parameters are reused until they expire.
+#[async_trait]
+impl Cacheable<String, MemoryDbParameters, AwsClientError> for MemoryDbClient {
+    async fn get_cache_key(&self, input: &String) -> Result<CacheKey, AwsClientError> {
+        Ok(CacheKey {
+            content_key: self.content_key(input),
+            content_hash: None,
+        })
+    }
+
+    async fn load(&self, input: &String) -> Result<MemoryDbParameters, AwsClientError> {
+        self.describe_parameters(input).await
+    }
+
+    fn category_key(&self) -> String {
+        "aws/memorydb/parameters".to_string()
+    }
+
+    fn content_key(&self, input: &String) -> Option<String> {
+        Some(format!("{}_{}", PARAMETER_REGION, input))
+    }
+}
@@ -0,0 +1,25 @@
+pub mod accounts;
+pub mod auth;
+pub mod compute_optimizer;
+#[cfg(feature = "cost-explorer")]
+pub mod cost_explorer;
+pub mod docdb;
+pub mod download;
+pub mod ec2;
+pub mod elasticache;
+pub mod fan_out;
+pub mod memorydb;
+pub mod opensearch;
+pub mod price_bulk;
+pub mod price_bulk_builder;
+pub mod pricing_query;
+pub mod rds;
+pub mod redshift;
+pub mod savings_plans;
+pub mod spot_advisor;
+pub mod ssm;
+mod util;
+
+#[cfg(not(feature = "cost-explorer"))]
+pub use pekora_core::model::aws::cost_explorer;
+pub use pekora_core::model::aws::{price_bulk_types, schema, types};
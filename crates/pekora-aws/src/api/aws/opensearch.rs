@@ -0,0 +1,155 @@
+use crate::api::aws::accounts::MultiAccountClientSet;
+use crate::api::aws::auth::AwsAuthConfig;
+use crate::api::aws::fan_out::{fan_out, FanOutConfig, RegionResults};
+use crate::api::aws::util::{AwsClientError, AwsClientResult};
+use crate::metrics;
+use crate::util::ClientSet;
+use aws_config::SdkConfig;
+use log::info;
+use std::sync::Arc;
+
+pub use pekora_core::model::aws::opensearch::{
+    InstanceTypeDetails, ListInstanceTypeDetailsResponse,
+};
+
+fn build_client_set(config: SdkConfig) -> ClientSet<SdkConfig, aws_sdk_opensearch::Client> {
+    ClientSet::new(
+        config,
+        Box::new(|config, region| {
+            let mut builder = config.into_builder();
+            builder.set_region(aws_config::Region::new(region));
+            let new_config = builder.build();
+            aws_sdk_opensearch::Client::new(&new_config)
+        }),
+    )
+}
+
+pub struct OpenSearchClient {
+    client_set: Arc<ClientSet<SdkConfig, aws_sdk_opensearch::Client>>,
+    regions: Vec<String>,
+    fan_out: FanOutConfig,
+}
+
+impl OpenSearchClient {
+    /// Queries `regions`. OpenSearch Service has no call listing the regions it is in, so they
+    /// are given.
+    pub async fn new(aws_sdk_config: Option<SdkConfig>, regions: Vec<String>) -> Self {
+        Self::with_auth(aws_sdk_config, &AwsAuthConfig::default(), regions).await
+    }
+
+    /// Like `new`, authenticating with `auth` unless an SDK config is given.
+    pub async fn with_auth(
+        aws_sdk_config: Option<SdkConfig>,
+        auth: &AwsAuthConfig,
+        regions: Vec<String>,
+    ) -> Self {
+        let config = auth.resolve(aws_sdk_config).await;
+        Self {
+            client_set: Arc::new(build_client_set(config)),
+            regions,
+            fan_out: FanOutConfig::default(),
+        }
+    }
+
+    /// Queries `regions` of `account`.
+    pub async fn for_account(
+        accounts: &MultiAccountClientSet<aws_sdk_opensearch::Client>,
+        account: &str,
+        regions: Vec<String>,
+    ) -> AwsClientResult<Self> {
+        Ok(Self {
+            client_set: accounts.account(account).await?.client_set,
+            regions,
+            fan_out: FanOutConfig::default(),
+        })
+    }
+
+    /// Concurrency and retries of calls made in every region.
+    pub fn with_fan_out(mut self, fan_out: FanOutConfig) -> Self {
+        self.fan_out = fan_out;
+        self
+    }
+
+    async fn region_clients(&self) -> Vec<(String, Arc<aws_sdk_opensearch::Client>)> {
+        let mut clients = Vec::with_capacity(self.regions.len());
+        for region in &self.regions {
+            clients.push((region.clone(), self.client_set.get(region).await));
+        }
+        clients
+    }
+
+    /// Instance types available in each region to domains of `engine_version`, as the API
+    /// names it, e.g. `OpenSearch_2.11` or `Elasticsearch_7.10`.
+    pub async fn list_instance_type_details(
+        &self,
+        engine_version: &str,
+    ) -> RegionResults<Vec<InstanceTypeDetails>> {
+        let engine_version = engine_version.to_string();
+        fan_out(&self.fan_out, self.region_clients().await, move |client| {
+            list_instance_type_details(client, engine_version.clone())
+        })
+        .await
+    }
+}
+
+async fn list_instance_type_details(
+    client: Arc<aws_sdk_opensearch::Client>,
+    engine_version: String,
+) -> AwsClientResult<Vec<InstanceTypeDetails>> {
+    info!(
+        "OpenSearchClient: Requesting ListInstanceTypeDetails (region={:?}, engine_version={})",
+        client.config().region(),
+        engine_version
+    );
+    let mut stream = client
+        .list_instance_type_details()
+        .engine_version(engine_version)
+        .retrieve_azs(true)
+        .into_paginator()
+        .send();
+
+    let mut details = Vec::new();
+    while let Some(page_result) = stream.next().await {
+        metrics::global().record_request();
+        let page = page_result.map_err(AwsClientError::ListInstanceTypeDetailsFailure)?;
+        details.extend(
+            page.instance_type_details
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(instance_type_details),
+        );
+    }
+    Ok(details)
+}
+
+/// Typed `details`, `None` if it lacks an instance type.
+pub fn instance_type_details(
+    details: aws_sdk_opensearch::types::InstanceTypeDetails,
+) -> Option<InstanceTypeDetails> {
+    Some(InstanceTypeDetails {
+        instance_type: details.instance_type?.as_str().to_string(),
+        instance_role: details.instance_role.unwrap_or_default(),
+        availability_zones: details.availability_zones.unwrap_or_default(),
+        warm_enabled: details.warm_enabled,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::instance_type_details;
+    use aws_sdk_opensearch::types::{InstanceTypeDetails, OpenSearchPartitionInstanceType};
+
+    #[test]
+    fn test_instance_type_details() {
+        let details = InstanceTypeDetails::builder()
+            .instance_type(OpenSearchPartitionInstanceType::R6gLargeSearch)
+            .instance_role("data")
+            .availability_zones("us-east-1a")
+            .warm_enabled(false)
+            .build();
+        let details = instance_type_details(details).unwrap();
+        assert_eq!(details.instance_type, "r6g.large.search");
+        assert_eq!(details.instance_role, vec!["data"]);
+        assert!(instance_type_details(InstanceTypeDetails::builder().build()).is_none());
+    }
+}
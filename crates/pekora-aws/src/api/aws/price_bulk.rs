@@ -0,0 +1,1138 @@
+use crate::api::aws::download::{download_resumable, remove_partial};
+use crate::api::aws::price_bulk_builder::PriceBulkContext;
+pub use crate::api::aws::price_bulk_types::Partition;
+use crate::api::aws::price_bulk_types::*;
+use crate::api::aws::types::{KnownValues, UnknownValuesError};
+use crate::cache::{CacheKey, Cacheable, CacheableArc, ConditionalLoad, LAST_MODIFIED_HASH_PREFIX};
+use crate::metrics;
+use crate::status::RequestOutcome;
+use crate::util::{RetryClass, RetryPolicy};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use flate2::read::GzDecoder;
+use log::{debug, warn};
+use reqwest::header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use std::fs::File;
+use std::future::Future;
+use std::io::{BufRead, BufReader, Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// Body chunks received ahead of the parser
+const BODY_CHUNKS_BUFFERED: usize = 16;
+const LAST_MODIFIED_HASH_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+pub struct ServiceIndexClient {
+    context: Arc<PriceBulkContext>,
+}
+
+#[async_trait]
+impl Cacheable<(), ServiceListResponse, PriceBulkError> for ServiceIndexClient {
+    async fn get_cache_key(&self, input: &()) -> Result<CacheKey, PriceBulkError> {
+        let request_url = self.request_url();
+        let content_hash = load_etag(&self.context, request_url.as_str()).await?;
+        Ok(CacheKey {
+            content_key: self.content_key(input),
+            content_hash,
+        })
+    }
+
+    async fn load(&self, input: &()) -> Result<ServiceListResponse, PriceBulkError> {
+        unconditional(self.load_conditional(input, None).await?)
+    }
+
+    fn category_key(&self) -> String {
+        self.context.partition.category_key("service_index")
+    }
+
+    fn content_key(&self, _input: &()) -> Option<String> {
+        None
+    }
+
+    async fn load_conditional(
+        &self,
+        input: &(),
+        cached_key: Option<&CacheKey>,
+    ) -> Result<ConditionalLoad<ServiceListResponse>, PriceBulkError> {
+        let request_url = self.request_url();
+        with_retry(&self.context.retry_policy, request_url.as_str(), || {
+            load_json_conditional(
+                &self.context,
+                request_url.as_str(),
+                self.content_key(input),
+                cached_key,
+            )
+        })
+        .await
+    }
+}
+
+impl ServiceIndexClient {
+    pub fn new_cacheable_arc(
+        context: Arc<PriceBulkContext>,
+    ) -> CacheableArc<(), ServiceListResponse, PriceBulkError> {
+        Arc::new(Box::new(Self { context }))
+    }
+
+    fn request_url(&self) -> String {
+        format!(
+            "{}/offers/v1.0/{}/index.json",
+            self.context.base_url,
+            self.context.partition.path_segment()
+        )
+    }
+}
+
+pub struct RegionIndexClient {
+    context: Arc<PriceBulkContext>,
+}
+
+#[async_trait]
+impl Cacheable<String, RegionIndexResponse, PriceBulkError> for RegionIndexClient {
+    async fn get_cache_key(&self, service_code: &String) -> Result<CacheKey, PriceBulkError> {
+        let request_url = self.request_url(service_code);
+        let content_hash = load_etag(&self.context, request_url.as_str()).await?;
+        Ok(CacheKey {
+            content_key: self.content_key(service_code),
+            content_hash,
+        })
+    }
+
+    async fn load(&self, service_code: &String) -> Result<RegionIndexResponse, PriceBulkError> {
+        unconditional(self.load_conditional(service_code, None).await?)
+    }
+
+    fn category_key(&self) -> String {
+        self.context.partition.category_key("region_index")
+    }
+
+    fn content_key(&self, service_code: &String) -> Option<String> {
+        Some(service_code.clone())
+    }
+
+    async fn load_conditional(
+        &self,
+        service_code: &String,
+        cached_key: Option<&CacheKey>,
+    ) -> Result<ConditionalLoad<RegionIndexResponse>, PriceBulkError> {
+        let request_url = self.request_url(service_code);
+        with_retry(&self.context.retry_policy, request_url.as_str(), || {
+            load_json_conditional(
+                &self.context,
+                request_url.as_str(),
+                self.content_key(service_code),
+                cached_key,
+            )
+        })
+        .await
+    }
+}
+
+impl RegionIndexClient {
+    pub fn new_cacheable_arc(
+        context: Arc<PriceBulkContext>,
+    ) -> CacheableArc<String, RegionIndexResponse, PriceBulkError> {
+        Arc::new(Box::new(Self { context }))
+    }
+
+    fn request_url(&self, service_code: &String) -> String {
+        format!(
+            "{}/offers/v1.0/{}/{}/current/region_index.json",
+            self.context.base_url,
+            self.context.partition.path_segment(),
+            service_code
+        )
+    }
+}
+
+pub struct PricingListClient {
+    context: Arc<PriceBulkContext>,
+}
+
+#[async_trait]
+impl Cacheable<PriceBulkOffer, PricingListResponse, PriceBulkError> for PricingListClient {
+    async fn get_cache_key(&self, input: &PriceBulkOffer) -> Result<CacheKey, PriceBulkError> {
+        let request_url = format!(
+            "{}/{}",
+            self.context.base_url,
+            input.path(self.context.partition)
+        );
+        Ok(CacheKey {
+            content_key: self.content_key(input),
+            content_hash: load_etag(&self.context, request_url.as_str()).await?,
+        })
+    }
+
+    async fn load(&self, input: &PriceBulkOffer) -> Result<PricingListResponse, PriceBulkError> {
+        unconditional(self.load_conditional(input, None).await?)
+    }
+
+    fn category_key(&self) -> String {
+        self.context.partition.category_key("pricing_list")
+    }
+
+    fn content_key(&self, input: &PriceBulkOffer) -> Option<String> {
+        Some(input.tag())
+    }
+
+    async fn load_conditional(
+        &self,
+        input: &PriceBulkOffer,
+        cached_key: Option<&CacheKey>,
+    ) -> Result<ConditionalLoad<PricingListResponse>, PriceBulkError> {
+        let request_url = format!(
+            "{}/{}",
+            self.context.base_url,
+            input.path(self.context.partition)
+        );
+        let partial_path = self.partial_path(input);
+        let loaded: ConditionalLoad<PricingListResponse> =
+            with_retry(&self.context.retry_policy, request_url.as_str(), || {
+                download_json_conditional(
+                    &self.context,
+                    request_url.as_str(),
+                    self.content_key(input),
+                    cached_key,
+                    &partial_path,
+                )
+            })
+            .await?;
+        if let ConditionalLoad::Modified { result, .. } = &loaded {
+            result.check_known(self.context.strict)?;
+        }
+        Ok(loaded)
+    }
+}
+
+impl PricingListClient {
+    pub fn new_cacheable_arc(
+        context: Arc<PriceBulkContext>,
+    ) -> CacheableArc<PriceBulkOffer, PricingListResponse, PriceBulkError> {
+        Arc::new(Box::new(Self { context }))
+    }
+
+    /// Partial downloads are kept next to the cache entries of the same category.
+    fn partial_path(&self, input: &PriceBulkOffer) -> PathBuf {
+        self.context
+            .download_directory
+            .join(self.category_key())
+            .join(format!("{}.partial", input.tag()))
+    }
+}
+
+pub struct SavingsPlanListClient {
+    context: Arc<PriceBulkContext>,
+}
+
+#[async_trait]
+impl Cacheable<PriceBulkSavingsPlan, SavingsPlanListResponse, PriceBulkError>
+    for SavingsPlanListClient
+{
+    async fn get_cache_key(
+        &self,
+        input: &PriceBulkSavingsPlan,
+    ) -> Result<CacheKey, PriceBulkError> {
+        let request_url = format!(
+            "{}/{}",
+            self.context.base_url,
+            input.path(self.context.partition)
+        );
+        Ok(CacheKey {
+            content_key: self.content_key(input),
+            content_hash: load_etag(&self.context, request_url.as_str()).await?,
+        })
+    }
+
+    async fn load(
+        &self,
+        input: &PriceBulkSavingsPlan,
+    ) -> Result<SavingsPlanListResponse, PriceBulkError> {
+        unconditional(self.load_conditional(input, None).await?)
+    }
+
+    fn category_key(&self) -> String {
+        self.context.partition.category_key("savings_plan_list")
+    }
+
+    fn content_key(&self, input: &PriceBulkSavingsPlan) -> Option<String> {
+        Some(input.tag())
+    }
+
+    async fn load_conditional(
+        &self,
+        input: &PriceBulkSavingsPlan,
+        cached_key: Option<&CacheKey>,
+    ) -> Result<ConditionalLoad<SavingsPlanListResponse>, PriceBulkError> {
+        let request_url = format!(
+            "{}/{}",
+            self.context.base_url,
+            input.path(self.context.partition)
+        );
+        let partial_path = self.partial_path(input, 1);
+        let loaded: ConditionalLoad<SavingsPlanListResponse> =
+            with_retry(&self.context.retry_policy, request_url.as_str(), || {
+                download_json_conditional(
+                    &self.context,
+                    request_url.as_str(),
+                    self.content_key(input),
+                    cached_key,
+                    &partial_path,
+                )
+            })
+            .await?;
+        let (mut result, cache_key) = match loaded {
+            ConditionalLoad::Modified { result, cache_key } => (result, cache_key),
+            ConditionalLoad::NotModified => return Ok(ConditionalLoad::NotModified),
+        };
+
+        // Split offers link to the next file, which is merged into the first. Only the first
+        // file's ETag is used for the cache key.
+        let mut visited = HashSet::from([request_url]);
+        while let Some(next_file_url) = result.next_file_url.take() {
+            let next_url = self.resolve_url(&next_file_url);
+            if !visited.insert(next_url.clone()) {
+                return Err(PriceBulkError::ContinuationLoop(next_url));
+            }
+            debug!("Following savings plan continuation: {}", next_url);
+            let partial_path = self.partial_path(input, visited.len());
+            let next: SavingsPlanListResponse = unconditional(
+                with_retry(&self.context.retry_policy, next_url.as_str(), || {
+                    download_json_conditional(
+                        &self.context,
+                        next_url.as_str(),
+                        None,
+                        None,
+                        &partial_path,
+                    )
+                })
+                .await?,
+            )?;
+            result.products.extend(next.products);
+            result.terms.savings_plan.extend(next.terms.savings_plan);
+            result.next_file_url = next.next_file_url;
+        }
+        result.check_known(self.context.strict)?;
+        Ok(ConditionalLoad::Modified { result, cache_key })
+    }
+}
+
+impl SavingsPlanListClient {
+    pub fn new_cacheable_arc(
+        context: Arc<PriceBulkContext>,
+    ) -> CacheableArc<PriceBulkSavingsPlan, SavingsPlanListResponse, PriceBulkError> {
+        Arc::new(Box::new(Self { context }))
+    }
+
+    /// Partial downloads are kept next to the cache entries of the same category. Continuation
+    /// files of split offers are numbered from 2.
+    fn partial_path(&self, input: &PriceBulkSavingsPlan, file_number: usize) -> PathBuf {
+        let filename = match file_number {
+            1 => format!("{}.partial", input.tag()),
+            n => format!("{}.{}.partial", input.tag(), n),
+        };
+        self.context
+            .download_directory
+            .join(self.category_key())
+            .join(filename)
+    }
+
+    /// Continuation URLs are either absolute or relative to the base URL.
+    fn resolve_url(&self, url: &str) -> String {
+        if url.starts_with("http://") || url.starts_with("https://") {
+            url.to_string()
+        } else {
+            format!("{}/{}", self.context.base_url, url.trim_start_matches('/'))
+        }
+    }
+}
+
+pub(crate) fn unconditional<T>(loaded: ConditionalLoad<T>) -> PriceBulkResult<T> {
+    match loaded {
+        ConditionalLoad::Modified { result, .. } => Ok(result),
+        ConditionalLoad::NotModified => Err(PriceBulkError::UnexpectedNotModified),
+    }
+}
+
+pub(crate) async fn load_etag(
+    context: &PriceBulkContext,
+    url: &str,
+) -> PriceBulkResult<Option<String>> {
+    if let Some(content_hash) = context.head_memo.get(url) {
+        debug!("Reusing content hash of {}", url);
+        return Ok(content_hash);
+    }
+    let response = with_retry(&context.retry_policy, url, || async {
+        let mut permit = context.acquire().await;
+        metrics::global().record_request();
+        context
+            .client
+            .head(url)
+            .send()
+            .await
+            .map_err(PriceBulkError::from)
+            .and_then(|response| {
+                response
+                    .error_for_status()
+                    .map_err(PriceBulkError::HttpResponseFailure)
+            })
+            .inspect_err(|e| {
+                permit.observe(e);
+                context.log_failure("HEAD", url, e)
+            })
+    })
+    .await?;
+    let content_hash = response_content_hash(&response);
+    context.log_request("HEAD", url, RequestOutcome::Ok, content_hash.as_deref(), 0);
+    context.head_memo.insert(url, content_hash.clone());
+    Ok(content_hash)
+}
+
+/// Content hash of a response: its ETag, or its normalized `Last-Modified` date if the ETag is
+/// missing. `None` if neither is usable.
+pub(crate) fn response_content_hash(response: &reqwest::Response) -> Option<String> {
+    let header = |name: HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+    if let Some(etag) = header(ETAG) {
+        return Some(etag.trim_matches('"').to_string());
+    }
+    let last_modified = DateTime::parse_from_rfc2822(header(LAST_MODIFIED)?).ok()?;
+    Some(format!(
+        "{}{}",
+        LAST_MODIFIED_HASH_PREFIX,
+        last_modified
+            .with_timezone(&Utc)
+            .format(LAST_MODIFIED_HASH_FORMAT)
+    ))
+}
+
+/// Whether `url` was seen within the memo TTL with the content hash of `cached_key`, in which
+/// case a conditional GET is skipped as not modified.
+fn memoized_unchanged(
+    context: &PriceBulkContext,
+    url: &str,
+    cached_key: Option<&CacheKey>,
+) -> bool {
+    let content_hash = cached_key.and_then(|cache_key| cache_key.content_hash.clone());
+    content_hash.is_some() && context.head_memo.get(url) == Some(content_hash)
+}
+
+/// Validator header for a content hash from `response_content_hash`: `If-None-Match` for ETags
+/// and `If-Modified-Since` for `Last-Modified` dates. The value doubles as `If-Range` value.
+pub(crate) fn validator_header(content_hash: &str) -> (HeaderName, String) {
+    let last_modified = content_hash
+        .strip_prefix(LAST_MODIFIED_HASH_PREFIX)
+        .and_then(|date| NaiveDateTime::parse_from_str(date, LAST_MODIFIED_HASH_FORMAT).ok());
+    match last_modified {
+        Some(date) => (
+            IF_MODIFIED_SINCE,
+            date.and_utc().format(HTTP_DATE_FORMAT).to_string(),
+        ),
+        None => (IF_NONE_MATCH, format!("\"{}\"", content_hash)),
+    }
+}
+
+/// Sends a GET conditional on `content_hash`, returning `None` on 304 Not Modified.
+async fn send_conditional_request(
+    client: &reqwest::Client,
+    url: &str,
+    content_hash: Option<&str>,
+) -> PriceBulkResult<Option<reqwest::Response>> {
+    let mut request = client.get(url);
+    if let Some(content_hash) = content_hash {
+        let (name, value) = validator_header(content_hash);
+        debug!("Requesting URL: {} ({}: {})", url, name, value);
+        request = request.header(name, value);
+    } else {
+        debug!("Requesting URL: {}", url);
+    }
+    metrics::global().record_request();
+    let response = request.send().await?;
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        debug!("Not modified: {}", url);
+        return Ok(None);
+    }
+    response
+        .error_for_status()
+        .map(Some)
+        .map_err(PriceBulkError::HttpResponseFailure)
+}
+
+pub(crate) async fn load_json_conditional<T: DeserializeOwned + Send + 'static>(
+    context: &PriceBulkContext,
+    url: &str,
+    content_key: Option<String>,
+    cached_key: Option<&CacheKey>,
+) -> PriceBulkResult<ConditionalLoad<T>> {
+    if memoized_unchanged(context, url, cached_key) {
+        debug!("Reusing content hash of {}", url);
+        return Ok(ConditionalLoad::NotModified);
+    }
+    let mut permit = context.acquire().await;
+    let etag = cached_key.and_then(|cache_key| cache_key.content_hash.as_deref());
+    let response = match send_conditional_request(&context.client, url, etag)
+        .await
+        .inspect_err(|e| {
+            permit.observe(e);
+            context.log_failure("GET", url, e)
+        })? {
+        Some(response) => response,
+        None => {
+            context.log_request("GET", url, RequestOutcome::NotModified, etag, 0);
+            return Ok(ConditionalLoad::NotModified);
+        }
+    };
+    let content_hash = response_content_hash(&response);
+    context.head_memo.insert(url, content_hash.clone());
+    let (result, bytes) = read_json(response)
+        .await
+        .inspect_err(|e| context.log_failure("GET", url, e))?;
+    context.log_request(
+        "GET",
+        url,
+        RequestOutcome::Ok,
+        content_hash.as_deref(),
+        bytes,
+    );
+    Ok(ConditionalLoad::Modified {
+        result,
+        cache_key: CacheKey {
+            content_key,
+            content_hash,
+        },
+    })
+}
+
+/// Downloads to disk so interrupted downloads can be resumed, then parses the completed file.
+async fn download_json_conditional<T: DeserializeOwned>(
+    context: &PriceBulkContext,
+    url: &str,
+    content_key: Option<String>,
+    cached_key: Option<&CacheKey>,
+    partial_path: &Path,
+) -> PriceBulkResult<ConditionalLoad<T>> {
+    if memoized_unchanged(context, url, cached_key) {
+        debug!("Reusing content hash of {}", url);
+        return Ok(ConditionalLoad::NotModified);
+    }
+    let etag = cached_key.and_then(|cache_key| cache_key.content_hash.as_deref());
+    let download = {
+        let mut permit = context.acquire().await;
+        match download_resumable(&context.download_client, url, etag, partial_path)
+            .await
+            .inspect_err(|e| {
+                permit.observe(e);
+                context.log_failure("GET", url, e)
+            })? {
+            Some(download) => download,
+            None => {
+                context.log_request("GET", url, RequestOutcome::NotModified, etag, 0);
+                return Ok(ConditionalLoad::NotModified);
+            }
+        }
+    };
+    context.head_memo.insert(url, download.content_hash.clone());
+    context.log_request(
+        "GET",
+        url,
+        RequestOutcome::Ok,
+        download.content_hash.as_deref(),
+        std::fs::metadata(&download.path).map_or(0, |metadata| metadata.len()),
+    );
+    // A file that fails to parse is corrupt either way, so don't resume from it
+    let result = read_json_file(&download.path);
+    remove_partial(&download.path).await;
+    Ok(ConditionalLoad::Modified {
+        result: result?,
+        cache_key: CacheKey {
+            content_key,
+            content_hash: download.content_hash,
+        },
+    })
+}
+
+fn read_json_file<T: DeserializeOwned>(path: &Path) -> PriceBulkResult<T> {
+    let mut file = File::open(path).map_err(PriceBulkError::IO)?;
+    let mut magic = [0u8; 2];
+    let is_gzip = file.read_exact(&mut magic).is_ok() && magic == GZIP_MAGIC;
+    file.rewind().map_err(PriceBulkError::IO)?;
+    let reader = BufReader::new(file);
+    if is_gzip {
+        return Ok(serde_json::from_reader(GzDecoder::new(reader))?);
+    }
+    Ok(serde_json::from_reader(reader)?)
+}
+
+/// Reads a JSON body, returning it with the body length. The body is parsed on a blocking thread
+/// while it is still being received, so it is never buffered whole. Responses with
+/// `Content-Encoding` are decoded by reqwest as they stream in, but some objects are stored
+/// gzipped without the header, so gzip bodies are also detected by their magic bytes.
+async fn read_json<T: DeserializeOwned + Send + 'static>(
+    mut response: reqwest::Response,
+) -> PriceBulkResult<(T, u64)> {
+    let (sender, receiver) = tokio::sync::mpsc::channel(BODY_CHUNKS_BUFFERED);
+    let parse = tokio::task::spawn_blocking(move || -> PriceBulkResult<T> {
+        let mut reader = BufReader::new(ChunkReader {
+            chunks: receiver,
+            current: Cursor::new(Vec::new()),
+        });
+        let is_gzip = reader
+            .fill_buf()
+            .map_err(PriceBulkError::IO)?
+            .starts_with(&GZIP_MAGIC);
+        if is_gzip {
+            debug!("Decompressing gzip body without Content-Encoding");
+            return Ok(serde_json::from_reader(GzDecoder::new(reader))?);
+        }
+        Ok(serde_json::from_reader(reader)?)
+    });
+    let received = async move {
+        let mut bytes = 0;
+        while let Some(chunk) = response.chunk().await? {
+            bytes += chunk.len() as u64;
+            metrics::global().record_bytes_downloaded(chunk.len() as u64);
+            if sender.send(chunk.to_vec()).await.is_err() {
+                // The parser failed and stopped reading
+                break;
+            }
+        }
+        Ok::<_, PriceBulkError>(bytes)
+    }
+    .await;
+    let parsed = parse
+        .await
+        .map_err(|e| PriceBulkError::IO(std::io::Error::other(e)))?;
+    // A body cut short also fails to parse, but the transfer error says why
+    let bytes = received?;
+    Ok((parsed?, bytes))
+}
+
+/// Body chunks read synchronously by a parser on a blocking thread.
+struct ChunkReader {
+    chunks: tokio::sync::mpsc::Receiver<Vec<u8>>,
+    current: Cursor<Vec<u8>>,
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let read = self.current.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            match self.chunks.blocking_recv() {
+                Some(chunk) => self.current = Cursor::new(chunk),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+/// Runs `operation` until it succeeds, fails with an error `policy` doesn't retry on, or runs out
+/// of attempts.
+pub(crate) async fn with_retry<T, F, Fut>(
+    policy: &RetryPolicy,
+    url: &str,
+    mut operation: F,
+) -> PriceBulkResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = PriceBulkResult<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match operation().await {
+            Ok(result) => return Ok(result),
+            Err(e) if policy.should_retry(attempt, e.retry_class()) => {
+                let backoff = policy.backoff(attempt);
+                warn!(
+                    "Request to {} failed (attempt {}/{}), retrying in {:?}: {}",
+                    url, attempt, policy.max_attempts, backoff, e
+                );
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            Err(e) if attempt > 1 => {
+                return Err(PriceBulkError::Retried {
+                    attempts: attempt,
+                    source: Box::new(e),
+                })
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+pub type PriceBulkResult<T> = Result<T, PriceBulkError>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum PriceBulkError {
+    #[error("HTTP client failure: {0}")]
+    HttpFailure(#[from] reqwest::Error),
+    #[error("HTTP response error: {0}")]
+    HttpResponseFailure(reqwest::Error),
+    #[error("Response deserialization failed: {0}")]
+    Deserialize(#[from] serde_json::Error),
+    #[error("Download IO failed: {0}")]
+    IO(std::io::Error),
+    #[error("Server reported not modified for an unconditional request")]
+    UnexpectedNotModified,
+    #[error("Savings plan continuation files link back to {0}")]
+    ContinuationLoop(String),
+    #[error("Strict parsing failed: {0}")]
+    UnknownValues(#[from] UnknownValuesError),
+    #[error("Request failed after {attempts} attempts: {source}")]
+    Retried {
+        attempts: u32,
+        source: Box<PriceBulkError>,
+    },
+}
+
+impl PriceBulkError {
+    /// Transient failure class of this error, if it is worth retrying.
+    pub fn retry_class(&self) -> Option<RetryClass> {
+        match self {
+            PriceBulkError::HttpFailure(e) | PriceBulkError::HttpResponseFailure(e) => {
+                classify_reqwest_error(e)
+            }
+            PriceBulkError::Deserialize(_)
+            | PriceBulkError::IO(_)
+            | PriceBulkError::UnexpectedNotModified
+            | PriceBulkError::ContinuationLoop(_)
+            | PriceBulkError::UnknownValues(_)
+            | PriceBulkError::Retried { .. } => None,
+        }
+    }
+}
+
+fn classify_reqwest_error(e: &reqwest::Error) -> Option<RetryClass> {
+    if e.is_timeout() {
+        return Some(RetryClass::Timeout);
+    }
+    if let Some(status) = e.status() {
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Some(RetryClass::TooManyRequests);
+        }
+        if status.is_server_error() {
+            return Some(RetryClass::ServerError);
+        }
+        return None;
+    }
+    if e.is_connect() || e.is_body() || e.is_request() {
+        return Some(RetryClass::Connect);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{send_conditional_request, with_retry, Partition, PriceBulkError};
+    use crate::api::aws::price_bulk_builder::PriceBulkClientBuilder;
+    use crate::api::aws::price_bulk_types::{PriceBulkOffer, PriceBulkSavingsPlan};
+    use crate::cache::{ConditionalLoad, FileBackedCacheableBuilder, HashSource};
+    use crate::util::testing::serve;
+    use crate::util::RetryPolicy;
+    use axum::extract::State;
+    use axum::http::{header, HeaderMap, StatusCode};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::get;
+    use axum::Router;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const SERVICE_INDEX_BODY: &str =
+        r#"{"formatVersion":"v1.0","publicationDate":"2024-03-12T15:37:24Z","offers":{}}"#;
+
+    async fn service_index(headers: HeaderMap) -> Response {
+        if headers
+            .get(header::IF_NONE_MATCH)
+            .is_some_and(|etag| etag == "\"v1\"")
+        {
+            return StatusCode::NOT_MODIFIED.into_response();
+        }
+        ([(header::ETAG, "\"v1\"")], SERVICE_INDEX_BODY).into_response()
+    }
+
+    #[tokio::test]
+    async fn test_conditional_load() {
+        let base_url =
+            serve(Router::new().route("/offers/v1.0/aws/index.json", get(service_index))).await;
+        let client = PriceBulkClientBuilder::new()
+            .base_url(base_url)
+            .build()
+            .unwrap()
+            .service_index();
+
+        let cache_key = match client.load_conditional(&(), None).await.unwrap() {
+            ConditionalLoad::Modified { cache_key, .. } => cache_key,
+            ConditionalLoad::NotModified => panic!("expected a full response"),
+        };
+        assert_eq!(cache_key.content_hash, Some("v1".to_string()));
+
+        let reloaded = client
+            .load_conditional(&(), Some(&cache_key))
+            .await
+            .unwrap();
+        assert!(matches!(reloaded, ConditionalLoad::NotModified));
+    }
+
+    async fn flaky(State(calls): State<Arc<AtomicU32>>) -> Response {
+        if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+            return StatusCode::SERVICE_UNAVAILABLE.into_response();
+        }
+        "ok".into_response()
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_server_error() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let base_url = serve(
+            Router::new()
+                .route("/flaky", get(flaky))
+                .with_state(calls.clone()),
+        )
+        .await;
+        let url = format!("{}/flaky", base_url);
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..RetryPolicy::default()
+        };
+
+        let client = reqwest::Client::new();
+        let body = with_retry(&policy, url.as_str(), || async {
+            let response = send_conditional_request(&client, url.as_str(), None).await?;
+            Ok(response.unwrap().text().await?)
+        })
+        .await
+        .unwrap();
+        assert_eq!(body, "ok");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        calls.store(0, Ordering::SeqCst);
+        let result = with_retry(&RetryPolicy::none(), url.as_str(), || async {
+            send_conditional_request(&client, url.as_str(), None).await
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(PriceBulkError::HttpResponseFailure(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retry_head() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let base_url = serve(
+            Router::new()
+                .route("/offers/v1.0/aws/index.json", get(flaky))
+                .with_state(calls.clone()),
+        )
+        .await;
+        let client = PriceBulkClientBuilder::new()
+            .base_url(base_url)
+            .retry_policy(RetryPolicy {
+                initial_backoff: Duration::from_millis(1),
+                ..RetryPolicy::default()
+            })
+            .build()
+            .unwrap()
+            .service_index();
+
+        client.get_cache_key(&()).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    fn gzip(body: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(body.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_gzip_bodies() {
+        let router = Router::new()
+            .route(
+                "/encoded/offers/v1.0/aws/index.json",
+                get(|| async {
+                    (
+                        [(header::CONTENT_ENCODING, "gzip")],
+                        gzip(SERVICE_INDEX_BODY),
+                    )
+                }),
+            )
+            .route(
+                "/raw/offers/v1.0/aws/index.json",
+                get(|| async { gzip(SERVICE_INDEX_BODY) }),
+            );
+        let base_url = serve(router).await;
+
+        for prefix in ["encoded", "raw"] {
+            let client = PriceBulkClientBuilder::new()
+                .base_url(format!("{}/{}", base_url, prefix))
+                .build()
+                .unwrap()
+                .service_index();
+            let response = client.load(&()).await.unwrap();
+            assert_eq!(response.format_version, "v1.0");
+        }
+    }
+
+    const OFFER_PATH: &str = "/offers/v1.0/aws/AmazonEC2/20240312153724/us-east-1/index.json";
+
+    const OFFER_BODY: &str = r#"{"formatVersion": "v1.0",
+        "publicationDate": "2024-03-12T15:37:24Z", "version": "20240312153724",
+        "products": {
+            "SKU1": {"sku": "SKU1", "productFamily": "Compute Instance", "attributes": {
+                "instanceType": "m5.large", "operatingSystem": "Linux"}}},
+        "terms": {"OnDemand": {}, "Reserved": {}}}"#;
+
+    /// Serves the offer only to clients accepting gzip, like a compressing CDN would.
+    async fn gzip_only(headers: HeaderMap) -> Response {
+        let accepts_gzip = headers
+            .get(header::ACCEPT_ENCODING)
+            .and_then(|encoding| encoding.to_str().ok())
+            .is_some_and(|encoding| encoding.contains("gzip"));
+        if !accepts_gzip {
+            return StatusCode::NOT_ACCEPTABLE.into_response();
+        }
+        (
+            [(header::ETAG, "\"v1\""), (header::CONTENT_ENCODING, "gzip")],
+            gzip(OFFER_BODY),
+        )
+            .into_response()
+    }
+
+    #[tokio::test]
+    async fn test_offer_download_negotiates_gzip() {
+        let base_url = serve(Router::new().route(OFFER_PATH, get(gzip_only))).await;
+        let download_directory =
+            std::env::temp_dir().join(format!("pekora-offer-gzip-{}", std::process::id()));
+        let client = PriceBulkClientBuilder::new()
+            .base_url(base_url)
+            .download_directory(download_directory.to_string_lossy())
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap()
+            .pricing_list();
+
+        let response = client
+            .load(&PriceBulkOffer {
+                service_code: "AmazonEC2".to_string(),
+                offer_version: "20240312153724".to_string(),
+                region: "us-east-1".to_string(),
+                filename: "index.json".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.products["SKU1"].sku, "SKU1");
+        std::fs::remove_dir_all(&download_directory).unwrap();
+    }
+
+    const SAVINGS_PLAN_PATH: &str =
+        "/savingsPlan/v1.0/aws/AWSComputeSavingsPlan/20240312234047/us-east-1";
+
+    fn savings_plan_file(sku: &str, next_file_url: Option<&str>) -> String {
+        let next_file_url = next_file_url
+            .map(|url| format!(r#","nextFileUrl":"{}""#, url))
+            .unwrap_or_default();
+        format!(
+            r#"{{"formatVersion":"v1.0","publicationDate":"2024-03-12T23:40:47Z","version":"20240312234047",
+            "products":[{{"sku":"{sku}","productFamily":"ComputeSavingsPlans","serviceCode":"ComputeSavingsPlans",
+            "usageType":"ComputeSP:1yrNoUpfront","operation":"",
+            "attributes":{{"purchaseOption":"No Upfront","productFamily":"ComputeSavingsPlans","regionCode":"us-east-1",
+            "serviceCode":"ComputeSavingsPlans","granularity":"hourly","locationType":"AWS Region",
+            "purchaseTerm":"1yr","location":"Any","usageType":"ComputeSP:1yrNoUpfront"}}}}],
+            "terms":{{"savingsPlan":[{{"sku":"{sku}","description":"1 year No Upfront","effectiveDate":"2024-03-01T00:00:00Z",
+            "leaseContractLength":{{"duration":1,"unit":"year"}},"rates":[]}}]}}{next_file_url}}}"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_savings_plan_continuation_files() {
+        let router = Router::new()
+            .route(
+                &format!("{}/index.json", SAVINGS_PLAN_PATH),
+                get(|| async {
+                    savings_plan_file("SP1", Some(&format!("{}/index.2.json", SAVINGS_PLAN_PATH)))
+                }),
+            )
+            .route(
+                &format!("{}/index.2.json", SAVINGS_PLAN_PATH),
+                get(|| async { savings_plan_file("SP2", None) }),
+            );
+        let base_url = serve(router).await;
+        let client = PriceBulkClientBuilder::new()
+            .base_url(base_url)
+            .download_directory("test_cache/savings_plan_continuation")
+            .build()
+            .unwrap()
+            .savings_plan_list();
+
+        let response = client
+            .load(&PriceBulkSavingsPlan {
+                service_code: "AWSComputeSavingsPlan".to_string(),
+                offer_version: "20240312234047".to_string(),
+                region: "us-east-1".to_string(),
+                filename: "index.json".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.products.len(), 2);
+        assert_eq!(response.terms.savings_plan.len(), 2);
+        assert_eq!(response.terms.savings_plan[1].sku, "SP2");
+        assert!(response.next_file_url.is_none());
+    }
+
+    async fn last_modified_only(headers: HeaderMap) -> Response {
+        if headers
+            .get(header::IF_MODIFIED_SINCE)
+            .is_some_and(|date| date == "Tue, 12 Mar 2024 15:37:24 GMT")
+        {
+            return StatusCode::NOT_MODIFIED.into_response();
+        }
+        (
+            [(header::LAST_MODIFIED, "Tue, 12 Mar 2024 15:37:24 GMT")],
+            SERVICE_INDEX_BODY,
+        )
+            .into_response()
+    }
+
+    #[tokio::test]
+    async fn test_last_modified_fallback() {
+        let base_url =
+            serve(Router::new().route("/offers/v1.0/aws/index.json", get(last_modified_only)))
+                .await;
+        let client = PriceBulkClientBuilder::new()
+            .base_url(base_url)
+            .build()
+            .unwrap()
+            .service_index();
+
+        let cache_key = match client.load_conditional(&(), None).await.unwrap() {
+            ConditionalLoad::Modified { cache_key, .. } => cache_key,
+            ConditionalLoad::NotModified => panic!("expected a full response"),
+        };
+        assert_eq!(
+            cache_key.content_hash.as_deref(),
+            Some("lm-20240312T153724Z")
+        );
+        assert_eq!(cache_key.hash_source(), Some(HashSource::LastModified));
+
+        let reloaded = client
+            .load_conditional(&(), Some(&cache_key))
+            .await
+            .unwrap();
+        assert!(matches!(reloaded, ConditionalLoad::NotModified));
+    }
+
+    async fn unavailable_once(State(calls): State<Arc<AtomicU32>>) -> Response {
+        if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            return StatusCode::SERVICE_UNAVAILABLE.into_response();
+        }
+        ([(header::ETAG, "\"v1\"")], SERVICE_INDEX_BODY).into_response()
+    }
+
+    #[tokio::test]
+    async fn test_head_failure_not_memoized() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let base_url = serve(
+            Router::new()
+                .route("/offers/v1.0/aws/index.json", get(unavailable_once))
+                .with_state(calls.clone()),
+        )
+        .await;
+        let client = PriceBulkClientBuilder::new()
+            .base_url(base_url)
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap()
+            .service_index();
+
+        assert!(matches!(
+            client.get_cache_key(&()).await,
+            Err(PriceBulkError::HttpResponseFailure(_))
+        ));
+        // Only the successful HEAD is memoized
+        for _ in 0..2 {
+            let cache_key = client.get_cache_key(&()).await.unwrap();
+            assert_eq!(cache_key.content_hash.as_deref(), Some("v1"));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_no_validator_headers() {
+        let base_url = serve(Router::new().route(
+            "/offers/v1.0/aws/index.json",
+            get(|| async { SERVICE_INDEX_BODY }),
+        ))
+        .await;
+        let client = PriceBulkClientBuilder::new()
+            .base_url(base_url)
+            .build()
+            .unwrap()
+            .service_index();
+        let cacheable = FileBackedCacheableBuilder::new(
+            Some("test_cache/no_validator_headers".to_string()),
+            None,
+        )
+        .build(client);
+
+        // The service index has no content key, so without validators it can't be cached
+        for _ in 0..2 {
+            let loaded = cacheable.load(&()).await.unwrap();
+            assert_eq!(loaded.result.format_version, "v1.0");
+            assert_eq!(loaded.cache_key.content_hash, None);
+            assert!(!loaded.cache_hit);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_china_partition() {
+        let router = Router::new()
+            .route(
+                "/offers/v1.0/cn/index.json",
+                get(|| async { ([(header::ETAG, "\"v1\"")], SERVICE_INDEX_BODY) }),
+            )
+            .route(
+                "/offers/v1.0/cn/AmazonEC2/current/region_index.json",
+                get(|| async {
+                    r#"{"formatVersion":"v1.0","publicationDate":"2024-03-12T15:37:24Z","regions":{
+                    "cn-north-1":{"regionCode":"cn-north-1",
+                    "currentVersionUrl":"/offers/v1.0/cn/AmazonEC2/20240312153724/cn-north-1/index.json"}}}"#
+                }),
+            );
+        let base_url = serve(router).await;
+        let clients = PriceBulkClientBuilder::new()
+            .partition(Partition::AwsCn)
+            .base_url(base_url)
+            .build()
+            .unwrap();
+
+        clients.service_index().load(&()).await.unwrap();
+        let region_index = clients
+            .region_index()
+            .load(&"AmazonEC2".to_string())
+            .await
+            .unwrap();
+        let offer = &region_index.regions["cn-north-1"].current_version_url;
+        assert_eq!(offer.region, "cn-north-1");
+        assert_eq!(
+            offer.path(Partition::AwsCn),
+            "offers/v1.0/cn/AmazonEC2/20240312153724/cn-north-1/index.json"
+        );
+        assert!(Partition::AwsCn.contains_region(&offer.region));
+        assert!(!Partition::Aws.contains_region(&offer.region));
+    }
+}
@@ -0,0 +1,515 @@
+use crate::api::aws::price_bulk::{
+    Partition, PriceBulkError, PriceBulkResult, PricingListClient, RegionIndexClient,
+    SavingsPlanListClient, ServiceIndexClient,
+};
+use crate::api::aws::price_bulk_types::*;
+use crate::api::aws::spot_advisor::{
+    SpotAdvisorClient, SpotAdvisorResponse, DEFAULT_SPOT_ADVISOR_URL,
+};
+use crate::cache::{CacheableArc, DEFAULT_CACHE_DIRECTORY};
+use crate::status::{RequestLog, RequestLogEntry, RequestOutcome};
+use crate::util::{AdaptiveLimiter, AdaptivePermit, RetryClass, RetryPolicy};
+use chrono::Utc;
+use log::warn;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const DEFAULT_USER_AGENT: &str = concat!("pekora-rs/", env!("CARGO_PKG_VERSION"));
+pub const DEFAULT_HEAD_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+/// Upper bound of adaptive concurrency if `max_concurrent_requests` is not set.
+pub const DEFAULT_MAX_ADAPTIVE_REQUESTS: usize = 16;
+
+/// HTTP options shared by all price bulk clients.
+#[derive(Debug, Clone, Default)]
+pub struct PriceBulkClientBuilder {
+    partition: Option<Partition>,
+    base_url: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<String>,
+    user_agent: Option<String>,
+    max_concurrent_requests: Option<usize>,
+    min_concurrent_requests: Option<usize>,
+    retry_policy: Option<RetryPolicy>,
+    download_directory: Option<String>,
+    head_cache_ttl: Option<Duration>,
+    spot_advisor_url: Option<String>,
+    log_requests: bool,
+    strict: bool,
+}
+
+impl PriceBulkClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Price list to query. Also picks the default base URL, `aws` unless set.
+    pub fn partition(mut self, partition: Partition) -> Self {
+        self.partition = Some(partition);
+        self
+    }
+
+    /// Overrides the endpoint of the partition, e.g. for a mirror of the price list.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Timeout of a whole request, including reading the body.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Proxy URL used for all requests, e.g. `http://proxy.internal:3128`.
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Upper bound of requests in flight across all clients built from this builder.
+    pub fn max_concurrent_requests(mut self, max_concurrent_requests: usize) -> Self {
+        self.max_concurrent_requests = Some(max_concurrent_requests);
+        self
+    }
+
+    /// Adapts the number of requests in flight between this and `max_concurrent_requests`,
+    /// halving it when upstream throttles and ramping it up again while requests succeed.
+    pub fn min_concurrent_requests(mut self, min_concurrent_requests: usize) -> Self {
+        self.min_concurrent_requests = Some(min_concurrent_requests);
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Directory partial downloads are kept in. Should match the cache directory.
+    pub fn download_directory(mut self, download_directory: impl Into<String>) -> Self {
+        self.download_directory = Some(download_directory.into());
+        self
+    }
+
+    /// How long content hashes from HEAD requests are reused before asking upstream again.
+    /// `Duration::ZERO` disables the memo.
+    pub fn head_cache_ttl(mut self, head_cache_ttl: Duration) -> Self {
+        self.head_cache_ttl = Some(head_cache_ttl);
+        self
+    }
+
+    /// Overrides the URL of the Spot Advisor feed.
+    pub fn spot_advisor_url(mut self, spot_advisor_url: impl Into<String>) -> Self {
+        self.spot_advisor_url = Some(spot_advisor_url.into());
+        self
+    }
+
+    /// Appends every upstream request to `.pekora/requests.jsonl` in the download directory.
+    pub fn log_requests(mut self, log_requests: bool) -> Self {
+        self.log_requests = log_requests;
+        self
+    }
+
+    /// Fails loading pricing and savings plan files with enum values pekora doesn't know, instead
+    /// of keeping them as `Unknown` variants.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    fn client_builder(&self) -> PriceBulkResult<reqwest::ClientBuilder> {
+        let mut client_builder = reqwest::Client::builder().user_agent(
+            self.user_agent
+                .clone()
+                .unwrap_or(DEFAULT_USER_AGENT.to_string()),
+        );
+        if let Some(timeout) = self.timeout {
+            client_builder = client_builder.timeout(timeout);
+        }
+        if let Some(connect_timeout) = self.connect_timeout {
+            client_builder = client_builder.connect_timeout(connect_timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            client_builder = client_builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        Ok(client_builder)
+    }
+
+    pub fn build(self) -> PriceBulkResult<PriceBulkClients> {
+        let client = self.client_builder()?.build()?;
+        let download_client = self.client_builder()?.no_gzip().no_deflate().build()?;
+
+        let partition = self.partition.unwrap_or_default();
+        let download_directory = PathBuf::from(
+            self.download_directory
+                .unwrap_or(DEFAULT_CACHE_DIRECTORY.to_string()),
+        );
+        let context = PriceBulkContext {
+            client,
+            download_client,
+            base_url: self
+                .base_url
+                .unwrap_or(partition.default_base_url().to_string()),
+            partition,
+            spot_advisor_url: self
+                .spot_advisor_url
+                .unwrap_or(DEFAULT_SPOT_ADVISOR_URL.to_string()),
+            retry_policy: self.retry_policy.unwrap_or_default(),
+            request_log: self
+                .log_requests
+                .then(|| RequestLog::new(&download_directory)),
+            download_directory,
+            limiter: match (self.min_concurrent_requests, self.max_concurrent_requests) {
+                (Some(min), max) => Some(AdaptiveLimiter::new(
+                    min,
+                    max.unwrap_or(DEFAULT_MAX_ADAPTIVE_REQUESTS),
+                )),
+                (None, Some(max)) => Some(AdaptiveLimiter::fixed(max)),
+                (None, None) => None,
+            },
+            head_memo: HeadMemo::new(self.head_cache_ttl.unwrap_or(DEFAULT_HEAD_CACHE_TTL)),
+            strict: self.strict,
+        };
+        Ok(PriceBulkClients {
+            context: Arc::new(context),
+        })
+    }
+}
+
+/// Request slot handed out by `PriceBulkContext::acquire`.
+pub(crate) struct RequestPermit<'a>(Option<AdaptivePermit<'a>>);
+
+impl RequestPermit<'_> {
+    /// Lowers the concurrency limit if `error` is upstream throttling the request.
+    pub(crate) fn observe(&mut self, error: &PriceBulkError) {
+        if error.retry_class() == Some(RetryClass::TooManyRequests) {
+            self.throttled();
+        }
+    }
+
+    pub(crate) fn throttled(&mut self) {
+        if let Some(permit) = &mut self.0 {
+            permit.throttled();
+        }
+    }
+}
+
+/// State shared by the price bulk clients.
+#[derive(Debug)]
+pub struct PriceBulkContext {
+    pub(crate) client: reqwest::Client,
+    /// Client leaving response bodies encoded, for downloads resumed with ranges of the encoded
+    /// representation
+    pub(crate) download_client: reqwest::Client,
+    pub(crate) base_url: String,
+    pub(crate) partition: Partition,
+    pub(crate) spot_advisor_url: String,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) download_directory: PathBuf,
+    request_log: Option<RequestLog>,
+    limiter: Option<AdaptiveLimiter>,
+    pub(crate) head_memo: HeadMemo,
+    pub(crate) strict: bool,
+}
+
+impl PriceBulkContext {
+    /// Waits for a request slot if concurrency is limited. The slot is held until the permit drops.
+    pub(crate) async fn acquire(&self) -> RequestPermit<'_> {
+        match &self.limiter {
+            Some(limiter) => RequestPermit(Some(limiter.acquire().await)),
+            None => RequestPermit(None),
+        }
+    }
+
+    /// Appends a request to the request log, if enabled.
+    pub(crate) fn log_request(
+        &self,
+        method: &str,
+        url: &str,
+        outcome: RequestOutcome,
+        content_hash: Option<&str>,
+        bytes: u64,
+    ) {
+        self.append_request_log(RequestLogEntry {
+            timestamp: Utc::now(),
+            method: method.to_string(),
+            url: url.to_string(),
+            outcome,
+            content_hash: content_hash.map(str::to_string),
+            bytes,
+            error: None,
+        });
+    }
+
+    pub(crate) fn log_failure(&self, method: &str, url: &str, error: &PriceBulkError) {
+        self.append_request_log(RequestLogEntry {
+            timestamp: Utc::now(),
+            method: method.to_string(),
+            url: url.to_string(),
+            outcome: RequestOutcome::Failed,
+            content_hash: None,
+            bytes: 0,
+            error: Some(error.to_string()),
+        });
+    }
+
+    fn append_request_log(&self, entry: RequestLogEntry) {
+        if let Some(request_log) = &self.request_log {
+            if let Err(e) = request_log.record(&entry) {
+                warn!("Failed to append to the request log: {}", e);
+            }
+        }
+    }
+}
+
+/// Recently seen content hashes per URL, so a burst of loads sends one HEAD per file.
+#[derive(Debug)]
+pub(crate) struct HeadMemo {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, Option<String>)>>,
+}
+
+impl HeadMemo {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Content hash of `url` if it was seen within the TTL. The outer `None` means unknown.
+    pub(crate) fn get(&self, url: &str) -> Option<Option<String>> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(url)
+            .filter(|(seen_at, _)| seen_at.elapsed() < self.ttl)
+            .map(|(_, content_hash)| content_hash.clone())
+    }
+
+    pub(crate) fn insert(&self, url: &str, content_hash: Option<String>) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(url.to_string(), (Instant::now(), content_hash));
+    }
+}
+
+/// Factory for the price bulk clients, built by `PriceBulkClientBuilder`.
+#[derive(Debug, Clone)]
+pub struct PriceBulkClients {
+    context: Arc<PriceBulkContext>,
+}
+
+impl PriceBulkClients {
+    pub fn base_url(&self) -> &str {
+        &self.context.base_url
+    }
+
+    pub fn partition(&self) -> Partition {
+        self.context.partition
+    }
+
+    pub fn service_index(&self) -> CacheableArc<(), ServiceListResponse, PriceBulkError> {
+        ServiceIndexClient::new_cacheable_arc(self.context.clone())
+    }
+
+    pub fn region_index(&self) -> CacheableArc<String, RegionIndexResponse, PriceBulkError> {
+        RegionIndexClient::new_cacheable_arc(self.context.clone())
+    }
+
+    pub fn pricing_list(
+        &self,
+    ) -> CacheableArc<PriceBulkOffer, PricingListResponse, PriceBulkError> {
+        PricingListClient::new_cacheable_arc(self.context.clone())
+    }
+
+    pub fn savings_plan_list(
+        &self,
+    ) -> CacheableArc<PriceBulkSavingsPlan, SavingsPlanListResponse, PriceBulkError> {
+        SavingsPlanListClient::new_cacheable_arc(self.context.clone())
+    }
+
+    pub fn spot_advisor(&self) -> CacheableArc<(), SpotAdvisorResponse, PriceBulkError> {
+        SpotAdvisorClient::new_cacheable_arc(self.context.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PriceBulkClientBuilder;
+    use crate::cache::ConditionalLoad;
+    use crate::status::{RequestLog, RequestOutcome};
+    use crate::util::testing::serve;
+    use axum::extract::State;
+    use axum::http::header;
+    use axum::response::IntoResponse;
+    use axum::routing::get;
+    use axum::Router;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const INDEX: &str =
+        r#"{"formatVersion":"v1.0","publicationDate":"2024-03-12T15:37:24Z","offers":{}}"#;
+
+    #[derive(Default)]
+    struct InFlight {
+        current: AtomicUsize,
+        max: AtomicUsize,
+    }
+
+    async fn slow_index(State(in_flight): State<Arc<InFlight>>) -> &'static str {
+        let current = in_flight.current.fetch_add(1, Ordering::SeqCst) + 1;
+        in_flight.max.fetch_max(current, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        in_flight.current.fetch_sub(1, Ordering::SeqCst);
+        INDEX
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrent_requests() {
+        let in_flight = Arc::new(InFlight::default());
+        let base_url = serve(
+            Router::new()
+                .route("/offers/v1.0/aws/index.json", get(slow_index))
+                .with_state(in_flight.clone()),
+        )
+        .await;
+        let client = PriceBulkClientBuilder::new()
+            .base_url(base_url)
+            .max_concurrent_requests(1)
+            .build()
+            .unwrap()
+            .service_index();
+
+        let results = tokio::join!(
+            client.load(&()),
+            client.load(&()),
+            client.load(&()),
+            client.load(&())
+        );
+        results.0.unwrap();
+        results.3.unwrap();
+        assert_eq!(in_flight.max.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_invalid_proxy() {
+        assert!(PriceBulkClientBuilder::new()
+            .proxy("not a url")
+            .build()
+            .is_err());
+    }
+
+    async fn counted_index(State(requests): State<Arc<AtomicUsize>>) -> impl IntoResponse {
+        requests.fetch_add(1, Ordering::SeqCst);
+        ([(header::ETAG, "\"v1\"")], INDEX)
+    }
+
+    #[tokio::test]
+    async fn test_head_cache() {
+        for (ttl, expected_requests) in [(None, 1), (Some(Duration::ZERO), 3)] {
+            let requests = Arc::new(AtomicUsize::new(0));
+            let base_url = serve(
+                Router::new()
+                    .route("/offers/v1.0/aws/index.json", get(counted_index))
+                    .with_state(requests.clone()),
+            )
+            .await;
+            let mut builder = PriceBulkClientBuilder::new().base_url(base_url);
+            if let Some(ttl) = ttl {
+                builder = builder.head_cache_ttl(ttl);
+            }
+            let client = builder.build().unwrap().service_index();
+            for _ in 0..3 {
+                let cache_key = client.get_cache_key(&()).await.unwrap();
+                assert_eq!(cache_key.content_hash, Some("v1".to_string()));
+            }
+            assert_eq!(requests.load(Ordering::SeqCst), expected_requests);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_head_cache_conditional_load() {
+        for (ttl, expected_requests) in [(None, 1), (Some(Duration::ZERO), 3)] {
+            let requests = Arc::new(AtomicUsize::new(0));
+            let base_url = serve(
+                Router::new()
+                    .route("/offers/v1.0/aws/index.json", get(counted_index))
+                    .with_state(requests.clone()),
+            )
+            .await;
+            let mut builder = PriceBulkClientBuilder::new().base_url(base_url);
+            if let Some(ttl) = ttl {
+                builder = builder.head_cache_ttl(ttl);
+            }
+            let client = builder.build().unwrap().service_index();
+            let cache_key = match client.load_conditional(&(), None).await.unwrap() {
+                ConditionalLoad::Modified { cache_key, .. } => cache_key,
+                ConditionalLoad::NotModified => panic!("expected a full response"),
+            };
+            // The server ignores validators, so only the memo can answer not modified
+            let reloaded = client
+                .load_conditional(&(), Some(&cache_key))
+                .await
+                .unwrap();
+            assert_eq!(
+                matches!(reloaded, ConditionalLoad::NotModified),
+                ttl.is_none()
+            );
+            let cache_key = client.get_cache_key(&()).await.unwrap();
+            assert_eq!(cache_key.content_hash, Some("v1".to_string()));
+            assert_eq!(requests.load(Ordering::SeqCst), expected_requests);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_log() {
+        let base_url = serve(Router::new().route(
+            "/offers/v1.0/aws/index.json",
+            get(|| async { ([(header::ETAG, "\"v1\"")], INDEX) }),
+        ))
+        .await;
+        let directory =
+            std::env::temp_dir().join(format!("pekora-requests-{}", std::process::id()));
+        let client = PriceBulkClientBuilder::new()
+            .base_url(base_url.clone())
+            .download_directory(directory.to_string_lossy())
+            .log_requests(true)
+            .build()
+            .unwrap()
+            .service_index();
+        client.get_cache_key(&()).await.unwrap();
+        client.load(&()).await.unwrap();
+
+        let entries = RequestLog::new(&directory)
+            .since(chrono::DateTime::UNIX_EPOCH)
+            .unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.method.as_str(), entry.bytes))
+                .collect::<Vec<_>>(),
+            vec![("HEAD", 0), ("GET", INDEX.len() as u64)]
+        );
+        assert!(entries.iter().all(|entry| {
+            entry.outcome == RequestOutcome::Ok
+                && entry.content_hash.as_deref() == Some("v1")
+                && entry.url == format!("{}/offers/v1.0/aws/index.json", base_url)
+        }));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
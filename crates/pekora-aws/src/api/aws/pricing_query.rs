@@ -0,0 +1,281 @@
+use crate::api::aws::price_bulk_types::PricingListResponseProduct;
+use crate::api::aws::types::{PriceOffering, RITermAttributes};
+use crate::api::aws::util::{AwsClientError, AwsClientResult};
+use crate::cache::{CacheKey, Cacheable, CacheableArc};
+use crate::metrics;
+use crate::transform::aws::on_demand::OnDemandRate;
+use crate::util::hash::{HashAlgorithm, StableHasher};
+use async_trait::async_trait;
+use aws_config::{BehaviorVersion, SdkConfig};
+use aws_sdk_pricing::error::BuildError;
+use aws_sdk_pricing::types::{Filter, FilterType};
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// The Price List Query API is only served from a few regions, independent of the prices queried.
+const PRICING_API_REGION: &str = "us-east-1";
+
+/// Products of one service matching all `filters`, optionally limited to a region.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProductQuery {
+    pub service_code: String,
+    pub region: Option<String>,
+    /// Attribute name to exact value, e.g. `instanceType` to `m5.large`
+    pub filters: BTreeMap<String, String>,
+}
+
+impl ProductQuery {
+    /// Readable, filename-safe key of the query. Unsafe characters are replaced, so a hash of
+    /// the exact query is appended to tell apart queries that read the same.
+    fn cache_key(&self, hash_algorithm: HashAlgorithm) -> String {
+        let mut hasher = StableHasher::new(hash_algorithm);
+        hasher
+            .field(&self.service_code)
+            .field(self.region.as_deref().unwrap_or_default());
+        let mut parts = vec![self.service_code.clone()];
+        parts.extend(self.region.clone());
+        for (name, value) in &self.filters {
+            hasher.field(name).field(value);
+            parts.push(format!("{}-{}", name, value));
+        }
+        parts.push(hasher.finish()[..16].to_string());
+        parts
+            .join("-")
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                    c
+                } else {
+                    '-'
+                }
+            })
+            .collect()
+    }
+}
+
+/// A single product with its terms, as returned by GetProducts.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceListItem {
+    pub product: PricingListResponseProduct<HashMap<String, String>>,
+    pub service_code: String,
+    pub terms: PriceListItemTerms,
+    pub version: String,
+    pub publication_date: DateTime<Utc>,
+}
+
+impl PriceListItem {
+    /// On-demand price dimensions of the product, in the shape of pivoted bulk file rates.
+    pub fn on_demand_rates(&self) -> Vec<OnDemandRate> {
+        let attributes = Arc::new(self.product.attributes.clone());
+        self.terms
+            .on_demand
+            .values()
+            .flat_map(|offering| {
+                offering
+                    .price_dimensions
+                    .values()
+                    .map(move |dimension| (offering, dimension))
+            })
+            .map(|(offering, dimension)| OnDemandRate {
+                sku: self.product.sku.clone(),
+                product_family: self.product.product_family.clone(),
+                attributes: attributes.clone(),
+                offer_term_code: offering.offer_term_code.clone(),
+                effective_date: offering.effective_date,
+                rate_code: dimension.rate_code.clone(),
+                description: dimension.description.clone(),
+                unit: dimension.unit.clone(),
+                price_per_unit: dimension.price_per_unit.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Terms of one product, keyed by offer term code.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PriceListItemTerms {
+    #[serde(rename = "OnDemand", default)]
+    pub on_demand: HashMap<String, PriceOffering<HashMap<String, String>>>,
+    #[serde(rename = "Reserved", default)]
+    pub reserved: HashMap<String, PriceOffering<RITermAttributes>>,
+}
+
+/// Client of the Price List Query API, for lookups too small to justify a bulk file.
+pub struct PricingQueryClient {
+    client: aws_sdk_pricing::Client,
+    hash_algorithm: HashAlgorithm,
+}
+
+impl PricingQueryClient {
+    pub async fn new(aws_sdk_config: Option<SdkConfig>) -> Self {
+        let config = match aws_sdk_config {
+            Some(config) => config,
+            None => aws_config::load_defaults(BehaviorVersion::latest()).await,
+        };
+        let mut builder = config.into_builder();
+        builder.set_region(Some(aws_config::Region::new(PRICING_API_REGION)));
+        Self {
+            client: aws_sdk_pricing::Client::new(&builder.build()),
+            hash_algorithm: HashAlgorithm::default(),
+        }
+    }
+
+    /// Algorithm of the hash in cache keys, e.g. `Pekora::hash_algorithm`.
+    pub fn with_hash_algorithm(mut self, hash_algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = hash_algorithm;
+        self
+    }
+
+    pub async fn new_cacheable_arc(
+        aws_sdk_config: Option<SdkConfig>,
+        hash_algorithm: HashAlgorithm,
+    ) -> CacheableArc<ProductQuery, Vec<PriceListItem>, AwsClientError> {
+        Arc::new(Box::new(
+            Self::new(aws_sdk_config)
+                .await
+                .with_hash_algorithm(hash_algorithm),
+        ))
+    }
+
+    /// Values of `attribute_name` across the products of `service_code`, e.g. every
+    /// `instanceType` of `AmazonEC2`.
+    pub async fn attribute_values(
+        &self,
+        service_code: &str,
+        attribute_name: &str,
+    ) -> AwsClientResult<Vec<String>> {
+        info!(
+            "PricingQueryClient: GetAttributeValues for {}/{}",
+            service_code, attribute_name
+        );
+        let mut stream = self
+            .client
+            .get_attribute_values()
+            .service_code(service_code)
+            .attribute_name(attribute_name)
+            .into_paginator()
+            .send();
+
+        let mut result = Vec::new();
+        while let Some(page) = stream.next().await {
+            metrics::global().record_request();
+            result.extend(
+                page?
+                    .attribute_values()
+                    .iter()
+                    .filter_map(|value| value.value().map(str::to_string)),
+            );
+        }
+        Ok(result)
+    }
+
+    async fn get_products(&self, query: &ProductQuery) -> AwsClientResult<Vec<PriceListItem>> {
+        info!("PricingQueryClient: GetProducts for {:?}", query);
+        let mut filters = Vec::with_capacity(query.filters.len() + 1);
+        if let Some(region) = &query.region {
+            filters.push(term_match("regionCode", region)?);
+        }
+        for (name, value) in &query.filters {
+            filters.push(term_match(name, value)?);
+        }
+        let mut stream = self
+            .client
+            .get_products()
+            .service_code(&query.service_code)
+            .set_filters(Some(filters))
+            .into_paginator()
+            .send();
+
+        let mut result = Vec::new();
+        while let Some(page) = stream.next().await {
+            metrics::global().record_request();
+            for item in page?.price_list() {
+                result.push(serde_json::from_str(item).map_err(AwsClientError::Deserialize)?);
+            }
+        }
+        Ok(result)
+    }
+}
+
+fn term_match(field: &str, value: &str) -> Result<Filter, BuildError> {
+    Filter::builder()
+        .r#type(FilterType::TermMatch)
+        .field(field)
+        .value(value)
+        .build()
+}
+
+/// Query results carry no validators, so cached results are reused until they expire.
+#[async_trait]
+impl Cacheable<ProductQuery, Vec<PriceListItem>, AwsClientError> for PricingQueryClient {
+    async fn get_cache_key(&self, input: &ProductQuery) -> Result<CacheKey, AwsClientError> {
+        Ok(CacheKey {
+            content_key: self.content_key(input),
+            content_hash: None,
+        })
+    }
+
+    async fn load(&self, input: &ProductQuery) -> Result<Vec<PriceListItem>, AwsClientError> {
+        self.get_products(input).await
+    }
+
+    fn category_key(&self) -> String {
+        "aws/query/products".to_string()
+    }
+
+    fn content_key(&self, input: &ProductQuery) -> Option<String> {
+        Some(input.cache_key(self.hash_algorithm))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PriceListItem, ProductQuery};
+    use crate::util::hash::HashAlgorithm;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_price_list_item() {
+        let item: PriceListItem = serde_json::from_str(
+            r#"{"product":{"productFamily":"Compute Instance","attributes":{"instanceType":"m5.large",
+            "regionCode":"us-east-1"},"sku":"SKU1"},"serviceCode":"AmazonEC2","terms":{"OnDemand":{
+            "SKU1.JRTCKXETXF":{"priceDimensions":{"SKU1.JRTCKXETXF.6YS6EN2CT7":{"unit":"Hrs",
+            "endRange":"Inf","description":"$0.096 per On Demand Linux m5.large Instance Hour",
+            "appliesTo":[],"rateCode":"SKU1.JRTCKXETXF.6YS6EN2CT7","beginRange":"0",
+            "pricePerUnit":{"USD":"0.0960000000"}}},"sku":"SKU1","effectiveDate":"2024-03-01T00:00:00Z",
+            "offerTermCode":"JRTCKXETXF","termAttributes":{}}}},"version":"20240312153724",
+            "publicationDate":"2024-03-12T15:37:24Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(item.product.attributes["instanceType"], "m5.large");
+        let dimension = item.terms.on_demand["SKU1.JRTCKXETXF"]
+            .price_dimensions
+            .values()
+            .next()
+            .unwrap();
+        assert_eq!(dimension.price_per_unit["USD"].raw(), "0.0960000000");
+        assert!(item.terms.reserved.is_empty());
+        let rates = item.on_demand_rates();
+        assert_eq!(rates.len(), 1);
+        assert_eq!(rates[0].sku, "SKU1");
+        assert_eq!(rates[0].unit, "Hrs");
+
+        let query = ProductQuery {
+            service_code: "AmazonEC2".to_string(),
+            region: Some("us-east-1".to_string()),
+            filters: BTreeMap::from([("operatingSystem".to_string(), "Red Hat/Linux".to_string())]),
+        };
+        let key = query.cache_key(HashAlgorithm::Xxh3);
+        assert!(key.starts_with("AmazonEC2-us-east-1-operatingSystem-Red-Hat-Linux-"));
+        let similar = ProductQuery {
+            filters: BTreeMap::from([("operatingSystem".to_string(), "Red Hat-Linux".to_string())]),
+            ..query.clone()
+        };
+        assert_ne!(similar.cache_key(HashAlgorithm::Xxh3), key);
+        assert_ne!(query.cache_key(HashAlgorithm::Sha256), key);
+    }
+}
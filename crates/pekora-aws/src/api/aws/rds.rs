@@ -0,0 +1,273 @@
+use crate::api::aws::accounts::MultiAccountClientSet;
+use crate::api::aws::auth::AwsAuthConfig;
+use crate::api::aws::fan_out::{fan_out, FanOutConfig, RegionResults};
+use crate::api::aws::util::{AwsClientError, AwsClientResult};
+use crate::metrics;
+use crate::util::ClientSet;
+use aws_config::SdkConfig;
+use log::info;
+use std::sync::Arc;
+
+pub use pekora_core::model::aws::rds::{
+    pricing_engine, AvailabilityZone, OrderableDbInstanceOption,
+    OrderableDbInstanceOptionsResponse, RecurringCharge, ReservedDbInstancesOffering,
+    ReservedDbInstancesOfferingsResponse,
+};
+
+fn build_client_set(config: SdkConfig) -> ClientSet<SdkConfig, aws_sdk_rds::Client> {
+    ClientSet::new(
+        config,
+        Box::new(|config, region| {
+            let mut builder = config.into_builder();
+            builder.set_region(aws_config::Region::new(region));
+            let new_config = builder.build();
+            aws_sdk_rds::Client::new(&new_config)
+        }),
+    )
+}
+
+pub struct RdsClient {
+    client_set: Arc<ClientSet<SdkConfig, aws_sdk_rds::Client>>,
+    regions: Vec<String>,
+    fan_out: FanOutConfig,
+}
+
+impl RdsClient {
+    /// Queries `regions`. RDS has no call listing the regions it is in, so they are given.
+    pub async fn new(aws_sdk_config: Option<SdkConfig>, regions: Vec<String>) -> Self {
+        Self::with_auth(aws_sdk_config, &AwsAuthConfig::default(), regions).await
+    }
+
+    /// Like `new`, authenticating with `auth` unless an SDK config is given.
+    pub async fn with_auth(
+        aws_sdk_config: Option<SdkConfig>,
+        auth: &AwsAuthConfig,
+        regions: Vec<String>,
+    ) -> Self {
+        let config = auth.resolve(aws_sdk_config).await;
+        Self {
+            client_set: Arc::new(build_client_set(config)),
+            regions,
+            fan_out: FanOutConfig::default(),
+        }
+    }
+
+    /// Queries `regions` of `account`.
+    pub async fn for_account(
+        accounts: &MultiAccountClientSet<aws_sdk_rds::Client>,
+        account: &str,
+        regions: Vec<String>,
+    ) -> AwsClientResult<Self> {
+        Ok(Self {
+            client_set: accounts.account(account).await?.client_set,
+            regions,
+            fan_out: FanOutConfig::default(),
+        })
+    }
+
+    /// Concurrency and retries of calls made in every region.
+    pub fn with_fan_out(mut self, fan_out: FanOutConfig) -> Self {
+        self.fan_out = fan_out;
+        self
+    }
+
+    async fn region_clients(&self) -> Vec<(String, Arc<aws_sdk_rds::Client>)> {
+        let mut clients = Vec::with_capacity(self.regions.len());
+        for region in &self.regions {
+            clients.push((region.clone(), self.client_set.get(region).await));
+        }
+        clients
+    }
+
+    /// Instance options orderable in each region for `engines`, as the RDS API names them,
+    /// e.g. `postgres` or `aurora-mysql`. The API asks for one engine at a time.
+    pub async fn describe_orderable_db_instance_options(
+        &self,
+        engines: &[String],
+    ) -> RegionResults<Vec<OrderableDbInstanceOption>> {
+        let engines = engines.to_vec();
+        fan_out(&self.fan_out, self.region_clients().await, move |client| {
+            describe_orderable_db_instance_options(client, engines.clone())
+        })
+        .await
+    }
+
+    /// Reserved DB instance offerings of each region, Single-AZ and Multi-AZ.
+    pub async fn describe_reserved_db_instances_offerings(
+        &self,
+    ) -> RegionResults<Vec<ReservedDbInstancesOffering>> {
+        fan_out(
+            &self.fan_out,
+            self.region_clients().await,
+            describe_reserved_db_instances_offerings,
+        )
+        .await
+    }
+}
+
+async fn describe_orderable_db_instance_options(
+    client: Arc<aws_sdk_rds::Client>,
+    engines: Vec<String>,
+) -> AwsClientResult<Vec<OrderableDbInstanceOption>> {
+    let mut options = Vec::new();
+    for engine in engines {
+        info!(
+            "RdsClient: Requesting DescribeOrderableDBInstanceOptions (region={:?}, engine={})",
+            client.config().region(),
+            engine
+        );
+        let mut stream = client
+            .describe_orderable_db_instance_options()
+            .engine(engine)
+            .into_paginator()
+            .send();
+        while let Some(page_result) = stream.next().await {
+            metrics::global().record_request();
+            let page =
+                page_result.map_err(AwsClientError::DescribeOrderableDbInstanceOptionsFailure)?;
+            options.extend(
+                page.orderable_db_instance_options
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(orderable_db_instance_option),
+            );
+        }
+    }
+    Ok(options)
+}
+
+async fn describe_reserved_db_instances_offerings(
+    client: Arc<aws_sdk_rds::Client>,
+) -> AwsClientResult<Vec<ReservedDbInstancesOffering>> {
+    info!(
+        "RdsClient: Requesting DescribeReservedDBInstancesOfferings (region={:?})",
+        client.config().region()
+    );
+    let mut stream = client
+        .describe_reserved_db_instances_offerings()
+        .into_paginator()
+        .send();
+
+    let mut offerings = Vec::new();
+    while let Some(page_result) = stream.next().await {
+        metrics::global().record_request();
+        let page =
+            page_result.map_err(AwsClientError::DescribeReservedDbInstancesOfferingsFailure)?;
+        offerings.extend(
+            page.reserved_db_instances_offerings
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(reserved_db_instances_offering),
+        );
+    }
+    Ok(offerings)
+}
+
+/// Typed `option`, `None` if it lacks an engine or instance class.
+pub fn orderable_db_instance_option(
+    option: aws_sdk_rds::types::OrderableDbInstanceOption,
+) -> Option<OrderableDbInstanceOption> {
+    Some(OrderableDbInstanceOption {
+        engine: option.engine?,
+        engine_version: option.engine_version,
+        db_instance_class: option.db_instance_class?,
+        license_model: option.license_model,
+        availability_zones: option
+            .availability_zones
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|zone| Some(AvailabilityZone { name: zone.name? }))
+            .collect(),
+        multi_az_capable: option.multi_az_capable.unwrap_or_default(),
+        storage_type: option.storage_type,
+    })
+}
+
+/// Typed `offering`, `None` if it lacks an ID, instance class, term, product or offering type.
+pub fn reserved_db_instances_offering(
+    offering: aws_sdk_rds::types::ReservedDbInstancesOffering,
+) -> Option<ReservedDbInstancesOffering> {
+    Some(ReservedDbInstancesOffering {
+        reserved_db_instances_offering_id: offering.reserved_db_instances_offering_id?,
+        db_instance_class: offering.db_instance_class?,
+        duration: offering.duration?.into(),
+        fixed_price: offering.fixed_price.unwrap_or_default(),
+        usage_price: offering.usage_price.unwrap_or_default(),
+        currency_code: offering.currency_code,
+        product_description: offering.product_description?,
+        offering_type: offering.offering_type?,
+        multi_az: offering.multi_az.unwrap_or_default(),
+        recurring_charges: offering
+            .recurring_charges
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|charge| {
+                Some(RecurringCharge {
+                    recurring_charge_amount: charge.recurring_charge_amount?,
+                    recurring_charge_frequency: charge.recurring_charge_frequency?,
+                })
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{orderable_db_instance_option, reserved_db_instances_offering};
+    use aws_sdk_rds::types::{
+        AvailabilityZone, OrderableDbInstanceOption, RecurringCharge, ReservedDbInstancesOffering,
+    };
+
+    #[test]
+    fn test_orderable_db_instance_option() {
+        let option = OrderableDbInstanceOption::builder()
+            .engine("postgres")
+            .engine_version("16.2")
+            .db_instance_class("db.m6g.large")
+            .availability_zones(AvailabilityZone::builder().name("us-east-1a").build())
+            .availability_zones(AvailabilityZone::builder().build())
+            .multi_az_capable(true)
+            .storage_type("gp3")
+            .build();
+        let option = orderable_db_instance_option(option).unwrap();
+        assert_eq!(option.db_instance_class, "db.m6g.large");
+        assert_eq!(option.availability_zones.len(), 1);
+        assert!(option.multi_az_capable);
+        assert!(
+            orderable_db_instance_option(OrderableDbInstanceOption::builder().build()).is_none()
+        );
+    }
+
+    #[test]
+    fn test_reserved_db_instances_offering() {
+        let offering = ReservedDbInstancesOffering::builder()
+            .reserved_db_instances_offering_id("0a1b2c3d")
+            .db_instance_class("db.r6g.large")
+            .duration(31536000)
+            .fixed_price(1000.0)
+            .usage_price(0.0)
+            .currency_code("USD")
+            .product_description("oracle-se2(li)")
+            .offering_type("Partial Upfront")
+            .multi_az(true)
+            .recurring_charges(
+                RecurringCharge::builder()
+                    .recurring_charge_amount(0.114)
+                    .recurring_charge_frequency("Hourly")
+                    .build(),
+            )
+            .build();
+        let offering = reserved_db_instances_offering(offering).unwrap();
+        assert!(offering.multi_az);
+        assert_eq!(offering.duration, 31536000);
+        assert_eq!(offering.recurring_charges[0].recurring_charge_amount, 0.114);
+        assert_eq!(
+            offering.engine_and_license(),
+            Some(("Oracle", Some("License included")))
+        );
+        assert!(
+            reserved_db_instances_offering(ReservedDbInstancesOffering::builder().build())
+                .is_none()
+        );
+    }
+}
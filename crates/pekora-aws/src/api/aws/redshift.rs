@@ -0,0 +1,174 @@
+use crate::api::aws::accounts::MultiAccountClientSet;
+use crate::api::aws::auth::AwsAuthConfig;
+use crate::api::aws::fan_out::{fan_out, FanOutConfig, RegionResults};
+use crate::api::aws::util::{AwsClientError, AwsClientResult};
+use crate::metrics;
+use crate::util::ClientSet;
+use aws_config::SdkConfig;
+use aws_sdk_redshift::types::ActionType;
+use log::info;
+use std::sync::Arc;
+
+pub use pekora_core::model::aws::redshift::{
+    NodeConfigurationOption, NodeConfigurationOptionsResponse,
+};
+
+fn build_client_set(config: SdkConfig) -> ClientSet<SdkConfig, aws_sdk_redshift::Client> {
+    ClientSet::new(
+        config,
+        Box::new(|config, region| {
+            let mut builder = config.into_builder();
+            builder.set_region(aws_config::Region::new(region));
+            let new_config = builder.build();
+            aws_sdk_redshift::Client::new(&new_config)
+        }),
+    )
+}
+
+/// What node configurations are evaluated for. Redshift only evaluates them against an
+/// existing cluster or snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeConfigurationAction {
+    /// Elastic resize of the cluster of this identifier
+    ResizeCluster(String),
+    /// Restore of the snapshot of this identifier
+    RestoreSnapshot(String),
+}
+
+pub struct RedshiftClient {
+    client_set: Arc<ClientSet<SdkConfig, aws_sdk_redshift::Client>>,
+    regions: Vec<String>,
+    fan_out: FanOutConfig,
+}
+
+impl RedshiftClient {
+    /// Queries `regions`. Redshift has no call listing the regions it is in, so they are given.
+    pub async fn new(aws_sdk_config: Option<SdkConfig>, regions: Vec<String>) -> Self {
+        Self::with_auth(aws_sdk_config, &AwsAuthConfig::default(), regions).await
+    }
+
+    /// Like `new`, authenticating with `auth` unless an SDK config is given.
+    pub async fn with_auth(
+        aws_sdk_config: Option<SdkConfig>,
+        auth: &AwsAuthConfig,
+        regions: Vec<String>,
+    ) -> Self {
+        let config = auth.resolve(aws_sdk_config).await;
+        Self {
+            client_set: Arc::new(build_client_set(config)),
+            regions,
+            fan_out: FanOutConfig::default(),
+        }
+    }
+
+    /// Queries `regions` of `account`.
+    pub async fn for_account(
+        accounts: &MultiAccountClientSet<aws_sdk_redshift::Client>,
+        account: &str,
+        regions: Vec<String>,
+    ) -> AwsClientResult<Self> {
+        Ok(Self {
+            client_set: accounts.account(account).await?.client_set,
+            regions,
+            fan_out: FanOutConfig::default(),
+        })
+    }
+
+    /// Concurrency and retries of calls made in every region.
+    pub fn with_fan_out(mut self, fan_out: FanOutConfig) -> Self {
+        self.fan_out = fan_out;
+        self
+    }
+
+    async fn region_clients(&self) -> Vec<(String, Arc<aws_sdk_redshift::Client>)> {
+        let mut clients = Vec::with_capacity(self.regions.len());
+        for region in &self.regions {
+            clients.push((region.clone(), self.client_set.get(region).await));
+        }
+        clients
+    }
+
+    /// Node types and counts `action` allows in each region.
+    pub async fn describe_node_configuration_options(
+        &self,
+        action: &NodeConfigurationAction,
+    ) -> RegionResults<Vec<NodeConfigurationOption>> {
+        let action = action.clone();
+        fan_out(&self.fan_out, self.region_clients().await, move |client| {
+            describe_node_configuration_options(client, action.clone())
+        })
+        .await
+    }
+}
+
+async fn describe_node_configuration_options(
+    client: Arc<aws_sdk_redshift::Client>,
+    action: NodeConfigurationAction,
+) -> AwsClientResult<Vec<NodeConfigurationOption>> {
+    info!(
+        "RedshiftClient: Requesting DescribeNodeConfigurationOptions (region={:?}, action={:?})",
+        client.config().region(),
+        action
+    );
+    let request = client.describe_node_configuration_options();
+    let request = match action {
+        NodeConfigurationAction::ResizeCluster(cluster_identifier) => request
+            .action_type(ActionType::ResizeCluster)
+            .cluster_identifier(cluster_identifier),
+        NodeConfigurationAction::RestoreSnapshot(snapshot_identifier) => request
+            .action_type(ActionType::RestoreCluster)
+            .snapshot_identifier(snapshot_identifier),
+    };
+    let mut stream = request.into_paginator().send();
+
+    let mut options = Vec::new();
+    while let Some(page_result) = stream.next().await {
+        metrics::global().record_request();
+        let page = page_result.map_err(AwsClientError::DescribeNodeConfigurationOptionsFailure)?;
+        options.extend(
+            page.node_configuration_option_list
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(node_configuration_option),
+        );
+    }
+    Ok(options)
+}
+
+/// Typed `option`, `None` if it lacks a node type or count.
+pub fn node_configuration_option(
+    option: aws_sdk_redshift::types::NodeConfigurationOption,
+) -> Option<NodeConfigurationOption> {
+    Some(NodeConfigurationOption {
+        node_type: option.node_type?,
+        number_of_nodes: option.number_of_nodes?,
+        estimated_disk_utilization_percent: option.estimated_disk_utilization_percent,
+        mode: option.mode.map(|mode| mode.as_str().to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::node_configuration_option;
+    use aws_sdk_redshift::types::{Mode, NodeConfigurationOption};
+
+    #[test]
+    fn test_node_configuration_option() {
+        let option = NodeConfigurationOption::builder()
+            .node_type("ra3.xlplus")
+            .number_of_nodes(2)
+            .estimated_disk_utilization_percent(41.5)
+            .mode(Mode::Standard)
+            .build();
+        let option = node_configuration_option(option).unwrap();
+        assert_eq!(option.node_type, "ra3.xlplus");
+        assert_eq!(option.number_of_nodes, 2);
+        assert_eq!(option.mode.as_deref(), Some("standard"));
+        assert!(node_configuration_option(
+            NodeConfigurationOption::builder()
+                .node_type("dc2.large")
+                .build()
+        )
+        .is_none());
+    }
+}
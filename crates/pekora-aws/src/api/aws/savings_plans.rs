@@ -0,0 +1,386 @@
+use crate::api::aws::types::{PurchaseOption, SavingsPlanType};
+use crate::api::aws::util::{AwsClientError, AwsClientResult};
+use crate::cache::{CacheKey, Cacheable, CacheableArc};
+use crate::metrics;
+use async_trait::async_trait;
+use aws_config::{BehaviorVersion, SdkConfig};
+use aws_sdk_savingsplans::types::{
+    SavingsPlanOfferingFilterAttribute, SavingsPlanOfferingFilterElement,
+    SavingsPlanOfferingRateFilterElement, SavingsPlanRateFilterAttribute,
+};
+use log::info;
+use std::sync::Arc;
+
+pub use pekora_core::model::aws::savings_plans::{
+    ParentSavingsPlanOffering, SavingsPlanOffering, SavingsPlanOfferingProperty,
+    SavingsPlanOfferingRate, SavingsPlansOfferingRatesResponse, SavingsPlansOfferingsResponse,
+};
+
+/// The Savings Plans API has a single endpoint, independent of the region of the plans.
+const SAVINGS_PLANS_API_REGION: &str = "us-east-1";
+
+/// Offerings of one plan type sold in one region.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavingsPlansOfferingsQuery {
+    pub plan_type: SavingsPlanType,
+    pub region: String,
+}
+
+/// Rates of the offerings of one plan type for usage in one region.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavingsPlansRatesQuery {
+    pub plan_type: SavingsPlanType,
+    pub region: String,
+}
+
+/// Client of the Savings Plans API, for the offerings and rates sold right now.
+pub struct SavingsPlansClient {
+    client: aws_sdk_savingsplans::Client,
+}
+
+impl SavingsPlansClient {
+    pub async fn new(aws_sdk_config: Option<SdkConfig>) -> Self {
+        let config = match aws_sdk_config {
+            Some(config) => config,
+            None => aws_config::load_defaults(BehaviorVersion::latest()).await,
+        };
+        let mut builder = config.into_builder();
+        builder.set_region(Some(aws_config::Region::new(SAVINGS_PLANS_API_REGION)));
+        Self {
+            client: aws_sdk_savingsplans::Client::new(&builder.build()),
+        }
+    }
+
+    /// Caches offerings by plan type and region.
+    pub async fn new_offerings_cacheable_arc(
+        aws_sdk_config: Option<SdkConfig>,
+    ) -> CacheableArc<SavingsPlansOfferingsQuery, SavingsPlansOfferingsResponse, AwsClientError>
+    {
+        Arc::new(Box::new(Self::new(aws_sdk_config).await))
+    }
+
+    /// Caches offering rates by plan type and region.
+    pub async fn new_rates_cacheable_arc(
+        aws_sdk_config: Option<SdkConfig>,
+    ) -> CacheableArc<SavingsPlansRatesQuery, SavingsPlansOfferingRatesResponse, AwsClientError>
+    {
+        Arc::new(Box::new(Self::new(aws_sdk_config).await))
+    }
+
+    pub async fn describe_savings_plans_offerings(
+        &self,
+        query: &SavingsPlansOfferingsQuery,
+    ) -> AwsClientResult<SavingsPlansOfferingsResponse> {
+        let request = self
+            .client
+            .describe_savings_plans_offerings()
+            .plan_types(sdk_plan_type(&query.plan_type))
+            .filters(
+                SavingsPlanOfferingFilterElement::builder()
+                    .name(SavingsPlanOfferingFilterAttribute::Region)
+                    .values(&query.region)
+                    .build(),
+            );
+
+        let mut search_results = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            info!(
+                "SavingsPlansClient: Requesting DescribeSavingsPlansOfferings (plan_type={}, region={})",
+                query.plan_type.as_str(),
+                query.region
+            );
+            metrics::global().record_request();
+            let output = request
+                .clone()
+                .set_next_token(next_token)
+                .send()
+                .await
+                .map_err(AwsClientError::DescribeSavingsPlansOfferingsFailure)?;
+            search_results.extend(
+                output
+                    .search_results
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(savings_plan_offering),
+            );
+            next_token = output.next_token.filter(|token| !token.is_empty());
+            if next_token.is_none() {
+                break;
+            }
+        }
+        Ok(SavingsPlansOfferingsResponse {
+            search_results,
+            next_token: None,
+        })
+    }
+
+    pub async fn describe_savings_plans_offering_rates(
+        &self,
+        query: &SavingsPlansRatesQuery,
+    ) -> AwsClientResult<SavingsPlansOfferingRatesResponse> {
+        let request = self
+            .client
+            .describe_savings_plans_offering_rates()
+            .savings_plan_types(sdk_plan_type(&query.plan_type))
+            .filters(
+                SavingsPlanOfferingRateFilterElement::builder()
+                    .name(SavingsPlanRateFilterAttribute::Region)
+                    .values(&query.region)
+                    .build(),
+            );
+
+        let mut search_results = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            info!(
+                "SavingsPlansClient: Requesting DescribeSavingsPlansOfferingRates (plan_type={}, region={})",
+                query.plan_type.as_str(),
+                query.region
+            );
+            metrics::global().record_request();
+            let output = request
+                .clone()
+                .set_next_token(next_token)
+                .send()
+                .await
+                .map_err(AwsClientError::DescribeSavingsPlansOfferingRatesFailure)?;
+            search_results.extend(
+                output
+                    .search_results
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(savings_plan_offering_rate),
+            );
+            next_token = output.next_token.filter(|token| !token.is_empty());
+            if next_token.is_none() {
+                break;
+            }
+        }
+        Ok(SavingsPlansOfferingRatesResponse {
+            search_results,
+            next_token: None,
+        })
+    }
+}
+
+/// Plan type as the Savings Plans API names it, e.g. `EC2Instance`.
+fn sdk_plan_type(plan_type: &SavingsPlanType) -> aws_sdk_savingsplans::types::SavingsPlanType {
+    let name = match plan_type {
+        SavingsPlanType::Compute => "Compute",
+        SavingsPlanType::Ec2Instance => "EC2Instance",
+        SavingsPlanType::Unknown(name) => name,
+    };
+    aws_sdk_savingsplans::types::SavingsPlanType::from(name)
+}
+
+/// Typed `offering`, `None` if it lacks an ID, plan type or payment option.
+pub fn savings_plan_offering(
+    offering: aws_sdk_savingsplans::types::SavingsPlanOffering,
+) -> Option<SavingsPlanOffering> {
+    Some(SavingsPlanOffering {
+        offering_id: offering.offering_id?,
+        product_types: offering
+            .product_types
+            .unwrap_or_default()
+            .iter()
+            .map(|product_type| product_type.as_str().to_string())
+            .collect(),
+        plan_type: SavingsPlanType::parse(offering.plan_type?.as_str()),
+        description: offering.description,
+        payment_option: PurchaseOption::parse(offering.payment_option?.as_str()),
+        duration_seconds: offering.duration_seconds,
+        currency: offering
+            .currency
+            .map(|currency| currency.as_str().to_string()),
+        service_code: offering.service_code,
+        usage_type: offering.usage_type,
+        operation: offering.operation,
+        properties: offering
+            .properties
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|property| {
+                Some(SavingsPlanOfferingProperty {
+                    name: property.name?.as_str().to_string(),
+                    value: property.value?,
+                })
+            })
+            .collect(),
+    })
+}
+
+/// Typed `rate`, `None` if it lacks its offering, a rate in decimal, a usage type or operation.
+pub fn savings_plan_offering_rate(
+    rate: aws_sdk_savingsplans::types::SavingsPlanOfferingRate,
+) -> Option<SavingsPlanOfferingRate> {
+    let offering = rate.savings_plan_offering?;
+    Some(SavingsPlanOfferingRate {
+        savings_plan_offering: ParentSavingsPlanOffering {
+            offering_id: offering.offering_id?,
+            payment_option: PurchaseOption::parse(offering.payment_option?.as_str()),
+            plan_type: SavingsPlanType::parse(offering.plan_type?.as_str()),
+            duration_seconds: offering.duration_seconds,
+            currency: offering
+                .currency
+                .map(|currency| currency.as_str().to_string()),
+            plan_description: offering.plan_description,
+        },
+        rate: rate.rate?.parse().ok()?,
+        unit: rate.unit.map(|unit| unit.as_str().to_string()),
+        product_type: rate
+            .product_type
+            .map(|product_type| product_type.as_str().to_string()),
+        service_code: rate
+            .service_code
+            .map(|service_code| service_code.as_str().to_string()),
+        usage_type: rate.usage_type?,
+        operation: rate.operation?,
+        properties: rate
+            .properties
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|property| {
+                Some(SavingsPlanOfferingProperty {
+                    name: property.name?,
+                    value: property.value?,
+                })
+            })
+            .collect(),
+    })
+}
+
+/// Offerings of a plan type in a region. The API carries no validators, so cached offerings are
+/// reused until they expire.
+#[async_trait]
+impl Cacheable<SavingsPlansOfferingsQuery, SavingsPlansOfferingsResponse, AwsClientError>
+    for SavingsPlansClient
+{
+    async fn get_cache_key(
+        &self,
+        input: &SavingsPlansOfferingsQuery,
+    ) -> Result<CacheKey, AwsClientError> {
+        Ok(CacheKey {
+            content_key: self.content_key(input),
+            content_hash: None,
+        })
+    }
+
+    async fn load(
+        &self,
+        input: &SavingsPlansOfferingsQuery,
+    ) -> Result<SavingsPlansOfferingsResponse, AwsClientError> {
+        self.describe_savings_plans_offerings(input).await
+    }
+
+    fn category_key(&self) -> String {
+        "aws/savingsplans/offerings".to_string()
+    }
+
+    fn content_key(&self, input: &SavingsPlansOfferingsQuery) -> Option<String> {
+        Some(format!("{}_{}", input.plan_type.as_str(), input.region))
+    }
+}
+
+/// Offering rates of a plan type in a region. The API carries no validators, so cached rates are
+/// reused until they expire.
+#[async_trait]
+impl Cacheable<SavingsPlansRatesQuery, SavingsPlansOfferingRatesResponse, AwsClientError>
+    for SavingsPlansClient
+{
+    async fn get_cache_key(
+        &self,
+        input: &SavingsPlansRatesQuery,
+    ) -> Result<CacheKey, AwsClientError> {
+        Ok(CacheKey {
+            content_key: self.content_key(input),
+            content_hash: None,
+        })
+    }
+
+    async fn load(
+        &self,
+        input: &SavingsPlansRatesQuery,
+    ) -> Result<SavingsPlansOfferingRatesResponse, AwsClientError> {
+        self.describe_savings_plans_offering_rates(input).await
+    }
+
+    fn category_key(&self) -> String {
+        "aws/savingsplans/offering-rates".to_string()
+    }
+
+    fn content_key(&self, input: &SavingsPlansRatesQuery) -> Option<String> {
+        Some(format!("{}_{}", input.plan_type.as_str(), input.region))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{savings_plan_offering, savings_plan_offering_rate};
+    use crate::api::aws::types::{PurchaseOption, SavingsPlanType};
+    use aws_sdk_savingsplans::types::{
+        CurrencyCode, ParentSavingsPlanOffering, SavingsPlanOffering, SavingsPlanOfferingProperty,
+        SavingsPlanOfferingPropertyKey, SavingsPlanOfferingRate, SavingsPlanOfferingRateProperty,
+        SavingsPlanPaymentOption,
+    };
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_savings_plan_offering() {
+        let offering = SavingsPlanOffering::builder()
+            .offering_id("0a1b2c3d")
+            .plan_type(aws_sdk_savingsplans::types::SavingsPlanType::Ec2Instance)
+            .payment_option(SavingsPlanPaymentOption::NoUpfront)
+            .duration_seconds(31536000)
+            .currency(CurrencyCode::Usd)
+            .properties(
+                SavingsPlanOfferingProperty::builder()
+                    .name(SavingsPlanOfferingPropertyKey::Region)
+                    .value("us-west-2")
+                    .build(),
+            )
+            .build();
+        let offering = savings_plan_offering(offering).unwrap();
+        assert_eq!(offering.plan_type, SavingsPlanType::Ec2Instance);
+        assert_eq!(offering.payment_option, PurchaseOption::NoUpfront);
+        assert_eq!(offering.properties[0].name, "region");
+        assert!(savings_plan_offering(SavingsPlanOffering::builder().build()).is_none());
+    }
+
+    #[test]
+    fn test_savings_plan_offering_rate() {
+        let rate = SavingsPlanOfferingRate::builder()
+            .savings_plan_offering(
+                ParentSavingsPlanOffering::builder()
+                    .offering_id("0a1b2c3d")
+                    .plan_type(aws_sdk_savingsplans::types::SavingsPlanType::Compute)
+                    .payment_option(SavingsPlanPaymentOption::AllUpfront)
+                    .duration_seconds(94608000)
+                    .build(),
+            )
+            .rate("0.0412")
+            .usage_type("USW2-BoxUsage:m5.large")
+            .operation("RunInstances")
+            .properties(
+                SavingsPlanOfferingRateProperty::builder()
+                    .name("region")
+                    .value("us-west-2")
+                    .build(),
+            )
+            .build();
+        let rate = savings_plan_offering_rate(rate).unwrap();
+        assert_eq!(rate.rate, Decimal::new(412, 4));
+        assert_eq!(
+            rate.savings_plan_offering.payment_option,
+            PurchaseOption::AllUpfront
+        );
+        assert_eq!(rate.property("region"), Some("us-west-2"));
+
+        let invalid = SavingsPlanOfferingRate::builder()
+            .rate("n/a")
+            .usage_type("USW2-BoxUsage:m5.large")
+            .operation("RunInstances")
+            .build();
+        assert!(savings_plan_offering_rate(invalid).is_none());
+    }
+}
@@ -0,0 +1,113 @@
+use crate::api::aws::price_bulk::{
+    load_etag, load_json_conditional, unconditional, with_retry, PriceBulkError,
+};
+use crate::api::aws::price_bulk_builder::PriceBulkContext;
+use crate::cache::{CacheKey, Cacheable, CacheableArc, ConditionalLoad};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+pub use pekora_core::model::aws::spot_advisor::{
+    InterruptionRange, SpotAdvice, SpotAdvisorResponse, SpotInstanceType,
+};
+
+/// Public feed behind the EC2 Spot Instance Advisor.
+pub const DEFAULT_SPOT_ADVISOR_URL: &str =
+    "https://spot-bid-advisor.s3.amazonaws.com/spot-advisor-data.json";
+
+/// Client of the Spot Advisor feed. The feed covers the `aws` partition only.
+pub struct SpotAdvisorClient {
+    context: Arc<PriceBulkContext>,
+}
+
+impl SpotAdvisorClient {
+    pub fn new_cacheable_arc(
+        context: Arc<PriceBulkContext>,
+    ) -> CacheableArc<(), SpotAdvisorResponse, PriceBulkError> {
+        Arc::new(Box::new(Self { context }))
+    }
+}
+
+#[async_trait]
+impl Cacheable<(), SpotAdvisorResponse, PriceBulkError> for SpotAdvisorClient {
+    async fn get_cache_key(&self, input: &()) -> Result<CacheKey, PriceBulkError> {
+        let content_hash = load_etag(&self.context, &self.context.spot_advisor_url).await?;
+        Ok(CacheKey {
+            content_key: self.content_key(input),
+            content_hash,
+        })
+    }
+
+    async fn load(&self, input: &()) -> Result<SpotAdvisorResponse, PriceBulkError> {
+        unconditional(self.load_conditional(input, None).await?)
+    }
+
+    fn category_key(&self) -> String {
+        "aws/spot_advisor".to_string()
+    }
+
+    fn content_key(&self, _input: &()) -> Option<String> {
+        None
+    }
+
+    async fn load_conditional(
+        &self,
+        input: &(),
+        cached_key: Option<&CacheKey>,
+    ) -> Result<ConditionalLoad<SpotAdvisorResponse>, PriceBulkError> {
+        let url = self.context.spot_advisor_url.as_str();
+        with_retry(&self.context.retry_policy, url, || {
+            load_json_conditional(&self.context, url, self.content_key(input), cached_key)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::api::aws::price_bulk_builder::PriceBulkClientBuilder;
+    use crate::util::testing::serve;
+    use axum::routing::get;
+    use axum::Router;
+
+    pub(crate) const SPOT_ADVISOR_BODY: &str = r#"{
+        "global_rate": "<10%",
+        "instance_types": {
+            "m5.large": {"emr": true, "cores": 2, "ram_gb": 8.0},
+            "c5.xlarge": {"emr": true, "cores": 4, "ram_gb": 8.0}
+        },
+        "ranges": [
+            {"index": 0, "label": "<5%", "dots": 0, "max": 5},
+            {"index": 1, "label": "5-10%", "dots": 1, "max": 11},
+            {"index": 4, "label": ">20%", "dots": 4, "max": 100}
+        ],
+        "spot_advisor": {
+            "us-east-1": {
+                "Linux": {"m5.large": {"s": 55, "r": 1}, "c5.xlarge": {"s": 62, "r": 4}},
+                "Windows": {"m5.large": {"s": 40, "r": 0}}
+            }
+        }
+    }"#;
+
+    #[tokio::test]
+    async fn test_spot_advisor() {
+        let base_url = serve(Router::new().route(
+            "/spot-advisor-data.json",
+            get(|| async { SPOT_ADVISOR_BODY }),
+        ))
+        .await;
+        let client = PriceBulkClientBuilder::new()
+            .spot_advisor_url(format!("{}/spot-advisor-data.json", base_url))
+            .build()
+            .unwrap()
+            .spot_advisor();
+        let response = client.load(&()).await.unwrap();
+        let advice = &response.spot_advisor["us-east-1"]["Linux"]["c5.xlarge"];
+        assert_eq!(advice.savings, 62);
+        assert_eq!(
+            response.range(advice.interruption_range).unwrap().label,
+            ">20%"
+        );
+        assert!(response.range(2).is_none());
+        assert_eq!(response.instance_types["m5.large"].cores, 2);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::api::aws::util::{AwsClientError, AwsClientResult};
+use crate::cache::{CacheKey, Cacheable, CacheableArc};
+use crate::metrics;
+use async_trait::async_trait;
+use aws_config::{BehaviorVersion, SdkConfig};
+use log::info;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Public parameters are the same everywhere, so they are asked of a single region.
+const PUBLIC_PARAMETER_REGION: &str = "us-east-1";
+
+/// Path of the public parameters describing each region, named by region code.
+const REGIONS_PATH: &str = "/aws/service/global-infrastructure/regions";
+
+/// Most names GetParameters accepts at once.
+const GET_PARAMETERS_BATCH_SIZE: usize = 10;
+
+/// Map of (region code) -> (long name), e.g. `ap-northeast-1` -> `Asia Pacific (Tokyo)`
+pub type RegionLongNames = BTreeMap<String, String>;
+
+/// Client of the SSM public parameters AWS publishes about its global infrastructure.
+pub struct SsmClient {
+    client: aws_sdk_ssm::Client,
+}
+
+impl SsmClient {
+    pub async fn new(aws_sdk_config: Option<SdkConfig>) -> Self {
+        let config = match aws_sdk_config {
+            Some(config) => config,
+            None => aws_config::load_defaults(BehaviorVersion::latest()).await,
+        };
+        let mut builder = config.into_builder();
+        builder.set_region(Some(aws_config::Region::new(PUBLIC_PARAMETER_REGION)));
+        Self {
+            client: aws_sdk_ssm::Client::new(&builder.build()),
+        }
+    }
+
+    /// Caches the long names of regions.
+    pub async fn new_cacheable_arc(
+        aws_sdk_config: Option<SdkConfig>,
+    ) -> CacheableArc<(), RegionLongNames, AwsClientError> {
+        Arc::new(Box::new(Self::new(aws_sdk_config).await))
+    }
+
+    /// Long names of every region AWS announced, including ones not enabled for the account.
+    pub async fn region_long_names(&self) -> AwsClientResult<RegionLongNames> {
+        info!("SsmClient: GetParametersByPath {}", REGIONS_PATH);
+        let mut stream = self
+            .client
+            .get_parameters_by_path()
+            .path(REGIONS_PATH)
+            .into_paginator()
+            .send();
+        let mut codes = Vec::new();
+        while let Some(page_result) = stream.next().await {
+            metrics::global().record_request();
+            let page = page_result.map_err(AwsClientError::GetParametersByPathFailure)?;
+            codes.extend(
+                page.parameters
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|parameter| parameter.value),
+            );
+        }
+
+        let mut result = RegionLongNames::new();
+        for batch in codes.chunks(GET_PARAMETERS_BATCH_SIZE) {
+            info!("SsmClient: GetParameters of {} long names", batch.len());
+            let names = batch
+                .iter()
+                .map(|code| format!("{}/{}/longName", REGIONS_PATH, code))
+                .collect();
+            metrics::global().record_request();
+            let output = self
+                .client
+                .get_parameters()
+                .set_names(Some(names))
+                .send()
+                .await
+                .map_err(AwsClientError::GetParametersFailure)?;
+            for parameter in output.parameters.unwrap_or_default() {
+                let code = parameter.name.as_deref().and_then(long_name_region);
+                if let Some((code, long_name)) = code.zip(parameter.value) {
+                    result.insert(code.to_string(), long_name);
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Region code of a `longName` parameter name.
+fn long_name_region(name: &str) -> Option<&str> {
+    name.strip_prefix(REGIONS_PATH)?
+        .strip_prefix('/')?
+        .strip_suffix("/longName")
+}
+
+/// Region long names, taken from public parameters without validators. Cached names are reused
+/// until they expire.
+#[async_trait]
+impl Cacheable<(), RegionLongNames, AwsClientError> for SsmClient {
+    async fn get_cache_key(&self, input: &()) -> Result<CacheKey, AwsClientError> {
+        Ok(CacheKey {
+            content_key: self.content_key(input),
+            content_hash: None,
+        })
+    }
+
+    async fn load(&self, _input: &()) -> Result<RegionLongNames, AwsClientError> {
+        self.region_long_names().await
+    }
+
+    fn category_key(&self) -> String {
+        "aws/ssm/global-infrastructure".to_string()
+    }
+
+    fn content_key(&self, _input: &()) -> Option<String> {
+        Some("region-long-names".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::long_name_region;
+
+    #[test]
+    fn test_long_name_region() {
+        assert_eq!(
+            long_name_region("/aws/service/global-infrastructure/regions/ap-northeast-1/longName"),
+            Some("ap-northeast-1")
+        );
+        assert_eq!(
+            long_name_region("/aws/service/global-infrastructure/regions/ap-northeast-1/domain"),
+            None
+        );
+        assert_eq!(
+            long_name_region("/aws/service/global-infrastructure/services/ec2/longName"),
+            None
+        );
+    }
+}
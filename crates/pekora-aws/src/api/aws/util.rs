@@ -0,0 +1,188 @@
+use crate::util::RetryClass;
+use aws_sdk_computeoptimizer::operation::get_ec2_instance_recommendations::GetEC2InstanceRecommendationsError;
+#[cfg(feature = "cost-explorer")]
+use aws_sdk_costexplorer::operation::get_cost_and_usage::GetCostAndUsageError;
+#[cfg(feature = "cost-explorer")]
+use aws_sdk_costexplorer::operation::get_reservation_coverage::GetReservationCoverageError;
+#[cfg(feature = "cost-explorer")]
+use aws_sdk_costexplorer::operation::get_savings_plans_utilization::GetSavingsPlansUtilizationError;
+use aws_sdk_docdb::operation::describe_engine_default_cluster_parameters::DescribeEngineDefaultClusterParametersError;
+use aws_sdk_docdb::operation::describe_orderable_db_instance_options::DescribeOrderableDBInstanceOptionsError as DescribeOrderableDocDbInstanceOptionsError;
+use aws_sdk_ec2::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_ec2::operation::describe_host_reservation_offerings::DescribeHostReservationOfferingsError;
+use aws_sdk_ec2::operation::describe_instance_type_offerings::DescribeInstanceTypeOfferingsError;
+use aws_sdk_ec2::operation::describe_instance_types::DescribeInstanceTypesError;
+use aws_sdk_ec2::operation::describe_regions::DescribeRegionsError;
+use aws_sdk_elasticache::operation::describe_engine_default_parameters::DescribeEngineDefaultParametersError;
+use aws_sdk_elasticache::operation::describe_reserved_cache_nodes_offerings::DescribeReservedCacheNodesOfferingsError;
+use aws_sdk_memorydb::operation::describe_parameters::DescribeParametersError;
+use aws_sdk_opensearch::operation::list_instance_type_details::ListInstanceTypeDetailsError;
+use aws_sdk_pricing::error::BuildError;
+use aws_sdk_pricing::operation::get_attribute_values::GetAttributeValuesError;
+use aws_sdk_pricing::operation::get_products::GetProductsError;
+use aws_sdk_rds::operation::describe_orderable_db_instance_options::DescribeOrderableDBInstanceOptionsError;
+use aws_sdk_rds::operation::describe_reserved_db_instances_offerings::DescribeReservedDBInstancesOfferingsError;
+use aws_sdk_redshift::operation::describe_node_configuration_options::DescribeNodeConfigurationOptionsError;
+use aws_sdk_savingsplans::operation::describe_savings_plans_offering_rates::DescribeSavingsPlansOfferingRatesError;
+use aws_sdk_savingsplans::operation::describe_savings_plans_offerings::DescribeSavingsPlansOfferingsError;
+use aws_sdk_ssm::operation::get_parameters::GetParametersError;
+use aws_sdk_ssm::operation::get_parameters_by_path::GetParametersByPathError;
+
+pub type AwsClientResult<T> = Result<T, AwsClientError>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum AwsClientError {
+    #[error("Compute Optimizer GetEC2InstanceRecommendations failed: {0}")]
+    GetEc2InstanceRecommendationsFailure(#[from] SdkError<GetEC2InstanceRecommendationsError>),
+    #[cfg(feature = "cost-explorer")]
+    #[error("Cost Explorer GetCostAndUsage failed: {0}")]
+    GetCostAndUsageFailure(#[from] SdkError<GetCostAndUsageError>),
+    #[cfg(feature = "cost-explorer")]
+    #[error("Cost Explorer GetSavingsPlansUtilization failed: {0}")]
+    GetSavingsPlansUtilizationFailure(#[from] SdkError<GetSavingsPlansUtilizationError>),
+    #[cfg(feature = "cost-explorer")]
+    #[error("Cost Explorer GetReservationCoverage failed: {0}")]
+    GetReservationCoverageFailure(#[from] SdkError<GetReservationCoverageError>),
+    #[error("DocumentDB DescribeEngineDefaultClusterParameters failed: {0}")]
+    DescribeEngineDefaultClusterParametersFailure(
+        #[from] SdkError<DescribeEngineDefaultClusterParametersError>,
+    ),
+    #[error("DocumentDB DescribeOrderableDBInstanceOptions failed: {0}")]
+    DescribeOrderableDocDbInstanceOptionsFailure(
+        #[from] SdkError<DescribeOrderableDocDbInstanceOptionsError>,
+    ),
+    #[error("EC2 DescribeInstanceTypes failed: {0}")]
+    DescribeInstanceTypesFailure(#[from] SdkError<DescribeInstanceTypesError>),
+    #[error("EC2 DescribeInstanceTypeOfferings failed: {0}")]
+    DescribeInstanceTypeOfferingsFailure(#[from] SdkError<DescribeInstanceTypeOfferingsError>),
+    #[error("EC2 DescribeHostReservationOfferings failed: {0}")]
+    DescribeHostReservationOfferingsFailure(
+        #[from] SdkError<DescribeHostReservationOfferingsError>,
+    ),
+    #[error("EC2 DescribeRegions failed: {0}")]
+    DescribeRegionsFailure(#[from] SdkError<DescribeRegionsError>),
+    #[error("Elasticache DescribeCacheParameters failed: {0}")]
+    DescribeEngineDefaultParametersFailure(#[from] SdkError<DescribeEngineDefaultParametersError>),
+    #[error("Elasticache DescribeReservedCacheNodesOfferings failed: {0}")]
+    DescribeReservedCacheNodesOfferingsFailure(
+        #[from] SdkError<DescribeReservedCacheNodesOfferingsError>,
+    ),
+    #[error("MemoryDB DescribeParameters failed: {0}")]
+    DescribeParametersFailure(#[from] SdkError<DescribeParametersError>),
+    #[error("OpenSearch ListInstanceTypeDetails failed: {0}")]
+    ListInstanceTypeDetailsFailure(#[from] SdkError<ListInstanceTypeDetailsError>),
+    #[error("Pricing GetProducts failed: {0}")]
+    GetProductsFailure(#[from] SdkError<GetProductsError>),
+    #[error("Pricing GetAttributeValues failed: {0}")]
+    GetAttributeValuesFailure(#[from] SdkError<GetAttributeValuesError>),
+    #[error("RDS DescribeOrderableDBInstanceOptions failed: {0}")]
+    DescribeOrderableDbInstanceOptionsFailure(
+        #[from] SdkError<DescribeOrderableDBInstanceOptionsError>,
+    ),
+    #[error("RDS DescribeReservedDBInstancesOfferings failed: {0}")]
+    DescribeReservedDbInstancesOfferingsFailure(
+        #[from] SdkError<DescribeReservedDBInstancesOfferingsError>,
+    ),
+    #[error("Redshift DescribeNodeConfigurationOptions failed: {0}")]
+    DescribeNodeConfigurationOptionsFailure(
+        #[from] SdkError<DescribeNodeConfigurationOptionsError>,
+    ),
+    #[error("Savings Plans DescribeSavingsPlansOfferings failed: {0}")]
+    DescribeSavingsPlansOfferingsFailure(#[from] SdkError<DescribeSavingsPlansOfferingsError>),
+    #[error("Savings Plans DescribeSavingsPlansOfferingRates failed: {0}")]
+    DescribeSavingsPlansOfferingRatesFailure(
+        #[from] SdkError<DescribeSavingsPlansOfferingRatesError>,
+    ),
+    #[error("SSM GetParametersByPath failed: {0}")]
+    GetParametersByPathFailure(#[from] SdkError<GetParametersByPathError>),
+    #[error("SSM GetParameters failed: {0}")]
+    GetParametersFailure(#[from] SdkError<GetParametersError>),
+    #[error("Invalid request: {0}")]
+    InvalidRequest(#[from] BuildError),
+    #[error("Response lacks {0}")]
+    MissingField(&'static str),
+    #[error("Response deserialization failed: {0}")]
+    Deserialize(serde_json::Error),
+    #[error("Tokio thread error: {0}")]
+    Tokio(#[from] tokio::task::JoinError),
+    #[error("Unknown AWS account {0}")]
+    UnknownAccount(String),
+    #[error("{region} failed: {source}")]
+    Region {
+        region: String,
+        source: Box<AwsClientError>,
+    },
+}
+
+/// Error codes AWS APIs answer with when a caller is throttled.
+const THROTTLING_CODES: [&str; 4] = [
+    "Throttling",
+    "ThrottlingException",
+    "RequestLimitExceeded",
+    "TooManyRequestsException",
+];
+
+impl AwsClientError {
+    /// Transient failure class of this error, if it is worth retrying.
+    pub fn retry_class(&self) -> Option<RetryClass> {
+        match self {
+            AwsClientError::GetEc2InstanceRecommendationsFailure(e) => sdk_retry_class(e),
+            #[cfg(feature = "cost-explorer")]
+            AwsClientError::GetCostAndUsageFailure(e) => sdk_retry_class(e),
+            #[cfg(feature = "cost-explorer")]
+            AwsClientError::GetSavingsPlansUtilizationFailure(e) => sdk_retry_class(e),
+            #[cfg(feature = "cost-explorer")]
+            AwsClientError::GetReservationCoverageFailure(e) => sdk_retry_class(e),
+            AwsClientError::DescribeEngineDefaultClusterParametersFailure(e) => sdk_retry_class(e),
+            AwsClientError::DescribeOrderableDocDbInstanceOptionsFailure(e) => sdk_retry_class(e),
+            AwsClientError::DescribeInstanceTypesFailure(e) => sdk_retry_class(e),
+            AwsClientError::DescribeInstanceTypeOfferingsFailure(e) => sdk_retry_class(e),
+            AwsClientError::DescribeHostReservationOfferingsFailure(e) => sdk_retry_class(e),
+            AwsClientError::DescribeRegionsFailure(e) => sdk_retry_class(e),
+            AwsClientError::DescribeEngineDefaultParametersFailure(e) => sdk_retry_class(e),
+            AwsClientError::DescribeReservedCacheNodesOfferingsFailure(e) => sdk_retry_class(e),
+            AwsClientError::DescribeParametersFailure(e) => sdk_retry_class(e),
+            AwsClientError::ListInstanceTypeDetailsFailure(e) => sdk_retry_class(e),
+            AwsClientError::GetProductsFailure(e) => sdk_retry_class(e),
+            AwsClientError::GetAttributeValuesFailure(e) => sdk_retry_class(e),
+            AwsClientError::DescribeOrderableDbInstanceOptionsFailure(e) => sdk_retry_class(e),
+            AwsClientError::DescribeReservedDbInstancesOfferingsFailure(e) => sdk_retry_class(e),
+            AwsClientError::DescribeNodeConfigurationOptionsFailure(e) => sdk_retry_class(e),
+            AwsClientError::DescribeSavingsPlansOfferingsFailure(e) => sdk_retry_class(e),
+            AwsClientError::DescribeSavingsPlansOfferingRatesFailure(e) => sdk_retry_class(e),
+            AwsClientError::GetParametersByPathFailure(e) => sdk_retry_class(e),
+            AwsClientError::GetParametersFailure(e) => sdk_retry_class(e),
+            AwsClientError::Region { source, .. } => source.retry_class(),
+            AwsClientError::InvalidRequest(_)
+            | AwsClientError::MissingField(_)
+            | AwsClientError::Deserialize(_)
+            | AwsClientError::Tokio(_)
+            | AwsClientError::UnknownAccount(_) => None,
+        }
+    }
+}
+
+fn sdk_retry_class<E: ProvideErrorMetadata>(error: &SdkError<E>) -> Option<RetryClass> {
+    match error {
+        SdkError::TimeoutError(_) => return Some(RetryClass::Timeout),
+        SdkError::DispatchFailure(failure) if failure.is_timeout() => {
+            return Some(RetryClass::Timeout)
+        }
+        SdkError::DispatchFailure(failure) if failure.is_io() => return Some(RetryClass::Connect),
+        _ => {}
+    }
+    if error
+        .code()
+        .is_some_and(|code| THROTTLING_CODES.contains(&code))
+    {
+        return Some(RetryClass::TooManyRequests);
+    }
+    let status = error.raw_response()?.status();
+    if status.as_u16() == 429 {
+        Some(RetryClass::TooManyRequests)
+    } else if status.is_server_error() {
+        Some(RetryClass::ServerError)
+    } else {
+        None
+    }
+}
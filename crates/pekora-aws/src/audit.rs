@@ -0,0 +1,245 @@
+//! Reports which services' offer files match the registered attribute schemas
+use crate::api::aws::price_bulk_types::{PriceBulkOffer, PricingListResponse};
+use crate::api::aws::schema::SchemaRegistry;
+use crate::facade::Pekora;
+use futures::{stream, StreamExt};
+use log::info;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+const DEFAULT_CONCURRENCY: usize = 4;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CoverageStatus {
+    /// Matches the service's registered attribute schema
+    Typed,
+    /// Has a registered schema, but the offer file doesn't fit it
+    TypedFailed { error: String },
+    /// Parses generically, no schema registered yet
+    Untyped,
+    /// Doesn't parse at all, or couldn't be downloaded
+    Failed { error: String },
+    /// No offer file for the audited region
+    NotInRegion,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceCoverage {
+    pub service_code: String,
+    pub products: usize,
+    /// Distinct product attribute names across the offer file
+    pub attribute_keys: usize,
+    #[serde(flatten)]
+    pub status: CoverageStatus,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CoverageReport {
+    pub region: String,
+    pub services: Vec<ServiceCoverage>,
+}
+
+impl CoverageReport {
+    pub fn failures(&self) -> impl Iterator<Item = &ServiceCoverage> {
+        self.services.iter().filter(|service| {
+            matches!(
+                service.status,
+                CoverageStatus::TypedFailed { .. } | CoverageStatus::Failed { .. }
+            )
+        })
+    }
+}
+
+impl Display for CoverageReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut typed = 0;
+        let mut untyped = 0;
+        for service in &self.services {
+            match &service.status {
+                CoverageStatus::Typed => {
+                    typed += 1;
+                    writeln!(f, "typed     {}", service.service_code)?;
+                }
+                CoverageStatus::Untyped => {
+                    untyped += 1;
+                    writeln!(
+                        f,
+                        "untyped   {} ({} products, {} attributes)",
+                        service.service_code, service.products, service.attribute_keys
+                    )?;
+                }
+                CoverageStatus::TypedFailed { error } => {
+                    writeln!(f, "mismatch  {}: {}", service.service_code, error)?
+                }
+                CoverageStatus::Failed { error } => {
+                    writeln!(f, "failed    {}: {}", service.service_code, error)?
+                }
+                CoverageStatus::NotInRegion => writeln!(
+                    f,
+                    "skipped   {} (not in {})",
+                    service.service_code, self.region
+                )?,
+            }
+        }
+        write!(
+            f,
+            "{} typed, {} untyped, {} failed in {}",
+            typed,
+            untyped,
+            self.failures().count(),
+            self.region
+        )
+    }
+}
+
+/// Downloads the current offer of each service in `region` and checks it against the schemas
+/// registered with `pekora`.
+pub async fn coverage(
+    pekora: &Pekora,
+    region: &str,
+    concurrency: Option<usize>,
+    services: Option<Vec<String>>,
+) -> anyhow::Result<CoverageReport> {
+    let services = services.map(|services| services.into_iter().collect::<HashSet<_>>());
+    let service_index = pekora
+        .cacheable_builder()
+        .build(pekora.clients().service_index())
+        .load(&())
+        .await?
+        .result;
+    let mut service_codes = service_index
+        .offers
+        .into_values()
+        .filter(|offer| offer.current_region_index_url.is_some())
+        .map(|offer| offer.offer_code)
+        .filter(|code| {
+            services
+                .as_ref()
+                .is_none_or(|services| services.contains(code))
+        })
+        .collect::<Vec<_>>();
+    service_codes.sort();
+    info!("Auditing {} services in {}", service_codes.len(), region);
+
+    let region_index = pekora
+        .cacheable_builder()
+        .build(pekora.clients().region_index());
+    let pricing_list = pekora
+        .cacheable_builder()
+        .build(pekora.clients().pricing_list());
+    let mut coverages = stream::iter(service_codes)
+        .map(|service_code| {
+            let (region_index, pricing_list) = (&region_index, &pricing_list);
+            async move {
+                let offer = match region_index.load(&service_code).await {
+                    Ok(loaded) => loaded
+                        .result
+                        .regions
+                        .get(region)
+                        .map(|region| region.current_version_url.clone()),
+                    Err(e) => return failed(service_code, e.to_string()),
+                };
+                let offer: PriceBulkOffer = match offer {
+                    Some(offer) => offer,
+                    None => {
+                        return ServiceCoverage {
+                            service_code,
+                            products: 0,
+                            attribute_keys: 0,
+                            status: CoverageStatus::NotInRegion,
+                        }
+                    }
+                };
+                match pricing_list.load(&offer).await {
+                    Ok(loaded) => classify(pekora.schemas(), service_code, loaded.result),
+                    Err(e) => failed(service_code, e.to_string()),
+                }
+            }
+        })
+        .buffer_unordered(concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1))
+        .collect::<Vec<_>>()
+        .await;
+    coverages.sort_by(|a, b| a.service_code.cmp(&b.service_code));
+    Ok(CoverageReport {
+        region: region.to_string(),
+        services: coverages,
+    })
+}
+
+fn failed(service_code: String, error: String) -> ServiceCoverage {
+    ServiceCoverage {
+        service_code,
+        products: 0,
+        attribute_keys: 0,
+        status: CoverageStatus::Failed { error },
+    }
+}
+
+fn classify(
+    schemas: &SchemaRegistry,
+    service_code: String,
+    response: PricingListResponse,
+) -> ServiceCoverage {
+    let products = response.products.len();
+    let attribute_keys = response
+        .products
+        .values()
+        .flat_map(|product| product.attributes.keys())
+        .collect::<HashSet<_>>()
+        .len();
+    let status = match schemas.get(&service_code) {
+        Some(schema) => match schema.validate(&response) {
+            Ok(()) => CoverageStatus::Typed,
+            Err(e) => CoverageStatus::TypedFailed {
+                error: e.to_string(),
+            },
+        },
+        None => CoverageStatus::Untyped,
+    };
+    ServiceCoverage {
+        service_code,
+        products,
+        attribute_keys,
+        status,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{coverage, CoverageStatus};
+    use crate::api::aws::price_bulk_builder::PriceBulkClientBuilder;
+    use crate::facade::Pekora;
+
+    /// Walks the live service index. Downloads several gigabytes on a cold cache, so run with
+    /// `cargo test -- --ignored test_live_coverage` and `PEKORA_AUDIT_SERVICES` to narrow it.
+    #[tokio::test]
+    #[ignore]
+    async fn test_live_coverage() {
+        let services = std::env::var("PEKORA_AUDIT_SERVICES")
+            .ok()
+            .map(|services| services.split(',').map(str::to_string).collect());
+        let clients = PriceBulkClientBuilder::new()
+            .download_directory("test_cache/audit")
+            .build()
+            .unwrap();
+        let pekora = Pekora::new(clients, Some("test_cache/audit".to_string()), None);
+
+        let report = coverage(&pekora, "us-east-1", None, services)
+            .await
+            .unwrap();
+        println!("{}", report);
+        let failures = report
+            .services
+            .iter()
+            .filter(|service| matches!(service.status, CoverageStatus::TypedFailed { .. }))
+            .map(|service| service.service_code.as_str())
+            .collect::<Vec<_>>();
+        assert!(
+            failures.is_empty(),
+            "typed models don't fit: {:?}",
+            failures
+        );
+    }
+}
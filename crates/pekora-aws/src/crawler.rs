@@ -0,0 +1,262 @@
+//! Fetches every current offer of every service through the cache layer
+use crate::api::aws::price_bulk_types::PriceBulkOffer;
+use crate::facade::Pekora;
+use crate::metrics;
+use futures::{stream, StreamExt};
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::time::Instant;
+
+const DEFAULT_CONCURRENCY: usize = 4;
+
+pub struct Crawler {
+    pekora: Pekora,
+    concurrency: usize,
+    services: Option<HashSet<String>>,
+}
+
+impl Crawler {
+    /// Crawls `services`, or every service in the service index if `None`.
+    pub fn new(pekora: Pekora, concurrency: Option<usize>, services: Option<Vec<String>>) -> Self {
+        Self {
+            pekora,
+            concurrency: concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1),
+            services: services.map(|services| services.into_iter().collect()),
+        }
+    }
+
+    /// Walks the service and region indexes, then downloads every offer of the clients'
+    /// partition. Failures of individual offers are reported instead of aborting the crawl.
+    pub async fn run(&self) -> anyhow::Result<CrawlReport> {
+        let started = Instant::now();
+        let service_index = self
+            .pekora
+            .cacheable_builder()
+            .build(self.pekora.clients().service_index())
+            .load(&())
+            .await?
+            .result;
+        let mut service_codes = service_index
+            .offers
+            .into_values()
+            .filter(|offer| offer.current_region_index_url.is_some())
+            .map(|offer| offer.offer_code)
+            .filter(|code| {
+                self.services
+                    .as_ref()
+                    .is_none_or(|services| services.contains(code))
+            })
+            .collect::<Vec<_>>();
+        service_codes.sort();
+        info!("Crawling {} services", service_codes.len());
+
+        let mut report = CrawlReport::default();
+        let region_index = self
+            .pekora
+            .cacheable_builder()
+            .build(self.pekora.clients().region_index());
+        let region_indexes = stream::iter(service_codes)
+            .map(|service_code| {
+                let region_index = &region_index;
+                async move {
+                    let result = region_index.load(&service_code).await;
+                    (service_code, result)
+                }
+            })
+            .buffer_unordered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+
+        let partition = self.pekora.clients().partition();
+        let mut offers: Vec<PriceBulkOffer> = Vec::new();
+        for (service_code, result) in region_indexes {
+            match result {
+                Ok(loaded) => offers.extend(
+                    loaded
+                        .result
+                        .regions
+                        .into_values()
+                        .map(|region| region.current_version_url)
+                        .filter(|offer| partition.contains_region(&offer.region)),
+                ),
+                Err(e) => report.outcomes.push(CrawlOutcome {
+                    target: format!("{} region index", service_code),
+                    offer: None,
+                    result: Err(e.to_string()),
+                }),
+            }
+        }
+        offers.sort_by_key(|offer| offer.tag());
+        info!("Fetching {} offers", offers.len());
+
+        let pricing_list = self
+            .pekora
+            .cacheable_builder()
+            .build(self.pekora.clients().pricing_list());
+        let outcomes = stream::iter(offers)
+            .map(|offer| {
+                let pricing_list = &pricing_list;
+                async move {
+                    let result = pricing_list
+                        .load(&offer)
+                        .await
+                        .map(|loaded| loaded.cache_hit)
+                        .map_err(|e| e.to_string());
+                    match &result {
+                        Ok(_) => info!("Fetched {}", offer.tag()),
+                        Err(e) => warn!("Failed to fetch {}: {}", offer.tag(), e),
+                    }
+                    CrawlOutcome {
+                        target: offer.tag(),
+                        offer: Some(offer),
+                        result,
+                    }
+                }
+            })
+            .buffer_unordered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+        report.outcomes.extend(outcomes);
+        report.outcomes.sort_by(|a, b| a.target.cmp(&b.target));
+        metrics::global().record_crawl(started.elapsed());
+        Ok(report)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrawlOutcome {
+    pub target: String,
+    /// Offer fetched, `None` for region indexes
+    #[serde(skip)]
+    pub offer: Option<PriceBulkOffer>,
+    /// Whether the offer was served from cache, or why it failed.
+    pub result: Result<bool, String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CrawlReport {
+    pub outcomes: Vec<CrawlOutcome>,
+}
+
+impl CrawlReport {
+    pub fn failures(&self) -> impl Iterator<Item = &CrawlOutcome> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.result.is_err())
+    }
+}
+
+impl Display for CrawlReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut cache_hits = 0;
+        for outcome in &self.outcomes {
+            match &outcome.result {
+                Ok(true) => {
+                    cache_hits += 1;
+                    writeln!(f, "ok      {} (cached)", outcome.target)?;
+                }
+                Ok(false) => writeln!(f, "ok      {}", outcome.target)?,
+                Err(e) => writeln!(f, "failed  {}: {}", outcome.target, e)?,
+            }
+        }
+        let failures = self.failures().count();
+        write!(
+            f,
+            "{} succeeded ({} from cache), {} failed",
+            self.outcomes.len() - failures,
+            cache_hits,
+            failures
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Crawler;
+    use crate::api::aws::price_bulk_builder::PriceBulkClientBuilder;
+    use crate::facade::Pekora;
+    use crate::util::testing::serve;
+    use axum::http::header;
+    use axum::routing::get;
+    use axum::Router;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    const SERVICE_INDEX_BODY: &str = r#"{
+        "formatVersion": "v1.0",
+        "publicationDate": "2024-03-12T15:37:24Z",
+        "offers": {
+            "AmazonFoo": {
+                "offerCode": "AmazonFoo",
+                "currentRegionIndexUrl": "/offers/v1.0/aws/AmazonFoo/current/region_index.json"
+            },
+            "AmazonBar": {"offerCode": "AmazonBar"}
+        }
+    }"#;
+
+    const REGION_INDEX_BODY: &str = r#"{
+        "formatVersion": "v1.0",
+        "publicationDate": "2024-03-12T15:37:24Z",
+        "regions": {
+            "us-east-1": {
+                "regionCode": "us-east-1",
+                "currentVersionUrl": "/offers/v1.0/aws/AmazonFoo/20240312153724/us-east-1/index.json"
+            },
+            "us-west-2": {
+                "regionCode": "us-west-2",
+                "currentVersionUrl": "/offers/v1.0/aws/AmazonFoo/20240312153724/us-west-2/index.json"
+            }
+        }
+    }"#;
+
+    const PRICING_LIST_BODY: &str = r#"{
+        "formatVersion": "v1.0",
+        "publicationDate": "2024-03-12T15:37:24Z",
+        "version": "20240312153724",
+        "products": {},
+        "terms": {"OnDemand": {}, "Reserved": {}}
+    }"#;
+
+    #[tokio::test]
+    async fn test_crawl_reports_each_offer() {
+        let router = Router::new()
+            .route(
+                "/offers/v1.0/aws/index.json",
+                get(|| async { ([(header::ETAG, "\"v1\"")], SERVICE_INDEX_BODY) }),
+            )
+            .route(
+                "/offers/v1.0/aws/AmazonFoo/current/region_index.json",
+                get(|| async { REGION_INDEX_BODY }),
+            )
+            .route(
+                "/offers/v1.0/aws/AmazonFoo/20240312153724/us-east-1/index.json",
+                get(|| async { PRICING_LIST_BODY }),
+            );
+        let base_url = serve(router).await;
+        let cache_directory = format!(
+            "test_cache/crawler-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_micros()
+        );
+        let clients = PriceBulkClientBuilder::new()
+            .base_url(base_url)
+            .download_directory(cache_directory.clone())
+            .retry_policy(crate::util::RetryPolicy::none())
+            .build()
+            .unwrap();
+        let crawler = Crawler::new(
+            Pekora::new(clients, Some(cache_directory), None),
+            None,
+            None,
+        );
+
+        let report = crawler.run().await.unwrap();
+        assert_eq!(report.outcomes.len(), 2);
+        assert!(report.outcomes[0].result.is_ok());
+        assert!(report.outcomes[0].target.starts_with("us-east-1"));
+        assert_eq!(report.failures().count(), 1);
+    }
+}
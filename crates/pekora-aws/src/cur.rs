@@ -0,0 +1,379 @@
+//! Cost and Usage Report (CUR 2.0) parquet files, summed into instance usage. Exports are read
+//! from local disk or straight from their S3 bucket.
+use crate::transform::aws::cur::{CurLineItem, CurPurchaseOption, CurUsageBuilder, CurUsageReport};
+use anyhow::{anyhow, bail};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Float64Type, TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+    TimestampSecondType,
+};
+use arrow_array::{Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, TimeUnit};
+use aws_config::SdkConfig;
+use chrono::{DateTime, Utc};
+use log::info;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ProjectionMask;
+use parquet::file::reader::ChunkReader;
+use rust_decimal::Decimal;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+const PRODUCT_CODE: &str = "line_item_product_code";
+const LINE_ITEM_TYPE: &str = "line_item_line_item_type";
+const USAGE_TYPE: &str = "line_item_usage_type";
+const USAGE_AMOUNT: &str = "line_item_usage_amount";
+const UNBLENDED_COST: &str = "line_item_unblended_cost";
+const USAGE_START: &str = "line_item_usage_start_date";
+const USAGE_END: &str = "line_item_usage_end_date";
+const INSTANCE_TYPE: &str = "product_instance_type";
+const REGION: &str = "product_region_code";
+const PRICING_TERM: &str = "pricing_term";
+
+const COLUMNS: [&str; 10] = [
+    PRODUCT_CODE,
+    LINE_ITEM_TYPE,
+    USAGE_TYPE,
+    USAGE_AMOUNT,
+    UNBLENDED_COST,
+    USAGE_START,
+    USAGE_END,
+    INSTANCE_TYPE,
+    REGION,
+    PRICING_TERM,
+];
+
+/// Usage types of instances running, on any purchase option.
+fn is_instance_usage(usage_type: &str) -> bool {
+    ["BoxUsage", "DedicatedUsage", "SpotUsage"]
+        .iter()
+        .any(|marker| usage_type.contains(marker))
+}
+
+/// Sums the EC2 instance usage of the CUR parquet files at `path`, a file or a directory searched
+/// recursively, e.g. a local copy of the S3 prefix of an export.
+pub fn read_usage(path: &Path) -> anyhow::Result<CurUsageReport> {
+    let files = parquet_files(path)?;
+    if files.is_empty() {
+        bail!("No parquet files under {}", path.display());
+    }
+    let mut builder = CurUsageBuilder::new();
+    for file in &files {
+        info!("cur: Reading {}", file.display());
+        read_parquet(File::open(file)?, &mut builder)
+            .map_err(|e| anyhow!("Failed to read {}: {}", file.display(), e))?;
+    }
+    builder
+        .build()
+        .ok_or_else(|| anyhow!("No EC2 instance usage in {}", path.display()))
+}
+
+/// Bucket and key prefix of an `s3://bucket/prefix` URI.
+pub fn parse_s3_uri(uri: &str) -> Option<(&str, &str)> {
+    let (bucket, prefix) = match uri.strip_prefix("s3://")?.split_once('/') {
+        Some((bucket, prefix)) => (bucket, prefix),
+        None => (uri.strip_prefix("s3://")?, ""),
+    };
+    if bucket.is_empty() {
+        None
+    } else {
+        Some((bucket, prefix))
+    }
+}
+
+/// Like `read_usage`, for the CUR parquet files under `uri`, `s3://bucket/prefix`, e.g. the S3
+/// destination of the export. Read with the credentials and region of `sdk_config`, so the
+/// bucket is expected in that region.
+pub async fn read_s3_usage(sdk_config: &SdkConfig, uri: &str) -> anyhow::Result<CurUsageReport> {
+    let (bucket, prefix) = parse_s3_uri(uri).ok_or_else(|| anyhow!("Invalid S3 URI {}", uri))?;
+    let client = aws_sdk_s3::Client::new(sdk_config);
+    let mut stream = client
+        .list_objects_v2()
+        .bucket(bucket)
+        .prefix(prefix)
+        .into_paginator()
+        .send();
+    let mut keys = Vec::new();
+    while let Some(page) = stream.next().await {
+        keys.extend(
+            page?
+                .contents
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|object| object.key)
+                .filter(|key| key.ends_with(".parquet")),
+        );
+    }
+    if keys.is_empty() {
+        bail!("No parquet files under {}", uri);
+    }
+    keys.sort();
+
+    let mut builder = CurUsageBuilder::new();
+    for key in &keys {
+        info!("cur: Reading s3://{}/{}", bucket, key);
+        let object = client.get_object().bucket(bucket).key(key).send().await?;
+        let body = object.body.collect().await?.into_bytes();
+        read_parquet(body, &mut builder)
+            .map_err(|e| anyhow!("Failed to read s3://{}/{}: {}", bucket, key, e))?;
+    }
+    builder
+        .build()
+        .ok_or_else(|| anyhow!("No EC2 instance usage in {}", uri))
+}
+
+fn parquet_files(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let entry_path = entry?.path();
+        if entry_path.is_dir() {
+            files.extend(parquet_files(&entry_path)?);
+        } else if entry_path.extension().is_some_and(|ext| ext == "parquet") {
+            files.push(entry_path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn read_parquet<R: ChunkReader + 'static>(
+    input: R,
+    builder: &mut CurUsageBuilder,
+) -> anyhow::Result<()> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(input)?;
+    let roots = reader
+        .schema()
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| COLUMNS.contains(&field.name().as_str()))
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    let projection = ProjectionMask::roots(reader.parquet_schema(), roots);
+    for batch in reader.with_projection(projection).build()? {
+        read_batch(&batch?, builder)?;
+    }
+    Ok(())
+}
+
+fn strings<'a>(batch: &'a RecordBatch, name: &str) -> anyhow::Result<Option<&'a StringArray>> {
+    match batch.column_by_name(name) {
+        Some(column) => Ok(Some(
+            column
+                .as_string_opt::<i32>()
+                .ok_or_else(|| anyhow!("Expected strings in {}", name))?,
+        )),
+        None => Ok(None),
+    }
+}
+
+fn timestamps(batch: &RecordBatch, name: &str) -> anyhow::Result<Vec<Option<DateTime<Utc>>>> {
+    let column = batch
+        .column_by_name(name)
+        .ok_or_else(|| anyhow!("Missing column {}", name))?;
+    let (values, nanos_per_unit): (Vec<Option<i64>>, i64) = match column.data_type() {
+        DataType::Timestamp(TimeUnit::Second, _) => (
+            column
+                .as_primitive::<TimestampSecondType>()
+                .iter()
+                .collect(),
+            1_000_000_000,
+        ),
+        DataType::Timestamp(TimeUnit::Millisecond, _) => (
+            column
+                .as_primitive::<TimestampMillisecondType>()
+                .iter()
+                .collect(),
+            1_000_000,
+        ),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => (
+            column
+                .as_primitive::<TimestampMicrosecondType>()
+                .iter()
+                .collect(),
+            1_000,
+        ),
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => (
+            column
+                .as_primitive::<TimestampNanosecondType>()
+                .iter()
+                .collect(),
+            1,
+        ),
+        data_type => bail!("Expected timestamps in {}, got {}", name, data_type),
+    };
+    Ok(values
+        .into_iter()
+        .map(|value| value.map(|value| DateTime::from_timestamp_nanos(value * nanos_per_unit)))
+        .collect())
+}
+
+fn amounts(batch: &RecordBatch, name: &str) -> anyhow::Result<Vec<Decimal>> {
+    let column = batch
+        .column_by_name(name)
+        .ok_or_else(|| anyhow!("Missing column {}", name))?
+        .as_primitive_opt::<Float64Type>()
+        .ok_or_else(|| anyhow!("Expected doubles in {}", name))?;
+    Ok(column
+        .iter()
+        .map(|value| {
+            value
+                .and_then(|value| Decimal::try_from(value).ok())
+                .unwrap_or_default()
+        })
+        .collect())
+}
+
+fn value(column: &StringArray, row: usize) -> Option<&str> {
+    if column.is_null(row) {
+        None
+    } else {
+        Some(column.value(row))
+    }
+}
+
+fn read_batch(batch: &RecordBatch, builder: &mut CurUsageBuilder) -> anyhow::Result<()> {
+    let required =
+        |name: &str| strings(batch, name)?.ok_or_else(|| anyhow!("Missing column {}", name));
+    let product_codes = required(PRODUCT_CODE)?;
+    let line_item_types = required(LINE_ITEM_TYPE)?;
+    let usage_types = required(USAGE_TYPE)?;
+    let instance_types = required(INSTANCE_TYPE)?;
+    let regions = required(REGION)?;
+    let pricing_terms = strings(batch, PRICING_TERM)?;
+    let usage_amounts = amounts(batch, USAGE_AMOUNT)?;
+    let unblended_costs = amounts(batch, UNBLENDED_COST)?;
+    let starts = timestamps(batch, USAGE_START)?;
+    let ends = timestamps(batch, USAGE_END)?;
+
+    for row in 0..batch.num_rows() {
+        if value(product_codes, row) != Some("AmazonEC2")
+            || !value(usage_types, row).is_some_and(is_instance_usage)
+        {
+            continue;
+        }
+        let purchase_option = match value(line_item_types, row).and_then(|line_item_type| {
+            CurPurchaseOption::from_line_item(
+                line_item_type,
+                pricing_terms.and_then(|pricing_terms| value(pricing_terms, row)),
+            )
+        }) {
+            Some(purchase_option) => purchase_option,
+            None => continue,
+        };
+        let (start, end) = match (starts[row], ends[row]) {
+            (Some(start), Some(end)) => (start, end),
+            _ => continue,
+        };
+        builder.add(CurLineItem {
+            region: value(regions, row),
+            instance_type: value(instance_types, row),
+            purchase_option,
+            usage_hours: usage_amounts[row],
+            unblended_cost_usd: unblended_costs[row],
+            start,
+            end,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_s3_uri, read_usage};
+    use crate::transform::aws::cur::CurPurchaseOption;
+    use arrow_array::{
+        ArrayRef, Float64Array, RecordBatch, StringArray, TimestampMillisecondArray,
+    };
+    use parquet::arrow::ArrowWriter;
+    use rust_decimal::Decimal;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_read_usage() {
+        let strings = |values: [&str; 3]| Arc::new(StringArray::from(values.to_vec())) as ArrayRef;
+        let hour = 3_600_000;
+        let batch = RecordBatch::try_from_iter([
+            (
+                "line_item_product_code",
+                strings(["AmazonEC2", "AmazonEC2", "AmazonEC2"]),
+            ),
+            (
+                "line_item_line_item_type",
+                strings(["Usage", "SavingsPlanCoveredUsage", "Usage"]),
+            ),
+            (
+                "line_item_usage_type",
+                strings([
+                    "BoxUsage:m5.large",
+                    "BoxUsage:m5.large",
+                    "EBS:VolumeUsage.gp3",
+                ]),
+            ),
+            (
+                "product_instance_type",
+                strings(["m5.large", "m5.large", ""]),
+            ),
+            (
+                "product_region_code",
+                strings(["us-east-1", "us-east-1", "us-east-1"]),
+            ),
+            ("pricing_term", strings(["OnDemand", "", ""])),
+            (
+                "line_item_usage_amount",
+                Arc::new(Float64Array::from(vec![1.0, 1.0, 100.0])) as ArrayRef,
+            ),
+            (
+                "line_item_unblended_cost",
+                Arc::new(Float64Array::from(vec![0.096, 0.0, 8.0])) as ArrayRef,
+            ),
+            (
+                "line_item_usage_start_date",
+                Arc::new(TimestampMillisecondArray::from(vec![0, hour, 0])) as ArrayRef,
+            ),
+            (
+                "line_item_usage_end_date",
+                Arc::new(TimestampMillisecondArray::from(vec![
+                    hour,
+                    2 * hour,
+                    2 * hour,
+                ])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        let directory = std::env::temp_dir().join(format!("pekora-cur-{}", std::process::id()));
+        let partition = directory.join("BILLING_PERIOD=1970-01");
+        std::fs::create_dir_all(&partition).unwrap();
+        let file = std::fs::File::create(partition.join("part-0.parquet")).unwrap();
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let report = read_usage(&directory).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(report.usage.len(), 2);
+        assert_eq!(report.usage[0].purchase_option, CurPurchaseOption::OnDemand);
+        assert_eq!(report.usage[0].unblended_cost_usd, Decimal::new(96, 3));
+        assert_eq!(
+            report.usage[1].purchase_option,
+            CurPurchaseOption::SavingsPlan
+        );
+        assert_eq!((report.period_end - report.period_start).num_hours(), 2);
+        assert_eq!(report.ec2_usage("us-east-1")[0].instances, Decimal::ONE);
+    }
+
+    #[test]
+    fn test_parse_s3_uri() {
+        assert_eq!(
+            parse_s3_uri("s3://billing/cur/pekora/data"),
+            Some(("billing", "cur/pekora/data"))
+        );
+        assert_eq!(parse_s3_uri("s3://billing"), Some(("billing", "")));
+        assert_eq!(parse_s3_uri("s3:///cur"), None);
+        assert_eq!(parse_s3_uri("/tmp/cur"), None);
+    }
+}
@@ -0,0 +1,516 @@
+use crate::api::aws::price_bulk_types::{PriceBulkOffer, PriceBulkSavingsPlan};
+use crate::api::aws::types::{
+    Currency, DiscountedRate, LeaseContractLength, Price, SavingsPlanProductAttributes,
+    SavingsPlanTermRate, SavingsPlanType,
+};
+use crate::cache::CacheKey;
+use crate::dataset::columnar::{
+    int32_column, int32_field, string_column, string_field, timestamp_column, timestamp_field,
+    ColumnarRow,
+};
+use crate::dataset::{DatasetKind, LoadedRows};
+use crate::facade::{DataSource, Pekora};
+use crate::transform::aws::location::LocationFilter;
+use crate::transform::aws::on_demand::{self, OnDemandRate};
+use crate::transform::aws::savings_plan::{self, PivotedSavingsPlanTermRate};
+use arrow_array::{ArrayRef, Int32Array, RecordBatch, StringArray, TimestampMillisecondArray};
+use arrow_schema::Field;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Offer version the bulk API resolves to the latest publication.
+const CURRENT_VERSION: &str = "current";
+
+/// On-demand EC2 rates of a region, indexed by instance type.
+pub struct Ec2OnDemand;
+
+#[async_trait]
+impl DatasetKind for Ec2OnDemand {
+    const NAME: &'static str = "aws/ec2/on_demand";
+    type Key = String;
+    type Row = OnDemandRate;
+
+    async fn source_cache_key(pekora: &Pekora, region: &String) -> anyhow::Result<CacheKey> {
+        Ok(pekora
+            .clients()
+            .pricing_list()
+            .get_cache_key(&ec2_offer(region))
+            .await?)
+    }
+
+    async fn cached_source_key(
+        pekora: &Pekora,
+        region: &String,
+    ) -> anyhow::Result<Option<CacheKey>> {
+        Ok(pekora
+            .cacheable_builder()
+            .build(pekora.clients().pricing_list())
+            .latest_cache_key(&ec2_offer(region))
+            .await?)
+    }
+
+    async fn load(pekora: &Pekora, region: &String) -> anyhow::Result<LoadedRows<OnDemandRate>> {
+        let cached = pekora
+            .cacheable_builder()
+            .build(pekora.clients().pricing_list());
+        let loaded = cached.load(&ec2_offer(region)).await?;
+        let response = loaded.result;
+        Ok(LoadedRows {
+            source: DataSource::loaded(loaded.cache_hit),
+            version: response.version.clone(),
+            publication_date: response.publication_date,
+            rows: on_demand::pivot(response),
+            cache_key: loaded.cache_key,
+            cache_hit: loaded.cache_hit,
+        })
+    }
+
+    fn index_key(row: &OnDemandRate) -> Option<String> {
+        row.attributes.get("instanceType").cloned()
+    }
+
+    fn sku(row: &OnDemandRate) -> &str {
+        &row.sku
+    }
+}
+
+/// Rates of the `AWSComputeSavingsPlan` offer of a region, indexed by discounted usage type. The
+/// offer covers both Compute and EC2 Instance Savings Plans.
+pub struct ComputeSavingsPlan;
+
+#[async_trait]
+impl DatasetKind for ComputeSavingsPlan {
+    const NAME: &'static str = "aws/savings_plan/compute";
+    type Key = String;
+    type Row = PivotedSavingsPlanTermRate;
+
+    async fn source_cache_key(pekora: &Pekora, region: &String) -> anyhow::Result<CacheKey> {
+        Ok(pekora
+            .clients()
+            .savings_plan_list()
+            .get_cache_key(&compute_savings_plan(region))
+            .await?)
+    }
+
+    async fn cached_source_key(
+        pekora: &Pekora,
+        region: &String,
+    ) -> anyhow::Result<Option<CacheKey>> {
+        Ok(pekora
+            .cacheable_builder()
+            .build(pekora.clients().savings_plan_list())
+            .latest_cache_key(&compute_savings_plan(region))
+            .await?)
+    }
+
+    async fn load(
+        pekora: &Pekora,
+        region: &String,
+    ) -> anyhow::Result<LoadedRows<PivotedSavingsPlanTermRate>> {
+        let cached = pekora
+            .cacheable_builder()
+            .build(pekora.clients().savings_plan_list());
+        let loaded = cached.load(&compute_savings_plan(region)).await?;
+        let response = loaded.result;
+        let version = response.version.clone();
+        let publication_date = response.publication_date;
+        Ok(LoadedRows {
+            rows: savings_plan::pivot(response, &LocationFilter::default())?,
+            source: DataSource::loaded(loaded.cache_hit),
+            version,
+            publication_date,
+            cache_key: loaded.cache_key,
+            cache_hit: loaded.cache_hit,
+        })
+    }
+
+    fn index_key(row: &PivotedSavingsPlanTermRate) -> Option<String> {
+        Some(row.term_rate.discounted_usage_type.clone())
+    }
+
+    fn sku(row: &PivotedSavingsPlanTermRate) -> &str {
+        &row.savings_plan_sku
+    }
+}
+
+/// EC2 Instance Savings Plan rates of a region, indexed by instance family.
+pub struct Ec2InstanceSavingsPlan;
+
+#[async_trait]
+impl DatasetKind for Ec2InstanceSavingsPlan {
+    const NAME: &'static str = "aws/savings_plan/ec2_instance";
+    type Key = String;
+    type Row = PivotedSavingsPlanTermRate;
+
+    async fn source_cache_key(pekora: &Pekora, region: &String) -> anyhow::Result<CacheKey> {
+        ComputeSavingsPlan::source_cache_key(pekora, region).await
+    }
+
+    async fn cached_source_key(
+        pekora: &Pekora,
+        region: &String,
+    ) -> anyhow::Result<Option<CacheKey>> {
+        ComputeSavingsPlan::cached_source_key(pekora, region).await
+    }
+
+    async fn load(
+        pekora: &Pekora,
+        region: &String,
+    ) -> anyhow::Result<LoadedRows<PivotedSavingsPlanTermRate>> {
+        let mut loaded = ComputeSavingsPlan::load(pekora, region).await?;
+        loaded
+            .rows
+            .retain(|row| row.plan_type() == &SavingsPlanType::Ec2Instance);
+        Ok(loaded)
+    }
+
+    fn index_key(row: &PivotedSavingsPlanTermRate) -> Option<String> {
+        row.savings_plan_attributes.instance_type.clone()
+    }
+
+    fn sku(row: &PivotedSavingsPlanTermRate) -> &str {
+        &row.savings_plan_sku
+    }
+}
+
+fn ec2_offer(region: &str) -> PriceBulkOffer {
+    PriceBulkOffer {
+        service_code: "AmazonEC2".to_string(),
+        offer_version: CURRENT_VERSION.to_string(),
+        region: region.to_string(),
+        filename: "index.json".to_string(),
+    }
+}
+
+fn compute_savings_plan(region: &str) -> PriceBulkSavingsPlan {
+    PriceBulkSavingsPlan {
+        service_code: "AWSComputeSavingsPlan".to_string(),
+        offer_version: CURRENT_VERSION.to_string(),
+        region: region.to_string(),
+        filename: "index.json".to_string(),
+    }
+}
+
+fn strings<'a>(values: impl Iterator<Item = &'a str>) -> ArrayRef {
+    Arc::new(StringArray::from_iter_values(values))
+}
+
+fn timestamps(values: impl Iterator<Item = i64>) -> ArrayRef {
+    Arc::new(TimestampMillisecondArray::from_iter_values(values).with_timezone("UTC"))
+}
+
+/// Nested maps are stored as JSON strings.
+fn json_strings<T: serde::Serialize>(values: impl Iterator<Item = T>) -> anyhow::Result<ArrayRef> {
+    let values = values
+        .map(|value| serde_json::to_string(&value))
+        .collect::<serde_json::Result<Vec<_>>>()?;
+    Ok(strings(values.iter().map(String::as_str)))
+}
+
+impl ColumnarRow for OnDemandRate {
+    fn fields() -> Vec<Field> {
+        vec![
+            string_field("sku"),
+            string_field("product_family"),
+            string_field("attributes"),
+            string_field("offer_term_code"),
+            timestamp_field("effective_date"),
+            string_field("rate_code"),
+            string_field("description"),
+            string_field("unit"),
+            string_field("price_per_unit"),
+        ]
+    }
+
+    fn to_columns(rows: &[Self]) -> anyhow::Result<Vec<ArrayRef>> {
+        Ok(vec![
+            strings(rows.iter().map(|row| row.sku.as_str())),
+            strings(rows.iter().map(|row| row.product_family.as_str())),
+            json_strings(rows.iter().map(|row| row.attributes.as_ref()))?,
+            strings(rows.iter().map(|row| row.offer_term_code.as_str())),
+            timestamps(rows.iter().map(|row| row.effective_date.timestamp_millis())),
+            strings(rows.iter().map(|row| row.rate_code.as_str())),
+            strings(rows.iter().map(|row| row.description.as_str())),
+            strings(rows.iter().map(|row| row.unit.as_str())),
+            json_strings(rows.iter().map(|row| &row.price_per_unit))?,
+        ])
+    }
+
+    fn from_batch(batch: &RecordBatch) -> anyhow::Result<Vec<Self>> {
+        let skus = string_column(batch, "sku")?;
+        let product_families = string_column(batch, "product_family")?;
+        let attributes = string_column(batch, "attributes")?;
+        let offer_term_codes = string_column(batch, "offer_term_code")?;
+        let effective_dates = timestamp_column(batch, "effective_date")?;
+        let rate_codes = string_column(batch, "rate_code")?;
+        let descriptions = string_column(batch, "description")?;
+        let units = string_column(batch, "unit")?;
+        let prices = string_column(batch, "price_per_unit")?;
+
+        // Rows of the same product share their attributes, as they do after a pivot
+        let mut attribute_lookup: HashMap<String, Arc<HashMap<String, String>>> = HashMap::new();
+        let mut rows = Vec::with_capacity(batch.num_rows());
+        for i in 0..batch.num_rows() {
+            let attributes = match attribute_lookup.get(&skus[i]) {
+                Some(attributes) => attributes.clone(),
+                None => {
+                    let attributes = Arc::new(serde_json::from_str(&attributes[i])?);
+                    attribute_lookup.insert(skus[i].clone(), Arc::clone(&attributes));
+                    attributes
+                }
+            };
+            let price_per_unit: HashMap<String, Price> = serde_json::from_str(&prices[i])?;
+            rows.push(OnDemandRate {
+                sku: skus[i].clone(),
+                product_family: product_families[i].clone(),
+                attributes,
+                offer_term_code: offer_term_codes[i].clone(),
+                effective_date: effective_dates[i],
+                rate_code: rate_codes[i].clone(),
+                description: descriptions[i].clone(),
+                unit: units[i].clone(),
+                price_per_unit,
+            });
+        }
+        Ok(rows)
+    }
+}
+
+impl ColumnarRow for PivotedSavingsPlanTermRate {
+    fn fields() -> Vec<Field> {
+        vec![
+            string_field("savings_plan_sku"),
+            timestamp_field("savings_plan_effective_date"),
+            string_field("savings_plan_attributes"),
+            int32_field("lease_contract_duration"),
+            string_field("lease_contract_unit"),
+            string_field("discounted_sku"),
+            string_field("discounted_usage_type"),
+            string_field("discounted_operation"),
+            string_field("discounted_service_code"),
+            string_field("rate_code"),
+            string_field("unit"),
+            string_field("price"),
+            string_field("currency"),
+        ]
+    }
+
+    fn to_columns(rows: &[Self]) -> anyhow::Result<Vec<ArrayRef>> {
+        Ok(vec![
+            strings(rows.iter().map(|row| row.savings_plan_sku.as_str())),
+            timestamps(
+                rows.iter()
+                    .map(|row| row.savings_plan_effective_date.timestamp_millis()),
+            ),
+            json_strings(rows.iter().map(|row| row.savings_plan_attributes.as_ref()))?,
+            Arc::new(Int32Array::from_iter_values(
+                rows.iter().map(|row| row.lease_contract_length.duration),
+            )),
+            strings(
+                rows.iter()
+                    .map(|row| row.lease_contract_length.unit.as_str()),
+            ),
+            strings(rows.iter().map(|row| row.term_rate.discounted_sku.as_str())),
+            strings(
+                rows.iter()
+                    .map(|row| row.term_rate.discounted_usage_type.as_str()),
+            ),
+            strings(
+                rows.iter()
+                    .map(|row| row.term_rate.discounted_operation.as_str()),
+            ),
+            strings(
+                rows.iter()
+                    .map(|row| row.term_rate.discounted_service_code.as_str()),
+            ),
+            strings(rows.iter().map(|row| row.term_rate.rate_code.as_str())),
+            strings(rows.iter().map(|row| row.term_rate.unit.as_str())),
+            strings(
+                rows.iter()
+                    .map(|row| row.term_rate.discounted_rate.price.raw()),
+            ),
+            strings(
+                rows.iter()
+                    .map(|row| row.term_rate.discounted_rate.currency.as_str()),
+            ),
+        ])
+    }
+
+    fn from_batch(batch: &RecordBatch) -> anyhow::Result<Vec<Self>> {
+        let skus = string_column(batch, "savings_plan_sku")?;
+        let effective_dates = timestamp_column(batch, "savings_plan_effective_date")?;
+        let attributes = string_column(batch, "savings_plan_attributes")?;
+        let durations = int32_column(batch, "lease_contract_duration")?;
+        let duration_units = string_column(batch, "lease_contract_unit")?;
+        let discounted_skus = string_column(batch, "discounted_sku")?;
+        let usage_types = string_column(batch, "discounted_usage_type")?;
+        let operations = string_column(batch, "discounted_operation")?;
+        let service_codes = string_column(batch, "discounted_service_code")?;
+        let rate_codes = string_column(batch, "rate_code")?;
+        let units = string_column(batch, "unit")?;
+        let prices = string_column(batch, "price")?;
+        let currencies = string_column(batch, "currency")?;
+
+        let mut attribute_lookup: HashMap<String, Arc<SavingsPlanProductAttributes>> =
+            HashMap::new();
+        let mut rows = Vec::with_capacity(batch.num_rows());
+        for i in 0..batch.num_rows() {
+            let savings_plan_attributes = match attribute_lookup.get(&skus[i]) {
+                Some(attributes) => attributes.clone(),
+                None => {
+                    let attributes = Arc::new(serde_json::from_str(&attributes[i])?);
+                    attribute_lookup.insert(skus[i].clone(), Arc::clone(&attributes));
+                    attributes
+                }
+            };
+            let currency: Currency =
+                serde_json::from_value(serde_json::Value::String(currencies[i].clone()))?;
+            rows.push(PivotedSavingsPlanTermRate {
+                savings_plan_sku: skus[i].clone(),
+                savings_plan_effective_date: effective_dates[i],
+                savings_plan_attributes,
+                lease_contract_length: LeaseContractLength {
+                    duration: durations[i],
+                    unit: duration_units[i].clone(),
+                },
+                term_rate: SavingsPlanTermRate {
+                    discounted_sku: discounted_skus[i].clone(),
+                    discounted_usage_type: usage_types[i].clone(),
+                    discounted_operation: operations[i].clone(),
+                    discounted_service_code: service_codes[i].clone(),
+                    rate_code: rate_codes[i].clone(),
+                    unit: units[i].clone(),
+                    discounted_rate: DiscountedRate {
+                        price: Price::new(prices[i].clone()),
+                        currency,
+                    },
+                },
+            });
+        }
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Ec2OnDemand;
+    use crate::api::aws::price_bulk_builder::PriceBulkClientBuilder;
+    use crate::cache::Namespace;
+    use crate::facade::{DataSource, Pekora};
+    use crate::util::hash::{hash_fields, HashAlgorithm};
+    use crate::util::testing::serve;
+    use axum::http::{header, HeaderMap, StatusCode};
+    use axum::response::IntoResponse;
+    use axum::routing::get;
+    use axum::Router;
+    use std::path::Path;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    const PRICING_LIST_BODY: &str = r#"{
+        "formatVersion": "v1.0",
+        "publicationDate": "2024-03-12T15:37:24Z",
+        "version": "20240312153724",
+        "products": {
+            "SKU1": {"productFamily": "Compute Instance", "sku": "SKU1",
+                     "attributes": {"instanceType": "m5.large"}}
+        },
+        "terms": {
+            "OnDemand": {
+                "SKU1": {
+                    "SKU1.JRTCKXETXF": {
+                        "offerTermCode": "JRTCKXETXF",
+                        "sku": "SKU1",
+                        "effectiveDate": "2024-03-01T00:00:00Z",
+                        "priceDimensions": {
+                            "SKU1.JRTCKXETXF.6YS6EN2CT7": {
+                                "rateCode": "SKU1.JRTCKXETXF.6YS6EN2CT7",
+                                "description": "$0.096 per On Demand Linux m5.large Instance Hour",
+                                "unit": "Hrs",
+                                "pricePerUnit": {"USD": "0.0960000000"}
+                            }
+                        },
+                        "termAttributes": {}
+                    }
+                }
+            },
+            "Reserved": {}
+        }
+    }"#;
+
+    #[tokio::test]
+    async fn test_ec2_on_demand_dataset() {
+        let base_url = serve(Router::new().route(
+            "/offers/v1.0/aws/AmazonEC2/current/us-east-1/index.json",
+            get(|headers: HeaderMap| async move {
+                if headers.get(header::IF_NONE_MATCH).is_some() {
+                    return StatusCode::NOT_MODIFIED.into_response();
+                }
+                ([(header::ETAG, "\"v1\"")], PRICING_LIST_BODY).into_response()
+            }),
+        ))
+        .await;
+        let cache_directory = format!(
+            "test_cache/dataset-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_micros()
+        );
+        let clients = PriceBulkClientBuilder::new()
+            .base_url(base_url)
+            .download_directory(cache_directory.clone())
+            .build()
+            .unwrap();
+        let pekora = Pekora::new(clients, Some(cache_directory.clone()), None);
+
+        let mut dataset = pekora
+            .dataset::<Ec2OnDemand>("us-east-1".to_string())
+            .await
+            .unwrap();
+        assert_eq!(dataset.rows().len(), 1);
+        assert_eq!(dataset.iter().count(), 1);
+        assert_eq!(dataset.index()["m5.large"][0].unit, "Hrs");
+        assert_eq!(dataset.metadata().version, "20240312153724");
+        assert_eq!(dataset.metadata().row_count, 1);
+
+        assert!(!dataset.metadata().cache_hit);
+        assert_eq!(dataset.metadata().source, DataSource::Network);
+        let derived_file = format!(
+            "derived/aws/ec2/on_demand/us-east-1_{}.arrow",
+            hash_fields(HashAlgorithm::Xxh3, &["v1"])
+        );
+        let derived_path = Path::new(&cache_directory).join(&derived_file);
+        assert!(derived_path.exists());
+
+        // Served from the derived file without pivoting again
+        assert!(!dataset.refresh().await.unwrap());
+        assert!(dataset.metadata().cache_hit);
+        assert_eq!(dataset.metadata().source, DataSource::Derived);
+        assert_eq!(dataset.rows().len(), 1);
+        let row = &dataset.rows()[0];
+        assert_eq!(row.attributes["instanceType"], "m5.large");
+        assert_eq!(row.price_per_unit["USD"].raw(), "0.0960000000");
+        assert_eq!(row.effective_date.to_rfc3339(), "2024-03-01T00:00:00+00:00");
+        assert_eq!(dataset.metadata().version, "20240312153724");
+
+        std::fs::remove_file(&derived_path).unwrap();
+        let plan = pekora.plan::<Ec2OnDemand>(&"us-east-1".to_string()).await;
+        assert_eq!(plan.source, DataSource::RawCache);
+        dataset.refresh().await.unwrap();
+        assert_eq!(dataset.metadata().source, DataSource::RawCache);
+        assert!(derived_path.exists());
+
+        // Namespaces share the raw file but not derived rows
+        let team = pekora.with_namespace(Namespace::new("team-a").unwrap());
+        let plan = team.plan::<Ec2OnDemand>(&"us-east-1".to_string()).await;
+        assert_eq!(plan.source, DataSource::RawCache);
+        team.dataset::<Ec2OnDemand>("us-east-1".to_string())
+            .await
+            .unwrap();
+        assert!(Path::new(&cache_directory)
+            .join("namespaces/team-a")
+            .join(&derived_file)
+            .exists());
+    }
+}
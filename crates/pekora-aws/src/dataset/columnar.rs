@@ -0,0 +1,220 @@
+//! Arrow IPC files of pivoted rows, so warm loads skip both the download and the pivot
+use crate::cache::Namespace;
+use crate::pipeline::write_atomically;
+use crate::util::hash::{hash_fields, HashAlgorithm};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Int32Type, TimestampMillisecondType};
+use arrow_array::{Array, ArrayRef, RecordBatch};
+use arrow_ipc::reader::FileReader;
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const VERSION_METADATA: &str = "pekora.version";
+const PUBLICATION_DATE_METADATA: &str = "pekora.publication_date";
+
+/// Rows that can be stored as Arrow columns.
+pub trait ColumnarRow: Sized {
+    fn fields() -> Vec<Field>;
+    /// One array per field of `fields`, in the same order.
+    fn to_columns(rows: &[Self]) -> anyhow::Result<Vec<ArrayRef>>;
+    fn from_batch(batch: &RecordBatch) -> anyhow::Result<Vec<Self>>;
+}
+
+/// Rows read back from a derived file.
+pub struct DerivedRows<R> {
+    pub rows: Vec<R>,
+    pub version: String,
+    pub publication_date: DateTime<Utc>,
+}
+
+/// Derived files of one dataset kind, at `<cache directory>/derived/<kind>/<key>_<hash>.arrow`,
+/// or under `<cache directory>/namespaces/<namespace>/` for a namespace. The hash is a hash of the
+/// content hash of the source offer, so a new offer version invalidates the derived file without
+/// any bookkeeping.
+pub struct DerivedCache {
+    directory: PathBuf,
+    hash_algorithm: HashAlgorithm,
+}
+
+impl DerivedCache {
+    pub fn new(
+        cache_directory: &str,
+        namespace: Option<&Namespace>,
+        kind: &str,
+        hash_algorithm: HashAlgorithm,
+    ) -> Self {
+        let mut directory = PathBuf::from(cache_directory);
+        if let Some(namespace) = namespace {
+            directory = directory.join("namespaces").join(namespace.as_str());
+        }
+        Self {
+            directory: directory.join("derived").join(kind),
+            hash_algorithm,
+        }
+    }
+
+    fn filename_hash(&self, content_hash: &str) -> String {
+        hash_fields(self.hash_algorithm, &[content_hash])
+    }
+
+    fn path(&self, key: &str, content_hash: &str) -> PathBuf {
+        self.directory.join(format!(
+            "{}_{}.arrow",
+            key,
+            self.filename_hash(content_hash)
+        ))
+    }
+
+    pub fn contains(&self, key: &str, content_hash: &str) -> bool {
+        self.path(key, content_hash).is_file()
+    }
+
+    /// Reads the rows derived from `content_hash`. Unreadable files are treated as missing.
+    pub fn read<R: ColumnarRow>(&self, key: &str, content_hash: &str) -> Option<DerivedRows<R>> {
+        let path = self.path(key, content_hash);
+        let file = File::open(&path).ok()?;
+        match read_rows(BufReader::new(file)) {
+            Ok(rows) => {
+                debug!("Derived cache hit: {}", path.display());
+                Some(rows)
+            }
+            Err(e) => {
+                warn!("Ignoring unreadable derived file {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Writes `rows`, then removes files derived from older offers of `key`.
+    pub fn write<R: ColumnarRow>(
+        &self,
+        key: &str,
+        content_hash: &str,
+        version: &str,
+        publication_date: DateTime<Utc>,
+        rows: &[R],
+    ) -> anyhow::Result<()> {
+        let schema = Arc::new(Schema::new_with_metadata(
+            R::fields(),
+            HashMap::from([
+                (VERSION_METADATA.to_string(), version.to_string()),
+                (
+                    PUBLICATION_DATE_METADATA.to_string(),
+                    publication_date.to_rfc3339(),
+                ),
+            ]),
+        ));
+        let batch = RecordBatch::try_new(schema.clone(), R::to_columns(rows)?)?;
+        let path = self.path(key, content_hash);
+        write_atomically(&path, |writer| {
+            let mut writer = FileWriter::try_new(writer, &schema)?;
+            writer.write(&batch)?;
+            writer.finish()?;
+            Ok(())
+        })?;
+
+        let prefix = format!("{}_", key);
+        let current = self.filename_hash(content_hash);
+        for entry in std::fs::read_dir(&self.directory)?.flatten() {
+            let filename = entry.file_name().to_string_lossy().to_string();
+            let stale = filename
+                .strip_prefix(&prefix)
+                .and_then(|rest| rest.strip_suffix(".arrow"))
+                .is_some_and(|hash| hash != current && !hash.contains('_'));
+            if stale {
+                debug!("Removing stale derived file {}", filename);
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_rows<R: ColumnarRow>(reader: BufReader<File>) -> anyhow::Result<DerivedRows<R>> {
+    let reader = FileReader::try_new(reader, None)?;
+    let metadata = reader.schema().metadata().clone();
+    let version = match metadata.get(VERSION_METADATA) {
+        Some(version) => version.clone(),
+        None => anyhow::bail!("Missing {} metadata", VERSION_METADATA),
+    };
+    let publication_date = match metadata.get(PUBLICATION_DATE_METADATA) {
+        Some(date) => DateTime::parse_from_rfc3339(date)?.with_timezone(&Utc),
+        None => anyhow::bail!("Missing {} metadata", PUBLICATION_DATE_METADATA),
+    };
+    let mut rows = Vec::new();
+    for batch in reader {
+        rows.extend(R::from_batch(&batch?)?);
+    }
+    Ok(DerivedRows {
+        rows,
+        version,
+        publication_date,
+    })
+}
+
+// ======= Column helpers for `ColumnarRow` implementations ==========
+
+pub(crate) fn string_field(name: &str) -> Field {
+    Field::new(name, DataType::Utf8, false)
+}
+
+pub(crate) fn timestamp_field(name: &str) -> Field {
+    Field::new(
+        name,
+        DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+        false,
+    )
+}
+
+pub(crate) fn int32_field(name: &str) -> Field {
+    Field::new(name, DataType::Int32, false)
+}
+
+fn column<'a>(batch: &'a RecordBatch, name: &str) -> anyhow::Result<&'a ArrayRef> {
+    match batch.column_by_name(name) {
+        Some(column) => Ok(column),
+        None => anyhow::bail!("Missing column {}", name),
+    }
+}
+
+pub(crate) fn string_column(batch: &RecordBatch, name: &str) -> anyhow::Result<Vec<String>> {
+    match column(batch, name)?.as_string_opt::<i32>() {
+        Some(array) => Ok(array
+            .iter()
+            .map(|v| v.unwrap_or_default().to_string())
+            .collect()),
+        None => anyhow::bail!("Column {} is not a string column", name),
+    }
+}
+
+pub(crate) fn timestamp_column(
+    batch: &RecordBatch,
+    name: &str,
+) -> anyhow::Result<Vec<DateTime<Utc>>> {
+    let array = match column(batch, name)?.as_primitive_opt::<TimestampMillisecondType>() {
+        Some(array) => array,
+        None => anyhow::bail!("Column {} is not a timestamp column", name),
+    };
+    array
+        .values()
+        .iter()
+        .map(|millis| match DateTime::from_timestamp_millis(*millis) {
+            Some(date) => Ok(date),
+            None => anyhow::bail!("Invalid timestamp {} in column {}", millis, name),
+        })
+        .collect()
+}
+
+pub(crate) fn int32_column(batch: &RecordBatch, name: &str) -> anyhow::Result<Vec<i32>> {
+    match column(batch, name)?.as_primitive_opt::<Int32Type>() {
+        Some(array) if array.null_count() == 0 => Ok(array.values().to_vec()),
+        _ => anyhow::bail!("Column {} is not a non-null int32 column", name),
+    }
+}
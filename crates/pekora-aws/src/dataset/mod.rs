@@ -0,0 +1,235 @@
+//! Typed handles over loaded datasets, independent of where the rows came from
+
+mod aws;
+pub mod columnar;
+
+pub use aws::{ComputeSavingsPlan, Ec2InstanceSavingsPlan, Ec2OnDemand};
+
+use crate::cache::{CacheKey, HashSource};
+use crate::dataset::columnar::{ColumnarRow, DerivedCache};
+use crate::facade::{DataSource, Pekora};
+use crate::history::OfferMark;
+use crate::util::hash::{hash_fields, HashAlgorithm};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// A kind of dataset, describing how its rows are loaded and indexed.
+#[async_trait]
+pub trait DatasetKind: Sized + Send + Sync + 'static {
+    const NAME: &'static str;
+    type Key: Clone + Display + Send + Sync;
+    type Row: ColumnarRow + Send + Sync;
+
+    /// Cache key of the source file, checked without downloading it.
+    async fn source_cache_key(pekora: &Pekora, key: &Self::Key) -> anyhow::Result<CacheKey>;
+
+    /// Cache key of the newest source file in the local cache, without contacting upstream.
+    async fn cached_source_key(
+        pekora: &Pekora,
+        key: &Self::Key,
+    ) -> anyhow::Result<Option<CacheKey>>;
+
+    async fn load(pekora: &Pekora, key: &Self::Key) -> anyhow::Result<LoadedRows<Self::Row>>;
+
+    /// Key rows are grouped by in `Dataset::index`.
+    fn index_key(row: &Self::Row) -> Option<String>;
+
+    /// SKU a row belongs to, tracked across offer versions by the history store.
+    fn sku(row: &Self::Row) -> &str;
+}
+
+/// Rows of a dataset along with where they were loaded from.
+pub struct LoadedRows<R> {
+    pub rows: Vec<R>,
+    pub source: DataSource,
+    pub version: String,
+    pub publication_date: DateTime<Utc>,
+    pub cache_key: CacheKey,
+    pub cache_hit: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DatasetMetadata {
+    pub kind: &'static str,
+    pub key: String,
+    pub version: String,
+    pub publication_date: DateTime<Utc>,
+    pub cache_key: CacheKey,
+    /// Whether the cache key was derived from an ETag or `Last-Modified`.
+    pub hash_source: Option<HashSource>,
+    pub cache_hit: bool,
+    /// Where the planner loaded the rows from.
+    pub source: DataSource,
+    pub loaded_at: DateTime<Utc>,
+    pub row_count: usize,
+    /// Hash of the kind, key, offer version and content hash, equal for datasets built from the
+    /// same source file
+    pub fingerprint: String,
+}
+
+pub struct Dataset<T: DatasetKind> {
+    pekora: Pekora,
+    key: T::Key,
+    rows: Vec<T::Row>,
+    metadata: DatasetMetadata,
+}
+
+impl<T: DatasetKind> Dataset<T> {
+    pub async fn load(pekora: Pekora, key: T::Key) -> anyhow::Result<Self> {
+        let loaded = load_rows::<T>(&pekora, &key).await?;
+        if pekora.records_history() {
+            record_history::<T>(&pekora, &key, &loaded);
+        }
+        let metadata = Self::build_metadata(&key, &loaded, pekora.hash_algorithm());
+        Ok(Self {
+            pekora,
+            key,
+            rows: loaded.rows,
+            metadata,
+        })
+    }
+
+    pub fn rows(&self) -> &[T::Row] {
+        &self.rows
+    }
+
+    /// Rows in load order, all of which are held in memory.
+    pub fn iter(&self) -> std::slice::Iter<'_, T::Row> {
+        self.rows.iter()
+    }
+
+    /// Groups rows by `DatasetKind::index_key`. Rows without a key are left out.
+    pub fn index(&self) -> HashMap<String, Vec<&T::Row>> {
+        let mut index: HashMap<String, Vec<&T::Row>> = HashMap::new();
+        for row in &self.rows {
+            if let Some(key) = T::index_key(row) {
+                index.entry(key).or_default().push(row);
+            }
+        }
+        index
+    }
+
+    pub fn metadata(&self) -> &DatasetMetadata {
+        &self.metadata
+    }
+
+    /// Reloads the dataset, returning whether its content changed.
+    pub async fn refresh(&mut self) -> anyhow::Result<bool> {
+        let loaded = load_rows::<T>(&self.pekora, &self.key).await?;
+        if self.pekora.records_history() {
+            record_history::<T>(&self.pekora, &self.key, &loaded);
+        }
+        let changed = loaded.cache_key != self.metadata.cache_key;
+        self.metadata = Self::build_metadata(&self.key, &loaded, self.pekora.hash_algorithm());
+        self.rows = loaded.rows;
+        Ok(changed)
+    }
+
+    fn build_metadata(
+        key: &T::Key,
+        loaded: &LoadedRows<T::Row>,
+        hash_algorithm: HashAlgorithm,
+    ) -> DatasetMetadata {
+        let key = key.to_string();
+        let fingerprint = hash_fields(
+            hash_algorithm,
+            &[
+                T::NAME,
+                key.as_str(),
+                loaded.version.as_str(),
+                loaded.cache_key.content_hash.as_deref().unwrap_or_default(),
+            ],
+        );
+        DatasetMetadata {
+            kind: T::NAME,
+            key,
+            version: loaded.version.clone(),
+            publication_date: loaded.publication_date,
+            cache_key: loaded.cache_key.clone(),
+            hash_source: loaded.cache_key.hash_source(),
+            cache_hit: loaded.cache_hit,
+            source: loaded.source,
+            loaded_at: Utc::now(),
+            row_count: loaded.rows.len(),
+            fingerprint,
+        }
+    }
+}
+
+/// Loads rows from the source chosen by `Pekora::plan`. Rows pivoted from the raw file are
+/// written to the derived cache for next time.
+async fn load_rows<T: DatasetKind>(
+    pekora: &Pekora,
+    key: &T::Key,
+) -> anyhow::Result<LoadedRows<T::Row>> {
+    let plan = pekora.plan::<T>(key).await;
+    let derived = DerivedCache::new(
+        pekora.cache_directory(),
+        pekora.namespace(),
+        T::NAME,
+        pekora.hash_algorithm(),
+    );
+    let key_name = key.to_string();
+    if let (DataSource::Derived, Some(source_key)) = (plan.source, plan.source_key) {
+        let content_hash = source_key.content_hash.as_deref().unwrap_or_default();
+        match derived.read::<T::Row>(&key_name, content_hash) {
+            Some(hit) => {
+                return Ok(LoadedRows {
+                    rows: hit.rows,
+                    source: DataSource::Derived,
+                    version: hit.version,
+                    publication_date: hit.publication_date,
+                    cache_key: source_key,
+                    cache_hit: true,
+                })
+            }
+            None => info!(
+                "Derived file of {} {} unusable, loading the source",
+                T::NAME,
+                key_name
+            ),
+        }
+    }
+
+    let loaded = T::load(pekora, key).await?;
+    if let Some(content_hash) = &loaded.cache_key.content_hash {
+        if let Err(e) = derived.write(
+            &key_name,
+            content_hash,
+            &loaded.version,
+            loaded.publication_date,
+            &loaded.rows,
+        ) {
+            warn!(
+                "Failed to write derived cache of {} {}: {}",
+                T::NAME,
+                key_name,
+                e
+            );
+        }
+    }
+    Ok(loaded)
+}
+
+/// Records the SKUs of `loaded` in the history store, leaving tombstones for removed SKUs.
+fn record_history<T: DatasetKind>(pekora: &Pekora, key: &T::Key, loaded: &LoadedRows<T::Row>) {
+    let store = pekora.history::<T>(key);
+    let offer = OfferMark {
+        version: loaded.version.clone(),
+        publication_date: loaded.publication_date,
+    };
+    match store.record(&offer, loaded.rows.iter().map(T::sku)) {
+        Ok(update) if !update.removed.is_empty() => info!(
+            "{} SKUs of {} {} removed in offer {}",
+            update.removed.len(),
+            T::NAME,
+            key,
+            offer.version
+        ),
+        Ok(_) => {}
+        Err(e) => warn!("Failed to record SKU history of {} {}: {}", T::NAME, key, e),
+    }
+}
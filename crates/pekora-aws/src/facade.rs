@@ -0,0 +1,459 @@
+use crate::api::aws::price_bulk_builder::PriceBulkClients;
+use crate::api::aws::price_bulk_types::{
+    CloudWatchPricingListResponse, DataTransferPricingListResponse, DocDbPricingListResponse,
+    EbsPricingListResponse, EksPricingListResponse, ElastiCachePricingListResponse,
+    FargatePricingListResponse, LambdaPricingListResponse, MemoryDbPricingListResponse,
+    NetworkPricingListResponse, OpenSearchPricingListResponse, PriceBulkOffer, PricingListResponse,
+    RdsPricingListResponse, RedshiftPricingListResponse, SageMakerPricingListResponse,
+    TypedPricingListResponse,
+};
+use crate::api::aws::schema::SchemaRegistry;
+use crate::api::aws::spot_advisor::SpotAdvisorResponse;
+use crate::api::aws::ssm::SsmClient;
+use crate::cache::{CacheKey, FileBackedCacheableBuilder, Namespace, DEFAULT_CACHE_DIRECTORY};
+use crate::dataset::columnar::DerivedCache;
+use crate::dataset::{Dataset, DatasetKind};
+use crate::history::HistoryStore;
+use crate::metrics::{self, MetricsRegistry};
+use crate::transform::aws::location::RegionNames;
+use crate::transform::aws::overlay::DiscountOverlay;
+use crate::util::hash::HashAlgorithm;
+use aws_config::SdkConfig;
+use log::{info, warn};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Debug;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+/// Where dataset rows are loaded from, cheapest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum DataSource {
+    /// Pivoted rows in the derived columnar cache
+    Derived,
+    /// Raw offer file in the cache, revalidated upstream and pivoted again
+    RawCache,
+    /// Offer file downloaded from upstream
+    Network,
+}
+
+impl DataSource {
+    /// Source of rows pivoted from a raw offer file.
+    pub fn loaded(cache_hit: bool) -> Self {
+        if cache_hit {
+            DataSource::RawCache
+        } else {
+            DataSource::Network
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryPlan {
+    pub source: DataSource,
+    /// Upstream cache key of the source file, if it could be checked.
+    pub source_key: Option<CacheKey>,
+}
+
+/// Entry point for consuming datasets without wiring clients and caches by hand.
+#[derive(Debug, Clone)]
+pub struct Pekora {
+    clients: PriceBulkClients,
+    cache_directory: Option<String>,
+    cache_max_age: Option<chrono::Duration>,
+    namespace: Option<Namespace>,
+    overlay: Option<DiscountOverlay>,
+    hash_algorithm: HashAlgorithm,
+    schemas: Arc<SchemaRegistry>,
+    aws_sdk_config: Option<SdkConfig>,
+    region_names: Arc<OnceCell<Arc<RegionNames>>>,
+    record_history: bool,
+}
+
+impl Pekora {
+    pub fn new(
+        clients: PriceBulkClients,
+        cache_directory: Option<String>,
+        cache_max_age: Option<chrono::Duration>,
+    ) -> Self {
+        Self {
+            clients,
+            cache_directory,
+            cache_max_age,
+            namespace: None,
+            overlay: None,
+            hash_algorithm: HashAlgorithm::default(),
+            schemas: Arc::new(SchemaRegistry::builtin()),
+            aws_sdk_config: None,
+            region_names: Arc::new(OnceCell::new()),
+            record_history: true,
+        }
+    }
+
+    /// Keeps derived data of this instance apart from other namespaces sharing the cache
+    /// directory.
+    pub fn with_namespace(mut self, namespace: Namespace) -> Self {
+        self.namespace = Some(namespace);
+        self
+    }
+
+    pub fn namespace(&self) -> Option<&Namespace> {
+        self.namespace.as_ref()
+    }
+
+    /// Negotiated discounts of the namespace served, applied to the prices a server answers
+    /// with. Cached and derived data keep public prices.
+    pub fn with_overlay(mut self, overlay: DiscountOverlay) -> Self {
+        self.overlay = Some(overlay);
+        self
+    }
+
+    pub fn overlay(&self) -> Option<&DiscountOverlay> {
+        self.overlay.as_ref()
+    }
+
+    /// Algorithm of derived file names and dataset fingerprints. Changing it rebuilds derived
+    /// files once.
+    pub fn with_hash_algorithm(mut self, hash_algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = hash_algorithm;
+        self
+    }
+
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+
+    /// Loads datasets without recording their SKUs in the history store, e.g. for servers
+    /// answering reads, which would otherwise rewrite history files on every request.
+    pub fn without_history(mut self) -> Self {
+        self.record_history = false;
+        self
+    }
+
+    pub fn records_history(&self) -> bool {
+        self.record_history
+    }
+
+    /// SKU history of the dataset `T` of `key`, recorded as the dataset loads.
+    pub fn history<T: DatasetKind>(&self, key: &T::Key) -> HistoryStore {
+        HistoryStore::new(Path::new(self.cache_directory()), T::NAME, &key.to_string())
+    }
+
+    /// Replaces the built-in attribute schemas, e.g. to register schemas of more services.
+    pub fn with_schema_registry(mut self, schemas: SchemaRegistry) -> Self {
+        self.schemas = Arc::new(schemas);
+        self
+    }
+
+    pub fn schemas(&self) -> &SchemaRegistry {
+        &self.schemas
+    }
+
+    /// Credentials and settings of the AWS APIs this instance calls, e.g. to refresh region
+    /// names from SSM public parameters.
+    pub fn with_aws_sdk_config(mut self, aws_sdk_config: SdkConfig) -> Self {
+        self.aws_sdk_config = Some(aws_sdk_config);
+        self
+    }
+
+    /// Region codes and location names transforms resolve locations by. The bundled regions
+    /// are refreshed from SSM public parameters through the cache once per instance, and kept
+    /// as they are without an AWS SDK config or if SSM can't be reached.
+    pub async fn region_names(&self) -> Arc<RegionNames> {
+        self.region_names
+            .get_or_init(|| async {
+                let mut region_names = RegionNames::bundled();
+                let aws_sdk_config = match &self.aws_sdk_config {
+                    Some(aws_sdk_config) => aws_sdk_config.clone(),
+                    None => return Arc::new(region_names),
+                };
+                match self
+                    .cacheable_builder()
+                    .build(SsmClient::new_cacheable_arc(Some(aws_sdk_config)).await)
+                    .load(&())
+                    .await
+                {
+                    Ok(loaded) => {
+                        let added = region_names.refresh_long_names(&loaded.result);
+                        info!("Region names: {} regions added from SSM", added);
+                    }
+                    Err(e) => warn!("Region names: bundled only, SSM failed: {}", e),
+                }
+                Arc::new(region_names)
+            })
+            .await
+            .clone()
+    }
+
+    pub fn clients(&self) -> &PriceBulkClients {
+        &self.clients
+    }
+
+    pub fn cache_directory(&self) -> &str {
+        self.cache_directory
+            .as_deref()
+            .unwrap_or(DEFAULT_CACHE_DIRECTORY)
+    }
+
+    pub fn cacheable_builder(&self) -> FileBackedCacheableBuilder {
+        FileBackedCacheableBuilder::new(self.cache_directory.clone(), self.cache_max_age)
+    }
+
+    /// Registry the clients, caches and transforms of this instance record into. It is the
+    /// crate-wide one, so it also counts what other instances in the process did.
+    pub fn metrics(&self) -> &'static MetricsRegistry {
+        metrics::global()
+    }
+
+    /// Picks the cheapest source that is up to date with upstream for dataset `T` of `key`. Only
+    /// the content hash of the source is fetched, and only once per `head_cache_ttl`.
+    pub async fn plan<T: DatasetKind>(&self, key: &T::Key) -> QueryPlan {
+        let source_key = match T::source_cache_key(self, key).await {
+            Ok(source_key) => source_key,
+            Err(e) => {
+                info!(
+                    "Plan {} {}: Network, upstream check failed: {}",
+                    T::NAME,
+                    key,
+                    e
+                );
+                return QueryPlan {
+                    source: DataSource::Network,
+                    source_key: None,
+                };
+            }
+        };
+        let content_hash = match &source_key.content_hash {
+            Some(content_hash) => content_hash,
+            None => {
+                info!(
+                    "Plan {} {}: Network, upstream has no content hash",
+                    T::NAME,
+                    key
+                );
+                return QueryPlan {
+                    source: DataSource::Network,
+                    source_key: Some(source_key),
+                };
+            }
+        };
+
+        let source = if DerivedCache::new(
+            self.cache_directory(),
+            self.namespace(),
+            T::NAME,
+            self.hash_algorithm,
+        )
+        .contains(&key.to_string(), content_hash)
+        {
+            DataSource::Derived
+        } else {
+            match T::cached_source_key(self, key).await {
+                Ok(Some(cached)) if cached.content_hash == source_key.content_hash => {
+                    DataSource::RawCache
+                }
+                Ok(_) => DataSource::Network,
+                Err(e) => {
+                    warn!("Failed to look up cached {} {}: {}", T::NAME, key, e);
+                    DataSource::Network
+                }
+            }
+        };
+        info!(
+            "Plan {} {}: {:?} for {}",
+            T::NAME,
+            key,
+            source,
+            content_hash
+        );
+        QueryPlan {
+            source,
+            source_key: Some(source_key),
+        }
+    }
+
+    /// Loads dataset `T` for `key`, e.g. `pekora.dataset::<Ec2OnDemand>(region)`.
+    pub async fn dataset<T: DatasetKind>(&self, key: T::Key) -> anyhow::Result<Dataset<T>> {
+        Dataset::load(self.clone(), key).await
+    }
+
+    /// Loads the current RDS pricing list of `region` with typed product attributes.
+    pub async fn fetch_rds_pricing(&self, region: &str) -> anyhow::Result<RdsPricingListResponse> {
+        self.fetch_typed_pricing("AmazonRDS", region).await
+    }
+
+    /// Loads the current ElastiCache pricing list of `region` with typed product attributes.
+    pub async fn fetch_elasticache_pricing(
+        &self,
+        region: &str,
+    ) -> anyhow::Result<ElastiCachePricingListResponse> {
+        self.fetch_typed_pricing("AmazonElastiCache", region).await
+    }
+
+    pub async fn fetch_redshift_pricing(
+        &self,
+        region: &str,
+    ) -> anyhow::Result<RedshiftPricingListResponse> {
+        self.fetch_typed_pricing("AmazonRedshift", region).await
+    }
+
+    /// OpenSearch Service is priced under the AmazonES offer.
+    pub async fn fetch_opensearch_pricing(
+        &self,
+        region: &str,
+    ) -> anyhow::Result<OpenSearchPricingListResponse> {
+        self.fetch_typed_pricing("AmazonES", region).await
+    }
+
+    /// CloudWatch Logs is priced in the AmazonCloudWatch offer.
+    pub async fn fetch_cloudwatch_pricing(
+        &self,
+        region: &str,
+    ) -> anyhow::Result<CloudWatchPricingListResponse> {
+        self.fetch_typed_pricing("AmazonCloudWatch", region).await
+    }
+
+    pub async fn fetch_memorydb_pricing(
+        &self,
+        region: &str,
+    ) -> anyhow::Result<MemoryDbPricingListResponse> {
+        self.fetch_typed_pricing("AmazonMemoryDB", region).await
+    }
+
+    /// DocumentDB is priced under the AmazonDocDB offer.
+    pub async fn fetch_docdb_pricing(
+        &self,
+        region: &str,
+    ) -> anyhow::Result<DocDbPricingListResponse> {
+        self.fetch_typed_pricing("AmazonDocDB", region).await
+    }
+
+    pub async fn fetch_sagemaker_pricing(
+        &self,
+        region: &str,
+    ) -> anyhow::Result<SageMakerPricingListResponse> {
+        self.fetch_typed_pricing("AmazonSageMaker", region).await
+    }
+
+    pub async fn fetch_lambda_pricing(
+        &self,
+        region: &str,
+    ) -> anyhow::Result<LambdaPricingListResponse> {
+        self.fetch_typed_pricing("AWSLambda", region).await
+    }
+
+    /// Fargate is priced under the AmazonECS offer.
+    pub async fn fetch_fargate_pricing(
+        &self,
+        region: &str,
+    ) -> anyhow::Result<FargatePricingListResponse> {
+        self.fetch_typed_pricing("AmazonECS", region).await
+    }
+
+    /// Loads the current AWSDataTransfer pricing list of transfers out of `region`.
+    pub async fn fetch_data_transfer_pricing(
+        &self,
+        region: &str,
+    ) -> anyhow::Result<DataTransferPricingListResponse> {
+        self.fetch_typed_pricing("AWSDataTransfer", region).await
+    }
+
+    /// Loads the current AmazonVPC pricing list of `region`, which prices VPC endpoints.
+    pub async fn fetch_vpc_pricing(
+        &self,
+        region: &str,
+    ) -> anyhow::Result<NetworkPricingListResponse> {
+        self.fetch_typed_pricing("AmazonVPC", region).await
+    }
+
+    /// Loads the current AWSELB pricing list of `region`.
+    pub async fn fetch_elb_pricing(
+        &self,
+        region: &str,
+    ) -> anyhow::Result<NetworkPricingListResponse> {
+        self.fetch_typed_pricing("AWSELB", region).await
+    }
+
+    /// Loads the current AmazonEC2 pricing list of `region` typed for its NAT gateway products.
+    pub async fn fetch_nat_gateway_pricing(
+        &self,
+        region: &str,
+    ) -> anyhow::Result<NetworkPricingListResponse> {
+        self.fetch_typed_pricing("AmazonEC2", region).await
+    }
+
+    /// Loads the current AmazonEC2 pricing list of `region` typed for its EBS products.
+    pub async fn fetch_ebs_pricing(&self, region: &str) -> anyhow::Result<EbsPricingListResponse> {
+        self.fetch_typed_pricing("AmazonEC2", region).await
+    }
+
+    pub async fn fetch_eks_pricing(&self, region: &str) -> anyhow::Result<EksPricingListResponse> {
+        self.fetch_typed_pricing("AmazonEKS", region).await
+    }
+
+    /// Loads the current pricing list of any service, checked against its registered schema.
+    pub async fn fetch_pricing(
+        &self,
+        service_code: &str,
+        region: &str,
+    ) -> anyhow::Result<PricingListResponse> {
+        self.fetch_pricing_version(service_code, region, "current")
+            .await
+    }
+
+    /// Loads `offer_version` of the pricing list of any service, e.g. `20240312153724` or
+    /// `current`, checked against its registered schema.
+    pub async fn fetch_pricing_version(
+        &self,
+        service_code: &str,
+        region: &str,
+        offer_version: &str,
+    ) -> anyhow::Result<PricingListResponse> {
+        let response = self
+            .fetch_offer(service_code, region, offer_version)
+            .await?;
+        self.schemas.validate(service_code, &response)?;
+        Ok(response)
+    }
+
+    /// Loads the Spot Advisor feed of interruption frequencies and savings.
+    pub async fn fetch_spot_advisor(&self) -> anyhow::Result<SpotAdvisorResponse> {
+        let loaded = self
+            .cacheable_builder()
+            .build(self.clients.spot_advisor())
+            .load(&())
+            .await?;
+        Ok(loaded.result)
+    }
+
+    async fn fetch_typed_pricing<A: DeserializeOwned + Debug + Clone>(
+        &self,
+        service_code: &str,
+        region: &str,
+    ) -> anyhow::Result<TypedPricingListResponse<A>> {
+        Ok(self
+            .fetch_offer(service_code, region, "current")
+            .await?
+            .with_typed_attributes()?)
+    }
+
+    async fn fetch_offer(
+        &self,
+        service_code: &str,
+        region: &str,
+        offer_version: &str,
+    ) -> anyhow::Result<PricingListResponse> {
+        let loaded = self
+            .cacheable_builder()
+            .build(self.clients.pricing_list())
+            .load(&PriceBulkOffer {
+                service_code: service_code.to_string(),
+                offer_version: offer_version.to_string(),
+                region: region.to_string(),
+                filename: "index.json".to_string(),
+            })
+            .await?;
+        Ok(loaded.result)
+    }
+}
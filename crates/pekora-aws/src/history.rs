@@ -0,0 +1,255 @@
+//! SKU presence across offer versions, so removed SKUs leave a tombstone behind and diffs can
+//! tell SKUs listed again from new ones
+use crate::pipeline::write_atomically;
+use crate::status::INTERNAL_DIRECTORY;
+use crate::transform::aws::diff::OfferDiff;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// An offer version, identified by its version string and publication date.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OfferMark {
+    pub version: String,
+    pub publication_date: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkuHistory {
+    pub first_seen: OfferMark,
+    pub last_seen: OfferMark,
+    /// First offer the SKU was missing from. Cleared if the SKU comes back.
+    pub removed: Option<OfferMark>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkuStatus {
+    NeverExisted,
+    Active {
+        since: OfferMark,
+    },
+    Removed {
+        since: OfferMark,
+        tombstone: OfferMark,
+    },
+}
+
+/// SKUs that changed between the previously recorded offer and a newer one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryUpdate {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Previously removed SKUs that are listed again
+    pub restored: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct HistoryFile {
+    latest: Option<OfferMark>,
+    skus: BTreeMap<String, SkuHistory>,
+}
+
+impl HistoryFile {
+    fn status(&self, sku: &str) -> SkuStatus {
+        match self.skus.get(sku) {
+            None => SkuStatus::NeverExisted,
+            Some(SkuHistory {
+                first_seen,
+                removed: Some(tombstone),
+                ..
+            }) => SkuStatus::Removed {
+                since: first_seen.clone(),
+                tombstone: tombstone.clone(),
+            },
+            Some(history) => SkuStatus::Active {
+                since: history.first_seen.clone(),
+            },
+        }
+    }
+}
+
+/// History of the SKUs of one dataset, at `<cache directory>/.pekora/history/<kind>/<key>.json`.
+pub struct HistoryStore {
+    path: PathBuf,
+}
+
+impl HistoryStore {
+    pub fn new(cache_directory: &Path, kind: &str, key: &str) -> Self {
+        Self {
+            path: cache_directory
+                .join(INTERNAL_DIRECTORY)
+                .join("history")
+                .join(kind)
+                .join(format!("{}.json", key)),
+        }
+    }
+
+    /// Records the SKUs listed in `offer`. SKUs missing from it get a tombstone instead of being
+    /// forgotten. Offers not newer than the latest recorded one are ignored.
+    pub fn record<'a>(
+        &self,
+        offer: &OfferMark,
+        skus: impl IntoIterator<Item = &'a str>,
+    ) -> anyhow::Result<HistoryUpdate> {
+        let mut file = self.read()?;
+        if file
+            .latest
+            .as_ref()
+            .is_some_and(|latest| latest.publication_date >= offer.publication_date)
+        {
+            return Ok(HistoryUpdate::default());
+        }
+
+        let listed = skus.into_iter().collect::<HashSet<_>>();
+        let mut update = HistoryUpdate::default();
+        for (sku, history) in file.skus.iter_mut() {
+            let is_listed = listed.contains(sku.as_str());
+            match (&history.removed, is_listed) {
+                (None, true) => history.last_seen = offer.clone(),
+                (None, false) => {
+                    history.removed = Some(offer.clone());
+                    update.removed.push(sku.clone());
+                }
+                (Some(_), true) => {
+                    history.removed = None;
+                    history.last_seen = offer.clone();
+                    update.restored.push(sku.clone());
+                }
+                (Some(_), false) => {}
+            }
+        }
+        for sku in listed {
+            if !file.skus.contains_key(sku) {
+                file.skus.insert(
+                    sku.to_string(),
+                    SkuHistory {
+                        first_seen: offer.clone(),
+                        last_seen: offer.clone(),
+                        removed: None,
+                    },
+                );
+                update.added.push(sku.to_string());
+            }
+        }
+        update.added.sort();
+        file.latest = Some(offer.clone());
+
+        write_atomically(&self.path, |writer| {
+            serde_json::to_writer(writer, &file)?;
+            Ok(())
+        })?;
+        Ok(update)
+    }
+
+    pub fn status(&self, sku: &str) -> anyhow::Result<SkuStatus> {
+        Ok(self.read()?.status(sku))
+    }
+
+    /// Sets the `restored_skus` of `offer_diff` to its added SKUs first seen before `from`, the
+    /// offer it diffs from, whether they were removed since `from` or not yet recorded as
+    /// listed again.
+    pub fn mark_restored(
+        &self,
+        offer_diff: &mut OfferDiff,
+        from: &OfferMark,
+    ) -> anyhow::Result<()> {
+        let file = self.read()?;
+        offer_diff.restored_skus = offer_diff
+            .added_skus
+            .iter()
+            .filter(|sku| match file.status(sku) {
+                SkuStatus::NeverExisted => false,
+                SkuStatus::Active { since } | SkuStatus::Removed { since, .. } => {
+                    since.publication_date < from.publication_date
+                }
+            })
+            .cloned()
+            .collect();
+        Ok(())
+    }
+
+    fn read(&self) -> anyhow::Result<HistoryFile> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HistoryFile::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HistoryStore, OfferMark, SkuStatus};
+    use crate::transform::aws::diff::OfferDiff;
+    use chrono::{DateTime, Utc};
+
+    fn offer(version: &str) -> OfferMark {
+        let date = format!(
+            "{}-{}-{}T00:00:00Z",
+            &version[..4],
+            &version[4..6],
+            &version[6..]
+        );
+        OfferMark {
+            version: version.to_string(),
+            publication_date: DateTime::parse_from_rfc3339(&date)
+                .unwrap()
+                .with_timezone(&Utc),
+        }
+    }
+
+    #[test]
+    fn test_tombstones() {
+        let directory = std::env::temp_dir().join(format!("pekora-history-{}", std::process::id()));
+        let store = HistoryStore::new(&directory, "aws/ec2/on_demand", "us-east-1");
+
+        let update = store.record(&offer("20240301"), ["A", "B"]).unwrap();
+        assert_eq!(update.added, vec!["A", "B"]);
+        let update = store.record(&offer("20240401"), ["A"]).unwrap();
+        assert_eq!(update.removed, vec!["B"]);
+        // Older offers don't rewrite history
+        assert!(store
+            .record(&offer("20240315"), ["A", "B"])
+            .unwrap()
+            .restored
+            .is_empty());
+
+        assert_eq!(
+            store.status("A").unwrap(),
+            SkuStatus::Active {
+                since: offer("20240301")
+            }
+        );
+        assert_eq!(
+            store.status("B").unwrap(),
+            SkuStatus::Removed {
+                since: offer("20240301"),
+                tombstone: offer("20240401")
+            }
+        );
+        assert_eq!(store.status("C").unwrap(), SkuStatus::NeverExisted);
+
+        // B is listed again next to the new C, before and after recording that offer
+        let mut offer_diff = OfferDiff {
+            from_version: "20240401".to_string(),
+            to_version: "20240501".to_string(),
+            added_skus: vec!["B".to_string(), "C".to_string()],
+            removed_skus: Vec::new(),
+            restored_skus: Vec::new(),
+            changed: Vec::new(),
+        };
+        store
+            .mark_restored(&mut offer_diff, &offer("20240401"))
+            .unwrap();
+        assert_eq!(offer_diff.restored_skus, vec!["B"]);
+        let update = store.record(&offer("20240501"), ["A", "B", "C"]).unwrap();
+        assert_eq!(update.restored, vec!["B"]);
+        offer_diff.restored_skus.clear();
+        store
+            .mark_restored(&mut offer_diff, &offer("20240401"))
+            .unwrap();
+        assert_eq!(offer_diff.restored_skus, vec!["B"]);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,16 @@
+pub mod api;
+pub mod audit;
+pub mod crawler;
+#[cfg(feature = "parquet")]
+pub mod cur;
+pub mod dataset;
+mod facade;
+pub mod history;
+pub mod output;
+pub mod pipeline;
+pub mod price;
+pub mod store;
+pub mod util;
+
+pub use facade::{DataSource, Pekora, QueryPlan};
+pub use pekora_core::{cache, metrics, schema, status, transform};
@@ -0,0 +1,5 @@
+//! Files of transformed rows for loading into other tools, and uploads of them
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod s3;
+pub mod xlsx;
@@ -0,0 +1,366 @@
+//! Parquet files of normalized and pivoted pricing rows, with a fixed schema per row type so
+//! files of different offer versions can be queried together, e.g. by Athena or DuckDB
+use crate::pipeline::{columns, write_atomically, Record};
+use crate::transform::aws::normalize::NormalizedPriceRow;
+use crate::transform::aws::on_demand::OnDemandRate;
+use crate::transform::aws::savings_plan::PivotedSavingsPlanTermRate;
+use arrow_array::{
+    ArrayRef, Decimal128Array, Int32Array, RecordBatch, StringArray, TimestampMillisecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use rust_decimal::Decimal;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Precision of price columns, the most a 128-bit decimal holds
+const PRICE_PRECISION: u8 = 38;
+/// Scale of price columns. Prices of pricing files have at most 10 fractional digits.
+const PRICE_SCALE: i8 = 10;
+
+/// Rows written with the same schema on every run.
+pub trait ParquetRow: Sized {
+    fn schema() -> SchemaRef;
+    fn to_batch(rows: &[Self]) -> anyhow::Result<RecordBatch>;
+}
+
+/// Writes `rows` as one row group, compressed with zstd.
+pub fn write<R: ParquetRow>(rows: &[R], writer: impl Write + Send) -> anyhow::Result<()> {
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::default()))
+        .build();
+    let mut writer = ArrowWriter::try_new(writer, R::schema(), Some(properties))?;
+    writer.write(&R::to_batch(rows)?)?;
+    writer.close()?;
+    Ok(())
+}
+
+/// Writes pipeline records as one nullable string column per field of any record.
+pub fn write_records(records: &[Record], mut writer: impl Write) -> anyhow::Result<()> {
+    let columns = columns(records);
+    let schema = Arc::new(Schema::new(
+        columns
+            .iter()
+            .map(|column| optional_string(column))
+            .collect::<Vec<_>>(),
+    ));
+    let arrays = columns
+        .iter()
+        .map(|column| {
+            strings(
+                records
+                    .iter()
+                    .map(|record| record.get(*column).map(String::as_str)),
+            )
+        })
+        .collect::<Vec<_>>();
+    let batch = RecordBatch::try_new(schema.clone(), arrays)?;
+    // the Parquet writer needs a `Send` writer, which stdout is not
+    let mut buffer = Vec::new();
+    let mut parquet_writer = ArrowWriter::try_new(&mut buffer, schema, None)?;
+    parquet_writer.write(&batch)?;
+    parquet_writer.close()?;
+    writer.write_all(&buffer)?;
+    Ok(())
+}
+
+/// Writes `rows` to `path`, replacing it atomically.
+pub fn write_file<R: ParquetRow>(rows: &[R], path: &Path) -> anyhow::Result<()> {
+    write_atomically(path, |writer| write(rows, writer))
+}
+
+fn field(name: &str, data_type: DataType, nullable: bool) -> Field {
+    Field::new(name, data_type, nullable)
+}
+
+fn string(name: &str) -> Field {
+    field(name, DataType::Utf8, false)
+}
+
+fn optional_string(name: &str) -> Field {
+    field(name, DataType::Utf8, true)
+}
+
+fn price(name: &str) -> Field {
+    field(
+        name,
+        DataType::Decimal128(PRICE_PRECISION, PRICE_SCALE),
+        true,
+    )
+}
+
+fn timestamp(name: &str) -> Field {
+    field(
+        name,
+        DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+        false,
+    )
+}
+
+fn strings<'a>(values: impl Iterator<Item = Option<&'a str>>) -> ArrayRef {
+    Arc::new(StringArray::from_iter(values))
+}
+
+/// Prices with more fractional digits than the column are rounded.
+fn prices(values: impl Iterator<Item = Option<Decimal>>) -> anyhow::Result<ArrayRef> {
+    let array = Decimal128Array::from_iter(values.map(|value| {
+        value.map(|mut value| {
+            value.rescale(PRICE_SCALE as u32);
+            value.mantissa()
+        })
+    }))
+    .with_precision_and_scale(PRICE_PRECISION, PRICE_SCALE)?;
+    Ok(Arc::new(array))
+}
+
+impl ParquetRow for NormalizedPriceRow {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            optional_string("region"),
+            optional_string("service_code"),
+            string("sku"),
+            optional_string("instance_type"),
+            optional_string("platform"),
+            optional_string("component"),
+            string("purchase_model"),
+            optional_string("term"),
+            optional_string("purchase_option"),
+            optional_string("offering_class"),
+            price("effective_usd_per_hour"),
+        ]))
+    }
+
+    fn to_batch(rows: &[Self]) -> anyhow::Result<RecordBatch> {
+        let columns = vec![
+            strings(rows.iter().map(|row| row.region.as_deref())),
+            strings(rows.iter().map(|row| row.service_code.as_deref())),
+            strings(rows.iter().map(|row| Some(row.sku.as_str()))),
+            strings(rows.iter().map(|row| row.instance_type.as_deref())),
+            strings(rows.iter().map(|row| row.platform.as_deref())),
+            strings(
+                rows.iter()
+                    .map(|row| row.component.as_ref().map(|component| component.as_str())),
+            ),
+            strings(rows.iter().map(|row| Some(row.purchase_model.as_str()))),
+            strings(
+                rows.iter()
+                    .map(|row| row.term.as_ref().map(|term| term.as_str())),
+            ),
+            strings(rows.iter().map(|row| {
+                row.purchase_option
+                    .as_ref()
+                    .map(|purchase_option| purchase_option.as_str())
+            })),
+            strings(rows.iter().map(|row| {
+                row.offering_class
+                    .as_ref()
+                    .map(|offering_class| offering_class.as_str())
+            })),
+            prices(rows.iter().map(|row| Some(row.effective_usd_per_hour)))?,
+        ];
+        Ok(RecordBatch::try_new(Self::schema(), columns)?)
+    }
+}
+
+impl ParquetRow for OnDemandRate {
+    /// Attributes common to most services have their own columns, all of them are kept as a
+    /// JSON object in `attributes`.
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            string("sku"),
+            string("product_family"),
+            optional_string("region"),
+            optional_string("service_code"),
+            optional_string("instance_type"),
+            string("offer_term_code"),
+            timestamp("effective_date"),
+            string("rate_code"),
+            string("description"),
+            string("unit"),
+            price("price_usd"),
+            string("attributes"),
+        ]))
+    }
+
+    fn to_batch(rows: &[Self]) -> anyhow::Result<RecordBatch> {
+        let attribute = |name: &'static str| {
+            strings(
+                rows.iter()
+                    .map(move |row| row.attributes.get(name).map(String::as_str)),
+            )
+        };
+        let attributes = rows
+            .iter()
+            .map(|row| serde_json::to_string(row.attributes.as_ref()))
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        let columns = vec![
+            strings(rows.iter().map(|row| Some(row.sku.as_str()))),
+            strings(rows.iter().map(|row| Some(row.product_family.as_str()))),
+            attribute("regionCode"),
+            attribute("servicecode"),
+            attribute("instanceType"),
+            strings(rows.iter().map(|row| Some(row.offer_term_code.as_str()))),
+            Arc::new(
+                TimestampMillisecondArray::from_iter_values(
+                    rows.iter().map(|row| row.effective_date.timestamp_millis()),
+                )
+                .with_timezone("UTC"),
+            ),
+            strings(rows.iter().map(|row| Some(row.rate_code.as_str()))),
+            strings(rows.iter().map(|row| Some(row.description.as_str()))),
+            strings(rows.iter().map(|row| Some(row.unit.as_str()))),
+            prices(rows.iter().map(|row| {
+                row.price_per_unit
+                    .get("USD")
+                    .and_then(|price| price.value())
+            }))?,
+            strings(
+                attributes
+                    .iter()
+                    .map(|attributes| Some(attributes.as_str())),
+            ),
+        ];
+        Ok(RecordBatch::try_new(Self::schema(), columns)?)
+    }
+}
+
+impl ParquetRow for PivotedSavingsPlanTermRate {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            string("savings_plan_sku"),
+            timestamp("savings_plan_effective_date"),
+            string("plan_type"),
+            string("purchase_term"),
+            string("purchase_option"),
+            optional_string("region"),
+            optional_string("instance_family"),
+            field("lease_contract_duration", DataType::Int32, false),
+            string("lease_contract_unit"),
+            string("discounted_sku"),
+            string("discounted_usage_type"),
+            string("discounted_operation"),
+            string("discounted_service_code"),
+            string("rate_code"),
+            string("unit"),
+            price("price"),
+            string("currency"),
+        ]))
+    }
+
+    fn to_batch(rows: &[Self]) -> anyhow::Result<RecordBatch> {
+        let column = |value: fn(&Self) -> Option<&str>| strings(rows.iter().map(value));
+        let columns = vec![
+            column(|row| Some(&row.savings_plan_sku)),
+            Arc::new(
+                TimestampMillisecondArray::from_iter_values(
+                    rows.iter()
+                        .map(|row| row.savings_plan_effective_date.timestamp_millis()),
+                )
+                .with_timezone("UTC"),
+            ),
+            column(|row| Some(row.savings_plan_attributes.product_family.as_str())),
+            column(|row| Some(row.savings_plan_attributes.purchase_term.as_str())),
+            column(|row| Some(row.savings_plan_attributes.purchase_option.as_str())),
+            column(|row| row.savings_plan_attributes.region_code.as_deref()),
+            column(|row| row.savings_plan_attributes.instance_type.as_deref()),
+            Arc::new(Int32Array::from_iter_values(
+                rows.iter().map(|row| row.lease_contract_length.duration),
+            )),
+            column(|row| Some(&row.lease_contract_length.unit)),
+            column(|row| Some(&row.term_rate.discounted_sku)),
+            column(|row| Some(&row.term_rate.discounted_usage_type)),
+            column(|row| Some(&row.term_rate.discounted_operation)),
+            column(|row| Some(&row.term_rate.discounted_service_code)),
+            column(|row| Some(&row.term_rate.rate_code)),
+            column(|row| Some(&row.term_rate.unit)),
+            prices(
+                rows.iter()
+                    .map(|row| row.term_rate.discounted_rate.price.value()),
+            )?,
+            column(|row| Some(row.term_rate.discounted_rate.currency.as_str())),
+        ];
+        Ok(RecordBatch::try_new(Self::schema(), columns)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write, write_records, ParquetRow};
+    use crate::api::aws::types::{ContractLength, PurchaseOption, RIOfferingClass};
+    use crate::pipeline::Record;
+    use crate::transform::aws::normalize::{NormalizedPriceRow, PurchaseModel};
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Decimal128Type;
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_write_normalized_rows() {
+        let rows = vec![NormalizedPriceRow {
+            region: Some("us-east-1".to_string()),
+            service_code: Some("AmazonEC2".to_string()),
+            sku: "ABC".to_string(),
+            instance_type: Some("m5.large".to_string()),
+            platform: Some("Linux".to_string()),
+            component: None,
+            purchase_model: PurchaseModel::Reserved,
+            term: Some(ContractLength::OneYear),
+            purchase_option: Some(PurchaseOption::AllUpfront),
+            offering_class: Some(RIOfferingClass::Standard),
+            effective_usd_per_hour: Decimal::new(5_912_328_767, 11),
+        }];
+        let path = std::env::temp_dir().join(format!("pekora-rows-{}.parquet", std::process::id()));
+        write(&rows, std::fs::File::create(&path).unwrap()).unwrap();
+
+        let reader =
+            ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(
+            reader.schema().fields(),
+            NormalizedPriceRow::schema().fields()
+        );
+        let batch = reader.build().unwrap().next().unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(batch.column(7).as_string::<i32>().value(0), "OneYear");
+        assert!(batch.column(5).is_null(0));
+        assert_eq!(batch.column(9).as_string::<i32>().value(0), "standard");
+        let prices = batch.column(10).as_primitive::<Decimal128Type>();
+        // rounded to 10 fractional digits
+        assert_eq!(prices.value(0), 591_232_877);
+    }
+
+    #[test]
+    fn test_write_records() {
+        let records: Vec<Record> = vec![
+            [("instanceType", "m5.large"), ("price_usd", "0.096")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            [("instanceType", "m5.xlarge")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        ];
+        let path =
+            std::env::temp_dir().join(format!("pekora-export-{}.parquet", std::process::id()));
+        write_records(&records, std::fs::File::create(&path).unwrap()).unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        let prices = batch
+            .column_by_name("price_usd")
+            .unwrap()
+            .as_string::<i32>();
+        assert_eq!(prices.value(0), "0.096");
+        assert!(prices.is_null(1));
+    }
+}
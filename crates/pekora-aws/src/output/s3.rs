@@ -0,0 +1,261 @@
+//! Uploading exported files to S3, partitioned by service, region and offer version so every
+//! offer version of a dataset lands under its own prefix, e.g.
+//! `{prefix}/service=AmazonEC2/region=us-east-1/offer_version=20240312153724/ec2.parquet`.
+//! Requests are signed with SigV4 and sent as single `PutObject` calls, so files are limited to
+//! 5 GB.
+use aws_config::SdkConfig;
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
+use aws_sigv4::http_request::{
+    sign, PayloadChecksumKind, PercentEncodingMode, SignableBody, SignableRequest, SigningSettings,
+    UriPathNormalizationMode,
+};
+use aws_sigv4::sign::v4;
+use log::info;
+use serde::Deserialize;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Bucket and key prefix uploads are written to.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct S3Destination {
+    pub bucket: String,
+    /// Key prefix, e.g. `pekora/exports`. Uploads are written to the bucket root if empty.
+    #[serde(default)]
+    pub prefix: String,
+    /// Region of the bucket. The region of the AWS config if not set.
+    pub region: Option<String>,
+    /// Endpoint of an S3-compatible store, e.g. MinIO, addressed path-style
+    pub endpoint: Option<String>,
+}
+
+/// What an uploaded file was exported from.
+#[derive(Debug, Clone, Copy)]
+pub struct Partition<'a> {
+    pub service_code: &'a str,
+    pub region: &'a str,
+    pub offer_version: &'a str,
+}
+
+pub struct S3Uploader {
+    client: reqwest::Client,
+    credentials: SharedCredentialsProvider,
+    region: String,
+    destination: S3Destination,
+}
+
+impl S3Uploader {
+    pub fn new(sdk_config: &SdkConfig, destination: S3Destination) -> anyhow::Result<Self> {
+        let credentials = match sdk_config.credentials_provider() {
+            Some(credentials) => credentials,
+            None => anyhow::bail!("No AWS credentials to upload to S3 with"),
+        };
+        let region = match (&destination.region, sdk_config.region()) {
+            (Some(region), _) => region.clone(),
+            (None, Some(region)) => region.to_string(),
+            (None, None) => anyhow::bail!("Region of bucket {} is not set", destination.bucket),
+        };
+        Ok(Self {
+            client: reqwest::Client::new(),
+            credentials,
+            region,
+            destination,
+        })
+    }
+
+    /// Key of `file_name` in `partition`.
+    pub fn key(&self, partition: Partition, file_name: &str) -> String {
+        let prefix = self.destination.prefix.trim_matches('/');
+        let key = format!(
+            "service={}/region={}/offer_version={}/{}",
+            partition.service_code, partition.region, partition.offer_version, file_name
+        );
+        if prefix.is_empty() {
+            key
+        } else {
+            format!("{}/{}", prefix, key)
+        }
+    }
+
+    /// Uploads the file at `path` into `partition`, returning its `s3://` URI.
+    pub async fn upload(&self, partition: Partition<'_>, path: &Path) -> anyhow::Result<String> {
+        let file_name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(file_name) => file_name,
+            None => anyhow::bail!("{} has no file name", path.display()),
+        };
+        let key = self.key(partition, file_name);
+        let body = tokio::fs::read(path).await?;
+        self.put(&key, body, content_type(file_name)).await?;
+        let uri = format!("s3://{}/{}", self.destination.bucket, key);
+        info!("Uploaded {} to {}", path.display(), uri);
+        Ok(uri)
+    }
+
+    async fn put(&self, key: &str, body: Vec<u8>, content_type: &str) -> anyhow::Result<()> {
+        let encoded_key = key
+            .split('/')
+            .map(encode_segment)
+            .collect::<Vec<_>>()
+            .join("/");
+        let url = match &self.destination.endpoint {
+            Some(endpoint) => format!(
+                "{}/{}/{}",
+                endpoint.trim_end_matches('/'),
+                self.destination.bucket,
+                encoded_key
+            ),
+            None => format!(
+                "https://{}.s3.{}.amazonaws.com/{}",
+                self.destination.bucket, self.region, encoded_key
+            ),
+        };
+
+        let identity = self.credentials.provide_credentials().await?.into();
+        let mut settings = SigningSettings::default();
+        // S3 signs keys as sent, encoded once and without normalizing `.` segments
+        settings.percent_encoding_mode = PercentEncodingMode::Single;
+        settings.uri_path_normalization_mode = UriPathNormalizationMode::Disabled;
+        settings.payload_checksum_kind = PayloadChecksumKind::XAmzSha256;
+        let params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&self.region)
+            .name("s3")
+            .time(SystemTime::now())
+            .settings(settings)
+            .build()?
+            .into();
+        let signable = SignableRequest::new(
+            "PUT",
+            url.as_str(),
+            std::iter::empty(),
+            SignableBody::Bytes(&body),
+        )?;
+        let (instructions, _) = sign(signable, &params)?.into_parts();
+
+        let mut request = self
+            .client
+            .put(&url)
+            .header(reqwest::header::CONTENT_TYPE, content_type);
+        for (name, value) in instructions.headers() {
+            request = request.header(name, value);
+        }
+        let response = request.body(body).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            anyhow::bail!(
+                "Upload to {} failed with {}: {}",
+                key,
+                status,
+                message.trim()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Percent-encodes everything but unreserved characters, as SigV4 expects of key segments.
+fn encode_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn content_type(file_name: &str) -> &'static str {
+    match file_name.rsplit('.').next() {
+        Some("csv") => "text/csv",
+        Some("json") => "application/json",
+        Some("jsonl") => "application/x-ndjson",
+        Some("parquet") => "application/vnd.apache.parquet",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Partition, S3Destination, S3Uploader};
+    use crate::util::testing::serve;
+    use aws_config::{BehaviorVersion, Region, SdkConfig};
+    use aws_credential_types::provider::SharedCredentialsProvider;
+    use aws_credential_types::Credentials;
+    use axum::extract::{Path, State};
+    use axum::http::{header, HeaderMap, StatusCode};
+    use axum::routing::put;
+    use axum::Router;
+    use std::sync::{Arc, Mutex};
+
+    type Uploads = Arc<Mutex<Vec<(String, String, String, String)>>>;
+
+    async fn record(
+        State(uploads): State<Uploads>,
+        Path(key): Path<String>,
+        headers: HeaderMap,
+        body: String,
+    ) -> StatusCode {
+        let header = |name| headers[name].to_str().unwrap().to_string();
+        uploads.lock().unwrap().push((
+            key,
+            header(header::AUTHORIZATION),
+            header(header::CONTENT_TYPE),
+            body,
+        ));
+        StatusCode::OK
+    }
+
+    #[tokio::test]
+    async fn test_upload() {
+        let uploads = Uploads::default();
+        let endpoint = serve(
+            Router::new()
+                .route("/exports/*key", put(record))
+                .with_state(uploads.clone()),
+        )
+        .await;
+        let sdk_config = SdkConfig::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(SharedCredentialsProvider::new(Credentials::new(
+                "AKID", "secret", None, None, "test",
+            )))
+            .build();
+        let uploader = S3Uploader::new(
+            &sdk_config,
+            S3Destination {
+                bucket: "exports".to_string(),
+                prefix: "pekora/".to_string(),
+                region: Some("ap-northeast-2".to_string()),
+                endpoint: Some(endpoint),
+            },
+        )
+        .unwrap();
+        let path = std::env::temp_dir().join(format!("pekora-upload-{}.csv", std::process::id()));
+        std::fs::write(&path, "sku,price_usd\nABC,0.096\n").unwrap();
+
+        let partition = Partition {
+            service_code: "AmazonEC2",
+            region: "us-east-1",
+            offer_version: "20240312153724",
+        };
+        let uri = uploader.upload(partition, &path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let key = format!(
+            "pekora/service=AmazonEC2/region=us-east-1/offer_version=20240312153724/{}",
+            path.file_name().unwrap().to_str().unwrap()
+        );
+        assert_eq!(uri, format!("s3://exports/{}", key));
+
+        let uploads = uploads.lock().unwrap();
+        let (uploaded_key, authorization, content_type, body) = &uploads[0];
+        assert_eq!(uploaded_key, &key);
+        assert!(authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKID/"));
+        assert!(authorization.contains("/ap-northeast-2/s3/aws4_request"));
+        assert!(authorization.contains("x-amz-content-sha256"));
+        assert_eq!(content_type, "text/csv");
+        assert_eq!(body, "sku,price_usd\nABC,0.096\n");
+    }
+}
@@ -0,0 +1,339 @@
+//! Excel workbooks of string tables, one worksheet per table. Cells holding plain decimal
+//! numbers are written as numbers so they can be summed, everything else as inline strings. The
+//! workbook is the minimal set of parts spreadsheet applications open, without styles.
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use std::collections::BTreeSet;
+use std::io::Write;
+
+/// Longest sheet name Excel accepts
+const MAX_SHEET_NAME_LENGTH: usize = 31;
+/// Integer digits of cells written as numbers, well below the 15 significant digits Excel keeps
+const MAX_NUMBER_DIGITS: usize = 12;
+
+/// A worksheet, with `columns` as its frozen header row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sheet {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Writes `sheets` as an xlsx workbook. Sheet names are shortened and made unique as Excel
+/// requires.
+pub fn write(sheets: &[Sheet], mut writer: impl Write) -> anyhow::Result<()> {
+    let names = sheet_names(sheets);
+    let mut archive = ZipWriter::default();
+    archive.add("[Content_Types].xml", &content_types(sheets.len()))?;
+    archive.add("_rels/.rels", ROOT_RELATIONSHIPS)?;
+    archive.add("xl/workbook.xml", &workbook(&names))?;
+    archive.add(
+        "xl/_rels/workbook.xml.rels",
+        &workbook_relationships(sheets.len()),
+    )?;
+    for (i, sheet) in sheets.iter().enumerate() {
+        archive.add(
+            &format!("xl/worksheets/sheet{}.xml", i + 1),
+            &worksheet(sheet),
+        )?;
+    }
+    writer.write_all(&archive.finish())?;
+    Ok(())
+}
+
+const XML_DECLARATION: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#;
+
+const ROOT_RELATIONSHIPS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+fn content_types(sheets: usize) -> String {
+    let overrides = (1..=sheets)
+        .map(|i| {
+            format!(
+                r#"<Override PartName="/xl/worksheets/sheet{}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
+                i
+            )
+        })
+        .collect::<String>();
+    format!(
+        r#"{}<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>{}</Types>"#,
+        XML_DECLARATION, overrides
+    )
+}
+
+fn workbook(names: &[String]) -> String {
+    let sheets = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            format!(
+                r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#,
+                escape(name),
+                i + 1,
+                i + 1
+            )
+        })
+        .collect::<String>();
+    format!(
+        r#"{}<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>{}</sheets></workbook>"#,
+        XML_DECLARATION, sheets
+    )
+}
+
+fn workbook_relationships(sheets: usize) -> String {
+    let relationships = (1..=sheets)
+        .map(|i| {
+            format!(
+                r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{}.xml"/>"#,
+                i, i
+            )
+        })
+        .collect::<String>();
+    format!(
+        r#"{}<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{}</Relationships>"#,
+        XML_DECLARATION, relationships
+    )
+}
+
+fn worksheet(sheet: &Sheet) -> String {
+    let mut rows = String::new();
+    for (i, row) in std::iter::once(&sheet.columns)
+        .chain(&sheet.rows)
+        .enumerate()
+    {
+        let number = i + 1;
+        rows.push_str(&format!(r#"<row r="{}">"#, number));
+        for (column, value) in row.iter().enumerate() {
+            let reference = format!("{}{}", column_name(column), number);
+            if value.is_empty() {
+                continue;
+            }
+            if i > 0 && is_number(value) {
+                rows.push_str(&format!(r#"<c r="{}"><v>{}</v></c>"#, reference, value));
+            } else {
+                rows.push_str(&format!(
+                    r#"<c r="{}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+                    reference,
+                    escape(value)
+                ));
+            }
+        }
+        rows.push_str("</row>");
+    }
+    format!(
+        r#"{}<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetViews><sheetView workbookViewId="0"><pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/></sheetView></sheetViews><sheetData>{}</sheetData></worksheet>"#,
+        XML_DECLARATION, rows
+    )
+}
+
+/// Letters of the zero-based column `index`, e.g. `AA` for 26.
+fn column_name(index: usize) -> String {
+    let mut name = Vec::new();
+    let mut index = index + 1;
+    while index > 0 {
+        let remainder = (index - 1) % 26;
+        name.push(b'A' + remainder as u8);
+        index = (index - 1) / 26;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// Plain decimals like `-0.096`. Codes with leading zeros and long digit strings such as offer
+/// versions stay text, as spreadsheets would round or reformat them.
+fn is_number(value: &str) -> bool {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    let (integer, fraction) = match digits.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (digits, None),
+    };
+    !integer.is_empty()
+        && integer.len() <= MAX_NUMBER_DIGITS
+        && integer.bytes().all(|byte| byte.is_ascii_digit())
+        && (integer == "0" || !integer.starts_with('0'))
+        && fraction.is_none_or(|fraction| {
+            !fraction.is_empty() && fraction.bytes().all(|byte| byte.is_ascii_digit())
+        })
+}
+
+/// Escapes markup and drops control characters XML cannot hold.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Sheet names without the characters Excel rejects, at most 31 characters and unique.
+fn sheet_names(sheets: &[Sheet]) -> Vec<String> {
+    let mut taken = BTreeSet::new();
+    sheets
+        .iter()
+        .enumerate()
+        .map(|(i, sheet)| {
+            let base = sheet
+                .name
+                .chars()
+                .filter(|c| !matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\'))
+                .take(MAX_SHEET_NAME_LENGTH)
+                .collect::<String>();
+            let base = if base.trim().is_empty() {
+                format!("Sheet{}", i + 1)
+            } else {
+                base
+            };
+            let mut name = base.clone();
+            let mut suffix = 2;
+            while !taken.insert(name.to_lowercase()) {
+                let suffix_text = format!(" ({})", suffix);
+                let kept = MAX_SHEET_NAME_LENGTH - suffix_text.len();
+                name = format!(
+                    "{}{}",
+                    base.chars().take(kept).collect::<String>(),
+                    suffix_text
+                );
+                suffix += 1;
+            }
+            name
+        })
+        .collect()
+}
+
+/// Deflated entries of a ZIP archive, built in memory.
+#[derive(Default)]
+struct ZipWriter {
+    data: Vec<u8>,
+    central_directory: Vec<u8>,
+    entries: u16,
+}
+
+impl ZipWriter {
+    fn add(&mut self, name: &str, contents: &str) -> anyhow::Result<()> {
+        let mut crc = Crc::new();
+        crc.update(contents.as_bytes());
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(contents.as_bytes())?;
+        let compressed = encoder.finish()?;
+        let offset = u32::try_from(self.data.len())?;
+        let compressed_size = u32::try_from(compressed.len())?;
+        let size = u32::try_from(contents.len())?;
+
+        // version needed, flags, deflate, time and date of 1980-01-01 00:00
+        let common = |buffer: &mut Vec<u8>| {
+            for value in [20u16, 0, 8, 0, 0x21] {
+                buffer.extend_from_slice(&value.to_le_bytes());
+            }
+            for value in [crc.sum(), compressed_size, size] {
+                buffer.extend_from_slice(&value.to_le_bytes());
+            }
+        };
+        self.data.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        common(&mut self.data);
+        self.data
+            .extend_from_slice(&u16::try_from(name.len())?.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        self.data.extend_from_slice(name.as_bytes());
+        self.data.extend_from_slice(&compressed);
+
+        self.central_directory
+            .extend_from_slice(&0x02014b50u32.to_le_bytes());
+        self.central_directory
+            .extend_from_slice(&20u16.to_le_bytes());
+        common(&mut self.central_directory);
+        // name length, then no extra field, comment, disk number or attributes
+        for value in [u16::try_from(name.len())?, 0, 0, 0, 0] {
+            self.central_directory
+                .extend_from_slice(&value.to_le_bytes());
+        }
+        self.central_directory
+            .extend_from_slice(&0u32.to_le_bytes());
+        self.central_directory
+            .extend_from_slice(&offset.to_le_bytes());
+        self.central_directory.extend_from_slice(name.as_bytes());
+        self.entries += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let offset = self.data.len() as u32;
+        let size = self.central_directory.len() as u32;
+        self.data.append(&mut self.central_directory);
+        self.data.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        for value in [0u16, 0, self.entries, self.entries] {
+            self.data.extend_from_slice(&value.to_le_bytes());
+        }
+        self.data.extend_from_slice(&size.to_le_bytes());
+        self.data.extend_from_slice(&offset.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{column_name, is_number, write, Sheet};
+    use flate2::read::DeflateDecoder;
+    use std::io::Read;
+
+    /// Contents of the entry `name`, found by scanning local file headers.
+    fn entry(archive: &[u8], name: &str) -> String {
+        let mut position = 0;
+        while archive[position..].starts_with(&0x04034b50u32.to_le_bytes()) {
+            let field = |offset: usize, length: usize| {
+                let bytes = &archive[position + offset..position + offset + length];
+                bytes
+                    .iter()
+                    .rev()
+                    .fold(0usize, |value, byte| value << 8 | *byte as usize)
+            };
+            let compressed_size = field(18, 4);
+            let name_length = field(26, 2);
+            let data_start = position + 30 + name_length;
+            let entry_name = &archive[position + 30..data_start];
+            if entry_name == name.as_bytes() {
+                let mut contents = String::new();
+                DeflateDecoder::new(&archive[data_start..data_start + compressed_size])
+                    .read_to_string(&mut contents)
+                    .unwrap();
+                return contents;
+            }
+            position = data_start + compressed_size;
+        }
+        panic!("no entry {}", name);
+    }
+
+    #[test]
+    fn test_write_workbook() {
+        let sheet = |name: &str| Sheet {
+            name: name.to_string(),
+            columns: vec!["sku".to_string(), "monthly_usd".to_string()],
+            rows: vec![vec!["A&B".to_string(), "70.08".to_string()]],
+        };
+        let mut archive = Vec::new();
+        let sheets = [sheet("on_demand"), sheet("on_demand"), sheet("On_Demand")];
+        write(&sheets, &mut archive).unwrap();
+
+        assert!(entry(&archive, "xl/workbook.xml").contains(
+            r#"<sheet name="on_demand (2)" sheetId="2" r:id="rId2"/><sheet name="On_Demand (3)" sheetId="3" r:id="rId3"/>"#
+        ));
+        let worksheet = entry(&archive, "xl/worksheets/sheet2.xml");
+        assert!(worksheet.contains(
+            r#"<row r="2"><c r="A2" t="inlineStr"><is><t xml:space="preserve">A&amp;B</t></is></c><c r="B2"><v>70.08</v></c></row>"#
+        ));
+        // end of central directory with the 3 sheets and 4 other parts
+        assert_eq!(archive[archive.len() - 12..archive.len() - 10], [7, 0]);
+
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(27), "AB");
+        assert!(is_number("-0.096") && is_number("12"));
+        assert!(!is_number("007") && !is_number("20240312153724") && !is_number("1e5"));
+    }
+}
@@ -0,0 +1,157 @@
+use crate::pipeline::Record;
+use crate::transform::aws::diff::OfferDiff;
+use std::collections::{BTreeSet, HashMap};
+
+/// Field of delta exports telling what happened to a record's rate since the base version.
+pub const CHANGE_FIELD: &str = "change";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Insert,
+    /// Insert of a SKU that was listed before and removed since
+    Restore,
+    Update,
+    Delete,
+}
+
+impl Change {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Change::Insert => "insert",
+            Change::Restore => "restore",
+            Change::Update => "update",
+            Change::Delete => "delete",
+        }
+    }
+}
+
+pub fn mark(mut record: Record, change: Change) -> Record {
+    record.insert(CHANGE_FIELD.to_string(), change.as_str().to_string());
+    record
+}
+
+/// Records of the rates `offer_diff` found inserted, restored, updated or deleted, marked with
+/// their change. Deleted records come from `previous`, the records of the base version, all
+/// others from `current`. Records are matched to the diff by their `sku` and `rate_code` fields.
+pub fn delta(offer_diff: &OfferDiff, previous: Vec<Record>, current: Vec<Record>) -> Vec<Record> {
+    let added_skus: BTreeSet<&str> = offer_diff.added_skus.iter().map(String::as_str).collect();
+    let removed_skus: BTreeSet<&str> = offer_diff.removed_skus.iter().map(String::as_str).collect();
+    let restored_skus: BTreeSet<&str> = offer_diff
+        .restored_skus
+        .iter()
+        .map(String::as_str)
+        .collect();
+    let changes: HashMap<&str, _> = offer_diff
+        .changed
+        .iter()
+        .map(|change| (change.rate_code.as_str(), change))
+        .collect();
+    let field = |record: &Record, name: &str| record.get(name).cloned().unwrap_or_default();
+    let current_rate_codes: BTreeSet<String> = current
+        .iter()
+        .map(|record| field(record, "rate_code"))
+        .collect();
+
+    let mut records = Vec::new();
+    for record in current {
+        let sku = field(&record, "sku");
+        let change = if restored_skus.contains(sku.as_str()) {
+            Change::Restore
+        } else if added_skus.contains(sku.as_str()) {
+            Change::Insert
+        } else {
+            match changes.get(field(&record, "rate_code").as_str()) {
+                Some(change) if change.from_usd.is_none() => Change::Insert,
+                Some(_) => Change::Update,
+                None => continue,
+            }
+        };
+        records.push(mark(record, change));
+    }
+    for record in previous {
+        let rate_code = field(&record, "rate_code");
+        let deleted = removed_skus.contains(field(&record, "sku").as_str())
+            || (changes.contains_key(rate_code.as_str())
+                && !current_rate_codes.contains(&rate_code));
+        if deleted {
+            records.push(mark(record, Change::Delete));
+        }
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::delta;
+    use crate::pipeline::Record;
+    use crate::transform::aws::diff::{OfferDiff, PriceChange};
+    use rust_decimal::Decimal;
+
+    fn record(sku: &str, rate_code: &str, price: &str) -> Record {
+        [("sku", sku), ("rate_code", rate_code), ("price_usd", price)]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn change(sku: &str, rate_code: &str, from: Option<i64>, to: Option<i64>) -> PriceChange {
+        PriceChange {
+            rate_code: rate_code.to_string(),
+            sku: sku.to_string(),
+            unit: "Hrs".to_string(),
+            from_usd: from.map(|from| Decimal::new(from, 2)),
+            to_usd: to.map(|to| Decimal::new(to, 2)),
+            change_percent: None,
+        }
+    }
+
+    #[test]
+    fn test_delta() {
+        let offer_diff = OfferDiff {
+            from_version: "20240301000000".to_string(),
+            to_version: "20240401000000".to_string(),
+            added_skus: vec!["BACK".to_string(), "NEW".to_string()],
+            removed_skus: vec!["GONE".to_string()],
+            restored_skus: vec!["BACK".to_string()],
+            changed: vec![
+                change("KEPT", "KEPT.1", Some(10), Some(9)),
+                change("KEPT", "KEPT.2", None, Some(20)),
+                change("KEPT", "KEPT.3", Some(30), None),
+            ],
+        };
+        let previous = vec![
+            record("GONE", "GONE.1", "0.5"),
+            record("KEPT", "KEPT.1", "0.10"),
+            record("KEPT", "KEPT.3", "0.30"),
+            record("SAME", "SAME.1", "0.7"),
+        ];
+        let current = vec![
+            record("BACK", "BACK.1", "0.6"),
+            record("KEPT", "KEPT.1", "0.09"),
+            record("KEPT", "KEPT.2", "0.20"),
+            record("NEW", "NEW.1", "0.4"),
+            record("SAME", "SAME.1", "0.7"),
+        ];
+
+        let changes = delta(&offer_diff, previous, current)
+            .into_iter()
+            .map(|record| {
+                (
+                    record["rate_code"].clone(),
+                    record["change"].clone(),
+                    record["price_usd"].clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let expected = [
+            ("BACK.1", "restore", "0.6"),
+            ("KEPT.1", "update", "0.09"),
+            ("KEPT.2", "insert", "0.20"),
+            ("NEW.1", "insert", "0.4"),
+            ("GONE.1", "delete", "0.5"),
+            ("KEPT.3", "delete", "0.30"),
+        ]
+        .map(|(a, b, c)| (a.to_string(), b.to_string(), c.to_string()));
+        assert_eq!(changes, expected);
+    }
+}
@@ -0,0 +1,562 @@
+//! Declarative fetch, filter and export pipelines defined in the config file
+mod delta;
+mod record;
+mod watermark;
+
+pub use delta::{Change, CHANGE_FIELD};
+pub use record::{dedup, Record, RecordKey, RecordSink, ToRecord};
+pub use watermark::{write_atomically, Watermark};
+
+use crate::dataset::{ComputeSavingsPlan, DatasetKind, Ec2InstanceSavingsPlan, Ec2OnDemand};
+use crate::facade::Pekora;
+use crate::history::OfferMark;
+use crate::output::s3::S3Destination;
+use crate::schema;
+use crate::transform::aws::{diff, on_demand};
+use crate::transform::sink::{JsonLinesWriter, RowSink};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::path::Path;
+
+/// A named pipeline, e.g.
+///
+/// ```toml
+/// [pipelines.tokyo-m5]
+/// source = { dataset = "ec2_on_demand", region = "ap-northeast-1" }
+/// filters = { instanceType = "m5.large", operatingSystem = "Linux" }
+/// export = { format = "csv", path = "tokyo-m5.csv" }
+/// actions = [
+///     { type = "export", format = "json_lines", path = "tokyo-m5.jsonl" },
+///     { type = "upload", bucket = "prices", prefix = "pekora" },
+///     { type = "notify" },
+/// ]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct PipelineConfig {
+    pub source: PipelineSource,
+    /// Rows are kept only if every field equals the given value.
+    #[serde(default)]
+    pub filters: BTreeMap<String, String>,
+    #[serde(default)]
+    pub export: ExportConfig,
+    /// Run in order after the export, unless it was skipped as up to date.
+    #[serde(default)]
+    pub actions: Vec<PipelineAction>,
+}
+
+/// Delivery step run after a pipeline refreshed its export.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PipelineAction {
+    /// Writes the filtered rows to another file as well
+    Export { format: ExportFormat, path: String },
+    /// Uploads every exported file to S3, partitioned by service, region and offer version.
+    /// `run` leaves it to the caller, which holds the AWS config.
+    Upload(S3Destination),
+    /// Sends the report to the notification sinks of the caller. `run` leaves it to the caller.
+    Notify,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "dataset", rename_all = "snake_case")]
+pub enum PipelineSource {
+    Ec2OnDemand { region: String },
+    ComputeSavingsPlan { region: String },
+    Ec2InstanceSavingsPlan { region: String },
+}
+
+impl PipelineSource {
+    /// Service code of the offer the dataset is read from.
+    pub fn service_code(&self) -> &'static str {
+        match self {
+            PipelineSource::Ec2OnDemand { .. } => "AmazonEC2",
+            PipelineSource::ComputeSavingsPlan { .. }
+            | PipelineSource::Ec2InstanceSavingsPlan { .. } => "AWSComputeSavingsPlan",
+        }
+    }
+
+    pub fn region(&self) -> &str {
+        match self {
+            PipelineSource::Ec2OnDemand { region }
+            | PipelineSource::ComputeSavingsPlan { region }
+            | PipelineSource::Ec2InstanceSavingsPlan { region } => region,
+        }
+    }
+}
+
+impl PipelineConfig {
+    /// Files written by the export and export actions.
+    pub fn exported_paths(&self) -> Vec<&str> {
+        let actions = self.actions.iter().filter_map(|action| match action {
+            PipelineAction::Export { path, .. } => Some(path.as_str()),
+            _ => None,
+        });
+        self.export
+            .path
+            .as_deref()
+            .into_iter()
+            .chain(actions)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExportConfig {
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// Output file. Written to stdout if not set. Files are replaced atomically and skipped
+    /// if already exported from the same offer version and schema version.
+    pub path: Option<String>,
+    /// Export only the rows inserted, updated or deleted since the offer version last exported
+    /// to `path`, with a `change` field. Rows of SKUs listed again after being removed are
+    /// marked `restore` rather than `insert`. Everything is exported as inserted the first time.
+    /// Supported for `ec2_on_demand` sources.
+    #[serde(default)]
+    pub delta: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+    #[serde(alias = "jsonl")]
+    #[cfg_attr(feature = "clap", value(alias = "jsonl"))]
+    JsonLines,
+    /// Every field a nullable string column
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineReport {
+    pub offer_version: String,
+    pub rows_read: usize,
+    pub duplicates_dropped: usize,
+    pub rows_written: usize,
+    /// Set if the export was already up to date with the offer version.
+    pub skipped: bool,
+    /// Offer version a delta export was compared against
+    pub delta_from: Option<String>,
+}
+
+impl Display for PipelineReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.skipped {
+            return write!(f, "already exported offer version {}", self.offer_version);
+        }
+        write!(
+            f,
+            "{} rows read, {} duplicates dropped, {} rows written",
+            self.rows_read, self.duplicates_dropped, self.rows_written
+        )?;
+        if let Some(delta_from) = &self.delta_from {
+            write!(f, ", changed since offer version {}", delta_from)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `pipeline`. File exports already produced from the current offer version are skipped
+/// unless `force` is set.
+pub async fn run(
+    pekora: &Pekora,
+    pipeline: &PipelineConfig,
+    force: bool,
+) -> anyhow::Result<PipelineReport> {
+    let delta = pipeline.export.delta;
+    if delta && pipeline.export.path.is_none() {
+        anyhow::bail!("Delta exports need an output path to compare against");
+    }
+    if delta && !matches!(pipeline.source, PipelineSource::Ec2OnDemand { .. }) {
+        anyhow::bail!("Delta exports are only supported for ec2_on_demand");
+    }
+    match &pipeline.source {
+        PipelineSource::Ec2OnDemand { region } => {
+            run_dataset::<Ec2OnDemand>(pekora, pipeline, region.clone(), force).await
+        }
+        PipelineSource::ComputeSavingsPlan { region } => {
+            run_dataset::<ComputeSavingsPlan>(pekora, pipeline, region.clone(), force).await
+        }
+        PipelineSource::Ec2InstanceSavingsPlan { region } => {
+            run_dataset::<Ec2InstanceSavingsPlan>(pekora, pipeline, region.clone(), force).await
+        }
+    }
+}
+
+/// Exports the rows of dataset `T`, flattening each row as it is written. Delta exports
+/// compare all records at once, so those are collected first.
+async fn run_dataset<T>(
+    pekora: &Pekora,
+    pipeline: &PipelineConfig,
+    key: T::Key,
+    force: bool,
+) -> anyhow::Result<PipelineReport>
+where
+    T: DatasetKind,
+    T::Row: ToRecord,
+{
+    let delta = pipeline.export.delta;
+    let dataset = pekora.dataset::<T>(key).await?;
+    let rows = dataset.rows();
+    let metadata = dataset.metadata().clone();
+    let watermark = Watermark {
+        dataset: metadata.kind.to_string(),
+        key: metadata.key,
+        offer_version: metadata.version,
+        filters: pipeline.filters.clone(),
+        format: pipeline.export.format,
+        schema_version: schema::VERSION,
+        delta,
+    };
+    let path = pipeline.export.path.as_deref().map(Path::new);
+    if let Some(path) = path {
+        if !force && watermark.is_current(path) {
+            info!("{} is up to date, skipping export", path.display());
+            return Ok(PipelineReport {
+                offer_version: watermark.offer_version,
+                rows_read: rows.len(),
+                duplicates_dropped: dedup(rows).1,
+                rows_written: 0,
+                skipped: true,
+                delta_from: None,
+            });
+        }
+    }
+
+    let delta_from = path
+        .filter(|_| delta)
+        .and_then(Watermark::read)
+        .filter(|previous| previous.same_source(&watermark))
+        .map(|previous| previous.offer_version);
+    let export = if delta {
+        let mut sink = RecordSink::new(Vec::new(), &pipeline.filters);
+        sink.write_all(rows)?;
+        let duplicates_dropped = sink.duplicates_dropped();
+        let records = match &delta_from {
+            Some(delta_from) => {
+                let region = pipeline.source.region();
+                on_demand_delta(pekora, region, delta_from, &watermark, sink.into_inner()).await?
+            }
+            // nothing to compare against, so everything is new
+            None => sink
+                .into_inner()
+                .into_iter()
+                .map(|record| delta::mark(record, Change::Insert))
+                .collect(),
+        };
+        Export::Records {
+            records,
+            duplicates_dropped,
+        }
+    } else {
+        Export::Rows {
+            rows,
+            filters: &pipeline.filters,
+        }
+    };
+
+    let (duplicates_dropped, rows_written) = match path {
+        Some(path) => export_file(&export, &watermark, path)?,
+        None => export.write(pipeline.export.format, std::io::stdout().lock())?,
+    };
+    for action in &pipeline.actions {
+        if let PipelineAction::Export { format, path } = action {
+            let watermark = Watermark {
+                format: *format,
+                ..watermark.clone()
+            };
+            export_file(&export, &watermark, Path::new(path))?;
+            info!("Exported {} rows to {}", rows_written, path);
+        }
+    }
+    Ok(PipelineReport {
+        offer_version: watermark.offer_version,
+        rows_read: rows.len(),
+        duplicates_dropped,
+        rows_written,
+        skipped: false,
+        delta_from,
+    })
+}
+
+/// Changes of `records`, the on-demand records of the current offer, since `from_version`.
+async fn on_demand_delta(
+    pekora: &Pekora,
+    region: &str,
+    from_version: &str,
+    watermark: &Watermark,
+    records: Vec<Record>,
+) -> anyhow::Result<Vec<Record>> {
+    let from = pekora
+        .fetch_pricing_version("AmazonEC2", region, from_version)
+        .await?;
+    let to = pekora
+        .fetch_pricing_version("AmazonEC2", region, &watermark.offer_version)
+        .await?;
+    let mut offer_diff = diff::diff(&from, &to);
+    drop(to);
+    let from_offer = OfferMark {
+        version: from.version.clone(),
+        publication_date: from.publication_date,
+    };
+    let history = pekora.history::<Ec2OnDemand>(&region.to_string());
+    if let Err(e) = history.mark_restored(&mut offer_diff, &from_offer) {
+        warn!("Failed to read SKU history of {}: {}", region, e);
+    }
+    let mut previous = RecordSink::new(Vec::new(), &watermark.filters);
+    on_demand::pivot_into(from, &mut previous)?;
+    let records = delta::delta(&offer_diff, previous.into_inner(), records);
+    info!(
+        "{} rows changed between offer versions {} and {}",
+        records.len(),
+        offer_diff.from_version,
+        offer_diff.to_version
+    );
+    Ok(records)
+}
+
+/// What a pipeline exports: dataset rows flattened as they are written, or the records of a
+/// delta.
+enum Export<'a, R> {
+    Rows {
+        rows: &'a [R],
+        filters: &'a BTreeMap<String, String>,
+    },
+    Records {
+        records: Vec<Record>,
+        duplicates_dropped: usize,
+    },
+}
+
+impl<R: ToRecord> Export<'_, R> {
+    /// Writes the export in `format`, returning how many rows were dropped as duplicates and how
+    /// many records were written.
+    fn write(&self, format: ExportFormat, writer: impl Write) -> anyhow::Result<(usize, usize)> {
+        match self {
+            Export::Rows { rows, filters } => export_rows(rows, filters, format, writer),
+            Export::Records {
+                records,
+                duplicates_dropped,
+            } => {
+                export(records, format, writer)?;
+                Ok((*duplicates_dropped, records.len()))
+            }
+        }
+    }
+}
+
+/// Writes `export` to `path` in the format of `watermark`, then the watermark next to it.
+fn export_file<R: ToRecord>(
+    export: &Export<'_, R>,
+    watermark: &Watermark,
+    path: &Path,
+) -> anyhow::Result<(usize, usize)> {
+    let mut counts = (0, 0);
+    write_atomically(path, |writer| {
+        counts = export.write(watermark.format, writer)?;
+        Ok(())
+    })?;
+    watermark.write(path)?;
+    Ok(counts)
+}
+
+/// Writes the records of `rows` kept by `filters` in `format`, flattening each row as it is
+/// written. CSV columns are the union of all record fields, found by a first pass over `rows`.
+/// Returns how many rows were dropped as duplicates and how many records were written.
+pub fn export_rows<R: ToRecord>(
+    rows: &[R],
+    filters: &BTreeMap<String, String>,
+    format: ExportFormat,
+    writer: impl Write,
+) -> anyhow::Result<(usize, usize)> {
+    let columns = match format {
+        ExportFormat::Csv => {
+            let mut columns = RecordSink::new(Columns::default(), filters);
+            for row in rows {
+                columns.write(row)?;
+            }
+            columns.into_inner().0
+        }
+        _ => BTreeSet::new(),
+    };
+    let mut sink = RecordSink::new(RecordWriter::new(format, columns, writer)?, filters);
+    sink.write_all(rows)?;
+    Ok((sink.duplicates_dropped(), sink.records_written()))
+}
+
+/// Writes `records` in `format`. CSV and Parquet columns are the union of all record fields.
+pub fn export(records: &[Record], format: ExportFormat, writer: impl Write) -> anyhow::Result<()> {
+    let columns = columns(records).into_iter().cloned().collect();
+    RecordWriter::new(format, columns, writer)?.write_all(records.iter().cloned())
+}
+
+pub(crate) fn columns(records: &[Record]) -> BTreeSet<&String> {
+    records.iter().flat_map(|record| record.keys()).collect()
+}
+
+/// Union of the fields of the records written.
+#[derive(Default)]
+struct Columns(BTreeSet<String>);
+
+impl RowSink<Record> for Columns {
+    fn write(&mut self, record: Record) -> anyhow::Result<()> {
+        self.0.extend(record.into_keys());
+        Ok(())
+    }
+}
+
+/// Writes records in an export format. CSV and JSON lines are written as records arrive, JSON
+/// and Parquet once the last one has.
+enum RecordWriter<W: Write> {
+    Csv {
+        writer: Box<csv::Writer<W>>,
+        columns: Vec<String>,
+    },
+    JsonLines(JsonLinesWriter<W>),
+    Json {
+        writer: Option<W>,
+        records: Vec<Record>,
+    },
+    #[cfg(feature = "parquet")]
+    Parquet {
+        writer: Option<W>,
+        records: Vec<Record>,
+    },
+}
+
+impl<W: Write> RecordWriter<W> {
+    /// `columns` are the CSV header, ignored by other formats.
+    fn new(format: ExportFormat, columns: BTreeSet<String>, writer: W) -> anyhow::Result<Self> {
+        Ok(match format {
+            ExportFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(writer);
+                writer.write_record(&columns)?;
+                RecordWriter::Csv {
+                    writer: Box::new(writer),
+                    columns: columns.into_iter().collect(),
+                }
+            }
+            ExportFormat::Json => RecordWriter::Json {
+                writer: Some(writer),
+                records: Vec::new(),
+            },
+            ExportFormat::JsonLines => RecordWriter::JsonLines(JsonLinesWriter::new(writer)),
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => RecordWriter::Parquet {
+                writer: Some(writer),
+                records: Vec::new(),
+            },
+        })
+    }
+}
+
+impl<W: Write> RowSink<Record> for RecordWriter<W> {
+    fn write(&mut self, record: Record) -> anyhow::Result<()> {
+        match self {
+            RecordWriter::Csv { writer, columns } => writer.write_record(
+                columns
+                    .iter()
+                    .map(|column| record.get(column).map(String::as_str).unwrap_or("")),
+            )?,
+            RecordWriter::JsonLines(writer) => writer.write(record)?,
+            RecordWriter::Json { records, .. } => records.push(record),
+            #[cfg(feature = "parquet")]
+            RecordWriter::Parquet { records, .. } => records.push(record),
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        match self {
+            RecordWriter::Csv { writer, .. } => writer.flush()?,
+            RecordWriter::JsonLines(writer) => RowSink::<Record>::finish(writer)?,
+            RecordWriter::Json { writer, records } => {
+                if let Some(mut writer) = writer.take() {
+                    serde_json::to_writer_pretty(&mut writer, records)?;
+                    writeln!(writer)?;
+                }
+            }
+            #[cfg(feature = "parquet")]
+            RecordWriter::Parquet { writer, records } => {
+                if let Some(writer) = writer.take() {
+                    crate::output::parquet::write_records(records, writer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export, ExportFormat, PipelineAction, PipelineConfig, PipelineSource, Record};
+    use crate::output::s3::S3Destination;
+
+    fn record(fields: &[(&str, &str)]) -> Record {
+        fields
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_pipeline() {
+        let pipeline: PipelineConfig = toml::from_str(
+            r#"
+            source = { dataset = "compute_savings_plan", region = "us-east-1" }
+            filters = { instance_type = "m5.large" }
+            export = { format = "json_lines", path = "out.jsonl" }
+            actions = [
+                { type = "export", format = "csv", path = "out.csv" },
+                { type = "upload", bucket = "prices", prefix = "pekora" },
+                { type = "notify" },
+            ]
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            pipeline.source,
+            PipelineSource::ComputeSavingsPlan { ref region } if region == "us-east-1"
+        ));
+        assert_eq!(pipeline.filters["instance_type"], "m5.large");
+        assert_eq!(pipeline.export.format, ExportFormat::JsonLines);
+        assert_eq!(
+            pipeline.actions,
+            [
+                PipelineAction::Export {
+                    format: ExportFormat::Csv,
+                    path: "out.csv".to_string()
+                },
+                PipelineAction::Upload(S3Destination {
+                    bucket: "prices".to_string(),
+                    prefix: "pekora".to_string(),
+                    region: None,
+                    endpoint: None,
+                }),
+                PipelineAction::Notify
+            ]
+        );
+        assert_eq!(pipeline.exported_paths(), ["out.jsonl", "out.csv"]);
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let records = vec![
+            record(&[("instanceType", "m5.large"), ("price_usd", "0.096")]),
+            record(&[("instanceType", "m5.large"), ("note", "a,b")]),
+        ];
+
+        let mut output = Vec::new();
+        export(&records, ExportFormat::Csv, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "instanceType,note,price_usd\nm5.large,,0.096\nm5.large,\"a,b\",\n"
+        );
+    }
+}
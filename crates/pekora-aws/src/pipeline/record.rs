@@ -0,0 +1,295 @@
+use crate::transform::aws::on_demand::OnDemandRate;
+use crate::transform::aws::savings_plan::PivotedSavingsPlanTermRate;
+use crate::transform::sink::RowSink;
+use chrono::{DateTime, Utc};
+use log::warn;
+use std::collections::{BTreeMap, HashSet};
+
+/// A flattened row, keyed by field name.
+pub type Record = BTreeMap<String, String>;
+
+/// Deterministic primary key of a normalized row. Two rows with the same key describe the same
+/// price, so only one of them may be exported.
+///
+/// | Row                          | sku              | rate_code                 | term                                |
+/// |------------------------------|------------------|---------------------------|-------------------------------------|
+/// | `OnDemandRate`               | product SKU      | price dimension rate code | offer term code                     |
+/// | `PivotedSavingsPlanTermRate` | savings plan SKU | discounted rate code      | contract length and purchase option |
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RecordKey {
+    pub provider: &'static str,
+    pub sku: String,
+    pub rate_code: String,
+    pub effective_date: DateTime<Utc>,
+    pub term: String,
+}
+
+/// Rows that can be flattened for filtering and exporting.
+pub trait ToRecord {
+    fn primary_key(&self) -> RecordKey;
+
+    fn to_record(&self) -> Record;
+}
+
+/// Keeps the first row of each primary key, returning the kept rows and how many were dropped.
+/// Upstream offers occasionally repeat rate codes, which would otherwise be counted twice.
+pub fn dedup<T: ToRecord>(rows: &[T]) -> (Vec<&T>, usize) {
+    let mut seen: HashSet<RecordKey> = HashSet::with_capacity(rows.len());
+    let kept = rows
+        .iter()
+        .filter(|row| seen.insert(row.primary_key()))
+        .collect::<Vec<_>>();
+    let dropped = rows.len() - kept.len();
+    if dropped > 0 {
+        warn!("Dropped {} rows with duplicate primary keys", dropped);
+    }
+    (kept, dropped)
+}
+
+impl<T: ToRecord> ToRecord for &T {
+    fn primary_key(&self) -> RecordKey {
+        (*self).primary_key()
+    }
+
+    fn to_record(&self) -> Record {
+        (*self).to_record()
+    }
+}
+
+/// Flattens rows as they are written and passes the records on to `sink`, dropping repeated
+/// primary keys like `dedup` and the records `filters` leave out.
+pub struct RecordSink<'a, S> {
+    sink: S,
+    filters: &'a BTreeMap<String, String>,
+    seen: HashSet<RecordKey>,
+    duplicates_dropped: usize,
+    records_written: usize,
+}
+
+impl<'a, S> RecordSink<'a, S> {
+    pub fn new(sink: S, filters: &'a BTreeMap<String, String>) -> Self {
+        Self {
+            sink,
+            filters,
+            seen: HashSet::new(),
+            duplicates_dropped: 0,
+            records_written: 0,
+        }
+    }
+
+    pub fn duplicates_dropped(&self) -> usize {
+        self.duplicates_dropped
+    }
+
+    /// Records passed on to the sink so far.
+    pub fn records_written(&self) -> usize {
+        self.records_written
+    }
+
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+}
+
+impl<R: ToRecord, S: RowSink<Record>> RowSink<R> for RecordSink<'_, S> {
+    fn write(&mut self, row: R) -> anyhow::Result<()> {
+        if !self.seen.insert(row.primary_key()) {
+            self.duplicates_dropped += 1;
+            return Ok(());
+        }
+        let record = row.to_record();
+        if !self
+            .filters
+            .iter()
+            .all(|(field, value)| record.get(field) == Some(value))
+        {
+            return Ok(());
+        }
+        self.records_written += 1;
+        self.sink.write(record)
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        if self.duplicates_dropped > 0 {
+            warn!(
+                "Dropped {} rows with duplicate primary keys",
+                self.duplicates_dropped
+            );
+        }
+        self.sink.finish()
+    }
+}
+
+impl ToRecord for OnDemandRate {
+    fn primary_key(&self) -> RecordKey {
+        RecordKey {
+            provider: "aws",
+            sku: self.sku.clone(),
+            rate_code: self.rate_code.clone(),
+            effective_date: self.effective_date,
+            term: self.offer_term_code.clone(),
+        }
+    }
+
+    fn to_record(&self) -> Record {
+        let mut record: Record = self
+            .attributes
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        record.insert("provider".to_string(), "aws".to_string());
+        record.insert("sku".to_string(), self.sku.clone());
+        record.insert("product_family".to_string(), self.product_family.clone());
+        record.insert("offer_term_code".to_string(), self.offer_term_code.clone());
+        record.insert(
+            "effective_date".to_string(),
+            self.effective_date.to_rfc3339(),
+        );
+        record.insert("rate_code".to_string(), self.rate_code.clone());
+        record.insert("description".to_string(), self.description.clone());
+        record.insert("unit".to_string(), self.unit.clone());
+        for (currency, price) in &self.price_per_unit {
+            record.insert(
+                format!("price_{}", currency.to_lowercase()),
+                price.raw().to_string(),
+            );
+        }
+        record
+    }
+}
+
+impl ToRecord for PivotedSavingsPlanTermRate {
+    fn primary_key(&self) -> RecordKey {
+        RecordKey {
+            provider: "aws",
+            sku: self.savings_plan_sku.clone(),
+            rate_code: self.term_rate.rate_code.clone(),
+            effective_date: self.savings_plan_effective_date,
+            term: format!(
+                "{} {} {:?}",
+                self.lease_contract_length.duration,
+                self.lease_contract_length.unit,
+                self.savings_plan_attributes.purchase_option
+            ),
+        }
+    }
+
+    fn to_record(&self) -> Record {
+        let attributes = &self.savings_plan_attributes;
+        let mut record = Record::new();
+        record.insert("provider".to_string(), "aws".to_string());
+        record.insert("sku".to_string(), self.savings_plan_sku.clone());
+        record.insert("rate_code".to_string(), self.term_rate.rate_code.clone());
+        record.insert(
+            "effective_date".to_string(),
+            self.savings_plan_effective_date.to_rfc3339(),
+        );
+        record.insert(
+            "discounted_sku".to_string(),
+            self.term_rate.discounted_sku.clone(),
+        );
+        record.insert(
+            "usage_type".to_string(),
+            self.term_rate.discounted_usage_type.clone(),
+        );
+        record.insert(
+            "operation".to_string(),
+            self.term_rate.discounted_operation.clone(),
+        );
+        record.insert(
+            "service_code".to_string(),
+            self.term_rate.discounted_service_code.clone(),
+        );
+        record.insert(
+            "plan_type".to_string(),
+            attributes.product_family.as_str().to_string(),
+        );
+        if let Some(instance_type) = &attributes.instance_type {
+            record.insert("instance_type".to_string(), instance_type.clone());
+        }
+        if let Some(region_code) = &attributes.region_code {
+            record.insert("region".to_string(), region_code.clone());
+        }
+        record.insert(
+            "purchase_option".to_string(),
+            format!("{:?}", attributes.purchase_option),
+        );
+        record.insert(
+            "term".to_string(),
+            format!("{:?}", attributes.purchase_term),
+        );
+        record.insert(
+            "location_type".to_string(),
+            format!("{:?}", attributes.location_type),
+        );
+        record.insert("unit".to_string(), self.term_rate.unit.clone());
+        record.insert(
+            "rate".to_string(),
+            self.term_rate.discounted_rate.price.raw().to_string(),
+        );
+        record.insert(
+            "currency".to_string(),
+            format!("{:?}", self.term_rate.discounted_rate.currency),
+        );
+        record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dedup, RecordSink, ToRecord};
+    use crate::api::aws::types::Price;
+    use crate::transform::aws::on_demand::OnDemandRate;
+    use crate::transform::sink::{JsonLinesWriter, RowSink};
+    use chrono::{TimeZone, Utc};
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::Arc;
+
+    fn rate(sku: &str, rate_code: &str) -> OnDemandRate {
+        OnDemandRate {
+            sku: sku.to_string(),
+            product_family: "Compute Instance".to_string(),
+            attributes: Arc::new(HashMap::new()),
+            offer_term_code: "JRTCKXETXF".to_string(),
+            effective_date: Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap(),
+            rate_code: rate_code.to_string(),
+            description: String::new(),
+            unit: "Hrs".to_string(),
+            price_per_unit: HashMap::from([("USD".to_string(), Price::new("0.096"))]),
+        }
+    }
+
+    #[test]
+    fn test_dedup_by_primary_key() {
+        let rows = vec![
+            rate("SKU1", "SKU1.JRTCKXETXF.A"),
+            rate("SKU1", "SKU1.JRTCKXETXF.A"),
+            rate("SKU1", "SKU1.JRTCKXETXF.B"),
+        ];
+        let (kept, dropped) = dedup(&rows);
+        assert_eq!(dropped, 1);
+        assert_eq!(kept.len(), 2);
+        assert_ne!(kept[0].primary_key(), kept[1].primary_key());
+        assert_eq!(
+            kept[0].to_record()["effective_date"],
+            "2024-03-01T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_record_sink() {
+        let rows = vec![
+            rate("SKU1", "SKU1.JRTCKXETXF.A"),
+            rate("SKU1", "SKU1.JRTCKXETXF.A"),
+            rate("SKU1", "SKU1.JRTCKXETXF.B"),
+        ];
+        let filters = BTreeMap::from([("rate_code".to_string(), "SKU1.JRTCKXETXF.B".to_string())]);
+        let mut sink = RecordSink::new(JsonLinesWriter::new(Vec::new()), &filters);
+        sink.write_all(&rows).unwrap();
+        assert_eq!(sink.duplicates_dropped(), 1);
+        assert_eq!(sink.records_written(), 1);
+        let output = String::from_utf8(sink.into_inner().into_inner()).unwrap();
+        assert_eq!(output.lines().count(), 1);
+        assert!(output.contains("\"rate_code\":\"SKU1.JRTCKXETXF.B\""));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::pipeline::ExportFormat;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// What an export file was last produced from, stored next to it as `{path}.watermark`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Watermark {
+    pub dataset: String,
+    pub key: String,
+    pub offer_version: String,
+    pub filters: BTreeMap<String, String>,
+    pub format: ExportFormat,
+    /// `schema::VERSION` the export was written with, 0 for exports older than schema versions
+    #[serde(default)]
+    pub schema_version: u32,
+    /// Whether only the changes since the previous export were written
+    #[serde(default)]
+    pub delta: bool,
+}
+
+impl Watermark {
+    pub fn read(path: &Path) -> Option<Self> {
+        let contents = std::fs::read_to_string(sidecar_path(path)).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    /// Whether `path` was already exported from the same offer version with the same settings.
+    pub fn is_current(&self, path: &Path) -> bool {
+        path.exists() && Self::read(path).as_ref() == Some(self)
+    }
+
+    /// Whether `self` and `other` were exported from the same dataset and filters, in any
+    /// format, so changes between their offer versions can be exported as a delta.
+    pub fn same_source(&self, other: &Self) -> bool {
+        self.dataset == other.dataset
+            && self.key == other.key
+            && self.filters == other.filters
+            && self.schema_version == other.schema_version
+    }
+
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        write_atomically(&sidecar_path(path), |writer| {
+            serde_json::to_writer(writer, self)?;
+            Ok(())
+        })
+    }
+}
+
+/// Writes to a temporary file renamed over `path` once complete, so readers never see a
+/// partially written file and a failed run leaves the previous file intact.
+pub fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temporary_path = with_suffix(path, "tmp");
+    let result = File::create(&temporary_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            write(&mut writer)?;
+            writer.flush()?;
+            writer.get_ref().sync_all()?;
+            Ok(())
+        });
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temporary_path);
+        return Err(e);
+    }
+    std::fs::rename(&temporary_path, path)?;
+    Ok(())
+}
+
+fn sidecar_path(path: &Path) -> PathBuf {
+    with_suffix(path, "watermark")
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_atomically, Watermark};
+    use crate::pipeline::ExportFormat;
+    use std::collections::BTreeMap;
+    use std::io::Write;
+    use std::path::Path;
+
+    #[test]
+    fn test_watermark() {
+        let path = Path::new("test_cache/watermark/export.csv");
+        let _ = std::fs::remove_dir_all("test_cache/watermark");
+        let watermark = Watermark {
+            dataset: "aws/ec2/on_demand".to_string(),
+            key: "us-east-1".to_string(),
+            offer_version: "20240312153724".to_string(),
+            filters: BTreeMap::new(),
+            format: ExportFormat::Csv,
+            schema_version: 1,
+            delta: false,
+        };
+        assert!(!watermark.is_current(path));
+
+        write_atomically(path, |writer| Ok(writer.write_all(b"a,b\n")?)).unwrap();
+        watermark.write(path).unwrap();
+        assert!(watermark.is_current(path));
+
+        let newer = Watermark {
+            offer_version: "20240401000000".to_string(),
+            ..watermark.clone()
+        };
+        assert!(!newer.is_current(path));
+        let migrated = Watermark {
+            schema_version: 2,
+            ..watermark.clone()
+        };
+        assert!(!migrated.is_current(path));
+
+        let failed = write_atomically(path, |writer| {
+            writer.write_all(b"partial")?;
+            anyhow::bail!("export failed")
+        });
+        assert!(failed.is_err());
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "a,b\n");
+    }
+}
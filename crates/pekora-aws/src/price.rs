@@ -0,0 +1,434 @@
+//! Resolves a single price from the bulk files, recording each step so surprising results can
+//! be traced back to the file, SKU, term and dimension they came from
+use crate::api::aws::price_bulk_types::PricingListResponse;
+use crate::api::aws::types::{PriceDimension, RIOfferingClass};
+use crate::facade::Pekora;
+use crate::transform::aws::effective_rate::reserved_offering;
+use crate::transform::aws::recommendation::Commitment;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+#[derive(Debug, Clone)]
+pub struct PriceQuery {
+    pub service_code: String,
+    pub region: String,
+    /// Product attribute to exact value. `productFamily` matches the product family.
+    pub filters: Vec<(String, String)>,
+    /// Standard reserved term to price, on-demand if `None`
+    pub reservation: Option<Commitment>,
+    /// Unit of the dimension to pick, e.g. `Hrs`. Any unit if `None`.
+    pub unit: Option<String>,
+}
+
+/// One step of resolving a price, from the service index down to the number.
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceStep {
+    pub stage: &'static str,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedPrice {
+    pub sku: String,
+    pub rate_code: String,
+    pub unit: String,
+    /// Reserved prices have upfront fees amortized over the term
+    pub usd: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceResolution {
+    /// `None` if a step found nothing, which the last trace step explains
+    pub price: Option<ResolvedPrice>,
+    pub trace: Vec<TraceStep>,
+}
+
+impl PriceResolution {
+    fn step(&mut self, stage: &'static str, detail: String) {
+        self.trace.push(TraceStep { stage, detail });
+    }
+
+    fn fail(mut self, stage: &'static str, detail: String) -> Self {
+        self.step(stage, detail);
+        self
+    }
+}
+
+/// Price of one SKU under its selected term and dimension.
+struct Candidate<'a> {
+    sku: &'a str,
+    term: String,
+    dimension: &'a PriceDimension,
+    dimension_detail: String,
+    usd: Decimal,
+}
+
+/// Resolves the price matching `query` in the current offer of its service and region. If
+/// several SKUs match, the cheapest is picked.
+pub async fn resolve(pekora: &Pekora, query: &PriceQuery) -> anyhow::Result<PriceResolution> {
+    let mut resolution = PriceResolution {
+        price: None,
+        trace: Vec::new(),
+    };
+    let service_index = pekora
+        .cacheable_builder()
+        .build(pekora.clients().service_index())
+        .load(&())
+        .await?
+        .result;
+    let offer = match service_index.offers.get(&query.service_code) {
+        Some(offer) => offer,
+        None => {
+            return Ok(resolution.fail(
+                "service index",
+                format!("{} is not in the service index", query.service_code),
+            ))
+        }
+    };
+    resolution.step(
+        "service index",
+        format!(
+            "{} listed in the index published {}, region index {}",
+            offer.offer_code,
+            service_index.publication_date.to_rfc3339(),
+            offer.current_region_index_url.as_deref().unwrap_or("-")
+        ),
+    );
+
+    let region_index = pekora
+        .cacheable_builder()
+        .build(pekora.clients().region_index())
+        .load(&query.service_code)
+        .await?
+        .result;
+    let offer = match region_index.regions.get(&query.region) {
+        Some(region) => region.current_version_url.clone(),
+        None => {
+            return Ok(resolution.fail(
+                "region index",
+                format!("{} has no offer in {}", query.service_code, query.region),
+            ))
+        }
+    };
+    resolution.step(
+        "region index",
+        format!(
+            "{} in {}: current offer version {}",
+            query.service_code, query.region, offer.offer_version
+        ),
+    );
+
+    let loaded = pekora
+        .cacheable_builder()
+        .build(pekora.clients().pricing_list())
+        .load(&offer)
+        .await?;
+    let response = loaded.result;
+    resolution.step(
+        "offer version",
+        format!(
+            "version {} published {}, {} products, {}",
+            response.version,
+            response.publication_date.to_rfc3339(),
+            response.products.len(),
+            if loaded.cache_hit {
+                "from cache"
+            } else {
+                "downloaded"
+            }
+        ),
+    );
+
+    let mut skus = response
+        .products
+        .values()
+        .filter(|product| {
+            query.filters.iter().all(|(field, value)| {
+                let actual = match field.as_str() {
+                    "productFamily" => Some(&product.product_family),
+                    _ => product.attributes.get(field),
+                };
+                actual == Some(value)
+            })
+        })
+        .map(|product| product.sku.as_str())
+        .collect::<Vec<_>>();
+    skus.sort();
+    let criteria = query
+        .filters
+        .iter()
+        .map(|(field, value)| format!("{}={}", field, value))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if skus.is_empty() {
+        return Ok(resolution.fail("SKU match", format!("no product matches {}", criteria)));
+    }
+    resolution.step(
+        "SKU match",
+        format!(
+            "{} matched {} of {} products: {}{}",
+            if criteria.is_empty() {
+                "no filters"
+            } else {
+                criteria.as_str()
+            },
+            skus.len(),
+            response.products.len(),
+            skus.iter().take(5).copied().collect::<Vec<_>>().join(", "),
+            if skus.len() > 5 { ", ..." } else { "" }
+        ),
+    );
+
+    let mut candidates = skus
+        .iter()
+        .filter_map(|sku| candidate(&response, sku, query))
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| a.usd.cmp(&b.usd).then(a.sku.cmp(b.sku)));
+    let term_kind = match &query.reservation {
+        Some(commitment) => format!(
+            "standard reserved {} {}",
+            commitment.term.as_str(),
+            commitment.payment_option.as_str()
+        ),
+        None => "on-demand".to_string(),
+    };
+    let unit = query.unit.as_deref().unwrap_or("any unit");
+    let chosen = match candidates.first() {
+        Some(chosen) => chosen,
+        None => {
+            return Ok(resolution.fail(
+                "term selection",
+                format!(
+                    "no matched SKU has a {} term priced in USD per {}",
+                    term_kind, unit
+                ),
+            ))
+        }
+    };
+    let mut selection = format!("{} of {}", chosen.term, chosen.sku);
+    if candidates.len() > 1 {
+        selection.push_str(&format!(
+            ", the cheapest of {} SKUs with a {} term; add filters to narrow",
+            candidates.len(),
+            term_kind
+        ));
+    }
+    resolution.step("term selection", selection);
+    resolution.step("dimension", chosen.dimension_detail.clone());
+    resolution.step(
+        "price",
+        format!(
+            "{} USD per {}",
+            chosen.usd.normalize(),
+            chosen.dimension.unit
+        ),
+    );
+    resolution.price = Some(ResolvedPrice {
+        sku: chosen.sku.to_string(),
+        rate_code: chosen.dimension.rate_code.clone(),
+        unit: chosen.dimension.unit.clone(),
+        usd: chosen.usd,
+    });
+    Ok(resolution)
+}
+
+fn candidate<'a>(
+    response: &'a PricingListResponse,
+    sku: &'a str,
+    query: &PriceQuery,
+) -> Option<Candidate<'a>> {
+    let unit_matches = |dimension: &&PriceDimension| {
+        query
+            .unit
+            .as_ref()
+            .is_none_or(|unit| &dimension.unit == unit)
+    };
+    match &query.reservation {
+        None => response
+            .terms
+            .on_demand
+            .get(sku)?
+            .values()
+            .flat_map(|offering| {
+                offering
+                    .price_dimensions
+                    .values()
+                    .filter(unit_matches)
+                    .map(move |dimension| (offering, dimension))
+            })
+            .filter_map(|(offering, dimension)| Some((offering, dimension, dimension.usd()?)))
+            // The first tier is what a single unit costs
+            .min_by_key(|(_, dimension, usd)| (dimension.begin(), *usd))
+            .map(|(offering, dimension, usd)| Candidate {
+                sku,
+                term: format!(
+                    "OnDemand term {} effective {}",
+                    offering.offer_term_code,
+                    offering.effective_date.to_rfc3339()
+                ),
+                dimension,
+                dimension_detail: format!(
+                    "{}: {} USD per {}{} ({})",
+                    dimension.rate_code,
+                    usd.normalize(),
+                    dimension.unit,
+                    if dimension.is_tiered() {
+                        format!(
+                            ", tier from {} to {}",
+                            dimension.begin(),
+                            dimension
+                                .end()
+                                .map_or("Inf".to_string(), |end| end.to_string())
+                        )
+                    } else {
+                        String::new()
+                    },
+                    dimension.description
+                ),
+                usd,
+            }),
+        Some(commitment) => {
+            let offering = response
+                .terms
+                .reserved
+                .get(sku)?
+                .values()
+                .find(|offering| {
+                    let attributes = &offering.term_attributes;
+                    attributes.lease_contract_length == commitment.term
+                        && attributes.purchase_option == commitment.payment_option
+                        && attributes.offering_class == Some(RIOfferingClass::Standard)
+                })?;
+            let dimension = offering
+                .price_dimensions
+                .values()
+                .filter(unit_matches)
+                .find(|dimension| dimension.unit == "Hrs")?;
+            let rate = reserved_offering(sku, offering, None)?;
+            Some(Candidate {
+                sku,
+                term: format!(
+                    "Reserved term {} effective {}",
+                    offering.offer_term_code,
+                    offering.effective_date.to_rfc3339()
+                ),
+                dimension,
+                dimension_detail: format!(
+                    "{}: {} USD per Hrs plus {} USD upfront spread over the term",
+                    dimension.rate_code,
+                    rate.recurring_usd_per_hour.normalize(),
+                    rate.upfront_usd.normalize()
+                ),
+                usd: rate.effective_usd_per_hour,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve, PriceQuery};
+    use crate::api::aws::price_bulk_builder::PriceBulkClientBuilder;
+    use crate::facade::Pekora;
+    use crate::transform::aws::recommendation::Commitment;
+    use crate::util::testing::serve;
+    use axum::http::header;
+    use axum::routing::get;
+    use axum::Router;
+    use rust_decimal::Decimal;
+
+    const SERVICE_INDEX_BODY: &str = r#"{"formatVersion": "v1.0",
+        "publicationDate": "2024-03-12T15:37:24Z",
+        "offers": {"AmazonEC2": {"offerCode": "AmazonEC2",
+            "currentRegionIndexUrl": "/offers/v1.0/aws/AmazonEC2/current/region_index.json"}}}"#;
+
+    const REGION_INDEX_BODY: &str = r#"{"formatVersion": "v1.0",
+        "publicationDate": "2024-03-12T15:37:24Z",
+        "regions": {"us-east-1": {"regionCode": "us-east-1",
+            "currentVersionUrl": "/offers/v1.0/aws/AmazonEC2/20240312153724/us-east-1/index.json"}}}"#;
+
+    const PRICING_LIST_BODY: &str = r#"{"formatVersion": "v1.0",
+        "publicationDate": "2024-03-12T15:37:24Z", "version": "20240312153724",
+        "products": {
+            "SKU1": {"sku": "SKU1", "productFamily": "Compute Instance", "attributes": {
+                "instanceType": "m5.large", "operatingSystem": "Linux"}},
+            "SKU2": {"sku": "SKU2", "productFamily": "Compute Instance", "attributes": {
+                "instanceType": "m5.large", "operatingSystem": "Windows"}}},
+        "terms": {
+            "OnDemand": {
+                "SKU1": {"SKU1.JRTCKXETXF": {"offerTermCode": "JRTCKXETXF", "sku": "SKU1",
+                    "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {},
+                    "priceDimensions": {"SKU1.JRTCKXETXF.6YS6EN2CT7": {
+                        "rateCode": "SKU1.JRTCKXETXF.6YS6EN2CT7", "description": "Linux",
+                        "unit": "Hrs", "pricePerUnit": {"USD": "0.0960000000"}}}}},
+                "SKU2": {"SKU2.JRTCKXETXF": {"offerTermCode": "JRTCKXETXF", "sku": "SKU2",
+                    "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {},
+                    "priceDimensions": {"SKU2.JRTCKXETXF.6YS6EN2CT7": {
+                        "rateCode": "SKU2.JRTCKXETXF.6YS6EN2CT7", "description": "Windows",
+                        "unit": "Hrs", "pricePerUnit": {"USD": "0.1880000000"}}}}}},
+            "Reserved": {}}}"#;
+
+    #[tokio::test]
+    async fn test_resolve() {
+        let router = Router::new()
+            .route(
+                "/offers/v1.0/aws/index.json",
+                get(|| async { ([(header::ETAG, "\"v1\"")], SERVICE_INDEX_BODY) }),
+            )
+            .route(
+                "/offers/v1.0/aws/AmazonEC2/current/region_index.json",
+                get(|| async { REGION_INDEX_BODY }),
+            )
+            .route(
+                "/offers/v1.0/aws/AmazonEC2/20240312153724/us-east-1/index.json",
+                get(|| async { PRICING_LIST_BODY }),
+            );
+        let base_url = serve(router).await;
+        let cache_directory = std::env::temp_dir()
+            .join(format!("pekora-price-{}", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let clients = PriceBulkClientBuilder::new()
+            .base_url(base_url)
+            .download_directory(cache_directory.clone())
+            .build()
+            .unwrap();
+        let pekora = Pekora::new(clients, Some(cache_directory.clone()), None);
+        let mut query = PriceQuery {
+            service_code: "AmazonEC2".to_string(),
+            region: "us-east-1".to_string(),
+            filters: vec![("instanceType".to_string(), "m5.large".to_string())],
+            reservation: None,
+            unit: Some("Hrs".to_string()),
+        };
+
+        let resolution = resolve(&pekora, &query).await.unwrap();
+        let price = resolution.price.unwrap();
+        assert_eq!(price.sku, "SKU1");
+        assert_eq!(price.usd, Decimal::new(96, 3));
+        let stages = resolution
+            .trace
+            .iter()
+            .map(|step| step.stage)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            stages,
+            vec![
+                "service index",
+                "region index",
+                "offer version",
+                "SKU match",
+                "term selection",
+                "dimension",
+                "price"
+            ]
+        );
+        assert!(resolution.trace[4].detail.contains("cheapest of 2 SKUs"));
+
+        query.reservation = Some(Commitment::parse("1yr", "AllUpfront").unwrap());
+        let resolution = resolve(&pekora, &query).await.unwrap();
+        assert!(resolution.price.is_none());
+        assert_eq!(resolution.trace.last().unwrap().stage, "term selection");
+        std::fs::remove_dir_all(&cache_directory).unwrap();
+    }
+}
@@ -0,0 +1,222 @@
+//! Appending normalized rows to ClickHouse through its HTTP interface. Every row is tagged with
+//! the offer version and publication date it was read from, so each crawl adds to a price
+//! history instead of replacing the prices of the previous one.
+use crate::dataset::DatasetMetadata;
+use crate::store::{price_row_values, PRICE_ROW_COLUMNS, PRICE_ROW_NOT_NULL};
+use crate::transform::aws::normalize::NormalizedPriceRow;
+use chrono::{DateTime, Utc};
+use log::info;
+use pekora_core::transform::sink::{JsonLinesWriter, RowSink};
+use serde_json::{Map, Value};
+
+/// Table created if missing before every append. Rows of an offer version appended twice are
+/// merged by the table engine; query with `FINAL` to never see both.
+pub const SCHEMA: &str = "\
+CREATE TABLE IF NOT EXISTS pekora_price_history (
+    region LowCardinality(String),
+    service_code LowCardinality(String),
+    sku String,
+    instance_type Nullable(String),
+    platform Nullable(String),
+    component LowCardinality(String),
+    purchase_model LowCardinality(String),
+    term LowCardinality(String),
+    purchase_option LowCardinality(String),
+    offering_class LowCardinality(String),
+    effective_usd_per_hour Decimal(38, 10),
+    dataset LowCardinality(String),
+    offer_version String,
+    publication_date DateTime64(3, 'UTC'),
+    loaded_at DateTime64(3, 'UTC')
+)
+ENGINE = ReplacingMergeTree(loaded_at)
+PARTITION BY toYYYYMM(publication_date)
+ORDER BY (service_code, region, sku, component, purchase_model, term, purchase_option, offering_class, publication_date, offer_version)
+";
+
+pub struct ClickHouseWriter {
+    client: reqwest::Client,
+    /// HTTP interface URL, e.g. `http://localhost:8123/?database=prices`
+    url: String,
+}
+
+impl ClickHouseWriter {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.into(),
+        }
+    }
+
+    /// Creates the history table if missing.
+    pub async fn create_table(&self) -> anyhow::Result<()> {
+        self.execute(SCHEMA, String::new()).await
+    }
+
+    /// Appends `rows` read from the offer of `metadata`, in one insert.
+    pub async fn append(
+        &self,
+        metadata: &DatasetMetadata,
+        rows: &[NormalizedPriceRow],
+    ) -> anyhow::Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let body = history_rows(metadata, Utc::now(), rows)?;
+        self.execute("INSERT INTO pekora_price_history FORMAT JSONEachRow", body)
+            .await?;
+        info!(
+            "Appended {} rows of {} {} version {} to ClickHouse",
+            rows.len(),
+            metadata.kind,
+            metadata.key,
+            metadata.version
+        );
+        Ok(())
+    }
+
+    async fn execute(&self, query: &str, body: String) -> anyhow::Result<()> {
+        let response = self
+            .client
+            .post(&self.url)
+            .query(&[("query", query)])
+            .body(body)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            anyhow::bail!("ClickHouse returned {}: {}", status, message.trim());
+        }
+        Ok(())
+    }
+}
+
+/// `JSONEachRow` body of `rows`, tagged with the offer of `metadata`.
+fn history_rows(
+    metadata: &DatasetMetadata,
+    loaded_at: DateTime<Utc>,
+    rows: &[NormalizedPriceRow],
+) -> anyhow::Result<String> {
+    let mut writer = JsonLinesWriter::new(Vec::new());
+    for row in rows {
+        let mut object = PRICE_ROW_COLUMNS
+            .iter()
+            .zip(price_row_values(row))
+            .map(|(column, value)| {
+                let value = match value {
+                    Some(value) => Value::String(value),
+                    None if PRICE_ROW_NOT_NULL.contains(column) => Value::String(String::new()),
+                    None => Value::Null,
+                };
+                (column.to_string(), value)
+            })
+            .collect::<Map<_, _>>();
+        object.insert("dataset".to_string(), metadata.kind.into());
+        object.insert("offer_version".to_string(), metadata.version.clone().into());
+        object.insert(
+            "publication_date".to_string(),
+            timestamp(metadata.publication_date).into(),
+        );
+        object.insert("loaded_at".to_string(), timestamp(loaded_at).into());
+        writer.write(Value::Object(object))?;
+    }
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+/// Format `DateTime64` columns parse without `date_time_input_format` set
+fn timestamp(date: DateTime<Utc>) -> String {
+    date.format("%Y-%m-%d %H:%M:%S%.3f").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ClickHouseWriter;
+    use crate::cache::CacheKey;
+    use crate::dataset::DatasetMetadata;
+    use crate::facade::DataSource;
+    use crate::transform::aws::normalize::{NormalizedPriceRow, PurchaseModel};
+    use crate::util::testing::serve;
+    use axum::extract::{Query, State};
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use axum::Router;
+    use chrono::{TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    type Requests = Arc<Mutex<Vec<(String, String)>>>;
+
+    async fn record(
+        State(requests): State<Requests>,
+        Query(params): Query<HashMap<String, String>>,
+        body: String,
+    ) -> StatusCode {
+        requests
+            .lock()
+            .unwrap()
+            .push((params["query"].clone(), body));
+        StatusCode::OK
+    }
+
+    #[tokio::test]
+    async fn test_append() {
+        let requests = Requests::default();
+        let url = serve(
+            Router::new()
+                .route("/", post(record))
+                .with_state(requests.clone()),
+        )
+        .await;
+        let metadata = DatasetMetadata {
+            kind: "ec2_on_demand",
+            key: "us-east-1".to_string(),
+            version: "20240301000000".to_string(),
+            publication_date: Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap(),
+            cache_key: CacheKey {
+                content_key: None,
+                content_hash: None,
+            },
+            hash_source: None,
+            cache_hit: false,
+            source: DataSource::Network,
+            loaded_at: Utc::now(),
+            row_count: 1,
+            fingerprint: String::new(),
+        };
+        let row = NormalizedPriceRow {
+            region: Some("us-east-1".to_string()),
+            service_code: Some("AmazonEC2".to_string()),
+            sku: "ABC".to_string(),
+            instance_type: Some("m5.large".to_string()),
+            platform: Some("Linux".to_string()),
+            component: None,
+            purchase_model: PurchaseModel::OnDemand,
+            term: None,
+            purchase_option: None,
+            offering_class: None,
+            effective_usd_per_hour: Decimal::new(96, 3),
+        };
+
+        let writer = ClickHouseWriter::new(url);
+        writer.create_table().await.unwrap();
+        writer.append(&metadata, &[row]).await.unwrap();
+        writer.append(&metadata, &[]).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0]
+            .0
+            .starts_with("CREATE TABLE IF NOT EXISTS pekora_price_history"));
+        let (query, body) = &requests[1];
+        assert_eq!(query, "INSERT INTO pekora_price_history FORMAT JSONEachRow");
+        let row: serde_json::Value = serde_json::from_str(body.trim_end()).unwrap();
+        assert_eq!(row["offer_version"], "20240301000000");
+        assert_eq!(row["publication_date"], "2024-03-01 00:00:00.000");
+        assert_eq!(row["effective_usd_per_hour"], "0.096");
+        assert_eq!(row["term"], "");
+        assert_eq!(row["offering_class"], "");
+        assert_eq!(row["component"], "");
+    }
+}
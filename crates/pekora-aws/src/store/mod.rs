@@ -0,0 +1,72 @@
+//! Loading datasets into external databases. PostgreSQL needs the `postgres` feature, SQLite
+//! and DuckDB database files the `sqlite` or `duckdb` feature.
+pub mod clickhouse;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(any(feature = "sqlite", feature = "duckdb"))]
+pub mod sqlite;
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "duckdb"))]
+mod upsert;
+
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "duckdb"))]
+pub(crate) use upsert::{first_of_each_key, Table};
+
+use crate::transform::aws::normalize::NormalizedPriceRow;
+
+/// Columns of normalized price rows, in the order of `price_row_values`.
+pub(crate) const PRICE_ROW_COLUMNS: &[&str] = &[
+    "region",
+    "service_code",
+    "sku",
+    "instance_type",
+    "platform",
+    "component",
+    "purchase_model",
+    "term",
+    "purchase_option",
+    "offering_class",
+    "effective_usd_per_hour",
+];
+
+/// Standard and convertible reservations of a SKU only differ in `offering_class`, and rows of
+/// SageMaker SKUs priced for several components in `component`
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "duckdb"))]
+pub(crate) const PRICE_ROW_PRIMARY_KEY: &[&str] = &[
+    "sku",
+    "component",
+    "purchase_model",
+    "term",
+    "purchase_option",
+    "offering_class",
+];
+
+/// Columns of normalized price rows storing absent values as empty strings
+pub(crate) const PRICE_ROW_NOT_NULL: &[&str] = &[
+    "region",
+    "service_code",
+    "component",
+    "term",
+    "purchase_option",
+    "offering_class",
+];
+
+pub(crate) fn price_row_values(row: &NormalizedPriceRow) -> Vec<Option<String>> {
+    vec![
+        row.region.clone(),
+        row.service_code.clone(),
+        Some(row.sku.clone()),
+        row.instance_type.clone(),
+        row.platform.clone(),
+        row.component
+            .map(|component| component.as_str().to_string()),
+        Some(row.purchase_model.as_str().to_string()),
+        row.term.as_ref().map(|term| term.as_str().to_string()),
+        row.purchase_option
+            .as_ref()
+            .map(|purchase_option| purchase_option.as_str().to_string()),
+        row.offering_class
+            .as_ref()
+            .map(|offering_class| offering_class.as_str().to_string()),
+        Some(row.effective_usd_per_hour.to_string()),
+    ]
+}
@@ -0,0 +1,405 @@
+//! Loading into PostgreSQL through sqlx. Rows are upserted in batches within one transaction, so
+//! loading the same offers again updates rows in place and a failed load changes nothing.
+use crate::api::aws::price_bulk_types::ServiceListResponse;
+use crate::dataset::DatasetMetadata;
+use crate::store::{
+    first_of_each_key, Table, PRICE_ROW_COLUMNS, PRICE_ROW_NOT_NULL, PRICE_ROW_PRIMARY_KEY,
+};
+use crate::transform::aws::normalize::NormalizedPriceRow;
+use crate::transform::aws::savings_plan::PivotedSavingsPlanTermRate;
+use anyhow::Context;
+use log::info;
+use sqlx::postgres::{PgPool, PgPoolOptions, Postgres};
+use sqlx::query_builder::Separated;
+use sqlx::{QueryBuilder, Transaction};
+
+/// Bind parameters of a statement are limited to 65535, so upserts are split into batches.
+const MAX_BIND_PARAMETERS: usize = 65535;
+
+/// Tables created if missing before every load.
+pub const SCHEMA: &str = "\
+CREATE TABLE IF NOT EXISTS pekora_services (
+    service_code text PRIMARY KEY,
+    offer_code text NOT NULL,
+    current_version_url text,
+    current_region_index_url text,
+    current_savings_plan_index_url text,
+    publication_date timestamptz NOT NULL
+);
+CREATE TABLE IF NOT EXISTS pekora_offers (
+    dataset text NOT NULL,
+    key text NOT NULL,
+    version text NOT NULL,
+    publication_date timestamptz NOT NULL,
+    row_count bigint NOT NULL,
+    loaded_at timestamptz NOT NULL,
+    PRIMARY KEY (dataset, key)
+);
+CREATE TABLE IF NOT EXISTS pekora_price_rows (
+    region text NOT NULL,
+    service_code text NOT NULL,
+    sku text NOT NULL,
+    instance_type text,
+    platform text,
+    component text NOT NULL,
+    purchase_model text NOT NULL,
+    term text NOT NULL,
+    purchase_option text NOT NULL,
+    offering_class text NOT NULL,
+    effective_usd_per_hour numeric NOT NULL,
+    PRIMARY KEY (sku, component, purchase_model, term, purchase_option, offering_class)
+);
+CREATE TABLE IF NOT EXISTS pekora_savings_plan_rates (
+    savings_plan_sku text NOT NULL,
+    rate_code text NOT NULL,
+    effective_date timestamptz NOT NULL,
+    plan_type text NOT NULL,
+    purchase_term text NOT NULL,
+    purchase_option text NOT NULL,
+    region text,
+    instance_family text,
+    discounted_sku text NOT NULL,
+    discounted_usage_type text NOT NULL,
+    discounted_operation text NOT NULL,
+    discounted_service_code text NOT NULL,
+    unit text NOT NULL,
+    price numeric,
+    currency text NOT NULL,
+    PRIMARY KEY (savings_plan_sku, rate_code)
+);
+";
+
+const SERVICES: Table = Table {
+    name: "pekora_services",
+    columns: &[
+        "service_code",
+        "offer_code",
+        "current_version_url",
+        "current_region_index_url",
+        "current_savings_plan_index_url",
+        "publication_date",
+    ],
+    primary_key: &["service_code"],
+    not_null: &[],
+};
+
+const OFFERS: Table = Table {
+    name: "pekora_offers",
+    columns: &[
+        "dataset",
+        "key",
+        "version",
+        "publication_date",
+        "row_count",
+        "loaded_at",
+    ],
+    primary_key: &["dataset", "key"],
+    not_null: &[],
+};
+
+const PRICE_ROWS: Table = Table {
+    name: "pekora_price_rows",
+    columns: PRICE_ROW_COLUMNS,
+    primary_key: PRICE_ROW_PRIMARY_KEY,
+    not_null: PRICE_ROW_NOT_NULL,
+};
+
+const SAVINGS_PLAN_RATES: Table = Table {
+    name: "pekora_savings_plan_rates",
+    columns: &[
+        "savings_plan_sku",
+        "rate_code",
+        "effective_date",
+        "plan_type",
+        "purchase_term",
+        "purchase_option",
+        "region",
+        "instance_family",
+        "discounted_sku",
+        "discounted_usage_type",
+        "discounted_operation",
+        "discounted_service_code",
+        "unit",
+        "price",
+        "currency",
+    ],
+    primary_key: &["savings_plan_sku", "rate_code"],
+    not_null: &[],
+};
+
+/// Connection to PostgreSQL, with the tables of `SCHEMA`.
+pub struct PostgresStore {
+    pool: PgPool,
+}
+
+impl PostgresStore {
+    /// Connects to `url`, a `postgres://` connection URI, and creates missing tables.
+    pub async fn connect(url: &str) -> anyhow::Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(url)
+            .await
+            .context("Failed to connect to PostgreSQL")?;
+        sqlx::raw_sql(SCHEMA)
+            .execute(&pool)
+            .await
+            .context("Failed to create tables")?;
+        Ok(Self { pool })
+    }
+
+    /// Starts a load. Its rows are only visible once committed, and a load dropped before
+    /// `PostgresLoad::commit` is rolled back.
+    pub async fn begin(&self) -> anyhow::Result<PostgresLoad> {
+        Ok(PostgresLoad {
+            transaction: self.pool.begin().await?,
+        })
+    }
+}
+
+/// Upserts of a load, in one transaction. Absent values are NULL, or empty strings in the
+/// `not_null` columns of a table.
+pub struct PostgresLoad {
+    transaction: Transaction<'static, Postgres>,
+}
+
+impl PostgresLoad {
+    pub async fn services(&mut self, response: &ServiceListResponse) -> anyhow::Result<()> {
+        let mut services = response.offers.iter().collect::<Vec<_>>();
+        services.sort_by_key(|(service_code, _)| *service_code);
+        self.upsert(
+            &SERVICES,
+            &services.iter().collect::<Vec<_>>(),
+            |values, (service_code, offer)| {
+                values
+                    .push_bind((*service_code).clone())
+                    .push_bind(offer.offer_code.clone())
+                    .push_bind(offer.current_version_url.clone())
+                    .push_bind(offer.current_region_index_url.clone())
+                    .push_bind(offer.current_savings_plan_index_url.clone())
+                    .push_bind(response.publication_date);
+            },
+        )
+        .await
+    }
+
+    /// Offer version a dataset was loaded from.
+    pub async fn offer(&mut self, metadata: &DatasetMetadata) -> anyhow::Result<()> {
+        self.upsert(&OFFERS, &[metadata], |values, metadata| {
+            values
+                .push_bind(metadata.kind)
+                .push_bind(metadata.key.clone())
+                .push_bind(metadata.version.clone())
+                .push_bind(metadata.publication_date)
+                .push_bind(metadata.row_count as i64)
+                .push_bind(metadata.loaded_at);
+        })
+        .await
+    }
+
+    pub async fn price_rows(&mut self, rows: &[NormalizedPriceRow]) -> anyhow::Result<()> {
+        let rows = first_of_each_key(&PRICE_ROWS, rows, |row| {
+            (
+                row.sku.as_str(),
+                row.component.map(|component| component.as_str()),
+                row.purchase_model.as_str(),
+                row.term.as_ref().map(|term| term.as_str()),
+                row.purchase_option
+                    .as_ref()
+                    .map(|purchase_option| purchase_option.as_str()),
+                row.offering_class
+                    .as_ref()
+                    .map(|offering_class| offering_class.as_str()),
+            )
+        });
+        self.upsert(&PRICE_ROWS, &rows, bind_price_row).await
+    }
+
+    pub async fn savings_plan_rates(
+        &mut self,
+        rows: &[PivotedSavingsPlanTermRate],
+    ) -> anyhow::Result<()> {
+        let rows = first_of_each_key(&SAVINGS_PLAN_RATES, rows, |row| {
+            (
+                row.savings_plan_sku.as_str(),
+                row.term_rate.rate_code.as_str(),
+            )
+        });
+        self.upsert(&SAVINGS_PLAN_RATES, &rows, |values, row| {
+            let attributes = &row.savings_plan_attributes;
+            let rate = &row.term_rate;
+            values
+                .push_bind(row.savings_plan_sku.clone())
+                .push_bind(rate.rate_code.clone())
+                .push_bind(row.savings_plan_effective_date)
+                .push_bind(attributes.product_family.as_str().to_string())
+                .push_bind(attributes.purchase_term.as_str().to_string())
+                .push_bind(attributes.purchase_option.as_str().to_string())
+                .push_bind(attributes.region_code.clone())
+                .push_bind(attributes.instance_type.clone())
+                .push_bind(rate.discounted_sku.clone())
+                .push_bind(rate.discounted_usage_type.clone())
+                .push_bind(rate.discounted_operation.clone())
+                .push_bind(rate.discounted_service_code.clone())
+                .push_bind(rate.unit.clone())
+                .push_bind(rate.discounted_rate.price.value())
+                .push_bind(rate.discounted_rate.currency.as_str().to_string());
+        })
+        .await
+    }
+
+    /// Commits the transaction.
+    pub async fn commit(self) -> anyhow::Result<()> {
+        self.transaction
+            .commit()
+            .await
+            .context("Failed to commit the load")?;
+        info!("Loaded into PostgreSQL");
+        Ok(())
+    }
+
+    /// Inserts `rows`, updating rows of the same primary key. `rows` must not repeat a primary
+    /// key, see `first_of_each_key`.
+    async fn upsert<T>(
+        &mut self,
+        table: &Table,
+        rows: &[&T],
+        bind: impl Fn(&mut Separated<'_, 'static, Postgres, &'static str>, &T),
+    ) -> anyhow::Result<()> {
+        for batch in rows.chunks(MAX_BIND_PARAMETERS / table.columns.len()) {
+            upsert_query(table, batch, &bind)
+                .build()
+                .execute(&mut *self.transaction)
+                .await
+                .with_context(|| format!("Failed to upsert into {}", table.name))?;
+        }
+        Ok(())
+    }
+}
+
+fn bind_price_row(
+    values: &mut Separated<'_, 'static, Postgres, &'static str>,
+    row: &NormalizedPriceRow,
+) {
+    values
+        .push_bind(row.region.clone().unwrap_or_default())
+        .push_bind(row.service_code.clone().unwrap_or_default())
+        .push_bind(row.sku.clone())
+        .push_bind(row.instance_type.clone())
+        .push_bind(row.platform.clone())
+        .push_bind(row.component.map_or("", |component| component.as_str()))
+        .push_bind(row.purchase_model.as_str())
+        .push_bind(
+            row.term
+                .as_ref()
+                .map_or(String::new(), |term| term.as_str().to_string()),
+        )
+        .push_bind(
+            row.purchase_option
+                .as_ref()
+                .map_or(String::new(), |purchase_option| {
+                    purchase_option.as_str().to_string()
+                }),
+        )
+        .push_bind(
+            row.offering_class
+                .as_ref()
+                .map_or(String::new(), |offering_class| {
+                    offering_class.as_str().to_string()
+                }),
+        )
+        .push_bind(row.effective_usd_per_hour);
+}
+
+/// `INSERT` of `rows` into `table`, with the values `bind` pushes for each row.
+fn upsert_query<T>(
+    table: &Table,
+    rows: &[&T],
+    bind: impl Fn(&mut Separated<'_, 'static, Postgres, &'static str>, &T),
+) -> QueryBuilder<'static, Postgres> {
+    let mut query = QueryBuilder::new(format!(
+        "INSERT INTO {} ({}) ",
+        table.name,
+        table.columns.join(", ")
+    ));
+    query.push_values(rows, |mut values, row| bind(&mut values, row));
+    query.push(format!(
+        " ON CONFLICT ({}) DO UPDATE SET {}",
+        table.primary_key.join(", "),
+        table.updates()
+    ));
+    query
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bind_price_row, upsert_query, PostgresStore, PRICE_ROWS};
+    use crate::transform::aws::normalize::{NormalizedPriceRow, PurchaseModel};
+    use rust_decimal::Decimal;
+
+    fn price_row() -> NormalizedPriceRow {
+        NormalizedPriceRow {
+            region: Some("us-east-1".to_string()),
+            service_code: Some("AmazonEC2".to_string()),
+            sku: "ABC".to_string(),
+            instance_type: Some("m5.large".to_string()),
+            platform: Some("Linux".to_string()),
+            component: None,
+            purchase_model: PurchaseModel::OnDemand,
+            term: None,
+            purchase_option: None,
+            offering_class: None,
+            effective_usd_per_hour: Decimal::new(96, 3),
+        }
+    }
+
+    #[test]
+    fn test_price_rows_query() {
+        let row = price_row();
+        let query = upsert_query(&PRICE_ROWS, &[&row, &row], bind_price_row);
+        assert_eq!(
+            query.sql(),
+            "INSERT INTO pekora_price_rows (region, service_code, sku, instance_type, platform, \
+             component, purchase_model, term, purchase_option, offering_class, \
+             effective_usd_per_hour) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11), \
+             ($12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22) \
+             ON CONFLICT (sku, component, purchase_model, term, purchase_option, offering_class) \
+             DO UPDATE SET region = EXCLUDED.region, service_code = EXCLUDED.service_code, \
+             instance_type = EXCLUDED.instance_type, platform = EXCLUDED.platform, \
+             effective_usd_per_hour = EXCLUDED.effective_usd_per_hour"
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_live_load() {
+        let url = std::env::var("PEKORA_TEST_DATABASE_URL").unwrap();
+        let store = PostgresStore::connect(&url).await.unwrap();
+        let row = NormalizedPriceRow {
+            sku: "PEKORA-TEST".to_string(),
+            ..price_row()
+        };
+
+        let mut load = store.begin().await.unwrap();
+        load.price_rows(&[row.clone(), row.clone()]).await.unwrap();
+        load.commit().await.unwrap();
+
+        let mut load = store.begin().await.unwrap();
+        load.price_rows(&[NormalizedPriceRow {
+            effective_usd_per_hour: Decimal::new(1, 1),
+            ..row.clone()
+        }])
+        .await
+        .unwrap();
+        drop(load);
+
+        let prices: Vec<(Decimal, String)> = sqlx::query_as(
+            "SELECT effective_usd_per_hour, term FROM pekora_price_rows WHERE sku = $1",
+        )
+        .bind(&row.sku)
+        .fetch_all(&store.pool)
+        .await
+        .unwrap();
+        assert_eq!(prices, vec![(Decimal::new(96, 3), String::new())]);
+    }
+}
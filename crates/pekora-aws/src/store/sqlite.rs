@@ -0,0 +1,430 @@
+//! Loading into a local SQLite or DuckDB database file, for ad-hoc SQL without a database
+//! server. Rows are upserted with bound parameters in one transaction, so loading again updates
+//! rows in place and a failed load changes nothing. SQLite needs the `sqlite` feature, DuckDB
+//! the `duckdb` feature.
+use crate::api::aws::price_bulk_types::PricingListResponseProduct;
+use crate::store::{
+    first_of_each_key, price_row_values, Table, PRICE_ROW_COLUMNS, PRICE_ROW_NOT_NULL,
+    PRICE_ROW_PRIMARY_KEY,
+};
+use crate::transform::aws::instance_specs::InstanceSpec;
+use crate::transform::aws::normalize::NormalizedPriceRow;
+use anyhow::Context;
+use log::info;
+#[cfg(feature = "sqlite")]
+use sqlx::sqlite::SqliteConnectOptions;
+#[cfg(feature = "sqlite")]
+use sqlx::{Connection, SqliteConnection};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Bind parameters of a SQLite statement are limited to 32766, so upserts are split into batches.
+const MAX_BIND_PARAMETERS: usize = 32766;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    Sqlite,
+    DuckDb,
+}
+
+/// Tables created if missing before every load. Types are understood by both engines.
+pub const SCHEMA: &str = "\
+CREATE TABLE IF NOT EXISTS products (
+    sku TEXT PRIMARY KEY,
+    service_code TEXT,
+    product_family TEXT NOT NULL,
+    region TEXT,
+    instance_type TEXT,
+    attributes TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS rates (
+    region TEXT NOT NULL,
+    service_code TEXT NOT NULL,
+    sku TEXT NOT NULL,
+    instance_type TEXT,
+    platform TEXT,
+    component TEXT NOT NULL,
+    purchase_model TEXT NOT NULL,
+    term TEXT NOT NULL,
+    purchase_option TEXT NOT NULL,
+    offering_class TEXT NOT NULL,
+    effective_usd_per_hour DECIMAL(18, 10) NOT NULL,
+    PRIMARY KEY (sku, component, purchase_model, term, purchase_option, offering_class)
+);
+CREATE TABLE IF NOT EXISTS instance_specs (
+    instance_type TEXT PRIMARY KEY,
+    architecture TEXT,
+    vcpus INTEGER,
+    memory_gib DECIMAL(18, 3),
+    network_performance TEXT,
+    gpus INTEGER,
+    gpu_memory_gib DECIMAL(18, 3),
+    gpu_model TEXT,
+    launch_date DATE
+);
+";
+
+const PRODUCTS: Table = Table {
+    name: "products",
+    columns: &[
+        "sku",
+        "service_code",
+        "product_family",
+        "region",
+        "instance_type",
+        "attributes",
+    ],
+    primary_key: &["sku"],
+    not_null: &[],
+};
+
+const RATES: Table = Table {
+    name: "rates",
+    columns: PRICE_ROW_COLUMNS,
+    primary_key: PRICE_ROW_PRIMARY_KEY,
+    not_null: PRICE_ROW_NOT_NULL,
+};
+
+const INSTANCE_SPECS: Table = Table {
+    name: "instance_specs",
+    columns: &[
+        "instance_type",
+        "architecture",
+        "vcpus",
+        "memory_gib",
+        "network_performance",
+        "gpus",
+        "gpu_memory_gib",
+        "gpu_model",
+        "launch_date",
+    ],
+    primary_key: &["instance_type"],
+    not_null: &[],
+};
+
+/// Rows of a load, in the tables they are upserted into. Absent values are NULL, or empty strings
+/// in the `not_null` columns of a table.
+#[derive(Default)]
+pub struct LocalLoad {
+    upserts: Vec<(&'static Table, Vec<Vec<Option<String>>>)>,
+}
+
+impl LocalLoad {
+    /// Products with their attributes as a JSON object.
+    pub fn products<'a>(
+        &mut self,
+        products: impl IntoIterator<Item = &'a PricingListResponseProduct<HashMap<String, String>>>,
+    ) -> anyhow::Result<()> {
+        let mut rows = Vec::new();
+        for product in products {
+            let attribute = |name: &str| product.attributes.get(name).cloned();
+            rows.push(vec![
+                Some(product.sku.clone()),
+                attribute("servicecode"),
+                Some(product.product_family.clone()),
+                attribute("regionCode"),
+                attribute("instanceType"),
+                Some(serde_json::to_string(&product.attributes)?),
+            ]);
+        }
+        self.upsert(&PRODUCTS, rows);
+        Ok(())
+    }
+
+    /// On-demand, reserved and savings plan rates.
+    pub fn rates(&mut self, rows: &[NormalizedPriceRow]) {
+        self.upsert(&RATES, rows.iter().map(price_row_values).collect());
+    }
+
+    pub fn instance_specs<'a>(&mut self, specs: impl IntoIterator<Item = &'a InstanceSpec>) {
+        let rows = specs
+            .into_iter()
+            .map(|spec| {
+                vec![
+                    Some(spec.instance_type.clone()),
+                    spec.architecture.clone(),
+                    spec.vcpus.map(|vcpus| vcpus.to_string()),
+                    spec.memory_gib.map(|memory| memory.to_string()),
+                    spec.network_performance.clone(),
+                    spec.gpus.map(|gpus| gpus.to_string()),
+                    spec.gpu_memory_gib.map(|memory| memory.to_string()),
+                    spec.gpu_model.clone(),
+                    spec.launch_date.map(|date| date.to_string()),
+                ]
+            })
+            .collect();
+        self.upsert(&INSTANCE_SPECS, rows);
+    }
+
+    fn upsert(&mut self, table: &'static Table, rows: Vec<Vec<Option<String>>>) {
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .zip(table.columns)
+                    .map(|(value, column)| match value {
+                        None if table.not_null.contains(column) => Some(String::new()),
+                        value => value,
+                    })
+                    .collect()
+            })
+            .collect();
+        self.upserts.push((table, rows));
+    }
+
+    /// `INSERT` statements of the load, each with the rows whose values it binds in order.
+    fn statements(&self) -> impl Iterator<Item = (&Table, String, Vec<&Vec<Option<String>>>)> {
+        self.upserts.iter().flat_map(|(table, rows)| {
+            let key_indices = table
+                .primary_key
+                .iter()
+                .filter_map(|key| table.columns.iter().position(|column| column == key))
+                .collect::<Vec<_>>();
+            let rows = first_of_each_key(table, rows, |row| {
+                key_indices
+                    .iter()
+                    .map(|i| row[*i].as_deref())
+                    .collect::<Vec<_>>()
+            });
+            rows.chunks(MAX_BIND_PARAMETERS / table.columns.len())
+                .map(|batch| (*table, upsert_statement(table, batch.len()), batch.to_vec()))
+                .collect::<Vec<_>>()
+        })
+    }
+}
+
+/// `INSERT` of `rows` rows into `table` with `?` parameters, which both engines bind.
+fn upsert_statement(table: &Table, rows: usize) -> String {
+    let row = format!("({})", vec!["?"; table.columns.len()].join(", "));
+    format!(
+        "INSERT INTO {} ({}) VALUES {} ON CONFLICT ({}) DO UPDATE SET {}",
+        table.name,
+        table.columns.join(", "),
+        vec![row; rows].join(", "),
+        table.primary_key.join(", "),
+        table.updates()
+    )
+}
+
+/// Upserts the rows `write` adds to a load into the database file at `path`, created if missing.
+pub async fn load(
+    engine: Engine,
+    path: &Path,
+    write: impl FnOnce(&mut LocalLoad) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut load = LocalLoad::default();
+    write(&mut load)?;
+    match engine {
+        #[cfg(feature = "sqlite")]
+        Engine::Sqlite => load_sqlite(path, &load).await?,
+        #[cfg(not(feature = "sqlite"))]
+        Engine::Sqlite => anyhow::bail!("SQLite requires pekora built with the sqlite feature"),
+        #[cfg(feature = "duckdb")]
+        Engine::DuckDb => {
+            let path = path.to_path_buf();
+            tokio::task::spawn_blocking(move || load_duckdb(&path, &load)).await??
+        }
+        #[cfg(not(feature = "duckdb"))]
+        Engine::DuckDb => anyhow::bail!("DuckDB requires pekora built with the duckdb feature"),
+    }
+    info!("Loaded into {}", path.display());
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+async fn load_sqlite(path: &Path, load: &LocalLoad) -> anyhow::Result<()> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true);
+    let mut connection = SqliteConnection::connect_with(&options)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut transaction = connection.begin().await?;
+    sqlx::raw_sql(SCHEMA)
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to create tables")?;
+    for (table, statement, rows) in load.statements() {
+        let mut query = sqlx::query(&statement);
+        for value in rows.into_iter().flatten() {
+            query = query.bind(value.as_deref());
+        }
+        query
+            .execute(&mut *transaction)
+            .await
+            .with_context(|| format!("Failed to upsert into {}", table.name))?;
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the load")?;
+    Ok(())
+}
+
+#[cfg(feature = "duckdb")]
+fn load_duckdb(path: &Path, load: &LocalLoad) -> anyhow::Result<()> {
+    let mut connection = duckdb::Connection::open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let transaction = connection.transaction()?;
+    transaction
+        .execute_batch(SCHEMA)
+        .context("Failed to create tables")?;
+    for (table, statement, rows) in load.statements() {
+        transaction
+            .execute(
+                &statement,
+                duckdb::params_from_iter(rows.into_iter().flatten()),
+            )
+            .with_context(|| format!("Failed to upsert into {}", table.name))?;
+    }
+    transaction.commit().context("Failed to commit the load")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, Engine, LocalLoad};
+    use crate::api::aws::types::{ContractLength, PurchaseOption, RIOfferingClass};
+    use crate::transform::aws::normalize::{NormalizedPriceRow, PurchaseModel};
+    use rust_decimal::Decimal;
+    #[cfg(feature = "sqlite")]
+    use sqlx::{Connection, SqliteConnection};
+
+    fn rows() -> Vec<NormalizedPriceRow> {
+        let row = NormalizedPriceRow {
+            region: Some("us-east-1".to_string()),
+            service_code: Some("AmazonEC2".to_string()),
+            sku: "ABC".to_string(),
+            instance_type: Some("m5.large".to_string()),
+            platform: Some("Red Hat Enterprise Linux with HA".to_string()),
+            component: None,
+            purchase_model: PurchaseModel::OnDemand,
+            term: None,
+            purchase_option: None,
+            offering_class: None,
+            effective_usd_per_hour: Decimal::new(96, 3),
+        };
+        let standard = NormalizedPriceRow {
+            purchase_model: PurchaseModel::Reserved,
+            term: Some(ContractLength::OneYear),
+            purchase_option: Some(PurchaseOption::NoUpfront),
+            offering_class: Some(RIOfferingClass::Standard),
+            platform: Some("Windows'".to_string()),
+            effective_usd_per_hour: Decimal::new(60, 3),
+            ..row.clone()
+        };
+        let convertible = NormalizedPriceRow {
+            offering_class: Some(RIOfferingClass::Convertible),
+            effective_usd_per_hour: Decimal::new(70, 3),
+            ..standard.clone()
+        };
+        vec![row.clone(), standard, convertible, row]
+    }
+
+    #[test]
+    fn test_rates_statements() {
+        let mut load = LocalLoad::default();
+        load.rates(&rows());
+        let statements = load.statements().collect::<Vec<_>>();
+        assert_eq!(statements.len(), 1);
+        let (_, statement, rows) = &statements[0];
+        assert!(statement.starts_with(
+            "INSERT INTO rates (region, service_code, sku, instance_type, platform, component, \
+             purchase_model, term, purchase_option, offering_class, effective_usd_per_hour) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?), (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?), \
+             (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+             ON CONFLICT (sku, component, purchase_model, term, purchase_option, offering_class) \
+             DO UPDATE SET region = EXCLUDED.region,"
+        ));
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0][5].as_deref(), Some(""));
+        assert_eq!(rows[0][7].as_deref(), Some(""));
+        assert_eq!(rows[2][9].as_deref(), Some("convertible"));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_load_sqlite() {
+        let path = std::env::temp_dir().join(format!("pekora-{}.sqlite", std::process::id()));
+        for _ in 0..2 {
+            load(Engine::Sqlite, &path, |load| {
+                load.rates(&rows());
+                Ok(())
+            })
+            .await
+            .unwrap();
+        }
+        // a load failing on its second row leaves the first one as it was
+        let failed = load(Engine::Sqlite, &path, |load| {
+            let rows = rows();
+            load.rates(&[
+                NormalizedPriceRow {
+                    effective_usd_per_hour: Decimal::ONE,
+                    ..rows[0].clone()
+                },
+                rows[1].clone(),
+            ]);
+            load.upserts[0].1[1][2] = None;
+            Ok(())
+        })
+        .await;
+
+        let mut connection = SqliteConnection::connect(&format!("sqlite://{}", path.display()))
+            .await
+            .unwrap();
+        let rates: Vec<(String, String, String, f64)> = sqlx::query_as(
+            "SELECT purchase_model, offering_class, platform, effective_usd_per_hour \
+             FROM rates ORDER BY effective_usd_per_hour",
+        )
+        .fetch_all(&mut connection)
+        .await
+        .unwrap();
+        connection.close().await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(failed.is_err());
+        assert_eq!(
+            rates,
+            vec![
+                (
+                    "reserved".to_string(),
+                    "standard".to_string(),
+                    "Windows'".to_string(),
+                    0.06
+                ),
+                (
+                    "reserved".to_string(),
+                    "convertible".to_string(),
+                    "Windows'".to_string(),
+                    0.07
+                ),
+                (
+                    "on_demand".to_string(),
+                    String::new(),
+                    "Red Hat Enterprise Linux with HA".to_string(),
+                    0.096
+                ),
+            ]
+        );
+    }
+
+    #[cfg(feature = "duckdb")]
+    #[tokio::test]
+    async fn test_load_duckdb() {
+        let path = std::env::temp_dir().join(format!("pekora-{}.duckdb", std::process::id()));
+        for _ in 0..2 {
+            load(Engine::DuckDb, &path, |load| {
+                load.rates(&rows());
+                Ok(())
+            })
+            .await
+            .unwrap();
+        }
+        let connection = duckdb::Connection::open(&path).unwrap();
+        let rates: usize = connection
+            .query_row("SELECT count(*) FROM rates", [], |row| row.get(0))
+            .unwrap();
+        drop(connection);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(rates, 3);
+    }
+}
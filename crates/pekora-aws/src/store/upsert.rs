@@ -0,0 +1,81 @@
+//! Tables the PostgreSQL, SQLite and DuckDB loads upsert rows into
+use log::warn;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A table rows are upserted into.
+pub(crate) struct Table {
+    pub(crate) name: &'static str,
+    pub(crate) columns: &'static [&'static str],
+    pub(crate) primary_key: &'static [&'static str],
+    /// Columns storing absent values as empty strings, so they can be part of the primary key
+    pub(crate) not_null: &'static [&'static str],
+}
+
+impl Table {
+    /// `SET` clause of an upsert, updating every column outside the primary key.
+    pub(crate) fn updates(&self) -> String {
+        self.columns
+            .iter()
+            .filter(|column| !self.primary_key.contains(column))
+            .map(|column| format!("{} = EXCLUDED.{}", column, column))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// The first of the rows of each `key`, as a statement can't update a row twice. Rows repeating a
+/// key with other values are logged, as their values don't make it into `table`.
+pub(crate) fn first_of_each_key<'a, T: PartialEq, K: Hash + Eq + Debug>(
+    table: &Table,
+    rows: impl IntoIterator<Item = &'a T>,
+    key: impl Fn(&'a T) -> K,
+) -> Vec<&'a T> {
+    let mut kept: HashMap<K, &T> = HashMap::new();
+    let mut unique = Vec::new();
+    let mut conflicts = 0;
+    let mut example = None;
+    for row in rows {
+        match kept.entry(key(row)) {
+            Entry::Occupied(entry) => {
+                if *entry.get() != row {
+                    conflicts += 1;
+                    example.get_or_insert_with(|| format!("{:?}", entry.key()));
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(row);
+                unique.push(row);
+            }
+        }
+    }
+    if let Some(example) = example {
+        warn!(
+            "Skipped {} rows of {} repeating a primary key with other values, e.g. {}",
+            conflicts, table.name, example
+        );
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{first_of_each_key, Table};
+
+    #[test]
+    fn test_first_of_each_key() {
+        let table = Table {
+            name: "rows",
+            columns: &["key", "value"],
+            primary_key: &["key"],
+            not_null: &[],
+        };
+        let rows = [("a", 1), ("b", 2), ("a", 1), ("a", 3)];
+        assert_eq!(
+            first_of_each_key(&table, &rows, |(key, _)| *key),
+            vec![&("a", 1), &("b", 2)]
+        );
+    }
+}
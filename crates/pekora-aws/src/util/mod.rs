@@ -0,0 +1,4 @@
+pub use pekora_core::util::*;
+
+#[cfg(test)]
+pub(crate) mod testing;
@@ -0,0 +1,40 @@
+//! Generates the gRPC service of `proto/pekora/v1/pricing.proto`. Messages are declared by hand
+//! in `server::grpc`, so building needs no `protoc`.
+use tonic_build::manual::{Builder, Method, Service};
+
+fn method(name: &str, route_name: &str, input_type: &str, output_type: &str) -> Method {
+    Method::builder()
+        .name(name)
+        .route_name(route_name)
+        .input_type(format!("super::{}", input_type))
+        .output_type(format!("super::{}", output_type))
+        .codec_path("tonic::codec::ProstCodec")
+        .build()
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    let pricing = Service::builder()
+        .name("Pricing")
+        .package("pekora.v1")
+        .method(method(
+            "get_prices",
+            "GetPrices",
+            "PriceRequest",
+            "PriceResponse",
+        ))
+        .method(method(
+            "get_savings_plan_rates",
+            "GetSavingsPlanRates",
+            "SavingsPlanRatesRequest",
+            "SavingsPlanRatesResponse",
+        ))
+        .method(method(
+            "estimate",
+            "Estimate",
+            "EstimateRequest",
+            "EstimateResponse",
+        ))
+        .build();
+    Builder::new().build_client(true).compile(&[pricing]);
+}
@@ -0,0 +1,469 @@
+use crate::notify::NotificationSinkConfig;
+use aws_config::SdkConfig;
+use log::debug;
+use pekora_aws::api::aws::auth::AwsAuthConfig;
+use pekora_aws::api::aws::price_bulk::Partition;
+use pekora_aws::cache::{Namespace, DEFAULT_CACHE_DIRECTORY};
+use pekora_aws::pipeline::PipelineConfig;
+use pekora_aws::transform::aws::overlay::DiscountOverlay;
+use pekora_aws::util::hash::HashAlgorithm;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Region of commands when no region is configured
+pub const DEFAULT_REGION: &str = "us-east-1";
+const DEFAULT_CONFIG_PATH: &str = "pekora.toml";
+const ENV_PREFIX: &str = "PEKORA_";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Yaml,
+    /// Comma separated, a row per record with nested fields flattened
+    Csv,
+    /// Bordered table, a row per record
+    Table,
+    /// Excel workbook with a sheet per purchase model if records have one
+    Xlsx,
+    /// Markdown table
+    Md,
+}
+
+/// Contents of the pekora configuration file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub cache_directory: Option<String>,
+    pub cache_max_age_hours: Option<i64>,
+    /// Team or payer account served, keeping its derived data apart in a shared cache
+    pub namespace: Option<Namespace>,
+    /// Negotiated discounts by namespace, applied to the prices `pekora serve` answers requests
+    /// of the namespace with, see `namespace` and the `X-Pekora-Namespace` header
+    pub namespaces: BTreeMap<Namespace, DiscountOverlay>,
+    /// How long HEAD results are reused, 0 to always ask upstream. Defaults to 10 minutes.
+    pub head_cache_ttl_seconds: Option<u64>,
+    /// Algorithm of derived file names and dataset fingerprints: xxh3 or sha256
+    pub hash_algorithm: Option<HashAlgorithm>,
+    /// Timeout of each price bulk request in seconds
+    pub timeout_seconds: Option<u64>,
+    /// Proxy URL for price bulk requests
+    pub proxy: Option<String>,
+    /// Maximum number of price bulk requests in flight
+    pub max_concurrent_requests: Option<usize>,
+    /// Adapt the number of price bulk requests in flight between this and
+    /// `max_concurrent_requests`, backing off when throttled
+    pub min_concurrent_requests: Option<usize>,
+    /// Regions queried by EC2 commands
+    pub regions: Option<Vec<String>>,
+    /// Price bulk API endpoint
+    pub base_url: Option<String>,
+    /// Price list partition: aws, aws-cn or aws-us-gov
+    pub partition: Option<Partition>,
+    /// AWS profile used by commands calling AWS APIs
+    pub profile: Option<String>,
+    /// IAM role assumed with the profile's credentials, e.g. a read-only role of another account
+    pub role_arn: Option<String>,
+    /// External ID required by the trust policy of `role_arn`
+    pub external_id: Option<String>,
+    /// Region of AWS API calls not tied to a queried region, e.g. STS
+    pub aws_region: Option<String>,
+    /// Credentials of other accounts by alias, used instead of the settings above when selected
+    pub accounts: BTreeMap<String, AwsAuthConfig>,
+    /// Alias in `accounts` whose credentials commands calling AWS APIs use
+    pub account: Option<String>,
+    pub output_format: Option<OutputFormat>,
+    /// Fail on unknown enum values in pricing files instead of keeping them as `Unknown`
+    pub strict: Option<bool>,
+    /// Append every upstream request to a log in the cache directory, see `pekora audit requests`
+    pub log_requests: Option<bool>,
+    /// PostgreSQL connection URI of `pekora load postgres` without `--dsn`, which keeps its
+    /// password out of process lists
+    pub database_url: Option<String>,
+    pub notifications: Vec<NotificationSinkConfig>,
+    /// Named pipelines run by `pekora run <name>`
+    pub pipelines: BTreeMap<String, PipelineConfig>,
+}
+
+/// Settings that can be set outside of the config file. Every field also reads from a
+/// `PEKORA_`-prefixed environment variable, e.g. `PEKORA_CACHE_DIRECTORY`. Lists are
+/// comma-separated.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ConfigOverrides {
+    pub cache_directory: Option<String>,
+    pub cache_max_age_hours: Option<i64>,
+    pub namespace: Option<Namespace>,
+    pub head_cache_ttl_seconds: Option<u64>,
+    pub hash_algorithm: Option<HashAlgorithm>,
+    pub timeout_seconds: Option<u64>,
+    pub proxy: Option<String>,
+    pub max_concurrent_requests: Option<usize>,
+    pub min_concurrent_requests: Option<usize>,
+    pub regions: Option<Vec<String>>,
+    pub base_url: Option<String>,
+    pub partition: Option<Partition>,
+    pub profile: Option<String>,
+    pub role_arn: Option<String>,
+    pub external_id: Option<String>,
+    pub aws_region: Option<String>,
+    pub account: Option<String>,
+    pub output_format: Option<OutputFormat>,
+    pub strict: Option<bool>,
+    pub log_requests: Option<bool>,
+    pub database_url: Option<String>,
+}
+
+impl ConfigOverrides {
+    pub fn from_env() -> ConfigResult<Self> {
+        Self::from_iter(std::env::vars())
+    }
+
+    fn from_iter(vars: impl Iterator<Item = (String, String)>) -> ConfigResult<Self> {
+        envy::prefixed(ENV_PREFIX)
+            .from_iter(vars)
+            .map_err(ConfigError::Env)
+    }
+}
+
+impl Config {
+    /// Loads the config file, then applies environment overrides and `overrides` in order.
+    /// Later layers win, so CLI flags take precedence over env vars over the config file.
+    pub fn load_layered(path: Option<&str>, overrides: ConfigOverrides) -> ConfigResult<Self> {
+        let mut config = Self::load_or_default(path)?;
+        config.merge(ConfigOverrides::from_env()?);
+        config.merge(overrides);
+        if let Some(account) = &config.account {
+            if !config.accounts.contains_key(account) {
+                return Err(ConfigError::UnknownAccount(account.clone()));
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn merge(&mut self, overrides: ConfigOverrides) {
+        if overrides.cache_directory.is_some() {
+            self.cache_directory = overrides.cache_directory;
+        }
+        if overrides.cache_max_age_hours.is_some() {
+            self.cache_max_age_hours = overrides.cache_max_age_hours;
+        }
+        if overrides.namespace.is_some() {
+            self.namespace = overrides.namespace;
+        }
+        if overrides.head_cache_ttl_seconds.is_some() {
+            self.head_cache_ttl_seconds = overrides.head_cache_ttl_seconds;
+        }
+        if overrides.hash_algorithm.is_some() {
+            self.hash_algorithm = overrides.hash_algorithm;
+        }
+        if overrides.timeout_seconds.is_some() {
+            self.timeout_seconds = overrides.timeout_seconds;
+        }
+        if overrides.proxy.is_some() {
+            self.proxy = overrides.proxy;
+        }
+        if overrides.max_concurrent_requests.is_some() {
+            self.max_concurrent_requests = overrides.max_concurrent_requests;
+        }
+        if overrides.min_concurrent_requests.is_some() {
+            self.min_concurrent_requests = overrides.min_concurrent_requests;
+        }
+        if overrides.regions.is_some() {
+            self.regions = overrides.regions;
+        }
+        if overrides.base_url.is_some() {
+            self.base_url = overrides.base_url;
+        }
+        if overrides.partition.is_some() {
+            self.partition = overrides.partition;
+        }
+        if overrides.profile.is_some() {
+            self.profile = overrides.profile;
+        }
+        if overrides.role_arn.is_some() {
+            self.role_arn = overrides.role_arn;
+        }
+        if overrides.external_id.is_some() {
+            self.external_id = overrides.external_id;
+        }
+        if overrides.aws_region.is_some() {
+            self.aws_region = overrides.aws_region;
+        }
+        if overrides.account.is_some() {
+            self.account = overrides.account;
+        }
+        if overrides.output_format.is_some() {
+            self.output_format = overrides.output_format;
+        }
+        if overrides.strict.is_some() {
+            self.strict = overrides.strict;
+        }
+        if overrides.log_requests.is_some() {
+            self.log_requests = overrides.log_requests;
+        }
+        if overrides.database_url.is_some() {
+            self.database_url = overrides.database_url;
+        }
+    }
+
+    pub fn cache_directory(&self) -> &str {
+        self.cache_directory
+            .as_deref()
+            .unwrap_or(DEFAULT_CACHE_DIRECTORY)
+    }
+
+    pub fn cache_max_age(&self) -> Option<chrono::Duration> {
+        self.cache_max_age_hours
+            .and_then(chrono::Duration::try_hours)
+    }
+
+    /// First configured region, or else `DEFAULT_REGION`.
+    pub fn first_region(&self) -> String {
+        self.regions
+            .as_ref()
+            .and_then(|regions| regions.first().cloned())
+            .unwrap_or(DEFAULT_REGION.to_string())
+    }
+
+    /// Configured regions, or else `DEFAULT_REGION` alone.
+    pub fn regions_or_default(&self) -> Vec<String> {
+        self.regions
+            .clone()
+            .unwrap_or_else(|| vec![DEFAULT_REGION.to_string()])
+    }
+
+    pub fn output_format(&self) -> OutputFormat {
+        self.output_format.unwrap_or_default()
+    }
+
+    /// Credentials of the selected account, or else of the top-level settings.
+    pub fn aws_auth(&self) -> AwsAuthConfig {
+        if let Some(auth) = self
+            .account
+            .as_ref()
+            .and_then(|account| self.accounts.get(account))
+        {
+            return auth.clone();
+        }
+        AwsAuthConfig {
+            profile: self.profile.clone(),
+            role_arn: self.role_arn.clone(),
+            external_id: self.external_id.clone(),
+            region: self.aws_region.clone(),
+        }
+    }
+
+    /// AWS SDK config using the configured profile and role, or the default credential chain.
+    pub async fn aws_sdk_config(&self) -> SdkConfig {
+        self.aws_auth().sdk_config().await
+    }
+
+    pub fn load(path: &Path) -> ConfigResult<Self> {
+        let contents = std::fs::read_to_string(path).map_err(ConfigError::IO)?;
+        toml::from_str(&contents).map_err(ConfigError::Parse)
+    }
+
+    /// Loads the config from `path`, or from `pekora.toml` if it exists. Missing default config
+    /// files are not an error, explicitly specified ones are.
+    pub fn load_or_default(path: Option<&str>) -> ConfigResult<Self> {
+        match path {
+            Some(path) => Self::load(Path::new(path)),
+            None => {
+                let path = Path::new(DEFAULT_CONFIG_PATH);
+                if path.exists() {
+                    Self::load(path)
+                } else {
+                    debug!("No config file found, using defaults");
+                    Ok(Self::default())
+                }
+            }
+        }
+    }
+}
+
+pub type ConfigResult<T> = Result<T, ConfigError>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error("Config IO failed: {0}")]
+    IO(std::io::Error),
+    #[error("Config parse failed: {0}")]
+    Parse(toml::de::Error),
+    #[error("Config environment variable invalid: {0}")]
+    Env(envy::Error),
+    #[error("Account {0} is not in the accounts of the config")]
+    UnknownAccount(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, ConfigOverrides, OutputFormat};
+    use crate::notify::NotificationSinkConfig;
+    use pekora_aws::cache::Namespace;
+    use pekora_aws::util::hash::HashAlgorithm;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_parse_notifications() {
+        let config: Config = toml::from_str(
+            r#"
+            [[notifications]]
+            type = "webhook"
+            url = "https://example.com/hook"
+
+            [[notifications]]
+            type = "slack"
+            webhook_url = "https://hooks.slack.com/services/x"
+            channel = "pricing"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.notifications.len(), 2);
+        assert!(matches!(
+            &config.notifications[1],
+            NotificationSinkConfig::Slack { channel: Some(channel), .. } if channel == "pricing"
+        ));
+    }
+
+    #[test]
+    fn test_accounts() {
+        let mut config: Config = toml::from_str(
+            r#"
+            profile = "default"
+
+            [accounts.prod]
+            profile = "payer"
+            role_arn = "arn:aws:iam::123456789012:role/pricing-read"
+            external_id = "pekora"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.aws_auth().profile.as_deref(), Some("default"));
+        config.merge(ConfigOverrides {
+            account: Some("prod".to_string()),
+            ..ConfigOverrides::default()
+        });
+        let auth = config.aws_auth();
+        assert_eq!(auth.profile.as_deref(), Some("payer"));
+        assert_eq!(auth.external_id.as_deref(), Some("pekora"));
+    }
+
+    #[test]
+    fn test_namespaces() {
+        let config: Config = toml::from_str(
+            r#"
+            [namespaces.team-a]
+            percent = 5
+            services = { AmazonEC2 = 12.5 }
+
+            [namespaces.team-b]
+            "#,
+        )
+        .unwrap();
+        let overlay = &config.namespaces[&Namespace::new("team-a").unwrap()];
+        assert_eq!(overlay.percent_of("AmazonRDS"), Decimal::new(5, 0));
+        assert_eq!(overlay.percent_of("AmazonEC2"), Decimal::new(125, 1));
+        assert!(config.namespaces[&Namespace::new("team-b").unwrap()]
+            .percent_of("AmazonEC2")
+            .is_zero());
+
+        let invalid = toml::from_str::<Config>("[namespaces.\"../team-c\"]\npercent = 5");
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_override_layers() {
+        let mut config: Config = toml::from_str(
+            r#"
+            cache_directory = "from-file"
+            base_url = "https://file.example.com"
+            profile = "file"
+            role_arn = "arn:aws:iam::123456789012:role/file"
+            "#,
+        )
+        .unwrap();
+        let env = ConfigOverrides::from_iter(
+            [
+                ("PEKORA_CACHE_DIRECTORY", "from-env"),
+                ("PEKORA_REGIONS", "us-east-1,eu-west-1"),
+                ("PEKORA_OUTPUT_FORMAT", "json"),
+                ("PEKORA_PROFILE", "env"),
+                ("PEKORA_EXTERNAL_ID", "env-id"),
+                ("PEKORA_NAMESPACE", "team-a"),
+                ("PEKORA_DATABASE_URL", "postgres://pekora@localhost/prices"),
+                ("UNRELATED", "ignored"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string())),
+        )
+        .unwrap();
+        config.merge(env);
+        config.merge(ConfigOverrides {
+            profile: Some("cli".to_string()),
+            ..ConfigOverrides::default()
+        });
+
+        assert_eq!(config.cache_directory(), "from-env");
+        assert_eq!(config.base_url.as_deref(), Some("https://file.example.com"));
+        assert_eq!(
+            config.regions,
+            Some(vec!["us-east-1".to_string(), "eu-west-1".to_string()])
+        );
+        assert_eq!(config.output_format(), OutputFormat::Json);
+        assert_eq!(config.profile.as_deref(), Some("cli"));
+        let auth = config.aws_auth();
+        assert_eq!(
+            auth.role_arn.as_deref(),
+            Some("arn:aws:iam::123456789012:role/file")
+        );
+        assert_eq!(auth.external_id.as_deref(), Some("env-id"));
+        assert_eq!(config.namespace.unwrap().as_str(), "team-a");
+        assert_eq!(
+            config.database_url.as_deref(),
+            Some("postgres://pekora@localhost/prices")
+        );
+
+        let invalid = ConfigOverrides::from_iter(
+            [("PEKORA_NAMESPACE".to_string(), "../team-b".to_string())].into_iter(),
+        );
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_client_env_overrides() {
+        let mut config: Config = toml::from_str(
+            r#"
+            aws_region = "ap-northeast-2"
+            timeout_seconds = 30
+            "#,
+        )
+        .unwrap();
+        config.merge(
+            ConfigOverrides::from_iter(
+                [
+                    ("PEKORA_AWS_REGION", "eu-west-1"),
+                    ("PEKORA_HEAD_CACHE_TTL_SECONDS", "0"),
+                    ("PEKORA_HASH_ALGORITHM", "sha256"),
+                    ("PEKORA_TIMEOUT_SECONDS", "120"),
+                    ("PEKORA_PROXY", "http://proxy.internal:3128"),
+                    ("PEKORA_MAX_CONCURRENT_REQUESTS", "8"),
+                    ("PEKORA_MIN_CONCURRENT_REQUESTS", "2"),
+                ]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string())),
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(config.aws_auth().region.as_deref(), Some("eu-west-1"));
+        assert_eq!(config.head_cache_ttl_seconds, Some(0));
+        assert_eq!(config.hash_algorithm, Some(HashAlgorithm::Sha256));
+        assert_eq!(config.timeout_seconds, Some(120));
+        assert_eq!(config.proxy.as_deref(), Some("http://proxy.internal:3128"));
+        assert_eq!(config.max_concurrent_requests, Some(8));
+        assert_eq!(config.min_concurrent_requests, Some(2));
+    }
+}
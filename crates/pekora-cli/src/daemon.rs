@@ -0,0 +1,365 @@
+//! State and health endpoints of `pekora daemon`, which refreshes the cache and reruns the
+//! configured pipelines on an interval, notifying of price changes in new offer versions.
+use crate::notify::{Notification, NotificationKind};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use log::warn;
+use pekora_aws::api::aws::price_bulk_types::PriceBulkOffer;
+use pekora_aws::metrics;
+use pekora_aws::pipeline::write_atomically;
+use pekora_aws::status::INTERNAL_DIRECTORY;
+use pekora_aws::transform::aws::diff::{OfferDiff, PriceChange};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Price changes listed in a notification
+const MAX_LISTED_CHANGES: usize = 20;
+
+/// Parses an interval such as `90s`, `30m`, `6h` or `1d`.
+pub fn parse_interval(value: &str) -> Result<Duration, String> {
+    let split = value.len().saturating_sub(1);
+    let amount = value
+        .get(..split)
+        .and_then(|amount| amount.parse::<u64>().ok())
+        .filter(|amount| *amount > 0);
+    let unit = match value.get(split..) {
+        Some("s") => 1,
+        Some("m") => 60,
+        Some("h") => 60 * 60,
+        Some("d") => 24 * 60 * 60,
+        _ => 0,
+    };
+    match amount {
+        Some(amount) if unit > 0 => Ok(Duration::from_secs(amount * unit)),
+        _ => Err(format!(
+            "Invalid interval {}, expected a number followed by s, m, h or d, e.g. 6h",
+            value
+        )),
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DaemonStatus {
+    pub cycles: u64,
+    /// Start of the cycle in progress
+    pub running_since: Option<DateTime<Utc>>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    /// Error of the last cycle, `None` if it succeeded
+    pub last_error: Option<String>,
+}
+
+impl DaemonStatus {
+    /// Alive unless a cycle has been running for more than two intervals, taken as stuck.
+    pub fn healthy(&self, now: DateTime<Utc>, interval: Duration) -> bool {
+        self.running_since
+            .is_none_or(|since| within(now, since, interval * 2))
+    }
+
+    /// Ready once a cycle succeeded, for as long as the last success is at most two intervals
+    /// old. A failed cycle leaves the daemon ready with the data of the last success.
+    pub fn ready(&self, now: DateTime<Utc>, interval: Duration) -> bool {
+        self.last_success_at
+            .is_some_and(|at| within(now, at, interval * 2))
+    }
+}
+
+fn within(now: DateTime<Utc>, at: DateTime<Utc>, duration: Duration) -> bool {
+    (now - at).to_std().map_or(true, |age| age <= duration)
+}
+
+/// Status of the daemon shared between its refresh loop and health endpoints.
+#[derive(Clone)]
+pub struct SharedStatus {
+    status: Arc<Mutex<DaemonStatus>>,
+    interval: Duration,
+}
+
+impl SharedStatus {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            status: Arc::default(),
+            interval,
+        }
+    }
+
+    pub fn start_cycle(&self) {
+        self.status.lock().unwrap().running_since = Some(Utc::now());
+    }
+
+    pub fn finish_cycle(&self, result: &anyhow::Result<()>) {
+        let now = Utc::now();
+        let mut status = self.status.lock().unwrap();
+        status.cycles += 1;
+        status.running_since = None;
+        status.last_finished_at = Some(now);
+        match result {
+            Ok(()) => {
+                metrics::global().record_refresh_success(now.timestamp());
+                status.last_success_at = Some(now);
+                status.last_error = None;
+            }
+            Err(e) => status.last_error = Some(e.to_string()),
+        }
+    }
+
+    pub fn snapshot(&self) -> DaemonStatus {
+        self.status.lock().unwrap().clone()
+    }
+}
+
+/// A current offer version replaced by another between two cycles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionChange {
+    pub service_code: String,
+    pub region: String,
+    pub from_version: String,
+    pub to_version: String,
+}
+
+/// Current offer versions seen by the daemon, by service and region. Kept in the cache
+/// directory between runs, so changes published while the daemon was down are notified too.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct OfferVersions(BTreeMap<String, BTreeMap<String, String>>);
+
+impl OfferVersions {
+    pub fn path(cache_directory: &Path) -> PathBuf {
+        cache_directory
+            .join(INTERNAL_DIRECTORY)
+            .join("daemon")
+            .join("offer_versions.json")
+    }
+
+    /// Versions saved by a previous run, none if it saved none or they can't be read.
+    pub fn load(path: &Path) -> Self {
+        let contents = match std::fs::read(path) {
+            Ok(contents) => contents,
+            Err(_) => return Self::default(),
+        };
+        match serde_json::from_slice(&contents) {
+            Ok(versions) => versions,
+            Err(e) => {
+                warn!("Ignoring offer versions of {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        write_atomically(path, |writer| Ok(serde_json::to_writer(writer, self)?))
+    }
+
+    /// Records the versions of `offers`, returning those replacing a version seen before. Offers
+    /// seen for the first time are only recorded.
+    pub fn update<'a>(
+        &mut self,
+        offers: impl IntoIterator<Item = &'a PriceBulkOffer>,
+    ) -> Vec<VersionChange> {
+        let mut changes = Vec::new();
+        for offer in offers {
+            let previous = self
+                .0
+                .entry(offer.service_code.clone())
+                .or_default()
+                .insert(offer.region.clone(), offer.offer_version.clone());
+            match previous {
+                Some(previous) if previous != offer.offer_version => changes.push(VersionChange {
+                    service_code: offer.service_code.clone(),
+                    region: offer.region.clone(),
+                    from_version: previous,
+                    to_version: offer.offer_version.clone(),
+                }),
+                _ => {}
+            }
+        }
+        changes
+    }
+}
+
+/// Details of a price change notification.
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceChangeSummary {
+    pub service_code: String,
+    pub region: String,
+    pub from_version: String,
+    pub to_version: String,
+    pub added_skus: usize,
+    pub removed_skus: usize,
+    pub changed_prices: usize,
+    /// Largest changes in percent first, followed by added and removed rate codes
+    pub largest_changes: Vec<PriceChange>,
+}
+
+impl PriceChangeSummary {
+    pub fn new(change: &VersionChange, offer_diff: &OfferDiff) -> Self {
+        let mut largest_changes = offer_diff.changed.clone();
+        largest_changes.sort_by_key(|change| {
+            std::cmp::Reverse(change.change_percent.map(|percent| percent.abs()))
+        });
+        largest_changes.truncate(MAX_LISTED_CHANGES);
+        Self {
+            service_code: change.service_code.clone(),
+            region: change.region.clone(),
+            from_version: change.from_version.clone(),
+            to_version: change.to_version.clone(),
+            added_skus: offer_diff.added_skus.len(),
+            removed_skus: offer_diff.removed_skus.len(),
+            changed_prices: offer_diff.changed.len(),
+            largest_changes,
+        }
+    }
+
+    pub fn notification(&self) -> anyhow::Result<Notification> {
+        let usd =
+            |usd: Option<Decimal>| usd.map_or("-".to_string(), |usd| usd.normalize().to_string());
+        let mut body = format!(
+            "{} {} changed from {} to {}: {} SKUs added, {} removed, {} prices changed",
+            self.service_code,
+            self.region,
+            self.from_version,
+            self.to_version,
+            self.added_skus,
+            self.removed_skus,
+            self.changed_prices
+        );
+        for change in &self.largest_changes {
+            body.push_str(&format!(
+                "\n{} {} -> {} USD per {}{}",
+                change.rate_code,
+                usd(change.from_usd),
+                usd(change.to_usd),
+                change.unit,
+                change
+                    .change_percent
+                    .map(|percent| format!(" ({:+}%)", percent))
+                    .unwrap_or_default()
+            ));
+        }
+        Ok(Notification {
+            kind: NotificationKind::PriceDiff,
+            title: format!("{} prices changed in {}", self.service_code, self.region),
+            body,
+            details: Some(serde_json::to_value(self)?),
+        })
+    }
+}
+
+/// `/healthz` and `/readyz`, answering 200 or 503 with the daemon status as JSON, and
+/// `/metrics` if built with the `metrics` feature.
+pub fn router(status: SharedStatus) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(status)
+        .merge(crate::server::metrics_router())
+}
+
+async fn healthz(State(status): State<SharedStatus>) -> (StatusCode, Json<DaemonStatus>) {
+    let snapshot = status.snapshot();
+    let healthy = snapshot.healthy(Utc::now(), status.interval);
+    (code(healthy), Json(snapshot))
+}
+
+async fn readyz(State(status): State<SharedStatus>) -> (StatusCode, Json<DaemonStatus>) {
+    let snapshot = status.snapshot();
+    let ready = snapshot.ready(Utc::now(), status.interval);
+    (code(ready), Json(snapshot))
+}
+
+fn code(ok: bool) -> StatusCode {
+    if ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_interval, DaemonStatus, OfferVersions, VersionChange};
+    use chrono::{TimeDelta, Utc};
+    use pekora_aws::api::aws::price_bulk_types::PriceBulkOffer;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("6h"), Ok(Duration::from_secs(6 * 60 * 60)));
+        assert_eq!(parse_interval("90s"), Ok(Duration::from_secs(90)));
+        for value in ["", "h", "0m", "6", "6w", "-1h"] {
+            assert!(parse_interval(value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_health() {
+        let now = Utc::now();
+        let interval = Duration::from_secs(60 * 60);
+        let mut status = DaemonStatus {
+            running_since: Some(now - TimeDelta::minutes(30)),
+            ..Default::default()
+        };
+        assert!(status.healthy(now, interval));
+        assert!(!status.ready(now, interval));
+
+        status.running_since = Some(now - TimeDelta::hours(3));
+        assert!(!status.healthy(now, interval));
+
+        status.running_since = None;
+        status.last_success_at = Some(now - TimeDelta::minutes(90));
+        status.last_error = Some("offer unavailable".to_string());
+        assert!(status.healthy(now, interval));
+        assert!(status.ready(now, interval));
+
+        status.last_success_at = Some(now - TimeDelta::hours(3));
+        assert!(!status.ready(now, interval));
+    }
+
+    #[test]
+    fn test_offer_versions() {
+        let offer = |service_code: &str, region: &str, offer_version: &str| PriceBulkOffer {
+            service_code: service_code.to_string(),
+            offer_version: offer_version.to_string(),
+            region: region.to_string(),
+            filename: "index.json".to_string(),
+        };
+        let mut versions = OfferVersions::default();
+        let first = [
+            offer("AmazonEC2", "us-east-1", "20240301000000"),
+            offer("AmazonEC2", "eu-west-1", "20240301000000"),
+        ];
+        assert!(versions.update(&first).is_empty());
+
+        let second = [
+            offer("AmazonEC2", "us-east-1", "20240312000000"),
+            offer("AmazonEC2", "eu-west-1", "20240301000000"),
+            offer("AmazonRDS", "us-east-1", "20240312000000"),
+        ];
+        assert_eq!(
+            versions.update(&second),
+            vec![VersionChange {
+                service_code: "AmazonEC2".to_string(),
+                region: "us-east-1".to_string(),
+                from_version: "20240301000000".to_string(),
+                to_version: "20240312000000".to_string(),
+            }]
+        );
+        assert!(versions.update(&second).is_empty());
+
+        let directory =
+            std::env::temp_dir().join(format!("pekora-offer-versions-{}", std::process::id()));
+        let path = OfferVersions::path(&directory);
+        assert!(OfferVersions::load(&path).0.is_empty());
+        versions.save(&path).unwrap();
+        let mut loaded = OfferVersions::load(&path);
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(loaded.update(&second).is_empty());
+        assert_eq!(loaded.update(&first).len(), 1);
+    }
+}
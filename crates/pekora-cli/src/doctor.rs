@@ -0,0 +1,218 @@
+//! Environment diagnostics for the `doctor` command
+use crate::config::{Config, ConfigOverrides};
+use crate::notify::NotificationDispatcher;
+use aws_config::SdkConfig;
+use pekora_aws::api::aws::price_bulk_builder::PriceBulkClients;
+use pekora_aws::status::format_bytes;
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::time::Duration;
+
+/// Bulk pricing files of large services take several GB once decompressed.
+const MIN_FREE_SPACE: u64 = 5 * 1024 * 1024 * 1024;
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to do about a warning or failure.
+    pub hint: Option<String>,
+}
+
+impl CheckResult {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Ok,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+impl Display for CheckResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self.status {
+            CheckStatus::Ok => " OK ",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        };
+        write!(f, "[{}] {}: {}", label, self.name, self.detail)?;
+        if let Some(hint) = &self.hint {
+            write!(f, "\n       hint: {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs all checks. Checks are independent, so one failing doesn't skip the others.
+pub async fn run(
+    clients: &PriceBulkClients,
+    config_path: Option<&str>,
+    config: &Config,
+) -> Vec<CheckResult> {
+    let cache_directory = Path::new(config.cache_directory());
+    vec![
+        check_config(config_path),
+        check_pricing_endpoint(clients).await,
+        check_aws_credentials(config.aws_sdk_config().await).await,
+        check_cache_directory(cache_directory),
+        check_disk_space(cache_directory),
+    ]
+}
+
+pub fn check_config(config_path: Option<&str>) -> CheckResult {
+    const NAME: &str = "config";
+    let config = match Config::load_layered(config_path, ConfigOverrides::default()) {
+        Ok(config) => config,
+        Err(e) => {
+            return CheckResult::fail(
+                NAME,
+                e.to_string(),
+                "Fix the config file or pass another one with --config",
+            )
+        }
+    };
+    if let Err(e) =
+        NotificationDispatcher::from_config(reqwest::Client::new(), &config.notifications, None)
+    {
+        return CheckResult::fail(
+            NAME,
+            e.to_string(),
+            "Check the [[notifications]] entries of the config file",
+        );
+    }
+    CheckResult::ok(
+        NAME,
+        format!("{} notification sink(s)", config.notifications.len()),
+    )
+}
+
+pub async fn check_pricing_endpoint(clients: &PriceBulkClients) -> CheckResult {
+    const NAME: &str = "pricing endpoint";
+    let hint = "Check outbound HTTPS access, or set --proxy if a proxy is required";
+    match tokio::time::timeout(NETWORK_TIMEOUT, clients.service_index().get_cache_key(&())).await {
+        Ok(Ok(_)) => CheckResult::ok(NAME, format!("{} reachable", clients.base_url())),
+        Ok(Err(e)) => CheckResult::fail(NAME, e.to_string(), hint),
+        Err(_) => CheckResult::fail(
+            NAME,
+            format!("no response within {:?}", NETWORK_TIMEOUT),
+            hint,
+        ),
+    }
+}
+
+pub async fn check_aws_credentials(mut config: SdkConfig) -> CheckResult {
+    const NAME: &str = "aws credentials";
+    let hint = "Only EC2 and ElastiCache commands need credentials. Set AWS_PROFILE, \
+        AWS_ACCESS_KEY_ID/AWS_SECRET_ACCESS_KEY, or run `aws sso login`";
+    if config.region().is_none() {
+        // STS is global, any region resolves an endpoint
+        config = config
+            .into_builder()
+            .region(aws_config::Region::new("us-east-1"))
+            .build();
+    }
+    let client = aws_sdk_sts::Client::new(&config);
+    match tokio::time::timeout(NETWORK_TIMEOUT, client.get_caller_identity().send()).await {
+        Ok(Ok(identity)) => CheckResult::ok(
+            NAME,
+            format!(
+                "authenticated as {}",
+                identity.arn().unwrap_or("unknown identity")
+            ),
+        ),
+        Ok(Err(e)) => CheckResult::fail(
+            NAME,
+            aws_sdk_sts::error::DisplayErrorContext(e).to_string(),
+            hint,
+        ),
+        Err(_) => CheckResult::fail(
+            NAME,
+            format!("STS did not respond within {:?}", NETWORK_TIMEOUT),
+            hint,
+        ),
+    }
+}
+
+pub fn check_cache_directory(cache_directory: &Path) -> CheckResult {
+    const NAME: &str = "cache directory";
+    let hint = "Make the cache directory writable or choose another one";
+    let probe = cache_directory.join(".pekora-doctor");
+    let result = std::fs::create_dir_all(cache_directory)
+        .and_then(|_| std::fs::write(&probe, b"ok"))
+        .and_then(|_| std::fs::remove_file(&probe));
+    match result {
+        Ok(_) => CheckResult::ok(NAME, format!("{} is writable", cache_directory.display())),
+        Err(e) => CheckResult::fail(NAME, format!("{}: {}", cache_directory.display(), e), hint),
+    }
+}
+
+pub fn check_disk_space(cache_directory: &Path) -> CheckResult {
+    const NAME: &str = "disk space";
+    match fs2::available_space(cache_directory) {
+        Ok(available) if available < MIN_FREE_SPACE => CheckResult::warn(
+            NAME,
+            format!("{} available", format_bytes(available)),
+            format!(
+                "Large pricing files may not fit, free up at least {}",
+                format_bytes(MIN_FREE_SPACE)
+            ),
+        ),
+        Ok(available) => CheckResult::ok(NAME, format!("{} available", format_bytes(available))),
+        Err(e) => CheckResult::warn(
+            NAME,
+            format!("could not determine free space: {}", e),
+            "Make sure the cache directory exists",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_cache_directory, check_config, CheckStatus};
+    use std::path::Path;
+
+    #[test]
+    fn test_check_config() {
+        assert_eq!(check_config(None).status, CheckStatus::Ok);
+        let result = check_config(Some("does-not-exist.toml"));
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.hint.is_some());
+    }
+
+    #[test]
+    fn test_check_cache_directory() {
+        let result = check_cache_directory(Path::new("test_cache/doctor"));
+        assert_eq!(result.status, CheckStatus::Ok);
+        assert!(!Path::new("test_cache/doctor/.pekora-doctor").exists());
+    }
+}
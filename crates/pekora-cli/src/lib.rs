@@ -0,0 +1,9 @@
+pub mod config;
+pub mod daemon;
+pub mod doctor;
+pub mod notify;
+pub mod output;
+pub mod repl;
+pub mod server;
+#[cfg(feature = "tui")]
+pub mod tui;
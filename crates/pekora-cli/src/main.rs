@@ -0,0 +1,3501 @@
+use clap::{Parser, Subcommand};
+use pekora_aws::api::aws::compute_optimizer::{
+    ComputeOptimizerClient, Ec2InstanceRecommendationsResponse,
+};
+use pekora_aws::api::aws::cost_explorer::{
+    CostAndUsageResponse, ReservationCoverageResponse, SavingsPlansUtilizationResponse,
+};
+#[cfg(feature = "cost-explorer")]
+use pekora_aws::api::aws::cost_explorer::{CostExplorerClient, TimePeriod};
+use pekora_aws::api::aws::docdb::{
+    DocDbClient, DEFAULT_PARAMETER_GROUP_FAMILY as DEFAULT_DOCDB_PARAMETER_GROUP_FAMILY,
+};
+use pekora_aws::api::aws::ec2::{self, Ec2Client};
+use pekora_aws::api::aws::elasticache::{
+    ElasticacheClient, MEMCACHED_PARAMETER_GROUP_FAMILY, REDIS_PARAMETER_GROUP_FAMILY,
+};
+use pekora_aws::api::aws::memorydb::{
+    MemoryDbClient, DEFAULT_PARAMETER_GROUP as DEFAULT_MEMORYDB_PARAMETER_GROUP,
+};
+use pekora_aws::api::aws::opensearch::OpenSearchClient;
+use pekora_aws::api::aws::price_bulk::Partition;
+use pekora_aws::api::aws::price_bulk_builder::PriceBulkClientBuilder;
+use pekora_aws::api::aws::price_bulk_types::{PriceBulkSavingsPlan, PricingListResponse};
+use pekora_aws::api::aws::pricing_query::{PricingQueryClient, ProductQuery};
+use pekora_aws::api::aws::rds::{
+    OrderableDbInstanceOptionsResponse, RdsClient, ReservedDbInstancesOfferingsResponse,
+};
+use pekora_aws::api::aws::redshift::{NodeConfigurationAction, RedshiftClient};
+use pekora_aws::api::aws::savings_plans::{
+    SavingsPlansClient, SavingsPlansOfferingRatesResponse, SavingsPlansOfferingsQuery,
+    SavingsPlansRatesQuery,
+};
+use pekora_aws::api::aws::ssm::SsmClient;
+use pekora_aws::api::aws::types::{KnownValues, LocationType, SavingsPlanType};
+use pekora_aws::audit;
+use pekora_aws::cache::Namespace;
+use pekora_aws::crawler::Crawler;
+#[cfg(feature = "parquet")]
+use pekora_aws::cur;
+use pekora_aws::dataset::{ComputeSavingsPlan, Ec2InstanceSavingsPlan, Ec2OnDemand};
+use pekora_aws::history::OfferMark;
+use pekora_aws::metrics;
+use pekora_aws::output::s3::{self, S3Uploader};
+use pekora_aws::pipeline;
+use pekora_aws::price::{self, PriceQuery};
+use pekora_aws::status::{parse_since, ErrorLog, RequestLog};
+use pekora_aws::store::clickhouse::ClickHouseWriter;
+#[cfg(feature = "postgres")]
+use pekora_aws::store::postgres::PostgresStore;
+#[cfg(any(feature = "sqlite", feature = "duckdb"))]
+use pekora_aws::store::sqlite::{self, Engine};
+use pekora_aws::transform;
+use pekora_aws::transform::aws::architecture;
+use pekora_aws::transform::aws::availability;
+use pekora_aws::transform::aws::break_even;
+use pekora_aws::transform::aws::cloudwatch::{self, CloudWatchUsage};
+use pekora_aws::transform::aws::cost_explorer;
+#[cfg(feature = "parquet")]
+use pekora_aws::transform::aws::cur::CurUsageReport;
+use pekora_aws::transform::aws::data_transfer::{self, TransferDestination};
+use pekora_aws::transform::aws::dedicated_host;
+use pekora_aws::transform::aws::diff;
+use pekora_aws::transform::aws::ebs::{self, VolumeSpec};
+use pekora_aws::transform::aws::elasticache::{self, CacheEngine};
+use pekora_aws::transform::aws::estimate::{self, InstanceRequirement, WorkloadSpec};
+use pekora_aws::transform::aws::gpu;
+use pekora_aws::transform::aws::instance_specs::EnrichedPriceRow;
+use pekora_aws::transform::aws::launch_dates::{self, LaunchDates};
+use pekora_aws::transform::aws::location::{LocationFilter, RegionNames};
+use pekora_aws::transform::aws::network::{self, NetworkComponent, NetworkCostLine};
+use pekora_aws::transform::aws::node_pricing;
+use pekora_aws::transform::aws::normalize::{self, NormalizedPriceRow, PurchaseModel};
+use pekora_aws::transform::aws::optimize::{self, RegionRates, UsageLine};
+use pekora_aws::transform::aws::orderable;
+use pekora_aws::transform::aws::rds_reserved;
+use pekora_aws::transform::aws::rds_storage::{
+    self, AuroraClusterSpec, AuroraStorageConfig, RdsStorageSpec,
+};
+use pekora_aws::transform::aws::recommendation::{self, Commitment, Ec2Usage};
+use pekora_aws::transform::aws::rightsizing;
+use pekora_aws::transform::aws::savings_plan_rates;
+use pekora_aws::transform::aws::simulate::{self, CandidatePlan, UsageSample};
+use pekora_aws::util::hash::HashAlgorithm;
+use pekora_aws::Pekora;
+use pekora_cli::config::{Config, ConfigOverrides, OutputFormat};
+use pekora_cli::daemon;
+use pekora_cli::doctor::{self, CheckStatus};
+use pekora_cli::notify::{Notification, NotificationDispatcher, NotificationKind};
+use pekora_cli::output;
+use pekora_cli::repl::{parse_filters, ReplSession};
+use pekora_cli::server;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+#[derive(Parser, Debug, Clone)]
+#[command(version, about, long_about = None)]
+pub struct Cli {
+    /// Path to the config file. Defaults to `pekora.toml` if present
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+    /// Print network, disk and transform statistics after the command finishes
+    #[arg(long, global = true)]
+    pub stats: bool,
+    /// Timeout of each price bulk request in seconds [env: PEKORA_TIMEOUT_SECONDS]
+    #[arg(long, global = true)]
+    pub timeout_seconds: Option<u64>,
+    /// Proxy URL for price bulk requests [env: PEKORA_PROXY]
+    #[arg(long, global = true)]
+    pub proxy: Option<String>,
+    /// Maximum number of price bulk requests in flight [env: PEKORA_MAX_CONCURRENT_REQUESTS]
+    #[arg(long, global = true)]
+    pub max_concurrent_requests: Option<usize>,
+    /// Adapt the number of price bulk requests in flight between this and
+    /// --max-concurrent-requests (default 16), backing off when throttled
+    /// [env: PEKORA_MIN_CONCURRENT_REQUESTS]
+    #[arg(long, global = true)]
+    pub min_concurrent_requests: Option<usize>,
+    /// How long HEAD results are reused in seconds, 0 to always ask upstream
+    /// [env: PEKORA_HEAD_CACHE_TTL_SECONDS] [default: 600]
+    #[arg(long, global = true)]
+    pub head_cache_ttl_seconds: Option<u64>,
+    /// Algorithm of derived file names and dataset fingerprints [env: PEKORA_HASH_ALGORITHM]
+    #[arg(long, global = true, value_enum)]
+    pub hash_algorithm: Option<HashAlgorithm>,
+    /// Cache directory [env: PEKORA_CACHE_DIRECTORY] [default: cached]
+    #[arg(long, global = true)]
+    pub cache_directory: Option<String>,
+    /// Maximum age of cache entries in hours [env: PEKORA_CACHE_MAX_AGE_HOURS]
+    #[arg(long, global = true)]
+    pub cache_max_age_hours: Option<i64>,
+    /// Namespace of the team or payer account served, keeping its derived data apart
+    /// [env: PEKORA_NAMESPACE]
+    #[arg(long, global = true)]
+    pub namespace: Option<Namespace>,
+    /// Regions queried by EC2 commands [env: PEKORA_REGIONS]
+    #[arg(long = "region", global = true)]
+    pub regions: Vec<String>,
+    /// Price bulk API endpoint [env: PEKORA_BASE_URL]
+    #[arg(long, global = true)]
+    pub base_url: Option<String>,
+    /// Price list partition [env: PEKORA_PARTITION]
+    #[arg(long, global = true, value_enum)]
+    pub partition: Option<Partition>,
+    /// AWS profile [env: PEKORA_PROFILE]
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+    /// IAM role to assume with the profile's credentials [env: PEKORA_ROLE_ARN]
+    #[arg(long, global = true)]
+    pub role_arn: Option<String>,
+    /// External ID of the assumed role [env: PEKORA_EXTERNAL_ID]
+    #[arg(long, global = true, requires = "role_arn")]
+    pub external_id: Option<String>,
+    /// Region of AWS API calls not tied to a queried region, e.g. STS [env: PEKORA_AWS_REGION]
+    #[arg(long, global = true)]
+    pub aws_region: Option<String>,
+    /// Account of the config's accounts to call AWS APIs as [env: PEKORA_ACCOUNT]
+    #[arg(long, global = true)]
+    pub account: Option<String>,
+    /// Output format of reports [env: PEKORA_OUTPUT_FORMAT]
+    #[arg(long = "output", alias = "output-format", global = true, value_enum)]
+    pub output_format: Option<OutputFormat>,
+    /// Fail on unknown enum values in pricing files [env: PEKORA_STRICT]
+    #[arg(long, global = true)]
+    pub strict: bool,
+    /// Append every upstream request to the request log [env: PEKORA_LOG_REQUESTS]
+    #[arg(long, global = true)]
+    pub log_requests: bool,
+    #[command(subcommand)]
+    pub command: Commands,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Commands {
+    /// Interactive mode keeping datasets loaded between queries
+    Repl,
+    /// Check connectivity, credentials, cache directory and config, printing what to fix
+    Doctor,
+    /// Serve prices, savings plan rates and estimates over HTTP. Queries without a region use
+    /// the first configured region, us-east-1 by default. Requests select one of the configured
+    /// `namespaces` with the `X-Pekora-Namespace` header.
+    Serve {
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+        /// Also serve the gRPC API of proto/pekora/v1/pricing.proto on this port
+        #[arg(long)]
+        grpc_port: Option<u16>,
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: std::net::IpAddr,
+    },
+    /// Keep refreshing current offers into the cache and rerunning the configured pipelines,
+    /// serving /healthz and /readyz
+    Daemon {
+        /// Time between refreshes as a number followed by s, m, h or d
+        #[arg(long, default_value = "6h", value_parser = daemon::parse_interval)]
+        interval: std::time::Duration,
+        /// Port of the health endpoints
+        #[arg(long, default_value_t = 8081)]
+        port: u16,
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: std::net::IpAddr,
+        /// Maximum number of offers downloaded at once
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+        /// Only refresh these services. Refreshes every service unless specified.
+        #[arg(long = "service")]
+        services: Vec<String>,
+    },
+    /// Dashboard of cache freshness, sizes, in-flight downloads and recent errors
+    #[cfg(feature = "tui")]
+    Top {
+        #[arg(long, default_value_t = 2)]
+        refresh_seconds: u64,
+    },
+    /// Run a pipeline defined in the config file
+    Run {
+        pipeline: String,
+        /// Export even if the output is already up to date with the offer version
+        #[arg(long)]
+        force: bool,
+    },
+    /// Write a normalized dataset to a file, skipped if already exported from the same offer
+    /// version. Uses the first configured region, us-east-1 by default.
+    Export {
+        #[arg(value_enum)]
+        dataset: ExportDataset,
+        /// Attribute filter as <field>=<value>, e.g. instanceType=m5.large
+        #[arg(long = "filter")]
+        filters: Vec<String>,
+        #[arg(long, value_enum, default_value_t = pipeline::ExportFormat::JsonLines)]
+        format: pipeline::ExportFormat,
+        /// Output file. Prints to stdout unless specified.
+        #[arg(long = "output-file")]
+        output: Option<String>,
+        /// Export even if the output is already up to date with the offer version
+        #[arg(long)]
+        force: bool,
+        /// Export only rows changed since the offer version last exported to the output, marked
+        /// in a `change` field. Supported for ec2-on-demand.
+        #[arg(long, requires = "output")]
+        delta: bool,
+    },
+    /// Check pricing files against the registered attribute schemas
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommands,
+    },
+    /// Look up prices through the Price List Query API instead of bulk files
+    Query {
+        #[command(subcommand)]
+        command: QueryCommands,
+    },
+    /// Load datasets into a database
+    Load {
+        #[command(subcommand)]
+        command: LoadCommands,
+    },
+    /// Price of the product matching all filters in the current bulk file. Uses the first
+    /// configured region, us-east-1 by default.
+    Price {
+        /// Attribute filter as <field>=<value>, e.g. instanceType=m5.large
+        filters: Vec<String>,
+        #[arg(long, default_value = "AmazonEC2")]
+        service: String,
+        /// Price a standard reservation of this term, 1yr or 3yr, instead of on-demand
+        #[arg(long)]
+        term: Option<String>,
+        /// Payment option of the reservation, NoUpfront, PartialUpfront or AllUpfront
+        #[arg(long, default_value = "NoUpfront")]
+        payment_option: String,
+        /// Unit of the price dimension, e.g. Hrs
+        #[arg(long)]
+        unit: Option<String>,
+        /// Print how the price was resolved, from the service index to the dimension chosen
+        #[arg(long)]
+        explain: bool,
+    },
+    /// Savings plan or reserved instance recommendations for steady EC2 usage, as CSV in the
+    /// column layout of Cost Explorer recommendation exports. Uses the first configured region,
+    /// us-east-1 by default.
+    Recommend {
+        #[arg(value_enum)]
+        kind: RecommendationKind,
+        /// CSV with columns instance_type, instances and optionally operating_system and tenancy
+        #[arg(long, required_unless_present_any = ["cost_and_usage", "cur", "cost_explorer_days"])]
+        usage: Option<String>,
+        /// Instead of --usage, output of `aws ce get-cost-and-usage` grouped by INSTANCE_TYPE
+        /// with the UsageQuantity metric, averaged over its period
+        #[arg(long, conflicts_with_all = ["usage", "cur"])]
+        cost_and_usage: Option<String>,
+        /// Instead of --usage, a CUR 2.0 parquet file, directory or `s3://bucket/prefix`,
+        /// averaged over its period. Requires the parquet feature.
+        #[arg(long, conflicts_with = "usage")]
+        cur: Option<String>,
+        /// Instead of --usage, running hours of the last days asked of Cost Explorer, averaged
+        /// over them. Requires the cost-explorer feature and billing permissions.
+        #[arg(long, conflicts_with_all = ["usage", "cost_and_usage", "cur"])]
+        cost_explorer_days: Option<u32>,
+        /// Term, 1yr or 3yr
+        #[arg(long, default_value = "1yr")]
+        term: String,
+        /// Payment option, NoUpfront, PartialUpfront or AllUpfront
+        #[arg(long, default_value = "NoUpfront")]
+        payment_option: String,
+        /// Output file. Prints to stdout unless specified.
+        #[arg(long = "output-file")]
+        output: Option<String>,
+    },
+    /// EC2 instance usage of a CUR 2.0 export by region, instance type and purchase option, as
+    /// CSV
+    #[cfg(feature = "parquet")]
+    CurUsage {
+        /// Parquet file, directory searched recursively, or `s3://bucket/prefix` of the export
+        path: String,
+        /// Output file. Prints to stdout unless specified.
+        #[arg(long = "output-file")]
+        output: Option<String>,
+    },
+    /// Utilization of Savings Plans and coverage of reservations from Cost Explorer output
+    CommitmentHealth {
+        /// Output of `aws ce get-savings-plans-utilization`
+        #[arg(long)]
+        utilization: Option<String>,
+        /// Output of `aws ce get-reservation-coverage`
+        #[arg(long)]
+        coverage: Option<String>,
+        /// Instead of --utilization and --coverage, both over the last days asked of Cost
+        /// Explorer. Requires the cost-explorer feature and billing permissions.
+        #[arg(long, conflicts_with_all = ["utilization", "coverage"])]
+        cost_explorer_days: Option<u32>,
+    },
+    /// Monthly hours at which each EC2 reservation and savings plan rate beats on-demand, as
+    /// CSV. Uses the first configured region, us-east-1 by default.
+    BreakEven {
+        /// Only rates of this instance type
+        #[arg(long)]
+        instance_type: Option<String>,
+        /// Output file. Prints to stdout unless specified.
+        #[arg(long = "output-file")]
+        output: Option<String>,
+    },
+    /// Replays an hourly usage trace against a savings plan commitment, reporting utilization,
+    /// coverage and realized savings
+    Simulate {
+        /// CSV with columns timestamp (RFC 3339) and on_demand_usd, the on-demand spend eligible
+        /// for the plan
+        #[arg(long)]
+        usage: String,
+        /// Commitment in USD per hour
+        #[arg(long)]
+        commitment: Decimal,
+        /// Average discount of the plan over the on-demand rates of the usage, in percent
+        #[arg(long)]
+        discount_percent: Decimal,
+    },
+    /// SKUs added and removed and prices changed between two offer versions. Uses the first
+    /// configured region, us-east-1 by default. Added EC2 SKUs that the SKU history saw before
+    /// are marked restored.
+    Diff {
+        #[arg(long, default_value = "AmazonEC2")]
+        service: String,
+        /// Older offer version, e.g. 20240312153724
+        #[arg(long)]
+        from: String,
+        /// Newer offer version
+        #[arg(long, default_value = "current")]
+        to: String,
+    },
+    /// Price per vCPU and per GiB of memory of EC2 instance types, on-demand, reserved and under
+    /// savings plans. Covers the configured regions, us-east-1 by default.
+    UnitCosts {
+        /// CSV of launch dates with columns instance and launch_date, overriding the bundled ones
+        #[arg(long)]
+        launch_dates: Option<String>,
+        /// Only print the most recently launched instance types at or under this USD per hour
+        #[arg(long)]
+        newest_under: Option<Decimal>,
+        /// Output file. Prints to stdout unless specified.
+        #[arg(long = "output-file")]
+        output: Option<String>,
+    },
+    /// Price per GPU and per GiB of GPU memory of accelerated EC2 instance types, on-demand,
+    /// reserved and under savings plans, as CSV. Covers the configured regions, us-east-1 by
+    /// default.
+    GpuPrices {
+        /// Output file. Prints to stdout unless specified.
+        #[arg(long = "output-file")]
+        output: Option<String>,
+    },
+    /// ElastiCache reserved node offerings against on-demand node prices, as CSV. Covers the
+    /// configured regions, us-east-1 by default.
+    ElasticacheReserved {
+        /// Node type, e.g. cache.r6g.large. All node types unless specified.
+        #[arg(long)]
+        node_type: Option<String>,
+        /// Output file. Prints to stdout unless specified.
+        #[arg(long = "output-file")]
+        output: Option<String>,
+    },
+    /// On-demand price of ElastiCache node types per GiB of memory usable by the engine, as CSV.
+    /// Covers the configured regions, us-east-1 by default.
+    ElasticacheMemory {
+        /// redis or memcached
+        #[arg(long, default_value = "redis")]
+        engine: String,
+        /// Output file. Prints to stdout unless specified.
+        #[arg(long = "output-file")]
+        output: Option<String>,
+    },
+    /// Graviton instance types priced against their x86 equivalents, as CSV unless the output
+    /// format is xlsx or md. Covers the configured regions, us-east-1 by default.
+    CompareArchitectures {
+        /// Throughput of a Graviton family relative to its x86 equivalent as <family>=<ratio>,
+        /// e.g. m7g=1.1. 1 unless specified.
+        #[arg(long)]
+        performance: Vec<String>,
+        /// Output file. Prints to stdout unless specified.
+        #[arg(long = "output-file")]
+        output: Option<String>,
+    },
+    /// Monthly cost of an Aurora cluster, under both storage configurations unless one is given.
+    /// Uses the first configured region, us-east-1 by default.
+    Aurora {
+        /// Aurora MySQL or Aurora PostgreSQL
+        #[arg(long, default_value = "Aurora MySQL")]
+        engine: String,
+        /// Instance class of the writer and replicas, e.g. db.r6g.large
+        #[arg(long)]
+        instance_class: String,
+        /// Readers in addition to the writer
+        #[arg(long, default_value = "0")]
+        replicas: u32,
+        /// Storage in GB
+        #[arg(long)]
+        storage: Decimal,
+        /// I/O requests per month
+        #[arg(long, default_value = "0")]
+        io_requests: Decimal,
+        /// standard or io-optimized
+        #[arg(long)]
+        storage_config: Option<String>,
+        /// Hours each instance runs per month
+        #[arg(long, default_value = "730")]
+        hours: Decimal,
+    },
+    /// On-demand prices of the RDS instance classes that can be launched. Uses the first
+    /// configured region, us-east-1 by default.
+    RdsOrderable {
+        /// Engines to ask the RDS API about, as it names them, e.g. postgres or aurora-mysql
+        #[arg(long = "engine", required_unless_present = "options")]
+        engines: Vec<String>,
+        /// Instead, read the output of `aws rds describe-orderable-db-instance-options` for the
+        /// same region
+        #[arg(long, conflicts_with = "engines")]
+        options: Option<String>,
+    },
+    /// Checks reserved DB instance offerings of the RDS API against the pricing list, as CSV.
+    /// Uses the first configured region, us-east-1 by default.
+    RdsReservedCheck {
+        /// Output of `aws rds describe-reserved-db-instances-offerings` for the same region.
+        /// Asks the RDS API unless specified.
+        #[arg(long)]
+        offerings: Option<String>,
+        /// Output file. Prints to stdout unless specified.
+        #[arg(long = "output-file")]
+        output: Option<String>,
+    },
+    /// Lists node prices of Redshift, OpenSearch Service, MemoryDB or DocumentDB, on-demand and
+    /// with the cheapest reservation, as CSV. Uses the first configured region, us-east-1 by
+    /// default.
+    NodePrices {
+        #[arg(long, value_enum)]
+        service: NodeService,
+        /// Output of `aws docdb describe-orderable-db-instance-options` or its RDS equivalent.
+        /// Only lists node types orderable in the region if specified.
+        #[arg(long)]
+        orderable: Option<String>,
+        /// DocumentDB only. Only lists instance classes orderable in the region, asked of the
+        /// DocumentDB API instead of read from --orderable.
+        #[arg(long, conflicts_with_all = ["orderable", "redshift_cluster", "engine_version"])]
+        docdb_orderable: bool,
+        /// Redshift only. Only lists node types this cluster can be resized to, asked of the
+        /// Redshift API.
+        #[arg(long, conflicts_with_all = ["orderable", "engine_version"])]
+        redshift_cluster: Option<String>,
+        /// OpenSearch only. Only lists instance types available to domains of this engine
+        /// version, e.g. OpenSearch_2.11, asked of the OpenSearch Service API.
+        #[arg(long, conflicts_with = "orderable")]
+        engine_version: Option<String>,
+        /// Output file. Prints to stdout unless specified.
+        #[arg(long = "output-file")]
+        output: Option<String>,
+    },
+    /// Cost per instance of Dedicated Hosts filled with one instance size, against shared
+    /// tenancy Linux prices, as CSV. Uses the first configured region, us-east-1 by default.
+    DedicatedHosts {
+        /// Host family, e.g. m5. All families unless specified.
+        #[arg(long)]
+        family: Option<String>,
+        /// Also price the cheapest host reservation of each family, through the EC2 API
+        #[arg(long)]
+        reservations: bool,
+        /// Output file. Prints to stdout unless specified.
+        #[arg(long = "output-file")]
+        output: Option<String>,
+    },
+    /// Prices the instance types Compute Optimizer recommends for instances of the configured
+    /// regions against their current ones, on-demand Linux, as CSV
+    Rightsizing {
+        /// Output of `aws compute-optimizer get-ec2-instance-recommendations`. Asks Compute
+        /// Optimizer in each region unless specified.
+        #[arg(long)]
+        recommendations: Option<String>,
+        /// Output file. Prints to stdout unless specified.
+        #[arg(long = "output-file")]
+        output: Option<String>,
+    },
+    /// Checks Savings Plan rates of the Savings Plans API against the bulk savings plan file, as
+    /// CSV. Uses the first configured region, us-east-1 by default.
+    SavingsPlanCheck {
+        /// Output of `aws savingsplans describe-savings-plans-offering-rates`. Rates of other
+        /// regions are skipped. Fetches the Compute Savings Plans rates of the region unless
+        /// specified.
+        #[arg(long)]
+        rates: Option<String>,
+        /// Output file. Prints to stdout unless specified.
+        #[arg(long = "output-file")]
+        output: Option<String>,
+    },
+    /// Monthly cost of RDS instance storage. Uses the first configured region, us-east-1 by
+    /// default.
+    RdsStorage {
+        /// Database engine, e.g. MySQL or PostgreSQL
+        #[arg(long, default_value = "MySQL")]
+        engine: String,
+        /// gp2, gp3, io1 or io2
+        #[arg(long, default_value = "gp3")]
+        volume_type: String,
+        #[arg(long)]
+        multi_az: bool,
+        /// Size in GB
+        #[arg(long)]
+        size: Decimal,
+        /// Provisioned IOPS
+        #[arg(long)]
+        iops: Option<Decimal>,
+    },
+    /// Monthly cost of an EBS volume. Uses the first configured region, us-east-1 by default.
+    Volume {
+        /// Volume type, e.g. gp3, io2 or st1
+        #[arg(long, default_value = "gp3")]
+        volume_type: String,
+        /// Size in GiB
+        #[arg(long)]
+        size: Decimal,
+        /// Provisioned IOPS
+        #[arg(long)]
+        iops: Option<Decimal>,
+        /// Provisioned throughput in MiB/s
+        #[arg(long)]
+        throughput: Option<Decimal>,
+    },
+    /// Cost of transferring data out of a region in a month, to the internet or another region.
+    /// Uses the first configured region as the source, us-east-1 by default.
+    Transfer {
+        /// `internet` or a region code, e.g. eu-west-1
+        #[arg(long, default_value = "internet")]
+        to: String,
+        /// GB transferred in the month
+        #[arg(long)]
+        gb: Decimal,
+    },
+    /// Monthly cost of CloudWatch metrics, dashboards, alarms and logs, before the free tier.
+    /// Uses the first configured region, us-east-1 by default.
+    Cloudwatch {
+        /// Custom metrics
+        #[arg(long, default_value_t = Decimal::ZERO)]
+        metrics: Decimal,
+        #[arg(long, default_value_t = Decimal::ZERO)]
+        dashboards: Decimal,
+        /// Standard resolution alarm metrics
+        #[arg(long, default_value_t = Decimal::ZERO)]
+        alarms: Decimal,
+        /// GB of logs ingested in the month
+        #[arg(long, default_value_t = Decimal::ZERO)]
+        logs_ingested_gb: Decimal,
+        /// Average GB of logs stored over the month
+        #[arg(long, default_value_t = Decimal::ZERO)]
+        logs_stored_gb: Decimal,
+    },
+    /// Monthly cost of an EC2 workload on-demand, reserved and under savings plans. Uses the
+    /// first configured region, us-east-1 by default.
+    Estimate {
+        /// Instance type, e.g. m5.large
+        #[arg(long, required_unless_present = "vcpus", conflicts_with = "vcpus")]
+        instance_type: Option<String>,
+        /// Minimum vCPUs, picking the cheapest instance type with enough vCPUs and memory
+        #[arg(long, requires = "memory")]
+        vcpus: Option<u32>,
+        /// Minimum memory in GiB
+        #[arg(long, requires = "vcpus")]
+        memory: Option<Decimal>,
+        #[arg(long, default_value = "Linux")]
+        operating_system: String,
+        #[arg(long, default_value = "Shared")]
+        tenancy: String,
+        #[arg(long, default_value = "1")]
+        instances: Decimal,
+        /// Hours each instance runs per month
+        #[arg(long, default_value = "730")]
+        hours: Decimal,
+        /// Estimate commitments of this term only, 1yr or 3yr
+        #[arg(long, requires = "payment_option")]
+        term: Option<String>,
+        /// Estimate commitments of this payment option only, NoUpfront, PartialUpfront or
+        /// AllUpfront
+        #[arg(long, requires = "term")]
+        payment_option: Option<String>,
+        /// Network infrastructure to add as resource[:count[:processed_gb]], where resource is
+        /// nat, alb, nlb, gwlb, clb or endpoint, e.g. nat:2:500. Repeatable.
+        #[arg(long)]
+        network: Vec<NetworkComponent>,
+    },
+    /// Reservations and savings plan commitments minimizing the cost of EC2 usage across regions,
+    /// with projected savings and break-even time
+    Optimize {
+        /// CSV with columns region, instance_type, hours per month and optionally
+        /// operating_system and tenancy
+        #[arg(long)]
+        usage: String,
+        /// Term, 1yr or 3yr
+        #[arg(long, default_value = "1yr")]
+        term: String,
+        /// Payment option, NoUpfront, PartialUpfront or AllUpfront
+        #[arg(long, default_value = "NoUpfront")]
+        payment_option: String,
+    },
+    /// Download pricing files into the cache
+    Fetch {
+        #[command(subcommand)]
+        command: FetchCommands,
+    },
+    Test {
+        #[command(subcommand)]
+        command: TestCommands,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum AuditCommands {
+    /// Report which services' offer files match their schema, have none, or fail to parse.
+    /// Checks the first configured region, us-east-1 by default.
+    Coverage {
+        /// Maximum number of offers downloaded at once
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+        /// Only audit these services. Audits every service unless specified.
+        #[arg(long = "service")]
+        services: Vec<String>,
+    },
+    /// Upstream requests recorded with `--log-requests`, with the content hash each returned
+    Requests {
+        /// RFC 3339 timestamp, date such as 2024-03-01, or age such as 30m, 12h or 7d
+        #[arg(long, default_value = "1d")]
+        since: String,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum LoadCommands {
+    /// Upsert the service index and the EC2 on-demand and savings plan prices of the configured
+    /// regions, us-east-1 by default, into PostgreSQL
+    Postgres {
+        /// Connection URI, database_url of the config file or PEKORA_DATABASE_URL by default
+        #[arg(long)]
+        dsn: Option<String>,
+    },
+    /// Upsert EC2 products and on-demand, reserved and savings plan rates of the configured
+    /// regions, us-east-1 by default, into a SQLite database file
+    Sqlite {
+        /// Database file, created if missing
+        #[arg(long)]
+        path: String,
+        /// Also load instance specs, through the EC2 API
+        #[arg(long)]
+        instance_specs: bool,
+    },
+    /// Like sqlite, into a DuckDB database file. Requires the duckdb feature
+    Duckdb {
+        /// Database file, created if missing
+        #[arg(long)]
+        path: String,
+        /// Also load instance specs, through the EC2 API
+        #[arg(long)]
+        instance_specs: bool,
+    },
+    /// Append the EC2 on-demand and savings plan prices of the configured regions, us-east-1 by
+    /// default, to the price history in ClickHouse, tagged with their offer versions
+    Clickhouse {
+        /// HTTP interface URL, with the database and credentials as query parameters if needed
+        #[arg(long, default_value = "http://localhost:8123/")]
+        url: String,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum QueryCommands {
+    /// Products matching all filters, limited to the first configured region if any
+    Products {
+        #[arg(long, default_value = "AmazonEC2")]
+        service: String,
+        /// Attribute filter as <field>=<value>, e.g. instanceType=m5.large
+        #[arg(long = "filter")]
+        filters: Vec<String>,
+    },
+    /// Hourly on-demand prices of an instance type on EC2 and as a SageMaker ML instance,
+    /// limited to the first configured region if any
+    Instances {
+        /// EC2 instance type, e.g. m5.large. SageMaker prices it as ml.m5.large.
+        instance_type: String,
+        /// Attribute filter as <field>=<value> on EC2 products, e.g. operatingSystem=Linux
+        #[arg(long = "filter")]
+        filters: Vec<String>,
+    },
+    /// Values of a product attribute across a service
+    AttributeValues {
+        #[arg(long, default_value = "AmazonEC2")]
+        service: String,
+        #[arg(long)]
+        attribute: String,
+    },
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+pub enum ExportDataset {
+    Ec2OnDemand,
+    ComputeSavingsPlan,
+    Ec2InstanceSavingsPlan,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+pub enum NodeService {
+    Redshift,
+    Opensearch,
+    Memorydb,
+    Docdb,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+pub enum RecommendationKind {
+    SavingsPlan,
+    ReservedInstance,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum FetchCommands {
+    /// Fetch the current offer of every service and region
+    All {
+        /// Maximum number of offers downloaded at once
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+        /// Only fetch these services. Fetches every service unless specified.
+        #[arg(long = "service")]
+        services: Vec<String>,
+    },
+    /// List the services with pricing files
+    Services,
+    /// Region codes and price list location names, the bundled ones with regions announced in
+    /// SSM public parameters since, one code and name per line.
+    RegionNames,
+    /// Savings Plans offerings sold in the first configured region, us-east-1 by default
+    SavingsPlansOfferings {
+        /// Compute or EC2Instance
+        #[arg(long, default_value = "Compute")]
+        plan_type: String,
+    },
+    /// List the regions a service has pricing files for
+    Regions {
+        #[arg(long, default_value = "AmazonEC2")]
+        service: String,
+    },
+    /// Download a pricing file of the first configured region. Text output summarizes it, JSON
+    /// output prints it whole.
+    Pricing {
+        #[arg(long, default_value = "AmazonEC2")]
+        service: String,
+        /// Offer version, e.g. 20240312153724
+        #[arg(long, default_value = "current")]
+        version: String,
+    },
+    /// Download a Savings Plans file of the first configured region and list its rates
+    SavingsPlans {
+        #[arg(long, default_value = "AWSComputeSavingsPlan")]
+        service: String,
+        /// Offer version, e.g. 20240312234047
+        #[arg(long, default_value = "current")]
+        version: String,
+        /// Location types to keep. Only AWS Regions are kept unless specified.
+        #[arg(long = "location-type", value_enum)]
+        location_types: Vec<LocationType>,
+    },
+    /// Specs of the instance types EC2 offers in the configured regions, or in every enabled
+    /// region if none are configured
+    Ec2InstanceTypes,
+    /// List the instance types EC2 offers in the first configured region and where
+    Ec2Offerings {
+        /// Locate offerings by availability zone ID or by region
+        #[arg(long, value_enum, default_value_t = OfferingLocation::Az)]
+        location_type: OfferingLocation,
+        /// Instead, list instance types with on-demand prices that are not offered
+        #[arg(long)]
+        check_prices: bool,
+    },
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+pub enum OfferingLocation {
+    Az,
+    Region,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TestCommands {
+    /// Spot Advisor interruption rates joined with on-demand prices of the first configured region
+    SpotAdvisor,
+    /// Regions enabled for the account
+    Ec2Regions,
+    RedisTypeSpecificParameters,
+    MemcachedTypeSpecificParameters,
+    /// Reserved cache node offerings of the first configured region
+    ReservedCacheNodesOfferings {
+        #[arg(long)]
+        node_type: Option<String>,
+    },
+    /// Parameters of a MemoryDB parameter group
+    MemorydbParameters {
+        #[arg(long, default_value = DEFAULT_MEMORYDB_PARAMETER_GROUP)]
+        parameter_group: String,
+    },
+    /// Engine default cluster parameters of a DocumentDB parameter group family
+    DocdbClusterParameters {
+        #[arg(long, default_value = DEFAULT_DOCDB_PARAMETER_GROUP_FAMILY)]
+        family: String,
+    },
+    /// Send a test notification to all configured sinks
+    Notify {
+        #[arg(long, default_value = "pekora test notification")]
+        title: String,
+        #[arg(long, default_value = "If you can read this, notifications work.")]
+        body: String,
+    },
+}
+
+impl Cli {
+    fn config_overrides(&self) -> ConfigOverrides {
+        ConfigOverrides {
+            cache_directory: self.cache_directory.clone(),
+            cache_max_age_hours: self.cache_max_age_hours,
+            namespace: self.namespace.clone(),
+            head_cache_ttl_seconds: self.head_cache_ttl_seconds,
+            hash_algorithm: self.hash_algorithm,
+            timeout_seconds: self.timeout_seconds,
+            proxy: self.proxy.clone(),
+            max_concurrent_requests: self.max_concurrent_requests,
+            min_concurrent_requests: self.min_concurrent_requests,
+            regions: if self.regions.is_empty() {
+                None
+            } else {
+                Some(self.regions.clone())
+            },
+            base_url: self.base_url.clone(),
+            partition: self.partition,
+            profile: self.profile.clone(),
+            role_arn: self.role_arn.clone(),
+            external_id: self.external_id.clone(),
+            aws_region: self.aws_region.clone(),
+            account: self.account.clone(),
+            output_format: self.output_format,
+            strict: self.strict.then_some(true),
+            log_requests: self.log_requests.then_some(true),
+            database_url: None,
+        }
+    }
+}
+
+async fn main_load_command(
+    command: LoadCommands,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let regions = config.regions_or_default();
+    match command {
+        #[cfg(feature = "postgres")]
+        LoadCommands::Postgres { dsn } => load_postgres(dsn, regions, config, pekora).await,
+        #[cfg(not(feature = "postgres"))]
+        LoadCommands::Postgres { .. } => {
+            anyhow::bail!("PostgreSQL requires pekora built with the postgres feature")
+        }
+        #[cfg(feature = "sqlite")]
+        LoadCommands::Sqlite {
+            path,
+            instance_specs,
+        } => {
+            load_local_database(
+                Engine::Sqlite,
+                &path,
+                instance_specs,
+                regions,
+                config,
+                pekora,
+            )
+            .await
+        }
+        #[cfg(not(feature = "sqlite"))]
+        LoadCommands::Sqlite { .. } => {
+            anyhow::bail!("SQLite requires pekora built with the sqlite feature")
+        }
+        #[cfg(feature = "duckdb")]
+        LoadCommands::Duckdb {
+            path,
+            instance_specs,
+        } => {
+            load_local_database(
+                Engine::DuckDb,
+                &path,
+                instance_specs,
+                regions,
+                config,
+                pekora,
+            )
+            .await
+        }
+        #[cfg(not(feature = "duckdb"))]
+        LoadCommands::Duckdb { .. } => {
+            anyhow::bail!("DuckDB requires pekora built with the duckdb feature")
+        }
+        LoadCommands::Clickhouse { url } => append_price_history(&url, regions, pekora).await,
+    }
+}
+
+/// The service index and EC2 prices of `regions`, into PostgreSQL at `dsn` or the configured one.
+#[cfg(feature = "postgres")]
+async fn load_postgres(
+    dsn: Option<String>,
+    regions: Vec<String>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let Some(dsn) = dsn.as_ref().or(config.database_url.as_ref()) else {
+        anyhow::bail!("Pass --dsn, or set database_url in the config file or PEKORA_DATABASE_URL");
+    };
+    let services = pekora
+        .cacheable_builder()
+        .build(pekora.clients().service_index())
+        .load(&())
+        .await?
+        .result;
+    let mut datasets = Vec::with_capacity(regions.len());
+    for region in regions {
+        let on_demand = pekora.dataset::<Ec2OnDemand>(region.clone()).await?;
+        let compute = pekora.dataset::<ComputeSavingsPlan>(region.clone()).await?;
+        let ec2_instance = pekora.dataset::<Ec2InstanceSavingsPlan>(region).await?;
+        datasets.push((on_demand, compute, ec2_instance));
+    }
+    let store = PostgresStore::connect(dsn).await?;
+    let mut load = store.begin().await?;
+    load.services(&services).await?;
+    let region_names = pekora.region_names().await;
+    for (on_demand, compute, ec2_instance) in &datasets {
+        let mut price_rows = normalize::from_on_demand(on_demand.rows(), &region_names);
+        for savings_plans in [compute.rows(), ec2_instance.rows()] {
+            price_rows.extend(normalize::from_savings_plans(
+                savings_plans,
+                on_demand.rows(),
+                &region_names,
+            ));
+            load.savings_plan_rates(savings_plans).await?;
+        }
+        load.price_rows(&price_rows).await?;
+        load.offer(on_demand.metadata()).await?;
+        load.offer(compute.metadata()).await?;
+        load.offer(ec2_instance.metadata()).await?;
+    }
+    load.commit().await
+}
+
+/// EC2 products and rates of `regions`, with instance specs if `with_instance_specs` is set.
+#[cfg(any(feature = "sqlite", feature = "duckdb"))]
+async fn load_local_database(
+    engine: Engine,
+    path: &str,
+    with_instance_specs: bool,
+    regions: Vec<String>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let specs = if with_instance_specs {
+        pekora
+            .cacheable_builder()
+            .build(Ec2Client::new_cacheable_arc(Some(config.aws_sdk_config().await)).await)
+            .load(&regions)
+            .await?
+            .result
+    } else {
+        HashMap::new()
+    };
+    let mut responses = Vec::with_capacity(regions.len());
+    let mut rates = Vec::new();
+    for region in regions {
+        let (response, rows) = load_normalized_ec2_rows(pekora, region).await?;
+        responses.push(response);
+        rates.extend(rows);
+    }
+    sqlite::load(engine, Path::new(path), |load| {
+        load.products(
+            responses
+                .iter()
+                .flat_map(|response| response.products.values()),
+        )?;
+        load.rates(&rates);
+        load.instance_specs(specs.values());
+        Ok(())
+    })
+    .await
+}
+
+/// Normalized rows of each region's datasets, appended with the offer they were read from.
+async fn append_price_history(
+    url: &str,
+    regions: Vec<String>,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let writer = ClickHouseWriter::new(url);
+    writer.create_table().await?;
+    let region_names = pekora.region_names().await;
+    for region in regions {
+        let on_demand = pekora.dataset::<Ec2OnDemand>(region.clone()).await?;
+        let compute = pekora.dataset::<ComputeSavingsPlan>(region.clone()).await?;
+        let ec2_instance = pekora.dataset::<Ec2InstanceSavingsPlan>(region).await?;
+        writer
+            .append(
+                on_demand.metadata(),
+                &normalize::from_on_demand(on_demand.rows(), &region_names),
+            )
+            .await?;
+        for (metadata, savings_plans) in [
+            (compute.metadata(), compute.rows()),
+            (ec2_instance.metadata(), ec2_instance.rows()),
+        ] {
+            writer
+                .append(
+                    metadata,
+                    &normalize::from_savings_plans(savings_plans, on_demand.rows(), &region_names),
+                )
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn main_query_command(
+    cmd: QueryCommands,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let aws_sdk_config = Some(config.aws_sdk_config().await);
+    match cmd {
+        QueryCommands::Products { service, filters } => {
+            let cached = pekora.cacheable_builder().build(
+                PricingQueryClient::new_cacheable_arc(aws_sdk_config, pekora.hash_algorithm())
+                    .await,
+            );
+            let query = ProductQuery {
+                service_code: service,
+                region: config
+                    .regions
+                    .as_ref()
+                    .and_then(|regions| regions.first().cloned()),
+                filters: parse_filters(&filters)?.into_iter().collect(),
+            };
+            let items = cached.load(&query).await?.result;
+            match config.output_format() {
+                OutputFormat::Text => {
+                    for item in &items {
+                        let prices = item
+                            .terms
+                            .on_demand
+                            .values()
+                            .flat_map(|offering| offering.price_dimensions.values())
+                            .map(|dimension| {
+                                format!(
+                                    "{} USD/{}",
+                                    dimension
+                                        .price_per_unit
+                                        .get("USD")
+                                        .map_or("-", |price| price.raw()),
+                                    dimension.unit
+                                )
+                            })
+                            .collect::<Vec<_>>();
+                        println!(
+                            "{} {} {}",
+                            item.product.sku,
+                            item.product.product_family,
+                            prices.join(", ")
+                        );
+                    }
+                    println!("{} products", items.len());
+                }
+                format => output::print(format, &items),
+            }
+        }
+        QueryCommands::Instances {
+            instance_type,
+            filters,
+        } => {
+            let cached = pekora.cacheable_builder().build(
+                PricingQueryClient::new_cacheable_arc(aws_sdk_config, pekora.hash_algorithm())
+                    .await,
+            );
+            let region = config
+                .regions
+                .as_ref()
+                .and_then(|regions| regions.first().cloned());
+            let mut ec2_filters = parse_filters(&filters)?
+                .into_iter()
+                .collect::<BTreeMap<_, _>>();
+            ec2_filters.insert("instanceType".to_string(), instance_type.clone());
+            let queries = [
+                ProductQuery {
+                    service_code: "AmazonEC2".to_string(),
+                    region: region.clone(),
+                    filters: ec2_filters,
+                },
+                ProductQuery {
+                    service_code: "AmazonSageMaker".to_string(),
+                    region,
+                    filters: BTreeMap::from([(
+                        "instanceName".to_string(),
+                        format!("ml.{}", instance_type),
+                    )]),
+                },
+            ];
+            let mut rates = Vec::new();
+            for query in &queries {
+                for item in cached.load(query).await?.result {
+                    rates.extend(item.on_demand_rates());
+                }
+            }
+            let mut rows = normalize::from_on_demand(&rates, &*pekora.region_names().await);
+            rows.sort_by(|a, b| {
+                (&a.service_code, &a.region, &a.platform, a.component)
+                    .cmp(&(&b.service_code, &b.region, &b.platform, b.component))
+                    .then(a.effective_usd_per_hour.cmp(&b.effective_usd_per_hour))
+            });
+            match config.output_format() {
+                OutputFormat::Text => {
+                    for row in &rows {
+                        let usage = match (row.component, &row.platform) {
+                            (Some(component), _) => component.as_str(),
+                            (None, Some(platform)) => platform.as_str(),
+                            (None, None) => "-",
+                        };
+                        println!(
+                            "{} {} {} {} {}: {} USD per hour",
+                            row.service_code.as_deref().unwrap_or("-"),
+                            row.region.as_deref().unwrap_or("-"),
+                            row.instance_type.as_deref().unwrap_or("-"),
+                            usage,
+                            row.sku,
+                            row.effective_usd_per_hour.normalize()
+                        );
+                    }
+                    println!("{} prices", rows.len());
+                }
+                format => output::print(format, &rows),
+            }
+        }
+        QueryCommands::AttributeValues { service, attribute } => {
+            let values = PricingQueryClient::new(aws_sdk_config)
+                .await
+                .attribute_values(&service, &attribute)
+                .await?;
+            match config.output_format() {
+                OutputFormat::Text => {
+                    for value in &values {
+                        println!("{}", value);
+                    }
+                }
+                format => output::print(format, &values),
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn main_price_command(
+    query: &PriceQuery,
+    explain: bool,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<bool> {
+    let resolution = price::resolve(pekora, query).await?;
+    match config.output_format() {
+        OutputFormat::Text => {
+            if explain || resolution.price.is_none() {
+                for step in &resolution.trace {
+                    println!("{:>14}: {}", step.stage, step.detail);
+                }
+            }
+            if let Some(price) = &resolution.price {
+                println!(
+                    "{} {}: {} USD per {}",
+                    price.sku,
+                    price.rate_code,
+                    price.usd.normalize(),
+                    price.unit
+                );
+            }
+        }
+        format => output::print(format, &resolution),
+    }
+    Ok(resolution.price.is_some())
+}
+
+/// Where `recommend` reads EC2 usage from.
+enum UsageSource<'a> {
+    Csv(&'a str),
+    CostAndUsage(&'a str),
+    #[cfg(feature = "parquet")]
+    Cur(&'a str),
+    /// Days of usage before today
+    #[cfg(feature = "cost-explorer")]
+    CostExplorer(u32),
+}
+
+impl<'a> UsageSource<'a> {
+    fn from_args(
+        usage: Option<&'a str>,
+        cost_and_usage: Option<&'a str>,
+        cur: Option<&'a str>,
+        cost_explorer_days: Option<u32>,
+    ) -> anyhow::Result<Self> {
+        match (usage, cost_and_usage, cur, cost_explorer_days) {
+            (Some(usage), None, None, None) => Ok(Self::Csv(usage)),
+            (None, Some(cost_and_usage), None, None) => Ok(Self::CostAndUsage(cost_and_usage)),
+            #[cfg(feature = "parquet")]
+            (None, None, Some(cur), None) => Ok(Self::Cur(cur)),
+            #[cfg(not(feature = "parquet"))]
+            (None, None, Some(_), None) => {
+                anyhow::bail!("--cur requires pekora built with the parquet feature")
+            }
+            #[cfg(feature = "cost-explorer")]
+            (None, None, None, Some(days)) => Ok(Self::CostExplorer(days)),
+            #[cfg(not(feature = "cost-explorer"))]
+            (None, None, None, Some(_)) => anyhow::bail!(
+                "--cost-explorer-days requires pekora built with the cost-explorer feature"
+            ),
+            _ => anyhow::bail!(
+                "Exactly one of --usage, --cost-and-usage, --cur or --cost-explorer-days is required"
+            ),
+        }
+    }
+
+    /// `region` only filters CUR and Cost Explorer usage, which span every region.
+    #[cfg_attr(
+        not(any(feature = "parquet", feature = "cost-explorer")),
+        allow(unused_variables)
+    )]
+    async fn load(&self, region: &str, config: &Config) -> anyhow::Result<Vec<Ec2Usage>> {
+        Ok(match self {
+            Self::Csv(path) => csv::Reader::from_path(path)?
+                .deserialize()
+                .collect::<Result<Vec<Ec2Usage>, _>>()?,
+            Self::CostAndUsage(path) => {
+                let response: CostAndUsageResponse =
+                    serde_json::from_str(&std::fs::read_to_string(path)?)?;
+                cost_explorer::ec2_usage(&response)?
+            }
+            #[cfg(feature = "parquet")]
+            Self::Cur(path) => read_cur_usage(path, config).await?.ec2_usage(region),
+            #[cfg(feature = "cost-explorer")]
+            Self::CostExplorer(days) => {
+                let response = CostExplorerClient::new(Some(config.aws_sdk_config().await))
+                    .await
+                    .get_ec2_usage(&cost_explorer_period(*days), region)
+                    .await?;
+                cost_explorer::ec2_usage(&response)?
+            }
+        })
+    }
+}
+
+/// CUR usage of a local `path`, or of an `s3://` URI read with the configured AWS credentials.
+#[cfg(feature = "parquet")]
+async fn read_cur_usage(path: &str, config: &Config) -> anyhow::Result<CurUsageReport> {
+    if path.starts_with("s3://") {
+        cur::read_s3_usage(&config.aws_sdk_config().await, path).await
+    } else {
+        cur::read_usage(Path::new(path))
+    }
+}
+
+async fn main_recommend_command(
+    kind: RecommendationKind,
+    usage: &UsageSource<'_>,
+    commitment: &Commitment,
+    output: Option<&str>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let region = config.first_region();
+    let usage = usage.load(&region, config).await?;
+    match kind {
+        RecommendationKind::SavingsPlan => {
+            let on_demand = pekora.dataset::<Ec2OnDemand>(region.clone()).await?;
+            let savings_plans = pekora.dataset::<ComputeSavingsPlan>(region.clone()).await?;
+            let recommendations = recommendation::savings_plan_recommendation(
+                &region,
+                &usage,
+                on_demand.rows(),
+                savings_plans.rows(),
+                commitment,
+            )
+            .into_iter()
+            .collect::<Vec<_>>();
+            write_recommendations(&recommendations, output)
+        }
+        RecommendationKind::ReservedInstance => {
+            let response = pekora.fetch_pricing("AmazonEC2", &region).await?;
+            let recommendations = recommendation::reserved_instance_recommendations(
+                &region, &usage, &response, commitment,
+            );
+            write_recommendations(&recommendations, output)
+        }
+    }
+}
+
+/// The `days` days before today, in UTC.
+#[cfg(feature = "cost-explorer")]
+fn cost_explorer_period(days: u32) -> TimePeriod {
+    TimePeriod::days_before(chrono::Utc::now().date_naive(), days)
+}
+
+/// Savings Plans utilization and reservation coverage over the last `days`. A missing
+/// utilization is not an error, as Cost Explorer has none without Savings Plans.
+#[cfg(feature = "cost-explorer")]
+async fn fetch_commitment_health(
+    days: u32,
+    config: &Config,
+) -> anyhow::Result<(
+    Option<SavingsPlansUtilizationResponse>,
+    Option<ReservationCoverageResponse>,
+)> {
+    let client = CostExplorerClient::new(Some(config.aws_sdk_config().await)).await;
+    let period = cost_explorer_period(days);
+    let utilization = match client.get_savings_plans_utilization(&period).await {
+        Ok(utilization) => Some(utilization),
+        Err(e) => {
+            log::warn!("No Savings Plans utilization: {}", e);
+            None
+        }
+    };
+    let coverage = client.get_reservation_coverage(&period).await?;
+    Ok((utilization, Some(coverage)))
+}
+
+async fn main_commitment_health_command(
+    utilization: Option<&str>,
+    coverage: Option<&str>,
+    cost_explorer_days: Option<u32>,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let (utilization, coverage) = match cost_explorer_days {
+        #[cfg(feature = "cost-explorer")]
+        Some(days) => fetch_commitment_health(days, config).await?,
+        #[cfg(not(feature = "cost-explorer"))]
+        Some(_) => anyhow::bail!(
+            "--cost-explorer-days requires pekora built with the cost-explorer feature"
+        ),
+        None => {
+            if utilization.is_none() && coverage.is_none() {
+                anyhow::bail!("Either --utilization or --coverage is required");
+            }
+            let utilization: Option<SavingsPlansUtilizationResponse> = utilization
+                .map(|path| anyhow::Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?))
+                .transpose()?;
+            let coverage: Option<ReservationCoverageResponse> = coverage
+                .map(|path| anyhow::Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?))
+                .transpose()?;
+            (utilization, coverage)
+        }
+    };
+    let summary = cost_explorer::commitment_summary(utilization.as_ref(), coverage.as_ref());
+    match config.output_format() {
+        OutputFormat::Text => {
+            let value = |value: Option<Decimal>| value.map_or("-".to_string(), |v| v.to_string());
+            println!(
+                "Savings Plans: {} USD committed, {} USD unused ({}% utilized), {} USD net savings",
+                value(summary.savings_plans_commitment_usd),
+                value(summary.savings_plans_unused_usd),
+                value(summary.savings_plans_utilization_percent),
+                value(summary.savings_plans_net_savings_usd)
+            );
+            println!(
+                "Reservations: {} reserved hours, {} on-demand hours ({}% covered)",
+                value(summary.reserved_hours),
+                value(summary.on_demand_hours),
+                value(summary.reservation_coverage_percent)
+            );
+        }
+        format => output::print(format, &summary),
+    }
+    Ok(())
+}
+
+async fn main_break_even_command(
+    instance_type: Option<&str>,
+    output: Option<&str>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let (_, mut rows) = load_normalized_ec2_rows(pekora, config.first_region()).await?;
+    if let Some(instance_type) = instance_type {
+        rows.retain(|row| row.instance_type.as_deref() == Some(instance_type));
+    }
+    write_recommendations(&break_even::break_even(&rows), output)
+}
+
+fn main_simulate_command(usage: &str, plan: &CandidatePlan, config: &Config) -> anyhow::Result<()> {
+    let samples = csv::Reader::from_path(usage)?
+        .deserialize()
+        .collect::<Result<Vec<UsageSample>, _>>()?;
+    let report = simulate::simulate(&samples, plan)?;
+    match config.output_format() {
+        OutputFormat::Text => {
+            println!(
+                "{} hours, {} with unused commitment",
+                report.hours, report.underutilized_hours
+            );
+            println!(
+                "Utilization {}%, coverage {}%",
+                report.utilization_percent, report.coverage_percent
+            );
+            println!(
+                "{} USD committed and {} USD on-demand, {} USD instead of {}, saving {} ({}%)",
+                report.commitment_usd,
+                report.uncovered_usd,
+                report.total_usd,
+                report.on_demand_usd,
+                report.savings_usd,
+                report.savings_percent
+            );
+        }
+        format => output::print(format, &report),
+    }
+    Ok(())
+}
+
+async fn main_diff_command(
+    service: &str,
+    region: &str,
+    from: &str,
+    to: &str,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let from = pekora.fetch_pricing_version(service, region, from).await?;
+    let to = pekora.fetch_pricing_version(service, region, to).await?;
+    let mut offer_diff = diff::diff(&from, &to);
+    // Only EC2 on-demand SKUs have a history
+    if service == "AmazonEC2" {
+        let from_offer = OfferMark {
+            version: from.version.clone(),
+            publication_date: from.publication_date,
+        };
+        pekora
+            .history::<Ec2OnDemand>(&region.to_string())
+            .mark_restored(&mut offer_diff, &from_offer)?;
+    }
+    match config.output_format() {
+        OutputFormat::Text => {
+            println!(
+                "{} {} from {} to {}",
+                service, region, offer_diff.from_version, offer_diff.to_version
+            );
+            for sku in &offer_diff.added_skus {
+                if offer_diff.restored_skus.contains(sku) {
+                    println!("+ {} (restored)", sku);
+                } else {
+                    println!("+ {}", sku);
+                }
+            }
+            for sku in &offer_diff.removed_skus {
+                println!("- {}", sku);
+            }
+            for change in &offer_diff.changed {
+                let usd = |usd: Option<Decimal>| {
+                    usd.map_or("-".to_string(), |usd| usd.normalize().to_string())
+                };
+                println!(
+                    "~ {} {} -> {} per {}{}",
+                    change.rate_code,
+                    usd(change.from_usd),
+                    usd(change.to_usd),
+                    change.unit,
+                    change
+                        .change_percent
+                        .map(|percent| format!(" ({:+}%)", percent))
+                        .unwrap_or_default()
+                );
+            }
+            println!(
+                "{} added, {} removed, {} prices changed",
+                offer_diff.added_skus.len(),
+                offer_diff.removed_skus.len(),
+                offer_diff.changed.len()
+            );
+        }
+        format => output::print(format, &offer_diff),
+    }
+    Ok(())
+}
+
+/// EC2 pricing list of `region` with its on-demand, reserved and savings plan rates.
+async fn load_normalized_ec2_rows(
+    pekora: &Pekora,
+    region: String,
+) -> anyhow::Result<(PricingListResponse, Vec<NormalizedPriceRow>)> {
+    let on_demand = pekora.dataset::<Ec2OnDemand>(region.clone()).await?;
+    let response = pekora.fetch_pricing("AmazonEC2", &region).await?;
+    let compute = pekora.dataset::<ComputeSavingsPlan>(region.clone()).await?;
+    let ec2_instance = pekora.dataset::<Ec2InstanceSavingsPlan>(region).await?;
+    let region_names = pekora.region_names().await;
+    let mut rows = normalize::from_on_demand(on_demand.rows(), &region_names);
+    rows.extend(normalize::from_reserved(&response, &region_names));
+    rows.extend(normalize::from_savings_plans(
+        compute.rows(),
+        on_demand.rows(),
+        &region_names,
+    ));
+    rows.extend(normalize::from_savings_plans(
+        ec2_instance.rows(),
+        on_demand.rows(),
+        &region_names,
+    ));
+    Ok((response, rows))
+}
+
+/// EC2 prices of the configured regions under every purchase model, joined with instance specs.
+async fn load_enriched_ec2_rows(
+    launch_dates: &LaunchDates,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<Vec<EnrichedPriceRow>> {
+    let regions = config.regions_or_default();
+    let mut specs = pekora
+        .cacheable_builder()
+        .build(Ec2Client::new_cacheable_arc(Some(config.aws_sdk_config().await)).await)
+        .load(&regions)
+        .await?
+        .result;
+    launch_dates.apply(&mut specs);
+    let mut rows = Vec::new();
+    for region in regions {
+        rows.extend(load_normalized_ec2_rows(pekora, region).await?.1);
+    }
+    Ok(transform::aws::instance_specs::join(&rows, &specs))
+}
+
+async fn main_ec2_offerings_command(
+    location_type: OfferingLocation,
+    check_prices: bool,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let region = config.first_region();
+    let location_type = match location_type {
+        OfferingLocation::Az => ec2::LocationType::AvailabilityZoneId,
+        OfferingLocation::Region => ec2::LocationType::Region,
+    };
+    let ec2_client = Ec2Client::new(
+        Some(config.aws_sdk_config().await),
+        Some(vec![region.clone()]),
+    )
+    .await;
+    let offerings = ec2_client
+        .describe_instance_type_offerings(location_type)
+        .await?
+        .into_complete()?
+        .remove(&region)
+        .unwrap_or_default();
+
+    if check_prices {
+        let on_demand = pekora.dataset::<Ec2OnDemand>(region.clone()).await?;
+        let rows = normalize::from_on_demand(on_demand.rows(), &*pekora.region_names().await);
+        let offered = HashMap::from([(region, offerings.into_keys().collect())]);
+        let unavailable = availability::priced_but_unavailable(&rows, &offered);
+        match config.output_format() {
+            OutputFormat::Text => {
+                for row in &unavailable {
+                    println!(
+                        "{}\t{}\t{} priced rows",
+                        row.region, row.instance_type, row.priced_rows
+                    );
+                }
+            }
+            format => output::print(format, &unavailable),
+        }
+        return Ok(());
+    }
+
+    match config.output_format() {
+        OutputFormat::Text => {
+            for (instance_type, locations) in &offerings {
+                println!(
+                    "{}\t{}",
+                    instance_type,
+                    locations.iter().cloned().collect::<Vec<_>>().join(",")
+                );
+            }
+        }
+        format => output::print(format, &offerings),
+    }
+    Ok(())
+}
+
+async fn main_unit_costs_command(
+    launch_dates: Option<&str>,
+    newest_under: Option<Decimal>,
+    output: Option<&str>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let mut dates = LaunchDates::bundled();
+    if let Some(path) = launch_dates {
+        dates.extend(LaunchDates::from_csv(std::fs::File::open(path)?)?);
+    }
+    let rows = load_enriched_ec2_rows(&dates, config, pekora).await?;
+    match newest_under {
+        Some(max_usd_per_hour) => output::save(
+            config.output_format(),
+            &launch_dates::newest_under(&rows, max_usd_per_hour),
+            output,
+        ),
+        None => output::save(config.output_format(), &rows, output),
+    }
+}
+
+async fn main_gpu_prices_command(
+    output: Option<&str>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let rows = load_enriched_ec2_rows(&LaunchDates::bundled(), config, pekora).await?;
+    write_recommendations(&gpu::gpu_prices(&rows), output)
+}
+
+async fn main_elasticache_reserved_command(
+    node_type: Option<&str>,
+    output: Option<&str>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let regions = config.regions_or_default();
+    let mut rows = Vec::new();
+    for region in regions {
+        let response = pekora.fetch_elasticache_pricing(&region).await?;
+        rows.extend(
+            elasticache::compare_reserved(&response, &region)
+                .into_iter()
+                .filter(|row| node_type.is_none_or(|node_type| row.node_type == node_type)),
+        );
+    }
+    write_recommendations(&rows, output)
+}
+
+async fn main_elasticache_memory_command(
+    engine: &str,
+    output: Option<&str>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let engine = CacheEngine::parse(engine)
+        .ok_or_else(|| anyhow::anyhow!("Expected redis or memcached, got {}", engine))?;
+    let parameter_group_family = match engine {
+        CacheEngine::Redis => REDIS_PARAMETER_GROUP_FAMILY,
+        CacheEngine::Memcached => MEMCACHED_PARAMETER_GROUP_FAMILY,
+    };
+    let parameters = pekora
+        .cacheable_builder()
+        .build(ElasticacheClient::new_cacheable_arc(Some(config.aws_sdk_config().await)).await)
+        .load(&parameter_group_family.to_string())
+        .await?
+        .result;
+    let regions = config.regions_or_default();
+    let mut rows = Vec::new();
+    for region in regions {
+        let response = pekora.fetch_elasticache_pricing(&region).await?;
+        rows.extend(elasticache::usable_memory_prices(
+            &response,
+            &region,
+            engine,
+            &parameters,
+        ));
+    }
+    write_recommendations(&rows, output)
+}
+
+async fn main_compare_architectures_command(
+    performance: &[String],
+    output: Option<&str>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let performance_ratios = performance
+        .iter()
+        .map(|ratio| {
+            let (family, ratio) = ratio
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Expected <family>=<ratio>, got {}", ratio))?;
+            Ok((family.to_string(), ratio.parse::<Decimal>()?))
+        })
+        .collect::<anyhow::Result<HashMap<_, _>>>()?;
+    let rows = load_enriched_ec2_rows(&LaunchDates::bundled(), config, pekora).await?;
+    let comparisons = architecture::compare(&rows, &performance_ratios);
+    match config.output_format() {
+        format @ (OutputFormat::Xlsx | OutputFormat::Md) => {
+            output::save(format, &comparisons, output)
+        }
+        _ => write_recommendations(&comparisons, output),
+    }
+}
+
+async fn main_aurora_command(
+    spec: &AuroraClusterSpec,
+    storage_config: Option<AuroraStorageConfig>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let region = config.first_region();
+    let response = pekora.fetch_rds_pricing(&region).await?;
+    let storage_configs = match storage_config {
+        Some(storage_config) => vec![storage_config],
+        None => vec![
+            AuroraStorageConfig::Standard,
+            AuroraStorageConfig::IoOptimized,
+        ],
+    };
+    let costs = storage_configs
+        .into_iter()
+        .map(|storage_config| rds_storage::estimate_aurora_cluster(&response, spec, storage_config))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    match config.output_format() {
+        OutputFormat::Text => {
+            for cost in &costs {
+                println!(
+                    "{:<13} {} x{}: {:>12} instances {:>12} storage {:>12} I/O {:>12} monthly",
+                    format!("{:?}", cost.storage_config),
+                    cost.instance_class,
+                    cost.instances,
+                    cost.instance_usd,
+                    cost.storage_usd,
+                    cost.io_usd,
+                    cost.monthly_usd
+                );
+            }
+        }
+        format => output::print(format, &costs),
+    }
+    Ok(())
+}
+
+async fn main_rds_orderable_command(
+    engines: &[String],
+    options: Option<&str>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let region = config.first_region();
+    let options = match options {
+        Some(path) => {
+            let response: OrderableDbInstanceOptionsResponse =
+                serde_json::from_str(&std::fs::read_to_string(path)?)?;
+            response.orderable_db_instance_options
+        }
+        None => RdsClient::new(Some(config.aws_sdk_config().await), vec![region.clone()])
+            .await
+            .describe_orderable_db_instance_options(engines)
+            .await
+            .into_complete()?
+            .into_values()
+            .flatten()
+            .collect(),
+    };
+    let response = pekora.fetch_rds_pricing(&region).await?;
+    let rows = orderable::orderable_instance_prices(&response, &options);
+    match config.output_format() {
+        OutputFormat::Text => {
+            for row in &rows {
+                println!(
+                    "{:<18} {:<16} {:<10} {:>12} USD per hour in {}",
+                    row.database_engine,
+                    row.instance_class,
+                    row.deployment_option,
+                    row.on_demand_usd_per_hour.normalize(),
+                    row.availability_zones.join(", ")
+                );
+            }
+        }
+        format => output::print(format, &rows),
+    }
+    Ok(())
+}
+
+async fn main_rds_reserved_check_command(
+    offerings: Option<&str>,
+    output: Option<&str>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let region = config.first_region();
+    let offerings = match offerings {
+        Some(path) => {
+            let response: ReservedDbInstancesOfferingsResponse =
+                serde_json::from_str(&std::fs::read_to_string(path)?)?;
+            response.reserved_db_instances_offerings
+        }
+        None => RdsClient::new(Some(config.aws_sdk_config().await), vec![region.clone()])
+            .await
+            .describe_reserved_db_instances_offerings()
+            .await
+            .into_complete()?
+            .into_values()
+            .flatten()
+            .collect(),
+    };
+    let response = pekora.fetch_rds_pricing(&region).await?;
+    write_recommendations(&rds_reserved::cross_check(&response, &offerings), output)
+}
+
+/// Where `node-prices` learns the node types usable in the region from.
+enum NodeTypeSource<'a> {
+    Orderable(&'a str),
+    DocDbOrderable,
+    RedshiftCluster(&'a str),
+    OpenSearchEngine(&'a str),
+}
+
+impl<'a> NodeTypeSource<'a> {
+    fn from_args(
+        service: NodeService,
+        orderable: Option<&'a str>,
+        docdb_orderable: bool,
+        redshift_cluster: Option<&'a str>,
+        engine_version: Option<&'a str>,
+    ) -> anyhow::Result<Option<Self>> {
+        if docdb_orderable {
+            if !matches!(service, NodeService::Docdb) {
+                anyhow::bail!("--docdb-orderable only applies to --service docdb");
+            }
+            return Ok(Some(Self::DocDbOrderable));
+        }
+        Ok(
+            match (service, orderable, redshift_cluster, engine_version) {
+                (_, Some(orderable), None, None) => Some(Self::Orderable(orderable)),
+                (NodeService::Redshift, None, Some(cluster), None) => {
+                    Some(Self::RedshiftCluster(cluster))
+                }
+                (NodeService::Opensearch, None, None, Some(engine_version)) => {
+                    Some(Self::OpenSearchEngine(engine_version))
+                }
+                (_, None, Some(_), None) => {
+                    anyhow::bail!("--redshift-cluster only applies to --service redshift")
+                }
+                (_, None, None, Some(_)) => {
+                    anyhow::bail!("--engine-version only applies to --service opensearch")
+                }
+                (_, None, None, None) => None,
+                _ => anyhow::bail!(
+                    "At most one of --orderable, --redshift-cluster or --engine-version is allowed"
+                ),
+            },
+        )
+    }
+
+    async fn load(&self, region: &str, config: &Config) -> anyhow::Result<BTreeSet<String>> {
+        let regions = vec![region.to_string()];
+        Ok(match self {
+            Self::Orderable(path) => {
+                let options: OrderableDbInstanceOptionsResponse =
+                    serde_json::from_str(&std::fs::read_to_string(path)?)?;
+                options
+                    .orderable_db_instance_options
+                    .into_iter()
+                    .map(|option| option.db_instance_class)
+                    .collect()
+            }
+            Self::DocDbOrderable => DocDbClient::new(Some(config.aws_sdk_config().await))
+                .await
+                .describe_orderable_db_instance_options(region)
+                .await?
+                .into_iter()
+                .map(|option| option.db_instance_class)
+                .collect(),
+            Self::RedshiftCluster(cluster) => {
+                RedshiftClient::new(Some(config.aws_sdk_config().await), regions)
+                    .await
+                    .describe_node_configuration_options(&NodeConfigurationAction::ResizeCluster(
+                        cluster.to_string(),
+                    ))
+                    .await
+                    .into_complete()?
+                    .into_values()
+                    .flatten()
+                    .map(|option| option.node_type)
+                    .collect()
+            }
+            Self::OpenSearchEngine(engine_version) => {
+                OpenSearchClient::new(Some(config.aws_sdk_config().await), regions)
+                    .await
+                    .list_instance_type_details(engine_version)
+                    .await
+                    .into_complete()?
+                    .into_values()
+                    .flatten()
+                    .map(|details| details.instance_type)
+                    .collect()
+            }
+        })
+    }
+}
+
+async fn main_node_prices_command(
+    service: NodeService,
+    node_types: Option<&NodeTypeSource<'_>>,
+    output: Option<&str>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let region = config.first_region();
+    let mut rows = match service {
+        NodeService::Redshift => {
+            node_pricing::redshift_node_prices(&pekora.fetch_redshift_pricing(&region).await?)
+        }
+        NodeService::Opensearch => {
+            node_pricing::opensearch_node_prices(&pekora.fetch_opensearch_pricing(&region).await?)
+        }
+        NodeService::Memorydb => {
+            node_pricing::memorydb_node_prices(&pekora.fetch_memorydb_pricing(&region).await?)
+        }
+        NodeService::Docdb => {
+            node_pricing::docdb_node_prices(&pekora.fetch_docdb_pricing(&region).await?)
+        }
+    };
+    if let Some(node_types) = node_types {
+        let node_types = node_types.load(&region, config).await?;
+        rows.retain(|row| node_types.contains(&row.node_type));
+    }
+    write_recommendations(&rows, output)
+}
+
+async fn main_dedicated_hosts_command(
+    family: Option<&str>,
+    reservations: bool,
+    output: Option<&str>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let region = config.first_region();
+    let response = pekora.fetch_pricing("AmazonEC2", &region).await?;
+    let mut hosts = dedicated_host::dedicated_hosts(&response, &*pekora.region_names().await);
+    if let Some(family) = family {
+        hosts.retain(|host| host.host_family == family);
+    }
+    let offerings = if reservations {
+        let ec2_client = Ec2Client::new(
+            Some(config.aws_sdk_config().await),
+            Some(vec![region.clone()]),
+        )
+        .await;
+        ec2_client
+            .describe_host_reservation_offerings()
+            .await?
+            .into_complete()?
+            .remove(&region)
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    let on_demand = pekora.dataset::<Ec2OnDemand>(region.clone()).await?;
+    write_recommendations(
+        &dedicated_host::slot_costs(&hosts, &region, &offerings, on_demand.rows()),
+        output,
+    )
+}
+
+async fn main_rightsizing_command(
+    recommendations: Option<&str>,
+    output: Option<&str>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let regions = config.regions_or_default();
+    let response: Ec2InstanceRecommendationsResponse = match recommendations {
+        Some(path) => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+        None => Ec2InstanceRecommendationsResponse {
+            instance_recommendations: ComputeOptimizerClient::new(
+                Some(config.aws_sdk_config().await),
+                regions.clone(),
+            )
+            .await
+            .get_ec2_instance_recommendations()
+            .await
+            .into_complete()?
+            .into_values()
+            .flatten()
+            .collect(),
+            next_token: None,
+        },
+    };
+    let mut rows = Vec::new();
+    for region in regions {
+        if !response
+            .instance_recommendations
+            .iter()
+            .any(|recommendation| recommendation.region() == Some(region.as_str()))
+        {
+            continue;
+        }
+        let on_demand = pekora.dataset::<Ec2OnDemand>(region.clone()).await?;
+        rows.extend(rightsizing::rightsizing_savings(
+            &response.instance_recommendations,
+            &region,
+            on_demand.rows(),
+        ));
+    }
+    write_recommendations(&rows, output)
+}
+
+async fn main_savings_plan_check_command(
+    rates: Option<&str>,
+    output: Option<&str>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let region = config.first_region();
+    let rates: SavingsPlansOfferingRatesResponse = match rates {
+        Some(path) => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+        None => {
+            let query = SavingsPlansRatesQuery {
+                plan_type: SavingsPlanType::Compute,
+                region: region.clone(),
+            };
+            pekora
+                .cacheable_builder()
+                .build(
+                    SavingsPlansClient::new_rates_cacheable_arc(Some(
+                        config.aws_sdk_config().await,
+                    ))
+                    .await,
+                )
+                .load(&query)
+                .await?
+                .result
+        }
+    };
+    rates.check_known(config.strict.unwrap_or(false))?;
+    let rates = rates
+        .search_results
+        .into_iter()
+        .filter(|rate| rate.property("region").is_none_or(|r| r == region))
+        .collect::<Vec<_>>();
+    let bulk = pekora.dataset::<ComputeSavingsPlan>(region.clone()).await?;
+    write_recommendations(
+        &savings_plan_rates::cross_check(bulk.rows(), &rates),
+        output,
+    )
+}
+
+async fn main_rds_storage_command(
+    spec: &RdsStorageSpec,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let region = config.first_region();
+    let response = pekora.fetch_rds_pricing(&region).await?;
+    let cost = rds_storage::price_rds_storage(&response, spec)?;
+    match config.output_format() {
+        OutputFormat::Text => {
+            println!(
+                "{} {} GB in {}: {} USD monthly",
+                cost.volume_type, spec.size_gb, region, cost.monthly_usd
+            );
+            println!("  storage {:>12}", cost.storage_usd);
+            println!("  IOPS    {:>12}", cost.iops_usd);
+        }
+        format => output::print(format, &cost),
+    }
+    Ok(())
+}
+
+async fn main_volume_command(
+    spec: &VolumeSpec,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let region = config.first_region();
+    let response = pekora.fetch_ebs_pricing(&region).await?;
+    let cost = ebs::price_volume(&ebs::pivot(&response), spec)?;
+    match config.output_format() {
+        OutputFormat::Text => {
+            println!(
+                "{} {} GiB in {}: {} USD monthly",
+                cost.volume_type, spec.size_gib, region, cost.monthly_usd
+            );
+            println!("  storage    {:>12}", cost.storage_usd);
+            println!("  IOPS       {:>12}", cost.iops_usd);
+            println!("  throughput {:>12}", cost.throughput_usd);
+        }
+        format => output::print(format, &cost),
+    }
+    Ok(())
+}
+
+async fn main_transfer_command(
+    destination: &TransferDestination,
+    gb: Decimal,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let region = config.first_region();
+    let response = pekora.fetch_data_transfer_pricing(&region).await?;
+    let rates = data_transfer::pivot(&response, &*pekora.region_names().await);
+    let cost = data_transfer::transfer_cost(&rates, &region, destination, gb)?;
+    match config.output_format() {
+        OutputFormat::Text => println!(
+            "{} GB from {} to {}: {} USD ({} USD per GB)",
+            cost.gb,
+            cost.from_region,
+            cost.to,
+            cost.usd,
+            cost.blended_usd_per_gb.unwrap_or_default().normalize()
+        ),
+        format => output::print(format, &cost),
+    }
+    Ok(())
+}
+
+async fn main_cloudwatch_command(
+    usage: &CloudWatchUsage,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let region = config.first_region();
+    let response = pekora.fetch_cloudwatch_pricing(&region).await?;
+    let estimate = cloudwatch::estimate(&cloudwatch::pivot(&response), &region, usage)?;
+    match config.output_format() {
+        OutputFormat::Text => {
+            for line in &estimate.lines {
+                println!(
+                    "{:<16} {:>12} {:>10} USD",
+                    format!("{:?}", line.meter),
+                    line.quantity,
+                    line.monthly_usd
+                );
+            }
+            println!(
+                "{} USD per month in {}",
+                estimate.monthly_usd, estimate.region
+            );
+        }
+        format => output::print(format, &estimate),
+    }
+    Ok(())
+}
+
+async fn main_estimate_command(
+    workload: &WorkloadSpec,
+    network: &[NetworkComponent],
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let response = pekora.fetch_pricing("AmazonEC2", &workload.region).await?;
+    let compute = pekora
+        .dataset::<ComputeSavingsPlan>(workload.region.clone())
+        .await?;
+    let ec2_instance = pekora
+        .dataset::<Ec2InstanceSavingsPlan>(workload.region.clone())
+        .await?;
+    let mut estimate = estimate::estimate(
+        workload,
+        &response,
+        compute.rows().iter().chain(ec2_instance.rows()),
+    )?;
+    estimate.network =
+        price_network(network, &workload.region, workload.hours_per_month, pekora).await?;
+    match config.output_format() {
+        OutputFormat::Text => {
+            println!(
+                "{} ({}) at {} USD per hour on-demand",
+                estimate.instance_type,
+                estimate.sku,
+                estimate.on_demand_usd_per_hour.normalize()
+            );
+            for cost in &estimate.costs {
+                println!(
+                    "{:<26} {:>4} {:<16} {:>12} upfront {:>12} monthly {:>7}%",
+                    cost.purchase_model.as_str(),
+                    cost.term.as_ref().map_or("", |term| term.as_str()),
+                    cost.purchase_option
+                        .as_ref()
+                        .map_or("", |option| option.as_str()),
+                    cost.upfront_usd,
+                    cost.monthly_usd,
+                    cost.savings_percent
+                );
+            }
+            for line in &estimate.network {
+                println!(
+                    "{:<26} x{:<4} {:>10} GB {:>12} hourly {:>12} processing {:>12} monthly",
+                    format!("{:?}", line.resource),
+                    line.count,
+                    line.processed_gb,
+                    line.hourly_usd,
+                    line.processing_usd,
+                    line.monthly_usd
+                );
+            }
+        }
+        format @ (OutputFormat::Xlsx | OutputFormat::Md) => {
+            output::print(format, &estimate_lines(&estimate)?)
+        }
+        format => output::print(format, &estimate),
+    }
+    Ok(())
+}
+
+/// Costs of `estimate` as one table, network resources as on-demand lines after the instances.
+fn estimate_lines(estimate: &estimate::CostEstimate) -> anyhow::Result<Vec<serde_json::Value>> {
+    let mut lines = Vec::with_capacity(estimate.costs.len() + estimate.network.len());
+    for cost in &estimate.costs {
+        let mut line = serde_json::to_value(cost)?;
+        line["instance_type"] = estimate.instance_type.clone().into();
+        line["sku"] = estimate.sku.clone().into();
+        lines.push(line);
+    }
+    for network_line in &estimate.network {
+        let mut line = serde_json::to_value(network_line)?;
+        line["purchase_model"] = PurchaseModel::OnDemand.as_str().into();
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+/// Prices `components` in `region`, loading only the pricing lists they need.
+async fn price_network(
+    components: &[NetworkComponent],
+    region: &str,
+    hours_per_month: Decimal,
+    pekora: &Pekora,
+) -> anyhow::Result<Vec<NetworkCostLine>> {
+    let service_codes: BTreeSet<&str> = components
+        .iter()
+        .map(|component| component.resource.service_code())
+        .collect();
+    let mut rates = Vec::new();
+    for service_code in service_codes {
+        let response = match service_code {
+            "AmazonEC2" => pekora.fetch_nat_gateway_pricing(region).await?,
+            "AmazonVPC" => pekora.fetch_vpc_pricing(region).await?,
+            _ => pekora.fetch_elb_pricing(region).await?,
+        };
+        rates.extend(network::pivot(&response));
+    }
+    components
+        .iter()
+        .map(|component| network::price_component(&rates, component, hours_per_month))
+        .collect()
+}
+
+async fn main_optimize_command(
+    usage: &str,
+    commitment: &Commitment,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let usage = csv::Reader::from_path(usage)?
+        .deserialize()
+        .collect::<Result<Vec<UsageLine>, _>>()?;
+    let regions: BTreeSet<&String> = usage.iter().map(|line| &line.region).collect();
+    let mut loaded = Vec::new();
+    for region in regions {
+        let response = pekora.fetch_pricing("AmazonEC2", region).await?;
+        let compute = pekora.dataset::<ComputeSavingsPlan>(region.clone()).await?;
+        let ec2_instance = pekora
+            .dataset::<Ec2InstanceSavingsPlan>(region.clone())
+            .await?;
+        let savings_plans: Vec<_> = compute
+            .rows()
+            .iter()
+            .chain(ec2_instance.rows())
+            .cloned()
+            .collect();
+        loaded.push((region.clone(), response, savings_plans));
+    }
+    let rates = loaded
+        .iter()
+        .map(|(region, response, savings_plans)| {
+            (
+                region.clone(),
+                RegionRates {
+                    pricing: response,
+                    savings_plans,
+                },
+            )
+        })
+        .collect();
+    let plan = optimize::optimize(&usage, commitment, &rates);
+    match config.output_format() {
+        OutputFormat::Text => {
+            for purchase in &plan.purchases {
+                println!(
+                    "{:<26} {:<16} {:<14} {:>6} x {:>10}/h {:>12} upfront {:>12} monthly, \
+                     break-even {}",
+                    purchase.purchase_model.as_str(),
+                    purchase.region,
+                    purchase.instance_type,
+                    purchase.quantity,
+                    purchase.hourly_commitment_usd,
+                    purchase.upfront_usd,
+                    purchase.monthly_usd,
+                    purchase
+                        .break_even_months
+                        .map_or("never".to_string(), |months| format!("{} months", months))
+                );
+            }
+            println!(
+                "Savings plan commitment {} USD per hour, {} USD upfront",
+                plan.savings_plan_hourly_commitment_usd, plan.upfront_usd
+            );
+            println!(
+                "{} USD monthly instead of {} on-demand, saving {} ({}%), break-even {}",
+                plan.planned_monthly_usd,
+                plan.on_demand_monthly_usd,
+                plan.monthly_savings_usd,
+                plan.savings_percent,
+                plan.break_even_months
+                    .map_or("never".to_string(), |months| format!("{} months", months))
+            );
+        }
+        format => output::print(format, &plan),
+    }
+    Ok(())
+}
+
+/// Runs a pipeline, then uploads its exports if it has upload actions and notifies the
+/// configured sinks if it has a notify action, unless its export was already up to date.
+async fn main_run_command(
+    name: &str,
+    pipeline_config: &pipeline::PipelineConfig,
+    force: bool,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<pipeline::PipelineReport> {
+    let report = pipeline::run(pekora, pipeline_config, force).await?;
+    if report.skipped {
+        return Ok(report);
+    }
+    for action in &pipeline_config.actions {
+        if let pipeline::PipelineAction::Upload(destination) = action {
+            let uploader = S3Uploader::new(&config.aws_sdk_config().await, destination.clone())?;
+            let partition = s3::Partition {
+                service_code: pipeline_config.source.service_code(),
+                region: pipeline_config.source.region(),
+                offer_version: &report.offer_version,
+            };
+            for path in pipeline_config.exported_paths() {
+                uploader.upload(partition, Path::new(path)).await?;
+            }
+        }
+    }
+    if !pipeline_config
+        .actions
+        .contains(&pipeline::PipelineAction::Notify)
+    {
+        return Ok(report);
+    }
+    let dispatcher = NotificationDispatcher::from_config(
+        reqwest::Client::new(),
+        &config.notifications,
+        Some(&config.aws_sdk_config().await),
+    )?;
+    let errors = dispatcher
+        .dispatch(&Notification {
+            kind: NotificationKind::PipelineRun,
+            title: format!("Pipeline {} refreshed", name),
+            body: report.to_string(),
+            details: Some(serde_json::to_value(&report)?),
+        })
+        .await;
+    for (sink, e) in errors {
+        eprintln!("Notification via {} failed: {}", sink, e);
+    }
+    Ok(report)
+}
+
+/// Refreshes current offers into the cache, notifying of price changes in offers whose version
+/// changed since the last cycle, then runs every configured pipeline. Pipelines whose export is
+/// up to date with the offer version are skipped.
+async fn main_daemon_cycle(
+    concurrency: usize,
+    services: Option<Vec<String>>,
+    versions: &mut daemon::OfferVersions,
+    dispatcher: &NotificationDispatcher,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let report = Crawler::new(pekora.clone(), Some(concurrency), services)
+        .run()
+        .await?;
+    eprintln!("{}", report);
+    let mut failures = report
+        .failures()
+        .map(|outcome| outcome.target.clone())
+        .collect::<Vec<_>>();
+    let changes = versions.update(
+        report
+            .outcomes
+            .iter()
+            .filter(|outcome| outcome.result.is_ok())
+            .filter_map(|outcome| outcome.offer.as_ref()),
+    );
+    if !dispatcher.is_empty() {
+        for change in changes {
+            if let Err(e) = main_notify_price_change(&change, dispatcher, pekora).await {
+                eprintln!(
+                    "Price change notification of {} {} failed: {}",
+                    change.service_code, change.region, e
+                );
+                failures.push(format!(
+                    "price changes of {} {}",
+                    change.service_code, change.region
+                ));
+            }
+        }
+    }
+    for (name, pipeline_config) in &config.pipelines {
+        match main_run_command(name, pipeline_config, false, config, pekora).await {
+            Ok(report) => eprintln!("{}", report),
+            Err(e) => {
+                eprintln!("Pipeline {} failed: {}", name, e);
+                failures.push(format!("pipeline {}", name));
+            }
+        }
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Failed to refresh {}", failures.join(", ")))
+    }
+}
+
+/// Diffs the offer versions of `change`, notifying if any SKU or price changed.
+async fn main_notify_price_change(
+    change: &daemon::VersionChange,
+    dispatcher: &NotificationDispatcher,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let from = pekora
+        .fetch_pricing_version(&change.service_code, &change.region, &change.from_version)
+        .await?;
+    let to = pekora
+        .fetch_pricing_version(&change.service_code, &change.region, &change.to_version)
+        .await?;
+    let offer_diff = diff::diff(&from, &to);
+    if offer_diff.is_empty() {
+        return Ok(());
+    }
+    let notification = daemon::PriceChangeSummary::new(change, &offer_diff).notification()?;
+    for (sink, e) in dispatcher.dispatch(&notification).await {
+        eprintln!("Notification via {} failed: {}", sink, e);
+    }
+    Ok(())
+}
+
+async fn main_daemon_command(
+    interval: std::time::Duration,
+    address: std::net::SocketAddr,
+    concurrency: usize,
+    services: Option<Vec<String>>,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let status = daemon::SharedStatus::new(interval);
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    eprintln!("Serving health checks on http://{}", address);
+    let health = axum::serve(listener, daemon::router(status.clone()));
+    let dispatcher = NotificationDispatcher::from_config(
+        reqwest::Client::new(),
+        &config.notifications,
+        Some(&config.aws_sdk_config().await),
+    )?;
+    let versions_path = daemon::OfferVersions::path(Path::new(pekora.cache_directory()));
+    let cycles = async {
+        let mut versions = daemon::OfferVersions::load(&versions_path);
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            status.start_cycle();
+            let result = main_daemon_cycle(
+                concurrency,
+                services.clone(),
+                &mut versions,
+                &dispatcher,
+                config,
+                pekora,
+            )
+            .await;
+            if let Err(e) = versions.save(&versions_path) {
+                eprintln!("Failed to save offer versions: {}", e);
+            }
+            if let Err(e) = &result {
+                eprintln!("{}", e);
+            }
+            status.finish_cycle(&result);
+        }
+    };
+    tokio::select! {
+        result = health => result?,
+        _ = cycles => {}
+        result = tokio::signal::ctrl_c() => result?,
+    }
+    Ok(())
+}
+
+fn write_recommendations<R: serde::Serialize>(
+    recommendations: &[R],
+    output: Option<&str>,
+) -> anyhow::Result<()> {
+    match output {
+        Some(path) => pipeline::write_atomically(Path::new(path), |writer| {
+            recommendation::write_csv(recommendations, writer)
+        }),
+        None => recommendation::write_csv(recommendations, std::io::stdout().lock()),
+    }
+}
+
+/// Bulk pricing files and EC2 instance types, printed as text or JSON.
+async fn main_fetch_command(
+    cmd: FetchCommands,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<bool> {
+    let cacheable_builder = pekora.cacheable_builder();
+    match cmd {
+        FetchCommands::Services => {
+            let response = cacheable_builder
+                .build(pekora.clients().service_index())
+                .load(&())
+                .await?
+                .result;
+            match config.output_format() {
+                OutputFormat::Text => {
+                    let mut services = response.offers.keys().collect::<Vec<_>>();
+                    services.sort();
+                    for service in services {
+                        println!("{}", service);
+                    }
+                }
+                format => output::print(format, &response),
+            }
+        }
+        FetchCommands::RegionNames => {
+            let long_names = cacheable_builder
+                .build(SsmClient::new_cacheable_arc(Some(config.aws_sdk_config().await)).await)
+                .load(&())
+                .await?
+                .result;
+            let mut region_names = RegionNames::bundled();
+            region_names.refresh_long_names(&long_names);
+            match config.output_format() {
+                OutputFormat::Text => {
+                    for (code, name) in region_names.entries() {
+                        println!("{}\t{}", code, name);
+                    }
+                }
+                format => {
+                    let entries = region_names
+                        .entries()
+                        .into_iter()
+                        .map(|(code, name)| {
+                            BTreeMap::from([("region_code", code), ("location", name)])
+                        })
+                        .collect::<Vec<_>>();
+                    output::print(format, &entries)
+                }
+            }
+        }
+        FetchCommands::SavingsPlansOfferings { plan_type } => {
+            let query = SavingsPlansOfferingsQuery {
+                plan_type: SavingsPlanType::parse(&plan_type),
+                region: config.first_region(),
+            };
+            let response = cacheable_builder
+                .build(
+                    SavingsPlansClient::new_offerings_cacheable_arc(Some(
+                        config.aws_sdk_config().await,
+                    ))
+                    .await,
+                )
+                .load(&query)
+                .await?
+                .result;
+            match config.output_format() {
+                OutputFormat::Text => {
+                    for offering in &response.search_results {
+                        println!(
+                            "{} {} {} {}s {}",
+                            offering.offering_id,
+                            offering.plan_type.as_str(),
+                            offering.payment_option.as_str(),
+                            offering.duration_seconds,
+                            offering.description.as_deref().unwrap_or_default()
+                        );
+                    }
+                }
+                format => output::print(format, &response),
+            }
+        }
+        FetchCommands::Regions { service } => {
+            let response = cacheable_builder
+                .build(pekora.clients().region_index())
+                .load(&service)
+                .await?
+                .result;
+            match config.output_format() {
+                OutputFormat::Text => {
+                    let mut regions = response.regions.values().collect::<Vec<_>>();
+                    regions.sort_by(|a, b| a.region_code.cmp(&b.region_code));
+                    for region in regions {
+                        println!(
+                            "{}\t{}",
+                            region.region_code, region.current_version_url.offer_version
+                        );
+                    }
+                }
+                format => output::print(format, &response),
+            }
+        }
+        FetchCommands::Pricing { service, version } => {
+            let region = config.first_region();
+            let response = pekora
+                .fetch_pricing_version(&service, &region, &version)
+                .await?;
+            match config.output_format() {
+                OutputFormat::Text => {
+                    println!(
+                        "{} {} version {} published {}",
+                        service, region, response.version, response.publication_date
+                    );
+                    let mut families: BTreeMap<&str, usize> = BTreeMap::new();
+                    for product in response.products.values() {
+                        *families.entry(product.product_family.as_str()).or_default() += 1;
+                    }
+                    for (family, products) in families {
+                        println!("{}\t{} products", family, products);
+                    }
+                    println!(
+                        "{} products, {} with on-demand terms, {} with reserved terms",
+                        response.products.len(),
+                        response.terms.on_demand.len(),
+                        response.terms.reserved.len()
+                    );
+                }
+                format => output::print(format, &response),
+            }
+        }
+        FetchCommands::SavingsPlans {
+            service,
+            version,
+            location_types,
+        } => {
+            let response = cacheable_builder
+                .build(pekora.clients().savings_plan_list())
+                .load(&PriceBulkSavingsPlan {
+                    region: config.first_region(),
+                    service_code: service,
+                    offer_version: version,
+                    filename: "index.json".to_string(),
+                })
+                .await?
+                .result;
+            let location_filter = if location_types.is_empty() {
+                LocationFilter::default()
+            } else {
+                LocationFilter::new(location_types.iter().copied())
+            };
+            let rates = transform::aws::savings_plan::pivot(response, &location_filter)?;
+            match config.output_format() {
+                OutputFormat::Text => {
+                    for rate in &rates {
+                        let attributes = &rate.savings_plan_attributes;
+                        println!(
+                            "{}\t{}\t{}\t{}\t{}\t{}\t{} {}",
+                            rate.savings_plan_sku,
+                            attributes.product_family.as_str(),
+                            attributes.purchase_term.as_str(),
+                            attributes.purchase_option.as_str(),
+                            rate.term_rate.discounted_usage_type,
+                            rate.term_rate.discounted_operation,
+                            rate.term_rate.discounted_rate.price.raw(),
+                            rate.term_rate.discounted_rate.currency.as_str()
+                        );
+                    }
+                }
+                format => output::print(format, &rates),
+            }
+        }
+        FetchCommands::Ec2InstanceTypes => {
+            let aws_sdk_config = config.aws_sdk_config().await;
+            let regions = match &config.regions {
+                Some(regions) => regions.clone(),
+                None => Ec2Client::new(Some(aws_sdk_config.clone()), None)
+                    .await
+                    .describe_regions()
+                    .await?
+                    .to_vec(),
+            };
+            let specs = cacheable_builder
+                .build(Ec2Client::new_cacheable_arc(Some(aws_sdk_config)).await)
+                .load(&regions)
+                .await?
+                .result;
+            let mut specs = specs.into_values().collect::<Vec<_>>();
+            specs.sort_by(|a, b| a.instance_type.cmp(&b.instance_type));
+            match config.output_format() {
+                OutputFormat::Text => {
+                    for spec in &specs {
+                        println!(
+                            "{}\t{}\t{} vCPUs\t{} GiB\t{} GPUs",
+                            spec.instance_type,
+                            spec.architecture.as_deref().unwrap_or("-"),
+                            spec.vcpus
+                                .map_or("-".to_string(), |vcpus| vcpus.to_string()),
+                            spec.memory_gib
+                                .map_or("-".to_string(), |memory| memory.normalize().to_string()),
+                            spec.gpus.unwrap_or(0)
+                        );
+                    }
+                }
+                format => output::print(format, &specs),
+            }
+        }
+        FetchCommands::Ec2Offerings {
+            location_type,
+            check_prices,
+        } => {
+            main_ec2_offerings_command(location_type, check_prices, config, pekora).await?;
+        }
+        FetchCommands::All {
+            concurrency,
+            services,
+        } => {
+            let services = if services.is_empty() {
+                None
+            } else {
+                Some(services)
+            };
+            let report = Crawler::new(pekora.clone(), Some(concurrency), services)
+                .run()
+                .await?;
+            match config.output_format() {
+                OutputFormat::Text => println!("{}", report),
+                format => output::print(format, &report),
+            }
+            if report.failures().next().is_some() {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+async fn main_test_command(
+    cmd: &TestCommands,
+    config: &Config,
+    pekora: &Pekora,
+) -> anyhow::Result<()> {
+    let format = config.output_format();
+    match cmd {
+        TestCommands::SpotAdvisor => {
+            let region = config.first_region();
+            let advisor = pekora.fetch_spot_advisor().await?;
+            let on_demand = pekora.dataset::<Ec2OnDemand>(region.clone()).await?;
+            let rates = transform::aws::spot::join(&advisor, &region, on_demand.rows());
+            output::print(format, &rates);
+        }
+        TestCommands::Ec2Regions => {
+            let ec2_client = Ec2Client::new(Some(config.aws_sdk_config().await), None).await;
+            let regions = ec2_client.describe_regions().await?;
+            output::print(format, &regions);
+        }
+        TestCommands::RedisTypeSpecificParameters => {
+            let client = ElasticacheClient::new(Some(config.aws_sdk_config().await)).await;
+            let parameters = client.list_redis_type_specific_parameters().await?;
+            output::print(format, &parameters);
+        }
+        TestCommands::MemcachedTypeSpecificParameters => {
+            let client = ElasticacheClient::new(Some(config.aws_sdk_config().await)).await;
+            let parameters = client.list_memcached_type_specific_parameters().await?;
+            output::print(format, &parameters);
+        }
+        TestCommands::MemorydbParameters { parameter_group } => {
+            let parameters = pekora
+                .cacheable_builder()
+                .build(MemoryDbClient::new_cacheable_arc(Some(config.aws_sdk_config().await)).await)
+                .load(parameter_group)
+                .await?
+                .result;
+            output::print(format, &parameters);
+        }
+        TestCommands::DocdbClusterParameters { family } => {
+            let parameters = pekora
+                .cacheable_builder()
+                .build(DocDbClient::new_cacheable_arc(Some(config.aws_sdk_config().await)).await)
+                .load(family)
+                .await?
+                .result;
+            output::print(format, &parameters);
+        }
+        TestCommands::ReservedCacheNodesOfferings { node_type } => {
+            let client = ElasticacheClient::new(Some(config.aws_sdk_config().await)).await;
+            let offerings = client
+                .describe_reserved_cache_nodes_offerings(
+                    &config.first_region(),
+                    node_type.as_deref(),
+                )
+                .await?;
+            output::print(format, &offerings);
+        }
+        TestCommands::Notify { title, body } => {
+            let dispatcher = NotificationDispatcher::from_config(
+                reqwest::Client::new(),
+                &config.notifications,
+                Some(&config.aws_sdk_config().await),
+            )?;
+            if dispatcher.is_empty() {
+                println!("No notification sinks configured");
+            }
+            let errors = dispatcher
+                .dispatch(&Notification {
+                    kind: NotificationKind::Test,
+                    title: title.clone(),
+                    body: body.clone(),
+                    details: None,
+                })
+                .await;
+            let failures = errors
+                .into_iter()
+                .map(|(sink, error)| BTreeMap::from([("sink", sink), ("error", error.to_string())]))
+                .collect::<Vec<_>>();
+            if !failures.is_empty() {
+                output::print(format, &failures);
+                anyhow::bail!("{} of the notification sinks failed", failures.len());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    let cli = Cli::parse();
+    let stats = cli.stats;
+    let result = main_command(cli).await;
+    if stats {
+        eprintln!("{}", metrics::global().snapshot());
+    }
+    match result {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs the command of `cli`. Returns `Ok(false)` when it completed but found failures, e.g.
+/// a check that didn't pass, so the caller sets the exit code after reporting stats.
+async fn main_command(cli: Cli) -> anyhow::Result<bool> {
+    let config = match Config::load_layered(cli.config.as_deref(), cli.config_overrides()) {
+        Ok(config) => config,
+        // Doctor reports config errors itself
+        Err(_) if matches!(cli.command, Commands::Doctor) => Config::default(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut client_builder = PriceBulkClientBuilder::new()
+        .download_directory(config.cache_directory())
+        .strict(config.strict.unwrap_or(false));
+    if let Some(partition) = config.partition {
+        client_builder = client_builder.partition(partition);
+    }
+    if let Some(base_url) = &config.base_url {
+        client_builder = client_builder.base_url(base_url);
+    }
+    if config.log_requests.unwrap_or(false) {
+        client_builder = client_builder.log_requests(true);
+    }
+    if let Some(head_cache_ttl_seconds) = config.head_cache_ttl_seconds {
+        client_builder =
+            client_builder.head_cache_ttl(std::time::Duration::from_secs(head_cache_ttl_seconds));
+    }
+    if let Some(timeout_seconds) = config.timeout_seconds {
+        client_builder = client_builder.timeout(std::time::Duration::from_secs(timeout_seconds));
+    }
+    if let Some(proxy) = &config.proxy {
+        client_builder = client_builder.proxy(proxy);
+    }
+    if let Some(max_concurrent_requests) = config.max_concurrent_requests {
+        client_builder = client_builder.max_concurrent_requests(max_concurrent_requests);
+    }
+    if let Some(min_concurrent_requests) = config.min_concurrent_requests {
+        client_builder = client_builder.min_concurrent_requests(min_concurrent_requests);
+    }
+    let clients = client_builder.build()?;
+    let mut pekora = Pekora::new(
+        clients.clone(),
+        Some(config.cache_directory().to_string()),
+        config.cache_max_age(),
+    )
+    .with_aws_sdk_config(config.aws_sdk_config().await);
+    if let Some(namespace) = &config.namespace {
+        pekora = pekora.with_namespace(namespace.clone());
+        if let Some(overlay) = config.namespaces.get(namespace) {
+            pekora = pekora.with_overlay(overlay.clone());
+        }
+    }
+    if let Some(hash_algorithm) = config.hash_algorithm {
+        pekora = pekora.with_hash_algorithm(hash_algorithm);
+    }
+
+    match cli.command {
+        Commands::Repl => {
+            ReplSession::new(pekora).run().await?;
+        }
+        Commands::Serve {
+            port,
+            grpc_port,
+            bind,
+        } => {
+            let state = server::AppState::new(pekora, config.first_region())
+                .with_namespaces(config.namespaces.clone());
+            let sdk_config = config.aws_sdk_config().await;
+            let address = std::net::SocketAddr::new(bind, port);
+            let result = match grpc_port {
+                Some(grpc_port) => {
+                    let grpc_address = std::net::SocketAddr::new(bind, grpc_port);
+                    tokio::try_join!(
+                        server::serve(state.clone(), sdk_config, address),
+                        server::grpc::serve(state, grpc_address),
+                    )
+                    .map(drop)
+                }
+                None => server::serve(state, sdk_config, address).await,
+            };
+            result?;
+        }
+        Commands::Daemon {
+            interval,
+            port,
+            bind,
+            concurrency,
+            services,
+        } => {
+            let services = if services.is_empty() {
+                None
+            } else {
+                Some(services)
+            };
+            let address = std::net::SocketAddr::new(bind, port);
+            main_daemon_command(interval, address, concurrency, services, &config, &pekora).await?;
+        }
+        Commands::Doctor => {
+            let results = doctor::run(&clients, cli.config.as_deref(), &config).await;
+            match config.output_format() {
+                OutputFormat::Text => {
+                    for result in &results {
+                        println!("{}", result);
+                    }
+                }
+                format => output::print(format, &results),
+            }
+            if results
+                .iter()
+                .any(|result| result.status == CheckStatus::Fail)
+            {
+                return Ok(false);
+            }
+        }
+        #[cfg(feature = "tui")]
+        Commands::Top { refresh_seconds } => {
+            pekora_cli::tui::run(
+                config.cache_directory().into(),
+                std::time::Duration::from_secs(refresh_seconds),
+            )?;
+        }
+        Commands::Load { command } => {
+            main_load_command(command, &config, &pekora).await?;
+        }
+        Commands::Query { command } => {
+            main_query_command(command, &config, &pekora).await?;
+        }
+        Commands::Price {
+            filters,
+            service,
+            term,
+            payment_option,
+            unit,
+            explain,
+        } => {
+            let query = parse_filters(&filters).and_then(|filters| {
+                Ok(PriceQuery {
+                    service_code: service,
+                    region: config.first_region(),
+                    filters,
+                    reservation: term
+                        .map(|term| Commitment::parse(&term, &payment_option))
+                        .transpose()?,
+                    unit,
+                })
+            });
+            let result = match query {
+                Ok(query) => main_price_command(&query, explain, &config, &pekora).await,
+                Err(e) => Err(e),
+            };
+            if !result? {
+                return Ok(false);
+            }
+        }
+        Commands::Estimate {
+            instance_type,
+            vcpus,
+            memory,
+            operating_system,
+            tenancy,
+            instances,
+            hours,
+            term,
+            payment_option,
+            network,
+        } => {
+            let instance = match (instance_type, vcpus.zip(memory)) {
+                (Some(instance_type), _) => InstanceRequirement::InstanceType(instance_type),
+                (None, Some((vcpus, memory_gib))) => {
+                    InstanceRequirement::Size { vcpus, memory_gib }
+                }
+                (None, None) => unreachable!("clap requires an instance type or size"),
+            };
+            let region = config.first_region();
+            let mut workload = WorkloadSpec::new(instance, region);
+            workload.operating_system = operating_system;
+            workload.tenancy = tenancy;
+            workload.instances = instances;
+            workload.hours_per_month = hours;
+            let result = match term.zip(payment_option) {
+                Some((term, payment_option)) => Commitment::parse(&term, &payment_option)
+                    .map(|commitment| workload.commitment = Some(commitment)),
+                None => Ok(()),
+            };
+            let result = match result {
+                Ok(()) => main_estimate_command(&workload, &network, &config, &pekora).await,
+                Err(e) => Err(e),
+            };
+            result?;
+        }
+        Commands::Optimize {
+            usage,
+            term,
+            payment_option,
+        } => {
+            let result = match Commitment::parse(&term, &payment_option) {
+                Ok(commitment) => {
+                    main_optimize_command(&usage, &commitment, &config, &pekora).await
+                }
+                Err(e) => Err(e),
+            };
+            result?;
+        }
+        Commands::BreakEven {
+            instance_type,
+            output,
+        } => {
+            main_break_even_command(
+                instance_type.as_deref(),
+                output.as_deref(),
+                &config,
+                &pekora,
+            )
+            .await?;
+        }
+        Commands::Diff { service, from, to } => {
+            let region = config.first_region();
+            main_diff_command(&service, &region, &from, &to, &config, &pekora).await?;
+        }
+        Commands::CompareArchitectures {
+            performance,
+            output,
+        } => {
+            main_compare_architectures_command(&performance, output.as_deref(), &config, &pekora)
+                .await?;
+        }
+        Commands::UnitCosts {
+            launch_dates,
+            newest_under,
+            output,
+        } => {
+            main_unit_costs_command(
+                launch_dates.as_deref(),
+                newest_under,
+                output.as_deref(),
+                &config,
+                &pekora,
+            )
+            .await?;
+        }
+        Commands::GpuPrices { output } => {
+            main_gpu_prices_command(output.as_deref(), &config, &pekora).await?;
+        }
+        Commands::ElasticacheReserved { node_type, output } => {
+            main_elasticache_reserved_command(
+                node_type.as_deref(),
+                output.as_deref(),
+                &config,
+                &pekora,
+            )
+            .await?;
+        }
+        Commands::ElasticacheMemory { engine, output } => {
+            main_elasticache_memory_command(&engine, output.as_deref(), &config, &pekora).await?;
+        }
+        Commands::Aurora {
+            engine,
+            instance_class,
+            replicas,
+            storage,
+            io_requests,
+            storage_config,
+            hours,
+        } => {
+            let spec = AuroraClusterSpec {
+                engine,
+                instance_class,
+                replicas,
+                storage_gb: storage,
+                io_requests,
+                hours_per_month: hours,
+            };
+            let storage_config = storage_config
+                .as_deref()
+                .map(|storage_config| {
+                    AuroraStorageConfig::parse(storage_config).ok_or_else(|| {
+                        anyhow::anyhow!("Unknown storage configuration {:?}", storage_config)
+                    })
+                })
+                .transpose();
+            let result = match storage_config {
+                Ok(storage_config) => {
+                    main_aurora_command(&spec, storage_config, &config, &pekora).await
+                }
+                Err(e) => Err(e),
+            };
+            result?;
+        }
+        Commands::RdsOrderable { engines, options } => {
+            main_rds_orderable_command(&engines, options.as_deref(), &config, &pekora).await?;
+        }
+        Commands::RdsReservedCheck { offerings, output } => {
+            main_rds_reserved_check_command(
+                offerings.as_deref(),
+                output.as_deref(),
+                &config,
+                &pekora,
+            )
+            .await?;
+        }
+        Commands::NodePrices {
+            service,
+            orderable,
+            docdb_orderable,
+            redshift_cluster,
+            engine_version,
+            output,
+        } => {
+            let node_types = NodeTypeSource::from_args(
+                service,
+                orderable.as_deref(),
+                docdb_orderable,
+                redshift_cluster.as_deref(),
+                engine_version.as_deref(),
+            )?;
+            main_node_prices_command(
+                service,
+                node_types.as_ref(),
+                output.as_deref(),
+                &config,
+                &pekora,
+            )
+            .await?;
+        }
+        Commands::DedicatedHosts {
+            family,
+            reservations,
+            output,
+        } => {
+            main_dedicated_hosts_command(
+                family.as_deref(),
+                reservations,
+                output.as_deref(),
+                &config,
+                &pekora,
+            )
+            .await?;
+        }
+        Commands::Rightsizing {
+            recommendations,
+            output,
+        } => {
+            main_rightsizing_command(
+                recommendations.as_deref(),
+                output.as_deref(),
+                &config,
+                &pekora,
+            )
+            .await?;
+        }
+        Commands::SavingsPlanCheck { rates, output } => {
+            main_savings_plan_check_command(rates.as_deref(), output.as_deref(), &config, &pekora)
+                .await?;
+        }
+        Commands::RdsStorage {
+            engine,
+            volume_type,
+            multi_az,
+            size,
+            iops,
+        } => {
+            let spec = RdsStorageSpec {
+                engine,
+                volume_type,
+                multi_az,
+                size_gb: size,
+                iops,
+            };
+            main_rds_storage_command(&spec, &config, &pekora).await?;
+        }
+        Commands::Volume {
+            volume_type,
+            size,
+            iops,
+            throughput,
+        } => {
+            let spec = VolumeSpec {
+                volume_type,
+                size_gib: size,
+                iops,
+                throughput_mibps: throughput,
+            };
+            main_volume_command(&spec, &config, &pekora).await?;
+        }
+        Commands::Cloudwatch {
+            metrics,
+            dashboards,
+            alarms,
+            logs_ingested_gb,
+            logs_stored_gb,
+        } => {
+            let usage = CloudWatchUsage {
+                metrics,
+                dashboards,
+                alarms,
+                logs_ingested_gb,
+                logs_stored_gb,
+            };
+            main_cloudwatch_command(&usage, &config, &pekora).await?;
+        }
+        Commands::Transfer { to, gb } => {
+            let destination = TransferDestination::parse(&to);
+            main_transfer_command(&destination, gb, &config, &pekora).await?;
+        }
+        Commands::Simulate {
+            usage,
+            commitment,
+            discount_percent,
+        } => {
+            let plan = CandidatePlan {
+                commitment_usd_per_hour: commitment,
+                discount_percent,
+            };
+            main_simulate_command(&usage, &plan, &config)?;
+        }
+        Commands::Recommend {
+            kind,
+            usage,
+            cost_and_usage,
+            cur,
+            cost_explorer_days,
+            term,
+            payment_option,
+            output,
+        } => {
+            let source = UsageSource::from_args(
+                usage.as_deref(),
+                cost_and_usage.as_deref(),
+                cur.as_deref(),
+                cost_explorer_days,
+            );
+            let result = match (source, Commitment::parse(&term, &payment_option)) {
+                (Ok(source), Ok(commitment)) => {
+                    main_recommend_command(
+                        kind,
+                        &source,
+                        &commitment,
+                        output.as_deref(),
+                        &config,
+                        &pekora,
+                    )
+                    .await
+                }
+                (Err(e), _) | (_, Err(e)) => Err(e),
+            };
+            result?;
+        }
+        #[cfg(feature = "parquet")]
+        Commands::CurUsage { path, output } => {
+            let report = read_cur_usage(&path, &config).await?;
+            write_recommendations(&report.usage, output.as_deref())?;
+        }
+        Commands::CommitmentHealth {
+            utilization,
+            coverage,
+            cost_explorer_days,
+        } => {
+            main_commitment_health_command(
+                utilization.as_deref(),
+                coverage.as_deref(),
+                cost_explorer_days,
+                &config,
+            )
+            .await?;
+        }
+        Commands::Fetch { command } => {
+            if !main_fetch_command(command, &config, &pekora).await? {
+                return Ok(false);
+            }
+        }
+        Commands::Audit {
+            command:
+                AuditCommands::Coverage {
+                    concurrency,
+                    services,
+                },
+        } => {
+            let services = if services.is_empty() {
+                None
+            } else {
+                Some(services)
+            };
+            let region = config.first_region();
+            let report = audit::coverage(&pekora, &region, Some(concurrency), services).await?;
+            match config.output_format() {
+                OutputFormat::Text => println!("{}", report),
+                format => output::print(format, &report),
+            }
+            if report.failures().next().is_some() {
+                return Ok(false);
+            }
+        }
+        Commands::Audit {
+            command: AuditCommands::Requests { since },
+        } => {
+            let since = parse_since(&since, chrono::Utc::now()).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Invalid --since {}, expected a timestamp, date or age",
+                    since
+                )
+            })?;
+            let entries = RequestLog::new(Path::new(config.cache_directory())).since(since)?;
+            match config.output_format() {
+                OutputFormat::Text => {
+                    for entry in &entries {
+                        println!(
+                            "{} {} {} {:?} {} {} bytes{}",
+                            entry.timestamp.to_rfc3339(),
+                            entry.method,
+                            entry.url,
+                            entry.outcome,
+                            entry.content_hash.as_deref().unwrap_or("-"),
+                            entry.bytes,
+                            entry
+                                .error
+                                .as_ref()
+                                .map(|error| format!(" ({})", error))
+                                .unwrap_or_default()
+                        );
+                    }
+                }
+                format => output::print(format, &entries),
+            }
+        }
+        Commands::Run { pipeline, force } => {
+            let result = match config.pipelines.get(&pipeline) {
+                Some(pipeline_config) => {
+                    main_run_command(&pipeline, pipeline_config, force, &config, &pekora).await
+                }
+                None => Err(anyhow::anyhow!(
+                    "No pipeline named {} in config, available: {:?}",
+                    pipeline,
+                    config.pipelines.keys().collect::<Vec<_>>()
+                )),
+            };
+            eprintln!("{}", result?);
+        }
+        Commands::Export {
+            dataset,
+            filters,
+            format,
+            output,
+            force,
+            delta,
+        } => {
+            let region = config.first_region();
+            let result = match parse_filters(&filters) {
+                Ok(filters) => {
+                    let pipeline_config = pipeline::PipelineConfig {
+                        source: match dataset {
+                            ExportDataset::Ec2OnDemand => {
+                                pipeline::PipelineSource::Ec2OnDemand { region }
+                            }
+                            ExportDataset::ComputeSavingsPlan => {
+                                pipeline::PipelineSource::ComputeSavingsPlan { region }
+                            }
+                            ExportDataset::Ec2InstanceSavingsPlan => {
+                                pipeline::PipelineSource::Ec2InstanceSavingsPlan { region }
+                            }
+                        },
+                        filters: filters.into_iter().collect(),
+                        export: pipeline::ExportConfig {
+                            format,
+                            path: output,
+                            delta,
+                        },
+                        actions: Vec::new(),
+                    };
+                    pipeline::run(&pekora, &pipeline_config, force).await
+                }
+                Err(e) => Err(e),
+            };
+            eprintln!("{}", result?);
+        }
+        Commands::Test { command } => {
+            let result = main_test_command(&command, &config, &pekora).await;
+            if let Err(e) = &result {
+                let error_log = ErrorLog::new(Path::new(config.cache_directory()));
+                if let Err(log_error) =
+                    error_log.record(&format!("test {:?}", command), &e.to_string())
+                {
+                    eprintln!("Failed to record error: {}", log_error);
+                }
+            }
+            result?;
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cli, OutputFormat};
+    use clap::{CommandFactory, Parser};
+
+    #[test]
+    fn cli_debug_assert() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn cli_output_format() {
+        let cli = Cli::try_parse_from(["pekora", "--output", "json", "doctor"]).unwrap();
+        assert!(matches!(cli.output_format, Some(OutputFormat::Json)));
+        // the former name keeps working
+        let cli = Cli::try_parse_from(["pekora", "doctor", "--output-format", "csv"]).unwrap();
+        assert!(matches!(cli.output_format, Some(OutputFormat::Csv)));
+    }
+}
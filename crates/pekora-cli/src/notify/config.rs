@@ -0,0 +1,75 @@
+use crate::notify::{NotificationSinkBox, NotifyResult, SlackSink, SnsSink, WebhookSink};
+use aws_config::SdkConfig;
+use serde::Deserialize;
+
+/// Sink definition in the `[[notifications]]` section of the config file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationSinkConfig {
+    Webhook {
+        url: String,
+    },
+    Slack {
+        webhook_url: String,
+        channel: Option<String>,
+    },
+    /// Publishes to an SNS topic in the region of its ARN
+    Sns {
+        topic_arn: String,
+    },
+    Email {
+        smtp_host: String,
+        smtp_port: Option<u16>,
+        username: Option<String>,
+        password: Option<String>,
+        from: String,
+        to: Vec<String>,
+    },
+}
+
+impl NotificationSinkConfig {
+    pub fn build(
+        &self,
+        client: reqwest::Client,
+        sdk_config: Option<&SdkConfig>,
+    ) -> NotifyResult<NotificationSinkBox> {
+        match self {
+            NotificationSinkConfig::Webhook { url } => {
+                Ok(Box::new(WebhookSink::new(client, url.clone())))
+            }
+            NotificationSinkConfig::Slack {
+                webhook_url,
+                channel,
+            } => Ok(Box::new(SlackSink::new(
+                client,
+                webhook_url.clone(),
+                channel.clone(),
+            ))),
+            NotificationSinkConfig::Sns { topic_arn } => {
+                Ok(Box::new(SnsSink::new(topic_arn.clone(), sdk_config)?))
+            }
+            #[cfg(feature = "email")]
+            NotificationSinkConfig::Email {
+                smtp_host,
+                smtp_port,
+                username,
+                password,
+                from,
+                to,
+            } => Ok(Box::new(crate::notify::EmailSink::new(
+                smtp_host,
+                *smtp_port,
+                username.clone().zip(password.clone()),
+                from,
+                to,
+            )?)),
+            #[cfg(not(feature = "email"))]
+            NotificationSinkConfig::Email { .. } => {
+                Err(crate::notify::NotifyError::FeatureDisabled(
+                    "email".to_string(),
+                    "email".to_string(),
+                ))
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::notify::{Notification, NotificationSink, NotifyError, NotifyResult};
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+/// Sends the notification as a plain text email over SMTP (STARTTLS).
+pub struct EmailSink {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl EmailSink {
+    pub fn new(
+        smtp_host: &str,
+        smtp_port: Option<u16>,
+        credentials: Option<(String, String)>,
+        from: &str,
+        to: &[String],
+    ) -> NotifyResult<Self> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(smtp_host)
+            .map_err(|e| NotifyError::Email(e.to_string()))?;
+        if let Some(port) = smtp_port {
+            builder = builder.port(port);
+        }
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        let from = from
+            .parse::<Mailbox>()
+            .map_err(|e| NotifyError::Email(e.to_string()))?;
+        let to = to
+            .iter()
+            .map(|address| address.parse::<Mailbox>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| NotifyError::Email(e.to_string()))?;
+        Ok(Self {
+            transport: builder.build(),
+            from,
+            to,
+        })
+    }
+}
+
+#[async_trait]
+impl NotificationSink for EmailSink {
+    fn name(&self) -> String {
+        format!("email({} recipients)", self.to.len())
+    }
+
+    async fn send(&self, notification: &Notification) -> NotifyResult<()> {
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(notification.title.clone());
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        let message = builder
+            .body(notification.body.clone())
+            .map_err(|e| NotifyError::Email(e.to_string()))?;
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| NotifyError::Email(e.to_string()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+//! Notification sinks shared by the subsystems that need to alert someone
+mod config;
+#[cfg(feature = "email")]
+mod email;
+mod slack;
+mod sns;
+mod webhook;
+
+pub use config::*;
+#[cfg(feature = "email")]
+pub use email::EmailSink;
+pub use slack::SlackSink;
+pub use sns::SnsSink;
+pub use webhook::WebhookSink;
+
+use async_trait::async_trait;
+use aws_config::SdkConfig;
+use log::{info, warn};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    PriceDiff,
+    Anomaly,
+    Budget,
+    PipelineRun,
+    Test,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    /// Structured details for sinks that can carry them, e.g. webhooks
+    pub details: Option<serde_json::Value>,
+}
+
+#[async_trait]
+pub trait NotificationSink {
+    fn name(&self) -> String;
+    async fn send(&self, notification: &Notification) -> NotifyResult<()>;
+}
+
+pub type NotificationSinkBox = Box<dyn NotificationSink + Send + Sync>;
+
+/// Fans a notification out to every configured sink. A failing sink doesn't stop delivery to the
+/// others.
+pub struct NotificationDispatcher {
+    sinks: Vec<NotificationSinkBox>,
+}
+
+impl NotificationDispatcher {
+    pub fn new(sinks: Vec<NotificationSinkBox>) -> Self {
+        Self { sinks }
+    }
+
+    /// Builds the sinks of `configs`. SNS sinks publish with the credentials and retry settings
+    /// of `sdk_config`.
+    pub fn from_config(
+        client: reqwest::Client,
+        configs: &[NotificationSinkConfig],
+        sdk_config: Option<&SdkConfig>,
+    ) -> NotifyResult<Self> {
+        let sinks = configs
+            .iter()
+            .map(|config| config.build(client.clone(), sdk_config))
+            .collect::<NotifyResult<Vec<_>>>()?;
+        Ok(Self::new(sinks))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    /// Sends `notification` to all sinks, returning the errors of sinks that failed.
+    pub async fn dispatch(&self, notification: &Notification) -> Vec<(String, NotifyError)> {
+        let mut errors = Vec::new();
+        for sink in &self.sinks {
+            match sink.send(notification).await {
+                Ok(()) => info!("Notification sent via {}", sink.name()),
+                Err(e) => {
+                    warn!("Notification via {} failed: {}", sink.name(), e);
+                    errors.push((sink.name(), e));
+                }
+            }
+        }
+        errors
+    }
+}
+
+pub type NotifyResult<T> = Result<T, NotifyError>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum NotifyError {
+    #[error("HTTP client failure: {0}")]
+    HttpFailure(#[from] reqwest::Error),
+    #[error("Email failure: {0}")]
+    Email(String),
+    #[error("AWS failure: {0}")]
+    Aws(String),
+    #[error("Sink {0} requires the `{1}` feature")]
+    FeatureDisabled(String, String),
+}
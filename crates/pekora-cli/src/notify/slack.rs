@@ -0,0 +1,51 @@
+use crate::notify::{Notification, NotificationSink, NotifyResult};
+use async_trait::async_trait;
+use serde::Serialize;
+
+/// Posts the notification to a Slack incoming webhook.
+pub struct SlackSink {
+    client: reqwest::Client,
+    webhook_url: String,
+    channel: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct SlackMessage<'a> {
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel: Option<&'a str>,
+}
+
+impl SlackSink {
+    pub fn new(client: reqwest::Client, webhook_url: String, channel: Option<String>) -> Self {
+        Self {
+            client,
+            webhook_url,
+            channel,
+        }
+    }
+}
+
+#[async_trait]
+impl NotificationSink for SlackSink {
+    fn name(&self) -> String {
+        match &self.channel {
+            Some(channel) => format!("slack({})", channel),
+            None => "slack".to_string(),
+        }
+    }
+
+    async fn send(&self, notification: &Notification) -> NotifyResult<()> {
+        let message = SlackMessage {
+            text: format!("*{}*\n{}", notification.title, notification.body),
+            channel: self.channel.as_deref(),
+        };
+        self.client
+            .post(&self.webhook_url)
+            .json(&message)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use crate::notify::{Notification, NotificationSink, NotifyError, NotifyResult};
+use async_trait::async_trait;
+use aws_config::{Region, SdkConfig};
+
+/// SNS limits subjects to 100 characters.
+const MAX_SUBJECT_LENGTH: usize = 100;
+
+/// Publishes the notification as JSON to an SNS topic.
+pub struct SnsSink {
+    client: Option<aws_sdk_sns::Client>,
+    topic_arn: String,
+}
+
+impl SnsSink {
+    /// Publishes to the region of `topic_arn` with the credentials and retry settings of
+    /// `sdk_config`. Sending fails without it.
+    pub fn new(topic_arn: String, sdk_config: Option<&SdkConfig>) -> NotifyResult<Self> {
+        let region = match topic_region(&topic_arn) {
+            Some(region) => region.to_string(),
+            None => return Err(NotifyError::Aws(format!("Invalid SNS topic {}", topic_arn))),
+        };
+        let client = sdk_config.map(|sdk_config| {
+            let config = aws_sdk_sns::config::Builder::from(sdk_config)
+                .region(Region::new(region))
+                .build();
+            aws_sdk_sns::Client::from_conf(config)
+        });
+        Ok(Self { client, topic_arn })
+    }
+}
+
+/// Region of `arn:aws:sns:<region>:<account>:<topic>`.
+fn topic_region(topic_arn: &str) -> Option<&str> {
+    match topic_arn.split(':').collect::<Vec<_>>()[..] {
+        ["arn", _, "sns", region, _, topic] if !region.is_empty() && !topic.is_empty() => {
+            Some(region)
+        }
+        _ => None,
+    }
+}
+
+fn subject(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| c.is_ascii() && !c.is_ascii_control())
+        .take(MAX_SUBJECT_LENGTH)
+        .collect()
+}
+
+#[async_trait]
+impl NotificationSink for SnsSink {
+    fn name(&self) -> String {
+        format!("sns({})", self.topic_arn)
+    }
+
+    async fn send(&self, notification: &Notification) -> NotifyResult<()> {
+        let client = match &self.client {
+            Some(client) => client,
+            None => return Err(NotifyError::Aws("No AWS config to publish with".into())),
+        };
+        let message =
+            serde_json::to_string(notification).map_err(|e| NotifyError::Aws(e.to_string()))?;
+        client
+            .publish()
+            .topic_arn(&self.topic_arn)
+            .subject(subject(&notification.title))
+            .message(message)
+            .send()
+            .await
+            .map_err(|e| NotifyError::Aws(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{subject, topic_region};
+
+    #[test]
+    fn test_topic() {
+        assert_eq!(
+            topic_region("arn:aws:sns:eu-west-1:123456789012:pricing"),
+            Some("eu-west-1")
+        );
+        assert_eq!(
+            topic_region("arn:aws-cn:sns:cn-north-1:123456789012:pricing"),
+            Some("cn-north-1")
+        );
+        assert_eq!(
+            topic_region("arn:aws:sqs:eu-west-1:123456789012:pricing"),
+            None
+        );
+        assert_eq!(topic_region("pricing"), None);
+        assert_eq!(subject("Prices → changed\n"), "Prices  changed");
+        assert_eq!(subject(&"x".repeat(150)).len(), 100);
+    }
+}
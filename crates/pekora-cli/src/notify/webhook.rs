@@ -0,0 +1,50 @@
+use crate::notify::{Notification, NotificationSink, NotifyResult};
+use async_trait::async_trait;
+
+/// Posts the notification as JSON to an arbitrary URL.
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl WebhookSink {
+    pub fn new(client: reqwest::Client, url: String) -> Self {
+        Self { client, url }
+    }
+}
+
+#[async_trait]
+impl NotificationSink for WebhookSink {
+    /// Names the host only, as paths and queries of webhook URLs often carry a secret token.
+    fn name(&self) -> String {
+        let host = reqwest::Url::parse(&self.url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string));
+        format!("webhook({})", host.as_deref().unwrap_or("invalid URL"))
+    }
+
+    async fn send(&self, notification: &Notification) -> NotifyResult<()> {
+        self.client
+            .post(&self.url)
+            .json(notification)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WebhookSink;
+    use crate::notify::NotificationSink;
+
+    #[test]
+    fn test_name_hides_token() {
+        let sink = WebhookSink::new(
+            reqwest::Client::new(),
+            "https://hooks.example.com/services/T000/SECRET?token=abc".to_string(),
+        );
+        assert_eq!(sink.name(), "webhook(hooks.example.com)");
+    }
+}
@@ -0,0 +1,254 @@
+use crate::config::OutputFormat;
+use comfy_table::{presets, Table};
+use pekora_aws::output::xlsx::{self, Sheet};
+use pekora_aws::pipeline;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+
+/// Column records are split into sheets of workbooks by
+const SHEET_COLUMN: &str = "purchase_model";
+
+/// Prints `value` as JSON, YAML, CSV, a table, a workbook or Markdown. Reports of the text
+/// format have their own layout and come out as a table here.
+pub fn print<T: Serialize>(format: OutputFormat, value: &T) {
+    let stdout = std::io::stdout();
+    if format == OutputFormat::Xlsx && stdout.is_terminal() {
+        eprintln!("Workbooks are binary, redirect the output to a file");
+        return;
+    }
+    if let Err(e) = write(format, value, stdout.lock()) {
+        eprintln!("{}", e);
+    }
+}
+
+/// Writes `value` to `path`, replacing it atomically, or prints it if no path is given.
+pub fn save<T: Serialize>(
+    format: OutputFormat,
+    value: &T,
+    path: Option<&str>,
+) -> anyhow::Result<()> {
+    match path {
+        Some(path) => {
+            pipeline::write_atomically(Path::new(path), |writer| write(format, value, writer))
+        }
+        None => {
+            print(format, value);
+            Ok(())
+        }
+    }
+}
+
+pub fn write<T: Serialize>(
+    format: OutputFormat,
+    value: &T,
+    mut writer: impl Write,
+) -> anyhow::Result<()> {
+    if format == OutputFormat::Json {
+        serde_json::to_writer_pretty(&mut writer, value)?;
+        writeln!(writer)?;
+        return Ok(());
+    }
+    if format == OutputFormat::Yaml {
+        serde_yaml::to_writer(writer, value)?;
+        return Ok(());
+    }
+    let (columns, rows) = tabulate(serde_json::to_value(value)?);
+    match format {
+        OutputFormat::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(writer);
+            csv_writer.write_record(&columns)?;
+            for row in rows {
+                csv_writer.write_record(row)?;
+            }
+            csv_writer.flush()?;
+        }
+        OutputFormat::Xlsx => xlsx::write(&sheets(columns, rows), writer)?,
+        OutputFormat::Md => {
+            let line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+            let escape = |cell: &String| cell.replace('|', "\\|").replace('\n', " ");
+            writeln!(writer, "{}", line(columns.iter().map(escape).collect()))?;
+            writeln!(
+                writer,
+                "{}",
+                line(columns.iter().map(|_| "---".to_string()).collect())
+            )?;
+            for row in &rows {
+                writeln!(writer, "{}", line(row.iter().map(escape).collect()))?;
+            }
+        }
+        _ => {
+            let mut table = Table::new();
+            table
+                .load_preset(presets::UTF8_FULL_CONDENSED)
+                .set_header(columns)
+                .add_rows(rows);
+            writeln!(writer, "{}", table)?;
+        }
+    }
+    Ok(())
+}
+
+/// Columns and rows of a serialized value. Arrays are a row per element, maps of objects a row
+/// per entry with the key in the first column. Nested objects are flattened to `parent.child`
+/// columns, nested arrays kept as JSON.
+fn tabulate(value: Value) -> (Vec<String>, Vec<Vec<String>>) {
+    let records = match value {
+        Value::Array(items) => items.into_iter().map(record).collect::<Vec<_>>(),
+        Value::Object(entries) if !entries.is_empty() && entries.values().all(Value::is_object) => {
+            entries
+                .into_iter()
+                .map(|(key, value)| {
+                    let mut record = vec![("key".to_string(), key)];
+                    record.extend(self::record(value));
+                    record
+                })
+                .collect()
+        }
+        value => vec![record(value)],
+    };
+    let mut columns: Vec<String> = Vec::new();
+    let mut seen = BTreeSet::new();
+    for (column, _) in records.iter().flatten() {
+        if seen.insert(column.as_str()) {
+            columns.push(column.clone());
+        }
+    }
+    let rows = records
+        .iter()
+        .map(|record| {
+            columns
+                .iter()
+                .map(|column| {
+                    record
+                        .iter()
+                        .find(|(name, _)| name == column)
+                        .map(|(_, cell)| cell.clone())
+                        .unwrap_or_default()
+                })
+                .collect()
+        })
+        .collect();
+    (columns, rows)
+}
+
+/// A sheet per value of the `purchase_model` column in order of appearance, or a single sheet.
+fn sheets(columns: Vec<String>, rows: Vec<Vec<String>>) -> Vec<Sheet> {
+    let index = match columns.iter().position(|column| column == SHEET_COLUMN) {
+        Some(index) => index,
+        None => {
+            return vec![Sheet {
+                name: "Report".to_string(),
+                columns,
+                rows,
+            }]
+        }
+    };
+    let mut sheets: Vec<Sheet> = Vec::new();
+    for row in rows {
+        match sheets.iter_mut().find(|sheet| sheet.name == row[index]) {
+            Some(sheet) => sheet.rows.push(row),
+            None => sheets.push(Sheet {
+                name: row[index].clone(),
+                columns: columns.clone(),
+                rows: vec![row],
+            }),
+        }
+    }
+    sheets
+}
+
+fn record(value: Value) -> Vec<(String, String)> {
+    let mut cells = Vec::new();
+    match value {
+        Value::Object(entries) => flatten("", entries, &mut cells),
+        value => cells.push(("value".to_string(), cell(value))),
+    }
+    cells
+}
+
+fn flatten(prefix: &str, entries: Map<String, Value>, cells: &mut Vec<(String, String)>) {
+    for (key, value) in entries {
+        let column = if prefix.is_empty() {
+            key
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            Value::Object(entries) => flatten(&column, entries, cells),
+            value => cells.push((column, cell(value))),
+        }
+    }
+}
+
+fn cell(value: Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value,
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sheets, tabulate, write};
+    use crate::config::OutputFormat;
+    use serde_json::json;
+
+    #[test]
+    fn test_write() {
+        let value = json!([
+            {"sku": "A1", "price": {"usd": "0.1"}, "tags": ["x"]},
+            {"sku": "B2", "price": {"usd": "0.25"}, "note": null},
+        ]);
+        let mut csv = Vec::new();
+        write(OutputFormat::Csv, &value, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "price.usd,sku,tags,note\n0.1,A1,\"[\"\"x\"\"]\",\n0.25,B2,,\n"
+        );
+
+        let value = json!({"m5.large": {"vcpu": 2}, "m5.xlarge": {"vcpu": 4}});
+        let mut table = Vec::new();
+        write(OutputFormat::Table, &value, &mut table).unwrap();
+        assert_eq!(
+            String::from_utf8(table).unwrap(),
+            "┌───────────┬──────┐\n\
+             │ key       ┆ vcpu │\n\
+             ╞═══════════╪══════╡\n\
+             │ m5.large  ┆ 2    │\n\
+             │ m5.xlarge ┆ 4    │\n\
+             └───────────┴──────┘\n"
+        );
+        let mut yaml = Vec::new();
+        write(OutputFormat::Yaml, &value, &mut yaml).unwrap();
+        assert_eq!(
+            String::from_utf8(yaml).unwrap(),
+            "m5.large:\n  vcpu: 2\nm5.xlarge:\n  vcpu: 4\n"
+        );
+
+        let value = json!([
+            {"purchase_model": "on_demand", "monthly_usd": "70.08", "note": "a|b"},
+            {"purchase_model": "reserved", "monthly_usd": "44.53"},
+            {"purchase_model": "on_demand", "monthly_usd": "35.04"},
+        ]);
+        let mut markdown = Vec::new();
+        write(OutputFormat::Md, &value, &mut markdown).unwrap();
+        assert_eq!(
+            String::from_utf8(markdown).unwrap(),
+            "| monthly_usd | note | purchase_model |\n| --- | --- | --- |\n\
+             | 70.08 | a\\|b | on_demand |\n| 44.53 |  | reserved |\n| 35.04 |  | on_demand |\n"
+        );
+        let (columns, rows) = tabulate(value);
+        let sheets = sheets(columns, rows);
+        assert_eq!(
+            sheets
+                .iter()
+                .map(|sheet| (sheet.name.as_str(), sheet.rows.len()))
+                .collect::<Vec<_>>(),
+            [("on_demand", 2), ("reserved", 1)]
+        );
+    }
+}
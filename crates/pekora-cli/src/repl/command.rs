@@ -0,0 +1,110 @@
+use clap::{Parser, Subcommand};
+use pekora_aws::api::aws::types::LocationType;
+
+#[derive(Parser, Debug)]
+#[command(no_binary_name = true, disable_version_flag = true)]
+pub struct ReplLine {
+    #[command(subcommand)]
+    pub command: ReplCommand,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum ReplCommand {
+    /// Load a pricing list into memory as <name>
+    LoadPricing {
+        name: String,
+        #[arg(long, default_value = "AmazonEC2")]
+        service: String,
+        #[arg(long, default_value = "ap-northeast-1")]
+        region: String,
+        #[arg(long)]
+        version: String,
+    },
+    /// Load and pivot a savings plan list into memory as <name>
+    LoadSavingsPlan {
+        name: String,
+        #[arg(long, default_value = "AWSComputeSavingsPlan")]
+        service: String,
+        #[arg(long, default_value = "ap-northeast-1")]
+        region: String,
+        #[arg(long)]
+        version: String,
+        #[arg(long = "location-type", value_enum)]
+        location_types: Vec<LocationType>,
+    },
+    /// List loaded datasets
+    Datasets,
+    /// Drop a loaded dataset
+    Drop { dataset: String },
+    /// Show rows whose fields match all <field>=<value> filters
+    Filter {
+        dataset: String,
+        filters: Vec<String>,
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Show everything known about a SKU
+    Explain { dataset: String, sku: String },
+    /// Show two SKUs side by side
+    Compare {
+        dataset: String,
+        left_sku: String,
+        right_sku: String,
+    },
+    /// Leave the REPL
+    #[command(alias = "quit")]
+    Exit,
+}
+
+/// Parses a REPL input line. Returns `Ok(None)` for blank lines.
+pub fn parse_line(line: &str) -> Result<Option<ReplCommand>, clap::Error> {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    if words.is_empty() {
+        return Ok(None);
+    }
+    ReplLine::try_parse_from(words).map(|parsed| Some(parsed.command))
+}
+
+/// Parses `field=value` filter arguments.
+pub fn parse_filters(filters: &[String]) -> anyhow::Result<Vec<(String, String)>> {
+    filters
+        .iter()
+        .map(|filter| match filter.split_once('=') {
+            Some((field, value)) => Ok((field.to_string(), value.to_string())),
+            None => anyhow::bail!("Invalid filter {}, expected <field>=<value>", filter),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_filters, parse_line, ReplCommand};
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(parse_line("   ").unwrap(), None);
+        assert_eq!(
+            parse_line("filter ec2 instanceType=m5.large --limit 5").unwrap(),
+            Some(ReplCommand::Filter {
+                dataset: "ec2".to_string(),
+                filters: vec!["instanceType=m5.large".to_string()],
+                limit: 5,
+            })
+        );
+        assert_eq!(parse_line("quit").unwrap(), Some(ReplCommand::Exit));
+        assert!(parse_line("load-pricing ec2").is_err());
+    }
+
+    #[test]
+    fn test_parse_filters() {
+        let filters = parse_filters(&["a=b".to_string(), "c=d=e".to_string()]).unwrap();
+        assert_eq!(
+            filters,
+            vec![
+                ("a".to_string(), "b".to_string()),
+                ("c".to_string(), "d=e".to_string())
+            ]
+        );
+        assert!(parse_filters(&["a".to_string()]).is_err());
+    }
+}
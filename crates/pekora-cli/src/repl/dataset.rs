@@ -0,0 +1,188 @@
+use pekora_aws::api::aws::price_bulk_types::{PricingListResponse, PricingListResponseProduct};
+use pekora_aws::transform::aws::savings_plan::PivotedSavingsPlanTermRate;
+use std::collections::HashMap;
+
+/// A dataset held in memory between REPL commands.
+pub enum LoadedDataset {
+    Pricing(Box<PricingListResponse>),
+    SavingsPlan(Vec<PivotedSavingsPlanTermRate>),
+}
+
+impl LoadedDataset {
+    pub fn describe(&self) -> String {
+        match self {
+            LoadedDataset::Pricing(response) => format!(
+                "pricing list version {} ({} products)",
+                response.version,
+                response.products.len()
+            ),
+            LoadedDataset::SavingsPlan(rows) => format!("savings plan rates ({} rows)", rows.len()),
+        }
+    }
+
+    /// Renders rows matching all `filters`, up to `limit` rows.
+    pub fn filter(&self, filters: &[(String, String)], limit: usize) -> Vec<String> {
+        match self {
+            LoadedDataset::Pricing(response) => response
+                .products
+                .values()
+                .filter(|product| {
+                    filters.iter().all(|(field, value)| {
+                        pricing_field(product, field).is_some_and(|v| &v == value)
+                    })
+                })
+                .take(limit)
+                .map(|product| {
+                    format!(
+                        "{} {} {}",
+                        product.sku,
+                        product.product_family,
+                        product
+                            .attributes
+                            .get("instanceType")
+                            .map(String::as_str)
+                            .unwrap_or("-")
+                    )
+                })
+                .collect(),
+            LoadedDataset::SavingsPlan(rows) => rows
+                .iter()
+                .filter(|row| {
+                    filters.iter().all(|(field, value)| {
+                        savings_plan_field(row, field).is_some_and(|v| &v == value)
+                    })
+                })
+                .take(limit)
+                .map(format_savings_plan_row)
+                .collect(),
+        }
+    }
+
+    pub fn explain(&self, sku: &str) -> Vec<String> {
+        match self {
+            LoadedDataset::Pricing(response) => {
+                let mut lines = Vec::new();
+                let product = match response.products.get(sku) {
+                    Some(product) => product,
+                    None => return vec![format!("SKU {} not found", sku)],
+                };
+                lines.push(format!("{} ({})", product.sku, product.product_family));
+                let mut attributes = product.attributes.iter().collect::<Vec<_>>();
+                attributes.sort();
+                for (key, value) in attributes {
+                    lines.push(format!("  {} = {}", key, value));
+                }
+                for offering in response
+                    .terms
+                    .on_demand
+                    .get(sku)
+                    .into_iter()
+                    .flat_map(|o| o.values())
+                {
+                    for dimension in offering.price_dimensions.values() {
+                        lines.push(format!(
+                            "  OnDemand {}: {:?} per {} ({})",
+                            offering.offer_term_code,
+                            dimension.price_per_unit,
+                            dimension.unit,
+                            dimension.description
+                        ));
+                    }
+                }
+                for offering in response
+                    .terms
+                    .reserved
+                    .get(sku)
+                    .into_iter()
+                    .flat_map(|o| o.values())
+                {
+                    for dimension in offering.price_dimensions.values() {
+                        lines.push(format!(
+                            "  Reserved {:?}/{}/{:?}: {:?} per {}",
+                            offering.term_attributes.lease_contract_length,
+                            offering
+                                .term_attributes
+                                .offering_class
+                                .as_ref()
+                                .map_or("-", |class| class.as_str()),
+                            offering.term_attributes.purchase_option,
+                            dimension.price_per_unit,
+                            dimension.unit,
+                        ));
+                    }
+                }
+                lines
+            }
+            LoadedDataset::SavingsPlan(rows) => {
+                let lines = rows
+                    .iter()
+                    .filter(|row| {
+                        row.savings_plan_sku == sku || row.term_rate.discounted_sku == sku
+                    })
+                    .map(|row| format!("{:#?}", row))
+                    .collect::<Vec<_>>();
+                if lines.is_empty() {
+                    vec![format!("SKU {} not found", sku)]
+                } else {
+                    lines
+                }
+            }
+        }
+    }
+
+    pub fn compare(&self, left_sku: &str, right_sku: &str) -> Vec<String> {
+        let left = self.explain(left_sku);
+        let right = self.explain(right_sku);
+        let width = left.iter().map(|line| line.len()).max().unwrap_or(0);
+        (0..left.len().max(right.len()))
+            .map(|i| {
+                format!(
+                    "{:width$} | {}",
+                    left.get(i).map(String::as_str).unwrap_or(""),
+                    right.get(i).map(String::as_str).unwrap_or(""),
+                    width = width
+                )
+            })
+            .collect()
+    }
+}
+
+fn pricing_field(
+    product: &PricingListResponseProduct<HashMap<String, String>>,
+    field: &str,
+) -> Option<String> {
+    match field {
+        "sku" => Some(product.sku.clone()),
+        "productFamily" => Some(product.product_family.clone()),
+        _ => product.attributes.get(field).cloned(),
+    }
+}
+
+fn savings_plan_field(row: &PivotedSavingsPlanTermRate, field: &str) -> Option<String> {
+    let attributes = &row.savings_plan_attributes;
+    match field {
+        "sku" => Some(row.savings_plan_sku.clone()),
+        "discounted_sku" => Some(row.term_rate.discounted_sku.clone()),
+        "usage_type" => Some(row.term_rate.discounted_usage_type.clone()),
+        "operation" => Some(row.term_rate.discounted_operation.clone()),
+        "service_code" => Some(row.term_rate.discounted_service_code.clone()),
+        "instance_type" => attributes.instance_type.clone(),
+        "plan_type" => Some(attributes.product_family.as_str().to_string()),
+        "region" => attributes.region_code.clone(),
+        "purchase_option" => Some(format!("{:?}", attributes.purchase_option)),
+        "term" => Some(format!("{:?}", attributes.purchase_term)),
+        "location_type" => Some(format!("{:?}", attributes.location_type)),
+        _ => None,
+    }
+}
+
+fn format_savings_plan_row(row: &PivotedSavingsPlanTermRate) -> String {
+    format!(
+        "{} {} {} {} {:?}",
+        row.savings_plan_sku,
+        row.term_rate.discounted_usage_type,
+        row.term_rate.discounted_operation,
+        row.term_rate.discounted_rate.price,
+        row.term_rate.discounted_rate.currency
+    )
+}
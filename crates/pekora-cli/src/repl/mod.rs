@@ -0,0 +1,151 @@
+/// Interactive mode keeping datasets in memory between queries
+mod command;
+mod dataset;
+
+pub use command::{parse_filters, parse_line, ReplCommand};
+pub use dataset::LoadedDataset;
+
+use pekora_aws::api::aws::price_bulk_types::{PriceBulkOffer, PriceBulkSavingsPlan};
+use pekora_aws::transform::aws::location::LocationFilter;
+use pekora_aws::transform::aws::savings_plan;
+use pekora_aws::Pekora;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::collections::BTreeMap;
+
+pub struct ReplSession {
+    pekora: Pekora,
+    datasets: BTreeMap<String, LoadedDataset>,
+}
+
+impl ReplSession {
+    pub fn new(pekora: Pekora) -> Self {
+        Self {
+            pekora,
+            datasets: BTreeMap::new(),
+        }
+    }
+
+    /// Runs the read-eval-print loop until `exit` or EOF.
+    pub async fn run(&mut self) -> anyhow::Result<()> {
+        let mut editor = DefaultEditor::new()?;
+        loop {
+            let line = match editor.readline("pekora> ") {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(e.into()),
+            };
+            let command = match parse_line(&line) {
+                Ok(Some(command)) => command,
+                Ok(None) => continue,
+                Err(e) => {
+                    println!("{}", e.render());
+                    continue;
+                }
+            };
+            let _ = editor.add_history_entry(line.as_str());
+            if command == ReplCommand::Exit {
+                break;
+            }
+            match self.execute(command).await {
+                Ok(lines) => {
+                    for line in lines {
+                        println!("{}", line);
+                    }
+                }
+                Err(e) => println!("error: {}", e),
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn execute(&mut self, command: ReplCommand) -> anyhow::Result<Vec<String>> {
+        match command {
+            ReplCommand::LoadPricing {
+                name,
+                service,
+                region,
+                version,
+            } => {
+                let cached = self
+                    .pekora
+                    .cacheable_builder()
+                    .build(self.pekora.clients().pricing_list());
+                let response = cached
+                    .load(&PriceBulkOffer {
+                        region,
+                        service_code: service,
+                        offer_version: version,
+                        filename: "index.json".to_string(),
+                    })
+                    .await?;
+                Ok(self.insert(name, LoadedDataset::Pricing(Box::new(response.result))))
+            }
+            ReplCommand::LoadSavingsPlan {
+                name,
+                service,
+                region,
+                version,
+                location_types,
+            } => {
+                let cached = self
+                    .pekora
+                    .cacheable_builder()
+                    .build(self.pekora.clients().savings_plan_list());
+                let response = cached
+                    .load(&PriceBulkSavingsPlan {
+                        region,
+                        service_code: service,
+                        offer_version: version,
+                        filename: "index.json".to_string(),
+                    })
+                    .await?;
+                let location_filter = if location_types.is_empty() {
+                    LocationFilter::default()
+                } else {
+                    LocationFilter::new(location_types)
+                };
+                let rows = savings_plan::pivot(response.result, &location_filter)?;
+                Ok(self.insert(name, LoadedDataset::SavingsPlan(rows)))
+            }
+            ReplCommand::Datasets => Ok(self
+                .datasets
+                .iter()
+                .map(|(name, dataset)| format!("{}: {}", name, dataset.describe()))
+                .collect()),
+            ReplCommand::Drop { dataset } => match self.datasets.remove(&dataset) {
+                Some(_) => Ok(vec![format!("Dropped {}", dataset)]),
+                None => anyhow::bail!("No dataset named {}", dataset),
+            },
+            ReplCommand::Filter {
+                dataset,
+                filters,
+                limit,
+            } => {
+                let filters = parse_filters(&filters)?;
+                Ok(self.get(&dataset)?.filter(&filters, limit))
+            }
+            ReplCommand::Explain { dataset, sku } => Ok(self.get(&dataset)?.explain(&sku)),
+            ReplCommand::Compare {
+                dataset,
+                left_sku,
+                right_sku,
+            } => Ok(self.get(&dataset)?.compare(&left_sku, &right_sku)),
+            ReplCommand::Exit => Ok(Vec::new()),
+        }
+    }
+
+    fn insert(&mut self, name: String, dataset: LoadedDataset) -> Vec<String> {
+        let line = format!("Loaded {}: {}", name, dataset.describe());
+        self.datasets.insert(name, dataset);
+        vec![line]
+    }
+
+    fn get(&self, name: &str) -> anyhow::Result<&LoadedDataset> {
+        match self.datasets.get(name) {
+            Some(dataset) => Ok(dataset),
+            None => anyhow::bail!("No dataset named {}", name),
+        }
+    }
+}
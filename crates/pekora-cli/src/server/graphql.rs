@@ -0,0 +1,536 @@
+//! GraphQL schema over the normalized pricing model, served at `POST /graphql` with GraphiQL at
+//! `GET /graphql`. Fields resolve only when selected, so product terms and instance specs are
+//! loaded only for queries asking for them.
+use crate::server::{parse_namespace, AppState, Tenant, DEFAULT_LIMIT, NAMESPACE_HEADER};
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+use aws_config::SdkConfig;
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::response::Html;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, NaiveDate, Utc};
+use pekora_aws::api::aws::ec2::Ec2Client;
+use pekora_aws::api::aws::price_bulk_types::PricingListResponse;
+use pekora_aws::api::aws::types::{ContractLength, PriceDimension, PurchaseOption};
+use pekora_aws::cache::Namespace;
+use pekora_aws::dataset::{ComputeSavingsPlan, Ec2InstanceSavingsPlan, Ec2OnDemand};
+use pekora_aws::transform::aws::instance_specs::InstanceSpec;
+use pekora_aws::transform::aws::normalize::{self, NormalizedPriceRow, PurchaseModel};
+use pekora_aws::transform::aws::overlay::DiscountOverlay;
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+pub type PricingSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Schema answering queries with `state`, loading instance specs with `sdk_config`.
+pub(crate) fn schema(state: AppState, sdk_config: SdkConfig) -> PricingSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(state)
+        .data(sdk_config)
+        .finish()
+}
+
+pub(crate) fn router(schema: PricingSchema) -> Router {
+    Router::new()
+        .route("/graphql", get(graphiql).post(execute))
+        .with_state(schema)
+}
+
+async fn execute(
+    State(schema): State<PricingSchema>,
+    headers: HeaderMap,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    let mut request = request.data(LoadedSpecs::default());
+    match parse_namespace(headers.get(NAMESPACE_HEADER).map(|value| value.to_str())) {
+        Ok(Some(namespace)) => request = request.data(namespace),
+        Ok(None) => {}
+        Err(e) => {
+            let error = async_graphql::ServerError::new(e.message, None);
+            return Json(async_graphql::Response::from_errors(vec![error]));
+        }
+    }
+    Json(schema.execute(request).await)
+}
+
+async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+/// What the request is answered with, selected by its `X-Pekora-Namespace` header
+fn tenant<'a>(ctx: &Context<'a>) -> async_graphql::Result<Tenant<'a>> {
+    let state = ctx.data::<AppState>()?;
+    state
+        .tenant(ctx.data_opt::<Namespace>())
+        .map_err(|e| async_graphql::Error::new(e.message))
+}
+
+/// Instance specs by region, loaded at most once per request
+#[derive(Default)]
+struct LoadedSpecs(Mutex<HashMap<String, Arc<HashMap<String, InstanceSpec>>>>);
+
+async fn specs(
+    ctx: &Context<'_>,
+    region: &str,
+) -> async_graphql::Result<Arc<HashMap<String, InstanceSpec>>> {
+    let mut loaded = ctx.data::<LoadedSpecs>()?.0.lock().await;
+    if let Some(specs) = loaded.get(region) {
+        return Ok(specs.clone());
+    }
+    let tenant = tenant(ctx)?;
+    let sdk_config = ctx.data::<SdkConfig>()?;
+    let specs = tenant
+        .pekora
+        .cacheable_builder()
+        .build(Ec2Client::new_cacheable_arc(Some(sdk_config.clone())).await)
+        .load(&vec![region.to_string()])
+        .await?
+        .result;
+    let specs = Arc::new(specs);
+    loaded.insert(region.to_string(), specs.clone());
+    Ok(specs)
+}
+
+/// Parses an argument the way the pricing files spell it, e.g. `1yr` or `No Upfront`.
+fn parse<T: DeserializeOwned>(name: &str, value: &str) -> async_graphql::Result<T> {
+    serde_json::from_value(value.into())
+        .map_err(|_| async_graphql::Error::new(format!("Unknown {} {}", name, value)))
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Products of the pricing list of a service, by SKU
+    #[allow(clippy::too_many_arguments)]
+    async fn products(
+        &self,
+        ctx: &Context<'_>,
+        region: Option<String>,
+        #[graphql(default = "AmazonEC2")] service: String,
+        sku: Option<String>,
+        product_family: Option<String>,
+        instance_type: Option<String>,
+        #[graphql(default = 100)] limit: usize,
+    ) -> async_graphql::Result<Vec<Product>> {
+        let tenant = tenant(ctx)?;
+        let region = region.unwrap_or_else(|| tenant.default_region.to_string());
+        let response = tenant.pricing(&service, &region).await?;
+        let mut skus = response
+            .products
+            .values()
+            .filter(|product| {
+                sku.as_ref().is_none_or(|sku| *sku == product.sku)
+                    && product_family
+                        .as_ref()
+                        .is_none_or(|family| *family == product.product_family)
+                    && instance_type.as_ref().is_none_or(|instance_type| {
+                        product.attributes.get("instanceType") == Some(instance_type)
+                    })
+            })
+            .map(|product| product.sku.clone())
+            .collect::<Vec<_>>();
+        skus.sort();
+        skus.truncate(limit);
+        Ok(skus
+            .into_iter()
+            .map(|sku| Product {
+                response: response.clone(),
+                sku,
+                service: service.clone(),
+                overlay: tenant.pekora.overlay().cloned(),
+            })
+            .collect())
+    }
+
+    /// EC2 rates of a region under every purchase model, in USD per hour
+    #[allow(clippy::too_many_arguments)]
+    async fn rates(
+        &self,
+        ctx: &Context<'_>,
+        region: Option<String>,
+        instance_type: Option<String>,
+        #[graphql(
+            desc = "`on_demand`, `reserved`, `compute_savings_plan` or `ec2_instance_savings_plan`"
+        )]
+        purchase_model: Option<String>,
+        #[graphql(desc = "`1yr` or `3yr`")] term: Option<String>,
+        purchase_option: Option<String>,
+        #[graphql(default_with = "DEFAULT_LIMIT")] limit: usize,
+    ) -> async_graphql::Result<Vec<Rate>> {
+        let purchase_model = purchase_model
+            .map(|model| parse::<PurchaseModel>("purchase model", &model))
+            .transpose()?;
+        let term = term
+            .map(|term| parse::<ContractLength>("term", &term))
+            .transpose()?;
+        let purchase_option = purchase_option
+            .map(|option| parse::<PurchaseOption>("purchase option", &option))
+            .transpose()?;
+        let wants = |model| purchase_model.is_none_or(|wanted| wanted == model);
+        let tenant = tenant(ctx)?;
+        let region = region.unwrap_or_else(|| tenant.default_region.to_string());
+
+        // Savings plan rates are normalized against on-demand rates, so those are always loaded
+        let on_demand = tenant.dataset::<Ec2OnDemand>(region.clone()).await?;
+        let region_names = tenant.pekora.region_names().await;
+        let mut rows = Vec::new();
+        if wants(PurchaseModel::OnDemand) {
+            rows.extend(normalize::from_on_demand(on_demand.rows(), &region_names));
+        }
+        if wants(PurchaseModel::Reserved) {
+            let response = tenant.pricing("AmazonEC2", &region).await?;
+            rows.extend(normalize::from_reserved(&response, &region_names));
+        }
+        if wants(PurchaseModel::ComputeSavingsPlan) {
+            let compute = tenant.dataset::<ComputeSavingsPlan>(region.clone()).await?;
+            rows.extend(normalize::from_savings_plans(
+                compute.rows(),
+                on_demand.rows(),
+                &region_names,
+            ));
+        }
+        if wants(PurchaseModel::Ec2InstanceSavingsPlan) {
+            let ec2_instance = tenant
+                .dataset::<Ec2InstanceSavingsPlan>(region.clone())
+                .await?;
+            rows.extend(normalize::from_savings_plans(
+                ec2_instance.rows(),
+                on_demand.rows(),
+                &region_names,
+            ));
+        }
+        Ok(rows
+            .into_iter()
+            .filter(|row| {
+                instance_type
+                    .as_ref()
+                    .is_none_or(|instance_type| row.instance_type.as_ref() == Some(instance_type))
+                    && term
+                        .as_ref()
+                        .is_none_or(|term| row.term.as_ref() == Some(term))
+                    && purchase_option
+                        .as_ref()
+                        .is_none_or(|option| row.purchase_option.as_ref() == Some(option))
+            })
+            .take(limit)
+            .map(|mut row| {
+                if let Some(overlay) = tenant.pekora.overlay() {
+                    overlay.normalized(&mut row);
+                }
+                Rate {
+                    row,
+                    region: region.clone(),
+                }
+            })
+            .collect())
+    }
+
+    /// Hardware of the EC2 instance types of a region, all of them if none are given
+    async fn instance_specs(
+        &self,
+        ctx: &Context<'_>,
+        region: Option<String>,
+        instance_types: Option<Vec<String>>,
+    ) -> async_graphql::Result<Vec<Spec>> {
+        let tenant = tenant(ctx)?;
+        let region = region.unwrap_or_else(|| tenant.default_region.to_string());
+        let specs = specs(ctx, &region).await?;
+        let mut specs = specs
+            .values()
+            .filter(|spec| {
+                instance_types
+                    .as_ref()
+                    .is_none_or(|instance_types| instance_types.contains(&spec.instance_type))
+            })
+            .cloned()
+            .map(Spec)
+            .collect::<Vec<_>>();
+        specs.sort_by(|a, b| a.0.instance_type.cmp(&b.0.instance_type));
+        Ok(specs)
+    }
+}
+
+/// A product of a pricing list
+pub struct Product {
+    response: Arc<PricingListResponse>,
+    sku: String,
+    /// Service code of the pricing list
+    service: String,
+    /// Discounts of the namespace the product was queried for
+    overlay: Option<DiscountOverlay>,
+}
+
+#[derive(SimpleObject)]
+pub struct Attribute {
+    name: String,
+    value: String,
+}
+
+/// An on-demand or reserved term of a product
+#[derive(SimpleObject)]
+pub struct Term {
+    /// `OnDemand` or `Reserved`
+    term_type: &'static str,
+    offer_term_code: String,
+    effective_date: DateTime<Utc>,
+    lease_contract_length: Option<String>,
+    offering_class: Option<String>,
+    purchase_option: Option<String>,
+    price_dimensions: Vec<Dimension>,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "PriceDimension")]
+pub struct Dimension {
+    rate_code: String,
+    description: String,
+    unit: String,
+    usd: Option<Decimal>,
+    begin_range: Option<String>,
+    end_range: Option<String>,
+}
+
+impl From<&PriceDimension> for Dimension {
+    fn from(dimension: &PriceDimension) -> Self {
+        Self {
+            rate_code: dimension.rate_code.clone(),
+            description: dimension.description.clone(),
+            unit: dimension.unit.clone(),
+            usd: dimension.usd(),
+            begin_range: dimension.begin_range.clone(),
+            end_range: dimension.end_range.clone(),
+        }
+    }
+}
+
+impl Product {
+    /// `dimension` with the discount of the namespace applied
+    fn dimension(&self, dimension: &PriceDimension) -> Dimension {
+        let mut dimension = Dimension::from(dimension);
+        if let Some(overlay) = &self.overlay {
+            dimension.usd = dimension.usd.map(|usd| overlay.apply(&self.service, usd));
+        }
+        dimension
+    }
+}
+
+#[Object]
+impl Product {
+    async fn sku(&self) -> &str {
+        &self.sku
+    }
+
+    async fn product_family(&self) -> &str {
+        &self.response.products[&self.sku].product_family
+    }
+
+    /// Value of one attribute, e.g. `instanceType`
+    async fn attribute(&self, name: String) -> Option<&str> {
+        self.response.products[&self.sku]
+            .attributes
+            .get(&name)
+            .map(String::as_str)
+    }
+
+    async fn attributes(&self) -> Vec<Attribute> {
+        let mut attributes = self.response.products[&self.sku]
+            .attributes
+            .iter()
+            .map(|(name, value)| Attribute {
+                name: name.clone(),
+                value: value.clone(),
+            })
+            .collect::<Vec<_>>();
+        attributes.sort_by(|a, b| a.name.cmp(&b.name));
+        attributes
+    }
+
+    async fn terms(&self) -> Vec<Term> {
+        let terms = &self.response.terms;
+        let on_demand = terms
+            .on_demand
+            .get(&self.sku)
+            .into_iter()
+            .flat_map(|offers| {
+                offers.values().map(|offer| Term {
+                    term_type: "OnDemand",
+                    offer_term_code: offer.offer_term_code.clone(),
+                    effective_date: offer.effective_date,
+                    lease_contract_length: None,
+                    offering_class: None,
+                    purchase_option: None,
+                    price_dimensions: offer
+                        .price_dimensions
+                        .values()
+                        .map(|dimension| self.dimension(dimension))
+                        .collect(),
+                })
+            });
+        let reserved = terms
+            .reserved
+            .get(&self.sku)
+            .into_iter()
+            .flat_map(|offers| {
+                offers.values().map(|offer| {
+                    let attributes = &offer.term_attributes;
+                    Term {
+                        term_type: "Reserved",
+                        offer_term_code: offer.offer_term_code.clone(),
+                        effective_date: offer.effective_date,
+                        lease_contract_length: Some(
+                            attributes.lease_contract_length.as_str().into(),
+                        ),
+                        offering_class: attributes
+                            .offering_class
+                            .as_ref()
+                            .map(|class| class.as_str().to_string()),
+                        purchase_option: Some(attributes.purchase_option.as_str().to_string()),
+                        price_dimensions: offer
+                            .price_dimensions
+                            .values()
+                            .map(|dimension| self.dimension(dimension))
+                            .collect(),
+                    }
+                })
+            });
+        let mut terms = on_demand.chain(reserved).collect::<Vec<_>>();
+        terms.sort_by(|a, b| a.offer_term_code.cmp(&b.offer_term_code));
+        terms
+    }
+}
+
+/// Hourly rate of one SKU under one purchase model
+pub struct Rate {
+    row: NormalizedPriceRow,
+    /// Region the rate was queried in, to load its instance specs from
+    region: String,
+}
+
+#[Object]
+impl Rate {
+    async fn region(&self) -> Option<&str> {
+        self.row.region.as_deref()
+    }
+
+    async fn service_code(&self) -> Option<&str> {
+        self.row.service_code.as_deref()
+    }
+
+    async fn sku(&self) -> &str {
+        &self.row.sku
+    }
+
+    async fn instance_type(&self) -> Option<&str> {
+        self.row.instance_type.as_deref()
+    }
+
+    async fn platform(&self) -> Option<&str> {
+        self.row.platform.as_deref()
+    }
+
+    async fn purchase_model(&self) -> &str {
+        self.row.purchase_model.as_str()
+    }
+
+    async fn term(&self) -> Option<&str> {
+        self.row.term.as_ref().map(|term| term.as_str())
+    }
+
+    async fn purchase_option(&self) -> Option<&str> {
+        self.row
+            .purchase_option
+            .as_ref()
+            .map(|option| option.as_str())
+    }
+
+    /// Standard or convertible for reserved rates
+    async fn offering_class(&self) -> Option<&str> {
+        self.row
+            .offering_class
+            .as_ref()
+            .map(|offering_class| offering_class.as_str())
+    }
+
+    /// Upfront fees amortized over the term
+    async fn effective_usd_per_hour(&self) -> Decimal {
+        self.row.effective_usd_per_hour
+    }
+
+    /// Hardware of the rate's instance type
+    async fn spec(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Spec>> {
+        let instance_type = match &self.row.instance_type {
+            Some(instance_type) => instance_type,
+            None => return Ok(None),
+        };
+        let specs = specs(ctx, &self.region).await?;
+        Ok(specs.get(instance_type).cloned().map(Spec))
+    }
+}
+
+pub struct Spec(InstanceSpec);
+
+#[Object(name = "InstanceSpec")]
+impl Spec {
+    async fn instance_type(&self) -> &str {
+        &self.0.instance_type
+    }
+
+    /// e.g. `arm64` or `x86_64`
+    async fn architecture(&self) -> Option<&str> {
+        self.0.architecture.as_deref()
+    }
+
+    async fn vcpus(&self) -> Option<i32> {
+        self.0.vcpus
+    }
+
+    async fn memory_gib(&self) -> Option<Decimal> {
+        self.0.memory_gib
+    }
+
+    async fn network_performance(&self) -> Option<&str> {
+        self.0.network_performance.as_deref()
+    }
+
+    async fn gpus(&self) -> Option<i32> {
+        self.0.gpus
+    }
+
+    async fn gpu_memory_gib(&self) -> Option<Decimal> {
+        self.0.gpu_memory_gib
+    }
+
+    async fn gpu_model(&self) -> Option<&str> {
+        self.0.gpu_model.as_deref()
+    }
+
+    async fn launch_date(&self) -> Option<NaiveDate> {
+        self.0.launch_date
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QueryRoot, Schema};
+    use async_graphql::{EmptyMutation, EmptySubscription};
+
+    #[tokio::test]
+    async fn test_schema() {
+        let schema = Schema::new(QueryRoot, EmptyMutation, EmptySubscription);
+        let sdl = schema.sdl();
+        assert!(sdl.contains("type Rate {"));
+        assert!(sdl.contains("spec: InstanceSpec"));
+        assert!(sdl.contains("terms: [Term!]!"));
+
+        let response = schema
+            .execute("{ rates(purchaseModel: \"spot\") { sku } }")
+            .await;
+        assert_eq!(response.errors[0].message, "Unknown purchase model spot");
+    }
+}
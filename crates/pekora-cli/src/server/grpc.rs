@@ -0,0 +1,387 @@
+//! gRPC API of `proto/pekora/v1/pricing.proto`, answering the same queries as the REST API for
+//! gRPC-only consumers. Messages mirror the proto file and must be kept in step with it.
+use crate::server::{
+    parse_namespace, ApiError, AppState, EstimateQuery, PricesQuery, PricesResponse,
+    SavingsPlanRatesQuery, SavingsPlanRatesResponse, NAMESPACE_HEADER,
+};
+use axum::http::StatusCode;
+use log::info;
+use pekora_aws::cache::Namespace;
+use pekora_aws::transform::aws::estimate::CostEstimate;
+use pekora_aws::transform::aws::on_demand::OnDemandRate;
+use pekora_aws::transform::aws::savings_plan::PivotedSavingsPlanTermRate;
+use rust_decimal::Decimal;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use tonic::{Request, Response, Status};
+
+pub mod proto {
+    use std::collections::HashMap;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct PriceRequest {
+        #[prost(string, tag = "1")]
+        pub service: String,
+        #[prost(string, tag = "2")]
+        pub region: String,
+        #[prost(string, tag = "3")]
+        pub instance_type: String,
+        #[prost(uint32, tag = "4")]
+        pub limit: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct PriceResponse {
+        #[prost(string, tag = "1")]
+        pub service: String,
+        #[prost(string, tag = "2")]
+        pub region: String,
+        #[prost(string, tag = "3")]
+        pub version: String,
+        #[prost(message, repeated, tag = "4")]
+        pub prices: Vec<Price>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Price {
+        #[prost(string, tag = "1")]
+        pub sku: String,
+        #[prost(string, tag = "2")]
+        pub product_family: String,
+        #[prost(string, tag = "3")]
+        pub rate_code: String,
+        #[prost(string, tag = "4")]
+        pub description: String,
+        #[prost(string, tag = "5")]
+        pub unit: String,
+        #[prost(map = "string, string", tag = "6")]
+        pub price_per_unit: HashMap<String, String>,
+        #[prost(map = "string, string", tag = "7")]
+        pub attributes: HashMap<String, String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SavingsPlanRatesRequest {
+        #[prost(string, tag = "1")]
+        pub region: String,
+        #[prost(string, tag = "2")]
+        pub instance_type: String,
+        #[prost(string, tag = "3")]
+        pub plan_type: String,
+        #[prost(uint32, tag = "4")]
+        pub limit: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SavingsPlanRatesResponse {
+        #[prost(string, tag = "1")]
+        pub region: String,
+        #[prost(message, repeated, tag = "2")]
+        pub rates: Vec<SavingsPlanRate>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SavingsPlanRate {
+        #[prost(string, tag = "1")]
+        pub sku: String,
+        #[prost(string, tag = "2")]
+        pub plan_type: String,
+        #[prost(string, tag = "3")]
+        pub term: String,
+        #[prost(string, tag = "4")]
+        pub purchase_option: String,
+        #[prost(string, tag = "5")]
+        pub instance_family: String,
+        #[prost(string, tag = "6")]
+        pub discounted_sku: String,
+        #[prost(string, tag = "7")]
+        pub usage_type: String,
+        #[prost(string, tag = "8")]
+        pub operation: String,
+        #[prost(string, tag = "9")]
+        pub unit: String,
+        #[prost(string, tag = "10")]
+        pub price: String,
+        #[prost(string, tag = "11")]
+        pub currency: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct EstimateRequest {
+        #[prost(string, tag = "1")]
+        pub region: String,
+        #[prost(string, tag = "2")]
+        pub instance_type: String,
+        #[prost(uint32, optional, tag = "3")]
+        pub vcpus: Option<u32>,
+        #[prost(string, optional, tag = "4")]
+        pub memory: Option<String>,
+        #[prost(string, tag = "5")]
+        pub operating_system: String,
+        #[prost(string, tag = "6")]
+        pub tenancy: String,
+        #[prost(string, optional, tag = "7")]
+        pub instances: Option<String>,
+        #[prost(string, optional, tag = "8")]
+        pub hours: Option<String>,
+        #[prost(string, tag = "9")]
+        pub term: String,
+        #[prost(string, tag = "10")]
+        pub payment_option: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct EstimateResponse {
+        #[prost(string, tag = "1")]
+        pub instance_type: String,
+        #[prost(string, tag = "2")]
+        pub sku: String,
+        #[prost(string, tag = "3")]
+        pub on_demand_usd_per_hour: String,
+        #[prost(message, repeated, tag = "4")]
+        pub costs: Vec<CostLine>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct CostLine {
+        #[prost(string, tag = "1")]
+        pub purchase_model: String,
+        #[prost(string, tag = "2")]
+        pub term: String,
+        #[prost(string, tag = "3")]
+        pub purchase_option: String,
+        #[prost(string, tag = "4")]
+        pub upfront_usd: String,
+        #[prost(string, tag = "5")]
+        pub monthly_usd: String,
+        #[prost(string, tag = "6")]
+        pub savings_percent: String,
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/pekora.v1.Pricing.rs"));
+}
+
+use proto::pricing_server::{Pricing, PricingServer};
+
+/// Serves the gRPC API on `address` until the process is stopped.
+pub async fn serve(state: AppState, address: SocketAddr) -> anyhow::Result<()> {
+    info!("Serving gRPC on {}", address);
+    tonic::transport::Server::builder()
+        .add_service(PricingServer::new(state))
+        .serve(address)
+        .await?;
+    Ok(())
+}
+
+#[tonic::async_trait]
+impl Pricing for AppState {
+    async fn get_prices(
+        &self,
+        request: Request<proto::PriceRequest>,
+    ) -> Result<Response<proto::PriceResponse>, Status> {
+        let namespace = namespace(&request)?;
+        let tenant = self.tenant(namespace.as_ref())?;
+        let request = request.into_inner();
+        let query = PricesQuery {
+            service: non_empty(request.service).unwrap_or_else(|| "AmazonEC2".to_string()),
+            region: non_empty(request.region),
+            instance_type: non_empty(request.instance_type),
+            limit: limit(request.limit),
+        };
+        let PricesResponse {
+            service,
+            region,
+            version,
+            rows,
+        } = tenant.prices(query).await?;
+        Ok(Response::new(proto::PriceResponse {
+            service,
+            region,
+            version,
+            prices: rows.iter().map(price).collect(),
+        }))
+    }
+
+    async fn get_savings_plan_rates(
+        &self,
+        request: Request<proto::SavingsPlanRatesRequest>,
+    ) -> Result<Response<proto::SavingsPlanRatesResponse>, Status> {
+        let namespace = namespace(&request)?;
+        let tenant = self.tenant(namespace.as_ref())?;
+        let request = request.into_inner();
+        let query = SavingsPlanRatesQuery {
+            region: non_empty(request.region),
+            instance_type: non_empty(request.instance_type),
+            plan_type: non_empty(request.plan_type),
+            limit: limit(request.limit),
+        };
+        let SavingsPlanRatesResponse { region, rows } = tenant.savings_plan_rates(query).await?;
+        Ok(Response::new(proto::SavingsPlanRatesResponse {
+            region,
+            rates: rows.iter().map(savings_plan_rate).collect(),
+        }))
+    }
+
+    async fn estimate(
+        &self,
+        request: Request<proto::EstimateRequest>,
+    ) -> Result<Response<proto::EstimateResponse>, Status> {
+        let namespace = namespace(&request)?;
+        let tenant = self.tenant(namespace.as_ref())?;
+        let query = estimate_query(request.into_inner())?;
+        let estimate = tenant.estimate(query).await?;
+        Ok(Response::new(estimate_response(&estimate)))
+    }
+}
+
+impl From<ApiError> for Status {
+    fn from(e: ApiError) -> Self {
+        match e.status {
+            StatusCode::BAD_REQUEST => Status::invalid_argument(e.message),
+            _ => Status::internal(e.message),
+        }
+    }
+}
+
+/// Namespace of the `x-pekora-namespace` metadata of `request`, if sent
+fn namespace<T>(request: &Request<T>) -> Result<Option<Namespace>, ApiError> {
+    parse_namespace(
+        request
+            .metadata()
+            .get(NAMESPACE_HEADER)
+            .map(|value| value.to_str()),
+    )
+}
+
+/// `None` for the empty string proto3 leaves unset fields as
+fn non_empty(value: String) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+fn limit(limit: u32) -> Option<usize> {
+    if limit == 0 {
+        None
+    } else {
+        Some(limit as usize)
+    }
+}
+
+fn decimal(name: &str, value: Option<String>) -> Result<Option<Decimal>, ApiError> {
+    value
+        .map(|value| {
+            Decimal::from_str(&value)
+                .map_err(|_| ApiError::bad_request(format!("{} {} is not a number", name, value)))
+        })
+        .transpose()
+}
+
+fn estimate_query(request: proto::EstimateRequest) -> Result<EstimateQuery, ApiError> {
+    Ok(EstimateQuery {
+        region: non_empty(request.region),
+        instance_type: non_empty(request.instance_type),
+        vcpus: request.vcpus,
+        memory: decimal("memory", request.memory)?,
+        operating_system: non_empty(request.operating_system),
+        tenancy: non_empty(request.tenancy),
+        instances: decimal("instances", request.instances)?,
+        hours: decimal("hours", request.hours)?,
+        term: non_empty(request.term),
+        payment_option: non_empty(request.payment_option),
+    })
+}
+
+fn price(rate: &OnDemandRate) -> proto::Price {
+    proto::Price {
+        sku: rate.sku.clone(),
+        product_family: rate.product_family.clone(),
+        rate_code: rate.rate_code.clone(),
+        description: rate.description.clone(),
+        unit: rate.unit.clone(),
+        price_per_unit: rate
+            .price_per_unit
+            .iter()
+            .map(|(currency, price)| (currency.clone(), price.raw().to_string()))
+            .collect(),
+        attributes: rate.attributes.as_ref().clone(),
+    }
+}
+
+fn savings_plan_rate(row: &PivotedSavingsPlanTermRate) -> proto::SavingsPlanRate {
+    let attributes = &row.savings_plan_attributes;
+    proto::SavingsPlanRate {
+        sku: row.savings_plan_sku.clone(),
+        plan_type: row.plan_type().as_str().to_string(),
+        term: attributes.purchase_term.as_str().to_string(),
+        purchase_option: attributes.purchase_option.as_str().to_string(),
+        instance_family: attributes.instance_type.clone().unwrap_or_default(),
+        discounted_sku: row.term_rate.discounted_sku.clone(),
+        usage_type: row.term_rate.discounted_usage_type.clone(),
+        operation: row.term_rate.discounted_operation.clone(),
+        unit: row.term_rate.unit.clone(),
+        price: row.term_rate.discounted_rate.price.raw().to_string(),
+        currency: row.term_rate.discounted_rate.currency.as_str().to_string(),
+    }
+}
+
+fn estimate_response(estimate: &CostEstimate) -> proto::EstimateResponse {
+    proto::EstimateResponse {
+        instance_type: estimate.instance_type.clone(),
+        sku: estimate.sku.clone(),
+        on_demand_usd_per_hour: estimate.on_demand_usd_per_hour.normalize().to_string(),
+        costs: estimate
+            .costs
+            .iter()
+            .map(|cost| proto::CostLine {
+                purchase_model: cost.purchase_model.as_str().to_string(),
+                term: cost
+                    .term
+                    .as_ref()
+                    .map_or("", |term| term.as_str())
+                    .to_string(),
+                purchase_option: cost
+                    .purchase_option
+                    .as_ref()
+                    .map_or("", |option| option.as_str())
+                    .to_string(),
+                upfront_usd: cost.upfront_usd.to_string(),
+                monthly_usd: cost.monthly_usd.to_string(),
+                savings_percent: cost.savings_percent.to_string(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{estimate_query, proto};
+    use axum::http::StatusCode;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_estimate_query() {
+        let query = estimate_query(proto::EstimateRequest {
+            instance_type: "m5.large".to_string(),
+            hours: Some("100".to_string()),
+            term: "1yr".to_string(),
+            payment_option: "NoUpfront".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(query.instance_type.as_deref(), Some("m5.large"));
+        assert_eq!(query.region, None);
+        assert_eq!(query.operating_system, None);
+        assert_eq!(query.hours, Some(Decimal::from(100)));
+        assert_eq!(query.term.as_deref(), Some("1yr"));
+
+        let error = estimate_query(proto::EstimateRequest {
+            memory: Some("lots".to_string()),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+    }
+}
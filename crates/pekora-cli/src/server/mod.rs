@@ -0,0 +1,717 @@
+//! HTTP API over the cache layer, so other tools can query prices without embedding the crate.
+//! Every endpoint answers with JSON, errors as `{"error": "..."}`. The OpenAPI document of the
+//! API is served at `/openapi.json` and browsable at `/swagger-ui`, and a GraphQL schema over
+//! the same data at `/graphql`. Built with the `metrics` feature, `/metrics` exports the
+//! Prometheus metrics of the process.
+//!
+//! Requests select one of the configured namespaces with the `X-Pekora-Namespace` header, or
+//! `x-pekora-namespace` metadata over gRPC. Prices are answered with the negotiated discounts of
+//! the namespace applied, and datasets derived for it are kept apart from other namespaces.
+pub mod graphql;
+pub mod grpc;
+
+use aws_config::SdkConfig;
+use axum::extract::{FromRequestParts, Query, State};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use log::info;
+use pekora_aws::api::aws::price_bulk_types::PricingListResponse;
+use pekora_aws::api::aws::types::SavingsPlanType;
+use pekora_aws::cache::Namespace;
+use pekora_aws::dataset::{
+    ComputeSavingsPlan, Dataset, DatasetKind, Ec2InstanceSavingsPlan, Ec2OnDemand,
+};
+use pekora_aws::transform::aws::estimate::{self, CostEstimate, InstanceRequirement, WorkloadSpec};
+use pekora_aws::transform::aws::on_demand::{self, OnDemandRate};
+use pekora_aws::transform::aws::overlay::DiscountOverlay;
+use pekora_aws::transform::aws::recommendation::Commitment;
+use pekora_aws::transform::aws::savings_plan::PivotedSavingsPlanTermRate;
+use pekora_aws::Pekora;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
+
+/// Rows returned when a query sets no `limit`
+const DEFAULT_LIMIT: usize = 1000;
+
+/// Header, and gRPC metadata key, of the namespace a request is answered for
+pub const NAMESPACE_HEADER: &str = "x-pekora-namespace";
+
+/// How long datasets and pricing lists loaded for a request are reused by later requests
+const LOADED_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Clone)]
+pub struct AppState {
+    /// Instance answering requests selecting no namespace
+    pekora: Pekora,
+    /// Instances answering requests of each namespace, with its discount overlay
+    namespaces: Arc<HashMap<Namespace, Pekora>>,
+    /// Region of queries not setting one
+    default_region: String,
+    loaded: Loaded,
+}
+
+impl AppState {
+    /// State answering queries without a region with `default_region`. Reads don't record SKU
+    /// history, which the commands refreshing the cache do.
+    pub fn new(pekora: Pekora, default_region: String) -> Self {
+        Self {
+            pekora: pekora.without_history(),
+            namespaces: Arc::default(),
+            default_region,
+            loaded: Loaded::default(),
+        }
+    }
+
+    /// Lets requests select any of `namespaces`, answering them with its discount overlay.
+    pub fn with_namespaces(
+        mut self,
+        namespaces: impl IntoIterator<Item = (Namespace, DiscountOverlay)>,
+    ) -> Self {
+        let namespaces = namespaces
+            .into_iter()
+            .map(|(namespace, overlay)| {
+                let pekora = self
+                    .pekora
+                    .clone()
+                    .with_namespace(namespace.clone())
+                    .with_overlay(overlay);
+                (namespace, pekora)
+            })
+            .collect();
+        self.namespaces = Arc::new(namespaces);
+        self
+    }
+
+    /// Instance answering requests of `namespace`, the default one for `None`.
+    pub(crate) fn tenant(&self, namespace: Option<&Namespace>) -> Result<Tenant<'_>, ApiError> {
+        let pekora = match namespace {
+            Some(namespace) => self
+                .namespaces
+                .get(namespace)
+                .ok_or_else(|| ApiError::bad_request(format!("Unknown namespace {}", namespace)))?,
+            None => &self.pekora,
+        };
+        Ok(Tenant {
+            pekora,
+            default_region: &self.default_region,
+            loaded: &self.loaded,
+        })
+    }
+}
+
+/// What one request is answered with, selected by its namespace
+pub(crate) struct Tenant<'a> {
+    pub(crate) pekora: &'a Pekora,
+    pub(crate) default_region: &'a str,
+    loaded: &'a Loaded,
+}
+
+impl Tenant<'_> {
+    /// Dataset `T` of `key`, reused across requests of the namespace for `LOADED_TTL`.
+    pub(crate) async fn dataset<T: DatasetKind>(
+        &self,
+        key: T::Key,
+    ) -> anyhow::Result<Arc<Dataset<T>>> {
+        let namespace = self.pekora.namespace().map_or("", Namespace::as_str);
+        let name = format!("{}/{}/{}", namespace, T::NAME, key);
+        self.loaded
+            .get_or_load(name, self.pekora.dataset::<T>(key))
+            .await
+    }
+
+    /// Current pricing list of a service, shared by every namespace and reused across requests
+    /// for `LOADED_TTL`.
+    pub(crate) async fn pricing(
+        &self,
+        service_code: &str,
+        region: &str,
+    ) -> anyhow::Result<Arc<PricingListResponse>> {
+        let name = format!("pricing/{}/{}", service_code, region);
+        self.loaded
+            .get_or_load(name, self.pekora.fetch_pricing(service_code, region))
+            .await
+    }
+}
+
+/// A loaded value and when it was loaded
+type LoadedValue = (Instant, Arc<dyn Any + Send + Sync>);
+
+/// Values loaded by earlier requests, by name. Values older than `LOADED_TTL` are loaded again,
+/// which only downloads offers that changed upstream.
+#[derive(Clone, Default)]
+struct Loaded(Arc<Mutex<HashMap<String, LoadedValue>>>);
+
+impl Loaded {
+    async fn get_or_load<V: Send + Sync + 'static>(
+        &self,
+        name: String,
+        load: impl Future<Output = anyhow::Result<V>>,
+    ) -> anyhow::Result<Arc<V>> {
+        let cached = self.0.lock().unwrap().get(&name).cloned();
+        if let Some((loaded_at, value)) = cached {
+            if loaded_at.elapsed() < LOADED_TTL {
+                if let Ok(value) = value.downcast::<V>() {
+                    return Ok(value);
+                }
+            }
+        }
+        let value = Arc::new(load.await?);
+        self.0
+            .lock()
+            .unwrap()
+            .insert(name, (Instant::now(), value.clone()));
+        Ok(value)
+    }
+}
+
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: e.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            error: self.message,
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+/// Namespace of the `X-Pekora-Namespace` header, if sent
+pub struct SelectedNamespace(pub Option<Namespace>);
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for SelectedNamespace {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let value = parts
+            .headers
+            .get(NAMESPACE_HEADER)
+            .map(|value| value.to_str());
+        Ok(Self(parse_namespace(value)?))
+    }
+}
+
+/// Namespace of the value of a namespace header, if sent
+pub(crate) fn parse_namespace<E>(
+    value: Option<Result<&str, E>>,
+) -> Result<Option<Namespace>, ApiError> {
+    value
+        .map(|value| {
+            let value = value
+                .map_err(|_| ApiError::bad_request(format!("{} is not ASCII", NAMESPACE_HEADER)))?;
+            Namespace::new(value).map_err(|e| ApiError::bad_request(e.to_string()))
+        })
+        .transpose()
+}
+
+/// Body of error responses
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub error: String,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "pekora", description = "AWS pricing from the pekora cache"),
+    paths(services, prices, savings_plan_rates, estimate),
+    components(schemas(ErrorBody, PricesResponse, SavingsPlanRatesResponse))
+)]
+pub struct ApiDoc;
+
+/// Routes of the API answering with `state`. Instance specs are described with the credentials
+/// of `sdk_config`.
+pub fn router(state: AppState, sdk_config: SdkConfig) -> Router {
+    Router::new()
+        .route("/v1/services", get(services))
+        .route("/v1/prices", get(prices))
+        .route("/v1/savings-plans/rates", get(savings_plan_rates))
+        .route("/v1/estimate", get(estimate))
+        .merge(SwaggerUi::new("/swagger-ui").url("/openapi.json", ApiDoc::openapi()))
+        .with_state(state.clone())
+        .merge(graphql::router(graphql::schema(state, sdk_config)))
+        .merge(metrics_router())
+}
+
+/// `/metrics` in the Prometheus text format.
+#[cfg(feature = "metrics")]
+pub fn metrics_router() -> Router {
+    Router::new().route("/metrics", get(metrics))
+}
+
+/// Empty without the `metrics` feature.
+#[cfg(not(feature = "metrics"))]
+pub fn metrics_router() -> Router {
+    Router::new()
+}
+
+#[cfg(feature = "metrics")]
+async fn metrics() -> impl IntoResponse {
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        pekora_aws::metrics::global().snapshot().to_prometheus(),
+    )
+}
+
+/// Serves the API on `address` until the process is stopped.
+pub async fn serve(
+    state: AppState,
+    sdk_config: SdkConfig,
+    address: SocketAddr,
+) -> anyhow::Result<()> {
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    info!("Serving on http://{}", listener.local_addr()?);
+    axum::serve(listener, router(state, sdk_config)).await?;
+    Ok(())
+}
+
+/// Index of the services AWS publishes offers of
+#[utoipa::path(
+    get,
+    path = "/v1/services",
+    responses(
+        (status = 200, body = serde_json::Value),
+        (status = 500, body = ErrorBody),
+    )
+)]
+async fn services(State(state): State<AppState>) -> Result<Response, ApiError> {
+    let services = state
+        .pekora
+        .cacheable_builder()
+        .build(state.pekora.clients().service_index())
+        .load(&())
+        .await
+        .map_err(anyhow::Error::from)?
+        .result;
+    Ok(Json(services).into_response())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PricesQuery {
+    /// Service code, `AmazonEC2` by default
+    #[serde(default = "default_service")]
+    pub service: String,
+    pub region: Option<String>,
+    pub instance_type: Option<String>,
+    pub limit: Option<usize>,
+}
+
+fn default_service() -> String {
+    "AmazonEC2".to_string()
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PricesResponse {
+    pub service: String,
+    pub region: String,
+    /// Offer version the rows were read from
+    pub version: String,
+    #[schema(value_type = Vec<Object>)]
+    pub rows: Vec<OnDemandRate>,
+}
+
+/// On-demand rates of a service, from the EC2 dataset for `AmazonEC2` and the pricing list of
+/// any other service.
+#[utoipa::path(
+    get,
+    path = "/v1/prices",
+    params(
+        PricesQuery,
+        ("x-pekora-namespace" = Option<String>, Header, description = "Namespace to answer for"),
+    ),
+    responses(
+        (status = 200, body = PricesResponse),
+        (status = 500, body = ErrorBody),
+    )
+)]
+async fn prices(
+    State(state): State<AppState>,
+    SelectedNamespace(namespace): SelectedNamespace,
+    Query(query): Query<PricesQuery>,
+) -> ApiResult<PricesResponse> {
+    let tenant = state.tenant(namespace.as_ref())?;
+    Ok(Json(tenant.prices(query).await?))
+}
+
+/// Rates of Compute and EC2 Instance Savings Plans
+#[utoipa::path(
+    get,
+    path = "/v1/savings-plans/rates",
+    params(
+        SavingsPlanRatesQuery,
+        ("x-pekora-namespace" = Option<String>, Header, description = "Namespace to answer for"),
+    ),
+    responses(
+        (status = 200, body = SavingsPlanRatesResponse),
+        (status = 400, body = ErrorBody),
+        (status = 500, body = ErrorBody),
+    )
+)]
+async fn savings_plan_rates(
+    State(state): State<AppState>,
+    SelectedNamespace(namespace): SelectedNamespace,
+    Query(query): Query<SavingsPlanRatesQuery>,
+) -> ApiResult<SavingsPlanRatesResponse> {
+    let tenant = state.tenant(namespace.as_ref())?;
+    Ok(Json(tenant.savings_plan_rates(query).await?))
+}
+
+/// Monthly cost of an EC2 workload on-demand, reserved and under savings plans
+#[utoipa::path(
+    get,
+    path = "/v1/estimate",
+    params(
+        EstimateQuery,
+        ("x-pekora-namespace" = Option<String>, Header, description = "Namespace to answer for"),
+    ),
+    responses(
+        (status = 200, body = serde_json::Value),
+        (status = 400, body = ErrorBody),
+        (status = 500, body = ErrorBody),
+    )
+)]
+async fn estimate(
+    State(state): State<AppState>,
+    SelectedNamespace(namespace): SelectedNamespace,
+    Query(query): Query<EstimateQuery>,
+) -> ApiResult<CostEstimate> {
+    let tenant = state.tenant(namespace.as_ref())?;
+    Ok(Json(tenant.estimate(query).await?))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SavingsPlanRatesQuery {
+    pub region: Option<String>,
+    /// Rates discounting this instance type only
+    pub instance_type: Option<String>,
+    /// `Compute` or `EC2Instance`, both if not set
+    pub plan_type: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SavingsPlanRatesResponse {
+    pub region: String,
+    #[schema(value_type = Vec<Object>)]
+    pub rows: Vec<PivotedSavingsPlanTermRate>,
+}
+
+/// Whether `row` discounts usage of `instance_type`, e.g. `BoxUsage:m5.large`.
+fn discounts(row: &PivotedSavingsPlanTermRate, instance_type: &str) -> bool {
+    row.term_rate
+        .discounted_usage_type
+        .rsplit_once(':')
+        .is_some_and(|(_, usage_instance_type)| usage_instance_type == instance_type)
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EstimateQuery {
+    pub region: Option<String>,
+    pub instance_type: Option<String>,
+    /// Minimum vCPUs, picking the cheapest instance type with enough vCPUs and memory
+    pub vcpus: Option<u32>,
+    /// Minimum memory in GiB
+    #[param(value_type = Option<String>)]
+    pub memory: Option<Decimal>,
+    pub operating_system: Option<String>,
+    pub tenancy: Option<String>,
+    #[param(value_type = Option<String>)]
+    pub instances: Option<Decimal>,
+    /// Hours each instance runs per month
+    #[param(value_type = Option<String>)]
+    pub hours: Option<Decimal>,
+    /// `1yr` or `3yr`, set together with `payment_option`
+    pub term: Option<String>,
+    pub payment_option: Option<String>,
+}
+
+impl EstimateQuery {
+    /// Workload of the query, in `default_region` unless it sets a region.
+    pub fn workload(self, default_region: String) -> Result<WorkloadSpec, ApiError> {
+        let instance = match (self.instance_type, self.vcpus, self.memory) {
+            (Some(instance_type), None, None) => InstanceRequirement::InstanceType(instance_type),
+            (None, Some(vcpus), Some(memory_gib)) => {
+                InstanceRequirement::Size { vcpus, memory_gib }
+            }
+            _ => {
+                return Err(ApiError::bad_request(
+                    "Expected either instance_type or both vcpus and memory",
+                ))
+            }
+        };
+        let mut workload = WorkloadSpec::new(instance, self.region.unwrap_or(default_region));
+        if let Some(operating_system) = self.operating_system {
+            workload.operating_system = operating_system;
+        }
+        if let Some(tenancy) = self.tenancy {
+            workload.tenancy = tenancy;
+        }
+        if let Some(instances) = self.instances {
+            workload.instances = instances;
+        }
+        if let Some(hours) = self.hours {
+            workload.hours_per_month = hours;
+        }
+        workload.commitment = match (self.term, self.payment_option) {
+            (Some(term), Some(payment_option)) => Some(
+                Commitment::parse(&term, &payment_option)
+                    .map_err(|e| ApiError::bad_request(e.to_string()))?,
+            ),
+            (None, None) => None,
+            _ => {
+                return Err(ApiError::bad_request(
+                    "Expected term and payment_option together",
+                ))
+            }
+        };
+        Ok(workload)
+    }
+}
+
+/// Queries shared by the REST and gRPC APIs
+impl Tenant<'_> {
+    /// On-demand rates of a service, from the EC2 dataset for `AmazonEC2` and the pricing list
+    /// of any other service.
+    pub(crate) async fn prices(&self, query: PricesQuery) -> Result<PricesResponse, ApiError> {
+        let region = query
+            .region
+            .unwrap_or_else(|| self.default_region.to_string());
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+        let matches = |rate: &&OnDemandRate| {
+            query.instance_type.as_ref().is_none_or(|instance_type| {
+                rate.attributes.get("instanceType") == Some(instance_type)
+            })
+        };
+        let (version, mut rows): (String, Vec<OnDemandRate>) = if query.service == "AmazonEC2" {
+            let dataset = self.dataset::<Ec2OnDemand>(region.clone()).await?;
+            let rows = dataset.rows().iter().filter(matches).take(limit).cloned();
+            (dataset.metadata().version.clone(), rows.collect())
+        } else {
+            let name = format!("on_demand/{}/{}", query.service, region);
+            let pivoted = self
+                .loaded
+                .get_or_load(name, async {
+                    let response = self.pekora.fetch_pricing(&query.service, &region).await?;
+                    Ok((response.version.clone(), on_demand::pivot(response)))
+                })
+                .await?;
+            let (version, rows) = pivoted.as_ref();
+            let rows = rows.iter().filter(matches).take(limit).cloned();
+            (version.clone(), rows.collect())
+        };
+        if let Some(overlay) = self.pekora.overlay() {
+            for row in &mut rows {
+                overlay.on_demand(&query.service, row);
+            }
+        }
+        Ok(PricesResponse {
+            service: query.service,
+            region,
+            version,
+            rows,
+        })
+    }
+
+    pub(crate) async fn savings_plan_rates(
+        &self,
+        query: SavingsPlanRatesQuery,
+    ) -> Result<SavingsPlanRatesResponse, ApiError> {
+        let region = query
+            .region
+            .unwrap_or_else(|| self.default_region.to_string());
+        let plan_type = match &query.plan_type {
+            Some(plan_type) => match serde_json::from_value(plan_type.as_str().into()) {
+                Ok(SavingsPlanType::Unknown(_)) | Err(_) => {
+                    return Err(ApiError::bad_request(format!(
+                        "Unknown plan type {}, expected Compute or EC2Instance",
+                        plan_type
+                    )))
+                }
+                Ok(plan_type) => Some(plan_type),
+            },
+            None => None,
+        };
+
+        let matches = |row: &&PivotedSavingsPlanTermRate| {
+            query
+                .instance_type
+                .as_ref()
+                .is_none_or(|instance_type| discounts(row, instance_type))
+        };
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+        let mut rows = Vec::new();
+        if plan_type
+            .as_ref()
+            .is_none_or(|t| *t == SavingsPlanType::Compute)
+        {
+            let compute = self.dataset::<ComputeSavingsPlan>(region.clone()).await?;
+            rows.extend(compute.rows().iter().filter(matches).take(limit).cloned());
+        }
+        if plan_type
+            .as_ref()
+            .is_none_or(|t| *t == SavingsPlanType::Ec2Instance)
+        {
+            let ec2_instance = self
+                .dataset::<Ec2InstanceSavingsPlan>(region.clone())
+                .await?;
+            let remaining = limit - rows.len();
+            rows.extend(
+                ec2_instance
+                    .rows()
+                    .iter()
+                    .filter(matches)
+                    .take(remaining)
+                    .cloned(),
+            );
+        }
+        if let Some(overlay) = self.pekora.overlay() {
+            for row in &mut rows {
+                overlay.savings_plan(row);
+            }
+        }
+        Ok(SavingsPlanRatesResponse { region, rows })
+    }
+
+    pub(crate) async fn estimate(&self, query: EstimateQuery) -> Result<CostEstimate, ApiError> {
+        let workload = query.workload(self.default_region.to_string())?;
+        let response = self.pricing("AmazonEC2", &workload.region).await?;
+        let compute = self
+            .dataset::<ComputeSavingsPlan>(workload.region.clone())
+            .await?;
+        let ec2_instance = self
+            .dataset::<Ec2InstanceSavingsPlan>(workload.region.clone())
+            .await?;
+        let mut estimate = estimate::estimate(
+            &workload,
+            &response,
+            compute.rows().iter().chain(ec2_instance.rows()),
+        )?;
+        if let Some(overlay) = self.pekora.overlay() {
+            overlay.estimate(&mut estimate);
+        }
+        Ok(estimate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_namespace, ApiDoc, EstimateQuery};
+    use pekora_aws::transform::aws::estimate::InstanceRequirement;
+    use rust_decimal::Decimal;
+    use utoipa::OpenApi;
+
+    fn query(params: &str) -> EstimateQuery {
+        serde_json::from_str(params).unwrap()
+    }
+
+    #[test]
+    fn test_estimate_workload() {
+        let workload = query(
+            r#"{"instance_type": "m5.large", "hours": "100", "term": "1yr",
+                "payment_option": "NoUpfront"}"#,
+        )
+        .workload("us-east-1".to_string())
+        .unwrap();
+        assert_eq!(
+            workload.instance,
+            InstanceRequirement::InstanceType("m5.large".to_string())
+        );
+        assert_eq!(workload.region, "us-east-1");
+        assert_eq!(workload.operating_system, "Linux");
+        assert_eq!(workload.hours_per_month, Decimal::from(100));
+        assert!(workload.commitment.is_some());
+
+        for params in [
+            r#"{"instance_type": "m5.large", "vcpus": 2, "memory": "8"}"#,
+            r#"{"vcpus": 2}"#,
+            r#"{"instance_type": "m5.large", "term": "1yr"}"#,
+            r#"{"instance_type": "m5.large", "term": "2yr", "payment_option": "NoUpfront"}"#,
+        ] {
+            let error = query(params).workload("us-east-1".to_string()).unwrap_err();
+            assert_eq!(error.status, axum::http::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[test]
+    fn test_parse_namespace() {
+        let namespace = parse_namespace::<()>(Some(Ok("team-a"))).unwrap();
+        assert_eq!(namespace.unwrap().as_str(), "team-a");
+        assert!(parse_namespace::<()>(None).unwrap().is_none());
+        for value in [Ok("../team-b"), Err(())] {
+            let error = parse_namespace(Some(value)).unwrap_err();
+            assert_eq!(error.status, axum::http::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[test]
+    fn test_openapi() {
+        let document = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let paths = document["paths"].as_object().unwrap();
+        assert_eq!(
+            paths.keys().collect::<Vec<_>>(),
+            [
+                "/v1/estimate",
+                "/v1/prices",
+                "/v1/savings-plans/rates",
+                "/v1/services"
+            ]
+        );
+        let parameters = document["paths"]["/v1/prices"]["get"]["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|parameter| parameter["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parameters,
+            [
+                "service",
+                "region",
+                "instance_type",
+                "limit",
+                "x-pekora-namespace"
+            ]
+        );
+        assert!(document["components"]["schemas"]["PricesResponse"].is_object());
+    }
+}
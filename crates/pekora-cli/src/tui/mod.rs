@@ -0,0 +1,164 @@
+use chrono::{DateTime, Utc};
+/// Terminal dashboard showing cache and download status, see `pekora top`
+use pekora_aws::status::{format_bytes, CacheStatus, ErrorLog, ErrorLogEntry};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::widgets::{Block, List, ListItem, Row, Table};
+use ratatui::{DefaultTerminal, Frame};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const RECENT_ERROR_COUNT: usize = 20;
+
+/// Runs the dashboard until `q` or `Esc` is pressed, rescanning every `refresh_interval`.
+pub fn run(cache_directory: PathBuf, refresh_interval: Duration) -> anyhow::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = run_loop(&mut terminal, &cache_directory, refresh_interval);
+    ratatui::restore();
+    result
+}
+
+fn run_loop(
+    terminal: &mut DefaultTerminal,
+    cache_directory: &Path,
+    refresh_interval: Duration,
+) -> anyhow::Result<()> {
+    let error_log = ErrorLog::new(cache_directory);
+    loop {
+        let status = CacheStatus::scan(cache_directory)?;
+        let errors = error_log.recent(RECENT_ERROR_COUNT)?;
+        terminal.draw(|frame| draw(frame, cache_directory, &status, &errors))?;
+
+        if event::poll(refresh_interval)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press
+                    && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+                {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, cache_directory: &Path, status: &CacheStatus, errors: &[ErrorLogEntry]) {
+    let now = Utc::now();
+    let [datasets_area, categories_area, downloads_area, errors_area] = Layout::vertical([
+        Constraint::Percentage(35),
+        Constraint::Percentage(20),
+        Constraint::Percentage(20),
+        Constraint::Percentage(25),
+    ])
+    .areas(frame.area());
+
+    let header_style = Style::new().bold();
+    let title = format!(" pekora top - {} (q to quit) ", cache_directory.display());
+
+    let dataset_rows = status.datasets.iter().map(|dataset| {
+        Row::new(vec![
+            dataset.category.clone(),
+            dataset.content_key.clone(),
+            format_age(now, dataset.last_updated),
+        ])
+    });
+    frame.render_widget(
+        Table::new(
+            dataset_rows,
+            [
+                Constraint::Percentage(35),
+                Constraint::Percentage(45),
+                Constraint::Percentage(20),
+            ],
+        )
+        .header(Row::new(vec!["Category", "Dataset", "Updated"]).style(header_style))
+        .block(Block::bordered().title(title)),
+        datasets_area,
+    );
+
+    let category_rows = status.categories.iter().map(|category| {
+        Row::new(vec![
+            category.category.clone(),
+            category.entries.to_string(),
+            format_bytes(category.bytes),
+        ])
+    });
+    frame.render_widget(
+        Table::new(
+            category_rows,
+            [
+                Constraint::Percentage(60),
+                Constraint::Percentage(15),
+                Constraint::Percentage(25),
+            ],
+        )
+        .header(Row::new(vec!["Category", "Entries", "Size"]).style(header_style))
+        .block(Block::bordered().title(" Cache ")),
+        categories_area,
+    );
+
+    let download_rows = status.downloads.iter().map(|download| {
+        let progress = match (download.progress(), download.total_bytes) {
+            (Some(progress), Some(total)) => format!(
+                "{} {:>5.1}% of {}",
+                progress_bar(progress, 20),
+                progress * 100.0,
+                format_bytes(total)
+            ),
+            _ => format_bytes(download.downloaded_bytes),
+        };
+        Row::new(vec![
+            format!("{}/{}", download.category, download.name),
+            progress,
+            format_age(now, download.last_updated),
+        ])
+    });
+    frame.render_widget(
+        Table::new(
+            download_rows,
+            [
+                Constraint::Percentage(45),
+                Constraint::Percentage(40),
+                Constraint::Percentage(15),
+            ],
+        )
+        .header(Row::new(vec!["Download", "Progress", "Active"]).style(header_style))
+        .block(Block::bordered().title(" Downloads ")),
+        downloads_area,
+    );
+
+    let error_items = errors.iter().map(|error| {
+        ListItem::new(format!(
+            "{} [{}] {}",
+            format_age(now, error.timestamp),
+            error.source,
+            error.message
+        ))
+    });
+    frame.render_widget(
+        List::new(error_items).block(Block::bordered().title(" Recent errors ")),
+        errors_area,
+    );
+}
+
+fn progress_bar(progress: f64, width: usize) -> String {
+    let filled = (progress * width as f64).round() as usize;
+    format!(
+        "[{}{}]",
+        "#".repeat(filled),
+        "-".repeat(width - filled.min(width))
+    )
+}
+
+fn format_age(now: DateTime<Utc>, time: DateTime<Utc>) -> String {
+    let age = now.signed_duration_since(time);
+    if age.num_days() > 0 {
+        format!("{}d {}h ago", age.num_days(), age.num_hours() % 24)
+    } else if age.num_hours() > 0 {
+        format!("{}h {}m ago", age.num_hours(), age.num_minutes() % 60)
+    } else if age.num_minutes() > 0 {
+        format!("{}m ago", age.num_minutes())
+    } else {
+        format!("{}s ago", age.num_seconds().max(0))
+    }
+}
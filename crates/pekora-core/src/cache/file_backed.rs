@@ -0,0 +1,431 @@
+use crate::cache::{CacheKey, CacheLoadResult, CacheableArc, ConditionalLoad};
+use crate::metrics;
+use chrono::{TimeZone, Utc};
+use log::{debug, warn};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::fs;
+
+pub const DEFAULT_CACHE_DIRECTORY: &str = "cached";
+
+pub struct FileBackedCacheableBuilder {
+    cache_directory: Arc<PathBuf>,
+    cache_max_age: chrono::Duration,
+}
+
+impl FileBackedCacheableBuilder {
+    pub fn new(cache_directory: Option<String>, cache_max_age: Option<chrono::Duration>) -> Self {
+        let cache_directory = cache_directory.unwrap_or(DEFAULT_CACHE_DIRECTORY.to_string());
+        let cache_max_age = cache_max_age.unwrap_or(chrono::Duration::try_days(7).unwrap());
+
+        Self {
+            cache_directory: Arc::new(PathBuf::from(Path::new(&cache_directory))),
+            cache_max_age,
+        }
+    }
+
+    pub fn build<I: Send + Sync, O: Serialize + DeserializeOwned + Send + Sync, E: Error>(
+        self,
+        cacheable: CacheableArc<I, O, E>,
+    ) -> FileBackedCacheable<I, O, E> {
+        FileBackedCacheable::new(
+            cacheable,
+            self.cache_max_age,
+            self.cache_directory
+                .clone()
+                .to_str()
+                .unwrap_or("")
+                .to_string(),
+        )
+    }
+}
+
+pub struct FileBackedCacheable<I: Send + Sync, O: Serialize + DeserializeOwned + Send + Sync, E> {
+    cache_directory: Arc<PathBuf>,
+    cacheable: CacheableArc<I, O, E>,
+    cache_max_age: chrono::Duration,
+}
+
+impl<I: Send + Sync, O: Serialize + DeserializeOwned + Send + Sync, E: Error>
+    FileBackedCacheable<I, O, E>
+{
+    pub fn new(
+        cacheable: CacheableArc<I, O, E>,
+        cache_max_age: chrono::Duration,
+        root_path: String,
+    ) -> Self {
+        Self {
+            cacheable,
+            cache_max_age,
+            cache_directory: Arc::new(PathBuf::from(Path::new(&root_path))),
+        }
+    }
+
+    pub async fn load(&self, input: &I) -> Result<CacheLoadResult<O>, CacheError<E>> {
+        let content_key = self.cacheable.content_key(input);
+        let cached_key = self.find_latest_cache_key(&content_key).await?;
+        debug!("Latest cache key: {:?}", cached_key);
+
+        let loaded = match &cached_key {
+            // Entries without a validator can't be revalidated, so they are used until they expire
+            Some(cache_key) if cache_key.content_hash.is_none() => ConditionalLoad::NotModified,
+            _ => self.fetch(input, cached_key.as_ref()).await?,
+        };
+        let (result, cache_key) = match (loaded, cached_key) {
+            (ConditionalLoad::Modified { result, cache_key }, _) => (result, cache_key),
+            (ConditionalLoad::NotModified, Some(cache_key)) => {
+                if let Some(result) = self.read_cache(&cache_key)? {
+                    debug!("Cache hit: {:?}", cache_key);
+                    metrics::global().record_cache_hit();
+                    return Ok(CacheLoadResult {
+                        result,
+                        cache_key,
+                        cache_hit: true,
+                    });
+                }
+                self.load_unconditional(input).await?
+            }
+            (ConditionalLoad::NotModified, None) => self.load_unconditional(input).await?,
+        };
+
+        metrics::global().record_cache_miss();
+        if cache_key.content_key.is_none() && cache_key.content_hash.is_none() {
+            // Nothing tells this entry apart from others of the category, so it isn't cached
+            warn!(
+                "Not caching {}: no content key or validator",
+                self.cacheable.category_key()
+            );
+        } else {
+            debug!("Cache miss, writing cache: {:?}", cache_key);
+            self.write_cache(&cache_key, &result).await?;
+        }
+        Ok(CacheLoadResult {
+            result,
+            cache_key,
+            cache_hit: false,
+        })
+    }
+
+    /// Key of the newest fresh cache entry of `input`, without contacting upstream.
+    pub async fn latest_cache_key(&self, input: &I) -> Result<Option<CacheKey>, CacheError<E>> {
+        self.find_latest_cache_key(&self.cacheable.content_key(input))
+            .await
+    }
+
+    async fn load_unconditional(&self, input: &I) -> Result<(O, CacheKey), CacheError<E>> {
+        match self.fetch(input, None).await? {
+            ConditionalLoad::Modified { result, cache_key } => Ok((result, cache_key)),
+            ConditionalLoad::NotModified => Err(CacheError::UnexpectedNotModified),
+        }
+    }
+
+    /// Loads `input` upstream unless unchanged since `cached_key`, recording how long it took.
+    async fn fetch(
+        &self,
+        input: &I,
+        cached_key: Option<&CacheKey>,
+    ) -> Result<ConditionalLoad<O>, CacheError<E>> {
+        let started = Instant::now();
+        let loaded = self.cacheable.load_conditional(input, cached_key).await;
+        metrics::global().record_fetch(started.elapsed());
+        loaded.map_err(CacheError::FetchFailed)
+    }
+
+    /// Finds the most recently written, non-expired cache entry for `content_key`.
+    async fn find_latest_cache_key(
+        &self,
+        content_key: &Option<String>,
+    ) -> Result<Option<CacheKey>, CacheError<E>> {
+        let category_directory = self.cache_directory.join(self.cacheable.category_key());
+        let prefix = format!("{}_", content_key.as_deref().unwrap_or(""));
+        let mut entries = match fs::read_dir(&category_directory).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(CacheError::IO(e)),
+        };
+
+        let mut latest: Option<(SystemTime, CacheKey)> = None;
+        while let Some(entry) = entries.next_entry().await.map_err(CacheError::IO)? {
+            let filename = entry.file_name().to_string_lossy().to_string();
+            let content_hash = match filename
+                .strip_prefix(&prefix)
+                .and_then(|f| f.strip_suffix(".json"))
+            {
+                Some(hash)
+                    if !hash.contains('_') && (content_key.is_some() || !hash.is_empty()) =>
+                {
+                    hash.to_string()
+                }
+                _ => continue,
+            };
+            let modified = entry
+                .metadata()
+                .await
+                .and_then(|metadata| metadata.modified())
+                .map_err(CacheError::IO)?;
+            if !self.is_fresh(modified) {
+                debug!("Cache expired: {}", filename);
+                continue;
+            }
+            if latest.as_ref().is_none_or(|(t, _)| modified > *t) {
+                latest = Some((
+                    modified,
+                    CacheKey {
+                        content_key: content_key.clone(),
+                        content_hash: if content_hash.is_empty() {
+                            None
+                        } else {
+                            Some(content_hash)
+                        },
+                    },
+                ));
+            }
+        }
+        Ok(latest.map(|(_, cache_key)| cache_key))
+    }
+
+    fn is_fresh(&self, modified: SystemTime) -> bool {
+        let now = chrono::Utc::now();
+        let modified_epoch = modified.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let modified = Utc.timestamp_opt(modified_epoch as i64, 0).unwrap();
+        now.signed_duration_since(modified) <= self.cache_max_age
+    }
+
+    fn read_cache(&self, cache_key: &CacheKey) -> Result<Option<O>, CacheError<E>> {
+        let cache_path = self
+            .cache_directory
+            .join(self.build_cache_filename(cache_key)?);
+        let file = match File::open(cache_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(CacheError::IO(e)),
+        };
+
+        let reader = BufReader::new(file);
+        let result: serde_json::Result<O> = serde_json::from_reader(reader);
+        match result {
+            Ok(result) => Ok(Some(result)),
+            Err(e) => {
+                warn!(
+                    "Cache deserialization failed, continuing as cache miss: {:?}",
+                    e
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    async fn write_cache(&self, cache_key: &CacheKey, result: &O) -> Result<(), CacheError<E>> {
+        let cache_path = self
+            .cache_directory
+            .join(self.build_cache_filename(cache_key)?);
+        if let Some(folder) = cache_path.parent() {
+            fs::create_dir_all(folder).await.map_err(CacheError::IO)?;
+        }
+
+        // TODO: Use tokio
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(cache_path)
+            .map_err(CacheError::IO)?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, result).map_err(CacheError::Serde)?;
+        writer.flush().map_err(CacheError::IO)?;
+        let written = writer.get_ref().metadata().map_err(CacheError::IO)?.len();
+        metrics::global().record_cache_bytes_written(written);
+        Ok(())
+    }
+
+    fn build_cache_filename(&self, cache_key: &CacheKey) -> Result<String, CacheError<E>> {
+        let filename = match &cache_key.content_key {
+            None => match cache_key.content_hash {
+                Some(ref hash) => format!("_{}", hash),
+                None => return Err(CacheError::MissingCacheKey),
+            },
+            Some(content_key) => match cache_key.content_hash {
+                Some(ref hash) => format!("{}_{}", content_key, hash),
+                None => format!("{}_", content_key),
+            },
+        };
+        Ok(format!(
+            "{}/{}.json",
+            self.cacheable.category_key(),
+            filename
+        ))
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum CacheError<E: Error> {
+    #[error("Cache fetch failed: {0}")]
+    FetchFailed(E),
+    #[error("Cache IO failed: {0}")]
+    Serde(serde_json::Error),
+    #[error("Cache IO failed: {0}")]
+    IO(std::io::Error),
+    #[error("Cacheable reported not modified without a cached entry")]
+    UnexpectedNotModified,
+    #[error("Cache key has neither a content key nor a content hash")]
+    MissingCacheKey,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::{CacheKey, Cacheable, ConditionalLoad};
+    use serde::{Deserialize, Serialize};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct TestObject {
+        a: String,
+        b: i32,
+    }
+
+    struct TestCacheable;
+
+    #[async_trait::async_trait]
+    impl Cacheable<String, TestObject, super::CacheError<std::convert::Infallible>> for TestCacheable {
+        async fn get_cache_key(
+            &self,
+            input: &String,
+        ) -> Result<CacheKey, super::CacheError<std::convert::Infallible>> {
+            Ok(CacheKey {
+                content_key: Some(format!("{}-key", input.clone())),
+                content_hash: None,
+            })
+        }
+
+        async fn load(
+            &self,
+            input: &String,
+        ) -> Result<TestObject, super::CacheError<std::convert::Infallible>> {
+            Ok(TestObject {
+                a: input.clone(),
+                b: 42,
+            })
+        }
+
+        fn content_key(&self, input: &String) -> Option<String> {
+            Some(format!("{}-key", input.clone()))
+        }
+
+        fn category_key(&self) -> String {
+            "test".to_string()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_backed_cacheable() {
+        let cache_key = format!(
+            "test-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_micros()
+        );
+
+        let cacheable = super::FileBackedCacheable::new(
+            Arc::new(Box::new(TestCacheable)),
+            chrono::Duration::try_days(1).unwrap(),
+            "test_cache".to_string(),
+        );
+        let result = cacheable.load(&cache_key.to_string()).await.unwrap();
+        assert_eq!(result.result.a, cache_key);
+        assert_eq!(result.result.b, 42);
+        assert_eq!(
+            result.cache_key.content_key,
+            Some(format!("{}-key", cache_key).to_string())
+        );
+        assert!(!result.cache_hit);
+
+        let result = cacheable.load(&cache_key.to_string()).await.unwrap();
+        assert_eq!(result.result.a, cache_key);
+        assert_eq!(result.result.b, 42);
+        assert_eq!(
+            result.cache_key.content_key,
+            Some(format!("{}-key", cache_key).to_string())
+        );
+        assert!(result.cache_hit);
+    }
+
+    /// Answers every load in full without a validator, like a server without ETags.
+    struct ValidatorlessCacheable(Arc<AtomicUsize>);
+
+    #[async_trait::async_trait]
+    impl Cacheable<String, TestObject, super::CacheError<std::convert::Infallible>>
+        for ValidatorlessCacheable
+    {
+        async fn get_cache_key(
+            &self,
+            input: &String,
+        ) -> Result<CacheKey, super::CacheError<std::convert::Infallible>> {
+            Ok(CacheKey {
+                content_key: self.content_key(input),
+                content_hash: None,
+            })
+        }
+
+        async fn load(
+            &self,
+            input: &String,
+        ) -> Result<TestObject, super::CacheError<std::convert::Infallible>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(TestObject {
+                a: input.clone(),
+                b: 42,
+            })
+        }
+
+        fn content_key(&self, input: &String) -> Option<String> {
+            Some(input.clone())
+        }
+
+        fn category_key(&self) -> String {
+            "test".to_string()
+        }
+
+        async fn load_conditional(
+            &self,
+            input: &String,
+            _cached_key: Option<&CacheKey>,
+        ) -> Result<ConditionalLoad<TestObject>, super::CacheError<std::convert::Infallible>>
+        {
+            Ok(ConditionalLoad::Modified {
+                result: self.load(input).await?,
+                cache_key: self.get_cache_key(input).await?,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validatorless_cache_hit() {
+        let input = format!(
+            "validatorless-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_micros()
+        );
+        let loads = Arc::new(AtomicUsize::new(0));
+        let cacheable = super::FileBackedCacheable::new(
+            Arc::new(Box::new(ValidatorlessCacheable(loads.clone()))),
+            chrono::Duration::try_days(1).unwrap(),
+            "test_cache".to_string(),
+        );
+        assert!(!cacheable.load(&input).await.unwrap().cache_hit);
+        let result = cacheable.load(&input).await.unwrap();
+        assert!(result.cache_hit);
+        assert_eq!(result.result.a, input);
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,139 @@
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+/// Content hashes derived from `Last-Modified` carry this prefix, ETags are used as-is.
+pub const LAST_MODIFIED_HASH_PREFIX: &str = "lm-";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    pub content_key: Option<String>,
+    pub content_hash: Option<String>,
+}
+
+/// Response header a content hash was derived from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum HashSource {
+    ETag,
+    LastModified,
+}
+
+impl CacheKey {
+    pub fn hash_source(&self) -> Option<HashSource> {
+        self.content_hash.as_ref().map(|hash| {
+            if hash.starts_with(LAST_MODIFIED_HASH_PREFIX) {
+                HashSource::LastModified
+            } else {
+                HashSource::ETag
+            }
+        })
+    }
+}
+
+/// Tenant of a shared deployment, e.g. a team or an AWS payer account. Data derived for a
+/// namespace is kept apart from other namespaces, while raw upstream files are shared.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Namespace(String);
+
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid namespace {0:?}, expected letters, digits, '-' and '_' only")]
+pub struct InvalidNamespace(String);
+
+impl Namespace {
+    pub fn new(name: impl Into<String>) -> Result<Self, InvalidNamespace> {
+        let name = name.into();
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if valid {
+            Ok(Self(name))
+        } else {
+            Err(InvalidNamespace(name))
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Namespace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl TryFrom<String> for Namespace {
+    type Error = InvalidNamespace;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        Self::new(name)
+    }
+}
+
+impl From<Namespace> for String {
+    fn from(namespace: Namespace) -> Self {
+        namespace.0
+    }
+}
+
+impl std::str::FromStr for Namespace {
+    type Err = InvalidNamespace;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::new(name)
+    }
+}
+
+#[async_trait]
+pub trait Cacheable<I: Sync, O: Serialize + DeserializeOwned + Send + Sync, E: Error> {
+    async fn get_cache_key(&self, input: &I) -> Result<CacheKey, E>;
+    async fn load(&self, input: &I) -> Result<O, E>;
+    fn category_key(&self) -> String;
+
+    /// Content key of `input`, computed without contacting upstream.
+    fn content_key(&self, input: &I) -> Option<String>;
+
+    /// Loads `input` unless it still matches `cached_key`.
+    ///
+    /// The default implementation compares `get_cache_key` against the cached key before calling
+    /// `load`. Implementations that support conditional requests should override this to avoid
+    /// the extra round trip.
+    async fn load_conditional(
+        &self,
+        input: &I,
+        cached_key: Option<&CacheKey>,
+    ) -> Result<ConditionalLoad<O>, E>
+    where
+        Self: Sync,
+    {
+        let cache_key = self.get_cache_key(input).await?;
+        if let Some(cached_key) = cached_key {
+            if cached_key.content_hash == cache_key.content_hash {
+                return Ok(ConditionalLoad::NotModified);
+            }
+        }
+        let result = self.load(input).await?;
+        Ok(ConditionalLoad::Modified { result, cache_key })
+    }
+}
+
+#[derive(Debug)]
+pub enum ConditionalLoad<O> {
+    NotModified,
+    Modified { result: O, cache_key: CacheKey },
+}
+
+#[derive(Debug)]
+pub struct CacheLoadResult<O> {
+    pub result: O,
+    pub cache_key: CacheKey,
+    pub cache_hit: bool,
+}
+
+pub type CacheableArc<I, O, E> = Arc<Box<dyn Cacheable<I, O, E> + Send + Sync>>;
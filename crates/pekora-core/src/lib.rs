@@ -0,0 +1,7 @@
+pub mod cache;
+pub mod metrics;
+pub mod model;
+pub mod schema;
+pub mod status;
+pub mod transform;
+pub mod util;
@@ -0,0 +1,311 @@
+use serde::Serialize;
+use std::fmt::{Display, Formatter, Write};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::Duration;
+
+static GLOBAL_METRICS: MetricsRegistry = MetricsRegistry::new();
+
+/// Upper bounds in seconds of the buckets of duration histograms, from HEAD requests to crawls
+/// of every offer.
+pub const DURATION_BUCKETS: [f64; 12] = [
+    0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0, 1800.0,
+];
+
+/// Crate-wide registry of what a run did against the network and disk.
+pub fn global() -> &'static MetricsRegistry {
+    &GLOBAL_METRICS
+}
+
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    requests_made: AtomicU64,
+    bytes_downloaded: AtomicU64,
+    cache_bytes_written: AtomicU64,
+    rows_pivoted: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    fetch_duration: DurationHistogram,
+    crawl_duration: DurationHistogram,
+    /// Unix time in seconds, zero if never
+    last_refresh_success: AtomicI64,
+}
+
+impl MetricsRegistry {
+    pub const fn new() -> Self {
+        Self {
+            requests_made: AtomicU64::new(0),
+            bytes_downloaded: AtomicU64::new(0),
+            cache_bytes_written: AtomicU64::new(0),
+            rows_pivoted: AtomicU64::new(0),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+            fetch_duration: DurationHistogram::new(),
+            crawl_duration: DurationHistogram::new(),
+            last_refresh_success: AtomicI64::new(0),
+        }
+    }
+
+    pub fn record_request(&self) {
+        self.requests_made.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_bytes_downloaded(&self, bytes: u64) {
+        self.bytes_downloaded.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_cache_bytes_written(&self, bytes: u64) {
+        self.cache_bytes_written.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_rows_pivoted(&self, rows: u64) {
+        self.rows_pivoted.fetch_add(rows, Ordering::Relaxed);
+    }
+
+    pub fn record_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_cache_miss(&self) {
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Time taken by an upstream load, including revalidations answered as not modified.
+    pub fn record_fetch(&self, duration: Duration) {
+        self.fetch_duration.record(duration);
+    }
+
+    pub fn record_crawl(&self, duration: Duration) {
+        self.crawl_duration.record(duration);
+    }
+
+    /// Marks a refresh of the cache as completed at `timestamp`, in Unix seconds.
+    pub fn record_refresh_success(&self, timestamp: i64) {
+        self.last_refresh_success
+            .store(timestamp, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let last_refresh_success = self.last_refresh_success.load(Ordering::Relaxed);
+        MetricsSnapshot {
+            requests_made: self.requests_made.load(Ordering::Relaxed),
+            bytes_downloaded: self.bytes_downloaded.load(Ordering::Relaxed),
+            cache_bytes_written: self.cache_bytes_written.load(Ordering::Relaxed),
+            rows_pivoted: self.rows_pivoted.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+            fetch_duration: self.fetch_duration.snapshot(),
+            crawl_duration: self.crawl_duration.snapshot(),
+            last_refresh_success: (last_refresh_success > 0).then_some(last_refresh_success),
+        }
+    }
+}
+
+/// Counts of durations by the first bucket of [`DURATION_BUCKETS`] they fit in.
+#[derive(Debug, Default)]
+struct DurationHistogram {
+    /// One more than the bounds, for durations above the last one
+    buckets: [AtomicU64; DURATION_BUCKETS.len() + 1],
+    sum_micros: AtomicU64,
+}
+
+impl DurationHistogram {
+    const fn new() -> Self {
+        Self {
+            buckets: [const { AtomicU64::new(0) }; DURATION_BUCKETS.len() + 1],
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let bucket = DURATION_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(DURATION_BUCKETS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> DurationSnapshot {
+        DurationSnapshot {
+            buckets: std::array::from_fn(|i| self.buckets[i].load(Ordering::Relaxed)),
+            sum_micros: self.sum_micros.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DurationSnapshot {
+    /// Counts by bucket of [`DURATION_BUCKETS`], not cumulative, the last one above all bounds
+    pub buckets: [u64; DURATION_BUCKETS.len() + 1],
+    pub sum_micros: u64,
+}
+
+impl DurationSnapshot {
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MetricsSnapshot {
+    pub requests_made: u64,
+    pub bytes_downloaded: u64,
+    pub cache_bytes_written: u64,
+    pub rows_pivoted: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub fetch_duration: DurationSnapshot,
+    pub crawl_duration: DurationSnapshot,
+    /// Unix time in seconds of the last successful refresh
+    pub last_refresh_success: Option<i64>,
+}
+
+impl MetricsSnapshot {
+    /// Renders the snapshot in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            (
+                "pekora_requests_total",
+                "Requests made upstream",
+                self.requests_made,
+            ),
+            (
+                "pekora_downloaded_bytes_total",
+                "Bytes downloaded upstream",
+                self.bytes_downloaded,
+            ),
+            (
+                "pekora_cache_written_bytes_total",
+                "Bytes written to the cache",
+                self.cache_bytes_written,
+            ),
+            (
+                "pekora_rows_pivoted_total",
+                "Rows produced by transforms",
+                self.rows_pivoted,
+            ),
+            (
+                "pekora_cache_hits_total",
+                "Loads served from the cache",
+                self.cache_hits,
+            ),
+            (
+                "pekora_cache_misses_total",
+                "Loads written to the cache after fetching upstream",
+                self.cache_misses,
+            ),
+        ];
+        for (name, help, value) in counters {
+            let _ = writeln!(out, "# HELP {} {}\n# TYPE {} counter", name, help, name);
+            let _ = writeln!(out, "{} {}", name, value);
+        }
+        let histograms = [
+            (
+                "pekora_fetch_duration_seconds",
+                "Time taken by upstream loads",
+                &self.fetch_duration,
+            ),
+            (
+                "pekora_crawl_duration_seconds",
+                "Time taken by crawls of every offer",
+                &self.crawl_duration,
+            ),
+        ];
+        for (name, help, histogram) in histograms {
+            let _ = writeln!(out, "# HELP {} {}\n# TYPE {} histogram", name, help, name);
+            let mut cumulative = 0;
+            for (bound, count) in DURATION_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative);
+            }
+            let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, histogram.count());
+            let _ = writeln!(
+                out,
+                "{}_sum {}",
+                name,
+                histogram.sum_micros as f64 / 1_000_000.0
+            );
+            let _ = writeln!(out, "{}_count {}", name, histogram.count());
+        }
+        if let Some(timestamp) = self.last_refresh_success {
+            let name = "pekora_last_refresh_success_timestamp_seconds";
+            let _ = writeln!(
+                out,
+                "# HELP {} Unix time of the last successful refresh\n# TYPE {} gauge",
+                name, name
+            );
+            let _ = writeln!(out, "{} {}", name, timestamp);
+        }
+        out
+    }
+}
+
+impl Display for MetricsSnapshot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "requests made:       {}", self.requests_made)?;
+        writeln!(f, "bytes downloaded:    {}", self.bytes_downloaded)?;
+        writeln!(f, "cache bytes written: {}", self.cache_bytes_written)?;
+        writeln!(
+            f,
+            "cache hits/misses:   {}/{}",
+            self.cache_hits, self.cache_misses
+        )?;
+        write!(f, "rows pivoted:        {}", self.rows_pivoted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MetricsRegistry;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_recording() {
+        let registry = Arc::new(MetricsRegistry::new());
+        let mut tasks = Vec::new();
+        for _ in 0..8 {
+            let registry = registry.clone();
+            tasks.push(tokio::spawn(async move {
+                for _ in 0..100 {
+                    registry.record_request();
+                    registry.record_bytes_downloaded(10);
+                }
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.requests_made, 800);
+        assert_eq!(snapshot.bytes_downloaded, 8000);
+        assert_eq!(snapshot.cache_bytes_written, 0);
+    }
+
+    #[test]
+    fn test_prometheus() {
+        let registry = MetricsRegistry::new();
+        registry.record_cache_hit();
+        registry.record_fetch(Duration::from_millis(200));
+        registry.record_fetch(Duration::from_secs(3600));
+        let text = registry.snapshot().to_prometheus();
+        assert!(
+            text.contains("# TYPE pekora_cache_hits_total counter\npekora_cache_hits_total 1\n")
+        );
+        assert!(text.contains("pekora_fetch_duration_seconds_bucket{le=\"0.1\"} 0\n"));
+        assert!(text.contains("pekora_fetch_duration_seconds_bucket{le=\"0.25\"} 1\n"));
+        assert!(text.contains("pekora_fetch_duration_seconds_bucket{le=\"1800\"} 1\n"));
+        assert!(text.contains("pekora_fetch_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(text.contains("pekora_fetch_duration_seconds_sum 3600.2\n"));
+        assert!(text.contains("pekora_crawl_duration_seconds_count 0\n"));
+        assert!(!text.contains("pekora_last_refresh_success"));
+
+        registry.record_refresh_success(1710000000);
+        let text = registry.snapshot().to_prometheus();
+        assert!(text.contains("pekora_last_refresh_success_timestamp_seconds 1710000000\n"));
+    }
+}
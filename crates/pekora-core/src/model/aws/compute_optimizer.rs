@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+/// Response of Compute Optimizer `GetEC2InstanceRecommendations`, in the form the AWS CLI prints
+/// it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ec2InstanceRecommendationsResponse {
+    pub instance_recommendations: Vec<InstanceRecommendation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceRecommendation {
+    pub instance_arn: String,
+    pub account_id: Option<String>,
+    pub instance_name: Option<String>,
+    pub current_instance_type: String,
+    /// e.g. `Overprovisioned` or `Optimized`
+    pub finding: String,
+    #[serde(default)]
+    pub recommendation_options: Vec<InstanceRecommendationOption>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceRecommendationOption {
+    pub instance_type: String,
+    /// 1 for the best option
+    pub rank: i32,
+    pub performance_risk: Option<f64>,
+    pub migration_effort: Option<String>,
+}
+
+impl InstanceRecommendation {
+    /// Region of the instance, from its ARN.
+    pub fn region(&self) -> Option<&str> {
+        self.instance_arn.split(':').nth(3)
+    }
+
+    /// Option ranked best, if any.
+    pub fn best_option(&self) -> Option<&InstanceRecommendationOption> {
+        self.recommendation_options
+            .iter()
+            .min_by_key(|option| option.rank)
+    }
+}
@@ -0,0 +1,158 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Response of Cost Explorer `GetCostAndUsage`, in the form the AWS CLI prints it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CostAndUsageResponse {
+    pub results_by_time: Vec<ResultByTime>,
+    #[serde(default)]
+    pub group_definitions: Vec<GroupDefinition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ResultByTime {
+    pub time_period: TimePeriod,
+    /// Metrics by name when not grouped, e.g. `UnblendedCost`
+    #[serde(default)]
+    pub total: HashMap<String, MetricValue>,
+    #[serde(default)]
+    pub groups: Vec<Group>,
+    #[serde(default)]
+    pub estimated: bool,
+}
+
+/// Start inclusive and end exclusive, as dates or, at hourly granularity, timestamps.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TimePeriod {
+    pub start: String,
+    pub end: String,
+}
+
+impl TimePeriod {
+    /// The `days` days before `end`, which is excluded.
+    pub fn days_before(end: NaiveDate, days: u32) -> Self {
+        let start = end - chrono::Days::new(days.into());
+        Self {
+            start: start.format("%Y-%m-%d").to_string(),
+            end: end.format("%Y-%m-%d").to_string(),
+        }
+    }
+
+    fn parse(value: &str) -> Option<DateTime<Utc>> {
+        match DateTime::parse_from_rfc3339(value) {
+            Ok(timestamp) => Some(timestamp.with_timezone(&Utc)),
+            Err(_) => Some(
+                NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                    .ok()?
+                    .and_hms_opt(0, 0, 0)?
+                    .and_utc(),
+            ),
+        }
+    }
+
+    /// Length of the period in hours, `None` if either end does not parse.
+    pub fn hours(&self) -> Option<i64> {
+        Some((Self::parse(&self.end)? - Self::parse(&self.start)?).num_hours())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Group {
+    /// Values of the group definitions, in the same order
+    pub keys: Vec<String>,
+    pub metrics: HashMap<String, MetricValue>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GroupDefinition {
+    /// e.g. `DIMENSION` or `TAG`
+    #[serde(rename = "Type")]
+    pub kind: String,
+    /// e.g. `INSTANCE_TYPE`
+    pub key: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MetricValue {
+    pub amount: Decimal,
+    /// e.g. `USD` or `Hrs`
+    pub unit: String,
+}
+
+/// Response of Cost Explorer `GetSavingsPlansUtilization`, in the form the AWS CLI prints it.
+/// Only the total over the requested period is kept.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SavingsPlansUtilizationResponse {
+    pub total: SavingsPlansUtilizationTotal,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SavingsPlansUtilizationTotal {
+    pub utilization: SavingsPlansUtilization,
+    pub savings: Option<SavingsPlansSavings>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SavingsPlansUtilization {
+    pub total_commitment: Decimal,
+    pub used_commitment: Decimal,
+    pub unused_commitment: Decimal,
+    pub utilization_percentage: Decimal,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SavingsPlansSavings {
+    pub net_savings: Decimal,
+    pub on_demand_cost_equivalent: Decimal,
+}
+
+/// Response of Cost Explorer `GetReservationCoverage`, in the form the AWS CLI prints it. Only
+/// the total over the requested period is kept.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReservationCoverageResponse {
+    pub total: ReservationCoverage,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReservationCoverage {
+    pub coverage_hours: CoverageHours,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CoverageHours {
+    pub on_demand_hours: Decimal,
+    pub reserved_hours: Decimal,
+    pub total_running_hours: Decimal,
+    pub coverage_hours_percentage: Decimal,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimePeriod;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_days_before() {
+        let period = TimePeriod::days_before(NaiveDate::from_ymd_opt(2024, 3, 2).unwrap(), 30);
+        assert_eq!(period.start, "2024-02-01");
+        assert_eq!(period.end, "2024-03-02");
+        assert_eq!(period.hours(), Some(30 * 24));
+    }
+}
@@ -0,0 +1,22 @@
+use super::rds::RecurringCharge;
+use serde::{Deserialize, Serialize};
+
+/// Offering of ElastiCache `DescribeReservedCacheNodesOfferings`, in the form the AWS CLI
+/// prints it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReservedCacheNodesOffering {
+    pub reserved_cache_nodes_offering_id: String,
+    pub cache_node_type: String,
+    /// Term in seconds
+    pub duration: i64,
+    /// Upfront fee
+    pub fixed_price: f64,
+    pub usage_price: f64,
+    /// Engine, e.g. `redis` or `memcached`
+    pub product_description: String,
+    /// e.g. `No Upfront`
+    pub offering_type: String,
+    #[serde(default)]
+    pub recurring_charges: Vec<RecurringCharge>,
+}
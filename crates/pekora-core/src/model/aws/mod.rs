@@ -0,0 +1,12 @@
+pub mod compute_optimizer;
+pub mod cost_explorer;
+pub mod elasticache;
+pub mod opensearch;
+pub mod price_bulk_types;
+pub mod rds;
+pub mod redshift;
+pub mod savings_plans;
+pub mod schema;
+pub mod spot_advisor;
+pub mod types;
+pub mod unit;
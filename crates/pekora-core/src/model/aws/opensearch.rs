@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// Response of OpenSearch Service `ListInstanceTypeDetails`, in the form the AWS CLI prints it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ListInstanceTypeDetailsResponse {
+    pub instance_type_details: Vec<InstanceTypeDetails>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+}
+
+/// Instance type available to domains of an engine version in a region.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct InstanceTypeDetails {
+    /// e.g. `r6g.large.search`
+    pub instance_type: String,
+    /// e.g. `data`, `master` or `ultrawarm`
+    #[serde(default)]
+    pub instance_role: Vec<String>,
+    #[serde(default)]
+    pub availability_zones: Vec<String>,
+    pub warm_enabled: Option<bool>,
+}
@@ -0,0 +1,416 @@
+use crate::model::aws::types::{
+    CloudWatchProductAttributes, DataTransferProductAttributes, DocDbProductAttributes,
+    EbsProductAttributes, EksProductAttributes, ElastiCacheProductAttributes,
+    FargateProductAttributes, KnownValues, LambdaProductAttributes, MemoryDbProductAttributes,
+    NetworkProductAttributes, OpenSearchProductAttributes, PriceOffering, RITermAttributes,
+    RdsProductAttributes, RedshiftProductAttributes, SageMakerProductAttributes, SavingPlanProduct,
+    SavingsPlanTerms, UnknownValue,
+};
+use crate::util::regex_extract_match_group;
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// AWS partition whose price list is queried. Partitions other than `aws` publish prices for
+/// their own regions only.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum Partition {
+    #[default]
+    Aws,
+    /// AWS China, served from its own endpoint under `/offers/v1.0/cn`
+    AwsCn,
+    /// AWS GovCloud (US). Its regions are published in the `aws` price list.
+    AwsUsGov,
+}
+
+impl Partition {
+    pub fn default_base_url(self) -> &'static str {
+        match self {
+            Partition::Aws | Partition::AwsUsGov => "https://pricing.us-east-1.amazonaws.com",
+            Partition::AwsCn => "https://pricing.cn-north-1.amazonaws.com.cn",
+        }
+    }
+
+    /// Partition segment of offer paths, e.g. `aws` in `/offers/v1.0/aws/index.json`.
+    pub fn path_segment(self) -> &'static str {
+        match self {
+            Partition::Aws | Partition::AwsUsGov => "aws",
+            Partition::AwsCn => "cn",
+        }
+    }
+
+    /// Cache category of price bulk files, kept apart per price list so cn files never replace
+    /// aws ones. GovCloud shares the `aws` price list and its cache.
+    pub fn category_key(self, name: &str) -> String {
+        match self {
+            Partition::Aws | Partition::AwsUsGov => format!("aws/bulk/{}", name),
+            Partition::AwsCn => format!("aws-cn/bulk/{}", name),
+        }
+    }
+
+    pub fn contains_region(self, region: &str) -> bool {
+        match self {
+            Partition::Aws => !region.starts_with("us-gov-") && !region.starts_with("cn-"),
+            Partition::AwsCn => region.starts_with("cn-"),
+            Partition::AwsUsGov => region.starts_with("us-gov-"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceListResponse {
+    pub format_version: String,
+    pub publication_date: DateTime<Utc>,
+    pub offers: HashMap<String, ServiceListResponseOffer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceListResponseOffer {
+    pub offer_code: String,
+    pub version_index_url: Option<String>,
+    pub current_version_url: Option<String>,
+    pub current_region_index_url: Option<String>,
+    pub savings_plan_version_index_url: Option<String>,
+    pub current_savings_plan_index_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionIndexResponse {
+    pub format_version: String,
+    pub publication_date: DateTime<Utc>,
+    pub regions: HashMap<String, RegionIndexResponseRegion>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionIndexResponseRegion {
+    pub region_code: String,
+    pub current_version_url: PriceBulkOffer,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProductResponse<PT: Debug + Clone, TT: Debug + Clone> {
+    pub format_version: String,
+    pub publication_date: DateTime<Utc>,
+    pub version: String,
+    pub products: PT,
+    pub terms: TT,
+    /// Continuation file of split offers. Clients follow it and merge the files, so loaded
+    /// responses never have it set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_file_url: Option<String>,
+}
+
+impl<PT: Debug + Clone + KnownValues, TT: Debug + Clone + KnownValues> KnownValues
+    for ProductResponse<PT, TT>
+{
+    fn collect_unknown(&self, unknown: &mut Vec<UnknownValue>) {
+        self.products.collect_unknown(unknown);
+        self.terms.collect_unknown(unknown);
+    }
+}
+
+pub type PricingListResponse = TypedPricingListResponse<HashMap<String, String>>;
+
+/// Pricing list with product attributes deserialized into `A`.
+pub type TypedPricingListResponse<A> =
+    ProductResponse<HashMap<String, PricingListResponseProduct<A>>, PricingListResponseTerms>;
+
+pub type RdsPricingListResponse = TypedPricingListResponse<RdsProductAttributes>;
+
+pub type ElastiCachePricingListResponse = TypedPricingListResponse<ElastiCacheProductAttributes>;
+
+pub type RedshiftPricingListResponse = TypedPricingListResponse<RedshiftProductAttributes>;
+
+/// AmazonES pricing list, the offer of OpenSearch Service.
+pub type OpenSearchPricingListResponse = TypedPricingListResponse<OpenSearchProductAttributes>;
+
+/// AmazonCloudWatch pricing list, including CloudWatch Logs.
+pub type CloudWatchPricingListResponse = TypedPricingListResponse<CloudWatchProductAttributes>;
+
+pub type MemoryDbPricingListResponse = TypedPricingListResponse<MemoryDbProductAttributes>;
+
+/// AmazonDocDB pricing list, the offer of DocumentDB.
+pub type DocDbPricingListResponse = TypedPricingListResponse<DocDbProductAttributes>;
+
+pub type SageMakerPricingListResponse = TypedPricingListResponse<SageMakerProductAttributes>;
+
+pub type LambdaPricingListResponse = TypedPricingListResponse<LambdaProductAttributes>;
+
+pub type FargatePricingListResponse = TypedPricingListResponse<FargateProductAttributes>;
+
+pub type EksPricingListResponse = TypedPricingListResponse<EksProductAttributes>;
+
+pub type DataTransferPricingListResponse = TypedPricingListResponse<DataTransferProductAttributes>;
+
+/// AmazonVPC, AWSELB or AmazonEC2 pricing list typed for network infrastructure products.
+pub type NetworkPricingListResponse = TypedPricingListResponse<NetworkProductAttributes>;
+
+/// AmazonEC2 pricing list typed for its EBS products.
+pub type EbsPricingListResponse = TypedPricingListResponse<EbsProductAttributes>;
+
+pub type SavingsPlanListResponse = ProductResponse<Vec<SavingPlanProduct>, SavingsPlanTerms>;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingListResponseProduct<T: Debug + Clone> {
+    pub product_family: String,
+    pub sku: String,
+    pub attributes: T,
+}
+
+/// Product attributes are plain strings or typed structs without lenient enums.
+impl<T: Debug + Clone> KnownValues for PricingListResponseProduct<T> {
+    fn collect_unknown(&self, _unknown: &mut Vec<UnknownValue>) {}
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PricingListResponseTerms {
+    #[serde(rename = "OnDemand")]
+    pub on_demand: HashMap<String, HashMap<String, PriceOffering<HashMap<String, String>>>>,
+    #[serde(rename = "Reserved")]
+    pub reserved: HashMap<String, HashMap<String, PriceOffering<RITermAttributes>>>,
+}
+
+impl KnownValues for PricingListResponseTerms {
+    fn collect_unknown(&self, unknown: &mut Vec<UnknownValue>) {
+        self.on_demand.collect_unknown(unknown);
+        self.reserved.collect_unknown(unknown);
+    }
+}
+
+impl PricingListResponse {
+    /// Converts product attributes into a service specific type.
+    pub fn with_typed_attributes<A: DeserializeOwned + Debug + Clone>(
+        self,
+    ) -> serde_json::Result<TypedPricingListResponse<A>> {
+        let mut products = HashMap::with_capacity(self.products.len());
+        for (sku, product) in self.products {
+            let attributes = serde_json::from_value(serde_json::Value::Object(
+                product
+                    .attributes
+                    .into_iter()
+                    .map(|(key, value)| (key, serde_json::Value::String(value)))
+                    .collect(),
+            ))?;
+            products.insert(
+                sku,
+                PricingListResponseProduct {
+                    product_family: product.product_family,
+                    sku: product.sku,
+                    attributes,
+                },
+            );
+        }
+        Ok(ProductResponse {
+            format_version: self.format_version,
+            publication_date: self.publication_date,
+            version: self.version,
+            products,
+            terms: self.terms,
+            next_file_url: self.next_file_url,
+        })
+    }
+}
+
+// ======= Utility types - not part of the response DTO ==========
+lazy_static! {
+    static ref OFFER_RESOURCE_REGEX: Regex =
+        Regex::new(r"^\/([^/]+)\/v1.0\/(?:aws|cn)\/([^/]+)\/([^/]+)\/([^/]+)\/([^/]+)$").unwrap();
+}
+
+/// Serialized as its offer path, as in region indexes.
+#[derive(Debug, Clone)]
+pub struct PriceBulkOffer {
+    pub service_code: String,
+    pub offer_version: String,
+    pub region: String,
+    pub filename: String,
+}
+
+impl PriceBulkOffer {
+    pub fn path(&self, partition: Partition) -> String {
+        format!(
+            "offers/v1.0/{}/{}/{}/{}/{}",
+            partition.path_segment(),
+            self.service_code,
+            self.offer_version,
+            self.region,
+            self.filename
+        )
+    }
+
+    pub fn tag(&self) -> String {
+        format!(
+            "{}-{}-{}",
+            self.region, self.service_code, self.offer_version,
+        )
+    }
+}
+
+impl Serialize for PriceBulkOffer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("/{}", self.path(Partition::Aws)))
+    }
+}
+
+impl<'de> Deserialize<'de> for PriceBulkOffer {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        PriceBulkOffer::try_from(s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl TryFrom<String> for PriceBulkOffer {
+    type Error = anyhow::Error;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let captures = match OFFER_RESOURCE_REGEX.captures(&s) {
+            Some(captures) => captures,
+            None => anyhow::bail!("Invalid offer resource path: {}", s),
+        };
+
+        let service_code = regex_extract_match_group(&captures, 2, "service_code")?;
+        let offer_version = regex_extract_match_group(&captures, 3, "offer_version")?;
+        let region = regex_extract_match_group(&captures, 4, "region")?;
+        let filename = regex_extract_match_group(&captures, 5, "filename")?;
+
+        Ok(PriceBulkOffer {
+            service_code,
+            offer_version,
+            region,
+            filename,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PriceBulkSavingsPlan {
+    pub service_code: String,
+    pub offer_version: String,
+    pub region: String,
+    pub filename: String,
+}
+
+impl PriceBulkSavingsPlan {
+    pub fn tag(&self) -> String {
+        format!(
+            "{}-{}-{}",
+            self.region, self.service_code, self.offer_version,
+        )
+    }
+
+    pub fn path(&self, partition: Partition) -> String {
+        format!(
+            "savingsPlan/v1.0/{}/{}/{}/{}/{}",
+            partition.path_segment(),
+            self.service_code,
+            self.offer_version,
+            self.region,
+            self.filename
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for PriceBulkSavingsPlan {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: &str = Deserialize::deserialize(deserializer)?;
+        PriceBulkSavingsPlan::try_from(s.to_string()).map_err(serde::de::Error::custom)
+    }
+}
+
+impl TryFrom<String> for PriceBulkSavingsPlan {
+    type Error = anyhow::Error;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let captures = match OFFER_RESOURCE_REGEX.captures(&s) {
+            Some(captures) => captures,
+            None => anyhow::bail!("Invalid offer resource path: {}", s),
+        };
+
+        let service_code = regex_extract_match_group(&captures, 2, "service_code")?;
+        let offer_version = regex_extract_match_group(&captures, 3, "offer_version")?;
+        let region = regex_extract_match_group(&captures, 4, "region")?;
+        let filename = regex_extract_match_group(&captures, 5, "filename")?;
+
+        Ok(PriceBulkSavingsPlan {
+            service_code,
+            offer_version,
+            region,
+            filename,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ElastiCachePricingListResponse, PricingListResponse, RdsPricingListResponse};
+
+    #[test]
+    fn test_rds_typed_attributes() {
+        let response: PricingListResponse = serde_json::from_str(
+            r#"{
+                "formatVersion": "v1.0",
+                "publicationDate": "2024-03-12T15:37:24Z",
+                "version": "20240312153724",
+                "products": {
+                    "INSTANCE": {"productFamily": "Database Instance", "sku": "INSTANCE",
+                        "attributes": {"databaseEngine": "PostgreSQL", "deploymentOption": "Multi-AZ",
+                            "instanceType": "db.r6g.large", "licenseModel": "No license required",
+                            "storage": "EBS Only", "vcpu": "2"}},
+                    "STORAGE": {"productFamily": "Database Storage", "sku": "STORAGE",
+                        "attributes": {"databaseEngine": "PostgreSQL", "volumeType": "General Purpose"}}
+                },
+                "terms": {"OnDemand": {}, "Reserved": {}}
+            }"#,
+        )
+        .unwrap();
+        let typed: RdsPricingListResponse = response.with_typed_attributes().unwrap();
+
+        let instance = &typed.products["INSTANCE"].attributes;
+        assert_eq!(instance.instance_type.as_deref(), Some("db.r6g.large"));
+        assert_eq!(instance.deployment_option.as_deref(), Some("Multi-AZ"));
+        assert_eq!(instance.other["vcpu"], "2");
+        let storage = &typed.products["STORAGE"].attributes;
+        assert_eq!(storage.database_engine.as_deref(), Some("PostgreSQL"));
+        assert!(storage.instance_type.is_none());
+    }
+
+    #[test]
+    fn test_elasticache_typed_attributes() {
+        let response: PricingListResponse = serde_json::from_str(
+            r#"{
+                "formatVersion": "v1.0",
+                "publicationDate": "2024-03-12T15:37:24Z",
+                "version": "20240312153724",
+                "products": {
+                    "NODE": {"productFamily": "Cache Instance", "sku": "NODE",
+                        "attributes": {"cacheEngine": "Redis", "instanceType": "cache.r6g.large",
+                            "memory": "13.07 GiB", "vcpu": "2"}}
+                },
+                "terms": {"OnDemand": {}, "Reserved": {}}
+            }"#,
+        )
+        .unwrap();
+        let typed: ElastiCachePricingListResponse = response.with_typed_attributes().unwrap();
+
+        let node = &typed.products["NODE"].attributes;
+        assert_eq!(node.cache_engine.as_deref(), Some("Redis"));
+        assert_eq!(node.memory.as_deref(), Some("13.07 GiB"));
+        assert_eq!(node.node_type(), Some("cache.r6g.large"));
+    }
+}
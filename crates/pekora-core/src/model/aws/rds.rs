@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+/// Response of RDS `DescribeOrderableDBInstanceOptions`, in the form the AWS CLI prints it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct OrderableDbInstanceOptionsResponse {
+    #[serde(rename = "OrderableDBInstanceOptions")]
+    pub orderable_db_instance_options: Vec<OrderableDbInstanceOption>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub marker: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct OrderableDbInstanceOption {
+    /// Engine as the RDS API names it, e.g. `mysql` or `aurora-postgresql`
+    pub engine: String,
+    pub engine_version: Option<String>,
+    #[serde(rename = "DBInstanceClass")]
+    pub db_instance_class: String,
+    pub license_model: Option<String>,
+    #[serde(default)]
+    pub availability_zones: Vec<AvailabilityZone>,
+    #[serde(rename = "MultiAZCapable", default)]
+    pub multi_az_capable: bool,
+    pub storage_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AvailabilityZone {
+    pub name: String,
+}
+
+/// Database engine of the pricing list for an engine of the RDS API, `None` if unknown.
+pub fn pricing_engine(api_engine: &str) -> Option<&'static str> {
+    match api_engine {
+        "mysql" => Some("MySQL"),
+        "mariadb" => Some("MariaDB"),
+        "postgres" => Some("PostgreSQL"),
+        "aurora-mysql" | "aurora" => Some("Aurora MySQL"),
+        "aurora-postgresql" => Some("Aurora PostgreSQL"),
+        "oracle-ee" | "oracle-se2" | "oracle-ee-cdb" | "oracle-se2-cdb" => Some("Oracle"),
+        "sqlserver-ee" | "sqlserver-se" | "sqlserver-ex" | "sqlserver-web" => Some("SQL Server"),
+        "db2-se" | "db2-ae" => Some("Db2"),
+        _ => None,
+    }
+}
+
+/// Response of RDS `DescribeReservedDBInstancesOfferings`, in the form the AWS CLI prints it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReservedDbInstancesOfferingsResponse {
+    #[serde(rename = "ReservedDBInstancesOfferings")]
+    pub reserved_db_instances_offerings: Vec<ReservedDbInstancesOffering>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub marker: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReservedDbInstancesOffering {
+    #[serde(rename = "ReservedDBInstancesOfferingId")]
+    pub reserved_db_instances_offering_id: String,
+    #[serde(rename = "DBInstanceClass")]
+    pub db_instance_class: String,
+    /// Term in seconds
+    pub duration: i64,
+    /// Upfront fee
+    pub fixed_price: f64,
+    pub usage_price: f64,
+    pub currency_code: Option<String>,
+    /// Engine with an optional license suffix, e.g. `postgresql` or `oracle-se2(li)`
+    pub product_description: String,
+    /// e.g. `No Upfront`
+    pub offering_type: String,
+    #[serde(rename = "MultiAZ", default)]
+    pub multi_az: bool,
+    #[serde(default)]
+    pub recurring_charges: Vec<RecurringCharge>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RecurringCharge {
+    pub recurring_charge_amount: f64,
+    /// e.g. `Hourly`
+    pub recurring_charge_frequency: String,
+}
+
+impl ReservedDbInstancesOffering {
+    /// Pricing list engine and license model of the product description. The license model is
+    /// `None` when the description does not name one.
+    pub fn engine_and_license(&self) -> Option<(&'static str, Option<&'static str>)> {
+        let (engine, license_model) = match self.product_description.split_once('(') {
+            Some((engine, "li)")) => (engine, Some("License included")),
+            Some((engine, "byol)")) => (engine, Some("Bring your own license")),
+            Some(_) => return None,
+            None => (self.product_description.as_str(), None),
+        };
+        let engine = match engine {
+            "postgresql" => "PostgreSQL",
+            engine => pricing_engine(engine)?,
+        };
+        Some((engine, license_model))
+    }
+}
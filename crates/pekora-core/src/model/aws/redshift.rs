@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// Response of Redshift `DescribeNodeConfigurationOptions`, in the form the AWS CLI prints it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct NodeConfigurationOptionsResponse {
+    pub node_configuration_option_list: Vec<NodeConfigurationOption>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub marker: Option<String>,
+}
+
+/// Node type and count a cluster or snapshot can be resized or restored to.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct NodeConfigurationOption {
+    /// e.g. `ra3.xlplus`
+    pub node_type: String,
+    pub number_of_nodes: i32,
+    pub estimated_disk_utilization_percent: Option<f64>,
+    /// `standard` or `high-performance`
+    pub mode: Option<String>,
+}
@@ -0,0 +1,106 @@
+use crate::model::aws::types::{KnownValues, PurchaseOption, SavingsPlanType, UnknownValue};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Response of Savings Plans `DescribeSavingsPlansOfferingRates`, in the form the AWS CLI prints
+/// it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavingsPlansOfferingRatesResponse {
+    pub search_results: Vec<SavingsPlanOfferingRate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+}
+
+impl KnownValues for SavingsPlansOfferingRatesResponse {
+    fn collect_unknown(&self, unknown: &mut Vec<UnknownValue>) {
+        for rate in &self.search_results {
+            rate.savings_plan_offering
+                .payment_option
+                .collect_unknown(unknown);
+            rate.savings_plan_offering
+                .plan_type
+                .collect_unknown(unknown);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavingsPlanOfferingRate {
+    pub savings_plan_offering: ParentSavingsPlanOffering,
+    pub rate: Decimal,
+    /// e.g. `Hrs`
+    pub unit: Option<String>,
+    pub product_type: Option<String>,
+    pub service_code: Option<String>,
+    /// Usage type the rate discounts, e.g. `USW2-BoxUsage:m5.large`
+    pub usage_type: String,
+    pub operation: String,
+    #[serde(default)]
+    pub properties: Vec<SavingsPlanOfferingProperty>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParentSavingsPlanOffering {
+    pub offering_id: String,
+    pub payment_option: PurchaseOption,
+    pub plan_type: SavingsPlanType,
+    pub duration_seconds: i64,
+    pub currency: Option<String>,
+    pub plan_description: Option<String>,
+}
+
+/// Response of Savings Plans `DescribeSavingsPlansOfferings`, in the form the AWS CLI prints it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavingsPlansOfferingsResponse {
+    pub search_results: Vec<SavingsPlanOffering>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+}
+
+impl KnownValues for SavingsPlansOfferingsResponse {
+    fn collect_unknown(&self, unknown: &mut Vec<UnknownValue>) {
+        for offering in &self.search_results {
+            offering.payment_option.collect_unknown(unknown);
+            offering.plan_type.collect_unknown(unknown);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavingsPlanOffering {
+    pub offering_id: String,
+    /// e.g. `EC2` or `Fargate`
+    #[serde(default)]
+    pub product_types: Vec<String>,
+    pub plan_type: SavingsPlanType,
+    pub description: Option<String>,
+    pub payment_option: PurchaseOption,
+    pub duration_seconds: i64,
+    pub currency: Option<String>,
+    pub service_code: Option<String>,
+    pub usage_type: Option<String>,
+    pub operation: Option<String>,
+    #[serde(default)]
+    pub properties: Vec<SavingsPlanOfferingProperty>,
+}
+
+/// e.g. `region` or `instanceType`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SavingsPlanOfferingProperty {
+    pub name: String,
+    pub value: String,
+}
+
+impl SavingsPlanOfferingRate {
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+            .map(|property| property.value.as_str())
+    }
+}
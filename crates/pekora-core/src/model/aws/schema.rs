@@ -0,0 +1,220 @@
+use crate::model::aws::price_bulk_types::PricingListResponse;
+use crate::model::aws::types::{
+    CloudWatchProductAttributes, DataTransferProductAttributes, DocDbProductAttributes,
+    EksProductAttributes, ElastiCacheProductAttributes, FargateProductAttributes,
+    LambdaProductAttributes, MemoryDbProductAttributes, NetworkProductAttributes,
+    OpenSearchProductAttributes, RdsProductAttributes, RedshiftProductAttributes,
+    SageMakerProductAttributes,
+};
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+pub type SchemaResult<T> = Result<T, SchemaError>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum SchemaError {
+    #[error("Product {sku} does not match the {service_code} schema: {message}")]
+    InvalidProduct {
+        service_code: String,
+        sku: String,
+        message: String,
+    },
+}
+
+/// Product attribute schema of one service.
+pub trait AttributeSchema: Debug + Send + Sync {
+    fn service_code(&self) -> &str;
+
+    /// Checks the attributes of a single product. Returns a description of the mismatch.
+    fn validate_attributes(&self, attributes: &HashMap<String, String>) -> Result<(), String>;
+
+    /// Checks every product of `response`, stopping at the first mismatch.
+    fn validate(&self, response: &PricingListResponse) -> SchemaResult<()> {
+        for (sku, product) in &response.products {
+            self.validate_attributes(&product.attributes)
+                .map_err(|message| SchemaError::InvalidProduct {
+                    service_code: self.service_code().to_string(),
+                    sku: sku.clone(),
+                    message,
+                })?;
+        }
+        Ok(())
+    }
+}
+
+/// Schema backed by a typed attribute struct such as `RdsProductAttributes`.
+#[derive(Debug)]
+pub struct TypedSchema<A> {
+    service_code: String,
+    _attributes: PhantomData<fn() -> A>,
+}
+
+impl<A> TypedSchema<A> {
+    pub fn new(service_code: impl Into<String>) -> Self {
+        Self {
+            service_code: service_code.into(),
+            _attributes: PhantomData,
+        }
+    }
+}
+
+impl<A: DeserializeOwned + Debug> AttributeSchema for TypedSchema<A> {
+    fn service_code(&self) -> &str {
+        &self.service_code
+    }
+
+    fn validate_attributes(&self, attributes: &HashMap<String, String>) -> Result<(), String> {
+        let value = serde_json::Value::Object(
+            attributes
+                .iter()
+                .map(|(key, value)| (key.clone(), serde_json::Value::String(value.clone())))
+                .collect(),
+        );
+        serde_json::from_value::<A>(value)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Schema of services without a typed struct, listing attributes every product must have.
+#[derive(Debug, Clone)]
+pub struct DynamicSchema {
+    service_code: String,
+    required: Vec<String>,
+}
+
+impl DynamicSchema {
+    pub fn new(service_code: impl Into<String>, required: Vec<String>) -> Self {
+        Self {
+            service_code: service_code.into(),
+            required,
+        }
+    }
+}
+
+impl AttributeSchema for DynamicSchema {
+    fn service_code(&self) -> &str {
+        &self.service_code
+    }
+
+    fn validate_attributes(&self, attributes: &HashMap<String, String>) -> Result<(), String> {
+        let missing = self
+            .required
+            .iter()
+            .filter(|name| !attributes.contains_key(name.as_str()))
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("missing attributes {}", missing.join(", ")))
+        }
+    }
+}
+
+/// Attribute schemas by service code.
+#[derive(Debug, Default)]
+pub struct SchemaRegistry {
+    schemas: BTreeMap<String, Box<dyn AttributeSchema>>,
+}
+
+impl SchemaRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with the schemas of the typed attribute structs in this crate.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(TypedSchema::<RdsProductAttributes>::new("AmazonRDS"));
+        registry.register(TypedSchema::<ElastiCacheProductAttributes>::new(
+            "AmazonElastiCache",
+        ));
+        registry.register(TypedSchema::<LambdaProductAttributes>::new("AWSLambda"));
+        registry.register(TypedSchema::<FargateProductAttributes>::new("AmazonECS"));
+        registry.register(TypedSchema::<EksProductAttributes>::new("AmazonEKS"));
+        registry.register(TypedSchema::<DataTransferProductAttributes>::new(
+            "AWSDataTransfer",
+        ));
+        registry.register(TypedSchema::<NetworkProductAttributes>::new("AmazonVPC"));
+        registry.register(TypedSchema::<NetworkProductAttributes>::new("AWSELB"));
+        registry.register(TypedSchema::<RedshiftProductAttributes>::new(
+            "AmazonRedshift",
+        ));
+        registry.register(TypedSchema::<OpenSearchProductAttributes>::new("AmazonES"));
+        registry.register(TypedSchema::<CloudWatchProductAttributes>::new(
+            "AmazonCloudWatch",
+        ));
+        registry.register(TypedSchema::<MemoryDbProductAttributes>::new(
+            "AmazonMemoryDB",
+        ));
+        registry.register(TypedSchema::<DocDbProductAttributes>::new("AmazonDocDB"));
+        registry.register(TypedSchema::<SageMakerProductAttributes>::new(
+            "AmazonSageMaker",
+        ));
+        registry
+    }
+
+    /// Registers `schema`, replacing any schema of the same service.
+    pub fn register(&mut self, schema: impl AttributeSchema + 'static) {
+        self.schemas
+            .insert(schema.service_code().to_string(), Box::new(schema));
+    }
+
+    pub fn get(&self, service_code: &str) -> Option<&dyn AttributeSchema> {
+        self.schemas.get(service_code).map(Box::as_ref)
+    }
+
+    pub fn service_codes(&self) -> impl Iterator<Item = &str> {
+        self.schemas.keys().map(String::as_str)
+    }
+
+    /// Validates `response` against the schema of `service_code`. Services without a
+    /// registered schema always pass.
+    pub fn validate(&self, service_code: &str, response: &PricingListResponse) -> SchemaResult<()> {
+        match self.get(service_code) {
+            Some(schema) => schema.validate(response),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DynamicSchema, SchemaError, SchemaRegistry};
+    use crate::model::aws::price_bulk_types::PricingListResponse;
+
+    const RESPONSE_BODY: &str = r#"{
+        "formatVersion": "v1.0",
+        "publicationDate": "2024-03-12T15:37:24Z",
+        "version": "20240312153724",
+        "products": {
+            "S3SKU": {"sku": "S3SKU", "productFamily": "Storage",
+                "attributes": {"storageClass": "General Purpose", "volumeType": "Standard"}}
+        },
+        "terms": {"OnDemand": {}, "Reserved": {}}
+    }"#;
+
+    #[test]
+    fn test_dynamic_schema() {
+        let response: PricingListResponse = serde_json::from_str(RESPONSE_BODY).unwrap();
+        let mut registry = SchemaRegistry::builtin();
+        assert!(registry.validate("AmazonS3", &response).is_ok());
+
+        registry.register(DynamicSchema::new(
+            "AmazonS3",
+            vec!["storageClass".to_string(), "usagetype".to_string()],
+        ));
+        match registry.validate("AmazonS3", &response) {
+            Err(SchemaError::InvalidProduct { sku, message, .. }) => {
+                assert_eq!(sku, "S3SKU");
+                assert_eq!(message, "missing attributes usagetype");
+            }
+            Ok(()) => panic!("expected a missing attribute"),
+        }
+        // Typed schemas are checked by deserializing, Lambda products need a usage type
+        assert!(registry.validate("AWSLambda", &response).is_err());
+    }
+}
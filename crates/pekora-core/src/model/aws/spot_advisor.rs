@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SpotAdvisorResponse {
+    pub global_rate: String,
+    pub instance_types: HashMap<String, SpotInstanceType>,
+    /// Interruption frequency buckets referenced by `SpotAdvice::interruption_range`
+    pub ranges: Vec<InterruptionRange>,
+    /// Region to operating system (`Linux` or `Windows`) to instance type
+    pub spot_advisor: HashMap<String, HashMap<String, HashMap<String, SpotAdvice>>>,
+}
+
+impl SpotAdvisorResponse {
+    pub fn range(&self, index: i32) -> Option<&InterruptionRange> {
+        self.ranges.iter().find(|range| range.index == index)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SpotInstanceType {
+    pub emr: bool,
+    pub cores: i32,
+    pub ram_gb: f64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct InterruptionRange {
+    pub index: i32,
+    /// e.g. `<5%` or `>20%`
+    pub label: String,
+    pub dots: i32,
+    /// Upper bound of the monthly interruption frequency in percent
+    pub max: i32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SpotAdvice {
+    /// Savings over on-demand in percent
+    #[serde(rename = "s")]
+    pub savings: i32,
+    #[serde(rename = "r")]
+    pub interruption_range: i32,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    pub(crate) const SPOT_ADVISOR_BODY: &str = r#"{
+        "global_rate": "<10%",
+        "instance_types": {
+            "m5.large": {"emr": true, "cores": 2, "ram_gb": 8.0},
+            "c5.xlarge": {"emr": true, "cores": 4, "ram_gb": 8.0}
+        },
+        "ranges": [
+            {"index": 0, "label": "<5%", "dots": 0, "max": 5},
+            {"index": 1, "label": "5-10%", "dots": 1, "max": 11},
+            {"index": 4, "label": ">20%", "dots": 4, "max": 100}
+        ],
+        "spot_advisor": {
+            "us-east-1": {
+                "Linux": {"m5.large": {"s": 55, "r": 1}, "c5.xlarge": {"s": 62, "r": 4}},
+                "Windows": {"m5.large": {"s": 40, "r": 0}}
+            }
+        }
+    }"#;
+}
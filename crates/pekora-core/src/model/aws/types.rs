@@ -0,0 +1,797 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+pub use crate::model::aws::unit::Unit;
+
+/// Value of a lenient enum that matched none of its variants, e.g. a new purchase option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownValue {
+    pub type_name: &'static str,
+    pub value: String,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("unknown {} value: {} ({} unknown values in total)", .0[0].type_name, .0[0].value, .0.len())]
+pub struct UnknownValuesError(pub Vec<UnknownValue>);
+
+/// Types holding lenient enums. Parsing always keeps unknown values as `Unknown` variants; strict
+/// callers reject them afterwards with `check_known`.
+pub trait KnownValues {
+    /// Appends the unknown enum values of `self`.
+    fn collect_unknown(&self, unknown: &mut Vec<UnknownValue>);
+
+    /// Fails on any unknown enum value if `strict`, e.g. to notice values AWS added.
+    fn check_known(&self, strict: bool) -> Result<(), UnknownValuesError> {
+        if !strict {
+            return Ok(());
+        }
+        let mut unknown = Vec::new();
+        self.collect_unknown(&mut unknown);
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(UnknownValuesError(unknown))
+        }
+    }
+}
+
+impl KnownValues for String {
+    fn collect_unknown(&self, _unknown: &mut Vec<UnknownValue>) {}
+}
+
+impl<T: KnownValues> KnownValues for Option<T> {
+    fn collect_unknown(&self, unknown: &mut Vec<UnknownValue>) {
+        if let Some(value) = self {
+            value.collect_unknown(unknown);
+        }
+    }
+}
+
+impl<T: KnownValues> KnownValues for Vec<T> {
+    fn collect_unknown(&self, unknown: &mut Vec<UnknownValue>) {
+        for value in self {
+            value.collect_unknown(unknown);
+        }
+    }
+}
+
+impl<K, V: KnownValues> KnownValues for HashMap<K, V> {
+    fn collect_unknown(&self, unknown: &mut Vec<UnknownValue>) {
+        for value in self.values() {
+            value.collect_unknown(unknown);
+        }
+    }
+}
+
+/// Implements (de)serialization of enums whose values AWS may extend. Unknown values become
+/// `Unknown(value)`, which `KnownValues::check_known` rejects in strict mode. The first listed
+/// value of each variant is what it serializes to.
+macro_rules! lenient_enum {
+    ($name:ident { $($variant:ident => $canonical:literal $(| $alias:literal)*),+ $(,)? }) => {
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($name::$variant => $canonical,)+
+                    $name::Unknown(value) => value,
+                }
+            }
+
+            /// Variant named by `value` or one of its aliases, else `Unknown`.
+            pub fn parse(value: &str) -> Self {
+                match value {
+                    $($canonical $(| $alias)* => $name::$variant,)+
+                    _ => $name::Unknown(value.to_string()),
+                }
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                Ok($name::parse(&value))
+            }
+        }
+
+        impl KnownValues for $name {
+            fn collect_unknown(&self, unknown: &mut Vec<UnknownValue>) {
+                if let $name::Unknown(value) = self {
+                    unknown.push(UnknownValue {
+                        type_name: stringify!($name),
+                        value: value.clone(),
+                    });
+                }
+            }
+        }
+    };
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContractLength {
+    OneYear,
+    ThreeYear,
+    Unknown(String),
+}
+
+lenient_enum!(ContractLength {
+    OneYear => "OneYear" | "1yr" | "1 yr",
+    ThreeYear => "ThreeYear" | "3yr" | "3 yr",
+});
+
+impl ContractLength {
+    /// Term of a duration in seconds as the AWS APIs give it, `None` if not a whole term.
+    pub fn from_seconds(seconds: i64) -> Option<Self> {
+        const SECONDS_PER_YEAR: i64 = 31_536_000;
+        match seconds {
+            SECONDS_PER_YEAR => Some(Self::OneYear),
+            seconds if seconds == 3 * SECONDS_PER_YEAR => Some(Self::ThreeYear),
+            _ => None,
+        }
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PurchaseOption {
+    NoUpfront,
+    PartialUpfront,
+    AllUpfront,
+    Unknown(String),
+}
+
+lenient_enum!(PurchaseOption {
+    NoUpfront => "NoUpfront" | "No Upfront",
+    PartialUpfront => "PartialUpfront" | "Partial Upfront",
+    AllUpfront => "AllUpfront" | "All Upfront",
+});
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RIOfferingClass {
+    Standard,
+    Convertible,
+    Unknown(String),
+}
+
+lenient_enum!(RIOfferingClass {
+    Standard => "standard",
+    Convertible => "convertible",
+});
+
+/// Kind of savings plan, from the product family of a savings plan product. Compute plans apply
+/// to any instance family in any region, EC2 Instance plans to one family in one region at a
+/// deeper discount.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SavingsPlanType {
+    Compute,
+    Ec2Instance,
+    Unknown(String),
+}
+
+lenient_enum!(SavingsPlanType {
+    Compute => "ComputeSavingsPlans" | "Compute",
+    Ec2Instance => "EC2InstanceSavingsPlans" | "EC2Instance",
+});
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Currency {
+    #[allow(clippy::upper_case_acronyms)]
+    USD,
+    Unknown(String),
+}
+
+lenient_enum!(Currency {
+    USD => "USD",
+});
+
+/// Kind of location a SKU is priced in. Local Zones, Wavelength Zones and Outposts share the
+/// region code of their parent region, so this is the only way to tell them apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum LocationType {
+    #[serde(alias = "AWS Region")]
+    Region,
+    #[serde(alias = "AWS Local Zone")]
+    LocalZone,
+    #[serde(alias = "AWS Wavelength Zone")]
+    WavelengthZone,
+    #[serde(alias = "AWS Outposts")]
+    Outposts,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceOffering<TA: Debug + Clone> {
+    pub offer_term_code: String,
+    pub sku: String,
+    pub effective_date: DateTime<Utc>,
+    pub price_dimensions: HashMap<String, PriceDimension>,
+    pub term_attributes: TA,
+}
+
+impl<TA: Debug + Clone + KnownValues> KnownValues for PriceOffering<TA> {
+    fn collect_unknown(&self, unknown: &mut Vec<UnknownValue>) {
+        self.term_attributes.collect_unknown(unknown);
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceDimension {
+    pub rate_code: String,
+    pub description: String,
+    pub unit: String,
+    pub price_per_unit: HashMap<String, Price>,
+    /// Usage range of tiered prices, e.g. `"0"` to `"6000000000"` GB-seconds.
+    #[serde(default)]
+    pub begin_range: Option<String>,
+    #[serde(default)]
+    pub end_range: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RITermAttributes {
+    #[serde(rename = "LeaseContractLength")]
+    pub lease_contract_length: ContractLength,
+    /// Not set on services without convertible reservations, e.g. ElastiCache.
+    #[serde(
+        rename = "OfferingClass",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub offering_class: Option<RIOfferingClass>,
+    #[serde(rename = "PurchaseOption")]
+    pub purchase_option: PurchaseOption,
+}
+
+impl KnownValues for RITermAttributes {
+    fn collect_unknown(&self, unknown: &mut Vec<UnknownValue>) {
+        self.lease_contract_length.collect_unknown(unknown);
+        self.offering_class.collect_unknown(unknown);
+        self.purchase_option.collect_unknown(unknown);
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavingPlanProduct {
+    pub sku: String,
+    pub product_family: SavingsPlanType,
+    pub service_code: String,
+    pub usage_type: String,
+    pub operation: String,
+    pub attributes: SavingsPlanProductAttributes,
+}
+
+impl KnownValues for SavingPlanProduct {
+    fn collect_unknown(&self, unknown: &mut Vec<UnknownValue>) {
+        self.product_family.collect_unknown(unknown);
+        self.attributes.collect_unknown(unknown);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavingsPlanProductAttributes {
+    pub purchase_option: PurchaseOption,
+    pub product_family: SavingsPlanType,
+    pub region_code: Option<String>,
+    pub service_code: String,
+    pub granularity: String,
+    /// Instance family of EC2 Instance Savings Plans, e.g. `m5`
+    pub instance_type: Option<String>,
+    pub location_type: LocationType,
+    pub purchase_term: ContractLength,
+    pub location: String,
+    pub usage_type: String,
+}
+
+impl KnownValues for SavingsPlanProductAttributes {
+    fn collect_unknown(&self, unknown: &mut Vec<UnknownValue>) {
+        self.purchase_option.collect_unknown(unknown);
+        self.product_family.collect_unknown(unknown);
+        self.purchase_term.collect_unknown(unknown);
+    }
+}
+
+/// Attributes of RDS pricing list products. Fields not common to all RDS product families are
+/// optional, e.g. storage products have no instance type.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RdsProductAttributes {
+    pub database_engine: Option<String>,
+    pub deployment_option: Option<String>,
+    pub instance_type: Option<String>,
+    pub license_model: Option<String>,
+    pub storage: Option<String>,
+    /// e.g. `USE1-InstanceUsageIOOptimized:db.r6g.lg` or `USE1-Aurora:StorageIOUsage`
+    #[serde(rename = "usagetype")]
+    pub usage_type: Option<String>,
+    /// Of storage products, e.g. `General Purpose-GP3` or `IO Optimized-Aurora`
+    pub volume_type: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+/// Attributes of ElastiCache pricing list products.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElastiCacheProductAttributes {
+    pub cache_engine: Option<String>,
+    pub instance_type: Option<String>,
+    pub memory: Option<String>,
+    pub cache_node_type: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+impl ElastiCacheProductAttributes {
+    /// Node type such as `cache.r6g.large`, matching the keys of `ElasticacheClient` parameters.
+    pub fn node_type(&self) -> Option<&str> {
+        self.cache_node_type
+            .as_deref()
+            .or(self.instance_type.as_deref())
+    }
+}
+
+/// Attributes of AmazonRedshift pricing list products. Node products have an instance type
+/// such as `ra3.xlplus`; managed storage and serverless products do not.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedshiftProductAttributes {
+    pub instance_type: Option<String>,
+    pub vcpu: Option<String>,
+    /// e.g. `32 GiB`
+    pub memory: Option<String>,
+    /// e.g. `32TB RMS`
+    pub storage: Option<String>,
+    #[serde(rename = "usagetype")]
+    pub usage_type: Option<String>,
+    pub region_code: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+/// Attributes of AmazonES (OpenSearch Service) pricing list products. Node products have an
+/// instance type such as `r6g.large.search`; volume and storage products do not.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenSearchProductAttributes {
+    pub instance_type: Option<String>,
+    pub vcpu: Option<String>,
+    /// e.g. `16`
+    pub memory_gib: Option<String>,
+    /// e.g. `EBS Only`
+    pub storage: Option<String>,
+    #[serde(rename = "usagetype")]
+    pub usage_type: Option<String>,
+    pub region_code: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+/// Attributes of AmazonMemoryDB pricing list products. Node products have an instance type such
+/// as `db.r6g.large`; data written products do not.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryDbProductAttributes {
+    pub instance_type: Option<String>,
+    pub vcpu: Option<String>,
+    /// e.g. `13.07 GiB`
+    pub memory: Option<String>,
+    /// e.g. `USE1-NodeUsage:db.r6g.large`
+    #[serde(rename = "usagetype")]
+    pub usage_type: Option<String>,
+    pub region_code: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+/// Attributes of AmazonDocDB (DocumentDB) pricing list products. Instance products have an
+/// instance type such as `db.r6g.large`; storage, I/O and backup products do not.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocDbProductAttributes {
+    pub instance_type: Option<String>,
+    pub vcpu: Option<String>,
+    /// e.g. `16 GiB`
+    pub memory: Option<String>,
+    /// e.g. `USE1-InstanceUsage:db.r6g.large`, or `USE1-InstanceUsageIOOptimized:db.r6g.large`
+    /// for clusters on I/O-Optimized storage
+    #[serde(rename = "usagetype")]
+    pub usage_type: Option<String>,
+    pub region_code: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+impl DocDbProductAttributes {
+    /// Storage configuration the instance price applies to, `None` for other products.
+    pub fn storage_configuration(&self) -> Option<&'static str> {
+        let (usage, _) = self.usage_type.as_deref()?.split_once(':')?;
+        if usage.ends_with("IOOptimized") {
+            Some("I/O-Optimized")
+        } else if usage.ends_with("InstanceUsage") {
+            Some("Standard")
+        } else {
+            None
+        }
+    }
+}
+
+/// Attributes of AmazonSageMaker pricing list products. Instance products name an ML instance
+/// type such as `ml.m5.large`, priced separately for each component that runs on it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SageMakerProductAttributes {
+    /// e.g. `ml.m5.large`
+    pub instance_name: Option<String>,
+    /// e.g. `Training` or `Hosting`
+    pub component: Option<String>,
+    pub v_cpu: Option<String>,
+    /// e.g. `8 GiB`
+    pub memory: Option<String>,
+    pub gpu: Option<String>,
+    /// e.g. `USE1-Train:ml.m5.large`
+    #[serde(rename = "usagetype")]
+    pub usage_type: Option<String>,
+    pub region_code: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+impl SageMakerProductAttributes {
+    pub fn component(&self) -> Option<SageMakerComponent> {
+        SageMakerComponent::from_usage_type(self.usage_type.as_deref()?)
+    }
+}
+
+/// What a SageMaker ML instance is billed for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SageMakerComponent {
+    Training,
+    Hosting,
+    Notebook,
+}
+
+impl SageMakerComponent {
+    /// Component of a SageMaker instance usage type, e.g. `Training` for
+    /// `USE1-Train:ml.m5.large`. Usage types of other components, such as processing jobs or
+    /// Studio notebooks, are `None`.
+    pub fn from_usage_type(usage_type: &str) -> Option<Self> {
+        let (operation, _) = usage_type.split_once(':')?;
+        // Usage types of us-east-1 have no region prefix
+        match operation.rsplit('-').next()? {
+            "Train" => Some(Self::Training),
+            "Host" => Some(Self::Hosting),
+            "Notebk" => Some(Self::Notebook),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Training => "Training",
+            Self::Hosting => "Hosting",
+            Self::Notebook => "Notebook",
+        }
+    }
+}
+
+/// Attributes of AWSLambda pricing list products. Prices are metered by usage type, e.g.
+/// `USE1-Lambda-GB-Second` or `USE1-Request-ARM`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LambdaProductAttributes {
+    #[serde(rename = "usagetype")]
+    pub usage_type: String,
+    pub group: Option<String>,
+    pub group_description: Option<String>,
+    pub region_code: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+/// Attributes of AmazonECS pricing list products, which are Fargate vCPU, memory and storage.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FargateProductAttributes {
+    #[serde(rename = "usagetype")]
+    pub usage_type: String,
+    #[serde(rename = "cputype")]
+    pub cpu_type: Option<String>,
+    #[serde(rename = "memorytype")]
+    pub memory_type: Option<String>,
+    pub operating_system: Option<String>,
+    pub cpu_architecture: Option<String>,
+    pub region_code: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+/// Attributes of AmazonEKS pricing list products, mostly cluster hours.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EksProductAttributes {
+    #[serde(rename = "usagetype")]
+    pub usage_type: String,
+    pub operation: Option<String>,
+    pub tiertype: Option<String>,
+    pub region_code: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+/// Attributes of AWSDataTransfer pricing list products, each a direction between two locations,
+/// e.g. from `Asia Pacific (Tokyo)` to `External` for internet egress.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataTransferProductAttributes {
+    /// e.g. `AWS Outbound`, `AWS Inbound` or `InterRegion Outbound`
+    pub transfer_type: Option<String>,
+    pub from_location: Option<String>,
+    pub from_location_type: Option<String>,
+    pub from_region_code: Option<String>,
+    pub to_location: Option<String>,
+    pub to_location_type: Option<String>,
+    pub to_region_code: Option<String>,
+    #[serde(rename = "usagetype")]
+    pub usage_type: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+/// Attributes of network infrastructure products: AmazonVPC endpoints, AWSELB load balancers and
+/// the NAT gateways of AmazonEC2. Hourly and data processing charges of a resource are told apart
+/// by usage type, e.g. `USE1-NatGateway-Hours` and `USE1-NatGateway-Bytes`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkProductAttributes {
+    #[serde(rename = "usagetype")]
+    pub usage_type: Option<String>,
+    /// e.g. `LoadBalancing:Application` or `NatGateway`
+    pub operation: Option<String>,
+    pub location_type: Option<String>,
+    pub region_code: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+/// Attributes of AmazonCloudWatch pricing list products, which cover CloudWatch Logs too.
+/// Prices are metered by usage type, e.g. `USE1-CW:MetricMonitorUsage` or
+/// `USE1-TimedStorage-ByteHrs`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloudWatchProductAttributes {
+    #[serde(rename = "usagetype")]
+    pub usage_type: Option<String>,
+    /// e.g. `Metric` or `Alarm`
+    pub group: Option<String>,
+    pub group_description: Option<String>,
+    pub location_type: Option<String>,
+    pub region_code: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+/// Attributes of AmazonEC2 pricing list products as far as EBS volumes go. Storage, IOPS and
+/// throughput products of a volume type share its `volumeApiName`, e.g. `gp3`, and are told
+/// apart by usage type.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EbsProductAttributes {
+    #[serde(rename = "usagetype")]
+    pub usage_type: Option<String>,
+    pub volume_api_name: Option<String>,
+    pub volume_type: Option<String>,
+    pub region_code: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavingsPlanTerms {
+    pub savings_plan: Vec<SavingsPlanTerm>,
+}
+
+impl KnownValues for SavingsPlanTerms {
+    fn collect_unknown(&self, unknown: &mut Vec<UnknownValue>) {
+        for term in &self.savings_plan {
+            for rate in &term.rates {
+                rate.discounted_rate.currency.collect_unknown(unknown);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavingsPlanTerm {
+    pub sku: String,
+    pub description: String,
+    pub effective_date: DateTime<Utc>,
+    pub lease_contract_length: LeaseContractLength,
+    pub rates: Vec<SavingsPlanTermRate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LeaseContractLength {
+    pub duration: i32,
+    pub unit: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavingsPlanTermRate {
+    pub discounted_sku: String,
+    pub discounted_usage_type: String,
+    pub discounted_operation: String,
+    pub discounted_service_code: String,
+    pub rate_code: String,
+    pub unit: String,
+    pub discounted_rate: DiscountedRate,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DiscountedRate {
+    pub price: Price,
+    pub currency: Currency,
+}
+
+impl DiscountedRate {
+    pub fn usd(&self) -> Option<Decimal> {
+        match self.currency {
+            Currency::USD => self.price.value(),
+            _ => None,
+        }
+    }
+}
+
+impl PriceDimension {
+    pub fn usd(&self) -> Option<Decimal> {
+        self.price_per_unit.get("USD").and_then(Price::value)
+    }
+
+    pub fn parsed_unit(&self) -> Unit {
+        Unit::parse(&self.unit)
+    }
+
+    /// Lower bound of the usage tier, zero for untiered prices.
+    pub fn begin(&self) -> Decimal {
+        self.begin_range
+            .as_deref()
+            .and_then(|range| Decimal::from_str(range).ok())
+            .unwrap_or_default()
+    }
+
+    /// Upper bound of the usage tier, `None` if the tier is unbounded (`"Inf"`).
+    pub fn end(&self) -> Option<Decimal> {
+        self.end_range
+            .as_deref()
+            .and_then(|range| Decimal::from_str(range).ok())
+    }
+
+    pub fn is_tiered(&self) -> bool {
+        self.begin_range.is_some() || self.end_range.is_some()
+    }
+}
+
+/// A price as published, e.g. `"0.0960000000"`, along with its decimal value. Serializes back
+/// to the original string so cached files round-trip exactly.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Price {
+    raw: String,
+    value: Option<Decimal>,
+}
+
+impl Price {
+    pub fn new(raw: impl Into<String>) -> Self {
+        let raw = raw.into();
+        let value = Decimal::from_str(&raw)
+            .or_else(|_| Decimal::from_scientific(&raw))
+            .ok();
+        Self { raw, value }
+    }
+
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// `None` if the published price is not a number.
+    pub fn value(&self) -> Option<Decimal> {
+        self.value
+    }
+}
+
+impl Debug for Price {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.raw, f)
+    }
+}
+
+impl Display for Price {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl Serialize for Price {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.raw)
+    }
+}
+
+impl<'de> Deserialize<'de> for Price {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Price::new(String::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ContractLength, KnownValues, Price, PriceDimension, PurchaseOption, RITermAttributes,
+    };
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_unknown_enum_values() {
+        let known: PurchaseOption = serde_json::from_str(r#""No Upfront""#).unwrap();
+        assert_eq!(known, PurchaseOption::NoUpfront);
+        assert_eq!(serde_json::to_string(&known).unwrap(), r#""NoUpfront""#);
+
+        let unknown: PurchaseOption = serde_json::from_str(r#""Light Utilization""#).unwrap();
+        assert_eq!(
+            unknown,
+            PurchaseOption::Unknown("Light Utilization".to_string())
+        );
+        assert_eq!(
+            serde_json::to_string(&unknown).unwrap(),
+            r#""Light Utilization""#
+        );
+        let length: ContractLength = serde_json::from_str(r#""3 yr""#).unwrap();
+        assert_eq!(length, ContractLength::ThreeYear);
+
+        let attributes: RITermAttributes = serde_json::from_str(
+            r#"{"LeaseContractLength": "5yr", "OfferingClass": "standard",
+                "PurchaseOption": "Light Utilization"}"#,
+        )
+        .unwrap();
+        assert!(attributes.check_known(false).is_ok());
+        let error = attributes.check_known(true).unwrap_err();
+        assert_eq!(error.0.len(), 2);
+        assert_eq!(error.0[0].type_name, "ContractLength");
+        assert_eq!(error.0[0].value, "5yr");
+        assert!(known.check_known(true).is_ok());
+    }
+
+    #[test]
+    fn test_price() {
+        let dimension: PriceDimension = serde_json::from_str(
+            r#"{"rateCode": "A.B.C", "description": "", "unit": "Hrs",
+                "pricePerUnit": {"USD": "0.0960000000", "CNY": "n/a"}}"#,
+        )
+        .unwrap();
+        assert_eq!(dimension.usd(), Some(Decimal::from_str("0.096").unwrap()));
+        assert_eq!(dimension.price_per_unit["CNY"].value(), None);
+        assert_eq!(
+            serde_json::to_value(&dimension.price_per_unit["USD"]).unwrap(),
+            "0.0960000000"
+        );
+        assert_eq!(
+            Price::new("1.5E-7").value(),
+            Some(Decimal::from_str("0.00000015").unwrap())
+        );
+    }
+}
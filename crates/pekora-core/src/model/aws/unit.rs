@@ -0,0 +1,149 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+
+/// Hours per month as used by Cost Explorer estimates and monthly prices.
+pub const HOURS_PER_MONTH: i64 = 730;
+
+const SECONDS_PER_HOUR: i64 = 3600;
+
+/// Unit of a price dimension. Units we don't know are kept as `Other` with the original string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Unit {
+    /// `Hrs`
+    Hours,
+    Seconds,
+    /// Upfront fees of reservations
+    Quantity,
+    GbMonth,
+    GbHours,
+    GbSeconds,
+    VcpuHours,
+    Requests,
+    Gb,
+    Other(String),
+}
+
+/// Period a time based unit charges for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Second,
+    Hour,
+    Month,
+}
+
+impl Period {
+    fn seconds(&self) -> Decimal {
+        match self {
+            Period::Second => Decimal::ONE,
+            Period::Hour => Decimal::from(SECONDS_PER_HOUR),
+            Period::Month => Decimal::from(HOURS_PER_MONTH * SECONDS_PER_HOUR),
+        }
+    }
+}
+
+impl Unit {
+    /// Parses units as price lists write them, e.g. `Hrs`, `GB-Mo` or `vCPU-Hours`.
+    pub fn parse(unit: &str) -> Self {
+        match unit {
+            "Hrs" | "Hours" | "Hour" | "hours" => Unit::Hours,
+            "Second" | "Seconds" | "seconds" => Unit::Seconds,
+            "Quantity" => Unit::Quantity,
+            "GB-Mo" | "GB-month" | "GB-Month" => Unit::GbMonth,
+            "GB-Hours" | "GB-Hour" => Unit::GbHours,
+            "GB-Seconds" | "GB-Second" | "Lambda-GB-Second" => Unit::GbSeconds,
+            "vCPU-Hours" | "vCPU-hour" | "vCPU-Hour" => Unit::VcpuHours,
+            "Requests" | "Request" | "requests" => Unit::Requests,
+            "GB" => Unit::Gb,
+            other => Unit::Other(other.to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Unit::Hours => "Hrs",
+            Unit::Seconds => "Second",
+            Unit::Quantity => "Quantity",
+            Unit::GbMonth => "GB-Mo",
+            Unit::GbHours => "GB-Hours",
+            Unit::GbSeconds => "GB-Seconds",
+            Unit::VcpuHours => "vCPU-Hours",
+            Unit::Requests => "Requests",
+            Unit::Gb => "GB",
+            Unit::Other(unit) => unit,
+        }
+    }
+
+    /// Period the unit charges for, `None` for units not based on time.
+    pub fn period(&self) -> Option<Period> {
+        match self {
+            Unit::Hours | Unit::GbHours | Unit::VcpuHours => Some(Period::Hour),
+            Unit::Seconds | Unit::GbSeconds => Some(Period::Second),
+            Unit::GbMonth => Some(Period::Month),
+            _ => None,
+        }
+    }
+
+    /// Converts `price` per unit to the same price per `period`, e.g. per second to per hour.
+    /// `None` for units not based on time.
+    pub fn price_per(&self, price: Decimal, period: Period) -> Option<Decimal> {
+        let own = self.period()?;
+        Some(price * period.seconds() / own.seconds())
+    }
+
+    pub fn price_per_hour(&self, price: Decimal) -> Option<Decimal> {
+        self.price_per(price, Period::Hour)
+    }
+
+    pub fn price_per_month(&self, price: Decimal) -> Option<Decimal> {
+        self.price_per(price, Period::Month)
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Unit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Unit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Unit::parse(&String::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Period, Unit};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_unit() {
+        assert_eq!(Unit::parse("Hrs"), Unit::Hours);
+        assert_eq!(Unit::parse("Lambda-GB-Second"), Unit::GbSeconds);
+        assert_eq!(Unit::parse("IOPS-Mo"), Unit::Other("IOPS-Mo".to_string()));
+        assert_eq!(Unit::parse("IOPS-Mo").as_str(), "IOPS-Mo");
+
+        let hourly = Decimal::new(1, 1);
+        assert_eq!(Unit::Hours.price_per_month(hourly), Some(Decimal::from(73)));
+        assert_eq!(
+            Unit::GbMonth.price_per_hour(Decimal::from(73)),
+            Some(Decimal::new(1, 1))
+        );
+        assert_eq!(
+            Unit::Seconds.price_per_hour(Decimal::new(1, 3)),
+            Some(Decimal::new(36, 1))
+        );
+        assert_eq!(
+            Unit::Hours.price_per(Decimal::from(36), Period::Second),
+            Some(Decimal::new(1, 2))
+        );
+        assert_eq!(Unit::Quantity.price_per_hour(hourly), None);
+    }
+}
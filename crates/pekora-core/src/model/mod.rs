@@ -0,0 +1,3 @@
+/// Vendor price list types, shared by the clients that fetch them and the transforms that
+/// read them
+pub mod aws;
@@ -0,0 +1,189 @@
+//! Version of the serialized form of pekora's public types: the price list responses in
+//! `model`, which are also what the file cache stores, and the rows and reports of `transform`.
+//!
+//! Within a version, changes are additive only: new optional fields, or fields with a serde
+//! default. Output of older releases of the same version keeps deserializing, and consumers
+//! should ignore fields they do not know. Renaming or removing a field, changing its type or
+//! unit, or renaming an enum value bumps `VERSION`.
+//!
+//! Every released version has fixtures under `fixtures/schema/v<version>`, in the form pekora
+//! serializes them, e.g. `OneYear` where the price list says `1yr`. When bumping, add the
+//! new version to `RELEASED` with its own fixtures and keep the old ones. Older fixtures must
+//! still deserialize into the current types, through serde aliases and defaults, which is the
+//! migration path for persisted output. Fixtures of the current version must round-trip:
+//! serializing them again keeps every field they have, while fields added since may appear too.
+//!
+//! Released fixtures are never edited, which `fixtures/schema/v<version>.sha256` pins. Samples of
+//! fields added within a version go into new fixture files, listed there as well.
+
+/// Current schema version.
+pub const VERSION: u32 = 1;
+
+/// Every released schema version, oldest first.
+pub const RELEASED: &[u32] = &[1];
+
+#[cfg(test)]
+mod tests {
+    use super::{RELEASED, VERSION};
+    use crate::model::aws::price_bulk_types::{
+        PricingListResponse, RegionIndexResponse, SavingsPlanListResponse, ServiceListResponse,
+    };
+    use crate::model::aws::spot_advisor::SpotAdvisorResponse;
+    use crate::transform::aws::architecture::ArchitectureComparison;
+    use crate::transform::aws::availability::UnavailableInstanceType;
+    use crate::transform::aws::break_even::BreakEvenRow;
+    use crate::transform::aws::cloudwatch::CloudWatchEstimate;
+    use crate::transform::aws::cost_explorer::CommitmentSummary;
+    use crate::transform::aws::cur::CurUsageReport;
+    use crate::transform::aws::data_transfer::TransferCost;
+    use crate::transform::aws::dedicated_host::HostSlotCost;
+    use crate::transform::aws::diff::OfferDiff;
+    use crate::transform::aws::ebs::VolumeCost;
+    use crate::transform::aws::elasticache::{ReservedNodeComparison, UsableMemoryPrice};
+    use crate::transform::aws::estimate::CostEstimate;
+    use crate::transform::aws::gpu::GpuPriceRow;
+    use crate::transform::aws::instance_specs::EnrichedPriceRow;
+    use crate::transform::aws::node_pricing::NodePrice;
+    use crate::transform::aws::normalize::NormalizedPriceRow;
+    use crate::transform::aws::optimize::CommitmentPlan;
+    use crate::transform::aws::orderable::OrderableInstancePrice;
+    use crate::transform::aws::rds_reserved::ReservedOfferingCheck;
+    use crate::transform::aws::rds_storage::{AuroraClusterCost, RdsStorageCost};
+    use crate::transform::aws::recommendation::{
+        ReservedInstanceRecommendation, SavingsPlanRecommendation,
+    };
+    use crate::transform::aws::rightsizing::RightsizingSaving;
+    use crate::transform::aws::savings_plan_rates::SavingsPlanRateCheck;
+    use crate::transform::aws::simulate::SimulationReport;
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    use serde_json::Value;
+    use sha2::{Digest, Sha256};
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    /// Whether `actual` has every field of `expected` with the same value.
+    fn contains(actual: &Value, expected: &Value) -> bool {
+        match (actual, expected) {
+            (Value::Object(actual), Value::Object(expected)) => {
+                expected.iter().all(|(key, expected)| {
+                    actual
+                        .get(key)
+                        .is_some_and(|actual| contains(actual, expected))
+                })
+            }
+            (Value::Array(actual), Value::Array(expected)) => {
+                actual.len() == expected.len()
+                    && actual
+                        .iter()
+                        .zip(expected)
+                        .all(|(actual, expected)| contains(actual, expected))
+            }
+            (actual, expected) => actual == expected,
+        }
+    }
+
+    fn check<T: DeserializeOwned + Serialize>(version: u32, name: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join(format!("fixtures/schema/v{}/{}.json", version, name));
+        let fixture: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let value: T = serde_json::from_value(fixture.clone())
+            .unwrap_or_else(|e| panic!("{} no longer deserializes: {}", path.display(), e));
+        if version == VERSION {
+            let serialized = serde_json::to_value(&value).unwrap();
+            assert!(
+                contains(&serialized, &fixture),
+                "{} does not round-trip: {}",
+                path.display(),
+                serialized
+            );
+        }
+    }
+
+    #[test]
+    fn test_released_fixtures_unchanged() {
+        let schema = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/schema");
+        for &version in RELEASED {
+            let sums_path = schema.join(format!("v{}.sha256", version));
+            let sums = std::fs::read_to_string(&sums_path).unwrap();
+            let pinned = sums
+                .lines()
+                .map(|line| {
+                    let (sum, name) = line.split_once("  ").unwrap();
+                    (name.to_string(), sum.to_string())
+                })
+                .collect::<BTreeMap<_, _>>();
+            let mut hashed = BTreeMap::new();
+            for entry in std::fs::read_dir(schema.join(format!("v{}", version))).unwrap() {
+                let path = entry.unwrap().path();
+                let sum = Sha256::digest(std::fs::read(&path).unwrap())
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect::<String>();
+                let name = path.file_name().unwrap().to_string_lossy().to_string();
+                hashed.insert(name, sum);
+            }
+            for (name, sum) in &pinned {
+                assert_eq!(
+                    hashed.get(name),
+                    Some(sum),
+                    "released fixture v{}/{} changed or was removed, add a new fixture instead",
+                    version,
+                    name
+                );
+            }
+            for name in hashed.keys() {
+                assert!(
+                    pinned.contains_key(name),
+                    "v{}/{} is missing from {}",
+                    version,
+                    name,
+                    sums_path.display()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_released_schemas() {
+        assert_eq!(RELEASED.last(), Some(&VERSION));
+        for &version in RELEASED {
+            check::<ServiceListResponse>(version, "service_index");
+            check::<RegionIndexResponse>(version, "region_index");
+            check::<PricingListResponse>(version, "pricing_list");
+            check::<SavingsPlanListResponse>(version, "savings_plan_list");
+            check::<SpotAdvisorResponse>(version, "spot_advisor");
+            check::<Vec<NormalizedPriceRow>>(version, "normalized_price_rows");
+            check::<Vec<NormalizedPriceRow>>(version, "sagemaker_price_rows");
+            check::<Vec<EnrichedPriceRow>>(version, "enriched_price_rows");
+            check::<CostEstimate>(version, "cost_estimate");
+            check::<CostEstimate>(version, "cost_estimate_network");
+            check::<SavingsPlanRecommendation>(version, "savings_plan_recommendation");
+            check::<ReservedInstanceRecommendation>(version, "reserved_instance_recommendation");
+            check::<CommitmentPlan>(version, "commitment_plan");
+            check::<Vec<BreakEvenRow>>(version, "break_even_rows");
+            check::<SimulationReport>(version, "simulation_report");
+            check::<OfferDiff>(version, "offer_diff");
+            check::<OfferDiff>(version, "offer_diff_restored");
+            check::<Vec<GpuPriceRow>>(version, "gpu_price_rows");
+            check::<Vec<ArchitectureComparison>>(version, "architecture_comparisons");
+            check::<VolumeCost>(version, "volume_cost");
+            check::<TransferCost>(version, "transfer_cost");
+            check::<Vec<AuroraClusterCost>>(version, "aurora_cluster_costs");
+            check::<RdsStorageCost>(version, "rds_storage_cost");
+            check::<Vec<ReservedNodeComparison>>(version, "reserved_node_comparisons");
+            check::<Vec<UsableMemoryPrice>>(version, "usable_memory_prices");
+            check::<Vec<OrderableInstancePrice>>(version, "orderable_instance_prices");
+            check::<Vec<ReservedOfferingCheck>>(version, "reserved_offering_checks");
+            check::<Vec<UnavailableInstanceType>>(version, "unavailable_instance_types");
+            check::<Vec<SavingsPlanRateCheck>>(version, "savings_plan_rate_checks");
+            check::<CommitmentSummary>(version, "commitment_summary");
+            check::<CurUsageReport>(version, "cur_usage_report");
+            check::<Vec<RightsizingSaving>>(version, "rightsizing_savings");
+            check::<Vec<NodePrice>>(version, "node_prices");
+            check::<Vec<HostSlotCost>>(version, "host_slot_costs");
+            check::<CloudWatchEstimate>(version, "cloudwatch_estimate");
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Directory inside the cache directory holding pekora's own bookkeeping files.
+pub const INTERNAL_DIRECTORY: &str = ".pekora";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryStatus {
+    pub category: String,
+    pub entries: usize,
+    pub bytes: u64,
+}
+
+/// Newest cache entry of a dataset, i.e. a category and content key pair.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetFreshness {
+    pub category: String,
+    pub content_key: String,
+    pub last_updated: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InFlightDownload {
+    pub category: String,
+    pub name: String,
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>,
+    pub last_updated: DateTime<Utc>,
+}
+
+impl InFlightDownload {
+    pub fn progress(&self) -> Option<f64> {
+        match self.total_bytes {
+            Some(total) if total > 0 => {
+                Some((self.downloaded_bytes as f64 / total as f64).min(1.0))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Snapshot of a cache directory.
+#[derive(Debug, Clone, Default)]
+pub struct CacheStatus {
+    pub categories: Vec<CategoryStatus>,
+    pub datasets: Vec<DatasetFreshness>,
+    pub downloads: Vec<InFlightDownload>,
+}
+
+impl CacheStatus {
+    pub fn scan(cache_directory: &Path) -> std::io::Result<Self> {
+        let mut categories: BTreeMap<String, CategoryStatus> = BTreeMap::new();
+        let mut datasets: BTreeMap<(String, String), DateTime<Utc>> = BTreeMap::new();
+        let mut downloads = Vec::new();
+
+        let mut pending = vec![cache_directory.to_path_buf()];
+        while let Some(directory) = pending.pop() {
+            let entries = match fs::read_dir(&directory) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            for entry in entries {
+                let entry = entry?;
+                let path = entry.path();
+                let metadata = entry.metadata()?;
+                let filename = entry.file_name().to_string_lossy().to_string();
+                if metadata.is_dir() {
+                    if filename != INTERNAL_DIRECTORY {
+                        pending.push(path);
+                    }
+                    continue;
+                }
+
+                let category = directory
+                    .strip_prefix(cache_directory)
+                    .unwrap_or(&directory)
+                    .to_string_lossy()
+                    .to_string();
+                let modified: DateTime<Utc> = metadata.modified()?.into();
+                if let Some(name) = filename.strip_suffix(".partial") {
+                    let total_bytes =
+                        fs::read_to_string(directory.join(format!("{}.length", filename)))
+                            .ok()
+                            .and_then(|length| length.trim().parse().ok());
+                    downloads.push(InFlightDownload {
+                        category,
+                        name: name.to_string(),
+                        downloaded_bytes: metadata.len(),
+                        total_bytes,
+                        last_updated: modified,
+                    });
+                    continue;
+                }
+                let stem = match filename.strip_suffix(".json") {
+                    Some(stem) => stem,
+                    None => continue,
+                };
+                let content_key = match stem.rsplit_once('_') {
+                    Some((content_key, _)) => content_key.to_string(),
+                    None => stem.to_string(),
+                };
+
+                let status = categories
+                    .entry(category.clone())
+                    .or_insert(CategoryStatus {
+                        category: category.clone(),
+                        entries: 0,
+                        bytes: 0,
+                    });
+                status.entries += 1;
+                status.bytes += metadata.len();
+                let last_updated = datasets.entry((category, content_key)).or_insert(modified);
+                if modified > *last_updated {
+                    *last_updated = modified;
+                }
+            }
+        }
+
+        Ok(Self {
+            categories: categories.into_values().collect(),
+            datasets: datasets
+                .into_iter()
+                .map(|((category, content_key), last_updated)| DatasetFreshness {
+                    category,
+                    content_key,
+                    last_updated,
+                })
+                .collect(),
+            downloads,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CacheStatus;
+
+    #[test]
+    fn test_scan() {
+        let directory = std::env::temp_dir().join(format!("pekora-status-{}", std::process::id()));
+        let category = directory.join("aws/bulk/pricing_list");
+        std::fs::create_dir_all(&category).unwrap();
+        std::fs::write(category.join("ap-northeast-1-AmazonEC2-1_abc.json"), "{}").unwrap();
+        std::fs::write(category.join("ap-northeast-1-AmazonEC2-1_def.json"), "{}").unwrap();
+        std::fs::write(category.join("us-east-1-AmazonEC2-1.partial"), "0123").unwrap();
+        std::fs::write(category.join("us-east-1-AmazonEC2-1.partial.length"), "8").unwrap();
+
+        let status = CacheStatus::scan(&directory).unwrap();
+        assert_eq!(status.categories.len(), 1);
+        assert_eq!(status.categories[0].category, "aws/bulk/pricing_list");
+        assert_eq!(status.categories[0].entries, 2);
+        assert_eq!(status.categories[0].bytes, 4);
+        assert_eq!(status.datasets.len(), 1);
+        assert_eq!(status.datasets[0].content_key, "ap-northeast-1-AmazonEC2-1");
+        assert_eq!(status.downloads.len(), 1);
+        assert_eq!(status.downloads[0].progress(), Some(0.5));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
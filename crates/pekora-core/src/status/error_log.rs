@@ -0,0 +1,59 @@
+use crate::status::INTERNAL_DIRECTORY;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorLogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub source: String,
+    pub message: String,
+}
+
+/// Append-only JSON Lines log of failures, kept in the cache directory.
+pub struct ErrorLog {
+    path: PathBuf,
+}
+
+impl ErrorLog {
+    pub fn new(cache_directory: &Path) -> Self {
+        Self {
+            path: cache_directory
+                .join(INTERNAL_DIRECTORY)
+                .join("errors.jsonl"),
+        }
+    }
+
+    pub fn record(&self, source: &str, message: &str) -> std::io::Result<()> {
+        if let Some(folder) = self.path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+        let entry = ErrorLogEntry {
+            timestamp: Utc::now(),
+            source: source.to_string(),
+            message: message.to_string(),
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)
+    }
+
+    /// Returns the `limit` most recent entries, newest first.
+    pub fn recent(&self, limit: usize) -> std::io::Result<Vec<ErrorLogEntry>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        Ok(contents
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .take(limit)
+            .collect())
+    }
+}
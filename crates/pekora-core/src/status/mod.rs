@@ -0,0 +1,24 @@
+/// On-disk state observable from outside the process doing the work
+mod cache;
+mod error_log;
+mod request_log;
+
+pub use cache::*;
+pub use error_log::*;
+pub use request_log::*;
+
+/// Formats a byte count with binary units, e.g. `1.5 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
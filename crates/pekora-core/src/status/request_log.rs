@@ -0,0 +1,143 @@
+use crate::status::INTERNAL_DIRECTORY;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestOutcome {
+    /// Content was received, or a HEAD request succeeded
+    Ok,
+    /// The cached copy matched upstream
+    NotModified,
+    Failed,
+}
+
+/// One request to upstream, enough to tell which version of a file a result was built from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestLogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub method: String,
+    pub url: String,
+    pub outcome: RequestOutcome,
+    /// ETag or normalized `Last-Modified` date of the response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Body bytes received
+    pub bytes: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Append-only JSON Lines log of upstream requests, kept in the cache directory.
+#[derive(Debug)]
+pub struct RequestLog {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl RequestLog {
+    pub fn new(cache_directory: &Path) -> Self {
+        Self {
+            path: cache_directory
+                .join(INTERNAL_DIRECTORY)
+                .join("requests.jsonl"),
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn record(&self, entry: &RequestLogEntry) -> std::io::Result<()> {
+        if let Some(folder) = self.path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+        let line = format!("{}\n", serde_json::to_string(entry)?);
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())
+    }
+
+    /// Entries at or after `since`, oldest first. Unparseable lines are skipped.
+    pub fn since(&self, since: DateTime<Utc>) -> std::io::Result<Vec<RequestLogEntry>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        Ok(contents
+            .lines()
+            .filter_map(|line| serde_json::from_str::<RequestLogEntry>(line).ok())
+            .filter(|entry| entry.timestamp >= since)
+            .collect())
+    }
+}
+
+/// Parses an RFC 3339 timestamp, a date such as `2024-03-01`, or an age such as `30m`, `12h`
+/// or `7d` relative to `now`.
+pub fn parse_since(value: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(date.and_hms_opt(0, 0, 0)?.and_utc());
+    }
+    let split = value.len().checked_sub(1)?;
+    let amount: i64 = value.get(..split)?.parse().ok()?;
+    let age = match value.get(split..)? {
+        "m" => Duration::try_minutes(amount)?,
+        "h" => Duration::try_hours(amount)?,
+        "d" => Duration::try_days(amount)?,
+        _ => return None,
+    };
+    now.checked_sub_signed(age)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_since, RequestLog, RequestLogEntry, RequestOutcome};
+    use chrono::{DateTime, Duration, Utc};
+
+    #[test]
+    fn test_request_log() {
+        let directory =
+            std::env::temp_dir().join(format!("pekora-request-log-{}", std::process::id()));
+        let log = RequestLog::new(&directory);
+        let now = Utc::now();
+        for (age, outcome) in [(48, RequestOutcome::Ok), (1, RequestOutcome::NotModified)] {
+            log.record(&RequestLogEntry {
+                timestamp: now - Duration::hours(age),
+                method: "GET".to_string(),
+                url: "https://pricing.us-east-1.amazonaws.com/offers/v1.0/aws/index.json"
+                    .to_string(),
+                outcome,
+                content_hash: Some("v1".to_string()),
+                bytes: 0,
+                error: None,
+            })
+            .unwrap();
+        }
+
+        let since = parse_since("1d", now).unwrap();
+        let entries = log.since(since).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].outcome, RequestOutcome::NotModified);
+        assert_eq!(log.since(now - Duration::days(3)).unwrap().len(), 2);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            parse_since("2024-03-01", now),
+            Some(
+                DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z")
+                    .unwrap()
+                    .with_timezone(&Utc)
+            )
+        );
+        assert_eq!(parse_since("30m", now), Some(now - Duration::minutes(30)));
+        assert_eq!(parse_since("soon", now), None);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::model::aws::types::{ContractLength, PurchaseOption};
+use crate::transform::aws::instance_specs::EnrichedPriceRow;
+use crate::transform::aws::normalize::PurchaseModel;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Price of a Graviton instance type next to its x86 equivalent under the same purchase model.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ArchitectureComparison {
+    pub region: Option<String>,
+    pub platform: Option<String>,
+    pub purchase_model: PurchaseModel,
+    pub term: Option<ContractLength>,
+    pub purchase_option: Option<PurchaseOption>,
+    pub arm_instance_type: String,
+    pub x86_instance_type: String,
+    pub arm_usd_per_hour: Decimal,
+    pub x86_usd_per_hour: Decimal,
+    /// Negative if the Graviton instance type is cheaper
+    pub delta_usd_per_hour: Decimal,
+    pub savings_percent: Decimal,
+    /// Throughput of the Graviton instance type relative to the x86 one
+    pub performance_ratio: Decimal,
+    /// Savings per unit of work, the Graviton price divided by `performance_ratio`
+    pub adjusted_savings_percent: Decimal,
+}
+
+/// Family of an instance type split into class, generation and the rest, e.g. `m`, `7` and `gd`
+/// for `m7gd`.
+fn split_family(family: &str) -> Option<(&str, u32, &str)> {
+    let digits_start = family.find(|c: char| c.is_ascii_digit())?;
+    let digits_end = family[digits_start..]
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(family.len(), |end| digits_start + end);
+    let generation = family[digits_start..digits_end].parse().ok()?;
+    Some((&family[..digits_start], generation, &family[digits_end..]))
+}
+
+/// x86 instance types that may correspond to Graviton `instance_type`, most likely first: Intel,
+/// then AMD, then plain families of the same generation, then of the previous one, e.g.
+/// `m7i.large`, `m7a.large`, `m7.large`, `m6i.large`, ... for `m7g.large`.
+fn x86_candidates(instance_type: &str) -> Vec<String> {
+    let (family, size) = match instance_type.split_once('.') {
+        Some(split) => split,
+        None => return vec![],
+    };
+    let (class, generation, rest) = match split_family(family) {
+        Some(split) => split,
+        None => return vec![],
+    };
+    let rest = match rest.strip_prefix('g') {
+        Some(rest) => rest,
+        None => return vec![],
+    };
+    [generation, generation.saturating_sub(1)]
+        .into_iter()
+        .flat_map(|generation| {
+            ["i", "a", ""].map(|vendor| format!("{class}{generation}{vendor}{rest}.{size}"))
+        })
+        .collect()
+}
+
+type PriceKey<'a> = (
+    Option<&'a str>,
+    Option<&'a str>,
+    &'static str,
+    Option<&'a str>,
+    Option<&'a str>,
+);
+
+fn price_key(row: &EnrichedPriceRow) -> PriceKey<'_> {
+    let price = &row.price;
+    (
+        price.region.as_deref(),
+        price.platform.as_deref(),
+        price.purchase_model.as_str(),
+        price.term.as_ref().map(ContractLength::as_str),
+        price.purchase_option.as_ref().map(PurchaseOption::as_str),
+    )
+}
+
+/// Pairs every arm64 instance type in `rows` with its x86 equivalent of the same vCPUs and
+/// memory, in the same region, platform and purchase model. `performance_ratios` gives the
+/// relative throughput by Graviton family, e.g. `m7g`, defaulting to 1. Instance types with
+/// several SKUs are compared at their cheapest.
+pub fn compare(
+    rows: &[EnrichedPriceRow],
+    performance_ratios: &HashMap<String, Decimal>,
+) -> Vec<ArchitectureComparison> {
+    let mut cheapest: BTreeMap<(PriceKey, &str), &EnrichedPriceRow> = BTreeMap::new();
+    for row in rows {
+        cheapest
+            .entry((price_key(row), row.spec.instance_type.as_str()))
+            .and_modify(|current| {
+                if row.price.effective_usd_per_hour < current.price.effective_usd_per_hour {
+                    *current = row;
+                }
+            })
+            .or_insert(row);
+    }
+
+    let mut comparisons = Vec::new();
+    for ((key, instance_type), arm) in &cheapest {
+        if arm.spec.architecture.as_deref() != Some("arm64") {
+            continue;
+        }
+        let x86 = x86_candidates(instance_type)
+            .into_iter()
+            .find_map(|candidate| {
+                let x86 = cheapest.get(&(*key, candidate.as_str()))?;
+                let same_size = x86.spec.architecture.as_deref() == Some("x86_64")
+                    && x86.spec.vcpus == arm.spec.vcpus
+                    && x86.spec.memory_gib == arm.spec.memory_gib;
+                same_size.then_some(*x86)
+            });
+        let x86 = match x86 {
+            Some(x86) => x86,
+            None => continue,
+        };
+        let arm_usd_per_hour = arm.price.effective_usd_per_hour;
+        let x86_usd_per_hour = x86.price.effective_usd_per_hour;
+        let performance_ratio = instance_type
+            .split_once('.')
+            .and_then(|(family, _)| performance_ratios.get(family))
+            .copied()
+            .filter(|ratio| !ratio.is_zero())
+            .unwrap_or(Decimal::ONE);
+        let savings = |arm_usd_per_hour: Decimal| {
+            if x86_usd_per_hour.is_zero() {
+                Decimal::ZERO
+            } else {
+                ((x86_usd_per_hour - arm_usd_per_hour) * Decimal::ONE_HUNDRED / x86_usd_per_hour)
+                    .round_dp(2)
+            }
+        };
+        comparisons.push(ArchitectureComparison {
+            region: arm.price.region.clone(),
+            platform: arm.price.platform.clone(),
+            purchase_model: arm.price.purchase_model,
+            term: arm.price.term.clone(),
+            purchase_option: arm.price.purchase_option.clone(),
+            arm_instance_type: instance_type.to_string(),
+            x86_instance_type: x86.spec.instance_type.clone(),
+            arm_usd_per_hour,
+            x86_usd_per_hour,
+            delta_usd_per_hour: arm_usd_per_hour - x86_usd_per_hour,
+            savings_percent: savings(arm_usd_per_hour),
+            performance_ratio,
+            adjusted_savings_percent: savings(arm_usd_per_hour / performance_ratio),
+        });
+    }
+    comparisons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, x86_candidates};
+    use crate::transform::aws::instance_specs::{join, InstanceSpec};
+    use crate::transform::aws::normalize::{NormalizedPriceRow, PurchaseModel};
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(
+            x86_candidates("t4g.micro")[3..],
+            ["t3i.micro", "t3a.micro", "t3.micro"]
+        );
+        assert!(x86_candidates("m7i.large").is_empty());
+
+        let spec = |instance_type: &str, architecture: &str| InstanceSpec {
+            instance_type: instance_type.to_string(),
+            architecture: Some(architecture.to_string()),
+            vcpus: Some(2),
+            memory_gib: Some(Decimal::from(8)),
+            network_performance: None,
+            gpus: None,
+            gpu_memory_gib: None,
+            gpu_model: None,
+            launch_date: None,
+        };
+        let specs: HashMap<String, InstanceSpec> = [
+            spec("m7g.large", "arm64"),
+            spec("m7i.large", "x86_64"),
+            spec("m7a.large", "x86_64"),
+            spec("c7g.large", "arm64"),
+        ]
+        .into_iter()
+        .map(|spec| (spec.instance_type.clone(), spec))
+        .collect();
+        let row = |instance_type: &str, price: i64| NormalizedPriceRow {
+            region: Some("ap-northeast-1".to_string()),
+            service_code: Some("AmazonEC2".to_string()),
+            sku: instance_type.to_string(),
+            instance_type: Some(instance_type.to_string()),
+            platform: Some("Linux".to_string()),
+            component: None,
+            purchase_model: PurchaseModel::OnDemand,
+            term: None,
+            purchase_option: None,
+            offering_class: None,
+            effective_usd_per_hour: Decimal::new(price, 4),
+        };
+        let rows = join(
+            &[
+                row("m7g.large", 1054),
+                row("m7i.large", 1260),
+                row("m7a.large", 1449),
+                row("c7g.large", 908),
+            ],
+            &specs,
+        );
+        let ratios = HashMap::from([("m7g".to_string(), Decimal::new(11, 1))]);
+
+        let comparisons = compare(&rows, &ratios);
+        assert_eq!(comparisons.len(), 1);
+        let comparison = &comparisons[0];
+        assert_eq!(comparison.x86_instance_type, "m7i.large");
+        assert_eq!(comparison.delta_usd_per_hour, Decimal::new(-206, 4));
+        assert_eq!(comparison.savings_percent, Decimal::new(1635, 2));
+        assert_eq!(comparison.adjusted_savings_percent, Decimal::new(2395, 2));
+    }
+}
@@ -0,0 +1,87 @@
+use crate::transform::aws::normalize::NormalizedPriceRow;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// An instance type with prices in a region it cannot be launched in.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct UnavailableInstanceType {
+    pub region: String,
+    pub instance_type: String,
+    /// Number of priced rows of the instance type
+    pub priced_rows: usize,
+}
+
+/// Instance types of priced `rows` that are missing from the instance type `offerings` of their
+/// region, e.g. from EC2 `DescribeInstanceTypeOfferings`. Regions without offerings are not
+/// checked. Sorted by region and instance type.
+pub fn priced_but_unavailable(
+    rows: &[NormalizedPriceRow],
+    offerings: &HashMap<String, BTreeSet<String>>,
+) -> Vec<UnavailableInstanceType> {
+    let mut unavailable: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for row in rows {
+        let (region, instance_type) = match (row.region.as_deref(), row.instance_type.as_deref()) {
+            (Some(region), Some(instance_type)) => (region, instance_type),
+            _ => continue,
+        };
+        match offerings.get(region) {
+            Some(offered) if !offered.contains(instance_type) => {
+                *unavailable.entry((region, instance_type)).or_default() += 1;
+            }
+            _ => {}
+        }
+    }
+    unavailable
+        .into_iter()
+        .map(
+            |((region, instance_type), priced_rows)| UnavailableInstanceType {
+                region: region.to_string(),
+                instance_type: instance_type.to_string(),
+                priced_rows,
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::priced_but_unavailable;
+    use crate::transform::aws::normalize::{NormalizedPriceRow, PurchaseModel};
+    use rust_decimal::Decimal;
+    use std::collections::{BTreeSet, HashMap};
+
+    fn row(region: &str, instance_type: &str) -> NormalizedPriceRow {
+        NormalizedPriceRow {
+            region: Some(region.to_string()),
+            service_code: Some("AmazonEC2".to_string()),
+            sku: format!("{region}-{instance_type}"),
+            instance_type: Some(instance_type.to_string()),
+            platform: Some("Linux".to_string()),
+            component: None,
+            purchase_model: PurchaseModel::OnDemand,
+            term: None,
+            purchase_option: None,
+            offering_class: None,
+            effective_usd_per_hour: Decimal::new(96, 3),
+        }
+    }
+
+    #[test]
+    fn test_priced_but_unavailable() {
+        let rows = [
+            row("us-east-1", "m5.large"),
+            row("us-east-1", "p5.48xlarge"),
+            row("us-east-1", "p5.48xlarge"),
+            row("ap-east-1", "p5.48xlarge"),
+        ];
+        let offerings = HashMap::from([(
+            "us-east-1".to_string(),
+            BTreeSet::from(["m5.large".to_string()]),
+        )]);
+
+        let unavailable = priced_but_unavailable(&rows, &offerings);
+        assert_eq!(unavailable.len(), 1);
+        assert_eq!(unavailable[0].instance_type, "p5.48xlarge");
+        assert_eq!(unavailable[0].priced_rows, 2);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::model::aws::types::{ContractLength, PurchaseOption};
+use crate::model::aws::unit::HOURS_PER_MONTH;
+use crate::transform::aws::normalize::{NormalizedPriceRow, PurchaseModel};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Utilization at which a commitment starts costing less than running the same SKU on-demand.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BreakEvenRow {
+    pub region: Option<String>,
+    pub instance_type: Option<String>,
+    pub platform: Option<String>,
+    pub sku: String,
+    pub purchase_model: PurchaseModel,
+    pub term: Option<ContractLength>,
+    pub purchase_option: Option<PurchaseOption>,
+    pub on_demand_usd_per_hour: Decimal,
+    /// Paid for every hour of the month, upfront fees amortized over the term
+    pub effective_usd_per_hour: Decimal,
+    /// Hours an instance has to run per month for the commitment to pay off, empty if it never
+    /// does
+    pub break_even_hours_per_month: Option<Decimal>,
+    pub break_even_utilization_percent: Option<Decimal>,
+}
+
+/// Break-even utilization of every commitment in `rows` against the on-demand row of the same
+/// SKU, e.g. of `normalize::from_on_demand`, `from_reserved` and `from_savings_plans` combined.
+/// Commitments without an on-demand price are left out.
+pub fn break_even(rows: &[NormalizedPriceRow]) -> Vec<BreakEvenRow> {
+    let mut on_demand: HashMap<&str, Decimal> = HashMap::new();
+    for row in rows {
+        if row.purchase_model == PurchaseModel::OnDemand && !row.effective_usd_per_hour.is_zero() {
+            on_demand
+                .entry(row.sku.as_str())
+                .and_modify(|price| *price = (*price).min(row.effective_usd_per_hour))
+                .or_insert(row.effective_usd_per_hour);
+        }
+    }
+
+    let hours_per_month = Decimal::from(HOURS_PER_MONTH);
+    let mut break_even_rows: Vec<BreakEvenRow> = rows
+        .iter()
+        .filter(|row| row.purchase_model != PurchaseModel::OnDemand)
+        .filter_map(|row| {
+            let on_demand_usd_per_hour = *on_demand.get(row.sku.as_str())?;
+            let utilization = row.effective_usd_per_hour / on_demand_usd_per_hour;
+            let pays_off = utilization <= Decimal::ONE;
+            Some(BreakEvenRow {
+                region: row.region.clone(),
+                instance_type: row.instance_type.clone(),
+                platform: row.platform.clone(),
+                sku: row.sku.clone(),
+                purchase_model: row.purchase_model,
+                term: row.term.clone(),
+                purchase_option: row.purchase_option.clone(),
+                on_demand_usd_per_hour,
+                effective_usd_per_hour: row.effective_usd_per_hour,
+                break_even_hours_per_month: pays_off
+                    .then(|| (utilization * hours_per_month).round_dp(1)),
+                break_even_utilization_percent: pays_off
+                    .then(|| (utilization * Decimal::ONE_HUNDRED).round_dp(2)),
+            })
+        })
+        .collect();
+    break_even_rows.sort_by(|a, b| {
+        (
+            &a.region,
+            &a.instance_type,
+            &a.platform,
+            a.purchase_model.as_str(),
+            a.term.as_ref().map(ContractLength::as_str),
+            a.purchase_option.as_ref().map(PurchaseOption::as_str),
+        )
+            .cmp(&(
+                &b.region,
+                &b.instance_type,
+                &b.platform,
+                b.purchase_model.as_str(),
+                b.term.as_ref().map(ContractLength::as_str),
+                b.purchase_option.as_ref().map(PurchaseOption::as_str),
+            ))
+    });
+    break_even_rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::break_even;
+    use crate::model::aws::types::{ContractLength, PurchaseOption};
+    use crate::transform::aws::normalize::{NormalizedPriceRow, PurchaseModel};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_break_even() {
+        let row = |sku: &str, purchase_model: PurchaseModel, price: i64| NormalizedPriceRow {
+            region: Some("us-east-1".to_string()),
+            service_code: Some("AmazonEC2".to_string()),
+            sku: sku.to_string(),
+            instance_type: Some("m5.large".to_string()),
+            platform: Some("Linux".to_string()),
+            component: None,
+            purchase_model,
+            term: (purchase_model != PurchaseModel::OnDemand).then_some(ContractLength::OneYear),
+            purchase_option: (purchase_model != PurchaseModel::OnDemand)
+                .then_some(PurchaseOption::NoUpfront),
+            offering_class: None,
+            effective_usd_per_hour: Decimal::new(price, 3),
+        };
+        let rows = break_even(&[
+            row("SKU1", PurchaseModel::ComputeSavingsPlan, 72),
+            row("SKU1", PurchaseModel::OnDemand, 96),
+            row("SKU1", PurchaseModel::Reserved, 120),
+            row("SKU2", PurchaseModel::Reserved, 60),
+        ]);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].purchase_model, PurchaseModel::ComputeSavingsPlan);
+        // 0.072 / 0.096 of 730 hours
+        assert_eq!(
+            rows[0].break_even_hours_per_month,
+            Some(Decimal::new(5475, 1))
+        );
+        assert_eq!(
+            rows[0].break_even_utilization_percent,
+            Some(Decimal::from(75))
+        );
+        assert_eq!(rows[1].break_even_hours_per_month, None);
+    }
+}
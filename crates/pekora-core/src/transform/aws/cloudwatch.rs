@@ -0,0 +1,231 @@
+use crate::metrics;
+use crate::model::aws::price_bulk_types::CloudWatchPricingListResponse;
+use crate::transform::aws::tiered::TieredPrice;
+use anyhow::anyhow;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// What a CloudWatch or CloudWatch Logs price is charged per.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloudWatchMeter {
+    /// Custom metrics stored in a month, tiered
+    Metrics,
+    Dashboards,
+    /// Standard resolution alarm metrics
+    Alarms,
+    /// GB of logs ingested
+    LogsIngestion,
+    /// GB-months of logs archived
+    LogsStorage,
+}
+
+impl CloudWatchMeter {
+    /// Meter of a usage type, with or without a region prefix, `None` for anything else in the
+    /// pricing list.
+    fn from_usage_type(usage_type: &str) -> Option<Self> {
+        let is = |name: &str| {
+            usage_type == name
+                || usage_type
+                    .strip_suffix(name)
+                    .is_some_and(|prefix| prefix.ends_with('-'))
+        };
+        if is("CW:MetricMonitorUsage") {
+            Some(Self::Metrics)
+        } else if is("DashboardsUsageHour") {
+            Some(Self::Dashboards)
+        } else if is("CW:AlarmMonitorUsage") {
+            Some(Self::Alarms)
+        } else if is("DataProcessing-Bytes") {
+            Some(Self::LogsIngestion)
+        } else if is("TimedStorage-ByteHrs") {
+            Some(Self::LogsStorage)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CloudWatchRate {
+    pub meter: CloudWatchMeter,
+    pub price: TieredPrice,
+}
+
+/// Rates of the metered CloudWatch usage in a pricing list. Products of local zones are skipped.
+pub fn pivot(response: &CloudWatchPricingListResponse) -> Vec<CloudWatchRate> {
+    let mut pivoted = Vec::new();
+    for (sku, offerings) in &response.terms.on_demand {
+        let attributes = match response.products.get(sku) {
+            Some(product) => &product.attributes,
+            None => continue,
+        };
+        if attributes
+            .location_type
+            .as_deref()
+            .is_some_and(|location_type| location_type != "AWS Region")
+        {
+            continue;
+        }
+        let meter = match attributes
+            .usage_type
+            .as_deref()
+            .and_then(CloudWatchMeter::from_usage_type)
+        {
+            Some(meter) => meter,
+            None => continue,
+        };
+        for offering in offerings.values() {
+            if let Some(price) = TieredPrice::from_dimensions(
+                sku,
+                &offering.offer_term_code,
+                offering.price_dimensions.values(),
+            ) {
+                pivoted.push(CloudWatchRate { meter, price });
+            }
+        }
+    }
+    metrics::global().record_rows_pivoted(pivoted.len() as u64);
+    pivoted
+}
+
+/// Monthly CloudWatch usage of an account in one region.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CloudWatchUsage {
+    pub metrics: Decimal,
+    pub dashboards: Decimal,
+    pub alarms: Decimal,
+    pub logs_ingested_gb: Decimal,
+    /// Average GB of logs kept over the month
+    pub logs_stored_gb: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CloudWatchCostLine {
+    pub meter: CloudWatchMeter,
+    pub quantity: Decimal,
+    pub monthly_usd: Decimal,
+}
+
+/// Monthly cost of CloudWatch usage. Free tier allowances are not deducted.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CloudWatchEstimate {
+    pub region: String,
+    pub lines: Vec<CloudWatchCostLine>,
+    pub monthly_usd: Decimal,
+}
+
+/// Prices `usage` with the rates of `region`. Meters without usage are left out. Fails if a
+/// meter with usage has no price in the region.
+pub fn estimate(
+    rates: &[CloudWatchRate],
+    region: &str,
+    usage: &CloudWatchUsage,
+) -> anyhow::Result<CloudWatchEstimate> {
+    let quantities = [
+        (CloudWatchMeter::Metrics, usage.metrics),
+        (CloudWatchMeter::Dashboards, usage.dashboards),
+        (CloudWatchMeter::Alarms, usage.alarms),
+        (CloudWatchMeter::LogsIngestion, usage.logs_ingested_gb),
+        (CloudWatchMeter::LogsStorage, usage.logs_stored_gb),
+    ];
+    let mut lines = Vec::new();
+    for (meter, quantity) in quantities {
+        if quantity <= Decimal::ZERO {
+            continue;
+        }
+        let rate = rates
+            .iter()
+            .find(|rate| rate.meter == meter)
+            .ok_or_else(|| anyhow!("{:?} has no price in {}", meter, region))?;
+        lines.push(CloudWatchCostLine {
+            meter,
+            quantity,
+            monthly_usd: rate.price.cost(quantity).round_dp(2),
+        });
+    }
+    Ok(CloudWatchEstimate {
+        region: region.to_string(),
+        monthly_usd: lines.iter().map(|line| line.monthly_usd).sum(),
+        lines,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{estimate, pivot, CloudWatchMeter, CloudWatchUsage};
+    use crate::model::aws::price_bulk_types::{CloudWatchPricingListResponse, PricingListResponse};
+    use rust_decimal::Decimal;
+
+    fn product(sku: &str, usage_type: &str) -> String {
+        format!(
+            r#""{sku}": {{"sku": "{sku}", "productFamily": "", "attributes": {{
+                "usagetype": "{usage_type}", "locationType": "AWS Region",
+                "regionCode": "us-east-1"}}}}"#
+        )
+    }
+
+    fn offering(sku: &str, unit: &str, tiers: &[(&str, &str, &str)]) -> String {
+        let dimensions = tiers
+            .iter()
+            .map(|(begin, end, price)| {
+                format!(
+                    r#""{sku}.JRTCKXETXF.{begin}": {{"rateCode": "{sku}.JRTCKXETXF.{begin}",
+                        "description": "", "unit": "{unit}", "beginRange": "{begin}",
+                        "endRange": "{end}", "pricePerUnit": {{"USD": "{price}"}}}}"#
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            r#""{sku}": {{"{sku}.JRTCKXETXF": {{"offerTermCode": "JRTCKXETXF", "sku": "{sku}",
+                "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {{}},
+                "priceDimensions": {{{dimensions}}}}}}}"#
+        )
+    }
+
+    #[test]
+    fn test_estimate() {
+        let json = format!(
+            r#"{{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724",
+            "products": {{{}, {}, {}, {}}},
+            "terms": {{"OnDemand": {{{}, {}, {}, {}}}, "Reserved": {{}}}}}}"#,
+            product("MET", "CW:MetricMonitorUsage"),
+            product("ING", "USE1-DataProcessing-Bytes"),
+            product("VEN", "USE1-VendedLog-Bytes"),
+            product("STO", "USE1-TimedStorage-ByteHrs"),
+            offering(
+                "MET",
+                "Metrics",
+                &[("0", "10000", "0.30"), ("10000", "250000", "0.10")]
+            ),
+            offering("ING", "GB", &[("0", "Inf", "0.50")]),
+            offering("VEN", "GB", &[("0", "10240", "0.50")]),
+            offering("STO", "GB-Mo", &[("0", "Inf", "0.03")]),
+        );
+        let response: PricingListResponse = serde_json::from_str(&json).unwrap();
+        let typed: CloudWatchPricingListResponse = response.with_typed_attributes().unwrap();
+        let rates = pivot(&typed);
+        assert_eq!(rates.len(), 3);
+
+        let usage = CloudWatchUsage {
+            metrics: Decimal::from(12000),
+            logs_ingested_gb: Decimal::from(100),
+            logs_stored_gb: Decimal::from(1000),
+            ..Default::default()
+        };
+        let cost = estimate(&rates, "us-east-1", &usage).unwrap();
+        assert_eq!(cost.lines.len(), 3);
+        assert_eq!(cost.lines[0].meter, CloudWatchMeter::Metrics);
+        // 10000 * 0.30 + 2000 * 0.10
+        assert_eq!(cost.lines[0].monthly_usd, Decimal::from(3200));
+        assert_eq!(cost.monthly_usd, Decimal::from(3280));
+
+        let alarms = CloudWatchUsage {
+            alarms: Decimal::from(5),
+            ..Default::default()
+        };
+        assert!(estimate(&rates, "us-east-1", &alarms).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use crate::model::aws::cost_explorer::{
+    CostAndUsageResponse, ReservationCoverageResponse, SavingsPlansUtilizationResponse,
+};
+use crate::transform::aws::recommendation::Ec2Usage;
+use anyhow::bail;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const USAGE_QUANTITY: &str = "UsageQuantity";
+
+/// Steady EC2 usage by instance type from `GetCostAndUsage` results grouped by `INSTANCE_TYPE`
+/// with the `UsageQuantity` metric, e.g. filtered to the `EC2: Running Hours` usage type group.
+/// Running hours are averaged over the whole period of the response, and usage in units other
+/// than hours is skipped. Sorted by instance type.
+pub fn ec2_usage(response: &CostAndUsageResponse) -> anyhow::Result<Vec<Ec2Usage>> {
+    if let Some(definition) = response.group_definitions.first() {
+        if definition.key != "INSTANCE_TYPE" {
+            bail!(
+                "Expected usage grouped by INSTANCE_TYPE, got {}",
+                definition.key
+            );
+        }
+    }
+    let mut period_hours = 0;
+    let mut running_hours: BTreeMap<&str, Decimal> = BTreeMap::new();
+    for result in &response.results_by_time {
+        period_hours += match result.time_period.hours() {
+            Some(hours) => hours,
+            None => bail!(
+                "Unrecognized time period {} to {}",
+                result.time_period.start,
+                result.time_period.end
+            ),
+        };
+        for group in &result.groups {
+            let (instance_type, usage) =
+                match (group.keys.first(), group.metrics.get(USAGE_QUANTITY)) {
+                    (Some(instance_type), Some(usage)) if usage.unit == "Hrs" => {
+                        (instance_type, usage)
+                    }
+                    _ => continue,
+                };
+            *running_hours.entry(instance_type).or_default() += usage.amount;
+        }
+    }
+    if period_hours <= 0 {
+        bail!("No usage period in the response");
+    }
+    Ok(running_hours
+        .into_iter()
+        .filter(|(_, hours)| !hours.is_zero())
+        .map(|(instance_type, hours)| Ec2Usage {
+            instance_type: instance_type.to_string(),
+            operating_system: "Linux".to_string(),
+            tenancy: "Shared".to_string(),
+            instances: (hours / Decimal::from(period_hours)).round_dp(2),
+        })
+        .collect())
+}
+
+/// How well existing Savings Plans and reservations are used and cover usage, over the period
+/// of the Cost Explorer responses it was built from.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct CommitmentSummary {
+    pub savings_plans_commitment_usd: Option<Decimal>,
+    pub savings_plans_unused_usd: Option<Decimal>,
+    pub savings_plans_utilization_percent: Option<Decimal>,
+    pub savings_plans_net_savings_usd: Option<Decimal>,
+    pub reserved_hours: Option<Decimal>,
+    pub on_demand_hours: Option<Decimal>,
+    pub reservation_coverage_percent: Option<Decimal>,
+}
+
+/// Summary of `GetSavingsPlansUtilization` and `GetReservationCoverage` totals, either of which
+/// may be missing.
+pub fn commitment_summary(
+    utilization: Option<&SavingsPlansUtilizationResponse>,
+    coverage: Option<&ReservationCoverageResponse>,
+) -> CommitmentSummary {
+    let mut summary = CommitmentSummary::default();
+    if let Some(total) = utilization.map(|response| &response.total) {
+        summary.savings_plans_commitment_usd = Some(total.utilization.total_commitment);
+        summary.savings_plans_unused_usd = Some(total.utilization.unused_commitment);
+        summary.savings_plans_utilization_percent = Some(total.utilization.utilization_percentage);
+        summary.savings_plans_net_savings_usd =
+            total.savings.as_ref().map(|savings| savings.net_savings);
+    }
+    if let Some(hours) = coverage.map(|response| &response.total.coverage_hours) {
+        summary.reserved_hours = Some(hours.reserved_hours);
+        summary.on_demand_hours = Some(hours.on_demand_hours);
+        summary.reservation_coverage_percent = Some(hours.coverage_hours_percentage);
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{commitment_summary, ec2_usage};
+    use crate::model::aws::cost_explorer::{CostAndUsageResponse, SavingsPlansUtilizationResponse};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_ec2_usage_and_summary() {
+        let response: CostAndUsageResponse = serde_json::from_str(
+            r#"{"GroupDefinitions": [{"Type": "DIMENSION", "Key": "INSTANCE_TYPE"}],
+                "ResultsByTime": [
+                    {"TimePeriod": {"Start": "2024-03-01", "End": "2024-03-02"}, "Total": {},
+                     "Groups": [
+                        {"Keys": ["m5.large"], "Metrics": {"UsageQuantity": {"Amount": "48", "Unit": "Hrs"}}},
+                        {"Keys": ["NoInstanceType"], "Metrics": {"UsageQuantity": {"Amount": "3.5", "Unit": "GB"}}}
+                     ], "Estimated": false},
+                    {"TimePeriod": {"Start": "2024-03-02", "End": "2024-03-03"}, "Total": {},
+                     "Groups": [
+                        {"Keys": ["m5.large"], "Metrics": {"UsageQuantity": {"Amount": "24", "Unit": "Hrs"}}},
+                        {"Keys": ["c5.xlarge"], "Metrics": {"UsageQuantity": {"Amount": "12", "Unit": "Hrs"}}}
+                     ], "Estimated": true}
+                ]}"#,
+        )
+        .unwrap();
+        let usage = ec2_usage(&response).unwrap();
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].instance_type, "c5.xlarge");
+        assert_eq!(usage[0].instances, Decimal::new(25, 2));
+        assert_eq!(usage[1].instances, Decimal::new(15, 1));
+
+        let utilization: SavingsPlansUtilizationResponse = serde_json::from_str(
+            r#"{"SavingsPlansUtilizationsByTime": [], "Total": {
+                "Utilization": {"TotalCommitment": "730", "UsedCommitment": "657",
+                    "UnusedCommitment": "73", "UtilizationPercentage": "90"},
+                "Savings": {"NetSavings": "210.4", "OnDemandCostEquivalent": "940.4"}}}"#,
+        )
+        .unwrap();
+        let summary = commitment_summary(Some(&utilization), None);
+        assert_eq!(summary.savings_plans_unused_usd, Some(Decimal::from(73)));
+        assert_eq!(summary.reservation_coverage_percent, None);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::transform::aws::recommendation::Ec2Usage;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How instance usage in a Cost and Usage Report was paid for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurPurchaseOption {
+    OnDemand,
+    Spot,
+    Reserved,
+    SavingsPlan,
+}
+
+impl CurPurchaseOption {
+    /// From the `line_item_line_item_type` and `pricing_term` columns, `None` for line items that
+    /// are not usage, e.g. fees, credits or the recurring charges of commitments.
+    pub fn from_line_item(line_item_type: &str, pricing_term: Option<&str>) -> Option<Self> {
+        match (line_item_type, pricing_term) {
+            ("Usage", Some("Spot")) => Some(Self::Spot),
+            ("Usage", _) => Some(Self::OnDemand),
+            ("DiscountedUsage", _) => Some(Self::Reserved),
+            ("SavingsPlanCoveredUsage", _) => Some(Self::SavingsPlan),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::OnDemand => "on_demand",
+            Self::Spot => "spot",
+            Self::Reserved => "reserved",
+            Self::SavingsPlan => "savings_plan",
+        }
+    }
+}
+
+/// Instance usage of a Cost and Usage Report by region, instance type and purchase option.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CurUsage {
+    pub region: String,
+    pub instance_type: String,
+    pub purchase_option: CurPurchaseOption,
+    pub usage_hours: Decimal,
+    pub unblended_cost_usd: Decimal,
+}
+
+/// Instance usage of a Cost and Usage Report and the period its line items span.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CurUsageReport {
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    /// Sorted by region, instance type and purchase option
+    pub usage: Vec<CurUsage>,
+}
+
+impl CurUsageReport {
+    /// Steady usage of `region` for the recommendation transforms: running hours of every
+    /// purchase option but spot, averaged over the period of the report.
+    pub fn ec2_usage(&self, region: &str) -> Vec<Ec2Usage> {
+        let period_hours = (self.period_end - self.period_start).num_hours();
+        if period_hours <= 0 {
+            return vec![];
+        }
+        let mut hours: BTreeMap<&str, Decimal> = BTreeMap::new();
+        for usage in &self.usage {
+            if usage.region == region && usage.purchase_option != CurPurchaseOption::Spot {
+                *hours.entry(&usage.instance_type).or_default() += usage.usage_hours;
+            }
+        }
+        hours
+            .into_iter()
+            .map(|(instance_type, hours)| Ec2Usage {
+                instance_type: instance_type.to_string(),
+                operating_system: "Linux".to_string(),
+                tenancy: "Shared".to_string(),
+                instances: (hours / Decimal::from(period_hours)).round_dp(2),
+            })
+            .collect()
+    }
+}
+
+/// Columns of a CUR usage line item, spanning `start` to `end`.
+#[derive(Debug, Clone)]
+pub struct CurLineItem<'a> {
+    pub region: Option<&'a str>,
+    pub instance_type: Option<&'a str>,
+    pub purchase_option: CurPurchaseOption,
+    pub usage_hours: Decimal,
+    pub unblended_cost_usd: Decimal,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// Sums CUR line items into a `CurUsageReport`.
+#[derive(Default)]
+pub struct CurUsageBuilder {
+    period: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    usage: BTreeMap<(String, String, CurPurchaseOption), (Decimal, Decimal)>,
+}
+
+impl CurUsageBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a usage line item. Only its period counts if it has no region or instance type.
+    pub fn add(&mut self, item: CurLineItem) {
+        self.period = Some(match self.period {
+            Some((period_start, period_end)) => {
+                (period_start.min(item.start), period_end.max(item.end))
+            }
+            None => (item.start, item.end),
+        });
+        let (region, instance_type) = match (item.region, item.instance_type) {
+            (Some(region), Some(instance_type))
+                if !region.is_empty() && !instance_type.is_empty() =>
+            {
+                (region, instance_type)
+            }
+            _ => return,
+        };
+        let (hours, cost) = self
+            .usage
+            .entry((
+                region.to_string(),
+                instance_type.to_string(),
+                item.purchase_option,
+            ))
+            .or_default();
+        *hours += item.usage_hours;
+        *cost += item.unblended_cost_usd;
+    }
+
+    /// `None` if no line item was added.
+    pub fn build(self) -> Option<CurUsageReport> {
+        let (period_start, period_end) = self.period?;
+        Some(CurUsageReport {
+            period_start,
+            period_end,
+            usage: self
+                .usage
+                .into_iter()
+                .map(
+                    |(
+                        (region, instance_type, purchase_option),
+                        (usage_hours, unblended_cost_usd),
+                    )| {
+                        CurUsage {
+                            region,
+                            instance_type,
+                            purchase_option,
+                            usage_hours,
+                            unblended_cost_usd,
+                        }
+                    },
+                )
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CurLineItem, CurPurchaseOption, CurUsageBuilder};
+    use chrono::{TimeZone, Utc};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_cur_usage() {
+        let day = |d| Utc.with_ymd_and_hms(2024, 3, d, 0, 0, 0).unwrap();
+        let mut builder = CurUsageBuilder::new();
+        for (line_item_type, pricing_term, hours) in [
+            ("Usage", Some("OnDemand"), 24),
+            ("SavingsPlanCoveredUsage", None, 24),
+            ("Usage", Some("Spot"), 48),
+        ] {
+            builder.add(CurLineItem {
+                region: Some("us-east-1"),
+                instance_type: Some("m5.large"),
+                purchase_option: CurPurchaseOption::from_line_item(line_item_type, pricing_term)
+                    .unwrap(),
+                usage_hours: Decimal::from(hours),
+                unblended_cost_usd: Decimal::new(96, 3) * Decimal::from(hours),
+                start: day(1),
+                end: day(3),
+            });
+        }
+        assert_eq!(CurPurchaseOption::from_line_item("Tax", None), None);
+
+        let report = builder.build().unwrap();
+        assert_eq!(report.usage.len(), 3);
+        assert_eq!(report.usage[0].purchase_option, CurPurchaseOption::OnDemand);
+        let usage = report.ec2_usage("us-east-1");
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].instances, Decimal::ONE);
+        assert!(report.ec2_usage("us-west-2").is_empty());
+    }
+}
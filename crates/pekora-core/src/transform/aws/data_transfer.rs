@@ -0,0 +1,242 @@
+use crate::metrics;
+use crate::model::aws::price_bulk_types::DataTransferPricingListResponse;
+use crate::model::aws::types::DataTransferProductAttributes;
+use crate::transform::aws::location::RegionNames;
+use crate::transform::aws::tiered::TieredPrice;
+use anyhow::anyhow;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// Where transferred data goes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TransferDestination {
+    Internet,
+    /// Another region, by region code
+    Region(String),
+}
+
+impl TransferDestination {
+    /// `internet`, or a region code.
+    pub fn parse(s: &str) -> Self {
+        if s.eq_ignore_ascii_case("internet") {
+            Self::Internet
+        } else {
+            Self::Region(s.to_string())
+        }
+    }
+}
+
+impl Display for TransferDestination {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Internet => write!(f, "internet"),
+            Self::Region(region) => write!(f, "{}", region),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TransferRate {
+    pub from_region: String,
+    pub destination: TransferDestination,
+    /// Per GB, internet egress in tiers of monthly volume
+    pub price: TieredPrice,
+}
+
+/// Outbound direction of a product, `None` for inbound transfers and transfers that don't leave
+/// a region for the internet or another region.
+fn classify(
+    attributes: &DataTransferProductAttributes,
+    region_names: &RegionNames,
+) -> Option<(String, TransferDestination)> {
+    let from_region = attributes.from_region_code.clone().or_else(|| {
+        region_names
+            .code(attributes.from_location.as_deref()?)
+            .map(str::to_string)
+    })?;
+    let destination = match attributes.transfer_type.as_deref()? {
+        "AWS Outbound" if attributes.to_location.as_deref() == Some("External") => {
+            TransferDestination::Internet
+        }
+        "InterRegion Outbound" => {
+            TransferDestination::Region(attributes.to_region_code.clone().or_else(|| {
+                region_names
+                    .code(attributes.to_location.as_deref()?)
+                    .map(str::to_string)
+            })?)
+        }
+        _ => return None,
+    };
+    Some((from_region, destination))
+}
+
+/// Per-GB rates of the outbound transfers in an AWSDataTransfer pricing list. Locations without
+/// a region code are resolved by `region_names`.
+pub fn pivot(
+    response: &DataTransferPricingListResponse,
+    region_names: &RegionNames,
+) -> Vec<TransferRate> {
+    let mut pivoted = Vec::new();
+    for (sku, offerings) in &response.terms.on_demand {
+        let (from_region, destination) = match response
+            .products
+            .get(sku)
+            .and_then(|product| classify(&product.attributes, region_names))
+        {
+            Some(classified) => classified,
+            None => continue,
+        };
+        for offering in offerings.values() {
+            if let Some(price) = TieredPrice::from_dimensions(
+                sku,
+                &offering.offer_term_code,
+                offering.price_dimensions.values(),
+            ) {
+                pivoted.push(TransferRate {
+                    from_region: from_region.clone(),
+                    destination: destination.clone(),
+                    price,
+                });
+            }
+        }
+    }
+    metrics::global().record_rows_pivoted(pivoted.len() as u64);
+    pivoted
+}
+
+/// Cost of transferring a volume of data in a month.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TransferCost {
+    pub from_region: String,
+    /// `internet` or a region code
+    pub to: String,
+    pub gb: Decimal,
+    pub usd: Decimal,
+    pub blended_usd_per_gb: Option<Decimal>,
+}
+
+/// Prices `gb` transferred from `from_region` to `destination` in a month. Internet egress is
+/// charged tier by tier. Fails if there is no rate for the direction.
+pub fn transfer_cost(
+    rates: &[TransferRate],
+    from_region: &str,
+    destination: &TransferDestination,
+    gb: Decimal,
+) -> anyhow::Result<TransferCost> {
+    let rate = rates
+        .iter()
+        .find(|rate| rate.from_region == from_region && &rate.destination == destination)
+        .ok_or_else(|| anyhow!("no transfer rate from {} to {}", from_region, destination))?;
+    Ok(TransferCost {
+        from_region: from_region.to_string(),
+        to: destination.to_string(),
+        gb,
+        usd: rate.price.cost(gb).round_dp(2),
+        blended_usd_per_gb: rate.price.blended_price(gb).map(|price| price.round_dp(6)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pivot, transfer_cost, TransferDestination};
+    use crate::model::aws::price_bulk_types::{
+        DataTransferPricingListResponse, PricingListResponse,
+    };
+    use crate::transform::aws::location::RegionNames;
+    use rust_decimal::Decimal;
+
+    fn product(sku: &str, transfer_type: &str, to_location: &str, to_region: &str) -> String {
+        format!(
+            r#""{sku}": {{"sku": "{sku}", "productFamily": "Data Transfer", "attributes": {{
+                "transferType": "{transfer_type}", "fromLocation": "Asia Pacific (Tokyo)",
+                "fromLocationType": "AWS Region", "fromRegionCode": "ap-northeast-1",
+                "toLocation": "{to_location}", "toRegionCode": "{to_region}",
+                "usagetype": "APN1-DataTransfer-Out-Bytes"}}}}"#
+        )
+    }
+
+    fn offering(sku: &str, tiers: &[(&str, Option<&str>, &str)]) -> String {
+        let dimensions = tiers
+            .iter()
+            .enumerate()
+            .map(|(i, (begin, end, price))| {
+                format!(
+                    r#""{sku}.JRTCKXETXF.{i}": {{"rateCode": "{sku}.JRTCKXETXF.{i}",
+                        "description": "", "unit": "GB", "beginRange": "{begin}",
+                        "endRange": "{}", "pricePerUnit": {{"USD": "{price}"}}}}"#,
+                    end.unwrap_or("Inf")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            r#""{sku}": {{"{sku}.JRTCKXETXF": {{"offerTermCode": "JRTCKXETXF", "sku": "{sku}",
+                "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {{}},
+                "priceDimensions": {{{dimensions}}}}}}}"#
+        )
+    }
+
+    #[test]
+    fn test_transfer_cost() {
+        let json = format!(
+            r#"{{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724",
+            "products": {{{}, {}, {}}},
+            "terms": {{"OnDemand": {{{}, {}, {}}}, "Reserved": {{}}}}}}"#,
+            product("OUT", "AWS Outbound", "External", ""),
+            product(
+                "USE1",
+                "InterRegion Outbound",
+                "US East (N. Virginia)",
+                "us-east-1"
+            ),
+            product("IN", "AWS Inbound", "External", ""),
+            offering(
+                "OUT",
+                &[
+                    ("0", Some("10240"), "0.114"),
+                    ("10240", Some("51200"), "0.089"),
+                    ("51200", None, "0.086"),
+                ]
+            ),
+            offering("USE1", &[("0", None, "0.09")]),
+            offering("IN", &[("0", None, "0")]),
+        );
+        let response: PricingListResponse = serde_json::from_str(&json).unwrap();
+        let typed: DataTransferPricingListResponse = response.with_typed_attributes().unwrap();
+        let rates = pivot(&typed, &RegionNames::bundled());
+        assert_eq!(rates.len(), 2);
+
+        let egress = transfer_cost(
+            &rates,
+            "ap-northeast-1",
+            &TransferDestination::parse("internet"),
+            Decimal::from(20480),
+        )
+        .unwrap();
+        assert_eq!(egress.to, "internet");
+        assert_eq!(
+            egress.usd,
+            Decimal::from(10240 * 114 + 10240 * 89) / Decimal::from(1000)
+        );
+        assert_eq!(egress.blended_usd_per_gb, Some(Decimal::new(1015, 4)));
+
+        let inter_region = transfer_cost(
+            &rates,
+            "ap-northeast-1",
+            &TransferDestination::parse("us-east-1"),
+            Decimal::from(100),
+        )
+        .unwrap();
+        assert_eq!(inter_region.usd, Decimal::from(9));
+
+        assert!(transfer_cost(
+            &rates,
+            "ap-northeast-1",
+            &TransferDestination::parse("eu-west-1"),
+            Decimal::from(100),
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use crate::metrics;
+use crate::model::aws::price_bulk_types::PricingListResponse;
+use crate::model::aws::types::{ContractLength, PurchaseOption};
+use crate::model::aws::unit::Unit;
+use crate::transform::aws::effective_rate::contract_hours;
+use crate::transform::aws::location::RegionNames;
+use crate::transform::aws::on_demand::{is_plain_instance, OnDemandRate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Attribute prefix of the number of instances of a size that fit on a host, e.g.
+/// `instanceCapacity2xlarge`.
+const CAPACITY_PREFIX: &str = "instanceCapacity";
+
+/// On-demand Dedicated Host of the AmazonEC2 offer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DedicatedHost {
+    pub sku: String,
+    pub region: Option<String>,
+    /// Instance family the host runs, e.g. `m5`
+    pub host_family: String,
+    pub physical_cores: Option<u32>,
+    /// Instances of each size a host filled with that size alone holds, e.g. `large` to 48
+    pub capacity: BTreeMap<String, u32>,
+    pub on_demand_usd_per_hour: Decimal,
+}
+
+/// Host products of an AmazonEC2 pricing list with their hourly on-demand price. Hosts priced
+/// otherwise are left out. Locations without a region code are resolved by `region_names`.
+pub fn dedicated_hosts(
+    response: &PricingListResponse,
+    region_names: &RegionNames,
+) -> Vec<DedicatedHost> {
+    let mut hosts = Vec::new();
+    for (sku, product) in &response.products {
+        if product.product_family != "Dedicated Host" {
+            continue;
+        }
+        let attributes = &product.attributes;
+        let host_family = match attributes.get("instanceType") {
+            Some(host_family) => host_family,
+            None => continue,
+        };
+        let on_demand_usd_per_hour = match response
+            .terms
+            .on_demand
+            .get(sku)
+            .into_iter()
+            .flat_map(|offerings| offerings.values())
+            .flat_map(|offering| offering.price_dimensions.values())
+            .find(|dimension| dimension.parsed_unit() == Unit::Hours)
+            .and_then(|dimension| dimension.usd())
+        {
+            Some(usd) => usd,
+            None => continue,
+        };
+        let capacity = attributes
+            .iter()
+            .filter_map(|(name, value)| {
+                let size = name.strip_prefix(CAPACITY_PREFIX)?.to_ascii_lowercase();
+                let count = value.parse::<u32>().ok().filter(|count| *count > 0)?;
+                Some((size, count))
+            })
+            .collect();
+        hosts.push(DedicatedHost {
+            sku: sku.clone(),
+            region: attributes.get("regionCode").cloned().or_else(|| {
+                region_names
+                    .code(attributes.get("location")?)
+                    .map(str::to_string)
+            }),
+            host_family: host_family.clone(),
+            physical_cores: attributes
+                .get("physicalCores")
+                .and_then(|cores| cores.parse().ok()),
+            capacity,
+            on_demand_usd_per_hour,
+        });
+    }
+    hosts
+}
+
+/// Reservation of a Dedicated Host family, e.g. from
+/// `Ec2Client::describe_host_reservation_offerings`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HostReservationOffering {
+    pub offering_id: String,
+    /// e.g. `m5`
+    pub instance_family: String,
+    pub term: Option<ContractLength>,
+    pub purchase_option: PurchaseOption,
+    pub upfront_usd: Decimal,
+    pub usd_per_hour: Decimal,
+}
+
+impl HostReservationOffering {
+    /// Hourly rate with the upfront fee spread over the term, `None` for unknown terms.
+    pub fn effective_usd_per_hour(&self) -> Option<Decimal> {
+        let hours = contract_hours(self.term.as_ref()?)?;
+        Some(self.usd_per_hour + self.upfront_usd / hours)
+    }
+}
+
+/// Cost of one instance on a Dedicated Host filled with instances of its size, next to the
+/// shared tenancy price of the same instance type.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HostSlotCost {
+    pub region: String,
+    pub host_family: String,
+    /// e.g. `m5.2xlarge`
+    pub instance_type: String,
+    /// Instances of the type a host holds
+    pub slots: u32,
+    pub host_usd_per_hour: Decimal,
+    pub usd_per_slot_hour: Decimal,
+    /// Cheapest host reservation of the family, spread over the slots
+    pub reserved_usd_per_slot_hour: Option<Decimal>,
+    /// On-demand Linux price with shared tenancy
+    pub shared_usd_per_hour: Option<Decimal>,
+    /// Extra cost of a slot over shared tenancy in percent, negative when the host is cheaper
+    pub premium_percent: Option<Decimal>,
+}
+
+/// Per-slot cost of the `hosts` of `region` for every instance size they hold, with the cheapest
+/// of `reservations` of each family and shared tenancy Linux rates from `on_demand`. Hosts are
+/// billed regardless of the operating system, so licenses are not included. Sorted by host family
+/// and then from the cheapest slot.
+pub fn slot_costs(
+    hosts: &[DedicatedHost],
+    region: &str,
+    reservations: &[HostReservationOffering],
+    on_demand: &[OnDemandRate],
+) -> Vec<HostSlotCost> {
+    let mut cheapest_reservation: BTreeMap<&str, Decimal> = BTreeMap::new();
+    for reservation in reservations {
+        if let Some(usd) = reservation.effective_usd_per_hour() {
+            cheapest_reservation
+                .entry(reservation.instance_family.as_str())
+                .and_modify(|cheapest| *cheapest = (*cheapest).min(usd))
+                .or_insert(usd);
+        }
+    }
+    let shared_usd_per_hour = |instance_type: &str| {
+        on_demand
+            .iter()
+            .filter(|rate| rate.parsed_unit() == Unit::Hours)
+            .filter(|rate| is_plain_instance(&rate.attributes, instance_type, "Linux", "Shared"))
+            .find_map(|rate| rate.price_per_unit.get("USD")?.value())
+    };
+
+    let mut rows = Vec::new();
+    for host in hosts {
+        if host.region.as_deref() != Some(region) {
+            continue;
+        }
+        let reserved = cheapest_reservation.get(host.host_family.as_str());
+        for (size, &slots) in &host.capacity {
+            let instance_type = format!("{}.{}", host.host_family, size);
+            let usd_per_slot_hour = host.on_demand_usd_per_hour / Decimal::from(slots);
+            let shared_usd_per_hour = shared_usd_per_hour(&instance_type);
+            rows.push(HostSlotCost {
+                region: region.to_string(),
+                host_family: host.host_family.clone(),
+                instance_type,
+                slots,
+                host_usd_per_hour: host.on_demand_usd_per_hour,
+                usd_per_slot_hour: usd_per_slot_hour.round_dp(6),
+                reserved_usd_per_slot_hour: reserved
+                    .map(|usd| (usd / Decimal::from(slots)).round_dp(6)),
+                shared_usd_per_hour,
+                premium_percent: shared_usd_per_hour.filter(|shared| !shared.is_zero()).map(
+                    |shared| {
+                        ((usd_per_slot_hour - shared) * Decimal::ONE_HUNDRED / shared).round_dp(2)
+                    },
+                ),
+            });
+        }
+    }
+    rows.sort_by(|a, b| {
+        a.host_family
+            .cmp(&b.host_family)
+            .then(a.usd_per_slot_hour.cmp(&b.usd_per_slot_hour))
+            .then_with(|| a.instance_type.cmp(&b.instance_type))
+    });
+    metrics::global().record_rows_pivoted(rows.len() as u64);
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dedicated_hosts, slot_costs, HostReservationOffering};
+    use crate::model::aws::price_bulk_types::PricingListResponse;
+    use crate::model::aws::types::{ContractLength, PurchaseOption};
+    use crate::transform::aws::location::RegionNames;
+    use crate::transform::aws::on_demand::pivot;
+    use rust_decimal::Decimal;
+
+    fn offering(sku: &str, usd: &str) -> String {
+        format!(
+            r#""{sku}": {{"{sku}.JRTCKXETXF": {{"offerTermCode": "JRTCKXETXF", "sku": "{sku}",
+                "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {{}},
+                "priceDimensions": {{"{sku}.JRTCKXETXF.6YS6EN2CT7": {{
+                    "rateCode": "{sku}.JRTCKXETXF.6YS6EN2CT7", "description": "",
+                    "unit": "Hrs", "pricePerUnit": {{"USD": "{usd}"}}}}}}}}}}"#
+        )
+    }
+
+    #[test]
+    fn test_slot_costs() {
+        let json = format!(
+            r#"{{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724", "products": {{
+                "HOST": {{"sku": "HOST", "productFamily": "Dedicated Host", "attributes": {{
+                    "instanceType": "m5", "tenancy": "Host", "physicalCores": "48",
+                    "regionCode": "us-east-1", "instanceCapacityLarge": "48",
+                    "instanceCapacity24xlarge": "1", "instanceCapacityMetal": "0"}}}},
+                "L": {{"sku": "L", "productFamily": "Compute Instance", "attributes": {{
+                    "instanceType": "m5.large", "operatingSystem": "Linux", "tenancy": "Shared",
+                    "preInstalledSw": "NA", "capacitystatus": "Used",
+                    "regionCode": "us-east-1"}}}}}},
+            "terms": {{"OnDemand": {{{}, {}}}, "Reserved": {{}}}}}}"#,
+            offering("HOST", "5.0690000000"),
+            offering("L", "0.0960000000"),
+        );
+        let response: PricingListResponse = serde_json::from_str(&json).unwrap();
+        let hosts = dedicated_hosts(&response, &RegionNames::bundled());
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(hosts[0].physical_cores, Some(48));
+        assert_eq!(hosts[0].capacity.len(), 2);
+
+        let reservations = [HostReservationOffering {
+            offering_id: "hro-1".to_string(),
+            instance_family: "m5".to_string(),
+            term: Some(ContractLength::OneYear),
+            purchase_option: PurchaseOption::AllUpfront,
+            upfront_usd: Decimal::from(26280),
+            usd_per_hour: Decimal::ZERO,
+        }];
+        let rows = slot_costs(&hosts, "us-east-1", &reservations, &pivot(response));
+        assert_eq!(rows.len(), 2);
+        let large = &rows[0];
+        assert_eq!(large.instance_type, "m5.large");
+        // 5.069 / 48
+        assert_eq!(large.usd_per_slot_hour, Decimal::new(105604, 6));
+        // 26280 / 8760 / 48
+        assert_eq!(
+            large.reserved_usd_per_slot_hour,
+            Some(Decimal::new(62500, 6))
+        );
+        assert_eq!(large.shared_usd_per_hour, Some(Decimal::new(96, 3)));
+        assert_eq!(large.premium_percent, Some(Decimal::new(1000, 2)));
+        assert_eq!(rows[1].shared_usd_per_hour, None);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::model::aws::price_bulk_types::PricingListResponse;
+use crate::model::aws::types::PriceDimension;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A rate code whose USD price differs between two offer versions.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PriceChange {
+    pub rate_code: String,
+    pub sku: String,
+    pub unit: String,
+    /// `None` if the rate code is new
+    pub from_usd: Option<Decimal>,
+    /// `None` if the rate code was removed
+    pub to_usd: Option<Decimal>,
+    /// Empty unless both prices are set and the old one is not zero
+    pub change_percent: Option<Decimal>,
+}
+
+/// Changes between two offer versions of a pricing list.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct OfferDiff {
+    pub from_version: String,
+    pub to_version: String,
+    pub added_skus: Vec<String>,
+    pub removed_skus: Vec<String>,
+    /// SKUs of `added_skus` listed in a version before `from_version` and removed since, as
+    /// told by the SKU history. Empty without one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub restored_skus: Vec<String>,
+    /// Price changes of on-demand and reserved rate codes, by rate code
+    pub changed: Vec<PriceChange>,
+}
+
+impl OfferDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_skus.is_empty() && self.removed_skus.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Price dimensions of all on-demand and reserved terms of `response`, by rate code.
+fn dimensions(response: &PricingListResponse) -> BTreeMap<&str, (&str, &PriceDimension)> {
+    let on_demand = response
+        .terms
+        .on_demand
+        .values()
+        .flat_map(|offerings| offerings.values())
+        .map(|offering| (&offering.sku, &offering.price_dimensions));
+    let reserved = response
+        .terms
+        .reserved
+        .values()
+        .flat_map(|offerings| offerings.values())
+        .map(|offering| (&offering.sku, &offering.price_dimensions));
+    on_demand
+        .chain(reserved)
+        .flat_map(|(sku, dimensions)| {
+            dimensions
+                .values()
+                .map(move |dimension| (dimension.rate_code.as_str(), (sku.as_str(), dimension)))
+        })
+        .collect()
+}
+
+/// Compares two offer versions of the same pricing list. Rate codes of SKUs added or removed
+/// entirely are reported as SKUs only.
+pub fn diff(from: &PricingListResponse, to: &PricingListResponse) -> OfferDiff {
+    let from_skus: BTreeSet<&str> = from.products.keys().map(String::as_str).collect();
+    let to_skus: BTreeSet<&str> = to.products.keys().map(String::as_str).collect();
+    let from_dimensions = dimensions(from);
+    let to_dimensions = dimensions(to);
+
+    let rate_codes: BTreeSet<&str> = from_dimensions
+        .keys()
+        .chain(to_dimensions.keys())
+        .copied()
+        .collect();
+    let changed = rate_codes
+        .into_iter()
+        .filter_map(|rate_code| {
+            let before = from_dimensions.get(rate_code);
+            let after = to_dimensions.get(rate_code);
+            let (sku, dimension) = after.or(before)?;
+            if !from_skus.contains(sku) || !to_skus.contains(sku) {
+                return None;
+            }
+            let from_usd = before.and_then(|(_, dimension)| dimension.usd());
+            let to_usd = after.and_then(|(_, dimension)| dimension.usd());
+            if from_usd == to_usd {
+                return None;
+            }
+            let change_percent = from_usd
+                .zip(to_usd)
+                .filter(|(from_usd, _)| !from_usd.is_zero())
+                .map(|(from_usd, to_usd)| {
+                    ((to_usd - from_usd) * Decimal::ONE_HUNDRED / from_usd).round_dp(2)
+                });
+            Some(PriceChange {
+                rate_code: rate_code.to_string(),
+                sku: sku.to_string(),
+                unit: dimension.unit.clone(),
+                from_usd,
+                to_usd,
+                change_percent,
+            })
+        })
+        .collect();
+
+    OfferDiff {
+        from_version: from.version.clone(),
+        to_version: to.version.clone(),
+        added_skus: to_skus
+            .difference(&from_skus)
+            .map(|sku| sku.to_string())
+            .collect(),
+        removed_skus: from_skus
+            .difference(&to_skus)
+            .map(|sku| sku.to_string())
+            .collect(),
+        restored_skus: Vec::new(),
+        changed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff;
+    use crate::model::aws::price_bulk_types::PricingListResponse;
+    use rust_decimal::Decimal;
+
+    fn response(version: &str, prices: &[(&str, &str)]) -> PricingListResponse {
+        let products = prices
+            .iter()
+            .map(|(sku, _)| {
+                format!(
+                    r#""{sku}": {{"sku": "{sku}", "productFamily": "Compute Instance",
+                        "attributes": {{}}}}"#
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let on_demand = prices
+            .iter()
+            .map(|(sku, price)| {
+                format!(
+                    r#""{sku}": {{"{sku}.JRTCKXETXF": {{"offerTermCode": "JRTCKXETXF",
+                        "sku": "{sku}", "effectiveDate": "2024-03-01T00:00:00Z",
+                        "termAttributes": {{}}, "priceDimensions": {{
+                            "{sku}.JRTCKXETXF.6YS6EN2CT7": {{
+                                "rateCode": "{sku}.JRTCKXETXF.6YS6EN2CT7", "description": "",
+                                "unit": "Hrs", "pricePerUnit": {{"USD": "{price}"}}}}}}}}}}"#
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        serde_json::from_str(&format!(
+            r#"{{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "{version}", "products": {{{products}}},
+            "terms": {{"OnDemand": {{{on_demand}}}, "Reserved": {{}}}}}}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_diff() {
+        let from = response(
+            "20240301000000",
+            &[("SKU1", "0.1000000000"), ("SKU2", "0.2000000000")],
+        );
+        let to = response(
+            "20240401000000",
+            &[("SKU1", "0.0900000000"), ("SKU3", "0.3000000000")],
+        );
+
+        let offer_diff = diff(&from, &to);
+        assert_eq!(offer_diff.added_skus, ["SKU3"]);
+        assert_eq!(offer_diff.removed_skus, ["SKU2"]);
+        assert_eq!(offer_diff.changed.len(), 1);
+        let change = &offer_diff.changed[0];
+        assert_eq!(change.rate_code, "SKU1.JRTCKXETXF.6YS6EN2CT7");
+        assert_eq!(change.from_usd, Some(Decimal::new(1, 1)));
+        assert_eq!(change.change_percent, Some(Decimal::from(-10)));
+        assert!(diff(&from, &from).is_empty());
+    }
+}
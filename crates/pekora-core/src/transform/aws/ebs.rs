@@ -0,0 +1,259 @@
+use crate::metrics;
+use crate::model::aws::price_bulk_types::EbsPricingListResponse;
+use crate::transform::aws::tiered::TieredPrice;
+use anyhow::anyhow;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// What an EBS price is charged per.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EbsMeter {
+    /// GB-month of provisioned storage
+    Storage,
+    /// Provisioned IOPS per month
+    Iops,
+    /// Provisioned throughput per month
+    Throughput,
+}
+
+impl EbsMeter {
+    /// Classifies a usage type such as `APN1-EBS:VolumeUsage.gp3` or `EBS:VolumeP-IOPS.io2`.
+    pub fn from_usage_type(usage_type: &str) -> Option<Self> {
+        if usage_type.contains("EBS:VolumeUsage") {
+            Some(Self::Storage)
+        } else if usage_type.contains("EBS:VolumeP-IOPS") {
+            Some(Self::Iops)
+        } else if usage_type.contains("EBS:VolumeP-Throughput") {
+            Some(Self::Throughput)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EbsRate {
+    /// Volume type as the EC2 API names it, e.g. `gp3`
+    pub volume_type: String,
+    pub meter: EbsMeter,
+    pub price: TieredPrice,
+}
+
+/// Monthly prices of the EBS volume types in an AmazonEC2 pricing list. io2 IOPS are priced in
+/// tiers.
+pub fn pivot(response: &EbsPricingListResponse) -> Vec<EbsRate> {
+    let mut pivoted = Vec::new();
+    for (sku, offerings) in &response.terms.on_demand {
+        let attributes = match response.products.get(sku) {
+            Some(product) => &product.attributes,
+            None => continue,
+        };
+        let (volume_type, meter) = match attributes.volume_api_name.as_ref().zip(
+            attributes
+                .usage_type
+                .as_deref()
+                .and_then(EbsMeter::from_usage_type),
+        ) {
+            Some(classified) => classified,
+            None => continue,
+        };
+        for offering in offerings.values() {
+            if let Some(price) = TieredPrice::from_dimensions(
+                sku,
+                &offering.offer_term_code,
+                offering.price_dimensions.values(),
+            ) {
+                pivoted.push(EbsRate {
+                    volume_type: volume_type.clone(),
+                    meter,
+                    price,
+                });
+            }
+        }
+    }
+    metrics::global().record_rows_pivoted(pivoted.len() as u64);
+    pivoted
+}
+
+/// A volume to price.
+#[derive(Debug, Clone)]
+pub struct VolumeSpec {
+    pub volume_type: String,
+    pub size_gib: Decimal,
+    /// Provisioned IOPS. gp3 includes 3,000 for free.
+    pub iops: Option<Decimal>,
+    /// Provisioned throughput in MiB/s. gp3 includes 125 for free.
+    pub throughput_mibps: Option<Decimal>,
+}
+
+/// Monthly cost of a volume, by meter.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct VolumeCost {
+    pub volume_type: String,
+    pub storage_usd: Decimal,
+    pub iops_usd: Decimal,
+    pub throughput_usd: Decimal,
+    pub monthly_usd: Decimal,
+}
+
+/// IOPS and MiB/s included in the storage price of `volume_type`.
+fn baseline(volume_type: &str) -> (Decimal, Decimal) {
+    match volume_type {
+        "gp3" => (Decimal::from(3000), Decimal::from(125)),
+        _ => (Decimal::ZERO, Decimal::ZERO),
+    }
+}
+
+/// Prices `spec` with the rates of one region. Fails if the volume type has no storage price,
+/// or IOPS or throughput are provisioned beyond the baseline on a type that doesn't sell them.
+pub fn price_volume(rates: &[EbsRate], spec: &VolumeSpec) -> anyhow::Result<VolumeCost> {
+    let rate = |meter: EbsMeter| {
+        rates
+            .iter()
+            .find(|rate| rate.volume_type == spec.volume_type && rate.meter == meter)
+    };
+    let charge = |meter: EbsMeter, quantity: Decimal| -> anyhow::Result<Decimal> {
+        if quantity <= Decimal::ZERO {
+            return Ok(Decimal::ZERO);
+        }
+        let rate = rate(meter).ok_or_else(|| {
+            anyhow!(
+                "{} volumes have no {:?} price in this region",
+                spec.volume_type,
+                meter
+            )
+        })?;
+        // gp3 throughput is listed per GiBps-month
+        let quantity = if meter == EbsMeter::Throughput && rate.price.unit.starts_with("GiBps") {
+            quantity / Decimal::from(1024)
+        } else {
+            quantity
+        };
+        Ok(rate.price.cost(quantity))
+    };
+
+    let (free_iops, free_throughput) = baseline(&spec.volume_type);
+    let storage_usd = charge(EbsMeter::Storage, spec.size_gib)?;
+    let iops_usd = charge(EbsMeter::Iops, spec.iops.unwrap_or_default() - free_iops)?;
+    let throughput_usd = charge(
+        EbsMeter::Throughput,
+        spec.throughput_mibps.unwrap_or_default() - free_throughput,
+    )?;
+    Ok(VolumeCost {
+        volume_type: spec.volume_type.clone(),
+        storage_usd: storage_usd.round_dp(2),
+        iops_usd: iops_usd.round_dp(2),
+        throughput_usd: throughput_usd.round_dp(2),
+        monthly_usd: (storage_usd + iops_usd + throughput_usd).round_dp(2),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pivot, price_volume, VolumeSpec};
+    use crate::model::aws::price_bulk_types::{EbsPricingListResponse, PricingListResponse};
+    use rust_decimal::Decimal;
+
+    fn product(sku: &str, volume_api_name: &str, usage_type: &str) -> String {
+        format!(
+            r#""{sku}": {{"sku": "{sku}", "productFamily": "Storage", "attributes": {{
+                "volumeApiName": "{volume_api_name}", "usagetype": "APN1-{usage_type}",
+                "regionCode": "ap-northeast-1"}}}}"#
+        )
+    }
+
+    fn offering(sku: &str, unit: &str, tiers: &[(&str, Option<&str>, &str)]) -> String {
+        let dimensions = tiers
+            .iter()
+            .enumerate()
+            .map(|(i, (begin, end, price))| {
+                format!(
+                    r#""{sku}.JRTCKXETXF.{i}": {{"rateCode": "{sku}.JRTCKXETXF.{i}",
+                        "description": "", "unit": "{unit}", "beginRange": "{begin}",
+                        "endRange": "{}", "pricePerUnit": {{"USD": "{price}"}}}}"#,
+                    end.unwrap_or("Inf")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            r#""{sku}": {{"{sku}.JRTCKXETXF": {{"offerTermCode": "JRTCKXETXF", "sku": "{sku}",
+                "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {{}},
+                "priceDimensions": {{{dimensions}}}}}}}"#
+        )
+    }
+
+    #[test]
+    fn test_price_volume() {
+        let json = format!(
+            r#"{{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724",
+            "products": {{{}, {}, {}, {}, {}}},
+            "terms": {{"OnDemand": {{{}, {}, {}, {}, {}}}, "Reserved": {{}}}}}}"#,
+            product("GP3", "gp3", "EBS:VolumeUsage.gp3"),
+            product("GP3IOPS", "gp3", "EBS:VolumeP-IOPS.gp3"),
+            product("GP3TP", "gp3", "EBS:VolumeP-Throughput.gp3"),
+            product("IO2", "io2", "EBS:VolumeUsage.io2"),
+            product("IO2IOPS", "io2", "EBS:VolumeP-IOPS.io2"),
+            offering("GP3", "GB-Mo", &[("0", None, "0.096")]),
+            offering("GP3IOPS", "IOPS-Mo", &[("0", None, "0.006")]),
+            offering("GP3TP", "GiBps-mo", &[("0", None, "49.152")]),
+            offering("IO2", "GB-Mo", &[("0", None, "0.142")]),
+            offering(
+                "IO2IOPS",
+                "IOPS-Mo",
+                &[
+                    ("0", Some("32000"), "0.074"),
+                    ("32000", Some("64000"), "0.052"),
+                    ("64000", None, "0.036")
+                ]
+            ),
+        );
+        let response: PricingListResponse = serde_json::from_str(&json).unwrap();
+        let typed: EbsPricingListResponse = response.with_typed_attributes().unwrap();
+        let rates = pivot(&typed);
+        assert_eq!(rates.len(), 5);
+
+        // 100 GiB, 1,000 IOPS and 125 MiB/s over the free baseline
+        let gp3 = price_volume(
+            &rates,
+            &VolumeSpec {
+                volume_type: "gp3".to_string(),
+                size_gib: Decimal::from(100),
+                iops: Some(Decimal::from(4000)),
+                throughput_mibps: Some(Decimal::from(250)),
+            },
+        )
+        .unwrap();
+        assert_eq!(gp3.storage_usd, Decimal::new(96, 1));
+        assert_eq!(gp3.iops_usd, Decimal::from(6));
+        assert_eq!(gp3.throughput_usd, Decimal::from(6));
+        assert_eq!(gp3.monthly_usd, Decimal::new(216, 1));
+
+        let io2 = price_volume(
+            &rates,
+            &VolumeSpec {
+                volume_type: "io2".to_string(),
+                size_gib: Decimal::from(100),
+                iops: Some(Decimal::from(40000)),
+                throughput_mibps: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            io2.iops_usd,
+            Decimal::from(32000 * 74 + 8000 * 52) / Decimal::from(1000)
+        );
+
+        let mut st1 = VolumeSpec {
+            volume_type: "st1".to_string(),
+            size_gib: Decimal::from(500),
+            iops: None,
+            throughput_mibps: None,
+        };
+        assert!(price_volume(&rates, &st1).is_err());
+        st1.volume_type = "io2".to_string();
+        st1.throughput_mibps = Some(Decimal::from(500));
+        assert!(price_volume(&rates, &st1).is_err());
+    }
+}
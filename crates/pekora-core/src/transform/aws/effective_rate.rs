@@ -0,0 +1,229 @@
+use crate::metrics;
+use crate::model::aws::price_bulk_types::PricingListResponse;
+use crate::model::aws::types::{
+    ContractLength, LeaseContractLength, PriceOffering, PurchaseOption, RITermAttributes,
+};
+use crate::model::aws::unit::Unit;
+use crate::transform::aws::on_demand::OnDemandRate;
+use crate::transform::aws::savings_plan::PivotedSavingsPlanTermRate;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+const HOURS_PER_YEAR: i64 = 8760;
+
+/// Hourly rate of a commitment with its upfront fee spread over the contract length, so
+/// commitments with different payment options compare with each other and with on-demand.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EffectiveRate {
+    /// On-demand SKU the rate applies to
+    pub sku: String,
+    /// Reserved offering term code or savings plan rate code
+    pub rate_code: String,
+    pub lease_contract_length: ContractLength,
+    pub purchase_option: PurchaseOption,
+    pub upfront_usd: Decimal,
+    pub recurring_usd_per_hour: Decimal,
+    pub effective_usd_per_hour: Decimal,
+    pub on_demand_usd_per_hour: Option<Decimal>,
+}
+
+impl EffectiveRate {
+    /// Savings of the effective rate over on-demand in percent.
+    pub fn savings_percent(&self) -> Option<Decimal> {
+        let on_demand = self
+            .on_demand_usd_per_hour
+            .filter(|price| !price.is_zero())?;
+        Some(
+            ((on_demand - self.effective_usd_per_hour) * Decimal::ONE_HUNDRED / on_demand)
+                .round_dp(2),
+        )
+    }
+}
+
+/// Hours in a contract of `length`, `None` for unknown lengths.
+pub fn contract_hours(length: &ContractLength) -> Option<Decimal> {
+    match length {
+        ContractLength::OneYear => Some(Decimal::from(HOURS_PER_YEAR)),
+        ContractLength::ThreeYear => Some(Decimal::from(3 * HOURS_PER_YEAR)),
+        ContractLength::Unknown(_) => None,
+    }
+}
+
+fn lease_hours(length: &LeaseContractLength) -> Option<Decimal> {
+    match length.unit.to_ascii_lowercase().as_str() {
+        "year" | "years" => Some(Decimal::from(i64::from(length.duration) * HOURS_PER_YEAR)),
+        _ => None,
+    }
+}
+
+/// Share of a savings plan commitment paid upfront. Partial upfront plans pay half.
+pub(crate) fn upfront_share(purchase_option: &PurchaseOption) -> Decimal {
+    match purchase_option {
+        PurchaseOption::PartialUpfront => Decimal::new(5, 1),
+        PurchaseOption::AllUpfront => Decimal::ONE,
+        _ => Decimal::ZERO,
+    }
+}
+
+/// Effective rates of the hourly reserved offerings in `response`, next to the hourly on-demand
+/// price of the same SKU. Offerings of unknown length or without USD prices are left out.
+pub fn reserved(response: &PricingListResponse) -> Vec<EffectiveRate> {
+    let mut rates = Vec::new();
+    for (sku, offerings) in &response.terms.reserved {
+        let on_demand_usd_per_hour = response
+            .terms
+            .on_demand
+            .get(sku)
+            .into_iter()
+            .flat_map(|offerings| offerings.values())
+            .flat_map(|offering| offering.price_dimensions.values())
+            .find(|dimension| dimension.parsed_unit() == Unit::Hours)
+            .and_then(|dimension| dimension.usd());
+        rates.extend(
+            offerings
+                .values()
+                .filter_map(|offering| reserved_offering(sku, offering, on_demand_usd_per_hour)),
+        );
+    }
+    sort(&mut rates);
+    metrics::global().record_rows_pivoted(rates.len() as u64);
+    rates
+}
+
+/// Effective rate of one reserved `offering` of `sku`, `None` if it is not an hourly offering of
+/// known length.
+pub fn reserved_offering(
+    sku: &str,
+    offering: &PriceOffering<RITermAttributes>,
+    on_demand_usd_per_hour: Option<Decimal>,
+) -> Option<EffectiveRate> {
+    let attributes = &offering.term_attributes;
+    let hours = contract_hours(&attributes.lease_contract_length)?;
+    let mut upfront_usd = Some(Decimal::ZERO);
+    let mut recurring_usd_per_hour = None;
+    for dimension in offering.price_dimensions.values() {
+        match dimension.parsed_unit() {
+            Unit::Quantity => upfront_usd = dimension.usd(),
+            Unit::Hours => recurring_usd_per_hour = dimension.usd(),
+            _ => {}
+        }
+    }
+    let (upfront_usd, recurring_usd_per_hour) = upfront_usd.zip(recurring_usd_per_hour)?;
+    Some(EffectiveRate {
+        sku: sku.to_string(),
+        rate_code: offering.offer_term_code.clone(),
+        lease_contract_length: attributes.lease_contract_length.clone(),
+        purchase_option: attributes.purchase_option.clone(),
+        upfront_usd,
+        recurring_usd_per_hour,
+        effective_usd_per_hour: recurring_usd_per_hour + upfront_usd / hours,
+        on_demand_usd_per_hour,
+    })
+}
+
+/// Effective rates of savings plan `rows`, next to the hourly `on_demand` price of the discounted
+/// SKU. Savings plan rates already include the upfront payment, which is split out here.
+pub fn savings_plan(
+    rows: &[PivotedSavingsPlanTermRate],
+    on_demand: &[OnDemandRate],
+) -> Vec<EffectiveRate> {
+    let on_demand_prices = hourly_on_demand_prices(on_demand);
+    let mut rates: Vec<EffectiveRate> = rows
+        .iter()
+        .filter_map(|row| {
+            let on_demand_usd_per_hour = on_demand_prices
+                .get(row.term_rate.discounted_sku.as_str())
+                .copied();
+            savings_plan_row(row, on_demand_usd_per_hour)
+        })
+        .collect();
+    sort(&mut rates);
+    metrics::global().record_rows_pivoted(rates.len() as u64);
+    rates
+}
+
+/// Hourly USD on-demand price by SKU.
+pub(crate) fn hourly_on_demand_prices(on_demand: &[OnDemandRate]) -> HashMap<&str, Decimal> {
+    on_demand
+        .iter()
+        .filter(|rate| rate.parsed_unit() == Unit::Hours)
+        .filter_map(|rate| Some((rate.sku.as_str(), rate.price_per_unit.get("USD")?.value()?)))
+        .collect()
+}
+
+pub(crate) fn savings_plan_row(
+    row: &PivotedSavingsPlanTermRate,
+    on_demand_usd_per_hour: Option<Decimal>,
+) -> Option<EffectiveRate> {
+    let hours = lease_hours(&row.lease_contract_length)?;
+    let effective_usd_per_hour = row.term_rate.discounted_rate.usd()?;
+    let purchase_option = row.savings_plan_attributes.purchase_option.clone();
+    let share = upfront_share(&purchase_option);
+    Some(EffectiveRate {
+        sku: row.term_rate.discounted_sku.clone(),
+        rate_code: row.term_rate.rate_code.clone(),
+        lease_contract_length: row.savings_plan_attributes.purchase_term.clone(),
+        upfront_usd: effective_usd_per_hour * hours * share,
+        recurring_usd_per_hour: effective_usd_per_hour * (Decimal::ONE - share),
+        effective_usd_per_hour,
+        purchase_option,
+        on_demand_usd_per_hour,
+    })
+}
+
+fn sort(rates: &mut [EffectiveRate]) {
+    rates.sort_by(|a, b| (&a.sku, &a.rate_code).cmp(&(&b.sku, &b.rate_code)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::reserved;
+    use crate::model::aws::price_bulk_types::PricingListResponse;
+    use crate::model::aws::types::PurchaseOption;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_reserved() {
+        let response: PricingListResponse = serde_json::from_str(
+            r#"{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724",
+            "products": {"SKU1": {"sku": "SKU1", "productFamily": "Compute Instance",
+                "attributes": {"instanceType": "m5.large"}}},
+            "terms": {
+                "OnDemand": {"SKU1": {"SKU1.JRTCKXETXF": {"offerTermCode": "JRTCKXETXF",
+                    "sku": "SKU1", "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {},
+                    "priceDimensions": {"SKU1.JRTCKXETXF.6YS6EN2CT7": {
+                        "rateCode": "SKU1.JRTCKXETXF.6YS6EN2CT7", "description": "",
+                        "unit": "Hrs", "pricePerUnit": {"USD": "0.0960000000"}}}}}},
+                "Reserved": {"SKU1": {
+                    "SKU1.6QCMYABX3D": {"offerTermCode": "6QCMYABX3D", "sku": "SKU1",
+                        "effectiveDate": "2024-03-01T00:00:00Z",
+                        "termAttributes": {"LeaseContractLength": "1yr",
+                            "OfferingClass": "standard", "PurchaseOption": "All Upfront"},
+                        "priceDimensions": {
+                            "SKU1.6QCMYABX3D.2TG2D8R56U": {"rateCode": "SKU1.6QCMYABX3D.2TG2D8R56U",
+                                "description": "Upfront Fee", "unit": "Quantity",
+                                "pricePerUnit": {"USD": "525.6"}},
+                            "SKU1.6QCMYABX3D.6YS6EN2CT7": {"rateCode": "SKU1.6QCMYABX3D.6YS6EN2CT7",
+                                "description": "", "unit": "Hrs",
+                                "pricePerUnit": {"USD": "0.0000000000"}}}},
+                    "SKU1.BPH4J8HBKS": {"offerTermCode": "BPH4J8HBKS", "sku": "SKU1",
+                        "effectiveDate": "2024-03-01T00:00:00Z",
+                        "termAttributes": {"LeaseContractLength": "1yr",
+                            "OfferingClass": "standard", "PurchaseOption": "No Upfront"},
+                        "priceDimensions": {
+                            "SKU1.BPH4J8HBKS.6YS6EN2CT7": {"rateCode": "SKU1.BPH4J8HBKS.6YS6EN2CT7",
+                                "description": "", "unit": "Hrs",
+                                "pricePerUnit": {"USD": "0.0650000000"}}}}}}}}"#,
+        )
+        .unwrap();
+        let rates = reserved(&response);
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].purchase_option, PurchaseOption::AllUpfront);
+        assert_eq!(rates[0].effective_usd_per_hour, Decimal::new(6, 2));
+        assert_eq!(rates[0].savings_percent(), Some(Decimal::new(375, 1)));
+        assert_eq!(rates[1].purchase_option, PurchaseOption::NoUpfront);
+        assert_eq!(rates[1].upfront_usd, Decimal::ZERO);
+        assert_eq!(rates[1].effective_usd_per_hour, Decimal::new(65, 3));
+    }
+}
@@ -0,0 +1,264 @@
+use crate::metrics;
+use crate::model::aws::price_bulk_types::ElastiCachePricingListResponse;
+use crate::model::aws::types::{ContractLength, PurchaseOption};
+use crate::model::aws::unit::{Unit, HOURS_PER_MONTH};
+use crate::transform::aws::effective_rate::reserved_offering;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A reserved node offering next to the on-demand price of the same node.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ReservedNodeComparison {
+    pub region: String,
+    /// e.g. `cache.r6g.large`
+    pub node_type: String,
+    /// e.g. `Redis`
+    pub cache_engine: Option<String>,
+    pub term: ContractLength,
+    pub purchase_option: PurchaseOption,
+    pub upfront_usd: Decimal,
+    pub recurring_usd_per_hour: Decimal,
+    /// Recurring charges plus the upfront fee spread over the term
+    pub effective_usd_per_hour: Decimal,
+    pub on_demand_usd_per_hour: Option<Decimal>,
+    pub savings_percent: Option<Decimal>,
+}
+
+/// Hourly on-demand USD price of `sku`.
+fn on_demand_usd_per_hour(response: &ElastiCachePricingListResponse, sku: &str) -> Option<Decimal> {
+    response
+        .terms
+        .on_demand
+        .get(sku)?
+        .values()
+        .flat_map(|offering| offering.price_dimensions.values())
+        .find(|dimension| dimension.parsed_unit() == Unit::Hours)?
+        .usd()
+}
+
+/// Reserved node offerings of an ElastiCache pricing list of `region`, each with the on-demand
+/// price of its node. Sorted by node type, engine, term and then effective rate.
+pub fn compare_reserved(
+    response: &ElastiCachePricingListResponse,
+    region: &str,
+) -> Vec<ReservedNodeComparison> {
+    let mut rows = Vec::new();
+    for (sku, offerings) in &response.terms.reserved {
+        let attributes = match response.products.get(sku) {
+            Some(product) => &product.attributes,
+            None => continue,
+        };
+        let node_type = match attributes.node_type() {
+            Some(node_type) => node_type,
+            None => continue,
+        };
+        let on_demand = on_demand_usd_per_hour(response, sku);
+        for offering in offerings.values() {
+            let rate = match reserved_offering(sku, offering, on_demand) {
+                Some(rate) => rate,
+                None => continue,
+            };
+            rows.push(ReservedNodeComparison {
+                region: region.to_string(),
+                node_type: node_type.to_string(),
+                cache_engine: attributes.cache_engine.clone(),
+                term: rate.lease_contract_length.clone(),
+                purchase_option: rate.purchase_option.clone(),
+                upfront_usd: rate.upfront_usd,
+                recurring_usd_per_hour: rate.recurring_usd_per_hour,
+                effective_usd_per_hour: rate.effective_usd_per_hour.round_dp(6),
+                on_demand_usd_per_hour: on_demand,
+                savings_percent: rate.savings_percent(),
+            });
+        }
+    }
+    rows.sort_by(|a, b| {
+        (&a.node_type, &a.cache_engine, a.term.as_str())
+            .cmp(&(&b.node_type, &b.cache_engine, b.term.as_str()))
+            .then(a.effective_usd_per_hour.cmp(&b.effective_usd_per_hour))
+    });
+    metrics::global().record_rows_pivoted(rows.len() as u64);
+    rows
+}
+
+/// Engine whose node type specific parameters bound the memory available to data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheEngine {
+    Redis,
+    Memcached,
+}
+
+impl CacheEngine {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "redis" => Some(Self::Redis),
+            "memcached" => Some(Self::Memcached),
+            _ => None,
+        }
+    }
+
+    /// Cache engine as the pricing list names it.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Redis => "Redis",
+            Self::Memcached => "Memcached",
+        }
+    }
+
+    /// Parameter holding the usable memory of a node type, and how many of its units make a GiB.
+    fn memory_parameter(&self) -> (&'static str, Decimal) {
+        match self {
+            // bytes
+            Self::Redis => ("maxmemory", Decimal::from(1 << 30)),
+            // MiB
+            Self::Memcached => ("max_cache_memory", Decimal::from(1024)),
+        }
+    }
+}
+
+/// On-demand price of a node type per GiB of memory the engine can use, which is less than the
+/// memory of the node.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct UsableMemoryPrice {
+    pub region: String,
+    pub node_type: String,
+    pub cache_engine: String,
+    pub usable_memory_gib: Decimal,
+    pub on_demand_usd_per_hour: Decimal,
+    pub usd_per_usable_gib_hour: Decimal,
+    pub usd_per_usable_gib_month: Decimal,
+}
+
+/// Joins the on-demand node prices of an ElastiCache pricing list of `region` with the node type
+/// specific `parameters` of `engine`, parameter values by name by node type. Node types without
+/// a memory parameter are skipped. Sorted from the cheapest per usable GiB.
+pub fn usable_memory_prices(
+    response: &ElastiCachePricingListResponse,
+    region: &str,
+    engine: CacheEngine,
+    parameters: &HashMap<String, HashMap<String, String>>,
+) -> Vec<UsableMemoryPrice> {
+    let (parameter, units_per_gib) = engine.memory_parameter();
+    let mut rows: Vec<UsableMemoryPrice> = response
+        .products
+        .iter()
+        .filter(|(_, product)| product.attributes.cache_engine.as_deref() == Some(engine.as_str()))
+        .filter_map(|(sku, product)| {
+            let node_type = product.attributes.node_type()?;
+            let usable_memory_gib = parameters
+                .get(node_type)?
+                .get(parameter)?
+                .parse::<Decimal>()
+                .ok()?
+                / units_per_gib;
+            let on_demand_usd_per_hour = on_demand_usd_per_hour(response, sku)?;
+            if usable_memory_gib.is_zero() {
+                return None;
+            }
+            let usd_per_usable_gib_hour = on_demand_usd_per_hour / usable_memory_gib;
+            Some(UsableMemoryPrice {
+                region: region.to_string(),
+                node_type: node_type.to_string(),
+                cache_engine: engine.as_str().to_string(),
+                usable_memory_gib: usable_memory_gib.round_dp(2),
+                on_demand_usd_per_hour,
+                usd_per_usable_gib_hour: usd_per_usable_gib_hour.round_dp(6),
+                usd_per_usable_gib_month: (usd_per_usable_gib_hour
+                    * Decimal::from(HOURS_PER_MONTH))
+                .round_dp(2),
+            })
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        a.usd_per_usable_gib_hour
+            .cmp(&b.usd_per_usable_gib_hour)
+            .then_with(|| a.node_type.cmp(&b.node_type))
+    });
+    metrics::global().record_rows_pivoted(rows.len() as u64);
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare_reserved, usable_memory_prices, CacheEngine};
+    use crate::model::aws::price_bulk_types::{
+        ElastiCachePricingListResponse, PricingListResponse,
+    };
+    use crate::model::aws::types::{ContractLength, PurchaseOption};
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_reserved_and_usable_memory() {
+        let response: PricingListResponse = serde_json::from_str(
+            r#"{
+                "formatVersion": "v1.0",
+                "publicationDate": "2024-03-12T15:37:24Z",
+                "version": "20240312153724",
+                "products": {
+                    "NODE": {"productFamily": "Cache Instance", "sku": "NODE",
+                        "attributes": {"cacheEngine": "Redis", "instanceType": "cache.r6g.large"}}
+                },
+                "terms": {
+                    "OnDemand": {"NODE": {"NODE.JRTCKXETXF": {"offerTermCode": "JRTCKXETXF",
+                        "sku": "NODE", "effectiveDate": "2024-03-01T00:00:00Z",
+                        "termAttributes": {}, "priceDimensions": {
+                            "NODE.JRTCKXETXF.6YS6EN2CT7": {"rateCode": "NODE.JRTCKXETXF.6YS6EN2CT7",
+                                "description": "", "unit": "Hrs",
+                                "pricePerUnit": {"USD": "0.2060000000"}}}}}},
+                    "Reserved": {"NODE": {
+                        "NODE.6QCMYABX3D": {"offerTermCode": "6QCMYABX3D", "sku": "NODE",
+                            "effectiveDate": "2024-03-01T00:00:00Z",
+                            "termAttributes": {"LeaseContractLength": "1yr",
+                                "PurchaseOption": "All Upfront"},
+                            "priceDimensions": {
+                                "NODE.6QCMYABX3D.2TG2D8R56U": {"rateCode": "NODE.6QCMYABX3D.2TG2D8R56U",
+                                    "description": "Upfront Fee", "unit": "Quantity",
+                                    "pricePerUnit": {"USD": "1138"}},
+                                "NODE.6QCMYABX3D.6YS6EN2CT7": {"rateCode": "NODE.6QCMYABX3D.6YS6EN2CT7",
+                                    "description": "", "unit": "Hrs",
+                                    "pricePerUnit": {"USD": "0.0000000000"}}}},
+                        "NODE.4NA7Y494T4": {"offerTermCode": "4NA7Y494T4", "sku": "NODE",
+                            "effectiveDate": "2024-03-01T00:00:00Z",
+                            "termAttributes": {"LeaseContractLength": "1yr",
+                                "PurchaseOption": "No Upfront"},
+                            "priceDimensions": {
+                                "NODE.4NA7Y494T4.6YS6EN2CT7": {"rateCode": "NODE.4NA7Y494T4.6YS6EN2CT7",
+                                    "description": "", "unit": "Hrs",
+                                    "pricePerUnit": {"USD": "0.1360000000"}}}}}}
+                }
+            }"#,
+        )
+        .unwrap();
+        let typed: ElastiCachePricingListResponse = response.with_typed_attributes().unwrap();
+        let rows = compare_reserved(&typed, "us-east-1");
+        assert_eq!(rows.len(), 2);
+
+        let all_upfront = &rows[0];
+        assert_eq!(all_upfront.node_type, "cache.r6g.large");
+        assert_eq!(all_upfront.term, ContractLength::OneYear);
+        assert_eq!(all_upfront.purchase_option, PurchaseOption::AllUpfront);
+        assert_eq!(all_upfront.effective_usd_per_hour, Decimal::new(129909, 6));
+        assert_eq!(all_upfront.savings_percent, Some(Decimal::new(3694, 2)));
+
+        let no_upfront = &rows[1];
+        assert_eq!(no_upfront.purchase_option, PurchaseOption::NoUpfront);
+        assert_eq!(no_upfront.savings_percent, Some(Decimal::new(3398, 2)));
+
+        // 13 GiB usable of the 13.07 GiB node
+        let parameters = HashMap::from([(
+            "cache.r6g.large".to_string(),
+            HashMap::from([("maxmemory".to_string(), "13958643712".to_string())]),
+        )]);
+        let rows = usable_memory_prices(&typed, "us-east-1", CacheEngine::Redis, &parameters);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].usable_memory_gib, Decimal::from(13));
+        assert_eq!(rows[0].usd_per_usable_gib_hour, Decimal::new(15846, 6));
+        assert_eq!(rows[0].usd_per_usable_gib_month, Decimal::new(1157, 2));
+        assert!(
+            usable_memory_prices(&typed, "us-east-1", CacheEngine::Memcached, &parameters)
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use crate::model::aws::price_bulk_types::PricingListResponse;
+use crate::model::aws::types::{ContractLength, PurchaseOption, RIOfferingClass, SavingsPlanType};
+use crate::model::aws::unit::{Unit, HOURS_PER_MONTH};
+use crate::transform::aws::effective_rate::{reserved_offering, savings_plan_row, EffectiveRate};
+use crate::transform::aws::network::NetworkCostLine;
+use crate::transform::aws::normalize::PurchaseModel;
+use crate::transform::aws::on_demand::is_plain_instance;
+use crate::transform::aws::recommendation::Commitment;
+use crate::transform::aws::savings_plan::PivotedSavingsPlanTermRate;
+use anyhow::anyhow;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Instances a workload runs on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstanceRequirement {
+    InstanceType(String),
+    /// Cheapest instance type with at least this many vCPUs and GiB of memory
+    Size {
+        vcpus: u32,
+        memory_gib: Decimal,
+    },
+}
+
+/// A workload to estimate the monthly cost of.
+#[derive(Debug, Clone)]
+pub struct WorkloadSpec {
+    pub instance: InstanceRequirement,
+    pub region: String,
+    pub operating_system: String,
+    pub tenancy: String,
+    pub instances: Decimal,
+    /// Hours each instance runs per month. Commitments are paid for every hour regardless.
+    pub hours_per_month: Decimal,
+    /// Estimate this term and payment option only, all of them if `None`
+    pub commitment: Option<Commitment>,
+}
+
+impl WorkloadSpec {
+    /// Linux instances on shared tenancy running all month.
+    pub fn new(instance: InstanceRequirement, region: String) -> Self {
+        Self {
+            instance,
+            region,
+            operating_system: "Linux".to_string(),
+            tenancy: "Shared".to_string(),
+            instances: Decimal::ONE,
+            hours_per_month: Decimal::from(HOURS_PER_MONTH),
+            commitment: None,
+        }
+    }
+}
+
+/// Monthly cost of a workload under one purchase model.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CostLine {
+    pub purchase_model: PurchaseModel,
+    /// `None` for on-demand
+    pub term: Option<ContractLength>,
+    pub purchase_option: Option<PurchaseOption>,
+    pub upfront_usd: Decimal,
+    /// Recurring charges plus the upfront fee spread over the term
+    pub monthly_usd: Decimal,
+    /// Savings over on-demand in percent, negative if the commitment costs more
+    pub savings_percent: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CostEstimate {
+    pub instance_type: String,
+    pub sku: String,
+    pub on_demand_usd_per_hour: Decimal,
+    /// On-demand first, commitments from the cheapest
+    pub costs: Vec<CostLine>,
+    /// NAT gateways, load balancers and endpoints in front of the instances, priced on-demand
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub network: Vec<NetworkCostLine>,
+}
+
+/// Estimates the monthly cost of `workload` from the EC2 pricing list of its region and the
+/// savings plan rates covering it. Reservations are standard offerings only.
+pub fn estimate<'a>(
+    workload: &WorkloadSpec,
+    response: &PricingListResponse,
+    savings_plans: impl IntoIterator<Item = &'a PivotedSavingsPlanTermRate>,
+) -> anyhow::Result<CostEstimate> {
+    let (sku, instance_type, on_demand_usd_per_hour) = select_instance(workload, response)
+        .ok_or_else(|| {
+            anyhow!(
+                "No {} {} instance matching {:?} in {}",
+                workload.operating_system,
+                workload.tenancy,
+                workload.instance,
+                workload.region
+            )
+        })?;
+    let attributes = &response.products[sku].attributes;
+
+    let mut rates: Vec<(PurchaseModel, EffectiveRate)> = response
+        .terms
+        .reserved
+        .get(sku)
+        .into_iter()
+        .flat_map(|offerings| offerings.values())
+        .filter(|offering| {
+            offering.term_attributes.offering_class == Some(RIOfferingClass::Standard)
+        })
+        .filter_map(|offering| reserved_offering(sku, offering, Some(on_demand_usd_per_hour)))
+        .map(|rate| (PurchaseModel::Reserved, rate))
+        .collect();
+    let usage = attributes.get("usagetype").zip(attributes.get("operation"));
+    for row in savings_plans {
+        let term_rate = &row.term_rate;
+        if usage
+            != Some((
+                &term_rate.discounted_usage_type,
+                &term_rate.discounted_operation,
+            ))
+        {
+            continue;
+        }
+        let purchase_model = match row.plan_type() {
+            SavingsPlanType::Compute => PurchaseModel::ComputeSavingsPlan,
+            SavingsPlanType::Ec2Instance => PurchaseModel::Ec2InstanceSavingsPlan,
+            SavingsPlanType::Unknown(_) => continue,
+        };
+        if let Some(rate) = savings_plan_row(row, Some(on_demand_usd_per_hour)) {
+            rates.push((purchase_model, rate));
+        }
+    }
+    if let Some(commitment) = &workload.commitment {
+        rates.retain(|(_, rate)| {
+            rate.lease_contract_length == commitment.term
+                && rate.purchase_option == commitment.payment_option
+        });
+    }
+
+    let on_demand_monthly = on_demand_usd_per_hour * workload.hours_per_month * workload.instances;
+    let mut commitments: Vec<CostLine> = rates
+        .into_iter()
+        .map(|(purchase_model, rate)| {
+            let monthly_usd =
+                rate.effective_usd_per_hour * Decimal::from(HOURS_PER_MONTH) * workload.instances;
+            CostLine {
+                purchase_model,
+                term: Some(rate.lease_contract_length),
+                purchase_option: Some(rate.purchase_option),
+                upfront_usd: (rate.upfront_usd * workload.instances).round_dp(2),
+                monthly_usd: monthly_usd.round_dp(2),
+                savings_percent: savings_percent(on_demand_monthly, monthly_usd),
+            }
+        })
+        .collect();
+    commitments.sort_by_key(|line| line.monthly_usd);
+    commitments.dedup();
+
+    let mut costs = vec![CostLine {
+        purchase_model: PurchaseModel::OnDemand,
+        term: None,
+        purchase_option: None,
+        upfront_usd: Decimal::ZERO,
+        monthly_usd: on_demand_monthly.round_dp(2),
+        savings_percent: Decimal::ZERO,
+    }];
+    costs.extend(commitments);
+    Ok(CostEstimate {
+        instance_type,
+        sku: sku.clone(),
+        on_demand_usd_per_hour,
+        costs,
+        network: Vec::new(),
+    })
+}
+
+fn savings_percent(on_demand: Decimal, cost: Decimal) -> Decimal {
+    if on_demand.is_zero() {
+        return Decimal::ZERO;
+    }
+    ((on_demand - cost) * Decimal::ONE_HUNDRED / on_demand).round_dp(2)
+}
+
+/// SKU, instance type and hourly on-demand price of the cheapest instance meeting the workload.
+fn select_instance<'a>(
+    workload: &WorkloadSpec,
+    response: &'a PricingListResponse,
+) -> Option<(&'a String, String, Decimal)> {
+    response
+        .products
+        .iter()
+        .filter_map(|(sku, product)| {
+            let attributes = &product.attributes;
+            let instance_type = attributes.get("instanceType")?;
+            let fits = match &workload.instance {
+                InstanceRequirement::InstanceType(required) => required == instance_type,
+                InstanceRequirement::Size { vcpus, memory_gib } => {
+                    attributes
+                        .get("vcpu")
+                        .and_then(|vcpu| vcpu.parse::<u32>().ok())
+                        .is_some_and(|vcpu| vcpu >= *vcpus)
+                        && attributes
+                            .get("memory")
+                            .and_then(|memory| parse_gib(memory))
+                            .is_some_and(|memory| memory >= *memory_gib)
+                }
+            };
+            if !fits
+                || !is_plain_instance(
+                    attributes,
+                    instance_type,
+                    &workload.operating_system,
+                    &workload.tenancy,
+                )
+            {
+                return None;
+            }
+            let price = response
+                .terms
+                .on_demand
+                .get(sku)?
+                .values()
+                .flat_map(|offering| offering.price_dimensions.values())
+                .find(|dimension| dimension.parsed_unit() == Unit::Hours)?
+                .usd()?;
+            Some((sku, instance_type.clone(), price))
+        })
+        .min_by(|a, b| (a.2, &a.1).cmp(&(b.2, &b.1)))
+}
+
+/// Parses memory attributes such as `8 GiB` or `1,952 GiB`.
+fn parse_gib(memory: &str) -> Option<Decimal> {
+    let amount = memory.strip_suffix("GiB")?.trim().replace(',', "");
+    Decimal::from_str(&amount).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{estimate, InstanceRequirement, WorkloadSpec};
+    use crate::model::aws::price_bulk_types::PricingListResponse;
+    use crate::model::aws::types::{ContractLength, PurchaseOption};
+    use crate::transform::aws::normalize::PurchaseModel;
+    use crate::transform::aws::recommendation::Commitment;
+    use rust_decimal::Decimal;
+
+    fn product(sku: &str, instance_type: &str, vcpu: &str, memory: &str) -> String {
+        format!(
+            r#""{sku}": {{"sku": "{sku}", "productFamily": "Compute Instance", "attributes": {{
+                "instanceType": "{instance_type}", "vcpu": "{vcpu}", "memory": "{memory}",
+                "operatingSystem": "Linux", "tenancy": "Shared", "preInstalledSw": "NA",
+                "capacitystatus": "Used", "licenseModel": "No License required",
+                "usagetype": "BoxUsage:{instance_type}", "operation": "RunInstances"}}}}"#
+        )
+    }
+
+    fn on_demand(sku: &str, price: &str) -> String {
+        format!(
+            r#""{sku}": {{"{sku}.JRTCKXETXF": {{"offerTermCode": "JRTCKXETXF", "sku": "{sku}",
+                "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {{}},
+                "priceDimensions": {{"{sku}.JRTCKXETXF.6YS6EN2CT7": {{
+                    "rateCode": "{sku}.JRTCKXETXF.6YS6EN2CT7", "description": "", "unit": "Hrs",
+                    "pricePerUnit": {{"USD": "{price}"}}}}}}}}}}"#
+        )
+    }
+
+    #[test]
+    fn test_estimate() {
+        let response: PricingListResponse = serde_json::from_str(&format!(
+            r#"{{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724",
+            "products": {{{}, {}}},
+            "terms": {{"OnDemand": {{{}, {}}},
+                "Reserved": {{"SKU2": {{
+                    "SKU2.6QCMYABX3D": {{"offerTermCode": "6QCMYABX3D", "sku": "SKU2",
+                        "effectiveDate": "2024-03-01T00:00:00Z",
+                        "termAttributes": {{"LeaseContractLength": "1yr",
+                            "OfferingClass": "standard", "PurchaseOption": "All Upfront"}},
+                        "priceDimensions": {{
+                            "SKU2.6QCMYABX3D.2TG2D8R56U": {{"rateCode": "SKU2.6QCMYABX3D.2TG2D8R56U",
+                                "description": "Upfront Fee", "unit": "Quantity",
+                                "pricePerUnit": {{"USD": "876"}}}},
+                            "SKU2.6QCMYABX3D.6YS6EN2CT7": {{"rateCode": "SKU2.6QCMYABX3D.6YS6EN2CT7",
+                                "description": "", "unit": "Hrs",
+                                "pricePerUnit": {{"USD": "0.0000000000"}}}}}}}},
+                    "SKU2.BPH4J8HBKS": {{"offerTermCode": "BPH4J8HBKS", "sku": "SKU2",
+                        "effectiveDate": "2024-03-01T00:00:00Z",
+                        "termAttributes": {{"LeaseContractLength": "1yr",
+                            "OfferingClass": "standard", "PurchaseOption": "No Upfront"}},
+                        "priceDimensions": {{
+                            "SKU2.BPH4J8HBKS.6YS6EN2CT7": {{"rateCode": "SKU2.BPH4J8HBKS.6YS6EN2CT7",
+                                "description": "", "unit": "Hrs",
+                                "pricePerUnit": {{"USD": "0.1200000000"}}}}}}}}}}}}}}}}"#,
+            product("SKU1", "m5.large", "2", "8 GiB"),
+            product("SKU2", "m5.xlarge", "4", "16 GiB"),
+            on_demand("SKU1", "0.0960000000"),
+            on_demand("SKU2", "0.1920000000"),
+        ))
+        .unwrap();
+
+        let mut workload = WorkloadSpec::new(
+            InstanceRequirement::Size {
+                vcpus: 3,
+                memory_gib: Decimal::from(8),
+            },
+            "us-east-1".to_string(),
+        );
+        workload.instances = Decimal::from(2);
+        let estimate = estimate(&workload, &response, &[]).unwrap();
+        assert_eq!(estimate.instance_type, "m5.xlarge");
+        assert_eq!(estimate.costs.len(), 3);
+        assert_eq!(estimate.costs[0].purchase_model, PurchaseModel::OnDemand);
+        assert_eq!(estimate.costs[0].monthly_usd, Decimal::new(28032, 2));
+        // 876 upfront over 8760 hours is 0.10 per hour
+        assert_eq!(
+            estimate.costs[1].purchase_option,
+            Some(PurchaseOption::AllUpfront)
+        );
+        assert_eq!(estimate.costs[1].upfront_usd, Decimal::from(1752));
+        assert_eq!(estimate.costs[1].monthly_usd, Decimal::from(146));
+        assert_eq!(estimate.costs[1].savings_percent, Decimal::new(4792, 2));
+
+        // Commitments are paid for every hour, on-demand only for the hours used
+        workload.hours_per_month = Decimal::from(100);
+        workload.commitment = Some(Commitment {
+            term: ContractLength::OneYear,
+            payment_option: PurchaseOption::NoUpfront,
+        });
+        let estimate = super::estimate(&workload, &response, &[]).unwrap();
+        assert_eq!(estimate.costs.len(), 2);
+        assert_eq!(estimate.costs[0].monthly_usd, Decimal::new(384, 1));
+        assert!(estimate.costs[1].savings_percent.is_sign_negative());
+
+        workload.instance = InstanceRequirement::InstanceType("c5.large".to_string());
+        assert!(super::estimate(&workload, &response, &[]).is_err());
+    }
+}
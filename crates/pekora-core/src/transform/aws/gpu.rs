@@ -0,0 +1,119 @@
+use crate::model::aws::types::{ContractLength, PurchaseOption};
+use crate::transform::aws::instance_specs::EnrichedPriceRow;
+use crate::transform::aws::normalize::PurchaseModel;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Price of an accelerated instance type per GPU and per GiB of GPU memory.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GpuPriceRow {
+    pub region: Option<String>,
+    pub instance_type: String,
+    pub gpu_model: Option<String>,
+    pub gpus: i32,
+    pub gpu_memory_gib: Option<Decimal>,
+    pub purchase_model: PurchaseModel,
+    pub term: Option<ContractLength>,
+    pub purchase_option: Option<PurchaseOption>,
+    pub usd_per_hour: Decimal,
+    pub usd_per_gpu_hour: Decimal,
+    pub usd_per_gpu_memory_gib_hour: Option<Decimal>,
+}
+
+/// GPU prices of the rows of `rows` with GPUs, from the cheapest per GPU hour.
+pub fn gpu_prices(rows: &[EnrichedPriceRow]) -> Vec<GpuPriceRow> {
+    let mut gpu_rows: Vec<GpuPriceRow> = rows
+        .iter()
+        .filter_map(|row| {
+            let gpus = row.spec.gpus.filter(|gpus| *gpus > 0)?;
+            let usd_per_hour = row.price.effective_usd_per_hour;
+            Some(GpuPriceRow {
+                region: row.price.region.clone(),
+                instance_type: row.spec.instance_type.clone(),
+                gpu_model: row.spec.gpu_model.clone(),
+                gpus,
+                gpu_memory_gib: row.spec.gpu_memory_gib,
+                purchase_model: row.price.purchase_model,
+                term: row.price.term.clone(),
+                purchase_option: row.price.purchase_option.clone(),
+                usd_per_hour,
+                usd_per_gpu_hour: usd_per_hour / Decimal::from(gpus),
+                usd_per_gpu_memory_gib_hour: row
+                    .spec
+                    .gpu_memory_gib
+                    .filter(|memory| !memory.is_zero())
+                    .map(|memory| usd_per_hour / memory),
+            })
+        })
+        .collect();
+    gpu_rows.sort_by(|a, b| {
+        (a.usd_per_gpu_hour, &a.region, &a.instance_type).cmp(&(
+            b.usd_per_gpu_hour,
+            &b.region,
+            &b.instance_type,
+        ))
+    });
+    gpu_rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::gpu_prices;
+    use crate::transform::aws::instance_specs::{join, InstanceSpec};
+    use crate::transform::aws::normalize::{NormalizedPriceRow, PurchaseModel};
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_gpu_prices() {
+        let spec = |instance_type: &str, gpus: Option<i32>, gpu_memory_gib: i64| InstanceSpec {
+            instance_type: instance_type.to_string(),
+            architecture: None,
+            vcpus: None,
+            memory_gib: None,
+            network_performance: None,
+            gpus,
+            gpu_memory_gib: gpus.map(|_| Decimal::from(gpu_memory_gib)),
+            gpu_model: gpus.map(|_| "NVIDIA A10G".to_string()),
+            launch_date: None,
+        };
+        let specs: HashMap<String, InstanceSpec> = [
+            spec("g5.xlarge", Some(1), 24),
+            spec("g5.12xlarge", Some(4), 96),
+            spec("m5.large", None, 0),
+        ]
+        .into_iter()
+        .map(|spec| (spec.instance_type.clone(), spec))
+        .collect();
+        let row = |instance_type: &str, price: i64| NormalizedPriceRow {
+            region: Some("us-east-1".to_string()),
+            service_code: Some("AmazonEC2".to_string()),
+            sku: instance_type.to_string(),
+            instance_type: Some(instance_type.to_string()),
+            platform: Some("Linux".to_string()),
+            component: None,
+            purchase_model: PurchaseModel::OnDemand,
+            term: None,
+            purchase_option: None,
+            offering_class: None,
+            effective_usd_per_hour: Decimal::new(price, 3),
+        };
+        let rows = join(
+            &[
+                row("g5.xlarge", 1006),
+                row("g5.12xlarge", 5672),
+                row("m5.large", 96),
+            ],
+            &specs,
+        );
+
+        let gpu_rows = gpu_prices(&rows);
+        assert_eq!(gpu_rows.len(), 2);
+        assert_eq!(gpu_rows[0].instance_type, "g5.xlarge");
+        assert_eq!(gpu_rows[1].usd_per_gpu_hour, Decimal::new(1418, 3));
+        assert_eq!(
+            gpu_rows[1].usd_per_gpu_memory_gib_hour,
+            Some(Decimal::new(5672, 3) / Decimal::from(96))
+        );
+    }
+}
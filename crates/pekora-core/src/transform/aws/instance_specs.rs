@@ -0,0 +1,118 @@
+use crate::transform::aws::normalize::NormalizedPriceRow;
+use chrono::NaiveDate;
+use log::debug;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Hardware of an instance type, as reported by DescribeInstanceTypes.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct InstanceSpec {
+    pub instance_type: String,
+    /// Processor architecture, e.g. `arm64` or `x86_64`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub architecture: Option<String>,
+    pub vcpus: Option<i32>,
+    pub memory_gib: Option<Decimal>,
+    /// e.g. `Up to 10 Gigabit`
+    pub network_performance: Option<String>,
+    pub gpus: Option<i32>,
+    pub gpu_memory_gib: Option<Decimal>,
+    /// Manufacturer and name of the GPUs, e.g. `NVIDIA A10G`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_model: Option<String>,
+    /// Launch date of the instance type or its family, from `launch_dates::LaunchDates`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub launch_date: Option<NaiveDate>,
+}
+
+/// A price row with the specs of its instance type and unit costs derived from them.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct EnrichedPriceRow {
+    #[serde(flatten)]
+    pub price: NormalizedPriceRow,
+    pub spec: InstanceSpec,
+    pub usd_per_vcpu_hour: Option<Decimal>,
+    pub usd_per_gib_hour: Option<Decimal>,
+    pub usd_per_gpu_hour: Option<Decimal>,
+}
+
+fn per_unit(price: Decimal, units: Option<Decimal>) -> Option<Decimal> {
+    units
+        .filter(|units| !units.is_zero())
+        .map(|units| price / units)
+}
+
+/// Joins `rows` with `specs` by instance type. Rows without an instance type or a known spec,
+/// e.g. of RDS instance classes, are left out.
+pub fn join(
+    rows: &[NormalizedPriceRow],
+    specs: &HashMap<String, InstanceSpec>,
+) -> Vec<EnrichedPriceRow> {
+    let mut joined = Vec::new();
+    for row in rows {
+        let spec = match row
+            .instance_type
+            .as_ref()
+            .and_then(|instance_type| specs.get(instance_type))
+        {
+            Some(spec) => spec,
+            None => {
+                debug!("No instance spec for {:?}, skipping", row.instance_type);
+                continue;
+            }
+        };
+        let price = row.effective_usd_per_hour;
+        joined.push(EnrichedPriceRow {
+            price: row.clone(),
+            spec: spec.clone(),
+            usd_per_vcpu_hour: per_unit(price, spec.vcpus.map(Decimal::from)),
+            usd_per_gib_hour: per_unit(price, spec.memory_gib),
+            usd_per_gpu_hour: per_unit(price, spec.gpus.map(Decimal::from)),
+        });
+    }
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{join, InstanceSpec};
+    use crate::transform::aws::normalize::{NormalizedPriceRow, PurchaseModel};
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_join() {
+        let spec = InstanceSpec {
+            instance_type: "m5.large".to_string(),
+            architecture: Some("x86_64".to_string()),
+            vcpus: Some(2),
+            memory_gib: Some(Decimal::from(8)),
+            network_performance: Some("Up to 10 Gigabit".to_string()),
+            gpus: None,
+            gpu_memory_gib: None,
+            gpu_model: None,
+            launch_date: None,
+        };
+        let specs = HashMap::from([("m5.large".to_string(), spec)]);
+
+        let row = |instance_type: &str| NormalizedPriceRow {
+            region: Some("us-east-1".to_string()),
+            service_code: Some("AmazonEC2".to_string()),
+            sku: "SKU1".to_string(),
+            instance_type: Some(instance_type.to_string()),
+            platform: Some("Linux".to_string()),
+            component: None,
+            purchase_model: PurchaseModel::OnDemand,
+            term: None,
+            purchase_option: None,
+            offering_class: None,
+            effective_usd_per_hour: Decimal::new(96, 3),
+        };
+        let joined = join(&[row("m5.large"), row("db.r6g.large")], &specs);
+        assert_eq!(joined.len(), 1);
+        assert_eq!(joined[0].usd_per_vcpu_hour, Some(Decimal::new(48, 3)));
+        assert_eq!(joined[0].usd_per_gib_hour, Some(Decimal::new(12, 3)));
+        assert_eq!(joined[0].usd_per_gpu_hour, None);
+    }
+}
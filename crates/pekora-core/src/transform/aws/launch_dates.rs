@@ -0,0 +1,153 @@
+use crate::transform::aws::instance_specs::{EnrichedPriceRow, InstanceSpec};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Read;
+
+/// Launch dates bundled with pekora, by family such as `m5` or instance type such as
+/// `m5.metal`. Refreshed by hand from the AWS launch announcements.
+const BUNDLED: &str = include_str!("../../../data/instance_launch_dates.csv");
+
+#[derive(Debug, Deserialize)]
+struct LaunchDateRow {
+    instance: String,
+    launch_date: NaiveDate,
+}
+
+/// When instance families, or instance types launched later than their family, became available.
+#[derive(Debug, Clone, Default)]
+pub struct LaunchDates {
+    dates: HashMap<String, NaiveDate>,
+}
+
+impl LaunchDates {
+    /// The table bundled with pekora.
+    pub fn bundled() -> Self {
+        Self::from_csv(BUNDLED.as_bytes()).expect("bundled launch dates are valid")
+    }
+
+    /// Reads a CSV with columns `instance` and `launch_date`, e.g. `m5,2017-11-28`.
+    pub fn from_csv(reader: impl Read) -> anyhow::Result<Self> {
+        let mut dates = HashMap::new();
+        for row in csv::Reader::from_reader(reader).deserialize() {
+            let row: LaunchDateRow = row?;
+            dates.insert(row.instance, row.launch_date);
+        }
+        Ok(Self { dates })
+    }
+
+    /// Adds the dates of `other`, replacing ours for the same family or instance type.
+    pub fn extend(&mut self, other: LaunchDates) {
+        self.dates.extend(other.dates);
+    }
+
+    /// Launch date of `instance_type`, or of its family if the type has none.
+    pub fn get(&self, instance_type: &str) -> Option<NaiveDate> {
+        self.dates.get(instance_type).copied().or_else(|| {
+            let (family, _) = instance_type.split_once('.')?;
+            self.dates.get(family).copied()
+        })
+    }
+
+    /// Sets the launch date of every spec in `specs` known to us.
+    pub fn apply(&self, specs: &mut HashMap<String, InstanceSpec>) {
+        for spec in specs.values_mut() {
+            spec.launch_date = self.get(&spec.instance_type);
+        }
+    }
+}
+
+/// Rows of the most recently launched instance types costing at most `max_usd_per_hour`,
+/// e.g. to find the newest generation available in a region under a budget. Rows without a
+/// launch date are left out.
+pub fn newest_under(
+    rows: &[EnrichedPriceRow],
+    max_usd_per_hour: Decimal,
+) -> Vec<&EnrichedPriceRow> {
+    let affordable = rows
+        .iter()
+        .filter(|row| row.price.effective_usd_per_hour <= max_usd_per_hour);
+    let newest = match affordable
+        .clone()
+        .filter_map(|row| row.spec.launch_date)
+        .max()
+    {
+        Some(newest) => newest,
+        None => return vec![],
+    };
+    let mut newest: Vec<&EnrichedPriceRow> = affordable
+        .filter(|row| row.spec.launch_date == Some(newest))
+        .collect();
+    newest.sort_by_key(|row| row.price.effective_usd_per_hour);
+    newest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{newest_under, LaunchDates};
+    use crate::transform::aws::instance_specs::{join, InstanceSpec};
+    use crate::transform::aws::normalize::{NormalizedPriceRow, PurchaseModel};
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_newest_under() {
+        let mut dates = LaunchDates::bundled();
+        assert_eq!(dates.get("m5.large"), NaiveDate::from_ymd_opt(2017, 11, 28));
+        dates.extend(
+            LaunchDates::from_csv("instance,launch_date\nm5.metal,2019-02-13\n".as_bytes())
+                .unwrap(),
+        );
+        assert_eq!(dates.get("m5.metal"), NaiveDate::from_ymd_opt(2019, 2, 13));
+        assert_eq!(dates.get("zz1.large"), None);
+
+        let mut specs: HashMap<String, InstanceSpec> = ["m5.large", "m7i.large", "m7i.xlarge"]
+            .into_iter()
+            .map(|instance_type| {
+                let spec = InstanceSpec {
+                    instance_type: instance_type.to_string(),
+                    architecture: None,
+                    vcpus: None,
+                    memory_gib: None,
+                    network_performance: None,
+                    gpus: None,
+                    gpu_memory_gib: None,
+                    gpu_model: None,
+                    launch_date: None,
+                };
+                (instance_type.to_string(), spec)
+            })
+            .collect();
+        dates.apply(&mut specs);
+        let row = |instance_type: &str, price: i64| NormalizedPriceRow {
+            region: Some("us-east-1".to_string()),
+            service_code: Some("AmazonEC2".to_string()),
+            sku: instance_type.to_string(),
+            instance_type: Some(instance_type.to_string()),
+            platform: Some("Linux".to_string()),
+            component: None,
+            purchase_model: PurchaseModel::OnDemand,
+            term: None,
+            purchase_option: None,
+            offering_class: None,
+            effective_usd_per_hour: Decimal::new(price, 3),
+        };
+        let rows = join(
+            &[
+                row("m5.large", 96),
+                row("m7i.large", 101),
+                row("m7i.xlarge", 202),
+            ],
+            &specs,
+        );
+
+        let newest = newest_under(&rows, Decimal::new(15, 2));
+        assert_eq!(newest.len(), 1);
+        assert_eq!(newest[0].spec.instance_type, "m7i.large");
+        let newest = newest_under(&rows, Decimal::new(1, 1));
+        assert_eq!(newest[0].spec.instance_type, "m5.large");
+        assert!(newest_under(&rows, Decimal::new(1, 2)).is_empty());
+    }
+}
@@ -0,0 +1,234 @@
+use crate::model::aws::price_bulk_types::PricingListResponse;
+use crate::model::aws::types::LocationType;
+use std::collections::{HashMap, HashSet};
+
+/// Location types to keep when pivoting.
+///
+/// The default only keeps AWS Regions, so Local Zone, Wavelength and Outposts SKUs don't mix
+/// into regional results unless explicitly requested.
+#[derive(Debug, Clone)]
+pub struct LocationFilter {
+    location_types: HashSet<LocationType>,
+}
+
+impl LocationFilter {
+    pub fn new(location_types: impl IntoIterator<Item = LocationType>) -> Self {
+        Self {
+            location_types: location_types.into_iter().collect(),
+        }
+    }
+
+    pub fn all() -> Self {
+        Self::new([
+            LocationType::Region,
+            LocationType::LocalZone,
+            LocationType::WavelengthZone,
+            LocationType::Outposts,
+        ])
+    }
+
+    pub fn matches(&self, location_type: &LocationType) -> bool {
+        self.location_types.contains(location_type)
+    }
+}
+
+impl Default for LocationFilter {
+    fn default() -> Self {
+        Self::new([LocationType::Region])
+    }
+}
+
+/// Regions by code and the `location` name price lists use for them. SSM public parameters call
+/// EU regions `Europe (...)`, which resolve as well. `pekora fetch region-names` lists the codes
+/// and names of this table with regions announced since added, to update it from.
+const BUNDLED_REGIONS: &[(&str, &str)] = &[
+    ("af-south-1", "Africa (Cape Town)"),
+    ("ap-east-1", "Asia Pacific (Hong Kong)"),
+    ("ap-northeast-1", "Asia Pacific (Tokyo)"),
+    ("ap-northeast-2", "Asia Pacific (Seoul)"),
+    ("ap-northeast-3", "Asia Pacific (Osaka)"),
+    ("ap-south-1", "Asia Pacific (Mumbai)"),
+    ("ap-south-2", "Asia Pacific (Hyderabad)"),
+    ("ap-southeast-1", "Asia Pacific (Singapore)"),
+    ("ap-southeast-2", "Asia Pacific (Sydney)"),
+    ("ap-southeast-3", "Asia Pacific (Jakarta)"),
+    ("ap-southeast-4", "Asia Pacific (Melbourne)"),
+    ("ca-central-1", "Canada (Central)"),
+    ("ca-west-1", "Canada West (Calgary)"),
+    ("cn-north-1", "China (Beijing)"),
+    ("cn-northwest-1", "China (Ningxia)"),
+    ("eu-central-1", "EU (Frankfurt)"),
+    ("eu-central-2", "EU (Zurich)"),
+    ("eu-north-1", "EU (Stockholm)"),
+    ("eu-south-1", "EU (Milan)"),
+    ("eu-south-2", "EU (Spain)"),
+    ("eu-west-1", "EU (Ireland)"),
+    ("eu-west-2", "EU (London)"),
+    ("eu-west-3", "EU (Paris)"),
+    ("il-central-1", "Israel (Tel Aviv)"),
+    ("me-central-1", "Middle East (UAE)"),
+    ("me-south-1", "Middle East (Bahrain)"),
+    ("sa-east-1", "South America (Sao Paulo)"),
+    ("us-east-1", "US East (N. Virginia)"),
+    ("us-east-2", "US East (Ohio)"),
+    ("us-gov-east-1", "AWS GovCloud (US-East)"),
+    ("us-gov-west-1", "AWS GovCloud (US-West)"),
+    ("us-west-1", "US West (N. California)"),
+    ("us-west-2", "US West (Oregon)"),
+];
+
+/// Bidirectional mapping between region codes such as `ap-northeast-1` and location names such
+/// as `Asia Pacific (Tokyo)`.
+#[derive(Debug, Clone, Default)]
+pub struct RegionNames {
+    names: HashMap<String, String>,
+    codes: HashMap<String, String>,
+}
+
+impl RegionNames {
+    /// The regions bundled with pekora.
+    pub fn bundled() -> Self {
+        let mut region_names = Self::default();
+        for (code, name) in BUNDLED_REGIONS {
+            region_names.insert(code, name);
+            if let Some(city) = name.strip_prefix("EU ") {
+                region_names
+                    .codes
+                    .insert(format!("Europe {}", city), code.to_string());
+            }
+        }
+        region_names
+    }
+
+    /// Sets the name of `code`. Names it had before keep resolving to it.
+    pub fn insert(&mut self, code: &str, name: &str) {
+        self.names.insert(code.to_string(), name.to_string());
+        self.codes.insert(name.to_string(), code.to_string());
+    }
+
+    /// Adds the `location` and `regionCode` pairs of regional products in `response`, e.g. of
+    /// the AmazonEC2 offer of a newly launched region, returning how many codes were new.
+    pub fn refresh(&mut self, response: &PricingListResponse) -> usize {
+        let mut added = 0;
+        for product in response.products.values() {
+            let attributes = &product.attributes;
+            if attributes.get("locationType").map(String::as_str) != Some("AWS Region") {
+                continue;
+            }
+            if let Some((code, name)) = attributes.get("regionCode").zip(attributes.get("location"))
+            {
+                if !self.names.contains_key(code) {
+                    added += 1;
+                }
+                self.insert(code, name);
+            }
+        }
+        added
+    }
+
+    /// Adds the long names of SSM public parameters, e.g. of `SsmClient::region_long_names`,
+    /// returning how many codes were new. Known regions keep their price list name, and new
+    /// ones are named like price lists name them, `EU (...)` rather than `Europe (...)`.
+    pub fn refresh_long_names<'a>(
+        &mut self,
+        long_names: impl IntoIterator<Item = (&'a String, &'a String)>,
+    ) -> usize {
+        let mut added = 0;
+        for (code, long_name) in long_names {
+            if !self.names.contains_key(code) {
+                let name = match long_name.strip_prefix("Europe ") {
+                    Some(city) => format!("EU {}", city),
+                    None => long_name.clone(),
+                };
+                self.insert(code, &name);
+                added += 1;
+            }
+            self.codes.insert(long_name.clone(), code.clone());
+        }
+        added
+    }
+
+    /// Region codes and their names, ordered by code.
+    pub fn entries(&self) -> Vec<(&str, &str)> {
+        let mut entries = self
+            .names
+            .iter()
+            .map(|(code, name)| (code.as_str(), name.as_str()))
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries
+    }
+
+    pub fn name(&self, code: &str) -> Option<&str> {
+        self.names.get(code).map(String::as_str)
+    }
+
+    pub fn code(&self, name: &str) -> Option<&str> {
+        self.codes.get(name).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RegionNames, BUNDLED_REGIONS};
+    use crate::model::aws::price_bulk_types::PricingListResponse;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_region_names() {
+        let mut region_names = RegionNames::bundled();
+        assert_eq!(
+            region_names.code("Asia Pacific (Tokyo)"),
+            Some("ap-northeast-1")
+        );
+        assert_eq!(
+            region_names.code("Europe (Frankfurt)"),
+            Some("eu-central-1")
+        );
+        assert_eq!(region_names.code("Atlantis"), None);
+        assert_eq!(
+            region_names.name("us-east-1"),
+            Some("US East (N. Virginia)")
+        );
+        let response: PricingListResponse = serde_json::from_str(
+            r#"{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724",
+            "products": {
+                "SKU1": {"sku": "SKU1", "productFamily": "Compute Instance",
+                    "attributes": {"location": "Mexico (Central)", "locationType": "AWS Region",
+                        "regionCode": "mx-central-1"}},
+                "SKU2": {"sku": "SKU2", "productFamily": "Compute Instance",
+                    "attributes": {"location": "US East (Boston)",
+                        "locationType": "AWS Local Zone", "regionCode": "us-east-1-bos-1"}}},
+            "terms": {"OnDemand": {}, "Reserved": {}}}"#,
+        )
+        .unwrap();
+        assert_eq!(region_names.refresh(&response), 1);
+        assert_eq!(region_names.code("Mexico (Central)"), Some("mx-central-1"));
+        assert_eq!(region_names.code("US East (Boston)"), None);
+    }
+
+    #[test]
+    fn test_refresh_long_names() {
+        let mut region_names = RegionNames::bundled();
+        let long_names = BTreeMap::from([
+            ("eu-west-1".to_string(), "Europe (Ireland)".to_string()),
+            ("eu-west-4".to_string(), "Europe (Atlantis)".to_string()),
+            ("ap-east-2".to_string(), "Asia Pacific (Taipei)".to_string()),
+        ]);
+        assert_eq!(region_names.refresh_long_names(&long_names), 2);
+        assert_eq!(region_names.name("eu-west-1"), Some("EU (Ireland)"));
+        assert_eq!(region_names.name("eu-west-4"), Some("EU (Atlantis)"));
+        assert_eq!(region_names.code("Europe (Atlantis)"), Some("eu-west-4"));
+        assert_eq!(
+            region_names.code("Asia Pacific (Taipei)"),
+            Some("ap-east-2")
+        );
+
+        // The regenerated table covers the bundled one, in its order
+        let entries = region_names.entries();
+        assert_eq!(entries.len(), BUNDLED_REGIONS.len() + 2);
+        assert!(BUNDLED_REGIONS.iter().all(|entry| entries.contains(entry)));
+        assert!(entries.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
@@ -0,0 +1,34 @@
+pub mod architecture;
+pub mod availability;
+pub mod break_even;
+pub mod cloudwatch;
+pub mod cost_explorer;
+pub mod cur;
+pub mod data_transfer;
+pub mod dedicated_host;
+pub mod diff;
+pub mod ebs;
+pub mod effective_rate;
+pub mod elasticache;
+pub mod estimate;
+pub mod gpu;
+pub mod instance_specs;
+pub mod launch_dates;
+pub mod location;
+pub mod network;
+pub mod node_pricing;
+pub mod normalize;
+pub mod on_demand;
+pub mod optimize;
+pub mod orderable;
+pub mod overlay;
+pub mod rds_reserved;
+pub mod rds_storage;
+pub mod recommendation;
+pub mod rightsizing;
+pub mod savings_plan;
+pub mod savings_plan_rates;
+pub mod serverless;
+pub mod simulate;
+pub mod spot;
+pub mod tiered;
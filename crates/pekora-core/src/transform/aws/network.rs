@@ -0,0 +1,294 @@
+use crate::metrics;
+use crate::model::aws::price_bulk_types::NetworkPricingListResponse;
+use crate::model::aws::types::NetworkProductAttributes;
+use crate::transform::aws::tiered::TieredPrice;
+use anyhow::{anyhow, bail};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Network infrastructure charged by the hour and by the data it processes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkResource {
+    NatGateway,
+    ApplicationLoadBalancer,
+    NetworkLoadBalancer,
+    GatewayLoadBalancer,
+    ClassicLoadBalancer,
+    /// Interface VPC endpoint, per availability zone
+    VpcEndpoint,
+}
+
+impl NetworkResource {
+    /// `nat`, `alb`, `nlb`, `gwlb`, `clb` or `endpoint`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "nat" => Some(Self::NatGateway),
+            "alb" => Some(Self::ApplicationLoadBalancer),
+            "nlb" => Some(Self::NetworkLoadBalancer),
+            "gwlb" => Some(Self::GatewayLoadBalancer),
+            "clb" => Some(Self::ClassicLoadBalancer),
+            "endpoint" => Some(Self::VpcEndpoint),
+            _ => None,
+        }
+    }
+
+    /// Service code of the pricing list the resource is priced in.
+    pub fn service_code(&self) -> &'static str {
+        match self {
+            Self::NatGateway => "AmazonEC2",
+            Self::VpcEndpoint => "AmazonVPC",
+            _ => "AWSELB",
+        }
+    }
+}
+
+/// What a network price is charged per.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NetworkMeter {
+    /// Each hour a resource exists
+    Hourly,
+    /// Each GB processed, or each LCU-hour for load balancers other than classic ones
+    Processing,
+}
+
+#[derive(Debug, Clone)]
+pub struct NetworkRate {
+    pub resource: NetworkResource,
+    pub meter: NetworkMeter,
+    pub price: TieredPrice,
+}
+
+/// Resource and meter of a product, `None` for anything else in the pricing list.
+fn classify(attributes: &NetworkProductAttributes) -> Option<(NetworkResource, NetworkMeter)> {
+    let usage_type = attributes.usage_type.as_deref()?;
+    if usage_type.ends_with("NatGateway-Hours") {
+        return Some((NetworkResource::NatGateway, NetworkMeter::Hourly));
+    }
+    if usage_type.ends_with("NatGateway-Bytes") {
+        return Some((NetworkResource::NatGateway, NetworkMeter::Processing));
+    }
+    if usage_type.ends_with("VpcEndpoint-Hours") {
+        return Some((NetworkResource::VpcEndpoint, NetworkMeter::Hourly));
+    }
+    if usage_type.ends_with("VpcEndpoint-Bytes") {
+        return Some((NetworkResource::VpcEndpoint, NetworkMeter::Processing));
+    }
+    let resource = match attributes.operation.as_deref()? {
+        "LoadBalancing:Application" => NetworkResource::ApplicationLoadBalancer,
+        "LoadBalancing:Network" => NetworkResource::NetworkLoadBalancer,
+        "LoadBalancing:Gateway" => NetworkResource::GatewayLoadBalancer,
+        "LoadBalancing" => NetworkResource::ClassicLoadBalancer,
+        _ => return None,
+    };
+    let meter = if usage_type.ends_with("LoadBalancerUsage") {
+        NetworkMeter::Hourly
+    } else if usage_type.ends_with("LCUUsage") || usage_type.ends_with("DataProcessing-Bytes") {
+        NetworkMeter::Processing
+    } else {
+        return None;
+    };
+    Some((resource, meter))
+}
+
+/// Hourly and processing rates of the network resources in a pricing list. Products of local
+/// zones and outposts are skipped.
+pub fn pivot(response: &NetworkPricingListResponse) -> Vec<NetworkRate> {
+    let mut pivoted = Vec::new();
+    for (sku, offerings) in &response.terms.on_demand {
+        let attributes = match response.products.get(sku) {
+            Some(product) => &product.attributes,
+            None => continue,
+        };
+        if attributes
+            .location_type
+            .as_deref()
+            .is_some_and(|location_type| location_type != "AWS Region")
+        {
+            continue;
+        }
+        let (resource, meter) = match classify(attributes) {
+            Some(classified) => classified,
+            None => continue,
+        };
+        for offering in offerings.values() {
+            if let Some(price) = TieredPrice::from_dimensions(
+                sku,
+                &offering.offer_term_code,
+                offering.price_dimensions.values(),
+            ) {
+                pivoted.push(NetworkRate {
+                    resource,
+                    meter,
+                    price,
+                });
+            }
+        }
+    }
+    metrics::global().record_rows_pivoted(pivoted.len() as u64);
+    pivoted
+}
+
+/// Resources of one kind and the data they process in a month.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkComponent {
+    pub resource: NetworkResource,
+    pub count: Decimal,
+    /// GB processed by all of them together. Load balancers other than classic ones are
+    /// approximated as one LCU-hour per GB, their processed bytes dimension.
+    pub processed_gb: Decimal,
+}
+
+impl FromStr for NetworkComponent {
+    type Err = anyhow::Error;
+
+    /// Parses `resource[:count[:processed_gb]]`, e.g. `nat:2:500`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let resource = parts.next().unwrap_or_default();
+        let resource = NetworkResource::parse(resource)
+            .ok_or_else(|| anyhow!("Unknown network resource {:?}", resource))?;
+        let count = parts.next().map(Decimal::from_str).transpose()?;
+        let processed_gb = parts.next().map(Decimal::from_str).transpose()?;
+        if parts.next().is_some() {
+            bail!("Expected resource[:count[:processed_gb]], got {:?}", s);
+        }
+        Ok(Self {
+            resource,
+            count: count.unwrap_or(Decimal::ONE),
+            processed_gb: processed_gb.unwrap_or_default(),
+        })
+    }
+}
+
+/// Monthly cost of a network component.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct NetworkCostLine {
+    pub resource: NetworkResource,
+    pub count: Decimal,
+    pub processed_gb: Decimal,
+    pub hourly_usd: Decimal,
+    pub processing_usd: Decimal,
+    pub monthly_usd: Decimal,
+}
+
+/// Prices `component` running `hours_per_month` with the rates of one region. Fails if the
+/// resource has no hourly price, or processes data without a processing price.
+pub fn price_component(
+    rates: &[NetworkRate],
+    component: &NetworkComponent,
+    hours_per_month: Decimal,
+) -> anyhow::Result<NetworkCostLine> {
+    let rate = |meter: NetworkMeter| {
+        rates
+            .iter()
+            .find(|rate| rate.resource == component.resource && rate.meter == meter)
+            .ok_or_else(|| {
+                anyhow!(
+                    "{:?} has no {:?} price in this region",
+                    component.resource,
+                    meter
+                )
+            })
+    };
+    let hourly_usd = rate(NetworkMeter::Hourly)?
+        .price
+        .cost(component.count * hours_per_month);
+    let processing_usd = if component.processed_gb > Decimal::ZERO {
+        rate(NetworkMeter::Processing)?
+            .price
+            .cost(component.processed_gb)
+    } else {
+        Decimal::ZERO
+    };
+    Ok(NetworkCostLine {
+        resource: component.resource,
+        count: component.count,
+        processed_gb: component.processed_gb,
+        hourly_usd: hourly_usd.round_dp(2),
+        processing_usd: processing_usd.round_dp(2),
+        monthly_usd: (hourly_usd + processing_usd).round_dp(2),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pivot, price_component, NetworkComponent, NetworkResource};
+    use crate::model::aws::price_bulk_types::{NetworkPricingListResponse, PricingListResponse};
+    use rust_decimal::Decimal;
+
+    fn product(sku: &str, usage_type: &str, operation: &str, location_type: &str) -> String {
+        format!(
+            r#""{sku}": {{"sku": "{sku}", "productFamily": "", "attributes": {{
+                "usagetype": "USE1-{usage_type}",
+                "operation": "{operation}", "locationType": "{location_type}",
+                "regionCode": "us-east-1"}}}}"#
+        )
+    }
+
+    fn offering(sku: &str, unit: &str, price: &str) -> String {
+        format!(
+            r#""{sku}": {{"{sku}.JRTCKXETXF": {{"offerTermCode": "JRTCKXETXF", "sku": "{sku}",
+                "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {{}},
+                "priceDimensions": {{"{sku}.JRTCKXETXF.6YS6EN2CT7": {{
+                    "rateCode": "{sku}.JRTCKXETXF.6YS6EN2CT7", "description": "",
+                    "unit": "{unit}", "beginRange": "0", "endRange": "Inf",
+                    "pricePerUnit": {{"USD": "{price}"}}}}}}}}}}"#
+        )
+    }
+
+    #[test]
+    fn test_price_component() {
+        let json = format!(
+            r#"{{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724",
+            "products": {{{}, {}, {}, {}, {}}},
+            "terms": {{"OnDemand": {{{}, {}, {}, {}, {}}}, "Reserved": {{}}}}}}"#,
+            product("NATH", "NatGateway-Hours", "NatGateway", "AWS Region"),
+            product("NATB", "NatGateway-Bytes", "NatGateway", "AWS Region"),
+            product(
+                "ALBH",
+                "LoadBalancerUsage",
+                "LoadBalancing:Application",
+                "AWS Region"
+            ),
+            product(
+                "ALBL",
+                "LCUUsage",
+                "LoadBalancing:Application",
+                "AWS Region"
+            ),
+            product(
+                "LZ",
+                "LoadBalancerUsage",
+                "LoadBalancing:Network",
+                "AWS Local Zone"
+            ),
+            offering("NATH", "Hrs", "0.045"),
+            offering("NATB", "GB", "0.045"),
+            offering("ALBH", "Hrs", "0.0252"),
+            offering("ALBL", "LCU-Hrs", "0.008"),
+            offering("LZ", "Hrs", "0.0252"),
+        );
+        let response: PricingListResponse = serde_json::from_str(&json).unwrap();
+        let typed: NetworkPricingListResponse = response.with_typed_attributes().unwrap();
+        let rates = pivot(&typed);
+        assert_eq!(rates.len(), 4);
+
+        let nat: NetworkComponent = "nat:2:1000".parse().unwrap();
+        let cost = price_component(&rates, &nat, Decimal::from(730)).unwrap();
+        assert_eq!(cost.hourly_usd, Decimal::new(657, 1));
+        assert_eq!(cost.processing_usd, Decimal::from(45));
+        assert_eq!(cost.monthly_usd, Decimal::new(1107, 1));
+
+        let alb: NetworkComponent = "alb".parse().unwrap();
+        assert_eq!(alb.resource, NetworkResource::ApplicationLoadBalancer);
+        let cost = price_component(&rates, &alb, Decimal::from(730)).unwrap();
+        assert_eq!(cost.monthly_usd, Decimal::new(1840, 2));
+
+        let nlb: NetworkComponent = "nlb:1".parse().unwrap();
+        assert!(price_component(&rates, &nlb, Decimal::from(730)).is_err());
+        assert!("vpn:1".parse::<NetworkComponent>().is_err());
+    }
+}
@@ -0,0 +1,272 @@
+use crate::metrics;
+use crate::model::aws::price_bulk_types::{
+    DocDbPricingListResponse, MemoryDbPricingListResponse, OpenSearchPricingListResponse,
+    RedshiftPricingListResponse, TypedPricingListResponse,
+};
+use crate::model::aws::types::{ContractLength, PurchaseOption};
+use crate::model::aws::unit::{Unit, HOURS_PER_MONTH};
+use crate::transform::aws::effective_rate::reserved_offering;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+/// Hourly price of a node of a managed service such as Redshift or DocumentDB, on-demand and
+/// with the cheapest reservation.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct NodePrice {
+    pub service_code: String,
+    pub sku: String,
+    /// e.g. `ra3.xlplus`, `r6g.large.search` or `db.r6g.large`
+    pub node_type: String,
+    pub vcpu: Option<Decimal>,
+    pub memory_gib: Option<Decimal>,
+    pub storage: Option<String>,
+    pub on_demand_usd_per_hour: Decimal,
+    pub on_demand_usd_per_month: Decimal,
+    pub reserved_usd_per_hour: Option<Decimal>,
+    pub reserved_term: Option<ContractLength>,
+    pub reserved_purchase_option: Option<PurchaseOption>,
+}
+
+/// Node columns of a product, taken from service specific attributes.
+struct NodeSpec<'a> {
+    node_type: &'a str,
+    vcpu: Option<&'a str>,
+    memory_gib: Option<&'a str>,
+    storage: Option<&'a str>,
+}
+
+/// Leading number of an attribute such as `32 GiB`.
+fn leading_number(value: &str) -> Option<Decimal> {
+    value
+        .split_whitespace()
+        .next()?
+        .replace(',', "")
+        .parse()
+        .ok()
+}
+
+fn node_prices<A: Debug + Clone>(
+    response: &TypedPricingListResponse<A>,
+    service_code: &str,
+    node: impl Fn(&A) -> Option<NodeSpec<'_>>,
+) -> Vec<NodePrice> {
+    let mut rows = Vec::new();
+    for (sku, product) in &response.products {
+        let spec = match node(&product.attributes) {
+            Some(spec) => spec,
+            None => continue,
+        };
+        let on_demand_usd_per_hour = match response
+            .terms
+            .on_demand
+            .get(sku)
+            .into_iter()
+            .flat_map(|offerings| offerings.values())
+            .flat_map(|offering| offering.price_dimensions.values())
+            .find(|dimension| dimension.parsed_unit() == Unit::Hours)
+            .and_then(|dimension| dimension.usd())
+        {
+            Some(usd) => usd,
+            None => continue,
+        };
+        let reserved = response
+            .terms
+            .reserved
+            .get(sku)
+            .into_iter()
+            .flat_map(|offerings| offerings.values())
+            .filter_map(|offering| reserved_offering(sku, offering, Some(on_demand_usd_per_hour)))
+            .min_by_key(|rate| rate.effective_usd_per_hour);
+        rows.push(NodePrice {
+            service_code: service_code.to_string(),
+            sku: sku.clone(),
+            node_type: spec.node_type.to_string(),
+            vcpu: spec.vcpu.and_then(leading_number),
+            memory_gib: spec.memory_gib.and_then(leading_number),
+            storage: spec.storage.map(str::to_string),
+            on_demand_usd_per_hour,
+            on_demand_usd_per_month: (on_demand_usd_per_hour * Decimal::from(HOURS_PER_MONTH))
+                .round_dp(2),
+            reserved_usd_per_hour: reserved
+                .as_ref()
+                .map(|rate| rate.effective_usd_per_hour.round_dp(6)),
+            reserved_term: reserved
+                .as_ref()
+                .map(|rate| rate.lease_contract_length.clone()),
+            reserved_purchase_option: reserved.map(|rate| rate.purchase_option),
+        });
+    }
+    rows.sort_by(|a, b| {
+        a.on_demand_usd_per_hour
+            .cmp(&b.on_demand_usd_per_hour)
+            .then_with(|| a.node_type.cmp(&b.node_type))
+    });
+    metrics::global().record_rows_pivoted(rows.len() as u64);
+    rows
+}
+
+/// Node prices of an AmazonRedshift pricing list, from the cheapest.
+pub fn redshift_node_prices(response: &RedshiftPricingListResponse) -> Vec<NodePrice> {
+    node_prices(response, "AmazonRedshift", |attributes| {
+        Some(NodeSpec {
+            node_type: attributes.instance_type.as_deref()?,
+            vcpu: attributes.vcpu.as_deref(),
+            memory_gib: attributes.memory.as_deref(),
+            storage: attributes.storage.as_deref(),
+        })
+    })
+}
+
+/// Node prices of an AmazonES pricing list, from the cheapest.
+pub fn opensearch_node_prices(response: &OpenSearchPricingListResponse) -> Vec<NodePrice> {
+    node_prices(response, "AmazonES", |attributes| {
+        Some(NodeSpec {
+            node_type: attributes.instance_type.as_deref()?,
+            vcpu: attributes.vcpu.as_deref(),
+            memory_gib: attributes.memory_gib.as_deref(),
+            storage: attributes.storage.as_deref(),
+        })
+    })
+}
+
+/// Node prices of an AmazonMemoryDB pricing list, from the cheapest.
+pub fn memorydb_node_prices(response: &MemoryDbPricingListResponse) -> Vec<NodePrice> {
+    node_prices(response, "AmazonMemoryDB", |attributes| {
+        Some(NodeSpec {
+            node_type: attributes.instance_type.as_deref()?,
+            vcpu: attributes.vcpu.as_deref(),
+            memory_gib: attributes.memory.as_deref(),
+            storage: None,
+        })
+    })
+}
+
+/// Instance prices of an AmazonDocDB pricing list, from the cheapest. Storage is the cluster
+/// storage configuration the price applies to, `Standard` or `I/O-Optimized`.
+pub fn docdb_node_prices(response: &DocDbPricingListResponse) -> Vec<NodePrice> {
+    node_prices(response, "AmazonDocDB", |attributes| {
+        Some(NodeSpec {
+            node_type: attributes.instance_type.as_deref()?,
+            vcpu: attributes.vcpu.as_deref(),
+            memory_gib: attributes.memory.as_deref(),
+            storage: Some(attributes.storage_configuration()?),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{docdb_node_prices, opensearch_node_prices, redshift_node_prices};
+    use crate::model::aws::price_bulk_types::PricingListResponse;
+    use crate::model::aws::types::PurchaseOption;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_node_prices() {
+        let response: PricingListResponse = serde_json::from_str(
+            r#"{
+                "formatVersion": "v1.0",
+                "publicationDate": "2024-03-12T15:37:24Z",
+                "version": "20240312153724",
+                "products": {
+                    "RA3": {"productFamily": "Compute Instance", "sku": "RA3",
+                        "attributes": {"instanceType": "ra3.xlplus", "vcpu": "4",
+                            "memory": "32 GiB", "storage": "32TB RMS"}},
+                    "RMS": {"productFamily": "Redshift Managed Storage", "sku": "RMS",
+                        "attributes": {"usagetype": "RMS:GB"}},
+                    "OS": {"productFamily": "Amazon OpenSearch Service Instance", "sku": "OS",
+                        "attributes": {"instanceType": "r6g.large.search", "vcpu": "2",
+                            "memoryGib": "16", "storage": "EBS Only"}}
+                },
+                "terms": {
+                    "OnDemand": {
+                        "RA3": {"RA3.JRTCKXETXF": {"offerTermCode": "JRTCKXETXF", "sku": "RA3",
+                            "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {},
+                            "priceDimensions": {"RA3.JRTCKXETXF.6YS6EN2CT7": {
+                                "rateCode": "RA3.JRTCKXETXF.6YS6EN2CT7", "description": "",
+                                "unit": "Hrs", "pricePerUnit": {"USD": "1.0860000000"}}}}},
+                        "OS": {"OS.JRTCKXETXF": {"offerTermCode": "JRTCKXETXF", "sku": "OS",
+                            "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {},
+                            "priceDimensions": {"OS.JRTCKXETXF.6YS6EN2CT7": {
+                                "rateCode": "OS.JRTCKXETXF.6YS6EN2CT7", "description": "",
+                                "unit": "Hrs", "pricePerUnit": {"USD": "0.1670000000"}}}}}
+                    },
+                    "Reserved": {"RA3": {
+                        "RA3.4NA7Y494T4": {"offerTermCode": "4NA7Y494T4", "sku": "RA3",
+                            "effectiveDate": "2024-03-01T00:00:00Z",
+                            "termAttributes": {"LeaseContractLength": "1yr",
+                                "PurchaseOption": "No Upfront"},
+                            "priceDimensions": {"RA3.4NA7Y494T4.6YS6EN2CT7": {
+                                "rateCode": "RA3.4NA7Y494T4.6YS6EN2CT7", "description": "",
+                                "unit": "Hrs", "pricePerUnit": {"USD": "0.8470000000"}}}}}}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        // One response holds both offers' products, each service's pricing list its own
+        let mut redshift_response = response.clone();
+        redshift_response.products.remove("OS");
+        let redshift = redshift_node_prices(&redshift_response.with_typed_attributes().unwrap());
+        assert_eq!(redshift.len(), 1);
+        assert_eq!(redshift[0].memory_gib, Some(Decimal::from(32)));
+        assert_eq!(redshift[0].on_demand_usd_per_month, Decimal::new(79278, 2));
+        assert_eq!(
+            redshift[0].reserved_usd_per_hour,
+            Some(Decimal::new(847, 3))
+        );
+        assert_eq!(
+            redshift[0].reserved_purchase_option,
+            Some(PurchaseOption::NoUpfront)
+        );
+
+        let mut opensearch_response = response;
+        opensearch_response.products.retain(|sku, _| sku == "OS");
+        let opensearch =
+            opensearch_node_prices(&opensearch_response.with_typed_attributes().unwrap());
+        assert_eq!(opensearch.len(), 1);
+        assert_eq!(opensearch[0].node_type, "r6g.large.search");
+        assert_eq!(opensearch[0].reserved_usd_per_hour, None);
+    }
+
+    #[test]
+    fn test_docdb_node_prices() {
+        let product = |sku: &str, usage_type: &str| {
+            format!(
+                r#""{sku}": {{"productFamily": "Database Instance", "sku": "{sku}",
+                    "attributes": {{"instanceType": "db.r6g.large", "vcpu": "2",
+                        "memory": "16 GiB", "usagetype": "{usage_type}"}}}}"#
+            )
+        };
+        let offering = |sku: &str, usd: &str| {
+            format!(
+                r#""{sku}": {{"{sku}.JRTCKXETXF": {{"offerTermCode": "JRTCKXETXF",
+                    "sku": "{sku}", "effectiveDate": "2024-03-01T00:00:00Z",
+                    "termAttributes": {{}}, "priceDimensions": {{"{sku}.JRTCKXETXF.6YS6EN2CT7": {{
+                        "rateCode": "{sku}.JRTCKXETXF.6YS6EN2CT7", "description": "",
+                        "unit": "Hrs", "pricePerUnit": {{"USD": "{usd}"}}}}}}}}}}"#
+            )
+        };
+        let json = format!(
+            r#"{{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724", "products": {{{}, {}, {}}},
+            "terms": {{"OnDemand": {{{}, {}, {}}}, "Reserved": {{}}}}}}"#,
+            product("STD", "USE1-InstanceUsage:db.r6g.large"),
+            product("IOO", "USE1-InstanceUsageIOOptimized:db.r6g.large"),
+            product("BAK", "USE1-BackupUsage"),
+            offering("STD", "0.2770000000"),
+            offering("IOO", "0.3050000000"),
+            offering("BAK", "0.0210000000"),
+        );
+        let response: PricingListResponse = serde_json::from_str(&json).unwrap();
+
+        let rows = docdb_node_prices(&response.with_typed_attributes().unwrap());
+        let storage = rows
+            .iter()
+            .map(|row| row.storage.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(storage, [Some("Standard"), Some("I/O-Optimized")]);
+        assert_eq!(rows[0].memory_gib, Some(Decimal::from(16)));
+    }
+}
@@ -0,0 +1,300 @@
+use crate::metrics;
+use crate::model::aws::price_bulk_types::PricingListResponse;
+use crate::model::aws::types::{
+    ContractLength, PurchaseOption, RIOfferingClass, SageMakerComponent, SavingsPlanType,
+};
+use crate::model::aws::unit::Unit;
+use crate::transform::aws::effective_rate::{
+    hourly_on_demand_prices, reserved_offering, savings_plan_row, EffectiveRate,
+};
+use crate::transform::aws::location::RegionNames;
+use crate::transform::aws::on_demand::OnDemandRate;
+use crate::transform::aws::savings_plan::PivotedSavingsPlanTermRate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PurchaseModel {
+    OnDemand,
+    Reserved,
+    ComputeSavingsPlan,
+    Ec2InstanceSavingsPlan,
+}
+
+impl PurchaseModel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PurchaseModel::OnDemand => "on_demand",
+            PurchaseModel::Reserved => "reserved",
+            PurchaseModel::ComputeSavingsPlan => "compute_savings_plan",
+            PurchaseModel::Ec2InstanceSavingsPlan => "ec2_instance_savings_plan",
+        }
+    }
+}
+
+/// Hourly price of one SKU under one purchase model, in the same shape for every model.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct NormalizedPriceRow {
+    pub region: Option<String>,
+    pub service_code: Option<String>,
+    pub sku: String,
+    pub instance_type: Option<String>,
+    /// Operating system of instances, engine of databases and caches
+    pub platform: Option<String>,
+    /// What a SageMaker ML instance is billed for, `None` for other services
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component: Option<SageMakerComponent>,
+    pub purchase_model: PurchaseModel,
+    /// `None` for on-demand
+    pub term: Option<ContractLength>,
+    pub purchase_option: Option<PurchaseOption>,
+    /// Standard or convertible, `None` for on-demand and savings plans
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offering_class: Option<RIOfferingClass>,
+    /// Upfront fees amortized over the term
+    pub effective_usd_per_hour: Decimal,
+}
+
+#[derive(Default)]
+struct ProductColumns {
+    region: Option<String>,
+    service_code: Option<String>,
+    instance_type: Option<String>,
+    platform: Option<String>,
+    component: Option<SageMakerComponent>,
+}
+
+impl ProductColumns {
+    fn new(attributes: &HashMap<String, String>, region_names: &RegionNames) -> Self {
+        let attribute =
+            |names: &[&str]| names.iter().find_map(|name| attributes.get(*name).cloned());
+        Self {
+            // Older offers name the location only
+            region: attribute(&["regionCode"]).or_else(|| {
+                let location = attributes.get("location")?;
+                region_names.code(location).map(str::to_string)
+            }),
+            service_code: attribute(&["servicecode"]),
+            // SageMaker names ML instance types `instanceName`
+            instance_type: attribute(&["instanceType", "instanceName"]),
+            platform: attribute(&["operatingSystem", "databaseEngine", "cacheEngine"]),
+            component: match attributes.get("servicecode").map(String::as_str) {
+                Some("AmazonSageMaker") => attributes
+                    .get("usagetype")
+                    .and_then(|usage_type| SageMakerComponent::from_usage_type(usage_type)),
+                _ => None,
+            },
+        }
+    }
+
+    fn row(self, rate: EffectiveRate, purchase_model: PurchaseModel) -> NormalizedPriceRow {
+        NormalizedPriceRow {
+            region: self.region,
+            service_code: self.service_code,
+            sku: rate.sku,
+            instance_type: self.instance_type,
+            platform: self.platform,
+            component: self.component,
+            purchase_model,
+            term: Some(rate.lease_contract_length),
+            purchase_option: Some(rate.purchase_option),
+            offering_class: None,
+            effective_usd_per_hour: rate.effective_usd_per_hour,
+        }
+    }
+}
+
+/// On-demand rates charged per hour or per second, as hourly prices. Rates in other units, e.g.
+/// storage per GB-month or vCPU-hours, are left out. Locations without a region code are resolved
+/// by `region_names`, as in the other `from_*` functions.
+pub fn from_on_demand(
+    rates: &[OnDemandRate],
+    region_names: &RegionNames,
+) -> Vec<NormalizedPriceRow> {
+    let rows = rates
+        .iter()
+        .filter_map(|rate| {
+            let unit = Unit::parse(&rate.unit);
+            if !matches!(unit, Unit::Hours | Unit::Seconds) {
+                return None;
+            }
+            let price = unit.price_per_hour(rate.price_per_unit.get("USD")?.value()?)?;
+            let columns = ProductColumns::new(&rate.attributes, region_names);
+            Some(NormalizedPriceRow {
+                region: columns.region,
+                service_code: columns.service_code,
+                sku: rate.sku.clone(),
+                instance_type: columns.instance_type,
+                platform: columns.platform,
+                component: columns.component,
+                purchase_model: PurchaseModel::OnDemand,
+                term: None,
+                purchase_option: None,
+                offering_class: None,
+                effective_usd_per_hour: price,
+            })
+        })
+        .collect::<Vec<_>>();
+    metrics::global().record_rows_pivoted(rows.len() as u64);
+    rows
+}
+
+/// Hourly reserved offerings of `response`.
+pub fn from_reserved(
+    response: &PricingListResponse,
+    region_names: &RegionNames,
+) -> Vec<NormalizedPriceRow> {
+    let mut rows = Vec::new();
+    for (sku, offerings) in &response.terms.reserved {
+        let product = match response.products.get(sku) {
+            Some(product) => product,
+            None => continue,
+        };
+        for offering in offerings.values() {
+            if let Some(rate) = reserved_offering(sku, offering, None) {
+                rows.push(NormalizedPriceRow {
+                    offering_class: offering.term_attributes.offering_class.clone(),
+                    ..ProductColumns::new(&product.attributes, region_names)
+                        .row(rate, PurchaseModel::Reserved)
+                });
+            }
+        }
+    }
+    metrics::global().record_rows_pivoted(rows.len() as u64);
+    rows
+}
+
+/// Savings plan rates, described by the attributes of the discounted SKU in `on_demand`.
+/// Rates of SKUs not in `on_demand` keep the region and service of the plan only.
+pub fn from_savings_plans(
+    rows: &[PivotedSavingsPlanTermRate],
+    on_demand: &[OnDemandRate],
+    region_names: &RegionNames,
+) -> Vec<NormalizedPriceRow> {
+    let attributes: HashMap<&str, &HashMap<String, String>> = on_demand
+        .iter()
+        .map(|rate| (rate.sku.as_str(), rate.attributes.as_ref()))
+        .collect();
+    let on_demand_prices = hourly_on_demand_prices(on_demand);
+    let normalized = rows
+        .iter()
+        .filter_map(|row| {
+            let discounted_sku = row.term_rate.discounted_sku.as_str();
+            let rate = savings_plan_row(row, on_demand_prices.get(discounted_sku).copied())?;
+            let purchase_model = match row.plan_type() {
+                SavingsPlanType::Ec2Instance => PurchaseModel::Ec2InstanceSavingsPlan,
+                _ => PurchaseModel::ComputeSavingsPlan,
+            };
+            let mut columns = attributes
+                .get(discounted_sku)
+                .map(|attributes| ProductColumns::new(attributes, region_names))
+                .unwrap_or_default();
+            columns.region = columns
+                .region
+                .or_else(|| row.savings_plan_attributes.region_code.clone());
+            columns.service_code = columns
+                .service_code
+                .or_else(|| Some(row.term_rate.discounted_service_code.clone()));
+            Some(columns.row(rate, purchase_model))
+        })
+        .collect::<Vec<_>>();
+    metrics::global().record_rows_pivoted(normalized.len() as u64);
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_on_demand, from_reserved, PurchaseModel};
+    use crate::model::aws::price_bulk_types::PricingListResponse;
+    use crate::model::aws::types::{
+        ContractLength, PurchaseOption, RIOfferingClass, SageMakerComponent,
+    };
+    use crate::transform::aws::location::RegionNames;
+    use crate::transform::aws::on_demand;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_normalize() {
+        let response: PricingListResponse = serde_json::from_str(
+            r#"{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724",
+            "products": {"SKU1": {"sku": "SKU1", "productFamily": "Database Instance",
+                "attributes": {"instanceType": "db.r6g.large", "databaseEngine": "PostgreSQL",
+                    "regionCode": "us-east-1", "servicecode": "AmazonRDS"}}},
+            "terms": {
+                "OnDemand": {"SKU1": {"SKU1.JRTCKXETXF": {"offerTermCode": "JRTCKXETXF",
+                    "sku": "SKU1", "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {},
+                    "priceDimensions": {"SKU1.JRTCKXETXF.6YS6EN2CT7": {
+                        "rateCode": "SKU1.JRTCKXETXF.6YS6EN2CT7", "description": "",
+                        "unit": "Hrs", "pricePerUnit": {"USD": "0.2600000000"}}}}}},
+                "Reserved": {"SKU1": {"SKU1.6QCMYABX3D": {"offerTermCode": "6QCMYABX3D",
+                    "sku": "SKU1", "effectiveDate": "2024-03-01T00:00:00Z",
+                    "termAttributes": {"LeaseContractLength": "1yr",
+                        "OfferingClass": "standard", "PurchaseOption": "All Upfront"},
+                    "priceDimensions": {
+                        "SKU1.6QCMYABX3D.2TG2D8R56U": {"rateCode": "SKU1.6QCMYABX3D.2TG2D8R56U",
+                            "description": "Upfront Fee", "unit": "Quantity",
+                            "pricePerUnit": {"USD": "1314"}},
+                        "SKU1.6QCMYABX3D.6YS6EN2CT7": {"rateCode": "SKU1.6QCMYABX3D.6YS6EN2CT7",
+                            "description": "", "unit": "Hrs",
+                            "pricePerUnit": {"USD": "0.0000000000"}}}}}}}}"#,
+        )
+        .unwrap();
+
+        let region_names = RegionNames::bundled();
+        let reserved = from_reserved(&response, &region_names);
+        assert_eq!(reserved.len(), 1);
+        assert_eq!(reserved[0].purchase_model, PurchaseModel::Reserved);
+        assert_eq!(reserved[0].platform.as_deref(), Some("PostgreSQL"));
+        assert_eq!(reserved[0].term, Some(ContractLength::OneYear));
+        assert_eq!(
+            reserved[0].purchase_option,
+            Some(PurchaseOption::AllUpfront)
+        );
+        assert_eq!(reserved[0].offering_class, Some(RIOfferingClass::Standard));
+        assert_eq!(reserved[0].effective_usd_per_hour, Decimal::new(15, 2));
+
+        let on_demand = from_on_demand(&on_demand::pivot(response), &region_names);
+        assert_eq!(on_demand.len(), 1);
+        assert_eq!(on_demand[0].purchase_model, PurchaseModel::OnDemand);
+        assert_eq!(on_demand[0].region, reserved[0].region);
+        assert_eq!(on_demand[0].service_code.as_deref(), Some("AmazonRDS"));
+        assert_eq!(on_demand[0].instance_type.as_deref(), Some("db.r6g.large"));
+        assert_eq!(on_demand[0].term, None);
+        assert_eq!(on_demand[0].effective_usd_per_hour, Decimal::new(26, 2));
+    }
+
+    #[test]
+    fn test_normalize_sagemaker() {
+        let response: PricingListResponse = serde_json::from_str(
+            r#"{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724",
+            "products": {"ML1": {"sku": "ML1", "productFamily": "ML Instance",
+                "attributes": {"instanceName": "ml.m5.large", "component": "Training",
+                    "usagetype": "USE2-Train:ml.m5.large", "regionCode": "us-east-2",
+                    "servicecode": "AmazonSageMaker"}}},
+            "terms": {"OnDemand": {"ML1": {"ML1.JRTCKXETXF": {"offerTermCode": "JRTCKXETXF",
+                "sku": "ML1", "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {},
+                "priceDimensions": {"ML1.JRTCKXETXF.6YS6EN2CT7": {
+                    "rateCode": "ML1.JRTCKXETXF.6YS6EN2CT7", "description": "",
+                    "unit": "Hrs", "pricePerUnit": {"USD": "0.1150000000"}}}}}},
+                "Reserved": {}}}"#,
+        )
+        .unwrap();
+
+        let rows = from_on_demand(&on_demand::pivot(response), &RegionNames::bundled());
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].instance_type.as_deref(), Some("ml.m5.large"));
+        assert_eq!(rows[0].component, Some(SageMakerComponent::Training));
+        assert_eq!(
+            SageMakerComponent::from_usage_type("Notebk:ml.t3.medium"),
+            Some(SageMakerComponent::Notebook)
+        );
+        assert_eq!(
+            SageMakerComponent::from_usage_type("USE1-Processing:ml.m5.large"),
+            None
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use crate::metrics;
+use crate::model::aws::price_bulk_types::PricingListResponse;
+use crate::model::aws::types::Price;
+use crate::model::aws::unit::Unit;
+use crate::transform::sink::RowSink;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// One price dimension of an on-demand offering, joined with its product.
+#[derive(Debug, Clone, Serialize)]
+pub struct OnDemandRate {
+    pub sku: String,
+    pub product_family: String,
+    pub attributes: Arc<HashMap<String, String>>,
+    pub offer_term_code: String,
+    pub effective_date: DateTime<Utc>,
+    pub rate_code: String,
+    pub description: String,
+    pub unit: String,
+    pub price_per_unit: HashMap<String, Price>,
+}
+
+impl OnDemandRate {
+    pub fn parsed_unit(&self) -> Unit {
+        Unit::parse(&self.unit)
+    }
+}
+
+/// Whether `attributes` describe a plain instance of `instance_type`, without preinstalled
+/// software, capacity reservations or bring-your-own licenses.
+pub(crate) fn is_plain_instance(
+    attributes: &HashMap<String, String>,
+    instance_type: &str,
+    operating_system: &str,
+    tenancy: &str,
+) -> bool {
+    let attribute = |name: &str| attributes.get(name).map(String::as_str);
+    attribute("instanceType") == Some(instance_type)
+        && attribute("operatingSystem") == Some(operating_system)
+        && attribute("tenancy") == Some(tenancy)
+        && attribute("preInstalledSw") == Some("NA")
+        && attribute("capacitystatus").is_none_or(|status| status == "Used")
+        && attribute("licenseModel") != Some("Bring your own license")
+}
+
+pub fn pivot(response: PricingListResponse) -> Vec<OnDemandRate> {
+    let mut pivoted: Vec<OnDemandRate> = Vec::new();
+    // collecting into a Vec never fails
+    let _ = pivot_into(response, &mut pivoted);
+    pivoted
+}
+
+/// Writes each rate to `sink` as it is pivoted, returning the number of rows written.
+pub fn pivot_into(
+    response: PricingListResponse,
+    sink: &mut impl RowSink<OnDemandRate>,
+) -> anyhow::Result<u64> {
+    let mut products = response.products;
+    let mut rows = 0;
+    for (sku, offerings) in response.terms.on_demand {
+        let product = match products.remove(&sku) {
+            Some(product) => product,
+            None => continue,
+        };
+        let attributes = Arc::new(product.attributes);
+        for offering in offerings.into_values() {
+            for dimension in offering.price_dimensions.into_values() {
+                sink.write(OnDemandRate {
+                    sku: sku.clone(),
+                    product_family: product.product_family.clone(),
+                    attributes: attributes.clone(),
+                    offer_term_code: offering.offer_term_code.clone(),
+                    effective_date: offering.effective_date,
+                    rate_code: dimension.rate_code,
+                    description: dimension.description,
+                    unit: dimension.unit,
+                    price_per_unit: dimension.price_per_unit,
+                })?;
+                rows += 1;
+            }
+        }
+    }
+    sink.finish()?;
+    metrics::global().record_rows_pivoted(rows);
+    Ok(rows)
+}
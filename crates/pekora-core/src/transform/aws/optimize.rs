@@ -0,0 +1,289 @@
+use crate::model::aws::price_bulk_types::PricingListResponse;
+use crate::model::aws::unit::HOURS_PER_MONTH;
+use crate::transform::aws::estimate::{estimate, CostEstimate, InstanceRequirement, WorkloadSpec};
+use crate::transform::aws::normalize::PurchaseModel;
+use crate::transform::aws::recommendation::Commitment;
+use crate::transform::aws::savings_plan::PivotedSavingsPlanTermRate;
+use log::warn;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Monthly instance hours of one instance type in one region.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UsageLine {
+    pub region: String,
+    pub instance_type: String,
+    #[serde(default = "default_operating_system")]
+    pub operating_system: String,
+    #[serde(default = "default_tenancy")]
+    pub tenancy: String,
+    pub hours: Decimal,
+}
+
+fn default_operating_system() -> String {
+    "Linux".to_string()
+}
+
+fn default_tenancy() -> String {
+    "Shared".to_string()
+}
+
+/// Rates of one region.
+#[derive(Debug, Clone, Copy)]
+pub struct RegionRates<'a> {
+    /// EC2 pricing list, for on-demand and reserved rates
+    pub pricing: &'a PricingListResponse,
+    pub savings_plans: &'a [PivotedSavingsPlanTermRate],
+}
+
+/// Reservations or savings plan commitment of one instance type.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Purchase {
+    pub purchase_model: PurchaseModel,
+    pub region: String,
+    pub instance_type: String,
+    /// Instances covered, reserved instances to buy for reservations
+    pub quantity: Decimal,
+    /// Savings plan commitment in USD per hour, zero for reservations
+    pub hourly_commitment_usd: Decimal,
+    pub upfront_usd: Decimal,
+    /// Recurring charges plus the upfront fee spread over the term
+    pub monthly_usd: Decimal,
+    /// On-demand cost of the usage covered
+    pub on_demand_monthly_usd: Decimal,
+    /// Empty if the purchase never pays for itself
+    pub break_even_months: Option<Decimal>,
+}
+
+/// Purchases minimizing the monthly cost of a usage profile.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CommitmentPlan {
+    pub purchases: Vec<Purchase>,
+    pub savings_plan_hourly_commitment_usd: Decimal,
+    pub upfront_usd: Decimal,
+    /// Cost of all usage on-demand
+    pub on_demand_monthly_usd: Decimal,
+    pub planned_monthly_usd: Decimal,
+    pub monthly_savings_usd: Decimal,
+    pub savings_percent: Decimal,
+    pub break_even_months: Option<Decimal>,
+}
+
+/// Recommends reservations and savings plan commitments of `commitment` for `usage`.
+///
+/// Hours of a line are taken to run on as few instances as possible, each instance running the
+/// whole month except the last. Every instance is bought whichever way is cheapest: on-demand for
+/// the hours it runs, or a reservation or savings plan paid for every hour of the month. Lines
+/// without rates in `rates` are left on-demand with a warning.
+pub fn optimize(
+    usage: &[UsageLine],
+    commitment: &Commitment,
+    rates: &BTreeMap<String, RegionRates>,
+) -> CommitmentPlan {
+    let hours_per_month = Decimal::from(HOURS_PER_MONTH);
+    let months = Decimal::from(commitment.months());
+    let mut purchases: BTreeMap<(String, String, &str), Purchase> = BTreeMap::new();
+    let mut on_demand_monthly = Decimal::ZERO;
+    let mut planned_monthly = Decimal::ZERO;
+    for line in usage {
+        let (on_demand_hourly, costs) = match line_costs(line, commitment, rates) {
+            Some(estimate) => (estimate.on_demand_usd_per_hour, estimate.costs),
+            None => continue,
+        };
+        let mut remaining = line.hours.max(Decimal::ZERO);
+        while !remaining.is_zero() {
+            let hours = remaining.min(hours_per_month);
+            remaining -= hours;
+            let on_demand = on_demand_hourly * hours;
+            on_demand_monthly += on_demand;
+            let cheapest = costs[1..]
+                .iter()
+                .min_by_key(|cost| cost.monthly_usd)
+                .filter(|cost| cost.monthly_usd < on_demand);
+            let cost = match cheapest {
+                Some(cost) => cost,
+                None => {
+                    planned_monthly += on_demand;
+                    continue;
+                }
+            };
+            planned_monthly += cost.monthly_usd;
+            let purchase = purchases
+                .entry((
+                    line.region.clone(),
+                    line.instance_type.clone(),
+                    cost.purchase_model.as_str(),
+                ))
+                .or_insert_with(|| Purchase {
+                    purchase_model: cost.purchase_model,
+                    region: line.region.clone(),
+                    instance_type: line.instance_type.clone(),
+                    quantity: Decimal::ZERO,
+                    hourly_commitment_usd: Decimal::ZERO,
+                    upfront_usd: Decimal::ZERO,
+                    monthly_usd: Decimal::ZERO,
+                    on_demand_monthly_usd: Decimal::ZERO,
+                    break_even_months: None,
+                });
+            purchase.quantity += Decimal::ONE;
+            if cost.purchase_model != PurchaseModel::Reserved {
+                purchase.hourly_commitment_usd += cost.monthly_usd / hours_per_month;
+            }
+            purchase.upfront_usd += cost.upfront_usd;
+            purchase.monthly_usd += cost.monthly_usd;
+            purchase.on_demand_monthly_usd += on_demand;
+        }
+    }
+
+    let mut purchases: Vec<Purchase> = purchases.into_values().collect();
+    for purchase in &mut purchases {
+        purchase.break_even_months = break_even(
+            purchase.upfront_usd,
+            purchase.monthly_usd,
+            purchase.on_demand_monthly_usd,
+            months,
+        );
+        purchase.hourly_commitment_usd = purchase.hourly_commitment_usd.round_dp(3);
+        purchase.monthly_usd = purchase.monthly_usd.round_dp(2);
+        purchase.on_demand_monthly_usd = purchase.on_demand_monthly_usd.round_dp(2);
+    }
+    let upfront: Decimal = purchases.iter().map(|purchase| purchase.upfront_usd).sum();
+    let monthly_savings = on_demand_monthly - planned_monthly;
+    CommitmentPlan {
+        savings_plan_hourly_commitment_usd: purchases
+            .iter()
+            .map(|purchase| purchase.hourly_commitment_usd)
+            .sum(),
+        upfront_usd: upfront,
+        on_demand_monthly_usd: on_demand_monthly.round_dp(2),
+        planned_monthly_usd: planned_monthly.round_dp(2),
+        monthly_savings_usd: monthly_savings.round_dp(2),
+        savings_percent: if on_demand_monthly.is_zero() {
+            Decimal::ZERO
+        } else {
+            (monthly_savings * Decimal::ONE_HUNDRED / on_demand_monthly).round_dp(2)
+        },
+        break_even_months: break_even(upfront, planned_monthly, on_demand_monthly, months),
+        purchases,
+    }
+}
+
+/// Monthly costs of one instance of `line` running all month, on-demand first.
+fn line_costs(
+    line: &UsageLine,
+    commitment: &Commitment,
+    rates: &BTreeMap<String, RegionRates>,
+) -> Option<CostEstimate> {
+    let region_rates = match rates.get(&line.region) {
+        Some(region_rates) => region_rates,
+        None => {
+            warn!("No rates for {}, leaving it on-demand", line.region);
+            return None;
+        }
+    };
+    let mut workload = WorkloadSpec::new(
+        InstanceRequirement::InstanceType(line.instance_type.clone()),
+        line.region.clone(),
+    );
+    workload.operating_system = line.operating_system.clone();
+    workload.tenancy = line.tenancy.clone();
+    workload.commitment = Some(commitment.clone());
+    match estimate(&workload, region_rates.pricing, region_rates.savings_plans) {
+        Ok(estimate) => Some(estimate),
+        Err(e) => {
+            warn!("{}, leaving it out", e);
+            None
+        }
+    }
+}
+
+/// Months until the upfront fee is paid back by the difference between the on-demand cost and
+/// the recurring part of `monthly`.
+fn break_even(
+    upfront: Decimal,
+    monthly: Decimal,
+    on_demand_monthly: Decimal,
+    months: Decimal,
+) -> Option<Decimal> {
+    if upfront.is_zero() {
+        return (on_demand_monthly > monthly).then_some(Decimal::ZERO);
+    }
+    let recurring = monthly - upfront / months;
+    (on_demand_monthly > recurring).then(|| (upfront / (on_demand_monthly - recurring)).round_dp(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{optimize, RegionRates, UsageLine};
+    use crate::model::aws::price_bulk_types::PricingListResponse;
+    use crate::model::aws::types::{ContractLength, PurchaseOption};
+    use crate::transform::aws::normalize::PurchaseModel;
+    use crate::transform::aws::recommendation::Commitment;
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_optimize() {
+        let response: PricingListResponse = serde_json::from_str(
+            r#"{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724",
+            "products": {"SKU1": {"sku": "SKU1", "productFamily": "Compute Instance",
+                "attributes": {"instanceType": "m5.large", "operatingSystem": "Linux",
+                    "tenancy": "Shared", "preInstalledSw": "NA", "capacitystatus": "Used",
+                    "licenseModel": "No License required", "usagetype": "BoxUsage:m5.large",
+                    "operation": "RunInstances"}}},
+            "terms": {
+                "OnDemand": {"SKU1": {"SKU1.JRTCKXETXF": {"offerTermCode": "JRTCKXETXF",
+                    "sku": "SKU1", "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {},
+                    "priceDimensions": {"SKU1.JRTCKXETXF.6YS6EN2CT7": {
+                        "rateCode": "SKU1.JRTCKXETXF.6YS6EN2CT7", "description": "",
+                        "unit": "Hrs", "pricePerUnit": {"USD": "0.1000000000"}}}}}},
+                "Reserved": {"SKU1": {"SKU1.HU7G6KETJZ": {"offerTermCode": "HU7G6KETJZ",
+                    "sku": "SKU1", "effectiveDate": "2024-03-01T00:00:00Z",
+                    "termAttributes": {"LeaseContractLength": "1yr",
+                        "OfferingClass": "standard", "PurchaseOption": "Partial Upfront"},
+                    "priceDimensions": {
+                        "SKU1.HU7G6KETJZ.2TG2D8R56U": {"rateCode": "SKU1.HU7G6KETJZ.2TG2D8R56U",
+                            "description": "Upfront Fee", "unit": "Quantity",
+                            "pricePerUnit": {"USD": "262.8"}},
+                        "SKU1.HU7G6KETJZ.6YS6EN2CT7": {"rateCode": "SKU1.HU7G6KETJZ.6YS6EN2CT7",
+                            "description": "", "unit": "Hrs",
+                            "pricePerUnit": {"USD": "0.0300000000"}}}}}}}}"#,
+        )
+        .unwrap();
+        let rates = BTreeMap::from([(
+            "us-east-1".to_string(),
+            RegionRates {
+                pricing: &response,
+                savings_plans: &[],
+            },
+        )]);
+        let commitment =
+            Commitment::new(ContractLength::OneYear, PurchaseOption::PartialUpfront).unwrap();
+        // Two instances all month, and a third for a fifth of it
+        let usage = [UsageLine {
+            region: "us-east-1".to_string(),
+            instance_type: "m5.large".to_string(),
+            operating_system: "Linux".to_string(),
+            tenancy: "Shared".to_string(),
+            hours: Decimal::from(1606),
+        }];
+
+        let plan = optimize(&usage, &commitment, &rates);
+        assert_eq!(plan.purchases.len(), 1);
+        let purchase = &plan.purchases[0];
+        assert_eq!(purchase.purchase_model, PurchaseModel::Reserved);
+        assert_eq!(purchase.quantity, Decimal::from(2));
+        assert_eq!(purchase.upfront_usd, Decimal::new(5256, 1));
+        // 0.03 recurring and 0.03 of upfront per hour
+        assert_eq!(purchase.monthly_usd, Decimal::new(876, 1));
+        assert_eq!(purchase.break_even_months, Some(Decimal::new(51, 1)));
+        assert_eq!(plan.on_demand_monthly_usd, Decimal::new(1606, 1));
+        assert_eq!(plan.planned_monthly_usd, Decimal::new(1022, 1));
+        assert_eq!(plan.savings_percent, Decimal::new(3636, 2));
+        assert!(optimize(&usage, &commitment, &BTreeMap::new())
+            .purchases
+            .is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+use crate::model::aws::price_bulk_types::RdsPricingListResponse;
+use crate::model::aws::rds::{pricing_engine, OrderableDbInstanceOption};
+use crate::model::aws::unit::Unit;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// On-demand price of an RDS instance class that can actually be launched.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct OrderableInstancePrice {
+    pub sku: String,
+    pub database_engine: String,
+    pub instance_class: String,
+    /// `Single-AZ` or `Multi-AZ`
+    pub deployment_option: String,
+    pub license_model: Option<String>,
+    pub on_demand_usd_per_hour: Decimal,
+    /// Availability zones the class is orderable in, across engine versions
+    pub availability_zones: Vec<String>,
+}
+
+#[derive(Default)]
+struct Orderability {
+    availability_zones: BTreeSet<String>,
+    multi_az_capable: bool,
+}
+
+/// Instance prices of an RDS pricing list limited to the engine and instance class combinations
+/// in `options`, e.g. from `DescribeOrderableDBInstanceOptions` of the same region. Multi-AZ
+/// prices are kept only for combinations that are Multi-AZ capable. Sorted by engine, class and
+/// deployment option.
+pub fn orderable_instance_prices(
+    response: &RdsPricingListResponse,
+    options: &[OrderableDbInstanceOption],
+) -> Vec<OrderableInstancePrice> {
+    let mut orderable: HashMap<(&str, &str), Orderability> = HashMap::new();
+    for option in options {
+        let engine = match pricing_engine(&option.engine) {
+            Some(engine) => engine,
+            None => continue,
+        };
+        let entry = orderable
+            .entry((engine, option.db_instance_class.as_str()))
+            .or_default();
+        entry.multi_az_capable |= option.multi_az_capable;
+        entry.availability_zones.extend(
+            option
+                .availability_zones
+                .iter()
+                .map(|availability_zone| availability_zone.name.clone()),
+        );
+    }
+
+    let mut rows = Vec::new();
+    for (sku, product) in &response.products {
+        let attributes = &product.attributes;
+        let (engine, instance_class, deployment_option) = match (
+            attributes.database_engine.as_deref(),
+            attributes.instance_type.as_deref(),
+            attributes.deployment_option.as_deref(),
+        ) {
+            (Some(engine), Some(instance_class), Some(deployment_option)) => {
+                (engine, instance_class, deployment_option)
+            }
+            _ => continue,
+        };
+        let orderability = match orderable.get(&(engine, instance_class)) {
+            Some(orderability) => orderability,
+            None => continue,
+        };
+        if deployment_option.starts_with("Multi-AZ") && !orderability.multi_az_capable {
+            continue;
+        }
+        let on_demand_usd_per_hour = response
+            .terms
+            .on_demand
+            .get(sku)
+            .into_iter()
+            .flat_map(|offerings| offerings.values())
+            .flat_map(|offering| offering.price_dimensions.values())
+            .find(|dimension| dimension.parsed_unit() == Unit::Hours)
+            .and_then(|dimension| dimension.usd());
+        if let Some(on_demand_usd_per_hour) = on_demand_usd_per_hour {
+            rows.push(OrderableInstancePrice {
+                sku: sku.clone(),
+                database_engine: engine.to_string(),
+                instance_class: instance_class.to_string(),
+                deployment_option: deployment_option.to_string(),
+                license_model: attributes.license_model.clone(),
+                on_demand_usd_per_hour,
+                availability_zones: orderability.availability_zones.iter().cloned().collect(),
+            });
+        }
+    }
+    rows.sort_by(|a, b| {
+        (
+            &a.database_engine,
+            &a.instance_class,
+            &a.deployment_option,
+            &a.sku,
+        )
+            .cmp(&(
+                &b.database_engine,
+                &b.instance_class,
+                &b.deployment_option,
+                &b.sku,
+            ))
+    });
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::orderable_instance_prices;
+    use crate::model::aws::price_bulk_types::{PricingListResponse, RdsPricingListResponse};
+    use crate::model::aws::rds::OrderableDbInstanceOptionsResponse;
+
+    fn product(sku: &str, engine: &str, instance_class: &str, deployment: &str) -> String {
+        format!(
+            r#""{sku}": {{"sku": "{sku}", "productFamily": "Database Instance", "attributes": {{
+                "databaseEngine": "{engine}", "instanceType": "{instance_class}",
+                "deploymentOption": "{deployment}", "licenseModel": "No license required"}}}}"#
+        )
+    }
+
+    fn offering(sku: &str) -> String {
+        format!(
+            r#""{sku}": {{"{sku}.JRTCKXETXF": {{"offerTermCode": "JRTCKXETXF", "sku": "{sku}",
+                "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {{}},
+                "priceDimensions": {{"{sku}.JRTCKXETXF.6YS6EN2CT7": {{
+                    "rateCode": "{sku}.JRTCKXETXF.6YS6EN2CT7", "description": "",
+                    "unit": "Hrs", "pricePerUnit": {{"USD": "0.171"}}}}}}}}}}"#
+        )
+    }
+
+    #[test]
+    fn test_orderable_instance_prices() {
+        let json = format!(
+            r#"{{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724", "products": {{{}, {}, {}, {}}},
+            "terms": {{"OnDemand": {{{}, {}, {}, {}}}, "Reserved": {{}}}}}}"#,
+            product("M5", "MySQL", "db.m5.large", "Single-AZ"),
+            product("M5MAZ", "MySQL", "db.m5.large", "Multi-AZ"),
+            product("T2", "MySQL", "db.t2.micro", "Single-AZ"),
+            product("PGM5", "PostgreSQL", "db.m5.large", "Single-AZ"),
+            offering("M5"),
+            offering("M5MAZ"),
+            offering("T2"),
+            offering("PGM5"),
+        );
+        let response: PricingListResponse = serde_json::from_str(&json).unwrap();
+        let typed: RdsPricingListResponse = response.with_typed_attributes().unwrap();
+        let options: OrderableDbInstanceOptionsResponse = serde_json::from_str(
+            r#"{"OrderableDBInstanceOptions": [
+                {"Engine": "mysql", "EngineVersion": "8.0.35", "DBInstanceClass": "db.m5.large",
+                 "LicenseModel": "general-public-license", "StorageType": "gp3",
+                 "AvailabilityZones": [{"Name": "us-east-1a"}, {"Name": "us-east-1b"}],
+                 "MultiAZCapable": false},
+                {"Engine": "mysql", "EngineVersion": "8.0.36", "DBInstanceClass": "db.m5.large",
+                 "AvailabilityZones": [{"Name": "us-east-1c"}], "MultiAZCapable": false}
+            ]}"#,
+        )
+        .unwrap();
+
+        let rows = orderable_instance_prices(&typed, &options.orderable_db_instance_options);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].sku, "M5");
+        assert_eq!(
+            rows[0].availability_zones,
+            ["us-east-1a", "us-east-1b", "us-east-1c"]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use crate::model::aws::types::Price;
+use crate::transform::aws::estimate::CostEstimate;
+use crate::transform::aws::normalize::NormalizedPriceRow;
+use crate::transform::aws::on_demand::OnDemandRate;
+use crate::transform::aws::savings_plan::PivotedSavingsPlanTermRate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Negotiated discounts over public prices, e.g. of an enterprise agreement, in percent.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DiscountOverlay {
+    /// Discount of services without their own entry in `services`
+    pub percent: Decimal,
+    /// Discount by service code, e.g. `AmazonEC2`
+    pub services: BTreeMap<String, Decimal>,
+}
+
+impl DiscountOverlay {
+    /// Discount of `service_code` in percent.
+    pub fn percent_of(&self, service_code: &str) -> Decimal {
+        self.services
+            .get(service_code)
+            .copied()
+            .unwrap_or(self.percent)
+    }
+
+    /// `usd` of `service_code` after the discount.
+    pub fn apply(&self, service_code: &str, usd: Decimal) -> Decimal {
+        let percent = self.percent_of(service_code);
+        if percent.is_zero() {
+            return usd;
+        }
+        (usd * (Decimal::ONE_HUNDRED - percent) / Decimal::ONE_HUNDRED).normalize()
+    }
+
+    /// `price` after the discount, as is if it is not a number.
+    pub fn price(&self, service_code: &str, price: &Price) -> Price {
+        match price.value() {
+            Some(value) => Price::new(self.apply(service_code, value).to_string()),
+            None => price.clone(),
+        }
+    }
+
+    /// Discounts the on-demand rate of a product of `service_code`.
+    pub fn on_demand(&self, service_code: &str, rate: &mut OnDemandRate) {
+        for price in rate.price_per_unit.values_mut() {
+            *price = self.price(service_code, price);
+        }
+    }
+
+    /// Discounts a savings plan rate by the discount of the service it covers usage of.
+    pub fn savings_plan(&self, row: &mut PivotedSavingsPlanTermRate) {
+        let rate = &mut row.term_rate;
+        rate.discounted_rate.price =
+            self.price(&rate.discounted_service_code, &rate.discounted_rate.price);
+    }
+
+    /// Discounts a normalized row by the discount of its service, EC2 if it names none.
+    pub fn normalized(&self, row: &mut NormalizedPriceRow) {
+        let service_code = row.service_code.as_deref().unwrap_or("AmazonEC2");
+        row.effective_usd_per_hour = self.apply(service_code, row.effective_usd_per_hour);
+    }
+
+    /// Discounts the EC2 costs of an estimate, and its network costs by the service each
+    /// resource is priced in. Savings over on-demand are unchanged when both are discounted alike.
+    pub fn estimate(&self, estimate: &mut CostEstimate) {
+        estimate.on_demand_usd_per_hour = self.apply("AmazonEC2", estimate.on_demand_usd_per_hour);
+        for cost in &mut estimate.costs {
+            cost.upfront_usd = self.apply("AmazonEC2", cost.upfront_usd);
+            cost.monthly_usd = self.apply("AmazonEC2", cost.monthly_usd);
+        }
+        for line in &mut estimate.network {
+            let service_code = line.resource.service_code();
+            line.hourly_usd = self.apply(service_code, line.hourly_usd);
+            line.processing_usd = self.apply(service_code, line.processing_usd);
+            line.monthly_usd = self.apply(service_code, line.monthly_usd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DiscountOverlay;
+    use crate::model::aws::types::Price;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_discount_overlay() {
+        let overlay: DiscountOverlay =
+            serde_json::from_str(r#"{"percent": 5, "services": {"AmazonEC2": 12.5}}"#).unwrap();
+        assert_eq!(overlay.percent_of("AmazonRDS"), Decimal::new(5, 0));
+        assert_eq!(
+            overlay.apply("AmazonEC2", Decimal::new(96, 3)),
+            Decimal::new(84, 3)
+        );
+        assert_eq!(
+            overlay
+                .price("AmazonRDS", &Price::new("0.2000000000"))
+                .value(),
+            Some(Decimal::new(19, 2))
+        );
+        assert_eq!(overlay.price("AmazonRDS", &Price::new("n/a")).raw(), "n/a");
+        assert_eq!(
+            DiscountOverlay::default()
+                .price("AmazonEC2", &Price::new("0.0960000000"))
+                .raw(),
+            "0.0960000000"
+        );
+    }
+}
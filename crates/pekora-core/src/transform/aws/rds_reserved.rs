@@ -0,0 +1,240 @@
+use crate::model::aws::price_bulk_types::RdsPricingListResponse;
+use crate::model::aws::rds::ReservedDbInstancesOffering;
+use crate::model::aws::types::{ContractLength, PurchaseOption};
+use crate::transform::aws::effective_rate::reserved_offering;
+use rust_decimal::Decimal;
+use serde::de::value::Error as ValueError;
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Outcome of looking up a live reserved offering in the pricing list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Match,
+    PriceMismatch,
+    /// No reservation of the same instance, deployment, term and payment option in the offer
+    MissingFromOffer,
+    /// Engine, term or payment option the pricing list has no equivalent for
+    Unrecognized,
+}
+
+/// A reserved DB instance offering of the RDS API next to the same reservation in the pricing list.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ReservedOfferingCheck {
+    pub offering_id: String,
+    pub instance_class: String,
+    pub product_description: String,
+    pub multi_az: bool,
+    pub term: Option<ContractLength>,
+    pub purchase_option: Option<PurchaseOption>,
+    pub api_upfront_usd: Decimal,
+    pub api_usd_per_hour: Decimal,
+    pub offer_upfront_usd: Option<Decimal>,
+    pub offer_usd_per_hour: Option<Decimal>,
+    pub status: CheckStatus,
+}
+
+/// Prices of the reservations in a pricing list by engine, instance class, Multi-AZ, term and
+/// payment option, along with their license model.
+type OfferIndex<'a> =
+    HashMap<(&'a str, &'a str, bool, String, String), Vec<(Option<&'a str>, Decimal, Decimal)>>;
+
+fn index(response: &RdsPricingListResponse) -> OfferIndex<'_> {
+    let mut index: OfferIndex = HashMap::new();
+    for (sku, offerings) in &response.terms.reserved {
+        let attributes = match response.products.get(sku) {
+            Some(product) => &product.attributes,
+            None => continue,
+        };
+        let (engine, instance_class, multi_az) = match (
+            attributes.database_engine.as_deref(),
+            attributes.instance_type.as_deref(),
+            attributes.deployment_option.as_deref(),
+        ) {
+            (Some(engine), Some(instance_class), Some("Single-AZ")) => {
+                (engine, instance_class, false)
+            }
+            (Some(engine), Some(instance_class), Some("Multi-AZ")) => {
+                (engine, instance_class, true)
+            }
+            _ => continue,
+        };
+        for offering in offerings.values() {
+            if let Some(rate) = reserved_offering(sku, offering, None) {
+                index
+                    .entry((
+                        engine,
+                        instance_class,
+                        multi_az,
+                        rate.lease_contract_length.as_str().to_string(),
+                        rate.purchase_option.as_str().to_string(),
+                    ))
+                    .or_default()
+                    .push((
+                        attributes.license_model.as_deref(),
+                        rate.upfront_usd,
+                        rate.recurring_usd_per_hour,
+                    ));
+            }
+        }
+    }
+    index
+}
+
+fn purchase_option(offering_type: &str) -> Option<PurchaseOption> {
+    match PurchaseOption::deserialize(offering_type.into_deserializer()) {
+        Ok(PurchaseOption::Unknown(_)) | Err::<_, ValueError>(_) => None,
+        Ok(purchase_option) => Some(purchase_option),
+    }
+}
+
+fn usd(value: f64) -> Decimal {
+    Decimal::try_from(value).unwrap_or_default()
+}
+
+/// Checks reserved DB instance `offerings` of the RDS API against the reservations of the
+/// AmazonRDS pricing list of the same region. Prices match if equal to the cent upfront and to
+/// a millionth of a dollar hourly.
+pub fn cross_check(
+    response: &RdsPricingListResponse,
+    offerings: &[ReservedDbInstancesOffering],
+) -> Vec<ReservedOfferingCheck> {
+    let index = index(response);
+    offerings
+        .iter()
+        .map(|offering| {
+            let api_upfront_usd = usd(offering.fixed_price);
+            let api_usd_per_hour = usd(offering.usage_price)
+                + offering
+                    .recurring_charges
+                    .iter()
+                    .filter(|charge| charge.recurring_charge_frequency == "Hourly")
+                    .map(|charge| usd(charge.recurring_charge_amount))
+                    .sum::<Decimal>();
+            let term = ContractLength::from_seconds(offering.duration);
+            let purchase_option = purchase_option(&offering.offering_type);
+            let mut check = ReservedOfferingCheck {
+                offering_id: offering.reserved_db_instances_offering_id.clone(),
+                instance_class: offering.db_instance_class.clone(),
+                product_description: offering.product_description.clone(),
+                multi_az: offering.multi_az,
+                term: term.clone(),
+                purchase_option: purchase_option.clone(),
+                api_upfront_usd,
+                api_usd_per_hour,
+                offer_upfront_usd: None,
+                offer_usd_per_hour: None,
+                status: CheckStatus::Unrecognized,
+            };
+            let (engine, license_model, term, purchase_option) =
+                match (offering.engine_and_license(), term, purchase_option) {
+                    (Some((engine, license_model)), Some(term), Some(purchase_option)) => {
+                        (engine, license_model, term, purchase_option)
+                    }
+                    _ => return check,
+                };
+            let candidates = index.get(&(
+                engine,
+                offering.db_instance_class.as_str(),
+                offering.multi_az,
+                term.as_str().to_string(),
+                purchase_option.as_str().to_string(),
+            ));
+            let found = candidates
+                .into_iter()
+                .flatten()
+                .find(|(offer_license, _, _)| {
+                    license_model.is_none() || *offer_license == license_model
+                });
+            check.status = match found {
+                Some(&(_, upfront_usd, usd_per_hour)) => {
+                    check.offer_upfront_usd = Some(upfront_usd);
+                    check.offer_usd_per_hour = Some(usd_per_hour);
+                    if upfront_usd.round_dp(2) == api_upfront_usd.round_dp(2)
+                        && usd_per_hour.round_dp(6) == api_usd_per_hour.round_dp(6)
+                    {
+                        CheckStatus::Match
+                    } else {
+                        CheckStatus::PriceMismatch
+                    }
+                }
+                None => CheckStatus::MissingFromOffer,
+            };
+            check
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cross_check, CheckStatus};
+    use crate::model::aws::price_bulk_types::{PricingListResponse, RdsPricingListResponse};
+    use crate::model::aws::rds::ReservedDbInstancesOfferingsResponse;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_cross_check() {
+        let response: PricingListResponse = serde_json::from_str(
+            r#"{
+                "formatVersion": "v1.0",
+                "publicationDate": "2024-03-12T15:37:24Z",
+                "version": "20240312153724",
+                "products": {
+                    "MAZ": {"productFamily": "Database Instance", "sku": "MAZ",
+                        "attributes": {"databaseEngine": "PostgreSQL", "deploymentOption": "Multi-AZ",
+                            "instanceType": "db.m5.large", "licenseModel": "No license required"}}
+                },
+                "terms": {"OnDemand": {}, "Reserved": {"MAZ": {
+                    "MAZ.4NA7Y494T4": {"offerTermCode": "4NA7Y494T4", "sku": "MAZ",
+                        "effectiveDate": "2024-03-01T00:00:00Z",
+                        "termAttributes": {"LeaseContractLength": "1yr",
+                            "PurchaseOption": "No Upfront"},
+                        "priceDimensions": {
+                            "MAZ.4NA7Y494T4.6YS6EN2CT7": {"rateCode": "MAZ.4NA7Y494T4.6YS6EN2CT7",
+                                "description": "", "unit": "Hrs",
+                                "pricePerUnit": {"USD": "0.2350000000"}}}}}}}
+            }"#,
+        )
+        .unwrap();
+        let typed: RdsPricingListResponse = response.with_typed_attributes().unwrap();
+        let offerings: ReservedDbInstancesOfferingsResponse = serde_json::from_str(
+            r#"{"ReservedDBInstancesOfferings": [
+                {"ReservedDBInstancesOfferingId": "a", "DBInstanceClass": "db.m5.large",
+                 "Duration": 31536000, "FixedPrice": 0.0, "UsagePrice": 0.0,
+                 "CurrencyCode": "USD", "ProductDescription": "postgresql",
+                 "OfferingType": "No Upfront", "MultiAZ": true,
+                 "RecurringCharges": [{"RecurringChargeAmount": 0.235,
+                                       "RecurringChargeFrequency": "Hourly"}]},
+                {"ReservedDBInstancesOfferingId": "b", "DBInstanceClass": "db.m5.large",
+                 "Duration": 31536000, "FixedPrice": 0.0, "UsagePrice": 0.0,
+                 "ProductDescription": "postgresql", "OfferingType": "No Upfront",
+                 "MultiAZ": true, "RecurringCharges": [{"RecurringChargeAmount": 0.24,
+                                                        "RecurringChargeFrequency": "Hourly"}]},
+                {"ReservedDBInstancesOfferingId": "c", "DBInstanceClass": "db.m5.large",
+                 "Duration": 94608000, "FixedPrice": 2500.0, "UsagePrice": 0.0,
+                 "ProductDescription": "postgresql", "OfferingType": "All Upfront",
+                 "MultiAZ": true},
+                {"ReservedDBInstancesOfferingId": "d", "DBInstanceClass": "db.m5.large",
+                 "Duration": 31536000, "FixedPrice": 0.0, "UsagePrice": 0.0,
+                 "ProductDescription": "neptune", "OfferingType": "No Upfront"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let checks = cross_check(&typed, &offerings.reserved_db_instances_offerings);
+        let statuses = checks.iter().map(|check| check.status).collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                CheckStatus::Match,
+                CheckStatus::PriceMismatch,
+                CheckStatus::MissingFromOffer,
+                CheckStatus::Unrecognized
+            ]
+        );
+        assert_eq!(checks[1].api_usd_per_hour, Decimal::new(24, 2));
+        assert_eq!(checks[1].offer_usd_per_hour, Some(Decimal::new(235, 3)));
+    }
+}
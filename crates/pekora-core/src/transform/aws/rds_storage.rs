@@ -0,0 +1,370 @@
+use crate::model::aws::price_bulk_types::RdsPricingListResponse;
+use crate::model::aws::types::RdsProductAttributes;
+use crate::transform::aws::tiered::TieredPrice;
+use anyhow::{anyhow, bail};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// How Aurora charges for storage I/O.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuroraStorageConfig {
+    /// Cheaper instances and storage, every I/O request charged
+    Standard,
+    /// Pricier instances and storage, I/O included
+    IoOptimized,
+}
+
+impl AuroraStorageConfig {
+    /// `standard` or `io-optimized`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().replace('_', "-").as_str() {
+            "standard" => Some(Self::Standard),
+            "io-optimized" => Some(Self::IoOptimized),
+            _ => None,
+        }
+    }
+}
+
+/// An Aurora cluster to estimate the monthly cost of.
+#[derive(Debug, Clone)]
+pub struct AuroraClusterSpec {
+    /// `Aurora MySQL` or `Aurora PostgreSQL`
+    pub engine: String,
+    /// e.g. `db.r6g.large`
+    pub instance_class: String,
+    /// Readers in addition to the writer, all of the same class
+    pub replicas: u32,
+    pub storage_gb: Decimal,
+    /// I/O requests per month
+    pub io_requests: Decimal,
+    pub hours_per_month: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AuroraClusterCost {
+    pub instance_class: String,
+    pub storage_config: AuroraStorageConfig,
+    pub instances: u32,
+    pub instance_usd: Decimal,
+    pub storage_usd: Decimal,
+    pub io_usd: Decimal,
+    pub monthly_usd: Decimal,
+}
+
+/// On-demand price of the product matching `predicate`, the lowest SKU if several match so
+/// results don't depend on map order.
+fn find_price(
+    response: &RdsPricingListResponse,
+    predicate: impl Fn(&str, &RdsProductAttributes) -> bool,
+) -> Option<TieredPrice> {
+    let sku = response
+        .products
+        .iter()
+        .filter(|(_, product)| predicate(&product.product_family, &product.attributes))
+        .map(|(sku, _)| sku)
+        .filter(|sku| response.terms.on_demand.contains_key(*sku))
+        .min()?;
+    response.terms.on_demand[sku].values().find_map(|offering| {
+        TieredPrice::from_dimensions(
+            sku,
+            &offering.offer_term_code,
+            offering.price_dimensions.values(),
+        )
+    })
+}
+
+fn usage_type_ends_with(attributes: &RdsProductAttributes, suffix: &str) -> bool {
+    attributes
+        .usage_type
+        .as_deref()
+        .is_some_and(|usage_type| usage_type.ends_with(suffix))
+}
+
+/// Storage and I/O products are listed per engine in some regions and for `Any` in others.
+fn engine_matches(attributes: &RdsProductAttributes, engine: &str) -> bool {
+    match attributes.database_engine.as_deref() {
+        None | Some("Any") => true,
+        Some(database_engine) => database_engine == engine,
+    }
+}
+
+/// Estimates the monthly cost of `spec` under `storage_config` from the AmazonRDS pricing list
+/// of its region. Fails if the instance class or storage isn't sold there.
+pub fn estimate_aurora_cluster(
+    response: &RdsPricingListResponse,
+    spec: &AuroraClusterSpec,
+    storage_config: AuroraStorageConfig,
+) -> anyhow::Result<AuroraClusterCost> {
+    let io_optimized = storage_config == AuroraStorageConfig::IoOptimized;
+    let instance = find_price(response, |product_family, attributes| {
+        product_family == "Database Instance"
+            && attributes.database_engine.as_deref() == Some(spec.engine.as_str())
+            && attributes.instance_type.as_deref() == Some(spec.instance_class.as_str())
+            && attributes.usage_type.as_deref().is_some_and(|usage_type| {
+                usage_type.contains("InstanceUsageIOOptimized") == io_optimized
+                    && usage_type.contains("InstanceUsage")
+            })
+    })
+    .ok_or_else(|| {
+        anyhow!(
+            "No {} {} instance priced for {:?} storage",
+            spec.engine,
+            spec.instance_class,
+            storage_config
+        )
+    })?;
+    let storage_suffix = if io_optimized {
+        "Aurora:IO-OptimizedStorageUsage"
+    } else {
+        "Aurora:StorageUsage"
+    };
+    let storage = find_price(response, |_, attributes| {
+        usage_type_ends_with(attributes, storage_suffix) && engine_matches(attributes, &spec.engine)
+    })
+    .ok_or_else(|| anyhow!("No {:?} Aurora storage price", storage_config))?;
+
+    let instances = spec.replicas + 1;
+    let instance_usd = instance.cost(Decimal::from(instances) * spec.hours_per_month);
+    let storage_usd = storage.cost(spec.storage_gb);
+    let io_usd = if io_optimized || spec.io_requests <= Decimal::ZERO {
+        Decimal::ZERO
+    } else {
+        find_price(response, |_, attributes| {
+            usage_type_ends_with(attributes, "Aurora:StorageIOUsage")
+                && engine_matches(attributes, &spec.engine)
+        })
+        .ok_or_else(|| anyhow!("No Aurora I/O price"))?
+        .cost(spec.io_requests)
+    };
+    Ok(AuroraClusterCost {
+        instance_class: spec.instance_class.clone(),
+        storage_config,
+        instances,
+        instance_usd: instance_usd.round_dp(2),
+        storage_usd: storage_usd.round_dp(2),
+        io_usd: io_usd.round_dp(2),
+        monthly_usd: (instance_usd + storage_usd + io_usd).round_dp(2),
+    })
+}
+
+/// Storage of an RDS instance, not Aurora.
+#[derive(Debug, Clone)]
+pub struct RdsStorageSpec {
+    /// e.g. `MySQL`, matched against storage products listed per engine
+    pub engine: String,
+    /// `gp2`, `gp3`, `io1` or `io2`
+    pub volume_type: String,
+    pub multi_az: bool,
+    pub size_gb: Decimal,
+    /// Provisioned IOPS. gp3 includes 3,000, or 12,000 from 400 GB.
+    pub iops: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RdsStorageCost {
+    pub volume_type: String,
+    pub multi_az: bool,
+    pub storage_usd: Decimal,
+    pub iops_usd: Decimal,
+    pub monthly_usd: Decimal,
+}
+
+/// Usage types of the storage and IOPS of `volume_type`, after `RDS:`.
+fn rds_usage_types(volume_type: &str, multi_az: bool) -> anyhow::Result<(String, String)> {
+    let deployment = if multi_az { "Multi-AZ-" } else { "" };
+    let (storage, iops) = match volume_type {
+        "gp2" => ("GP2-Storage", "GP2-PIOPS"),
+        "gp3" => ("GP3-Storage", "GP3-PIOPS"),
+        "io1" => ("PIOPS-Storage", "PIOPS"),
+        "io2" => ("IO2-Storage", "IO2-PIOPS"),
+        _ => bail!("Unknown RDS volume type {:?}", volume_type),
+    };
+    Ok((
+        format!("RDS:{}{}", deployment, storage),
+        format!("RDS:{}{}", deployment, iops),
+    ))
+}
+
+/// Monthly cost of RDS storage from the AmazonRDS pricing list of its region.
+pub fn price_rds_storage(
+    response: &RdsPricingListResponse,
+    spec: &RdsStorageSpec,
+) -> anyhow::Result<RdsStorageCost> {
+    let (storage_usage_type, iops_usage_type) = rds_usage_types(&spec.volume_type, spec.multi_az)?;
+    let storage = find_price(response, |_, attributes| {
+        usage_type_ends_with(attributes, &storage_usage_type)
+            && engine_matches(attributes, &spec.engine)
+    })
+    .ok_or_else(|| anyhow!("No {} storage price for {}", spec.volume_type, spec.engine))?;
+    let baseline_iops = match spec.volume_type.as_str() {
+        "gp3" if spec.size_gb >= Decimal::from(400) => Decimal::from(12000),
+        "gp3" => Decimal::from(3000),
+        _ => Decimal::ZERO,
+    };
+    let iops = spec.iops.unwrap_or_default() - baseline_iops;
+    let iops_usd = if iops > Decimal::ZERO {
+        find_price(response, |_, attributes| {
+            usage_type_ends_with(attributes, &iops_usage_type)
+                && engine_matches(attributes, &spec.engine)
+        })
+        .ok_or_else(|| anyhow!("{} storage has no provisioned IOPS price", spec.volume_type))?
+        .cost(iops)
+    } else {
+        Decimal::ZERO
+    };
+    let storage_usd = storage.cost(spec.size_gb);
+    Ok(RdsStorageCost {
+        volume_type: spec.volume_type.clone(),
+        multi_az: spec.multi_az,
+        storage_usd: storage_usd.round_dp(2),
+        iops_usd: iops_usd.round_dp(2),
+        monthly_usd: (storage_usd + iops_usd).round_dp(2),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        estimate_aurora_cluster, price_rds_storage, AuroraClusterSpec, AuroraStorageConfig,
+        RdsStorageSpec,
+    };
+    use crate::model::aws::price_bulk_types::{PricingListResponse, RdsPricingListResponse};
+    use rust_decimal::Decimal;
+
+    fn product(sku: &str, family: &str, engine: &str, usage_type: &str) -> String {
+        format!(
+            r#""{sku}": {{"sku": "{sku}", "productFamily": "{family}", "attributes": {{
+                "databaseEngine": "{engine}", "instanceType": "db.r6g.large",
+                "usagetype": "USE1-{usage_type}"}}}}"#
+        )
+    }
+
+    fn offering(sku: &str, price: &str) -> String {
+        format!(
+            r#""{sku}": {{"{sku}.JRTCKXETXF": {{"offerTermCode": "JRTCKXETXF", "sku": "{sku}",
+                "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {{}},
+                "priceDimensions": {{"{sku}.JRTCKXETXF.6YS6EN2CT7": {{
+                    "rateCode": "{sku}.JRTCKXETXF.6YS6EN2CT7", "description": "",
+                    "unit": "", "beginRange": "0", "endRange": "Inf",
+                    "pricePerUnit": {{"USD": "{price}"}}}}}}}}}}"#
+        )
+    }
+
+    #[test]
+    fn test_estimate_aurora_cluster() {
+        let products = [
+            (
+                "STD",
+                "Database Instance",
+                "Aurora MySQL",
+                "InstanceUsage:db.r6g.large",
+                "0.26",
+            ),
+            (
+                "IOO",
+                "Database Instance",
+                "Aurora MySQL",
+                "InstanceUsageIOOptimized:db.r6g.lg",
+                "0.338",
+            ),
+            (
+                "STDS",
+                "Database Storage",
+                "Any",
+                "Aurora:StorageUsage",
+                "0.10",
+            ),
+            (
+                "IOOS",
+                "Database Storage",
+                "Any",
+                "Aurora:IO-OptimizedStorageUsage",
+                "0.225",
+            ),
+            (
+                "IO",
+                "System Operation",
+                "Any",
+                "Aurora:StorageIOUsage",
+                "0.0000002",
+            ),
+            (
+                "GP3",
+                "Database Storage",
+                "MySQL",
+                "RDS:Multi-AZ-GP3-Storage",
+                "0.23",
+            ),
+            (
+                "GP3IOPS",
+                "Provisioned IOPS",
+                "MySQL",
+                "RDS:Multi-AZ-GP3-PIOPS",
+                "0.04",
+            ),
+        ];
+        let json = format!(
+            r#"{{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724", "products": {{{}}},
+            "terms": {{"OnDemand": {{{}}}, "Reserved": {{}}}}}}"#,
+            products
+                .iter()
+                .map(|(sku, family, engine, usage_type, _)| product(
+                    sku, family, engine, usage_type
+                ))
+                .collect::<Vec<_>>()
+                .join(","),
+            products
+                .iter()
+                .map(|(sku, _, _, _, price)| offering(sku, price))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        let response: PricingListResponse = serde_json::from_str(&json).unwrap();
+        let typed: RdsPricingListResponse = response.with_typed_attributes().unwrap();
+
+        let spec = AuroraClusterSpec {
+            engine: "Aurora MySQL".to_string(),
+            instance_class: "db.r6g.large".to_string(),
+            replicas: 1,
+            storage_gb: Decimal::from(1000),
+            io_requests: Decimal::from(1_000_000_000),
+            hours_per_month: Decimal::from(730),
+        };
+        let standard =
+            estimate_aurora_cluster(&typed, &spec, AuroraStorageConfig::Standard).unwrap();
+        assert_eq!(standard.instances, 2);
+        assert_eq!(standard.instance_usd, Decimal::new(3796, 1));
+        assert_eq!(standard.storage_usd, Decimal::from(100));
+        assert_eq!(standard.io_usd, Decimal::from(200));
+        assert_eq!(standard.monthly_usd, Decimal::new(6796, 1));
+
+        let io_optimized =
+            estimate_aurora_cluster(&typed, &spec, AuroraStorageConfig::IoOptimized).unwrap();
+        assert_eq!(io_optimized.instance_usd, Decimal::new(49348, 2));
+        assert_eq!(io_optimized.io_usd, Decimal::ZERO);
+        assert_eq!(io_optimized.monthly_usd, Decimal::new(71848, 2));
+
+        let postgres = AuroraClusterSpec {
+            engine: "Aurora PostgreSQL".to_string(),
+            ..spec
+        };
+        assert!(estimate_aurora_cluster(&typed, &postgres, AuroraStorageConfig::Standard).is_err());
+
+        let storage = price_rds_storage(
+            &typed,
+            &RdsStorageSpec {
+                engine: "MySQL".to_string(),
+                volume_type: "gp3".to_string(),
+                multi_az: true,
+                size_gb: Decimal::from(500),
+                iops: Some(Decimal::from(15000)),
+            },
+        )
+        .unwrap();
+        assert_eq!(storage.storage_usd, Decimal::from(115));
+        assert_eq!(storage.iops_usd, Decimal::from(120));
+        assert_eq!(storage.monthly_usd, Decimal::from(235));
+    }
+}
@@ -0,0 +1,489 @@
+use crate::model::aws::price_bulk_types::PricingListResponse;
+use crate::model::aws::types::{ContractLength, PurchaseOption, RIOfferingClass};
+use crate::model::aws::unit::{Unit, HOURS_PER_MONTH};
+use crate::transform::aws::effective_rate::upfront_share;
+use crate::transform::aws::on_demand::{is_plain_instance, OnDemandRate};
+use crate::transform::aws::savings_plan::PivotedSavingsPlanTermRate;
+use log::warn;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Steady EC2 usage of one instance type.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ec2Usage {
+    pub instance_type: String,
+    #[serde(default = "default_operating_system")]
+    pub operating_system: String,
+    #[serde(default = "default_tenancy")]
+    pub tenancy: String,
+    /// Average number of instances running over a month
+    pub instances: Decimal,
+}
+
+fn default_operating_system() -> String {
+    "Linux".to_string()
+}
+
+fn default_tenancy() -> String {
+    "Shared".to_string()
+}
+
+impl Ec2Usage {
+    fn matches(&self, attributes: &HashMap<String, String>) -> bool {
+        is_plain_instance(
+            attributes,
+            &self.instance_type,
+            &self.operating_system,
+            &self.tenancy,
+        )
+    }
+}
+
+/// Term of a commitment, as written in the recommendation exports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commitment {
+    pub term: ContractLength,
+    pub payment_option: PurchaseOption,
+}
+
+impl Commitment {
+    pub fn new(term: ContractLength, payment_option: PurchaseOption) -> anyhow::Result<Self> {
+        if let ContractLength::Unknown(term) = term {
+            anyhow::bail!("Unknown term {}, expected 1yr or 3yr", term);
+        }
+        if let PurchaseOption::Unknown(option) = payment_option {
+            anyhow::bail!(
+                "Unknown payment option {}, expected NoUpfront, PartialUpfront or AllUpfront",
+                option
+            );
+        }
+        Ok(Self {
+            term,
+            payment_option,
+        })
+    }
+
+    /// Parses a term such as `1yr` and a payment option such as `NoUpfront` or `No Upfront`.
+    pub fn parse(term: &str, payment_option: &str) -> anyhow::Result<Self> {
+        let term = serde_json::from_value(serde_json::Value::String(term.to_string()))?;
+        let payment_option =
+            serde_json::from_value(serde_json::Value::String(payment_option.to_string()))?;
+        Self::new(term, payment_option)
+    }
+
+    pub(crate) fn months(&self) -> i64 {
+        match self.term {
+            ContractLength::ThreeYear => 36,
+            _ => 12,
+        }
+    }
+
+    fn hours(&self) -> Decimal {
+        Decimal::from(self.months() * HOURS_PER_MONTH)
+    }
+
+    fn term_label(&self) -> &'static str {
+        match self.term {
+            ContractLength::ThreeYear => "3 years",
+            _ => "1 year",
+        }
+    }
+
+    fn payment_label(&self) -> &'static str {
+        match self.payment_option {
+            PurchaseOption::PartialUpfront => "Partial Upfront",
+            PurchaseOption::AllUpfront => "All Upfront",
+            _ => "No Upfront",
+        }
+    }
+
+    /// Share of the total commitment paid upfront.
+    fn upfront_share(&self) -> Decimal {
+        upfront_share(&self.payment_option)
+    }
+}
+
+/// Savings plan recommendation, with the columns of a Cost Explorer recommendation export.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SavingsPlanRecommendation {
+    #[serde(rename = "Savings Plans Type")]
+    pub savings_plans_type: String,
+    #[serde(rename = "Term")]
+    pub term: String,
+    #[serde(rename = "Payment Option")]
+    pub payment_option: String,
+    #[serde(rename = "Region")]
+    pub region: String,
+    #[serde(rename = "Hourly Commitment")]
+    pub hourly_commitment: Decimal,
+    #[serde(rename = "Upfront Cost")]
+    pub upfront_cost: Decimal,
+    #[serde(rename = "Estimated Monthly On-Demand Cost")]
+    pub estimated_on_demand_cost: Decimal,
+    #[serde(rename = "Estimated Monthly Savings Plans Cost")]
+    pub estimated_savings_plans_cost: Decimal,
+    #[serde(rename = "Estimated Monthly Savings")]
+    pub estimated_monthly_savings: Decimal,
+    #[serde(rename = "Estimated Savings Percentage")]
+    pub estimated_savings_percentage: Decimal,
+    #[serde(rename = "Estimated Average Utilization")]
+    pub estimated_average_utilization: Decimal,
+    #[serde(rename = "Currency Code")]
+    pub currency_code: String,
+}
+
+/// Reserved instance recommendation, with the columns of a Cost Explorer recommendation export.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReservedInstanceRecommendation {
+    #[serde(rename = "Instance Type")]
+    pub instance_type: String,
+    #[serde(rename = "Region")]
+    pub region: String,
+    #[serde(rename = "Platform")]
+    pub platform: String,
+    #[serde(rename = "Tenancy")]
+    pub tenancy: String,
+    #[serde(rename = "Term")]
+    pub term: String,
+    #[serde(rename = "Payment Option")]
+    pub payment_option: String,
+    #[serde(rename = "Offering Class")]
+    pub offering_class: String,
+    #[serde(rename = "Recommended Instance Quantity Purchase")]
+    pub quantity: Decimal,
+    #[serde(rename = "Upfront Cost")]
+    pub upfront_cost: Decimal,
+    #[serde(rename = "Recurring Monthly Cost")]
+    pub recurring_monthly_cost: Decimal,
+    #[serde(rename = "Estimated Monthly On-Demand Cost")]
+    pub estimated_on_demand_cost: Decimal,
+    #[serde(rename = "Estimated Monthly Savings")]
+    pub estimated_monthly_savings: Decimal,
+    #[serde(rename = "Estimated Savings Percentage")]
+    pub estimated_savings_percentage: Decimal,
+    /// Empty if the reservation never pays for itself
+    #[serde(rename = "Break Even Months")]
+    pub break_even_months: Option<Decimal>,
+    #[serde(rename = "Currency Code")]
+    pub currency_code: String,
+}
+
+fn money(value: Decimal) -> Decimal {
+    value.round_dp(2)
+}
+
+fn percentage(savings: Decimal, cost: Decimal) -> Decimal {
+    if cost.is_zero() {
+        return Decimal::ZERO;
+    }
+    (savings * Decimal::ONE_HUNDRED / cost).round_dp(2)
+}
+
+/// Compute savings plan commitment covering all of `usage`. Usage without an on-demand or
+/// savings plan rate in `region` is left out with a warning. `None` if nothing is covered.
+pub fn savings_plan_recommendation(
+    region: &str,
+    usage: &[Ec2Usage],
+    on_demand: &[OnDemandRate],
+    savings_plans: &[PivotedSavingsPlanTermRate],
+    commitment: &Commitment,
+) -> Option<SavingsPlanRecommendation> {
+    let mut on_demand_hourly = Decimal::ZERO;
+    let mut hourly_commitment = Decimal::ZERO;
+    for line in usage {
+        let rates = on_demand
+            .iter()
+            .filter(|rate| rate.parsed_unit() == Unit::Hours && line.matches(&rate.attributes))
+            .filter_map(|rate| Some((rate, rate.price_per_unit.get("USD")?.value()?)))
+            .min_by_key(|(_, price)| *price)
+            .and_then(|(rate, on_demand_price)| {
+                let usage_type = rate.attributes.get("usagetype")?;
+                let operation = rate.attributes.get("operation")?;
+                let savings_plan_price = savings_plans
+                    .iter()
+                    .filter(|sp| {
+                        let attributes = &sp.savings_plan_attributes;
+                        attributes.purchase_term == commitment.term
+                            && attributes.purchase_option == commitment.payment_option
+                            && &sp.term_rate.discounted_usage_type == usage_type
+                            && &sp.term_rate.discounted_operation == operation
+                    })
+                    .find_map(|sp| sp.term_rate.discounted_rate.usd())?;
+                Some((on_demand_price, savings_plan_price))
+            });
+        match rates {
+            Some((on_demand_price, savings_plan_price)) => {
+                on_demand_hourly += on_demand_price * line.instances;
+                hourly_commitment += savings_plan_price * line.instances;
+            }
+            None => warn!(
+                "No savings plan rate for {} {} in {}, leaving it out",
+                line.instance_type, line.operating_system, region
+            ),
+        }
+    }
+    if hourly_commitment.is_zero() {
+        return None;
+    }
+
+    let hours_per_month = Decimal::from(HOURS_PER_MONTH);
+    let on_demand_cost = on_demand_hourly * hours_per_month;
+    let savings_plans_cost = hourly_commitment * hours_per_month;
+    Some(SavingsPlanRecommendation {
+        savings_plans_type: "Compute".to_string(),
+        term: commitment.term_label().to_string(),
+        payment_option: commitment.payment_label().to_string(),
+        region: region.to_string(),
+        hourly_commitment: hourly_commitment.round_dp(3),
+        upfront_cost: money(hourly_commitment * commitment.hours() * commitment.upfront_share()),
+        estimated_on_demand_cost: money(on_demand_cost),
+        estimated_savings_plans_cost: money(savings_plans_cost),
+        estimated_monthly_savings: money(on_demand_cost - savings_plans_cost),
+        estimated_savings_percentage: percentage(
+            on_demand_cost - savings_plans_cost,
+            on_demand_cost,
+        ),
+        estimated_average_utilization: Decimal::ONE_HUNDRED,
+        currency_code: "USD".to_string(),
+    })
+}
+
+/// Standard reserved instances for the whole instances of each usage line. Lines without a
+/// matching reservation in `response` are left out with a warning.
+pub fn reserved_instance_recommendations(
+    region: &str,
+    usage: &[Ec2Usage],
+    response: &PricingListResponse,
+    commitment: &Commitment,
+) -> Vec<ReservedInstanceRecommendation> {
+    let mut recommendations = Vec::new();
+    for line in usage {
+        let quantity = line.instances.floor();
+        if quantity.is_zero() {
+            continue;
+        }
+        let prices = response
+            .products
+            .iter()
+            .filter(|(_, product)| line.matches(&product.attributes))
+            .find_map(|(sku, _)| {
+                let on_demand_hourly = response
+                    .terms
+                    .on_demand
+                    .get(sku)?
+                    .values()
+                    .flat_map(|offering| offering.price_dimensions.values())
+                    .find(|dimension| dimension.parsed_unit() == Unit::Hours)?
+                    .usd()?;
+                let reservation = response
+                    .terms
+                    .reserved
+                    .get(sku)?
+                    .values()
+                    .find(|offering| {
+                        let attributes = &offering.term_attributes;
+                        attributes.lease_contract_length == commitment.term
+                            && attributes.purchase_option == commitment.payment_option
+                            && attributes.offering_class == Some(RIOfferingClass::Standard)
+                    })?;
+                let mut upfront = Decimal::ZERO;
+                let mut hourly = Decimal::ZERO;
+                for dimension in reservation.price_dimensions.values() {
+                    match dimension.parsed_unit() {
+                        Unit::Quantity => upfront = dimension.usd()?,
+                        Unit::Hours => hourly = dimension.usd()?,
+                        _ => {}
+                    }
+                }
+                Some((on_demand_hourly, upfront, hourly))
+            });
+        let (on_demand_hourly, upfront, hourly) = match prices {
+            Some(prices) => prices,
+            None => {
+                warn!(
+                    "No reserved instance offering for {} {} in {}, leaving it out",
+                    line.instance_type, line.operating_system, region
+                );
+                continue;
+            }
+        };
+
+        let hours_per_month = Decimal::from(HOURS_PER_MONTH);
+        let upfront_cost = upfront * quantity;
+        let recurring_monthly_cost = hourly * hours_per_month * quantity;
+        let on_demand_cost = on_demand_hourly * hours_per_month * quantity;
+        let reserved_cost =
+            upfront_cost / Decimal::from(commitment.months()) + recurring_monthly_cost;
+        let monthly_savings = on_demand_cost - reserved_cost;
+        let break_even_months = if on_demand_cost > recurring_monthly_cost {
+            Some((upfront_cost / (on_demand_cost - recurring_monthly_cost)).round_dp(1))
+        } else {
+            None
+        };
+        recommendations.push(ReservedInstanceRecommendation {
+            instance_type: line.instance_type.clone(),
+            region: region.to_string(),
+            platform: line.operating_system.clone(),
+            tenancy: line.tenancy.clone(),
+            term: commitment.term_label().to_string(),
+            payment_option: commitment.payment_label().to_string(),
+            offering_class: "Standard".to_string(),
+            quantity,
+            upfront_cost: money(upfront_cost),
+            recurring_monthly_cost: money(recurring_monthly_cost),
+            estimated_on_demand_cost: money(on_demand_cost),
+            estimated_monthly_savings: money(monthly_savings),
+            estimated_savings_percentage: percentage(monthly_savings, on_demand_cost),
+            break_even_months,
+            currency_code: "USD".to_string(),
+        });
+    }
+    recommendations
+}
+
+/// Writes recommendations as CSV under a header row. Nothing is written for no recommendations.
+pub fn write_csv<R: Serialize>(recommendations: &[R], writer: impl Write) -> anyhow::Result<()> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    for recommendation in recommendations {
+        csv_writer.serialize(recommendation)?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        reserved_instance_recommendations, savings_plan_recommendation, write_csv, Commitment,
+        Ec2Usage,
+    };
+    use crate::model::aws::price_bulk_types::PricingListResponse;
+    use crate::model::aws::types::{ContractLength, PurchaseOption};
+    use crate::transform::aws::on_demand;
+    use crate::transform::aws::savings_plan::PivotedSavingsPlanTermRate;
+    use rust_decimal::Decimal;
+
+    const PRICING_LIST: &str = r#"{
+        "formatVersion": "v1.0",
+        "publicationDate": "2024-03-12T15:37:24Z",
+        "version": "20240312153724",
+        "products": {
+            "SKU1": {"sku": "SKU1", "productFamily": "Compute Instance", "attributes": {
+                "instanceType": "m5.large", "operatingSystem": "Linux", "tenancy": "Shared",
+                "preInstalledSw": "NA", "capacitystatus": "Used",
+                "licenseModel": "No License required",
+                "usagetype": "BoxUsage:m5.large", "operation": "RunInstances"}}
+        },
+        "terms": {
+            "OnDemand": {"SKU1": {"SKU1.JRTCKXETXF": {"offerTermCode": "JRTCKXETXF", "sku": "SKU1",
+                "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {},
+                "priceDimensions": {"SKU1.JRTCKXETXF.6YS6EN2CT7": {"rateCode": "SKU1.JRTCKXETXF.6YS6EN2CT7",
+                    "description": "", "unit": "Hrs", "beginRange": "0", "endRange": "Inf",
+                    "pricePerUnit": {"USD": "0.0960000000"}}}}}},
+            "Reserved": {"SKU1": {"SKU1.HU7G6KETJZ": {"offerTermCode": "HU7G6KETJZ", "sku": "SKU1",
+                "effectiveDate": "2024-03-01T00:00:00Z",
+                "termAttributes": {"LeaseContractLength": "1yr", "OfferingClass": "standard",
+                    "PurchaseOption": "Partial Upfront"},
+                "priceDimensions": {
+                    "SKU1.HU7G6KETJZ.2TG2D8R56U": {"rateCode": "SKU1.HU7G6KETJZ.2TG2D8R56U",
+                        "description": "Upfront Fee", "unit": "Quantity",
+                        "pricePerUnit": {"USD": "292"}},
+                    "SKU1.HU7G6KETJZ.6YS6EN2CT7": {"rateCode": "SKU1.HU7G6KETJZ.6YS6EN2CT7",
+                        "description": "", "unit": "Hrs", "beginRange": "0", "endRange": "Inf",
+                        "pricePerUnit": {"USD": "0.0330000000"}}}}}}
+        }
+    }"#;
+
+    fn usage(instances: i64) -> Vec<Ec2Usage> {
+        let body = format!("instance_type,instances\nm5.large,{instances}\nx1.metal,1\n");
+        csv::Reader::from_reader(body.as_bytes())
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_recommendations() {
+        let response: PricingListResponse = serde_json::from_str(PRICING_LIST).unwrap();
+        let commitment =
+            Commitment::new(ContractLength::OneYear, PurchaseOption::PartialUpfront).unwrap();
+        assert_eq!(
+            Commitment::parse("1yr", "Partial Upfront").unwrap(),
+            commitment
+        );
+        assert!(Commitment::parse("5yr", "NoUpfront").is_err());
+
+        let reserved =
+            reserved_instance_recommendations("us-east-1", &usage(2), &response, &commitment);
+        assert_eq!(reserved.len(), 1);
+        let recommendation = &reserved[0];
+        assert_eq!(recommendation.quantity, Decimal::from(2));
+        assert_eq!(recommendation.upfront_cost, Decimal::from(584));
+        // 0.033 * 730 * 2 and 0.096 * 730 * 2
+        assert_eq!(recommendation.recurring_monthly_cost, Decimal::new(4818, 2));
+        assert_eq!(
+            recommendation.estimated_on_demand_cost,
+            Decimal::new(14016, 2)
+        );
+        // 140.16 - (584 / 12 + 48.18)
+        assert_eq!(
+            recommendation.estimated_monthly_savings,
+            Decimal::new(4331, 2)
+        );
+        assert_eq!(recommendation.break_even_months, Some(Decimal::new(63, 1)));
+
+        let savings_plan: PivotedSavingsPlanTermRate = PivotedSavingsPlanTermRate {
+            savings_plan_sku: "SP1".to_string(),
+            savings_plan_effective_date: response.publication_date,
+            savings_plan_attributes: std::sync::Arc::new(
+                serde_json::from_str(
+                    r#"{"purchaseOption": "Partial Upfront", "productFamily": "ComputeSavingsPlans",
+                    "serviceCode": "ComputeSavingsPlans", "granularity": "hourly",
+                    "locationType": "AWS Region", "purchaseTerm": "1yr", "location": "Any",
+                    "usageType": "ComputeSP:1yrPartialUpfront"}"#,
+                )
+                .unwrap(),
+            ),
+            lease_contract_length: serde_json::from_str(r#"{"duration": 1, "unit": "year"}"#)
+                .unwrap(),
+            term_rate: serde_json::from_str(
+                r#"{"discountedSku": "SKU1", "discountedUsageType": "BoxUsage:m5.large",
+                "discountedOperation": "RunInstances", "discountedServiceCode": "AmazonEC2",
+                "rateCode": "SP1.SKU1", "unit": "Hrs",
+                "discountedRate": {"price": "0.0620000000", "currency": "USD"}}"#,
+            )
+            .unwrap(),
+        };
+        let recommendation = savings_plan_recommendation(
+            "us-east-1",
+            &usage(2),
+            &on_demand::pivot(response),
+            &[savings_plan],
+            &commitment,
+        )
+        .unwrap();
+        assert_eq!(recommendation.hourly_commitment, Decimal::new(124, 3));
+        // 0.124 * 8760 / 2
+        assert_eq!(recommendation.upfront_cost, Decimal::new(54312, 2));
+        assert_eq!(
+            recommendation.estimated_monthly_savings,
+            Decimal::new(4964, 2)
+        );
+        assert_eq!(
+            recommendation.estimated_savings_percentage,
+            Decimal::new(3542, 2)
+        );
+
+        let mut output = Vec::new();
+        write_csv(&[recommendation], &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap().lines().next().unwrap(),
+            "Savings Plans Type,Term,Payment Option,Region,Hourly Commitment,Upfront Cost,\
+            Estimated Monthly On-Demand Cost,Estimated Monthly Savings Plans Cost,\
+            Estimated Monthly Savings,Estimated Savings Percentage,\
+            Estimated Average Utilization,Currency Code"
+        );
+    }
+}
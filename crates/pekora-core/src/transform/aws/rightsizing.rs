@@ -0,0 +1,153 @@
+use crate::model::aws::compute_optimizer::InstanceRecommendation;
+use crate::model::aws::unit::{Unit, HOURS_PER_MONTH};
+use crate::transform::aws::on_demand::{is_plain_instance, OnDemandRate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// An instance next to the instance type Compute Optimizer ranks best for it, both priced
+/// on-demand.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RightsizingSaving {
+    pub region: String,
+    pub instance_arn: String,
+    pub instance_name: Option<String>,
+    pub finding: String,
+    pub current_instance_type: String,
+    pub recommended_instance_type: String,
+    pub current_usd_per_hour: Option<Decimal>,
+    pub recommended_usd_per_hour: Option<Decimal>,
+    /// Negative when the recommendation costs more, e.g. for underprovisioned instances
+    pub monthly_savings_usd: Option<Decimal>,
+}
+
+/// Prices the best ranked option of each recommendation of `region` with Linux on-demand rates
+/// of the same region. Instances already on their best option are skipped. Sorted from the
+/// largest saving, unpriced rows last.
+pub fn rightsizing_savings(
+    recommendations: &[InstanceRecommendation],
+    region: &str,
+    on_demand: &[OnDemandRate],
+) -> Vec<RightsizingSaving> {
+    let linux_usd_per_hour = |instance_type: &str| {
+        on_demand
+            .iter()
+            .filter(|rate| rate.parsed_unit() == Unit::Hours)
+            .filter(|rate| is_plain_instance(&rate.attributes, instance_type, "Linux", "Shared"))
+            .find_map(|rate| rate.price_per_unit.get("USD")?.value())
+    };
+    let candidates = recommendations
+        .iter()
+        .filter(|recommendation| recommendation.region() == Some(region))
+        .filter_map(|recommendation| Some((recommendation, recommendation.best_option()?)))
+        .filter(|(recommendation, option)| {
+            option.instance_type != recommendation.current_instance_type
+        })
+        .collect::<Vec<_>>();
+    let mut prices: HashMap<&str, Option<Decimal>> = HashMap::new();
+    for (recommendation, option) in &candidates {
+        for instance_type in [&recommendation.current_instance_type, &option.instance_type] {
+            prices
+                .entry(instance_type)
+                .or_insert_with(|| linux_usd_per_hour(instance_type));
+        }
+    }
+
+    let mut rows: Vec<RightsizingSaving> = candidates
+        .into_iter()
+        .map(|(recommendation, option)| {
+            let current_usd_per_hour = prices[recommendation.current_instance_type.as_str()];
+            let recommended_usd_per_hour = prices[option.instance_type.as_str()];
+            RightsizingSaving {
+                region: region.to_string(),
+                instance_arn: recommendation.instance_arn.clone(),
+                instance_name: recommendation.instance_name.clone(),
+                finding: recommendation.finding.clone(),
+                current_instance_type: recommendation.current_instance_type.clone(),
+                recommended_instance_type: option.instance_type.clone(),
+                current_usd_per_hour,
+                recommended_usd_per_hour,
+                monthly_savings_usd: current_usd_per_hour.zip(recommended_usd_per_hour).map(
+                    |(current, recommended)| {
+                        ((current - recommended) * Decimal::from(HOURS_PER_MONTH)).round_dp(2)
+                    },
+                ),
+            }
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        b.monthly_savings_usd
+            .is_some()
+            .cmp(&a.monthly_savings_usd.is_some())
+            .then(b.monthly_savings_usd.cmp(&a.monthly_savings_usd))
+            .then_with(|| a.instance_arn.cmp(&b.instance_arn))
+    });
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rightsizing_savings;
+    use crate::model::aws::compute_optimizer::Ec2InstanceRecommendationsResponse;
+    use crate::model::aws::price_bulk_types::PricingListResponse;
+    use crate::transform::aws::on_demand::pivot;
+    use rust_decimal::Decimal;
+
+    fn product(sku: &str, instance_type: &str) -> String {
+        format!(
+            r#""{sku}": {{"sku": "{sku}", "productFamily": "Compute Instance", "attributes": {{
+                "instanceType": "{instance_type}", "operatingSystem": "Linux", "tenancy": "Shared",
+                "preInstalledSw": "NA", "capacitystatus": "Used"}}}}"#
+        )
+    }
+
+    fn offering(sku: &str, usd: &str) -> String {
+        format!(
+            r#""{sku}": {{"{sku}.JRTCKXETXF": {{"offerTermCode": "JRTCKXETXF", "sku": "{sku}",
+                "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {{}},
+                "priceDimensions": {{"{sku}.JRTCKXETXF.6YS6EN2CT7": {{
+                    "rateCode": "{sku}.JRTCKXETXF.6YS6EN2CT7", "description": "",
+                    "unit": "Hrs", "pricePerUnit": {{"USD": "{usd}"}}}}}}}}}}"#
+        )
+    }
+
+    #[test]
+    fn test_rightsizing_savings() {
+        let json = format!(
+            r#"{{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724", "products": {{{}, {}}},
+            "terms": {{"OnDemand": {{{}, {}}}, "Reserved": {{}}}}}}"#,
+            product("XL", "m5.xlarge"),
+            product("L", "m5.large"),
+            offering("XL", "0.1920000000"),
+            offering("L", "0.0960000000"),
+        );
+        let response: PricingListResponse = serde_json::from_str(&json).unwrap();
+        let on_demand = pivot(response);
+        let recommendations: Ec2InstanceRecommendationsResponse = serde_json::from_str(
+            r#"{"instanceRecommendations": [
+                {"instanceArn": "arn:aws:ec2:us-east-1:123456789012:instance/i-1",
+                 "currentInstanceType": "m5.xlarge", "finding": "Overprovisioned",
+                 "recommendationOptions": [
+                    {"instanceType": "t3.large", "rank": 2},
+                    {"instanceType": "m5.large", "rank": 1, "performanceRisk": 1.0}]},
+                {"instanceArn": "arn:aws:ec2:us-east-1:123456789012:instance/i-2",
+                 "currentInstanceType": "m5.large", "finding": "Optimized",
+                 "recommendationOptions": [{"instanceType": "m5.large", "rank": 1}]},
+                {"instanceArn": "arn:aws:ec2:us-west-2:123456789012:instance/i-3",
+                 "currentInstanceType": "m5.xlarge", "finding": "Overprovisioned",
+                 "recommendationOptions": [{"instanceType": "m5.large", "rank": 1}]}
+            ]}"#,
+        )
+        .unwrap();
+
+        let rows = rightsizing_savings(
+            &recommendations.instance_recommendations,
+            "us-east-1",
+            &on_demand,
+        );
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].recommended_instance_type, "m5.large");
+        assert_eq!(rows[0].monthly_savings_usd, Some(Decimal::new(7008, 2)));
+    }
+}
@@ -0,0 +1,373 @@
+use crate::metrics;
+use crate::model::aws::price_bulk_types::SavingsPlanListResponse;
+use crate::model::aws::types::{
+    ContractLength, LeaseContractLength, PurchaseOption, SavingsPlanProductAttributes,
+    SavingsPlanTermRate, SavingsPlanType,
+};
+use crate::model::aws::unit::Unit;
+use crate::transform::aws::location::LocationFilter;
+use crate::transform::aws::on_demand::OnDemandRate;
+use crate::transform::sink::RowSink;
+use anyhow::bail;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PivotedSavingsPlanTermRate {
+    pub savings_plan_sku: String,
+    pub savings_plan_effective_date: DateTime<Utc>,
+    pub savings_plan_attributes: Arc<SavingsPlanProductAttributes>,
+    pub lease_contract_length: LeaseContractLength,
+    pub term_rate: SavingsPlanTermRate,
+}
+
+impl PivotedSavingsPlanTermRate {
+    pub fn plan_type(&self) -> &SavingsPlanType {
+        &self.savings_plan_attributes.product_family
+    }
+}
+
+pub fn pivot(
+    response: SavingsPlanListResponse,
+    location_filter: &LocationFilter,
+) -> anyhow::Result<Vec<PivotedSavingsPlanTermRate>> {
+    let mut pivoted: Vec<PivotedSavingsPlanTermRate> = Vec::new();
+    pivot_into(response, location_filter, &mut pivoted)?;
+    Ok(pivoted)
+}
+
+/// Writes each rate to `sink` as it is pivoted, returning the number of rows written.
+pub fn pivot_into(
+    response: SavingsPlanListResponse,
+    location_filter: &LocationFilter,
+    sink: &mut impl RowSink<PivotedSavingsPlanTermRate>,
+) -> anyhow::Result<u64> {
+    let mut attribute_lookup: HashMap<String, Arc<SavingsPlanProductAttributes>> = HashMap::new();
+    for product in response.products {
+        attribute_lookup.insert(product.sku, Arc::new(product.attributes));
+    }
+
+    let mut rows = 0;
+    for term in response.terms.savings_plan {
+        let attributes = match attribute_lookup.get(&term.sku) {
+            Some(attributes) => attributes.clone(),
+            None => {
+                bail!("No attributes found for savings plan sku {}", term.sku);
+            }
+        };
+        if !location_filter.matches(&attributes.location_type) {
+            continue;
+        }
+        for rate in term.rates {
+            sink.write(PivotedSavingsPlanTermRate {
+                savings_plan_sku: term.sku.clone(),
+                savings_plan_effective_date: term.effective_date,
+                savings_plan_attributes: attributes.clone(),
+                lease_contract_length: term.lease_contract_length.clone(),
+                term_rate: rate,
+            })?;
+            rows += 1;
+        }
+    }
+    sink.finish()?;
+    metrics::global().record_rows_pivoted(rows);
+    Ok(rows)
+}
+
+/// Compute and EC2 Instance Savings Plan rates of one discounted SKU under the same term and
+/// purchase option.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanTypeComparison {
+    pub discounted_sku: String,
+    pub discounted_usage_type: String,
+    pub discounted_operation: String,
+    /// Family covered by the EC2 Instance Savings Plan, e.g. `m5`
+    pub instance_family: Option<String>,
+    pub purchase_term: ContractLength,
+    pub purchase_option: PurchaseOption,
+    pub on_demand_usd: Option<Decimal>,
+    pub compute_usd: Option<Decimal>,
+    pub ec2_instance_usd: Option<Decimal>,
+}
+
+impl PlanTypeComparison {
+    /// Percent off the on-demand rate with a Compute Savings Plan.
+    pub fn compute_discount(&self) -> Option<Decimal> {
+        discount(self.on_demand_usd?, self.compute_usd?)
+    }
+
+    /// Percent off the on-demand rate with an EC2 Instance Savings Plan.
+    pub fn ec2_instance_discount(&self) -> Option<Decimal> {
+        discount(self.on_demand_usd?, self.ec2_instance_usd?)
+    }
+}
+
+fn discount(on_demand: Decimal, discounted: Decimal) -> Option<Decimal> {
+    if on_demand.is_zero() {
+        return None;
+    }
+    Some(((on_demand - discounted) * Decimal::ONE_HUNDRED / on_demand).round_dp(2))
+}
+
+/// Pairs the Compute and EC2 Instance Savings Plan rates of each discounted SKU in `rows`,
+/// joined with the hourly on-demand rate of the SKU. Sorted by discounted SKU.
+pub fn compare_plan_types(
+    rows: &[PivotedSavingsPlanTermRate],
+    on_demand: &[OnDemandRate],
+) -> Vec<PlanTypeComparison> {
+    let on_demand_lookup = OnDemandLookup::new(on_demand);
+    let mut compared: BTreeMap<(String, String, String), PlanTypeComparison> = BTreeMap::new();
+    for row in rows {
+        let attributes = &row.savings_plan_attributes;
+        let rate = &row.term_rate;
+        let key = (
+            rate.discounted_sku.clone(),
+            attributes.purchase_term.as_str().to_string(),
+            attributes.purchase_option.as_str().to_string(),
+        );
+        let comparison = compared.entry(key).or_insert_with(|| PlanTypeComparison {
+            discounted_sku: rate.discounted_sku.clone(),
+            discounted_usage_type: rate.discounted_usage_type.clone(),
+            discounted_operation: rate.discounted_operation.clone(),
+            instance_family: None,
+            purchase_term: attributes.purchase_term.clone(),
+            purchase_option: attributes.purchase_option.clone(),
+            on_demand_usd: on_demand_lookup
+                .by_sku
+                .get(rate.discounted_sku.as_str())
+                .copied(),
+            compute_usd: None,
+            ec2_instance_usd: None,
+        });
+        match row.plan_type() {
+            SavingsPlanType::Compute => comparison.compute_usd = rate.discounted_rate.usd(),
+            SavingsPlanType::Ec2Instance => {
+                comparison.ec2_instance_usd = rate.discounted_rate.usd();
+                comparison.instance_family = attributes.instance_type.clone();
+            }
+            SavingsPlanType::Unknown(_) => {}
+        }
+    }
+    compared.into_values().collect()
+}
+
+/// Lowest hourly USD on-demand rates by SKU and by usage type and operation.
+struct OnDemandLookup<'a> {
+    by_sku: HashMap<&'a str, Decimal>,
+    by_usage: HashMap<(&'a str, &'a str), Decimal>,
+}
+
+impl<'a> OnDemandLookup<'a> {
+    fn new(on_demand: &'a [OnDemandRate]) -> Self {
+        let mut lookup = Self {
+            by_sku: HashMap::new(),
+            by_usage: HashMap::new(),
+        };
+        for rate in on_demand
+            .iter()
+            .filter(|rate| rate.parsed_unit() == Unit::Hours)
+        {
+            let price = match rate
+                .price_per_unit
+                .get("USD")
+                .and_then(|price| price.value())
+            {
+                Some(price) => price,
+                None => continue,
+            };
+            lookup
+                .by_sku
+                .entry(rate.sku.as_str())
+                .and_modify(|existing| *existing = (*existing).min(price))
+                .or_insert(price);
+            if let (Some(usage_type), Some(operation)) = (
+                rate.attributes.get("usagetype"),
+                rate.attributes.get("operation"),
+            ) {
+                lookup
+                    .by_usage
+                    .entry((usage_type.as_str(), operation.as_str()))
+                    .and_modify(|existing| *existing = (*existing).min(price))
+                    .or_insert(price);
+            }
+        }
+        lookup
+    }
+
+    fn get(&self, rate: &SavingsPlanTermRate) -> Option<Decimal> {
+        self.by_sku
+            .get(rate.discounted_sku.as_str())
+            .or_else(|| {
+                self.by_usage.get(&(
+                    rate.discounted_usage_type.as_str(),
+                    rate.discounted_operation.as_str(),
+                ))
+            })
+            .copied()
+    }
+}
+
+/// A savings plan rate next to the on-demand rate it discounts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavingsPlanDiscount {
+    pub savings_plan_sku: String,
+    pub plan_type: SavingsPlanType,
+    pub rate_code: String,
+    pub discounted_sku: String,
+    pub discounted_usage_type: String,
+    pub discounted_operation: String,
+    pub purchase_term: ContractLength,
+    pub purchase_option: PurchaseOption,
+    pub savings_plan_usd: Decimal,
+    pub on_demand_usd: Decimal,
+    pub hourly_savings_usd: Decimal,
+    /// Percent off the on-demand rate, `None` for free on-demand rates
+    pub discount_percent: Option<Decimal>,
+}
+
+/// Matches each hourly rate in `rows` to the on-demand rate of its discounted SKU, or of its
+/// usage type and operation when the SKU is missing. Rates without a match are left out.
+pub fn discounts(
+    rows: &[PivotedSavingsPlanTermRate],
+    on_demand: &[OnDemandRate],
+) -> Vec<SavingsPlanDiscount> {
+    let on_demand_lookup = OnDemandLookup::new(on_demand);
+    let mut discounts: Vec<SavingsPlanDiscount> = rows
+        .iter()
+        .filter(|row| Unit::parse(&row.term_rate.unit) == Unit::Hours)
+        .filter_map(|row| {
+            let rate = &row.term_rate;
+            let savings_plan_usd = rate.discounted_rate.usd()?;
+            let on_demand_usd = on_demand_lookup.get(rate)?;
+            Some(SavingsPlanDiscount {
+                savings_plan_sku: row.savings_plan_sku.clone(),
+                plan_type: row.plan_type().clone(),
+                rate_code: rate.rate_code.clone(),
+                discounted_sku: rate.discounted_sku.clone(),
+                discounted_usage_type: rate.discounted_usage_type.clone(),
+                discounted_operation: rate.discounted_operation.clone(),
+                purchase_term: row.savings_plan_attributes.purchase_term.clone(),
+                purchase_option: row.savings_plan_attributes.purchase_option.clone(),
+                savings_plan_usd,
+                on_demand_usd,
+                hourly_savings_usd: on_demand_usd - savings_plan_usd,
+                discount_percent: discount(on_demand_usd, savings_plan_usd),
+            })
+        })
+        .collect();
+    discounts.sort_by(|a, b| a.rate_code.cmp(&b.rate_code));
+    metrics::global().record_rows_pivoted(discounts.len() as u64);
+    discounts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare_plan_types, discounts, pivot};
+    use crate::model::aws::price_bulk_types::SavingsPlanListResponse;
+    use crate::model::aws::types::SavingsPlanType;
+    use crate::transform::aws::location::LocationFilter;
+    use crate::transform::aws::on_demand::OnDemandRate;
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn product(sku: &str, family: &str, instance_type: Option<&str>, usage_type: &str) -> String {
+        let instance_type = instance_type
+            .map(|instance_type| format!(r#""instanceType": "{}","#, instance_type))
+            .unwrap_or_default();
+        format!(
+            r#"{{"sku": "{sku}", "productFamily": "{family}", "serviceCode": "ComputeSavingsPlans",
+            "usageType": "{usage_type}", "operation": "", "attributes": {{
+                "purchaseOption": "No Upfront", "productFamily": "{family}",
+                "regionCode": "us-east-1", "serviceCode": "ComputeSavingsPlans",
+                "granularity": "hourly", {instance_type} "locationType": "AWS Region",
+                "purchaseTerm": "1yr", "location": "US East (N. Virginia)",
+                "usageType": "{usage_type}"}}}}"#
+        )
+    }
+
+    fn term(sku: &str, price: &str) -> String {
+        format!(
+            r#"{{"sku": "{sku}", "description": "", "effectiveDate": "2024-03-01T00:00:00Z",
+            "leaseContractLength": {{"duration": 1, "unit": "year"}},
+            "rates": [{{"discountedSku": "OD1", "discountedUsageType": "BoxUsage:m5.large",
+                "discountedOperation": "RunInstances", "discountedServiceCode": "AmazonEC2",
+                "rateCode": "{sku}.OD1", "unit": "Hrs",
+                "discountedRate": {{"price": "{price}", "currency": "USD"}}}}]}}"#
+        )
+    }
+
+    #[test]
+    fn test_compare_plan_types() {
+        let response: SavingsPlanListResponse = serde_json::from_str(&format!(
+            r#"{{"formatVersion": "v1.0", "version": "20240312234047", "publicationDate": "2024-03-12T23:40:47Z",
+            "products": [{}, {}], "terms": {{"savingsPlan": [{}, {}]}}}}"#,
+            product("CSP", "ComputeSavingsPlans", None, "ComputeSP:1yrNoUpfront"),
+            product("ISP", "EC2InstanceSavingsPlans", Some("m5"), "EC2SP:m5.1yrNoUpfront"),
+            term("CSP", "0.0620000000"),
+            term("ISP", "0.0580000000"),
+        ))
+        .unwrap();
+        let rows = pivot(response, &LocationFilter::default()).unwrap();
+        assert_eq!(rows[1].plan_type(), &SavingsPlanType::Ec2Instance);
+
+        let on_demand = OnDemandRate {
+            sku: "OD1".to_string(),
+            product_family: "Compute Instance".to_string(),
+            attributes: Arc::new(HashMap::new()),
+            offer_term_code: "JRTCKXETXF".to_string(),
+            effective_date: rows[0].savings_plan_effective_date,
+            rate_code: "OD1.JRTCKXETXF.6YS6EN2CT7".to_string(),
+            description: String::new(),
+            unit: "Hrs".to_string(),
+            price_per_unit: serde_json::from_str(r#"{"USD": "0.0960000000"}"#).unwrap(),
+        };
+        let compared = compare_plan_types(&rows, &[on_demand]);
+        assert_eq!(compared.len(), 1);
+        let comparison = &compared[0];
+        assert_eq!(comparison.instance_family.as_deref(), Some("m5"));
+        assert_eq!(comparison.compute_usd, Some(Decimal::new(62, 3)));
+        assert_eq!(comparison.compute_discount(), Some(Decimal::new(3542, 2)));
+        assert_eq!(
+            comparison.ec2_instance_discount(),
+            Some(Decimal::new(3958, 2))
+        );
+    }
+
+    #[test]
+    fn test_discounts() {
+        let response: SavingsPlanListResponse = serde_json::from_str(&format!(
+            r#"{{"formatVersion": "v1.0", "version": "20240312234047", "publicationDate": "2024-03-12T23:40:47Z",
+            "products": [{}], "terms": {{"savingsPlan": [{}]}}}}"#,
+            product("CSP", "ComputeSavingsPlans", None, "ComputeSP:1yrNoUpfront"),
+            term("CSP", "0.0620000000"),
+        ))
+        .unwrap();
+        let rows = pivot(response, &LocationFilter::default()).unwrap();
+
+        // Matched by usage type and operation, as the SKU differs
+        let on_demand = OnDemandRate {
+            sku: "OD2".to_string(),
+            product_family: "Compute Instance".to_string(),
+            attributes: Arc::new(HashMap::from([
+                ("usagetype".to_string(), "BoxUsage:m5.large".to_string()),
+                ("operation".to_string(), "RunInstances".to_string()),
+            ])),
+            offer_term_code: "JRTCKXETXF".to_string(),
+            effective_date: rows[0].savings_plan_effective_date,
+            rate_code: "OD2.JRTCKXETXF.6YS6EN2CT7".to_string(),
+            description: String::new(),
+            unit: "Hrs".to_string(),
+            price_per_unit: serde_json::from_str(r#"{"USD": "0.0960000000"}"#).unwrap(),
+        };
+        let matched = discounts(&rows, &[on_demand]);
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].plan_type, SavingsPlanType::Compute);
+        assert_eq!(matched[0].hourly_savings_usd, Decimal::new(34, 3));
+        assert_eq!(matched[0].discount_percent, Some(Decimal::new(3542, 2)));
+        assert!(discounts(&rows, &[]).is_empty());
+    }
+}
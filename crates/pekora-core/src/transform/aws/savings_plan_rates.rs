@@ -0,0 +1,162 @@
+use crate::model::aws::savings_plans::SavingsPlanOfferingRate;
+use crate::model::aws::types::{ContractLength, PurchaseOption, SavingsPlanType};
+use crate::transform::aws::savings_plan::PivotedSavingsPlanTermRate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Outcome of looking up a live Savings Plan rate in the bulk savings plan file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateStatus {
+    Match,
+    RateMismatch,
+    /// No rate of the same plan type, term and payment option for the usage in the bulk file
+    MissingFromBulk,
+    /// Plan type, term or payment option the bulk file has no equivalent for
+    Unrecognized,
+}
+
+/// A rate of the Savings Plans API next to the same rate in the bulk savings plan file.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SavingsPlanRateCheck {
+    pub offering_id: String,
+    pub plan_type: SavingsPlanType,
+    pub term: Option<ContractLength>,
+    pub purchase_option: PurchaseOption,
+    pub usage_type: String,
+    pub operation: String,
+    pub live_usd_per_hour: Decimal,
+    pub bulk_usd_per_hour: Option<Decimal>,
+    pub status: RateStatus,
+}
+
+/// Checks live Savings Plan offering `rates`, e.g. from `DescribeSavingsPlansOfferingRates`,
+/// against the pivoted `bulk` rates of the same region. Rates match if equal to a millionth of a
+/// dollar.
+pub fn cross_check(
+    bulk: &[PivotedSavingsPlanTermRate],
+    rates: &[SavingsPlanOfferingRate],
+) -> Vec<SavingsPlanRateCheck> {
+    let mut index: HashMap<(&str, &str, &str, &str, &str), Decimal> = HashMap::new();
+    for row in bulk {
+        if let Some(usd) = row.term_rate.discounted_rate.usd() {
+            index.insert(
+                (
+                    row.plan_type().as_str(),
+                    row.savings_plan_attributes.purchase_term.as_str(),
+                    row.savings_plan_attributes.purchase_option.as_str(),
+                    &row.term_rate.discounted_usage_type,
+                    &row.term_rate.discounted_operation,
+                ),
+                usd,
+            );
+        }
+    }
+
+    rates
+        .iter()
+        .map(|rate| {
+            let offering = &rate.savings_plan_offering;
+            let term = ContractLength::from_seconds(offering.duration_seconds);
+            let mut check = SavingsPlanRateCheck {
+                offering_id: offering.offering_id.clone(),
+                plan_type: offering.plan_type.clone(),
+                term: term.clone(),
+                purchase_option: offering.payment_option.clone(),
+                usage_type: rate.usage_type.clone(),
+                operation: rate.operation.clone(),
+                live_usd_per_hour: rate.rate,
+                bulk_usd_per_hour: None,
+                status: RateStatus::Unrecognized,
+            };
+            let term = match (term, &offering.plan_type, &offering.payment_option) {
+                (_, SavingsPlanType::Unknown(_), _) | (_, _, PurchaseOption::Unknown(_)) => {
+                    return check
+                }
+                (Some(term), _, _) => term,
+                (None, _, _) => return check,
+            };
+            let bulk_usd = index.get(&(
+                offering.plan_type.as_str(),
+                term.as_str(),
+                offering.payment_option.as_str(),
+                rate.usage_type.as_str(),
+                rate.operation.as_str(),
+            ));
+            check.bulk_usd_per_hour = bulk_usd.copied();
+            check.status = match bulk_usd {
+                Some(usd) if usd.round_dp(6) == rate.rate.round_dp(6) => RateStatus::Match,
+                Some(_) => RateStatus::RateMismatch,
+                None => RateStatus::MissingFromBulk,
+            };
+            check
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cross_check, RateStatus};
+    use crate::model::aws::price_bulk_types::SavingsPlanListResponse;
+    use crate::model::aws::savings_plans::SavingsPlansOfferingRatesResponse;
+    use crate::transform::aws::location::LocationFilter;
+    use crate::transform::aws::savings_plan::pivot;
+
+    #[test]
+    fn test_cross_check() {
+        let response: SavingsPlanListResponse = serde_json::from_str(
+            r#"{
+                "formatVersion": "v1.0",
+                "version": "20240312153724",
+                "publicationDate": "2024-03-12T15:37:24Z",
+                "products": [{"sku": "SP", "productFamily": "ComputeSavingsPlans",
+                    "serviceCode": "ComputeSavingsPlans", "usageType": "ComputeSP:1yrNoUpfront",
+                    "operation": "", "attributes": {"purchaseOption": "No Upfront",
+                        "granularity": "hourly", "regionCode": "us-west-2",
+                        "productFamily": "ComputeSavingsPlans", "serviceCode": "ComputeSavingsPlans",
+                        "locationType": "AWS Region", "location": "US West (Oregon)",
+                        "purchaseTerm": "1yr", "usageType": "ComputeSP:1yrNoUpfront"}}],
+                "terms": {"savingsPlan": [{"sku": "SP", "description": "", "effectiveDate":
+                    "2024-03-01T00:00:00Z", "leaseContractLength": {"duration": 1, "unit": "year"},
+                    "rates": [{"discountedSku": "M5", "discountedUsageType": "USW2-BoxUsage:m5.large",
+                        "discountedOperation": "RunInstances", "discountedServiceCode": "AmazonEC2",
+                        "rateCode": "SP.M5", "unit": "Hrs",
+                        "discountedRate": {"price": "0.0690000000", "currency": "USD"}}]}]}
+            }"#,
+        )
+        .unwrap();
+        let bulk = pivot(response, &LocationFilter::default()).unwrap();
+        let rates: SavingsPlansOfferingRatesResponse = serde_json::from_str(
+            r#"{"searchResults": [
+                {"savingsPlanOffering": {"offeringId": "a", "paymentOption": "No Upfront",
+                    "planType": "Compute", "durationSeconds": 31536000, "currency": "USD"},
+                 "rate": "0.069", "unit": "Hrs", "usageType": "USW2-BoxUsage:m5.large",
+                 "operation": "RunInstances",
+                 "properties": [{"name": "region", "value": "us-west-2"}]},
+                {"savingsPlanOffering": {"offeringId": "b", "paymentOption": "No Upfront",
+                    "planType": "Compute", "durationSeconds": 94608000},
+                 "rate": "0.047", "usageType": "USW2-BoxUsage:m5.large", "operation": "RunInstances"},
+                {"savingsPlanOffering": {"offeringId": "c", "paymentOption": "No Upfront",
+                    "planType": "SageMaker", "durationSeconds": 31536000},
+                 "rate": "0.1", "usageType": "USW2-ml.m5.large", "operation": "RunInstance"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let checks = cross_check(&bulk, &rates.search_results);
+        let statuses = checks.iter().map(|check| check.status).collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                RateStatus::Match,
+                RateStatus::MissingFromBulk,
+                RateStatus::Unrecognized
+            ]
+        );
+        assert_eq!(
+            rates.search_results[0].property("region"),
+            Some("us-west-2")
+        );
+    }
+}
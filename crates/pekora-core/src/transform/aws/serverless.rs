@@ -0,0 +1,256 @@
+use crate::metrics;
+use crate::model::aws::price_bulk_types::TypedPricingListResponse;
+use crate::model::aws::types::{
+    EksProductAttributes, FargateProductAttributes, LambdaProductAttributes,
+};
+use rust_decimal::prelude::ToPrimitive;
+use std::fmt::Debug;
+
+/// What a serverless price is charged per.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ServerlessMeter {
+    Requests,
+    GbSeconds,
+    VcpuHours,
+    GbHours,
+    ClusterHours,
+}
+
+impl ServerlessMeter {
+    /// Classifies a usage type such as `USE1-Lambda-GB-Second-ARM` or `USE1-Fargate-GB-Hours`.
+    pub fn from_usage_type(usage_type: &str) -> Option<Self> {
+        if usage_type.contains("Lambda-GB-Second") {
+            Some(Self::GbSeconds)
+        } else if usage_type.contains("Request") {
+            Some(Self::Requests)
+        } else if usage_type.contains("vCPU-Hours") {
+            Some(Self::VcpuHours)
+        } else if usage_type.contains("GB-Hours") {
+            Some(Self::GbHours)
+        } else if usage_type.contains("AmazonEKS-Hours") {
+            Some(Self::ClusterHours)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Architecture {
+    X86_64,
+    Arm64,
+}
+
+/// Attributes of serverless products, priced by usage type.
+pub trait ServerlessAttributes {
+    fn usage_type(&self) -> &str;
+}
+
+impl ServerlessAttributes for LambdaProductAttributes {
+    fn usage_type(&self) -> &str {
+        &self.usage_type
+    }
+}
+
+impl ServerlessAttributes for FargateProductAttributes {
+    fn usage_type(&self) -> &str {
+        &self.usage_type
+    }
+}
+
+impl ServerlessAttributes for EksProductAttributes {
+    fn usage_type(&self) -> &str {
+        &self.usage_type
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerlessRate {
+    pub sku: String,
+    pub usage_type: String,
+    pub meter: ServerlessMeter,
+    pub architecture: Architecture,
+    pub unit: String,
+    pub usd_per_unit: f64,
+    /// Lower bound of the usage tier this price applies to.
+    pub begin_range: f64,
+}
+
+/// Flattens on-demand prices of products with a known meter. Other products, e.g. Fargate
+/// ephemeral storage or Lambda provisioned concurrency, are skipped.
+pub fn pivot<A: ServerlessAttributes + Debug + Clone>(
+    response: TypedPricingListResponse<A>,
+) -> Vec<ServerlessRate> {
+    let mut pivoted = Vec::new();
+    for (sku, offerings) in response.terms.on_demand {
+        let product = match response.products.get(&sku) {
+            Some(product) => product,
+            None => continue,
+        };
+        let usage_type = product.attributes.usage_type();
+        let meter = match ServerlessMeter::from_usage_type(usage_type) {
+            Some(meter) => meter,
+            None => continue,
+        };
+        let architecture = if usage_type.contains("ARM") {
+            Architecture::Arm64
+        } else {
+            Architecture::X86_64
+        };
+        for offering in offerings.into_values() {
+            for dimension in offering.price_dimensions.into_values() {
+                let usd_per_unit = match dimension.usd().and_then(|price| price.to_f64()) {
+                    Some(price) => price,
+                    None => continue,
+                };
+                pivoted.push(ServerlessRate {
+                    sku: sku.clone(),
+                    usage_type: usage_type.to_string(),
+                    meter,
+                    architecture,
+                    unit: dimension.unit,
+                    usd_per_unit,
+                    begin_range: dimension
+                        .begin_range
+                        .and_then(|range| range.parse::<f64>().ok())
+                        .unwrap_or(0.0),
+                });
+            }
+        }
+    }
+    metrics::global().record_rows_pivoted(pivoted.len() as u64);
+    pivoted
+}
+
+/// Monthly usage of a serverless workload.
+#[derive(Debug, Clone, Default)]
+pub struct ServerlessUsage {
+    pub requests: f64,
+    pub gb_seconds: f64,
+    pub vcpu_hours: f64,
+    pub gb_hours: f64,
+    pub cluster_hours: f64,
+}
+
+impl ServerlessUsage {
+    fn quantity(&self, meter: ServerlessMeter) -> f64 {
+        match meter {
+            ServerlessMeter::Requests => self.requests,
+            ServerlessMeter::GbSeconds => self.gb_seconds,
+            ServerlessMeter::VcpuHours => self.vcpu_hours,
+            ServerlessMeter::GbHours => self.gb_hours,
+            ServerlessMeter::ClusterHours => self.cluster_hours,
+        }
+    }
+}
+
+/// Estimates the USD cost of `usage` on `architecture`, applying tiered prices to the portion
+/// of usage within each tier.
+pub fn estimate(
+    rates: &[ServerlessRate],
+    usage: &ServerlessUsage,
+    architecture: Architecture,
+) -> f64 {
+    let meters = [
+        ServerlessMeter::Requests,
+        ServerlessMeter::GbSeconds,
+        ServerlessMeter::VcpuHours,
+        ServerlessMeter::GbHours,
+        ServerlessMeter::ClusterHours,
+    ];
+    let mut total = 0.0;
+    for meter in meters {
+        let quantity = usage.quantity(meter);
+        if quantity <= 0.0 {
+            continue;
+        }
+        let mut tiers = rates
+            .iter()
+            .filter(|rate| {
+                rate.meter == meter
+                    && (rate.architecture == architecture || meter == ServerlessMeter::ClusterHours)
+            })
+            .collect::<Vec<_>>();
+        tiers.sort_by(|a, b| a.begin_range.total_cmp(&b.begin_range));
+        tiers.dedup_by(|a, b| a.begin_range == b.begin_range);
+        for (i, tier) in tiers.iter().enumerate() {
+            let end = tiers
+                .get(i + 1)
+                .map_or(f64::INFINITY, |next| next.begin_range);
+            let in_tier = quantity.min(end) - tier.begin_range;
+            if in_tier <= 0.0 {
+                break;
+            }
+            total += in_tier * tier.usd_per_unit;
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{estimate, pivot, Architecture, ServerlessMeter, ServerlessUsage};
+    use crate::model::aws::price_bulk_types::{LambdaPricingListResponse, PricingListResponse};
+
+    fn offering(sku: &str, unit: &str, tiers: &[(&str, &str)]) -> String {
+        let dimensions = tiers
+            .iter()
+            .enumerate()
+            .map(|(i, (begin_range, price))| {
+                format!(
+                    r#""{sku}.R{i}": {{"rateCode": "{sku}.R{i}", "description": "", "unit": "{unit}",
+                        "beginRange": "{begin_range}", "pricePerUnit": {{"USD": "{price}"}}}}"#
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            r#""{sku}": {{"{sku}.T": {{"offerTermCode": "T", "sku": "{sku}",
+                "effectiveDate": "2024-03-01T00:00:00Z", "priceDimensions": {{{dimensions}}},
+                "termAttributes": {{}}}}}}"#
+        )
+    }
+
+    #[test]
+    fn test_lambda_estimate() {
+        let json = format!(
+            r#"{{
+                "formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z", "version": "1",
+                "products": {{
+                    "REQ": {{"productFamily": "Serverless", "sku": "REQ",
+                        "attributes": {{"usagetype": "USE1-Request", "group": "AWS-Lambda-Requests"}}}},
+                    "DUR": {{"productFamily": "Serverless", "sku": "DUR",
+                        "attributes": {{"usagetype": "USE1-Lambda-GB-Second", "group": "AWS-Lambda-Duration"}}}},
+                    "DURARM": {{"productFamily": "Serverless", "sku": "DURARM",
+                        "attributes": {{"usagetype": "USE1-Lambda-GB-Second-ARM"}}}}
+                }},
+                "terms": {{"OnDemand": {{{}, {}, {}}}, "Reserved": {{}}}}
+            }}"#,
+            offering("REQ", "Requests", &[("0", "0.0000002")]),
+            offering(
+                "DUR",
+                "Lambda-GB-Second",
+                &[("0", "0.00001"), ("1000", "0.000005")]
+            ),
+            offering("DURARM", "Lambda-GB-Second", &[("0", "0.000008")]),
+        );
+        let response: PricingListResponse = serde_json::from_str(&json).unwrap();
+        let typed: LambdaPricingListResponse = response.with_typed_attributes().unwrap();
+        let rates = pivot(typed);
+        assert_eq!(rates.len(), 4);
+        assert!(rates
+            .iter()
+            .any(|rate| rate.meter == ServerlessMeter::GbSeconds
+                && rate.architecture == Architecture::Arm64));
+
+        let usage = ServerlessUsage {
+            requests: 1_000_000.0,
+            gb_seconds: 3000.0,
+            ..ServerlessUsage::default()
+        };
+        let x86 = estimate(&rates, &usage, Architecture::X86_64);
+        assert!((x86 - (0.2 + 1000.0 * 0.00001 + 2000.0 * 0.000005)).abs() < 1e-9);
+        let arm = estimate(&rates, &usage, Architecture::Arm64);
+        assert!((arm - 3000.0 * 0.000008).abs() < 1e-9);
+    }
+}
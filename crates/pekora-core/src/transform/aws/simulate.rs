@@ -0,0 +1,142 @@
+use anyhow::bail;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// On-demand spend eligible for a savings plan, e.g. one row per hour of a Cost Explorer export.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UsageSample {
+    pub timestamp: DateTime<Utc>,
+    pub on_demand_usd: Decimal,
+}
+
+/// A savings plan to replay a usage trace against.
+#[derive(Debug, Clone)]
+pub struct CandidatePlan {
+    pub commitment_usd_per_hour: Decimal,
+    /// Average discount of the plan over on-demand rates of the usage, in percent
+    pub discount_percent: Decimal,
+}
+
+/// Outcome of a replayed savings plan.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SimulationReport {
+    pub hours: u64,
+    /// Hours in which part of the commitment went unused
+    pub underutilized_hours: u64,
+    pub on_demand_usd: Decimal,
+    pub commitment_usd: Decimal,
+    /// On-demand spend the plan did not cover
+    pub uncovered_usd: Decimal,
+    pub total_usd: Decimal,
+    pub savings_usd: Decimal,
+    pub savings_percent: Decimal,
+    /// Share of the commitment spent on usage
+    pub utilization_percent: Decimal,
+    /// Share of the on-demand spend covered by the plan
+    pub coverage_percent: Decimal,
+}
+
+/// Replays `samples` hour by hour against `plan`. Samples are summed per hour, and hours between
+/// the first and last sample without any are taken as no usage, with the commitment still paid.
+pub fn simulate(samples: &[UsageSample], plan: &CandidatePlan) -> anyhow::Result<SimulationReport> {
+    if plan.discount_percent < Decimal::ZERO || plan.discount_percent >= Decimal::ONE_HUNDRED {
+        bail!(
+            "Discount must be at least 0% and below 100%, got {}%",
+            plan.discount_percent
+        );
+    }
+    let mut hourly: BTreeMap<DateTime<Utc>, Decimal> = BTreeMap::new();
+    for sample in samples {
+        *hourly
+            .entry(sample.timestamp.duration_trunc(Duration::hours(1))?)
+            .or_default() += sample.on_demand_usd;
+    }
+    let (first, last) = match (hourly.keys().next(), hourly.keys().next_back()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => bail!("Usage trace is empty"),
+    };
+
+    let rate = Decimal::ONE - plan.discount_percent / Decimal::ONE_HUNDRED;
+    // On-demand spend one hour of the commitment covers at full utilization
+    let coverable = plan.commitment_usd_per_hour / rate;
+    let hours = (last - first).num_hours() as u64 + 1;
+    let mut underutilized_hours = 0;
+    let mut on_demand_usd = Decimal::ZERO;
+    let mut covered_usd = Decimal::ZERO;
+    for hour in 0..hours {
+        let usage = hourly
+            .get(&(first + Duration::hours(hour as i64)))
+            .copied()
+            .unwrap_or_default();
+        let covered = usage.min(coverable);
+        if covered < coverable {
+            underutilized_hours += 1;
+        }
+        on_demand_usd += usage;
+        covered_usd += covered;
+    }
+
+    let commitment_usd = plan.commitment_usd_per_hour * Decimal::from(hours);
+    let uncovered_usd = on_demand_usd - covered_usd;
+    let total_usd = commitment_usd + uncovered_usd;
+    let savings_usd = on_demand_usd - total_usd;
+    let percent = |part: Decimal, whole: Decimal| {
+        if whole.is_zero() {
+            Decimal::ZERO
+        } else {
+            (part * Decimal::ONE_HUNDRED / whole).round_dp(2)
+        }
+    };
+    Ok(SimulationReport {
+        hours,
+        underutilized_hours,
+        on_demand_usd: on_demand_usd.round_dp(2),
+        commitment_usd: commitment_usd.round_dp(2),
+        uncovered_usd: uncovered_usd.round_dp(2),
+        total_usd: total_usd.round_dp(2),
+        savings_usd: savings_usd.round_dp(2),
+        savings_percent: percent(savings_usd, on_demand_usd),
+        utilization_percent: percent(covered_usd * rate, commitment_usd),
+        coverage_percent: percent(covered_usd, on_demand_usd),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{simulate, CandidatePlan, UsageSample};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_simulate() {
+        let samples: Vec<UsageSample> = csv::Reader::from_reader(
+            "timestamp,on_demand_usd
+2024-03-01T00:00:00Z,1.00
+2024-03-01T00:30:00Z,1.00
+2024-03-01T01:00:00Z,1.00
+2024-03-01T03:00:00Z,4.00
+"
+            .as_bytes(),
+        )
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+        let plan = CandidatePlan {
+            commitment_usd_per_hour: Decimal::ONE,
+            discount_percent: Decimal::from(50),
+        };
+
+        // Covers 2.00 on-demand per hour: 2.00, 1.00, nothing at 02:00, then 2.00 of 4.00
+        let report = simulate(&samples, &plan).unwrap();
+        assert_eq!(report.hours, 4);
+        assert_eq!(report.underutilized_hours, 2);
+        assert_eq!(report.on_demand_usd, Decimal::from(7));
+        assert_eq!(report.total_usd, Decimal::from(6));
+        assert_eq!(report.savings_percent, Decimal::new(1429, 2));
+        assert_eq!(report.utilization_percent, Decimal::new(625, 1));
+        assert_eq!(report.coverage_percent, Decimal::new(7143, 2));
+
+        assert!(simulate(&[], &plan).is_err());
+    }
+}
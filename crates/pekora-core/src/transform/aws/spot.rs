@@ -0,0 +1,118 @@
+use crate::metrics;
+use crate::model::aws::spot_advisor::SpotAdvisorResponse;
+use crate::model::aws::unit::Unit;
+use crate::transform::aws::on_demand::{is_plain_instance, OnDemandRate};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// Spot Advisor entry of one instance type, joined with its on-demand price.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpotRate {
+    pub region: String,
+    pub instance_type: String,
+    pub operating_system: String,
+    /// e.g. `<5%`
+    pub interruption_frequency: String,
+    /// Upper bound of the monthly interruption frequency in percent
+    pub interruption_max_percent: i32,
+    /// Savings over on-demand in percent
+    pub savings_percent: i32,
+    pub on_demand_usd_per_hour: Option<Decimal>,
+    /// On-demand price less the advertised savings. Spot prices move more often than the
+    /// feed is published, so this is an estimate.
+    pub spot_usd_per_hour: Option<Decimal>,
+}
+
+/// Joins the Spot Advisor entries of `region` with the shared tenancy on-demand rates of the
+/// same region. Entries without a known interruption range are left out.
+pub fn join(
+    advisor: &SpotAdvisorResponse,
+    region: &str,
+    on_demand: &[OnDemandRate],
+) -> Vec<SpotRate> {
+    let mut joined = Vec::new();
+    let operating_systems = match advisor.spot_advisor.get(region) {
+        Some(operating_systems) => operating_systems,
+        None => return joined,
+    };
+    for (operating_system, instance_types) in operating_systems {
+        for (instance_type, advice) in instance_types {
+            let range = match advisor.range(advice.interruption_range) {
+                Some(range) => range,
+                None => continue,
+            };
+            let on_demand_usd_per_hour = on_demand
+                .iter()
+                .filter(|rate| {
+                    rate.parsed_unit() == Unit::Hours
+                        && is_plain_instance(
+                            &rate.attributes,
+                            instance_type,
+                            operating_system,
+                            "Shared",
+                        )
+                })
+                .filter_map(|rate| rate.price_per_unit.get("USD")?.value())
+                .min();
+            let spot_usd_per_hour = on_demand_usd_per_hour
+                .map(|price| price * Decimal::from(100 - advice.savings) / Decimal::ONE_HUNDRED);
+            joined.push(SpotRate {
+                region: region.to_string(),
+                instance_type: instance_type.clone(),
+                operating_system: operating_system.clone(),
+                interruption_frequency: range.label.clone(),
+                interruption_max_percent: range.max,
+                savings_percent: advice.savings,
+                on_demand_usd_per_hour,
+                spot_usd_per_hour,
+            });
+        }
+    }
+    joined.sort_by(|a, b| {
+        (&a.instance_type, &a.operating_system).cmp(&(&b.instance_type, &b.operating_system))
+    });
+    metrics::global().record_rows_pivoted(joined.len() as u64);
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::join;
+    use crate::model::aws::price_bulk_types::PricingListResponse;
+    use crate::model::aws::spot_advisor::tests::SPOT_ADVISOR_BODY;
+    use crate::transform::aws::on_demand;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_join() {
+        let response: PricingListResponse = serde_json::from_str(
+            r#"{"formatVersion": "v1.0", "publicationDate": "2024-03-12T15:37:24Z",
+            "version": "20240312153724",
+            "products": {"SKU1": {"sku": "SKU1", "productFamily": "Compute Instance", "attributes": {
+                "instanceType": "m5.large", "operatingSystem": "Linux", "tenancy": "Shared",
+                "preInstalledSw": "NA", "capacitystatus": "Used"}}},
+            "terms": {"OnDemand": {"SKU1": {"SKU1.JRTCKXETXF": {"offerTermCode": "JRTCKXETXF",
+                "sku": "SKU1", "effectiveDate": "2024-03-01T00:00:00Z", "termAttributes": {},
+                "priceDimensions": {"SKU1.JRTCKXETXF.6YS6EN2CT7": {
+                    "rateCode": "SKU1.JRTCKXETXF.6YS6EN2CT7", "description": "", "unit": "Hrs",
+                    "pricePerUnit": {"USD": "0.0960000000"}}}}}},
+            "Reserved": {}}}"#,
+        )
+        .unwrap();
+        let advisor = serde_json::from_str(SPOT_ADVISOR_BODY).unwrap();
+        let rates = join(&advisor, "us-east-1", &on_demand::pivot(response));
+        // Windows m5.large has no on-demand rate above, c5.xlarge has none at all
+        assert_eq!(rates.len(), 3);
+        assert_eq!(rates[0].instance_type, "c5.xlarge");
+        assert_eq!(rates[0].interruption_frequency, ">20%");
+        assert_eq!(rates[0].spot_usd_per_hour, None);
+        assert_eq!(rates[1].operating_system, "Linux");
+        assert_eq!(rates[1].interruption_max_percent, 11);
+        assert_eq!(rates[1].on_demand_usd_per_hour, Some(Decimal::new(96, 3)));
+        assert_eq!(rates[1].spot_usd_per_hour, Some(Decimal::new(432, 4)));
+        assert_eq!(rates[2].operating_system, "Windows");
+        assert_eq!(rates[2].on_demand_usd_per_hour, None);
+
+        assert!(join(&advisor, "eu-west-1", &[]).is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+use crate::metrics;
+use crate::model::aws::price_bulk_types::TypedPricingListResponse;
+use crate::model::aws::types::PriceDimension;
+use rust_decimal::Decimal;
+use std::fmt::Debug;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriceTier {
+    pub begin: Decimal,
+    /// `None` for the last, unbounded tier.
+    pub end: Option<Decimal>,
+    pub usd_per_unit: Decimal,
+}
+
+/// Tiers of one on-demand offer, e.g. S3 storage priced per GB-month for the first 50 TB, the
+/// next 450 TB and everything over 500 TB.
+#[derive(Debug, Clone)]
+pub struct TieredPrice {
+    pub sku: String,
+    pub offer_term_code: String,
+    pub unit: String,
+    /// Sorted by `begin`
+    pub tiers: Vec<PriceTier>,
+}
+
+impl TieredPrice {
+    /// Builds tiers from the price dimensions of an offer. `None` if no dimension has a USD price.
+    pub fn from_dimensions<'a>(
+        sku: &str,
+        offer_term_code: &str,
+        dimensions: impl IntoIterator<Item = &'a PriceDimension>,
+    ) -> Option<Self> {
+        let mut unit = None;
+        let mut tiers = dimensions
+            .into_iter()
+            .filter_map(|dimension| {
+                let usd_per_unit = dimension.usd()?;
+                unit.get_or_insert_with(|| dimension.unit.clone());
+                Some(PriceTier {
+                    begin: dimension.begin(),
+                    end: dimension.end(),
+                    usd_per_unit,
+                })
+            })
+            .collect::<Vec<_>>();
+        tiers.sort_by_key(|tier| tier.begin);
+        Some(Self {
+            sku: sku.to_string(),
+            offer_term_code: offer_term_code.to_string(),
+            unit: unit?,
+            tiers,
+        })
+    }
+
+    /// Total USD cost of `quantity` units, each unit charged at the price of the tier it falls in.
+    pub fn cost(&self, quantity: Decimal) -> Decimal {
+        let mut cost = Decimal::ZERO;
+        for tier in &self.tiers {
+            if quantity <= tier.begin {
+                break;
+            }
+            let end = tier.end.map_or(quantity, |end| end.min(quantity));
+            cost += (end - tier.begin) * tier.usd_per_unit;
+        }
+        cost
+    }
+
+    /// Average USD price per unit at `quantity`. `None` for zero usage.
+    pub fn blended_price(&self, quantity: Decimal) -> Option<Decimal> {
+        if quantity <= Decimal::ZERO {
+            return None;
+        }
+        Some(self.cost(quantity) / quantity)
+    }
+}
+
+/// Collects on-demand offers priced in more than one tier.
+pub fn pivot<A: Debug + Clone>(response: &TypedPricingListResponse<A>) -> Vec<TieredPrice> {
+    let mut pivoted = Vec::new();
+    for (sku, offerings) in &response.terms.on_demand {
+        for offering in offerings.values() {
+            if !offering
+                .price_dimensions
+                .values()
+                .any(PriceDimension::is_tiered)
+            {
+                continue;
+            }
+            if let Some(price) = TieredPrice::from_dimensions(
+                sku,
+                &offering.offer_term_code,
+                offering.price_dimensions.values(),
+            ) {
+                pivoted.push(price);
+            }
+        }
+    }
+    metrics::global().record_rows_pivoted(pivoted.len() as u64);
+    pivoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TieredPrice;
+    use crate::model::aws::types::PriceDimension;
+    use rust_decimal::Decimal;
+
+    fn dimension(begin: &str, end: &str, price: &str) -> PriceDimension {
+        serde_json::from_str(&format!(
+            r#"{{"rateCode": "SKU.JRTCKXETXF.{begin}", "description": "", "unit": "GB-Mo",
+                "beginRange": "{begin}", "endRange": "{end}", "pricePerUnit": {{"USD": "{price}"}}}}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_blended_price() {
+        // S3 Standard storage, listed out of order as in the offer files
+        let dimensions = [
+            dimension("512000", "Inf", "0.021"),
+            dimension("0", "51200", "0.023"),
+            dimension("51200", "512000", "0.022"),
+        ];
+        let price = TieredPrice::from_dimensions("SKU", "JRTCKXETXF", &dimensions).unwrap();
+        assert_eq!(price.unit, "GB-Mo");
+        assert_eq!(price.tiers[0].begin, Decimal::ZERO);
+        assert_eq!(price.tiers[2].end, None);
+
+        assert_eq!(price.cost(Decimal::from(1000)), Decimal::new(23, 0));
+        // 51200 * 0.023 + 460800 * 0.022 + 488000 * 0.021
+        assert_eq!(
+            price.cost(Decimal::from(1_000_000)),
+            Decimal::new(21_563_200, 3)
+        );
+        assert_eq!(
+            price.blended_price(Decimal::from(1_000_000)),
+            Some(Decimal::new(215_632, 7))
+        );
+        assert_eq!(price.blended_price(Decimal::ZERO), None);
+    }
+}
@@ -0,0 +1,117 @@
+//! Destinations of transformed rows, fed one row at a time so large outputs need not be
+//! collected first
+use serde::Serialize;
+use std::io::Write;
+use std::sync::mpsc::SyncSender;
+
+/// Receives the rows of a transform as they are produced.
+pub trait RowSink<T> {
+    fn write(&mut self, row: T) -> anyhow::Result<()>;
+
+    /// Called once after the last row.
+    fn finish(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Writes each of `rows`, then finishes.
+    fn write_all(&mut self, rows: impl IntoIterator<Item = T>) -> anyhow::Result<()>
+    where
+        Self: Sized,
+    {
+        for row in rows {
+            self.write(row)?;
+        }
+        self.finish()
+    }
+}
+
+impl<T> RowSink<T> for Vec<T> {
+    fn write(&mut self, row: T) -> anyhow::Result<()> {
+        self.push(row);
+        Ok(())
+    }
+}
+
+/// Writes each row as one line of JSON.
+pub struct JsonLinesWriter<W: Write> {
+    writer: W,
+    rows: u64,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, rows: 0 }
+    }
+
+    /// Rows written so far.
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<T: Serialize, W: Write> RowSink<T> for JsonLinesWriter<W> {
+    fn write(&mut self, row: T) -> anyhow::Result<()> {
+        serde_json::to_writer(&mut self.writer, &row)?;
+        self.writer.write_all(b"\n")?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Sends rows to another thread, e.g. one writing them out. A bounded channel blocks the
+/// transform while the receiver catches up.
+pub struct ChannelSink<T> {
+    sender: SyncSender<T>,
+}
+
+impl<T> ChannelSink<T> {
+    pub fn new(sender: SyncSender<T>) -> Self {
+        Self { sender }
+    }
+}
+
+impl<T> RowSink<T> for ChannelSink<T> {
+    fn write(&mut self, row: T) -> anyhow::Result<()> {
+        if self.sender.send(row).is_err() {
+            anyhow::bail!("Receiver of rows was dropped");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChannelSink, JsonLinesWriter, RowSink};
+    use serde_json::json;
+
+    #[test]
+    fn test_sinks() {
+        let mut writer = JsonLinesWriter::new(Vec::new());
+        writer.write(json!({"sku": "A"})).unwrap();
+        writer.write(json!({"sku": "B"})).unwrap();
+        RowSink::<serde_json::Value>::finish(&mut writer).unwrap();
+        assert_eq!(writer.rows(), 2);
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "{\"sku\":\"A\"}\n{\"sku\":\"B\"}\n"
+        );
+
+        let (sender, receiver) = std::sync::mpsc::sync_channel(1);
+        let consumer = std::thread::spawn(move || receiver.iter().sum::<u32>());
+        let mut sink = ChannelSink::new(sender);
+        for row in 1..=4 {
+            sink.write(row).unwrap();
+        }
+        drop(sink);
+        assert_eq!(consumer.join().unwrap(), 10);
+    }
+}
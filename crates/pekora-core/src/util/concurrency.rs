@@ -0,0 +1,138 @@
+use log::debug;
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::sync::Notify;
+
+/// Concurrency limit that adapts to throttling: additive increase on success, multiplicative
+/// decrease when throttled (AIMD), between `min` and `max` permits.
+#[derive(Debug)]
+pub struct AdaptiveLimiter {
+    min: usize,
+    max: usize,
+    state: Mutex<LimiterState>,
+    released: Notify,
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    limit: f64,
+    in_flight: usize,
+    last_decrease: Option<Instant>,
+}
+
+impl AdaptiveLimiter {
+    /// Starts at `min` permits and ramps up from there.
+    pub fn new(min: usize, max: usize) -> Self {
+        let min = min.max(1);
+        Self {
+            min,
+            max: max.max(min),
+            state: Mutex::new(LimiterState {
+                limit: min as f64,
+                in_flight: 0,
+                last_decrease: None,
+            }),
+            released: Notify::new(),
+        }
+    }
+
+    /// Limiter that never adapts.
+    pub fn fixed(limit: usize) -> Self {
+        Self::new(limit, limit)
+    }
+
+    /// Number of permits currently handed out at most.
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit as usize
+    }
+
+    /// Waits until fewer than `limit` permits are held.
+    pub async fn acquire(&self) -> AdaptivePermit<'_> {
+        loop {
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.in_flight < state.limit as usize {
+                    state.in_flight += 1;
+                    return AdaptivePermit {
+                        limiter: self,
+                        acquired: Instant::now(),
+                        throttled: false,
+                    };
+                }
+            }
+            released.await;
+        }
+    }
+
+    fn release(&self, acquired: Instant, throttled: bool) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.in_flight -= 1;
+            if !throttled {
+                state.limit = (state.limit + 1.0 / state.limit).min(self.max as f64);
+            } else if state.last_decrease.is_none_or(|last| acquired >= last) {
+                // Requests sent before the last decrease were throttled under the old limit
+                state.limit = (state.limit / 2.0).max(self.min as f64);
+                state.last_decrease = Some(Instant::now());
+                debug!(
+                    "Throttled, lowering concurrency to {}",
+                    state.limit as usize
+                );
+            }
+        }
+        self.released.notify_waiters();
+    }
+}
+
+/// Permit of an `AdaptiveLimiter`. Counts as a success when dropped unless marked throttled.
+#[derive(Debug)]
+pub struct AdaptivePermit<'a> {
+    limiter: &'a AdaptiveLimiter,
+    acquired: Instant,
+    throttled: bool,
+}
+
+impl AdaptivePermit<'_> {
+    pub fn throttled(&mut self) {
+        self.throttled = true;
+    }
+}
+
+impl Drop for AdaptivePermit<'_> {
+    fn drop(&mut self) {
+        self.limiter.release(self.acquired, self.throttled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AdaptiveLimiter;
+
+    #[tokio::test]
+    async fn test_aimd() {
+        let limiter = AdaptiveLimiter::new(2, 8);
+        assert_eq!(limiter.limit(), 2);
+        for _ in 0..20 {
+            limiter.acquire().await;
+        }
+        assert_eq!(limiter.limit(), 6);
+
+        // Throttles of requests sent under the same limit only halve it once
+        let mut first = limiter.acquire().await;
+        let mut second = limiter.acquire().await;
+        first.throttled();
+        second.throttled();
+        drop(first);
+        drop(second);
+        assert_eq!(limiter.limit(), 3);
+
+        let mut third = limiter.acquire().await;
+        third.throttled();
+        drop(third);
+        assert_eq!(limiter.limit(), 2);
+        assert_eq!(AdaptiveLimiter::fixed(0).limit(), 1);
+    }
+}
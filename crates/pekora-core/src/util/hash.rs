@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use xxhash_rust::xxh3::Xxh3;
+
+/// Algorithm of the hashes pekora derives itself, e.g. for cache filenames and fingerprints.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// 128-bit XXH3, fast but not cryptographic
+    #[default]
+    Xxh3,
+    Sha256,
+}
+
+/// Hashes a sequence of fields into lowercase hex. Fields are length-prefixed, so `("ab", "c")`
+/// and `("a", "bc")` hash differently, and the result is the same across platforms and releases.
+pub struct StableHasher {
+    inner: Inner,
+}
+
+enum Inner {
+    Xxh3(Box<Xxh3>),
+    Sha256(Sha256),
+}
+
+impl StableHasher {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        let inner = match algorithm {
+            HashAlgorithm::Xxh3 => Inner::Xxh3(Box::default()),
+            HashAlgorithm::Sha256 => Inner::Sha256(Sha256::new()),
+        };
+        Self { inner }
+    }
+
+    pub fn field(&mut self, value: impl AsRef<[u8]>) -> &mut Self {
+        let value = value.as_ref();
+        let length = (value.len() as u64).to_le_bytes();
+        match &mut self.inner {
+            Inner::Xxh3(hasher) => {
+                hasher.update(&length);
+                hasher.update(value);
+            }
+            Inner::Sha256(hasher) => {
+                hasher.update(length);
+                hasher.update(value);
+            }
+        }
+        self
+    }
+
+    pub fn finish(self) -> String {
+        match self.inner {
+            Inner::Xxh3(hasher) => format!("{:032x}", hasher.digest128()),
+            Inner::Sha256(hasher) => {
+                hasher
+                    .finalize()
+                    .iter()
+                    .fold(String::with_capacity(64), |mut hex, byte| {
+                        let _ = write!(hex, "{:02x}", byte);
+                        hex
+                    })
+            }
+        }
+    }
+}
+
+/// Hash of `fields` in order.
+pub fn hash_fields<S: AsRef<[u8]>>(algorithm: HashAlgorithm, fields: &[S]) -> String {
+    let mut hasher = StableHasher::new(algorithm);
+    for field in fields {
+        hasher.field(field);
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash_fields, HashAlgorithm};
+
+    #[test]
+    fn test_hash_fields() {
+        for (algorithm, length) in [(HashAlgorithm::Xxh3, 32), (HashAlgorithm::Sha256, 64)] {
+            let hash = hash_fields(algorithm, &["ab", "c"]);
+            assert_eq!(hash.len(), length);
+            assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+            assert_eq!(hash, hash_fields(algorithm, &["ab", "c"]));
+            assert_ne!(hash, hash_fields(algorithm, &["a", "bc"]));
+        }
+        assert_eq!(
+            hash_fields::<&str>(HashAlgorithm::Sha256, &[]),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+}
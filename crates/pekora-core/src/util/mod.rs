@@ -0,0 +1,11 @@
+/// Vendor agnostic utility functions
+mod concurrency;
+pub mod hash;
+mod regex;
+mod retry;
+mod set;
+
+pub use concurrency::{AdaptiveLimiter, AdaptivePermit};
+pub use regex::regex_extract_match_group;
+pub use retry::{RetryClass, RetryPolicy};
+pub use set::ClientSet;
@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// Class of transient failure a request can be retried on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RetryClass {
+    /// HTTP 5xx responses
+    ServerError,
+    /// HTTP 429 responses
+    TooManyRequests,
+    /// Request or body read timed out
+    Timeout,
+    /// Connection could not be established or was reset
+    Connect,
+}
+
+/// Exponential backoff with full jitter.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub retry_on: HashSet<RetryClass>,
+}
+
+impl RetryPolicy {
+    /// Policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    pub fn should_retry(&self, attempt: u32, class: Option<RetryClass>) -> bool {
+        match class {
+            Some(class) => attempt < self.max_attempts && self.retry_on.contains(&class),
+            None => false,
+        }
+    }
+
+    /// Backoff before the attempt following `attempt` (1-based), picked uniformly between zero
+    /// and the exponential cap so concurrent crawlers don't retry in lockstep.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let cap = self
+            .initial_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff);
+        cap.mul_f64(fastrand::f64())
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            retry_on: HashSet::from([
+                RetryClass::ServerError,
+                RetryClass::TooManyRequests,
+                RetryClass::Timeout,
+                RetryClass::Connect,
+            ]),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use log::debug;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+struct Entry<T> {
+    client: Arc<T>,
+    created: Instant,
+    last_used: u64,
+}
+
+struct Clients<T> {
+    entries: HashMap<String, Entry<T>>,
+    /// Incremented on every `get`, orders entries by last use
+    tick: u64,
+}
+
+/// Clients created on first use for each key, usually a region, and reused after.
+pub struct ClientSet<K: Clone, T> {
+    lock: tokio::sync::Mutex<Clients<T>>,
+    client_factory: Box<dyn Fn(K, String) -> T + Send + Sync>,
+    initial_data: K,
+    capacity: Option<usize>,
+    ttl: Option<Duration>,
+}
+
+impl<K: Clone, T> ClientSet<K, T> {
+    pub fn new(initial_data: K, client_factory: Box<dyn Fn(K, String) -> T + Send + Sync>) -> Self {
+        Self {
+            lock: tokio::sync::Mutex::new(Clients {
+                entries: HashMap::new(),
+                tick: 0,
+            }),
+            client_factory,
+            initial_data,
+            capacity: None,
+            ttl: None,
+        }
+    }
+
+    /// Keeps at most `capacity` clients, evicting the least recently used.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity.max(1));
+        self
+    }
+
+    /// Creates clients again once they are older than `ttl`, e.g. to pick up rotated credentials.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub async fn get(&self, key: &str) -> Arc<T> {
+        let mut clients = self.lock.lock().await;
+        clients.tick += 1;
+        let tick = clients.tick;
+        if let Some(entry) = clients.entries.get_mut(key) {
+            if self.ttl.is_none_or(|ttl| entry.created.elapsed() < ttl) {
+                entry.last_used = tick;
+                return entry.client.clone();
+            }
+            debug!("ClientSet: Client for {} expired", key);
+        }
+        debug!("ClientSet: Creating new client for {}", key);
+        let client = Arc::new((self.client_factory)(
+            self.initial_data.clone(),
+            key.to_string(),
+        ));
+        clients.entries.insert(
+            key.to_string(),
+            Entry {
+                client: client.clone(),
+                created: Instant::now(),
+                last_used: tick,
+            },
+        );
+        if let Some(capacity) = self.capacity {
+            while clients.entries.len() > capacity {
+                let evicted = match clients
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                {
+                    Some((evicted, _)) => evicted.clone(),
+                    None => break,
+                };
+                debug!("ClientSet: Evicting client for {}", evicted);
+                clients.entries.remove(&evicted);
+            }
+        }
+        client
+    }
+
+    /// Keys of the clients created so far, sorted.
+    pub async fn regions(&self) -> Vec<String> {
+        let mut regions = self
+            .lock
+            .lock()
+            .await
+            .entries
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        regions.sort();
+        regions
+    }
+
+    /// Drops every client. Clients still in use stay valid until released.
+    pub async fn clear(&self) {
+        self.lock.lock().await.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ClientSet;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_capacity_and_ttl() {
+        let clients = ClientSet::new((), Box::new(|_, region: String| region)).with_capacity(2);
+        let first = clients.get("us-east-1").await;
+        clients.get("eu-west-1").await;
+        assert!(Arc::ptr_eq(&first, &clients.get("us-east-1").await));
+        clients.get("ap-northeast-2").await;
+        assert_eq!(clients.regions().await, ["ap-northeast-2", "us-east-1"]);
+        clients.clear().await;
+        assert!(clients.regions().await.is_empty());
+
+        let clients =
+            ClientSet::new((), Box::new(|_, region: String| region)).with_ttl(Duration::ZERO);
+        let first = clients.get("us-east-1").await;
+        assert!(!Arc::ptr_eq(&first, &clients.get("us-east-1").await));
+    }
+}
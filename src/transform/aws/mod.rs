@@ -1 +0,0 @@
-pub mod savings_plan;
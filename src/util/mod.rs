@@ -1,6 +0,0 @@
-/// Vendor agnostic utility functions
-mod regex;
-mod set;
-
-pub use regex::regex_extract_match_group;
-pub use set::ClientSet;